@@ -0,0 +1,382 @@
+//! Discrete LED arrays such as bar graphs and VU towers
+//!
+//! A MAX7219 can drive up to 64 single LEDs instead of digits or a matrix:
+//! each digit register switches eight of them, one per segment line. A
+//! [`BarDevice`] numbers those LEDs along the bar, so a level indicator is
+//! driven with [`BarDevice::set_level`] instead of register bytes.
+//!
+//! How the LEDs are numbered depends on the board, and is described by a
+//! [`BarWiring`].
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, Max7219, NUM_DIGITS, Result,
+    chain::{ChainLayout, DeviceKind},
+};
+
+/// Maximum number of LEDs driven by one MAX7219
+pub const MAX_LEDS: usize = 64;
+
+/// Digit register and segment bit driving one LED.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LedAddress {
+    /// Digit register (0 to 7)
+    pub digit: u8,
+    /// Bit of the digit register (0 = segment G, 7 = DP)
+    pub bit: u8,
+}
+
+impl LedAddress {
+    /// Create a new LED address
+    pub const fn new(digit: u8, bit: u8) -> Self {
+        Self { digit, bit }
+    }
+}
+
+/// How the LEDs of a bar are numbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BarWiring {
+    /// LEDs 0 to 7 are bits 0 to 7 of DIG0, LEDs 8 to 15 those of DIG1, and
+    /// so on
+    #[default]
+    DigitMajor,
+    /// LEDs 0 to 7 are bit 0 of DIG0 to DIG7, LEDs 8 to 15 bit 1, and so on
+    SegmentMajor,
+    /// The LED at each index of the table, for boards wired in another order
+    /// or with fewer than 64 LEDs
+    Custom(&'static [LedAddress]),
+}
+
+impl BarWiring {
+    /// Returns the number of LEDs in the bar.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::DigitMajor | Self::SegmentMajor => MAX_LEDS,
+            Self::Custom(map) => map.len(),
+        }
+    }
+
+    /// Returns `true` if the bar has no LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the address of the LED at `index`, or `None` past the end of
+    /// the bar.
+    pub fn locate(&self, index: usize) -> Option<LedAddress> {
+        if index >= self.len() {
+            return None;
+        }
+        let (major, minor) = ((index / 8) as u8, (index % 8) as u8);
+        match self {
+            Self::DigitMajor => Some(LedAddress::new(major, minor)),
+            Self::SegmentMajor => Some(LedAddress::new(minor, major)),
+            Self::Custom(map) => map.get(index).copied(),
+        }
+    }
+
+    /// Checks that every LED lies on the chip and none is listed twice.
+    fn validate(&self) -> Result<()> {
+        let mut used = [0u8; NUM_DIGITS as usize];
+        for index in 0..self.len() {
+            let address = self.locate(index).ok_or(Error::InvalidMapping)?;
+            let slot = used
+                .get_mut(address.digit as usize)
+                .filter(|_| address.bit <= 7)
+                .ok_or(Error::InvalidMapping)?;
+            if *slot & (1 << address.bit) != 0 {
+                return Err(Error::InvalidMapping);
+            }
+            *slot |= 1 << address.bit;
+        }
+        Ok(())
+    }
+}
+
+/// A bar of discrete LEDs driven by one device of the chain.
+///
+/// LEDs are switched through the driver's shadow state, so changing one
+/// LED doesn't disturb the others and only changed digit registers are
+/// sent. Digits of the device are switched to raw segment mode as needed.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut bar = BarDevice::from_spi(spi, 1)?;
+/// bar.set_level(40)?; // LEDs 0 to 39 on
+/// bar.set_led(63, true)?; // peak marker
+/// ```
+pub struct BarDevice<SPI> {
+    driver: Max7219<SPI>,
+    device_index: usize,
+    wiring: BarWiring,
+}
+
+impl<SPI> BarDevice<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Creates a bar on device 0 of an existing driver, with
+    /// [`BarWiring::DigitMajor`] numbering.
+    pub fn new(driver: Max7219<SPI>) -> Self {
+        Self {
+            driver,
+            device_index: 0,
+            wiring: BarWiring::default(),
+        }
+    }
+
+    /// Creates and initializes the driver for `device_count` chained
+    /// devices, with the bar on device 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device count is invalid or the MAX7219
+    /// initialization fails.
+    pub fn from_spi(spi: SPI, device_count: usize) -> Result<Self> {
+        let mut driver = Max7219::new(spi).with_device_count(device_count)?;
+        driver.init()?;
+        Ok(Self::new(driver))
+    }
+
+    /// Selects the device driving the bar, consuming and returning it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    pub fn with_device(mut self, device_index: usize) -> Result<Self> {
+        if device_index >= self.driver.device_count() {
+            return Err(Error::InvalidDeviceIndex);
+        }
+        self.device_index = device_index;
+        Ok(self)
+    }
+
+    /// Sets how the LEDs are numbered, consuming and returning the bar.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidMapping` if a custom table has more than 64
+    /// entries, lists an LED twice or addresses one past DIG7 or bit 7.
+    pub fn with_wiring(mut self, wiring: BarWiring) -> Result<Self> {
+        wiring.validate()?;
+        self.wiring = wiring;
+        Ok(self)
+    }
+
+    /// Checks the bar against a chain layout, consuming and returning it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceCount` if the layout doesn't describe
+    /// every device of the chain, and `Error::WrongDeviceKind` if the bar's
+    /// device is not declared as [`DeviceKind::BarGraph`].
+    pub fn with_layout(self, layout: &ChainLayout) -> Result<Self> {
+        if layout.len() != self.driver.device_count() {
+            return Err(Error::InvalidDeviceCount);
+        }
+        layout.require(self.device_index, DeviceKind::BarGraph)?;
+        Ok(self)
+    }
+
+    /// Provides mutable access to the underlying MAX7219 driver.
+    pub fn driver(&mut self) -> &mut Max7219<SPI> {
+        &mut self.driver
+    }
+
+    /// Returns the device driving the bar.
+    pub fn device_index(&self) -> usize {
+        self.device_index
+    }
+
+    /// Returns how the LEDs are numbered.
+    pub fn wiring(&self) -> BarWiring {
+        self.wiring
+    }
+
+    /// Returns the number of LEDs in the bar.
+    pub fn len(&self) -> usize {
+        self.wiring.len()
+    }
+
+    /// Returns `true` if the bar has no LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.wiring.is_empty()
+    }
+
+    /// Returns whether the LED at `index` is on, as last written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `index` is past the end of the bar.
+    pub fn led(&self, index: usize) -> Result<bool> {
+        let address = self.wiring.locate(index).ok_or(Error::BufferError)?;
+        self.driver
+            .led(self.device_index, address.digit, address.bit)
+    }
+
+    /// Switches the LED at `index` on or off, leaving the others as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `index` is past the end of the bar.
+    pub fn set_led(&mut self, index: usize, on: bool) -> Result<()> {
+        let address = self.wiring.locate(index).ok_or(Error::BufferError)?;
+        self.set_raw_mode()?;
+        self.driver
+            .set_led(self.device_index, address.digit, address.bit, on)
+    }
+
+    /// Lights the first `level` LEDs and switches the rest of the bar off.
+    ///
+    /// LEDs of the device that are not part of a custom wiring are left as
+    /// they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `level` is more than the number of
+    /// LEDs in the bar.
+    pub fn set_level(&mut self, level: usize) -> Result<()> {
+        if level > self.len() {
+            return Err(Error::BufferError);
+        }
+        let mut digits = *self.driver.device_state(self.device_index)?.digits();
+        for index in 0..self.len() {
+            if let Some(address) = self.wiring.locate(index)
+                && let Some(digit) = digits.get_mut(address.digit as usize)
+            {
+                let mask = 1 << address.bit;
+                if index < level {
+                    *digit |= mask;
+                } else {
+                    *digit &= !mask;
+                }
+            }
+        }
+        self.write_digits(&digits)
+    }
+
+    /// Returns the number of LEDs lit from the start of the bar.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if the driver lost the device.
+    pub fn level(&self) -> Result<usize> {
+        for index in 0..self.len() {
+            if !self.led(index)? {
+                return Ok(index);
+            }
+        }
+        Ok(self.len())
+    }
+
+    /// Switches every LED of the bar off.
+    pub fn clear(&mut self) -> Result<()> {
+        self.set_level(0)
+    }
+
+    /// Switches the device to raw segment mode if needed.
+    fn set_raw_mode(&mut self) -> Result<()> {
+        if self.driver.device_state(self.device_index)?.decode_mode() != 0 {
+            self.driver.set_device_decode_mask(self.device_index, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the digit registers that differ from the shadow state,
+    /// switching the device to raw segment mode first if needed.
+    fn write_digits(&mut self, digits: &[u8; NUM_DIGITS as usize]) -> Result<()> {
+        self.set_raw_mode()?;
+        let shown = *self.driver.device_state(self.device_index)?.digits();
+        for (digit, (&data, &current)) in digits.iter().zip(shown.iter()).enumerate() {
+            if data != current {
+                self.driver
+                    .write_raw_digit(self.device_index, digit as u8, data)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    fn write_reg(addr: u8, value: u8) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![addr, value]),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    #[test]
+    fn test_wiring_locate() {
+        assert_eq!(
+            BarWiring::DigitMajor.locate(10),
+            Some(LedAddress::new(1, 2))
+        );
+        assert_eq!(
+            BarWiring::SegmentMajor.locate(10),
+            Some(LedAddress::new(2, 1))
+        );
+        assert_eq!(BarWiring::DigitMajor.locate(64), None);
+
+        static MAP: [LedAddress; 2] = [LedAddress::new(3, 0), LedAddress::new(3, 0)];
+        assert_eq!(
+            BarWiring::Custom(&MAP).validate(),
+            Err(Error::InvalidMapping)
+        );
+        assert_eq!(BarWiring::Custom(&MAP[..1]).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_set_level_sends_changed_digits() {
+        let mut expected = Vec::new();
+        expected.extend(write_reg(Register::Digit0.addr(), 0xFF));
+        expected.extend(write_reg(Register::Digit1.addr(), 0b0000_0011));
+        expected.extend(write_reg(Register::Digit1.addr(), 0b0000_0001));
+        expected.extend(write_reg(Register::Digit7.addr(), 0b1000_0000));
+
+        let mut spi = SpiMock::new(&expected);
+        let mut bar = BarDevice::new(Max7219::new(&mut spi));
+        bar.set_level(10).unwrap();
+        bar.set_level(9).unwrap();
+        bar.set_led(63, true).unwrap();
+
+        assert_eq!(bar.level(), Ok(9));
+        assert_eq!(bar.led(63), Ok(true));
+        assert_eq!(bar.set_level(65), Err(Error::BufferError));
+        assert_eq!(bar.set_led(64, true), Err(Error::BufferError));
+        spi.done();
+    }
+
+    #[test]
+    fn test_with_layout() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let layout = ChainLayout::new(&[DeviceKind::Matrix, DeviceKind::BarGraph]).unwrap();
+
+        let bar = BarDevice::new(driver).with_layout(&layout);
+        assert!(matches!(
+            bar,
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::Matrix
+            })
+        ));
+        spi.done();
+
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let bar = BarDevice::new(driver)
+            .with_device(1)
+            .and_then(|bar| bar.with_layout(&layout))
+            .unwrap();
+        assert_eq!(bar.device_index(), 1);
+        spi.done();
+    }
+}
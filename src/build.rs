@@ -0,0 +1,409 @@
+//! Build-script helpers turning image sequences into animations
+//!
+//! Artists draw frames as monochrome PNG or BMP files in any image editor,
+//! one file per frame, and keep them in a folder. A build script converts
+//! the folder into RLE-compressed [`Animation`] data, which the firmware
+//! embeds with [`include_frames!`](crate::include_frames!):
+//!
+//! ```toml
+//! [build-dependencies]
+//! max7219-display = { version = "0.1", features = ["build"] }
+//! ```
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     max7219_display::build::emit_frames("assets/spinner", "spinner").unwrap();
+//! }
+//!
+//! // main.rs
+//! static SPINNER: Animation = max7219_display::include_frames!("spinner");
+//! ```
+//!
+//! Frames are played in file name order, so name them `00.png`, `01.png`,
+//! and so on. Light pixels (luminance of at least 50 %) are lit, dark or
+//! mostly transparent ones are off. Width and height must be multiples of
+//! 8; each 8x8 tile becomes one module of the frame, row by row, matching
+//! how [`LedMatrix::play_animation`] lays frames out.
+//!
+//! Needs `std`, so enable the feature for build dependencies only.
+//!
+//! [`Animation`]: crate::led_matrix::Animation
+//! [`LedMatrix::play_animation`]: crate::led_matrix::LedMatrix::play_animation
+
+use std::{
+    env,
+    ffi::OsStr,
+    format,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    println, vec,
+    vec::Vec,
+};
+
+use crate::{MAX_DISPLAYS, led_matrix::animation::encode_rle};
+
+/// Light level from which a pixel is lit
+const THRESHOLD: u32 = 128;
+
+/// A decoded image, reduced to lit and dark pixels
+struct Bitmap {
+    width: usize,
+    height: usize,
+    /// Row-major, top row first
+    lit: Vec<bool>,
+}
+
+impl Bitmap {
+    /// Returns whether the pixel at (`x`, `y`) is lit.
+    fn is_lit(&self, x: usize, y: usize) -> bool {
+        y.checked_mul(self.width)
+            .and_then(|row| row.checked_add(x))
+            .and_then(|index| self.lit.get(index))
+            .is_some_and(|&lit| lit)
+    }
+
+    /// Returns the rows of every 8x8 tile, tiles row by row.
+    fn rows(&self) -> Vec<u8> {
+        let tiles_per_row = self.width / 8;
+        let tiles = tiles_per_row.saturating_mul(self.height / 8);
+        let mut rows = Vec::with_capacity(tiles.saturating_mul(8));
+        for tile in 0..tiles {
+            let left = tile
+                .checked_rem(tiles_per_row)
+                .unwrap_or(0)
+                .saturating_mul(8);
+            let top = tile
+                .checked_div(tiles_per_row)
+                .unwrap_or(0)
+                .saturating_mul(8);
+            for y in top..top.saturating_add(8) {
+                let row = (0..8)
+                    .filter(|&col| self.is_lit(left.saturating_add(col), y))
+                    .fold(0, |row, col| row | (0x80 >> col));
+                rows.push(row);
+            }
+        }
+        rows
+    }
+}
+
+/// Returns an `InvalidData` error for `path`.
+fn invalid(path: &Path, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {message}", path.display()),
+    )
+}
+
+/// Returns whether a pixel with light level `level` and opacity `alpha`
+/// (both 0 to 255) is lit.
+fn lit(level: u32, alpha: u32) -> bool {
+    level >= THRESHOLD && alpha >= THRESHOLD
+}
+
+/// Returns the light level of an RGB color, 0 to 255.
+fn luminance(red: u8, green: u8, blue: u8) -> u32 {
+    // At most 255 * 1000, far from overflowing
+    u32::from(red)
+        .wrapping_mul(299)
+        .wrapping_add(u32::from(green).wrapping_mul(587))
+        .wrapping_add(u32::from(blue).wrapping_mul(114))
+        / 1000
+}
+
+/// Decodes a PNG file of any color type and bit depth.
+fn read_png(path: &Path) -> io::Result<Bitmap> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|error| invalid(path, &format!("{error}")))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|error| invalid(path, &format!("{error}")))?;
+
+    let channels = info.color_type.samples();
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut lit_pixels = Vec::with_capacity(width.saturating_mul(height));
+    for line in buffer.chunks(info.line_size).take(height) {
+        for pixel in line.chunks_exact(channels).take(width) {
+            let lit_pixel = match *pixel {
+                [level] => lit(level.into(), 255),
+                [level, alpha] => lit(level.into(), alpha.into()),
+                [red, green, blue] => lit(luminance(red, green, blue), 255),
+                [red, green, blue, alpha] => lit(luminance(red, green, blue), alpha.into()),
+                _ => return Err(invalid(path, "unsupported color type")),
+            };
+            lit_pixels.push(lit_pixel);
+        }
+    }
+    Ok(Bitmap {
+        width,
+        height,
+        lit: lit_pixels,
+    })
+}
+
+/// Reads a little-endian `u16` at `offset`.
+fn le_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a little-endian `u32` at `offset`.
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+/// Decodes an uncompressed BMP file with 1, 4, 8, 24 or 32 bits per pixel.
+fn read_bmp(path: &Path) -> io::Result<Bitmap> {
+    let data = fs::read(path)?;
+    let header = |offset| le_u32(&data, offset).ok_or_else(|| invalid(path, "truncated header"));
+    if !data.starts_with(b"BM") {
+        return Err(invalid(path, "not a BMP file"));
+    }
+    let pixels = header(10)? as usize;
+    let palette = (header(14)? as usize).saturating_add(14);
+    let width = header(18)? as i32;
+    let height = header(22)? as i32;
+    let bits = le_u16(&data, 28).ok_or_else(|| invalid(path, "truncated header"))?;
+    let compression = header(30)?;
+    // Bitfields are accepted for 32 bits per pixel, assuming BGRA order
+    if compression != 0 && !(compression == 3 && bits == 32) {
+        return Err(invalid(path, "compressed BMP files are not supported"));
+    }
+    if width <= 0 || height == 0 {
+        return Err(invalid(path, "empty image"));
+    }
+
+    let (width, top_down) = (width as usize, height < 0);
+    let height = height.unsigned_abs() as usize;
+    let stride = usize::from(bits)
+        .checked_mul(width)
+        .map(|line_bits| line_bits.div_ceil(32))
+        .and_then(|words| words.checked_mul(4))
+        .ok_or_else(|| invalid(path, "image too wide"))?;
+    let color = |index: usize| -> io::Result<u32> {
+        match data.get(palette.saturating_add(index.saturating_mul(4))..) {
+            Some(&[blue, green, red, ..]) => Ok(luminance(red, green, blue)),
+            _ => Err(invalid(path, "palette index out of range")),
+        }
+    };
+
+    let mut lit_pixels = Vec::with_capacity(width.saturating_mul(height));
+    for y in 0..height {
+        let line = if top_down {
+            y
+        } else {
+            height.saturating_sub(1).saturating_sub(y)
+        };
+        let line = line
+            .checked_mul(stride)
+            .and_then(|offset| offset.checked_add(pixels))
+            .and_then(|start| data.get(start..start.checked_add(stride)?))
+            .ok_or_else(|| invalid(path, "truncated pixel data"))?;
+        for x in 0..width {
+            // Within the line, whose length in bits was checked above
+            let bit = x.wrapping_mul(usize::from(bits));
+            let level = match (bits, line.get(bit / 8..)) {
+                (1 | 4 | 8, Some(&[byte, ..])) => {
+                    // Pixels of 1, 4 or 8 bits never straddle a byte
+                    let shift = 8usize.wrapping_sub(usize::from(bits)).wrapping_sub(bit % 8);
+                    let mask = u8::MAX >> (8u16.wrapping_sub(bits));
+                    color(usize::from((byte >> shift) & mask))?
+                }
+                (24 | 32, Some(&[blue, green, red, ..])) => luminance(red, green, blue),
+                _ => return Err(invalid(path, "unsupported bits per pixel")),
+            };
+            lit_pixels.push(lit(level, 255));
+        }
+    }
+    Ok(Bitmap {
+        width,
+        height,
+        lit: lit_pixels,
+    })
+}
+
+/// Returns the PNG and BMP files in `dir`, in file name order.
+fn image_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let extension = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase);
+        if matches!(extension.as_deref(), Some("png" | "bmp")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Converts the PNG and BMP images in `dir` into RLE data for
+/// [`Animation::from_rle`](crate::led_matrix::Animation::from_rle), one
+/// frame per image in file name order.
+///
+/// # Errors
+///
+/// Returns an `InvalidData` error if the folder holds no images, an image
+/// can't be decoded, the images differ in size or their size is not a
+/// multiple of 8 with at most [`MAX_DISPLAYS`] tiles.
+pub fn encode_frames(dir: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let dir = dir.as_ref();
+    let mut frames = Vec::new();
+    let mut size = None;
+    for path in image_files(dir)? {
+        let is_png = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        let bitmap = if is_png {
+            read_png(&path)?
+        } else {
+            read_bmp(&path)?
+        };
+        if bitmap.width % 8 != 0 || bitmap.height % 8 != 0 {
+            return Err(invalid(&path, "width and height must be multiples of 8"));
+        }
+        if *size.get_or_insert((bitmap.width, bitmap.height)) != (bitmap.width, bitmap.height) {
+            return Err(invalid(&path, "all frames must have the same size"));
+        }
+        frames.extend(bitmap.rows());
+    }
+
+    let Some((width, height)) = size else {
+        return Err(invalid(dir, "no PNG or BMP images found"));
+    };
+    let modules = (width / 8).saturating_mul(height / 8);
+    if modules == 0 || modules > MAX_DISPLAYS {
+        return Err(invalid(
+            dir,
+            &format!("frames must have 1 to {MAX_DISPLAYS} tiles of 8x8 pixels"),
+        ));
+    }
+    // Frames are at most 64 bytes, so even incompressible ones only grow by
+    // one control byte each
+    let capacity = frames
+        .len()
+        .saturating_add(frames.len().div_ceil(8))
+        .saturating_add(1);
+    let mut data = vec![0; capacity];
+    let len = encode_rle(&frames, modules, &mut data)
+        .map_err(|error| invalid(dir, &format!("{error}")))?;
+    data.truncate(len);
+    Ok(data)
+}
+
+/// Converts the images in `dir` with [`encode_frames`] and writes the
+/// result to `$OUT_DIR/<name>.rle`, to be embedded with
+/// [`include_frames!`](crate::include_frames!). Call it from a build script; it asks Cargo to rerun
+/// the script when the folder changes.
+///
+/// # Errors
+///
+/// Returns the errors of [`encode_frames`], or an error if `OUT_DIR` is
+/// not set or the file can't be written.
+pub fn emit_frames(dir: impl AsRef<Path>, name: &str) -> io::Result<()> {
+    let dir = dir.as_ref();
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let data = encode_frames(dir)?;
+    let out_dir = env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?;
+    fs::write(Path::new(&out_dir).join(format!("{name}.rle")), data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led_matrix::Animation;
+
+    /// Creates an empty folder for one test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("max7219-frames-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes an 8-bit grayscale PNG.
+    fn write_png(path: &Path, width: u32, height: u32, pixels: &[u8]) {
+        let mut encoder = png::Encoder::new(File::create(path).unwrap(), width, height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(pixels).unwrap();
+    }
+
+    /// Writes a bottom-up 24-bit BMP.
+    fn write_bmp(path: &Path, width: usize, height: usize, pixels: &[u8]) {
+        let stride = (24 * width).div_ceil(32) * 4;
+        let mut data = Vec::new();
+        data.extend(b"BM");
+        data.extend(((54 + stride * height) as u32).to_le_bytes());
+        data.extend([0; 4]);
+        data.extend(54u32.to_le_bytes());
+        data.extend(40u32.to_le_bytes());
+        data.extend((width as i32).to_le_bytes());
+        data.extend((height as i32).to_le_bytes());
+        data.extend(1u16.to_le_bytes());
+        data.extend(24u16.to_le_bytes());
+        data.extend([0; 24]);
+        for y in (0..height).rev() {
+            let mut line = vec![0; stride];
+            for x in 0..width {
+                line[x * 3..x * 3 + 3].fill(pixels[y * width + x]);
+            }
+            data.extend(line);
+        }
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn test_encode_frames() {
+        let dir = test_dir("sequence");
+        // Frame 0 lights the top-left pixel of the left tile, frame 1 the
+        // bottom-right pixel of the right tile
+        let mut first = [0u8; 16 * 8];
+        first[0] = 0xFF;
+        let mut second = [0u8; 16 * 8];
+        second[16 * 8 - 1] = 0xC0;
+        write_png(&dir.join("00.png"), 16, 8, &first);
+        write_bmp(&dir.join("01.bmp"), 16, 8, &second);
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let data = encode_frames(&dir).unwrap();
+        let animation = Animation::from_rle(&data).unwrap();
+        assert_eq!(animation.modules(), 2);
+        assert_eq!(animation.frame_count(), Ok(2));
+        let mut frame = [0; 16];
+        animation.frame(0, &mut frame).unwrap();
+        assert_eq!(frame, [0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        animation.frame(1, &mut frame).unwrap();
+        assert_eq!(frame, [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        let dir = test_dir("invalid");
+        assert_eq!(
+            encode_frames(&dir).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        write_png(&dir.join("00.png"), 8, 8, &[0; 64]);
+        write_png(&dir.join("01.png"), 16, 8, &[0; 128]);
+        assert_eq!(
+            encode_frames(&dir).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
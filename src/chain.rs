@@ -0,0 +1,219 @@
+//! Description of what each device in a chain drives
+//!
+//! A chain doesn't have to be made of one kind of module: a front panel may
+//! combine an 8x8 matrix for icons with an 8-digit 7-segment module for
+//! numbers. A [`ChainLayout`] records the kind and mounting of every device,
+//! so code driving the whole chain knows how to render into each of them.
+//!
+//! [`LedMatrix`](crate::LedMatrix) and [`SevenSegment`](crate::SevenSegment)
+//! accept a layout with `with_layout`: they then only drive the devices of
+//! their own kind and return `Error::WrongDeviceKind` for operations aimed at
+//! another one. [`BarDevice`](crate::BarDevice) checks that its device is a
+//! bar graph.
+
+use crate::{Error, MAX_DISPLAYS, Result};
+
+/// What a MAX7219 in the chain is wired to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeviceKind {
+    /// An 8x8 LED matrix
+    #[default]
+    Matrix,
+    /// Eight 7-segment digits
+    SevenSegment,
+    /// Up to 64 discrete LEDs, such as bar graphs
+    BarGraph,
+}
+
+/// How a module is mounted relative to its neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mounting {
+    /// The module's top edge is up
+    #[default]
+    Normal,
+    /// The module is rotated by 180 degrees
+    UpsideDown,
+}
+
+/// Kind and mounting of every device in a chain, indexed like the driver
+/// (0 = furthest from the microcontroller).
+///
+/// # Example
+///
+/// ```rust
+/// use max7219_display::chain::{ChainLayout, DeviceKind, Mounting};
+///
+/// // 7-segment module furthest from the MCU, two matrices before it
+/// let layout = ChainLayout::new(&[
+///     DeviceKind::SevenSegment,
+///     DeviceKind::Matrix,
+///     DeviceKind::Matrix,
+/// ])?;
+/// assert_eq!(layout.kind(0)?, DeviceKind::SevenSegment);
+///
+/// // The first matrix is mounted upside down
+/// let layout = layout.with_mounting(1, Mounting::UpsideDown)?;
+/// # Ok::<(), max7219_display::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainLayout {
+    kinds: [DeviceKind; MAX_DISPLAYS],
+    mountings: [Mounting; MAX_DISPLAYS],
+    len: usize,
+}
+
+impl ChainLayout {
+    /// Create a layout from the kind of each device, all mounted normally.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceCount` if `kinds` is empty or longer
+    /// than [`MAX_DISPLAYS`].
+    pub fn new(kinds: &[DeviceKind]) -> Result<Self> {
+        if kinds.is_empty() || kinds.len() > MAX_DISPLAYS {
+            return Err(Error::InvalidDeviceCount);
+        }
+        let mut layout = Self {
+            kinds: [DeviceKind::Matrix; MAX_DISPLAYS],
+            mountings: [Mounting::Normal; MAX_DISPLAYS],
+            len: kinds.len(),
+        };
+        for (slot, &kind) in layout.kinds.iter_mut().zip(kinds) {
+            *slot = kind;
+        }
+        Ok(layout)
+    }
+
+    /// Create a layout of `count` devices of the same kind.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceCount` if `count` is 0 or more than
+    /// [`MAX_DISPLAYS`].
+    pub fn uniform(kind: DeviceKind, count: usize) -> Result<Self> {
+        let kinds = [kind; MAX_DISPLAYS];
+        Self::new(kinds.get(..count).ok_or(Error::InvalidDeviceCount)?)
+    }
+
+    /// Sets how a device is mounted, consuming and returning the layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    pub fn with_mounting(mut self, device_index: usize, mounting: Mounting) -> Result<Self> {
+        let slot = self
+            .mountings
+            .get_mut(..self.len)
+            .and_then(|mountings| mountings.get_mut(device_index))
+            .ok_or(Error::InvalidDeviceIndex)?;
+        *slot = mounting;
+        Ok(self)
+    }
+
+    /// Returns the number of devices in the chain.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always `false`: a layout has at least one device.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the kind of a device.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    pub fn kind(&self, device_index: usize) -> Result<DeviceKind> {
+        self.kinds()
+            .get(device_index)
+            .copied()
+            .ok_or(Error::InvalidDeviceIndex)
+    }
+
+    /// Returns how a device is mounted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    pub fn mounting(&self, device_index: usize) -> Result<Mounting> {
+        self.mountings
+            .get(..self.len)
+            .and_then(|mountings| mountings.get(device_index))
+            .copied()
+            .ok_or(Error::InvalidDeviceIndex)
+    }
+
+    /// Checks that a device is of the given kind.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range
+    /// and `Error::WrongDeviceKind` if the device is of another kind.
+    pub fn require(&self, device_index: usize, kind: DeviceKind) -> Result<()> {
+        let actual = self.kind(device_index)?;
+        if actual != kind {
+            return Err(Error::WrongDeviceKind {
+                device_index,
+                kind: actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the kinds of all devices, indexed like the driver.
+    pub fn kinds(&self) -> &[DeviceKind] {
+        self.kinds.get(..self.len).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix]).unwrap();
+        assert_eq!(layout.len(), 2);
+        assert_eq!(layout.kind(1), Ok(DeviceKind::Matrix));
+        assert_eq!(layout.kind(2), Err(Error::InvalidDeviceIndex));
+
+        assert_eq!(
+            ChainLayout::uniform(DeviceKind::SevenSegment, 3).map(|layout| layout.len()),
+            Ok(3)
+        );
+        assert_eq!(ChainLayout::new(&[]), Err(Error::InvalidDeviceCount));
+        assert_eq!(
+            ChainLayout::uniform(DeviceKind::Matrix, MAX_DISPLAYS + 1),
+            Err(Error::InvalidDeviceCount)
+        );
+    }
+
+    #[test]
+    fn test_mounting_and_require() {
+        let layout = ChainLayout::new(&[DeviceKind::BarGraph, DeviceKind::Matrix])
+            .unwrap()
+            .with_mounting(1, Mounting::UpsideDown)
+            .unwrap();
+        assert_eq!(layout.mounting(0), Ok(Mounting::Normal));
+        assert_eq!(layout.mounting(1), Ok(Mounting::UpsideDown));
+        assert_eq!(
+            layout.with_mounting(2, Mounting::UpsideDown),
+            Err(Error::InvalidDeviceIndex)
+        );
+
+        assert_eq!(layout.require(1, DeviceKind::Matrix), Ok(()));
+        assert_eq!(
+            layout.require(0, DeviceKind::Matrix),
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::BarGraph
+            })
+        );
+        assert_eq!(
+            layout.require(2, DeviceKind::Matrix),
+            Err(Error::InvalidDeviceIndex)
+        );
+    }
+}
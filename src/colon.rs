@@ -0,0 +1,363 @@
+//! Blinking time separator
+//!
+//! A [`Colon`] blinks the separator of a home-made time display, either the
+//! decimal points of one or two 7-segment digits or two dots of pixels on
+//! an LED matrix. It keeps the watch-style rhythm of one second on and one
+//! second off, driven by `tick(dt_ms)`, so applications composing their own
+//! clock don't need a full clock widget for it.
+//!
+//! ```rust
+//! use max7219_display::colon::Colon;
+//!
+//! let mut colon = Colon::decimal_points(1, 2);
+//! assert!(colon.is_visible());
+//! assert!(colon.tick(1000)); // off after one second
+//! assert!(!colon.is_visible());
+//! ```
+
+#[cfg(any(feature = "led-matrix", feature = "seven-segment"))]
+use embedded_hal::spi::SpiDevice;
+
+#[cfg(any(feature = "led-matrix", feature = "seven-segment"))]
+use crate::Result;
+
+#[cfg(feature = "led-matrix")]
+use crate::led_matrix::{LedMatrix, display::UpdateMode};
+
+#[cfg(feature = "seven-segment")]
+use crate::seven_segment::{SevenSegment, fonts::DP};
+
+/// Default blink period: one second on, one second off (0.5 Hz)
+pub const DEFAULT_PERIOD_MS: u32 = 2000;
+
+/// What a [`Colon`] lights up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColonTarget {
+    /// The decimal points of one or two 7-segment digits (0 = leftmost)
+    DecimalPoints {
+        /// Digit whose decimal point is the (upper) dot
+        first: usize,
+        /// Digit whose decimal point is the second dot, if any
+        second: Option<usize>,
+    },
+    /// Two square dots of pixels on an LED matrix, one above the other
+    Dots {
+        /// Left column of both dots
+        x: usize,
+        /// Top row of the upper dot
+        top: usize,
+        /// Top row of the lower dot
+        bottom: usize,
+        /// Width and height of each dot in pixels
+        size: usize,
+    },
+}
+
+/// Blinking separator bound to decimal points or matrix pixels.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // "12.34" on a 7-segment display, with the decimal point of digit 1
+/// // as separator
+/// let mut colon = Colon::decimal_point(1);
+/// display.write_str("1234", &STANDARD_FONT)?;
+/// loop {
+///     if colon.tick(50) {
+///         colon.apply_segments(&mut display)?;
+///     }
+///     delay.delay_ms(50);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colon {
+    target: ColonTarget,
+    period_ms: u32,
+    elapsed_ms: u32,
+    blinking: bool,
+}
+
+impl Colon {
+    /// Blinks the given target with the default period
+    pub const fn new(target: ColonTarget) -> Self {
+        Self {
+            target,
+            period_ms: DEFAULT_PERIOD_MS,
+            elapsed_ms: 0,
+            blinking: true,
+        }
+    }
+
+    /// Blinks the decimal point of the digit at `position`
+    pub const fn decimal_point(position: usize) -> Self {
+        Self::new(ColonTarget::DecimalPoints {
+            first: position,
+            second: None,
+        })
+    }
+
+    /// Blinks the decimal points of the digits at `first` and `second`,
+    /// e.g. on displays with a colon wired to two DP segments
+    pub const fn decimal_points(first: usize, second: usize) -> Self {
+        Self::new(ColonTarget::DecimalPoints {
+            first,
+            second: Some(second),
+        })
+    }
+
+    /// Blinks two one-pixel dots at column `x`, rows `top` and `bottom`
+    pub const fn dots(x: usize, top: usize, bottom: usize) -> Self {
+        Self::new(ColonTarget::Dots {
+            x,
+            top,
+            bottom,
+            size: 1,
+        })
+    }
+
+    /// Sets the width and height of matrix dots in pixels
+    pub const fn with_dot_size(mut self, dot_size: usize) -> Self {
+        if let ColonTarget::Dots { x, top, bottom, .. } = self.target {
+            self.target = ColonTarget::Dots {
+                x,
+                top,
+                bottom,
+                size: dot_size,
+            };
+        }
+        self
+    }
+
+    /// Sets the time for one on/off cycle in milliseconds
+    pub const fn with_period_ms(mut self, period_ms: u32) -> Self {
+        self.period_ms = period_ms;
+        self
+    }
+
+    /// Returns what the colon lights up.
+    pub fn target(&self) -> ColonTarget {
+        self.target
+    }
+
+    /// Returns the time for one on/off cycle in milliseconds.
+    pub fn period_ms(&self) -> u32 {
+        self.period_ms
+    }
+
+    /// Starts or stops blinking. A colon that doesn't blink stays lit,
+    /// e.g. while the time is being set.
+    pub fn set_blinking(&mut self, blinking: bool) {
+        self.blinking = blinking;
+    }
+
+    /// Returns whether the colon blinks.
+    pub fn is_blinking(&self) -> bool {
+        self.blinking
+    }
+
+    /// Restarts the cycle with the colon lit.
+    ///
+    /// Call it when the displayed seconds change to keep the blink in step
+    /// with the clock.
+    pub fn sync(&mut self) {
+        self.elapsed_ms = 0;
+    }
+
+    /// Returns whether the colon is currently lit.
+    pub fn is_visible(&self) -> bool {
+        !self.blinking || self.period_ms == 0 || self.elapsed_ms < self.period_ms / 2
+    }
+
+    /// Advances the blink by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the colon switched on or off and needs redrawing.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        if self.period_ms == 0 {
+            return false;
+        }
+        let was_visible = self.is_visible();
+        self.elapsed_ms = u64::from(self.elapsed_ms)
+            .saturating_add(u64::from(dt_ms))
+            .checked_rem(u64::from(self.period_ms))
+            .unwrap_or(0) as u32;
+        self.is_visible() != was_visible
+    }
+
+    /// Sets or clears the decimal points of the target digits to match the
+    /// blink, leaving the rest of the digits as they are.
+    ///
+    /// Works for digits in Code B decode mode as well as raw segment
+    /// patterns, and only writes digits that change. Does nothing for a
+    /// [`ColonTarget::Dots`] target.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if a position is past the last digit.
+    #[cfg(feature = "seven-segment")]
+    pub fn apply_segments<SPI: SpiDevice>(&self, display: &mut SevenSegment<SPI>) -> Result<()> {
+        let ColonTarget::DecimalPoints { first, second } = self.target else {
+            return Ok(());
+        };
+        let visible = self.is_visible();
+        for position in core::iter::once(first).chain(second) {
+            let (device_index, digit) =
+                display.locate(position).ok_or(crate::Error::InvalidDigit)?;
+            // The decimal point keeps its bit on upside-down devices
+            let (digit, _) = display.physical(device_index, digit, DP);
+            let current = display.driver().device_state(device_index)?.digit(digit)?;
+            let wanted = if visible { current | DP } else { current & !DP };
+            if wanted != current {
+                display
+                    .driver()
+                    .write_raw_digit(device_index, digit, wanted)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws or erases the dots in the framebuffer to match the blink.
+    ///
+    /// In [`UpdateMode::WriteThrough`] the dots' rows are flushed right
+    /// away. Does nothing for a [`ColonTarget::DecimalPoints`] target.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if a dot lies outside the display.
+    #[cfg(feature = "led-matrix")]
+    pub fn draw<
+        SPI: SpiDevice,
+        const BUFFER_LENGTH: usize,
+        const DEVICE_COUNT: usize,
+        const LAYERS: usize,
+    >(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()> {
+        let ColonTarget::Dots {
+            x,
+            top,
+            bottom,
+            size,
+        } = self.target
+        else {
+            return Ok(());
+        };
+        let visible = self.is_visible();
+        for row in [top, bottom] {
+            for dy in 0..size {
+                for dx in 0..size {
+                    matrix.set_pixel(x.saturating_add(dx), row.saturating_add(dy), visible)?;
+                }
+            }
+        }
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            let first = top.min(bottom);
+            let last = top.max(bottom).saturating_add(size);
+            matrix.flush_region(x, first, size, last.saturating_sub(first))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blink_cycle() {
+        let mut colon = Colon::dots(3, 2, 5);
+        assert!(colon.is_visible());
+        assert!(!colon.tick(999));
+        assert!(colon.tick(1));
+        assert!(!colon.is_visible());
+        assert!(colon.tick(1000));
+        assert!(colon.is_visible());
+
+        colon.tick(1500);
+        colon.sync();
+        assert!(colon.is_visible());
+
+        colon.set_blinking(false);
+        assert!(!colon.tick(1000));
+        assert!(colon.is_visible());
+    }
+
+    #[cfg(feature = "seven-segment")]
+    #[test]
+    fn test_apply_segments() {
+        use crate::{Max7219, Register};
+        use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+        let expected_transactions = [
+            // Lit: DP of digit 1 on the single device
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit1.addr(), DP]),
+            Transaction::transaction_end(),
+            // Off again
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit1.addr(), 0x00]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut display = SevenSegment::new(Max7219::new(&mut spi));
+        let mut colon = Colon::decimal_point(1);
+
+        colon.apply_segments(&mut display).unwrap();
+        colon.apply_segments(&mut display).unwrap();
+        colon.tick(1000);
+        colon.apply_segments(&mut display).unwrap();
+        assert!(
+            Colon::decimal_point(8)
+                .apply_segments(&mut display)
+                .is_err()
+        );
+        spi.done();
+
+        // Digit 1 of an upside-down device is register 6
+        use crate::chain::{ChainLayout, DeviceKind, Mounting};
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit6.addr(), DP]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment])
+            .unwrap()
+            .with_mounting(0, Mounting::UpsideDown)
+            .unwrap();
+        let mut display = SevenSegment::new(Max7219::new(&mut spi))
+            .with_layout(layout)
+            .unwrap();
+        Colon::decimal_point(1)
+            .apply_segments(&mut display)
+            .unwrap();
+        spi.done();
+    }
+
+    #[cfg(feature = "led-matrix")]
+    #[test]
+    fn test_draw_dots() {
+        use crate::{Max7219, led_matrix::display::SingleMatrix};
+        use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+        let mut spi = SpiMock::new(&[]);
+        let mut matrix = SingleMatrix::from_driver(Max7219::new(&mut spi)).unwrap();
+        matrix.set_update_mode(UpdateMode::Buffered);
+        let mut colon = Colon::dots(3, 1, 5).with_dot_size(2);
+
+        colon.draw(&mut matrix).unwrap();
+        assert_eq!(matrix.get_pixel(4, 2), Ok(true));
+        assert_eq!(matrix.get_pixel(4, 6), Ok(true));
+        assert_eq!(matrix.get_pixel(4, 3), Ok(false));
+        colon.tick(1000);
+        colon.draw(&mut matrix).unwrap();
+        assert_eq!(matrix.get_pixel(3, 1), Ok(false));
+        assert!(
+            Colon::dots(7, 0, 4)
+                .with_dot_size(2)
+                .draw(&mut matrix)
+                .is_err()
+        );
+        spi.done();
+    }
+}
@@ -0,0 +1,361 @@
+//! Recording register writes and sending them in as few transactions as possible
+//!
+//! With the `nb` feature, [`Max7219::poll_execute`] sends a batch one
+//! transaction per call as an [`nb::Result`], for frameworks that poll
+//! until an operation stops returning `WouldBlock`.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{Error, MAX_DISPLAYS, Max7219, Register, Result};
+
+/// One recorded register write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Command {
+    device_index: usize,
+    register: Register,
+    data: u8,
+}
+
+/// Register writes to several devices, recorded first and sent together by
+/// [`Max7219::execute`].
+///
+/// Every SPI transaction carries one packet per device, so writes to
+/// different devices can share a transaction. The batch packs them: the
+/// first write of every device goes in the first transaction, the second in
+/// the next one, and so on, which takes as many transactions as the busiest
+/// device has writes. Devices with nothing left to do get no-ops.
+///
+/// Writes to one device are sent in the order they were recorded; writes
+/// to different devices in one transaction take effect at the same time.
+/// Repeated writes are all sent, since some sequences, like a shutdown
+/// pulse, depend on them.
+///
+/// `N` is the number of writes the batch can hold.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut batch = CommandBatch::<16>::new();
+/// batch.push(0, Register::Intensity, 2)?;
+/// batch.push(3, Register::Intensity, 15)?;
+/// batch.push(3, Register::Digit0, 0xFF)?;
+/// driver.execute(&batch)?; // 2 transactions instead of 3 full-chain writes
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandBatch<const N: usize = 64> {
+    commands: [Command; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for CommandBatch<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> CommandBatch<N> {
+    /// Create an empty batch
+    pub const fn new() -> Self {
+        Self {
+            commands: [Command {
+                device_index: 0,
+                register: Register::NoOp,
+                data: 0,
+            }; N],
+            len: 0,
+        }
+    }
+
+    /// Records a register write to one device.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is not below
+    /// [`MAX_DISPLAYS`] and `Error::BufferError` if the batch is full.
+    pub fn push(&mut self, device_index: usize, register: Register, data: u8) -> Result<()> {
+        if device_index >= MAX_DISPLAYS {
+            return Err(Error::InvalidDeviceIndex);
+        }
+        let slot = self.commands.get_mut(self.len).ok_or(Error::BufferError)?;
+        *slot = Command {
+            device_index,
+            register,
+            data,
+        };
+        self.len = self.len.saturating_add(1);
+        Ok(())
+    }
+
+    /// Records the same register write to every device from 0 to
+    /// `device_count - 1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_count` is more than
+    /// [`MAX_DISPLAYS`] and `Error::BufferError` if the batch can't hold the
+    /// writes; nothing is recorded then.
+    pub fn push_all(&mut self, device_count: usize, register: Register, data: u8) -> Result<()> {
+        if device_count > MAX_DISPLAYS {
+            return Err(Error::InvalidDeviceIndex);
+        }
+        if self.len.saturating_add(device_count) > N {
+            return Err(Error::BufferError);
+        }
+        for device_index in 0..device_count {
+            self.push(device_index, register, data)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of recorded writes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no write is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every recorded write.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns the number of SPI transactions [`Max7219::execute`] sends
+    /// for this batch.
+    pub fn transaction_count(&self) -> usize {
+        let mut writes = [0usize; MAX_DISPLAYS];
+        for command in self.commands() {
+            if let Some(count) = writes.get_mut(command.device_index) {
+                *count = count.saturating_add(1);
+            }
+        }
+        writes.into_iter().max().unwrap_or(0)
+    }
+
+    fn commands(&self) -> &[Command] {
+        self.commands.get(..self.len).unwrap_or_default()
+    }
+}
+
+/// How far a [`CommandBatch`] has been sent by [`Max7219::poll_execute`].
+///
+/// Start every run of a batch with a new progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchProgress {
+    /// Index of the next write to send for each device
+    next: [usize; MAX_DISPLAYS],
+    transactions: usize,
+}
+
+impl BatchProgress {
+    /// Create the progress of a batch nothing was sent of yet
+    pub const fn new() -> Self {
+        Self {
+            next: [0; MAX_DISPLAYS],
+            transactions: 0,
+        }
+    }
+
+    /// Returns the number of SPI transactions sent so far.
+    pub fn transactions(&self) -> usize {
+        self.transactions
+    }
+}
+
+impl<SPI> Max7219<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Sends the writes of a batch, packing writes to different devices
+    /// into shared transactions.
+    ///
+    /// Returns the number of SPI transactions sent. The batch is left as it
+    /// is, so it can be sent again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` before sending anything if a write
+    /// targets a device past the end of the chain.
+    pub fn execute<const N: usize>(&mut self, batch: &CommandBatch<N>) -> Result<usize> {
+        let mut progress = BatchProgress::new();
+        while self.send_next(batch, &mut progress)? {}
+        Ok(progress.transactions)
+    }
+
+    /// Sends the next transaction of a batch, reporting `WouldBlock` until
+    /// every write was sent; then returns the number of SPI transactions.
+    ///
+    /// Each call sends at most one transaction, so polling loops never
+    /// block for longer than that. The writes are packed like by
+    /// [`Self::execute`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` before sending anything if a write
+    /// targets a device past the end of the chain.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut progress = BatchProgress::new();
+    /// nb::block!(driver.poll_execute(&batch, &mut progress))?;
+    /// ```
+    #[cfg(feature = "nb")]
+    pub fn poll_execute<const N: usize>(
+        &mut self,
+        batch: &CommandBatch<N>,
+        progress: &mut BatchProgress,
+    ) -> nb::Result<usize, Error> {
+        if self.send_next(batch, progress)? {
+            Err(nb::Error::WouldBlock)
+        } else {
+            Ok(progress.transactions)
+        }
+    }
+
+    /// Sends the next transaction of a batch, returning `false` if every
+    /// write was already sent.
+    fn send_next<const N: usize>(
+        &mut self,
+        batch: &CommandBatch<N>,
+        progress: &mut BatchProgress,
+    ) -> Result<bool> {
+        let device_count = self.device_count();
+        if batch
+            .commands()
+            .iter()
+            .any(|command| command.device_index >= device_count)
+        {
+            return Err(Error::InvalidDeviceIndex);
+        }
+
+        let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+        let mut any = false;
+        let devices = ops
+            .iter_mut()
+            .zip(progress.next.iter_mut())
+            .take(device_count);
+        for (device_index, (op, next)) in devices.enumerate() {
+            let pending = batch
+                .commands()
+                .iter()
+                .enumerate()
+                .skip(*next)
+                .find(|(_, command)| command.device_index == device_index);
+            if let Some((index, command)) = pending {
+                *op = (command.register, command.data);
+                *next = index.saturating_add(1);
+                any = true;
+            }
+        }
+        if !any {
+            return Ok(false);
+        }
+        self.write_all_registers(&ops)?;
+        progress.transactions = progress.transactions.saturating_add(1);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    #[test]
+    fn test_push() {
+        let mut batch = CommandBatch::<3>::new();
+        assert!(batch.is_empty());
+        batch.push(1, Register::Digit0, 1).unwrap();
+        assert_eq!(
+            batch.push(MAX_DISPLAYS, Register::Digit0, 1),
+            Err(Error::InvalidDeviceIndex)
+        );
+        assert_eq!(
+            batch.push_all(3, Register::Intensity, 0),
+            Err(Error::BufferError)
+        );
+        assert_eq!(batch.len(), 1);
+        batch.push_all(2, Register::Intensity, 0).unwrap();
+        assert_eq!(batch.push(0, Register::Digit0, 1), Err(Error::BufferError));
+        assert_eq!(batch.transaction_count(), 2);
+
+        batch.clear();
+        assert_eq!(batch.transaction_count(), 0);
+    }
+
+    #[test]
+    fn test_execute_packs_devices() {
+        let expected = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Intensity.addr(),
+                2,
+                Register::NoOp.addr(),
+                0,
+                Register::Intensity.addr(),
+                15,
+            ]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0,
+                Register::NoOp.addr(),
+                0,
+                Register::Digit0.addr(),
+                0xFF,
+            ]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(3).unwrap();
+
+        let mut batch = CommandBatch::<4>::new();
+        batch.push(2, Register::Intensity, 15).unwrap();
+        batch.push(2, Register::Digit0, 0xFF).unwrap();
+        batch.push(0, Register::Intensity, 2).unwrap();
+        assert_eq!(driver.execute(&batch), Ok(2));
+        assert_eq!(driver.device_state(2).unwrap().digit(0), Ok(0xFF));
+
+        batch.push(3, Register::Digit0, 1).unwrap();
+        assert_eq!(driver.execute(&batch), Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
+
+    #[cfg(feature = "nb")]
+    #[test]
+    fn test_poll_execute_sends_one_transaction_per_call() {
+        let mut expected = Vec::new();
+        for register in [Register::Intensity, Register::Digit0] {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![
+                register.addr(),
+                1,
+                register.addr(),
+                1,
+            ]));
+            expected.push(Transaction::transaction_end());
+        }
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+
+        let mut batch = CommandBatch::<4>::new();
+        batch.push_all(2, Register::Intensity, 1).unwrap();
+        batch.push_all(2, Register::Digit0, 1).unwrap();
+        let mut progress = BatchProgress::new();
+        assert_eq!(
+            driver.poll_execute(&batch, &mut progress),
+            Err(nb::Error::WouldBlock)
+        );
+        assert_eq!(progress.transactions(), 1);
+        assert_eq!(
+            nb::block!(driver.poll_execute(&batch, &mut progress)),
+            Ok(2)
+        );
+        assert_eq!(driver.poll_execute(&batch, &mut progress), Ok(2));
+        spi.done();
+    }
+}
@@ -0,0 +1,228 @@
+//! Fading the displays in at power-on
+
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+
+use crate::{Brightness, MAX_DISPLAYS, Max7219, Register, Result};
+
+/// Number of intensity steps of a fade-in
+const FADE_STEPS: u32 = Brightness::MAX.level() as u32;
+
+impl<SPI> Max7219<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Takes every device out of shutdown at the lowest intensity and ramps
+    /// it up to its configured intensity over `duration_ms` milliseconds.
+    ///
+    /// The configured intensity is the one last set on each device, e.g.
+    /// with [`Self::set_intensity`] while still in shutdown. This avoids
+    /// the full-brightness flash of a plain [`Self::power_on`] at boot.
+    /// Devices that are already on keep their intensity instead of going
+    /// dark for the fade.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// driver.init()?;
+    /// driver.power_off()?;
+    /// driver.set_intensity_all(Brightness::from_percent(60))?;
+    /// // ... draw the first frame ...
+    /// driver.power_on_fade(500, &mut delay)?;
+    /// ```
+    pub fn power_on_fade<D: DelayNs>(&mut self, duration_ms: u32, delay: &mut D) -> Result<()> {
+        let targets = self.begin_fade_in()?;
+        let step_ms = duration_ms / FADE_STEPS;
+        for step in 1..=FADE_STEPS {
+            self.write_fade_step(&targets, step)?;
+            delay.delay_ms(step_ms);
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::power_on_fade`], waiting between steps with an async
+    /// delay so other tasks run during the fade.
+    #[cfg(feature = "async")]
+    pub async fn power_on_fade_async<D: embedded_hal_async::delay::DelayNs>(
+        &mut self,
+        duration_ms: u32,
+        delay: &mut D,
+    ) -> Result<()> {
+        let targets = self.begin_fade_in()?;
+        let step_ms = duration_ms / FADE_STEPS;
+        for step in 1..=FADE_STEPS {
+            self.write_fade_step(&targets, step)?;
+            delay.delay_ms(step_ms).await;
+        }
+        Ok(())
+    }
+
+    /// Sets every device in shutdown to the lowest intensity and powers
+    /// all devices on.
+    ///
+    /// Returns the intensities to fade in to.
+    fn begin_fade_in(&mut self) -> Result<[u8; MAX_DISPLAYS]> {
+        let device_count = self.device_count();
+        let mut targets = [0u8; MAX_DISPLAYS];
+        let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+        let mut dimmed = false;
+        let devices = targets.iter_mut().zip(ops.iter_mut()).take(device_count);
+        for (device_index, (target, op)) in devices.enumerate() {
+            let state = self.device_state(device_index)?;
+            *target = state.intensity();
+            if !state.is_powered_on() {
+                *op = (Register::Intensity, Brightness::MIN.level());
+                dimmed = true;
+            }
+        }
+        if dimmed {
+            self.write_all_registers(&ops)?;
+        }
+        self.power_on()?;
+        Ok(targets)
+    }
+
+    /// Writes the intensities of one fade-in step, if any device changes.
+    fn write_fade_step(&mut self, targets: &[u8; MAX_DISPLAYS], step: u32) -> Result<()> {
+        let device_count = self.device_count();
+        let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+        let mut changed = false;
+        let devices = ops.iter_mut().zip(targets).take(device_count);
+        for (device_index, (op, &target)) in devices.enumerate() {
+            let current = self.device_state(device_index)?.intensity();
+            // Devices that were already on start out at their target
+            let level = ((u32::from(target).saturating_mul(step) / FADE_STEPS) as u8).max(current);
+            if current != level {
+                *op = (Register::Intensity, level);
+                changed = true;
+            }
+        }
+        if changed {
+            self.write_all_registers(&ops)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, spi::Mock as SpiMock, spi::Transaction};
+
+    fn write(bytes: Vec<u8>) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(bytes),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    fn fade_in_transactions() -> Vec<Transaction<u8>> {
+        let (intensity, noop) = (Register::Intensity.addr(), Register::NoOp.addr());
+        let mut expected = write(vec![intensity, 5, intensity, 10]);
+        expected.extend(write(vec![intensity, 0, intensity, 0]));
+        expected.extend(write(vec![
+            Register::Shutdown.addr(),
+            1,
+            Register::Shutdown.addr(),
+            1,
+        ]));
+
+        // Device 0 ramps to 5 and device 1 to 10 in 15 steps, skipping
+        // steps where neither changes
+        let mut previous = [0, 0];
+        for step in 1..=15u8 {
+            let levels = [5 * step / 15, 10 * step / 15];
+            if levels == previous {
+                continue;
+            }
+            let mut bytes = Vec::new();
+            for (level, previous) in levels.iter().zip(previous) {
+                match *level == previous {
+                    true => bytes.extend([noop, 0]),
+                    false => bytes.extend([intensity, *level]),
+                }
+            }
+            expected.extend(write(bytes));
+            previous = levels;
+        }
+        expected
+    }
+
+    #[test]
+    fn test_power_on_fade_ramps_to_configured_intensity() {
+        let mut spi = SpiMock::new(&fade_in_transactions());
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        driver
+            .write_all_registers(&[(Register::Intensity, 5), (Register::Intensity, 10)])
+            .unwrap();
+
+        driver.power_on_fade(300, &mut NoopDelay::new()).unwrap();
+        assert!(driver.device_state(1).unwrap().is_powered_on());
+        assert_eq!(driver.device_state(0).unwrap().intensity(), 5);
+        assert_eq!(driver.device_state(1).unwrap().intensity(), 10);
+        spi.done();
+    }
+
+    #[test]
+    fn test_power_on_fade_keeps_lit_devices() {
+        let (intensity, noop) = (Register::Intensity.addr(), Register::NoOp.addr());
+        let shutdown = Register::Shutdown.addr();
+        let mut expected = write(vec![shutdown, 1, noop, 0]);
+        expected.extend(write(vec![intensity, 8, intensity, 10]));
+        expected.extend(write(vec![noop, 0, intensity, 0]));
+        expected.extend(write(vec![shutdown, 1, shutdown, 1]));
+        // Only device 1 ramps up, device 0 stays at 8 throughout
+        let mut previous = 0;
+        for step in 1..=15u8 {
+            let level = 10 * step / 15;
+            if level != previous {
+                expected.extend(write(vec![noop, 0, intensity, level]));
+                previous = level;
+            }
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        driver
+            .write_all_registers(&[(Register::Shutdown, 1), (Register::NoOp, 0)])
+            .unwrap();
+        driver
+            .write_all_registers(&[(Register::Intensity, 8), (Register::Intensity, 10)])
+            .unwrap();
+
+        driver.power_on_fade(300, &mut NoopDelay::new()).unwrap();
+        assert_eq!(driver.device_state(0).unwrap().intensity(), 8);
+        assert_eq!(driver.device_state(1).unwrap().intensity(), 10);
+        spi.done();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_power_on_fade_async() {
+        use core::{
+            future::Future,
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        struct ReadyDelay;
+
+        impl embedded_hal_async::delay::DelayNs for ReadyDelay {
+            async fn delay_ns(&mut self, _ns: u32) {}
+        }
+
+        let mut spi = SpiMock::new(&fade_in_transactions());
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        driver
+            .write_all_registers(&[(Register::Intensity, 5), (Register::Intensity, 10)])
+            .unwrap();
+
+        {
+            let mut delay = ReadyDelay;
+            let mut fade = pin!(driver.power_on_fade_async(300, &mut delay));
+            let mut context = Context::from_waker(Waker::noop());
+            assert_eq!(fade.as_mut().poll(&mut context), Poll::Ready(Ok(())));
+        }
+        spi.done();
+    }
+}
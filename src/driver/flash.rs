@@ -0,0 +1,167 @@
+//! Brightness flash alert for alarm conditions
+
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+
+use crate::{MAX_DISPLAYS, Max7219, Register, Result};
+
+/// Number of intensity steps used for each half of a ramp
+const RAMP_STEPS: u32 = 8;
+
+/// How [`Max7219::flash_alert_with_style`] draws attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlashStyle {
+    /// Ramp the intensity up to the maximum and back to where it was.
+    ///
+    /// Has no visible effect on devices already at full brightness.
+    #[default]
+    Intensity,
+    /// Blank the display using shutdown mode, then turn it back on.
+    Shutdown,
+}
+
+impl<SPI> Max7219<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Flashes every device `times` times, each flash lasting `period_ms`
+    /// milliseconds, by ramping the intensity to the maximum and back.
+    ///
+    /// See [`Self::flash_alert_with_style`].
+    pub fn flash_alert<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        times: u8,
+        period_ms: u32,
+    ) -> Result<()> {
+        self.flash_alert_with_style(delay, times, period_ms, FlashStyle::Intensity)
+    }
+
+    /// Flashes every device `times` times as a standard "get attention" signal.
+    ///
+    /// The digit registers are never touched, so whatever is shown stays on
+    /// the display. Afterwards each device's intensity and shutdown state
+    /// are restored from the driver's shadow state.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Three one-second blinks when the temperature limit is exceeded
+    /// driver.flash_alert_with_style(&mut delay, 3, 1000, FlashStyle::Shutdown)?;
+    /// ```
+    pub fn flash_alert_with_style<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        times: u8,
+        period_ms: u32,
+        style: FlashStyle,
+    ) -> Result<()> {
+        let device_count = self.device_count();
+        let mut intensities = [0u8; MAX_DISPLAYS];
+        let mut powered = [false; MAX_DISPLAYS];
+        let devices = intensities.iter_mut().zip(powered.iter_mut());
+        for (device_index, (intensity, powered)) in devices.take(device_count).enumerate() {
+            let state = self.device_state(device_index)?;
+            *intensity = state.intensity();
+            *powered = state.is_powered_on();
+        }
+
+        let half_period_ms = period_ms / 2;
+        for _ in 0..times {
+            match style {
+                FlashStyle::Intensity => {
+                    let step_ms = half_period_ms / RAMP_STEPS;
+                    let steps = (1..=RAMP_STEPS).chain((0..RAMP_STEPS).rev());
+                    for step in steps {
+                        let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+                        for (op, &base) in ops.iter_mut().zip(intensities.iter()) {
+                            let base = u32::from(base);
+                            let headroom = 0x0Fu32.saturating_sub(base);
+                            let level =
+                                base.saturating_add(headroom.saturating_mul(step) / RAMP_STEPS);
+                            *op = (Register::Intensity, level as u8);
+                        }
+                        self.write_all_registers(&ops)?;
+                        delay.delay_ms(step_ms);
+                    }
+                }
+                FlashStyle::Shutdown => {
+                    self.power_off()?;
+                    delay.delay_ms(half_period_ms);
+                    let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+                    for (op, &on) in ops.iter_mut().zip(powered.iter()) {
+                        *op = (Register::Shutdown, on as u8);
+                    }
+                    self.write_all_registers(&ops)?;
+                    delay.delay_ms(half_period_ms);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, spi::Mock as SpiMock, spi::Transaction};
+
+    fn write(bytes: Vec<u8>) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(bytes),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    #[test]
+    fn test_flash_alert_ramps_and_restores_intensity() {
+        let mut expected = write(vec![Register::Intensity.addr(), 0x04]);
+        for level in [5, 6, 8, 9, 10, 12, 13, 15, 13, 12, 10, 9, 8, 6, 5, 4] {
+            expected.extend(write(vec![Register::Intensity.addr(), level]));
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi);
+        driver.set_intensity(0, 0x04).unwrap();
+
+        driver.flash_alert(&mut NoopDelay::new(), 1, 800).unwrap();
+        assert_eq!(driver.device_state(0).unwrap().intensity(), 0x04);
+        spi.done();
+    }
+
+    #[test]
+    fn test_flash_alert_shutdown_restores_power_state() {
+        let mut expected = write(vec![
+            Register::NoOp.addr(),
+            0x00,
+            Register::Shutdown.addr(),
+            0x01,
+        ]);
+        for _ in 0..2 {
+            expected.extend(write(vec![
+                Register::Shutdown.addr(),
+                0x00,
+                Register::Shutdown.addr(),
+                0x00,
+            ]));
+            // Device 1 was powered on, device 0 was not
+            expected.extend(write(vec![
+                Register::Shutdown.addr(),
+                0x00,
+                Register::Shutdown.addr(),
+                0x01,
+            ]));
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        driver.power_on_device(1).unwrap();
+
+        driver
+            .flash_alert_with_style(&mut NoopDelay::new(), 2, 500, FlashStyle::Shutdown)
+            .unwrap();
+        assert!(!driver.device_state(0).unwrap().is_powered_on());
+        assert!(driver.device_state(1).unwrap().is_powered_on());
+        spi.done();
+    }
+}
@@ -0,0 +1,192 @@
+//! Named groups of devices, e.g. the sections of a front panel
+
+use core::ops::Range;
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{Brightness, Error, MAX_DISPLAYS, Max7219, Register, Result};
+
+/// A named, contiguous range of devices that is switched, dimmed and cleared
+/// together.
+///
+/// Groups are plain values, so a panel's layout can be declared once as a
+/// constant table and looked up by name with [`DeviceGroup::find`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// const PANEL: &[DeviceGroup] = &[
+///     DeviceGroup::new("banner", 0..4),
+///     DeviceGroup::new("clock", 4..6),
+/// ];
+///
+/// let clock = DeviceGroup::find(PANEL, "clock").unwrap();
+/// driver.set_intensity_group(clock, Brightness::MIN)?;
+/// driver.clear_group(DeviceGroup::find(PANEL, "banner").unwrap())?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceGroup {
+    name: &'static str,
+    start: usize,
+    end: usize,
+}
+
+impl DeviceGroup {
+    /// Create a group named `name` covering `devices`
+    pub const fn new(name: &'static str, devices: Range<usize>) -> Self {
+        Self {
+            name,
+            start: devices.start,
+            end: devices.end,
+        }
+    }
+
+    /// Returns the name of the group.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the devices of the group.
+    pub const fn devices(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns `true` if the group contains `device_index`.
+    pub const fn contains(&self, device_index: usize) -> bool {
+        self.start <= device_index && device_index < self.end
+    }
+
+    /// Returns the first group in `groups` named `name`.
+    pub fn find<'a>(groups: &'a [DeviceGroup], name: &str) -> Option<&'a DeviceGroup> {
+        groups.iter().find(|group| group.name == name)
+    }
+}
+
+impl<SPI> Max7219<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Powers on every device of a group in one transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if the group runs past the end of
+    /// the chain.
+    pub fn power_on_group(&mut self, group: &DeviceGroup) -> Result<()> {
+        self.power_on_range(group.devices())
+    }
+
+    /// Powers off every device of a group in one transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if the group runs past the end of
+    /// the chain.
+    pub fn power_off_group(&mut self, group: &DeviceGroup) -> Result<()> {
+        self.power_off_range(group.devices())
+    }
+
+    /// Sets the brightness of every device of a group in one transaction,
+    /// given as a [`Brightness`] or a raw intensity step from `0` to `15`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidIntensity` if a raw step is greater than 15,
+    /// or `Error::InvalidDeviceIndex` if the group runs past the end of the
+    /// chain.
+    pub fn set_intensity_group(
+        &mut self,
+        group: &DeviceGroup,
+        brightness: impl TryInto<Brightness, Error: Into<Error>>,
+    ) -> Result<()> {
+        self.set_intensity_range(group.devices(), brightness)
+    }
+
+    /// Clears every digit of the devices in a group, leaving the other
+    /// devices untouched.
+    ///
+    /// Digits already blank on every device of the group are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if the group runs past the end of
+    /// the chain.
+    pub fn clear_group(&mut self, group: &DeviceGroup) -> Result<()> {
+        let device_count = self.device_count();
+        if group.devices().end > device_count {
+            return Err(Error::InvalidDeviceIndex);
+        }
+        for digit_register in Register::digits() {
+            let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+            let mut changed = false;
+            for (device_index, op) in ops.iter_mut().enumerate() {
+                if group.contains(device_index) {
+                    let state = self.device_state(device_index)?;
+                    changed |= state.register_value(digit_register) != 0;
+                    *op = (digit_register, 0x00);
+                }
+            }
+            if changed {
+                self.write_all_registers(&ops)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    const PANEL: &[DeviceGroup] = &[
+        DeviceGroup::new("banner", 0..2),
+        DeviceGroup::new("clock", 2..3),
+    ];
+
+    #[test]
+    fn test_find() {
+        let clock = DeviceGroup::find(PANEL, "clock").unwrap();
+        assert_eq!(clock.name(), "clock");
+        assert_eq!(clock.devices(), 2..3);
+        assert!(clock.contains(2) && !clock.contains(1));
+        assert_eq!(DeviceGroup::find(PANEL, "missing"), None);
+    }
+
+    #[test]
+    fn test_group_operations() {
+        let (noop, digit0) = (Register::NoOp.addr(), Register::Digit0.addr());
+        let expected = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![noop, 0, noop, 0, Register::Shutdown.addr(), 0]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![digit0, 0x0F, noop, 0, digit0, 0xF0]),
+            Transaction::transaction_end(),
+            // Only digit 0 of the banner is lit
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![digit0, 0, digit0, 0, noop, 0]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(3).unwrap();
+
+        let banner = DeviceGroup::find(PANEL, "banner").unwrap();
+        let clock = DeviceGroup::find(PANEL, "clock").unwrap();
+        driver.power_off_group(clock).unwrap();
+        driver
+            .write_all_registers(&[
+                (Register::Digit0, 0x0F),
+                (Register::NoOp, 0),
+                (Register::Digit0, 0xF0),
+            ])
+            .unwrap();
+        driver.clear_group(banner).unwrap();
+        assert_eq!(driver.device_state(2).unwrap().digit(0), Ok(0xF0));
+        assert_eq!(
+            driver.clear_group(&DeviceGroup::new("extra", 2..4)),
+            Err(Error::InvalidDeviceIndex)
+        );
+        spi.done();
+    }
+}
@@ -1,11 +1,14 @@
 //! Core MAX7219 driver implementation
 
+use core::ops::Range;
+
 use embedded_hal::spi::SpiDevice;
 
 use crate::{
     MAX_DISPLAYS, NUM_DIGITS, Result,
+    driver::DeviceState,
     error::Error,
-    registers::{DecodeMode, Register},
+    registers::{Brightness, DecodeMode, Filler, NOOP_PACKET, Register},
 };
 
 /// Driver for the MAX7219 LED display controller.
@@ -14,6 +17,32 @@ pub struct Max7219<SPI> {
     spi: SPI,
     buffer: [u8; MAX_DISPLAYS * 2],
     device_count: usize,
+    /// Shadow copy of what was last written to each device
+    states: [DeviceState; MAX_DISPLAYS],
+    /// Called with the bytes of every transaction that was sent
+    frame_observer: Option<fn(&[u8])>,
+    /// Packet sent to the devices a transaction doesn't address
+    filler: Filler,
+    /// Number of times `resync_config` found the chain corrupted
+    resync_count: u32,
+    /// Whether DOUT of the last device is wired back to MISO
+    readback: bool,
+}
+
+/// Calls a frame observer.
+///
+/// With the `panic-free` feature the call crosses a non-unwinding boundary,
+/// so a panicking observer aborts instead of unwinding through the driver.
+#[cfg(feature = "panic-free")]
+#[allow(improper_ctypes_definitions)]
+extern "C" fn notify(observer: fn(&[u8]), frame: &[u8]) {
+    observer(frame);
+}
+
+/// Calls a frame observer.
+#[cfg(not(feature = "panic-free"))]
+fn notify(observer: fn(&[u8]), frame: &[u8]) {
+    observer(frame);
 }
 
 impl<SPI> Max7219<SPI>
@@ -33,7 +62,68 @@ where
             spi,
             device_count: 1, // Default to 1, use with_device_count to increase count
             buffer: [0; MAX_DISPLAYS * 2],
+            states: [DeviceState::new(); MAX_DISPLAYS],
+            frame_observer: None,
+            filler: Filler::NoOp,
+            resync_count: 0,
+            readback: false,
+        }
+    }
+
+    /// Sets the packet sent to devices a transaction doesn't address,
+    /// consuming and returning the driver.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut driver = Max7219::new(spi).with_device_count(8)?;
+    /// driver.init()?;
+    /// let mut driver = driver.with_filler(Filler::Repeat);
+    /// ```
+    pub fn with_filler(mut self, filler: Filler) -> Self {
+        self.filler = filler;
+        self
+    }
+
+    /// Returns the packet sent to devices a transaction doesn't address.
+    pub fn filler(&self) -> Filler {
+        self.filler
+    }
+
+    /// Returns the packet sent to an unaddressed device while `register`
+    /// is written to another one.
+    fn filler_packet(&self, device_index: usize, register: Register) -> (Register, u8) {
+        match self.filler {
+            Filler::NoOp => NOOP_PACKET,
+            Filler::NoOpWith(data) => (Register::NoOp, data),
+            Filler::Repeat => {
+                let state = self.states.get(device_index).copied().unwrap_or_default();
+                (register, state.register_value(register))
+            }
+        }
+    }
+
+    /// Fills the packets of all devices with the filler for `register`.
+    fn fill_buffer(&mut self, register: Register) {
+        let mut buffer = [0; MAX_DISPLAYS * 2];
+        for (device_index, packet) in buffer.chunks_exact_mut(2).enumerate() {
+            if device_index < self.device_count {
+                let (filler, data) = self.filler_packet(device_index, register);
+                packet.copy_from_slice(&[filler.addr(), data]);
+            }
         }
+        self.buffer = buffer;
+    }
+
+    /// Returns the SPI device, e.g. a [`SinkSpi`](crate::sink::SinkSpi) to
+    /// inspect what was sent.
+    pub fn spi(&self) -> &SPI {
+        &self.spi
+    }
+
+    /// Returns the SPI device mutably.
+    pub fn spi_mut(&mut self) -> &mut SPI {
+        &mut self.spi
     }
 
     /// Returns the number of MAX7219 devices managed by this driver.
@@ -63,6 +153,30 @@ where
         Ok(self)
     }
 
+    /// Returns the last-known register state of a device in the chain.
+    ///
+    /// The state is a shadow copy of every value the driver has sent to the
+    /// device (digit data, intensity, decode mode, scan limit, power and test
+    /// mode), so application code can query what the display currently shows
+    /// without tracking it separately.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// driver.set_intensity(0, 0x05)?;
+    /// assert_eq!(driver.device_state(0)?.intensity(), 0x05);
+    /// ```
+    pub fn device_state(&self, device_index: usize) -> Result<&DeviceState> {
+        self.states
+            .get(..self.device_count)
+            .and_then(|states| states.get(device_index))
+            .ok_or(Error::InvalidDeviceIndex)
+    }
+
     /// Initializes all configured displays.
     pub fn init(&mut self) -> Result<()> {
         self.power_on()?;
@@ -89,7 +203,7 @@ where
     /// and clears the rest of the buffer. Then the entire buffer is sent via SPI.
     ///
     /// The device at `device_index` will receive its register update, while other
-    /// devices in the chain will receive no-ops (zeros).
+    /// devices in the chain will receive the [`Filler`], no-ops by default.
     ///
     /// # Arguments
     ///
@@ -111,45 +225,173 @@ where
             return Err(Error::InvalidDeviceIndex);
         }
 
-        self.buffer = [0; MAX_DISPLAYS * 2];
+        self.fill_buffer(register);
 
-        let offset = device_index * 2; // 2 bytes(16 bits packet) per display
-        self.buffer[offset] = register as u8;
-        self.buffer[offset + 1] = data;
+        // 2 bytes (16 bits packet) per display
+        if let Some(packet) = self.buffer.chunks_exact_mut(2).nth(device_index) {
+            packet.copy_from_slice(&[register as u8, data]);
+        }
 
-        self.spi.write(&self.buffer[0..self.device_count * 2])?;
+        self.send()?;
+        if let Some(state) = self.states.get_mut(device_index) {
+            state.record(register, data);
+        }
 
         Ok(())
     }
 
     /// Write each (register, data) tuple to its corresponding MAX7219 device in the daisy chain.
     ///
-    /// The number of tuples in `ops` must exactly match `self.device_count`.
     /// Convention: ops[0] = furthest device from MCU, ops[device_count-1] = nearest device
     /// Because The first one we send in the SPI gets pushed till the last device.
     ///
-    /// # Panics (only in debug builds)
-    /// - If `ops.len() != self.device_count`.
+    /// Devices without a tuple get the [`Filler`], and tuples past the end of
+    /// the chain are ignored, so callers may pass a full `MAX_DISPLAYS` array.
     ///
     /// # Errors
     /// - Returns an SPI error if the write operation fails.
     pub(crate) fn write_all_registers(&mut self, ops: &[(Register, u8)]) -> Result<()> {
-        // clear the buffer: 2 bytes per device
-        self.buffer = [0; MAX_DISPLAYS * 2];
+        let ops = self.prepare(ops);
+        self.send()?;
+        self.record_all(ops);
+        Ok(())
+    }
 
-        for (i, &(reg, data)) in ops.iter().enumerate() {
-            let offset = i * 2;
-            self.buffer[offset] = reg as u8;
-            self.buffer[offset + 1] = data;
+    /// Writes like [`Self::write_all_registers`] in a full-duplex transfer,
+    /// returning whether the bytes shifted out of the chain were those of
+    /// the previous transaction.
+    ///
+    /// The comparison only means something if DOUT of the device furthest
+    /// from the MCU is wired back to MISO.
+    pub(super) fn write_all_registers_checked(&mut self, ops: &[(Register, u8)]) -> Result<bool> {
+        let previous = self.buffer;
+        let ops = self.prepare(ops);
+        let length = self.device_count.saturating_mul(2);
+        let mut readback = [0; MAX_DISPLAYS * 2];
+        let readback = readback
+            .get_mut(..length)
+            .ok_or(Error::InvalidDeviceCount)?;
+        let frame = self.buffer.get(..length).ok_or(Error::InvalidDeviceCount)?;
+        self.spi.transfer(readback, frame)?;
+        if let Some(observer) = self.frame_observer {
+            notify(observer, frame);
+        }
+        self.record_all(ops);
+        Ok(previous.get(..length) == Some(&*readback))
+    }
+
+    /// Fills the buffer with `ops` and the filler, returning the ops that
+    /// address a device of the chain.
+    fn prepare<'o>(&mut self, ops: &'o [(Register, u8)]) -> &'o [(Register, u8)] {
+        let ops = ops.get(..self.device_count).unwrap_or(ops);
+
+        // Devices without an operation get the filler for the register the
+        // others are written
+        let register = ops
+            .iter()
+            .map(|&(reg, _)| reg)
+            .find(|&reg| reg != Register::NoOp)
+            .unwrap_or(Register::NoOp);
+        self.fill_buffer(register);
+
+        for (packet, &(reg, data)) in self.buffer.chunks_exact_mut(2).zip(ops) {
+            if reg == Register::NoOp && data == 0 {
+                continue;
+            }
+            packet.copy_from_slice(&[reg as u8, data]);
+        }
+        ops
+    }
+
+    /// Records sent `ops` in the shadow copy.
+    fn record_all(&mut self, ops: &[(Register, u8)]) {
+        for (state, &(reg, data)) in self.states.iter_mut().zip(ops) {
+            state.record(reg, data);
         }
+    }
+
+    /// Returns the number of times [`Self::resync_config`] found the chain
+    /// corrupted, e.g. to log how often the field suspected power or EMI
+    /// trouble.
+    pub fn resync_count(&self) -> u32 {
+        self.resync_count
+    }
+
+    /// Resets the counter returned by [`Self::resync_count`].
+    pub fn reset_resync_count(&mut self) {
+        self.resync_count = 0;
+    }
+
+    /// Declares whether DOUT of the device furthest from the MCU is wired
+    /// back to MISO, consuming and returning the driver.
+    ///
+    /// Only then can [`Self::resync_config`] read back what the chain
+    /// holds and count the resyncs that found it corrupted. Off by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut driver = Max7219::new(spi).with_device_count(4)?.with_readback(true);
+    /// ```
+    pub fn with_readback(mut self, readback: bool) -> Self {
+        self.readback = readback;
+        self
+    }
 
-        // send exactly device_count packets
-        let len = self.device_count * 2;
-        self.spi.write(&self.buffer[..len])?;
+    /// Returns whether the chain is read back through MISO. See
+    /// [`Self::with_readback`].
+    pub fn readback(&self) -> bool {
+        self.readback
+    }
 
+    /// Counts a resync that repaired a corrupted chain.
+    pub(super) fn count_resync(&mut self) {
+        self.resync_count = self.resync_count.saturating_add(1);
+    }
+
+    /// Sends the packets of all devices in the buffer as one transaction.
+    fn send(&mut self) -> Result<()> {
+        let frame = self
+            .buffer
+            .get(..self.device_count.saturating_mul(2))
+            .ok_or(Error::InvalidDeviceCount)?;
+        self.spi.write(frame)?;
+        if let Some(observer) = self.frame_observer {
+            notify(observer, frame);
+        }
         Ok(())
     }
 
+    /// Returns the bytes of the most recent SPI transaction, two per device
+    /// with the furthest device first.
+    ///
+    /// Useful to verify the protocol output in tests or to forward frames
+    /// to a mirror display. The bytes are all zero before the first write.
+    pub fn last_frame_bytes(&self) -> &[u8] {
+        self.buffer
+            .get(..self.device_count.saturating_mul(2))
+            .unwrap_or(&self.buffer)
+    }
+
+    /// Sets a function called with the exact bytes of every SPI transaction
+    /// after it was sent, or removes it with `None`.
+    ///
+    /// The bytes are the same as [`Self::last_frame_bytes`]. A plain function
+    /// keeps the driver free of allocation; forward the bytes through a
+    /// `static` to reach other code.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// static FRAMES: AtomicUsize = AtomicUsize::new(0);
+    /// driver.set_frame_observer(Some(|_bytes| {
+    ///     FRAMES.fetch_add(1, Ordering::Relaxed);
+    /// }));
+    /// ```
+    pub fn set_frame_observer(&mut self, observer: Option<fn(&[u8])>) {
+        self.frame_observer = observer;
+    }
+
     // fn write_raw_register(&mut self, register: u8, data: u8) -> Result<(), SPI::Error> {
     //     self.spi.write(&[register, data])
     // }
@@ -158,14 +400,14 @@ where
     pub fn power_on(&mut self) -> Result<()> {
         let ops = [(Register::Shutdown, 0x01); MAX_DISPLAYS];
 
-        self.write_all_registers(&ops[..self.device_count])
+        self.write_all_registers(&ops)
     }
 
     /// Powers off all displays by writing `0x00` to the Shutdown register.
     pub fn power_off(&mut self) -> Result<()> {
         let ops = [(Register::Shutdown, 0x00); MAX_DISPLAYS];
 
-        self.write_all_registers(&ops[..self.device_count])
+        self.write_all_registers(&ops)
     }
 
     /// Powers on a single device by writing `0x01` to the Shutdown register.
@@ -198,7 +440,7 @@ where
     pub fn test_all(&mut self, enable: bool) -> Result<()> {
         let data = if enable { 0x01 } else { 0x00 };
         let ops: [(Register, u8); MAX_DISPLAYS] = [(Register::DisplayTest, data); MAX_DISPLAYS];
-        self.write_all_registers(&ops[..self.device_count])
+        self.write_all_registers(&ops)
     }
 
     /// Sets how many digits the MAX7219 should actively scan and display.
@@ -219,7 +461,7 @@ where
             return Err(Error::InvalidScanLimit);
         }
 
-        self.write_device_register(device_index, Register::ScanLimit, limit - 1)
+        self.write_device_register(device_index, Register::ScanLimit, limit.saturating_sub(1))
     }
 
     /// Set scan‐limit on all devices in one go.
@@ -229,9 +471,9 @@ where
         if !(1..=8).contains(&limit) {
             return Err(Error::InvalidScanLimit);
         }
-        let val = limit - 1;
+        let val = limit.saturating_sub(1);
         let ops: [(Register, u8); MAX_DISPLAYS] = [(Register::ScanLimit, val); MAX_DISPLAYS];
-        self.write_all_registers(&ops[..self.device_count])
+        self.write_all_registers(&ops)
     }
 
     /// Code B decoding allows the MAX7219 to automatically convert values like `0-9`, `E`, `H`, `L`, etc.
@@ -247,11 +489,20 @@ where
         self.write_device_register(device_index, Register::DecodeMode, mode as u8)
     }
 
+    /// Enables Code B decoding for the digits whose bit is set in `mask`
+    /// (bit 0 = DIG0) and raw segment data for the others.
+    ///
+    /// Unlike [`Self::set_device_decode_mode`], any combination of digits
+    /// can be selected.
+    pub fn set_device_decode_mask(&mut self, device_index: usize, mask: u8) -> Result<()> {
+        self.write_device_register(device_index, Register::DecodeMode, mask)
+    }
+
     /// Set decode‐mode on all devices in one go.
     pub fn set_decode_mode_all(&mut self, mode: DecodeMode) -> Result<()> {
         let byte = mode as u8;
         let ops: [(Register, u8); MAX_DISPLAYS] = [(Register::DecodeMode, byte); MAX_DISPLAYS];
-        self.write_all_registers(&ops[..self.device_count])
+        self.write_all_registers(&ops)
     }
 
     /// Clears all digits by writing 0 to each digit register (DIG0 to DIG7).
@@ -267,11 +518,34 @@ where
         Ok(())
     }
 
+    /// Clears the digits of one device that the shadow state shows as lit.
+    ///
+    /// Clearing a device this way costs one transaction per lit row at most
+    /// and nothing if it is already blank, so it never takes more
+    /// transactions than [`Self::clear_all`].
+    ///
+    /// The shadow state only knows what the driver sent: after power-up
+    /// without [`Self::init`], or if a device may have been disturbed, use
+    /// [`Self::clear_display`] to blank it for sure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    pub fn clear_lit_digits(&mut self, device_index: usize) -> Result<()> {
+        let digits = *self.device_state(device_index)?.digits();
+        for (digit_register, &data) in Register::digits().zip(digits.iter()) {
+            if data != 0 {
+                self.write_device_register(device_index, digit_register, 0x00)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Clears all digits on all connected MAX7219 displays.
     pub fn clear_all(&mut self) -> Result<()> {
         for digit_register in Register::digits() {
             let ops = [(digit_register, 0x00); MAX_DISPLAYS];
-            self.write_all_registers(&ops[..self.device_count])?;
+            self.write_all_registers(&ops)?;
         }
 
         Ok(())
@@ -350,26 +624,182 @@ where
         self.write_device_register(device_index, digit_register, value)
     }
 
-    /// Sets the brightness intensity (0 to 15) for a specific device.
+    /// Switches a single LED on or off, leaving the other LEDs of its digit
+    /// register as they were last written.
+    ///
+    /// The new register value is computed from the shadow state and only
+    /// sent if it changes, so LEDs of a cube, an indicator cluster or any
+    /// other array can be toggled one at a time. The digit should be in raw
+    /// mode: under Code B decoding the bits don't map to single segments.
     ///
     /// # Arguments
     ///
     /// - `device_index`: Index of the display in the daisy chain (0 = Furthest from the Microcontroller)
-    /// - `intensity`: Brightness level from `0` to `15` (`0x00` to `0x0F`)
-    pub fn set_intensity(&mut self, device_index: usize, intensity: u8) -> Result<()> {
-        if intensity > 0x0F {
-            return Err(Error::InvalidIntensity);
+    /// - `digit`: Digit register driving the LED (0 to 7)
+    /// - `segment`: Bit of the register driving the LED (0 = segment G, 7 = DP)
+    /// - `on`: Whether the LED is lit
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `digit` or `segment` is greater than 7.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// driver.set_led(0, 2, 5, true)?; // DIG2, segment B
+    /// ```
+    pub fn set_led(&mut self, device_index: usize, digit: u8, segment: u8, on: bool) -> Result<()> {
+        let current = self.led_register(device_index, digit, segment)?;
+        let mask = 1 << segment;
+        let value = if on { current | mask } else { current & !mask };
+        if value != current {
+            self.write_raw_digit(device_index, digit, value)?;
         }
-        self.write_device_register(device_index, Register::Intensity, intensity)
+        Ok(())
     }
 
-    /// Set intensity for all displays
-    pub fn set_intensity_all(&mut self, intensity: u8) -> Result<()> {
-        if intensity > 0x0F {
-            return Err(Error::InvalidIntensity);
+    /// Returns whether a single LED was last written as lit.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range
+    /// and `Error::InvalidDigit` if `digit` or `segment` is greater than 7.
+    pub fn led(&self, device_index: usize, digit: u8, segment: u8) -> Result<bool> {
+        let current = self.led_register(device_index, digit, segment)?;
+        Ok(current & (1 << segment) != 0)
+    }
+
+    /// Returns the shadow value of the digit register driving an LED.
+    fn led_register(&self, device_index: usize, digit: u8, segment: u8) -> Result<u8> {
+        if segment > 7 {
+            return Err(Error::InvalidDigit);
         }
-        let ops = [(Register::Intensity, intensity); MAX_DISPLAYS];
-        self.write_all_registers(&ops[..self.device_count])
+        self.device_state(device_index)?.digit(digit)
+    }
+
+    /// Sets the brightness of a specific device.
+    ///
+    /// # Arguments
+    ///
+    /// - `device_index`: Index of the display in the daisy chain (0 = Furthest from the Microcontroller)
+    /// - `brightness`: Brightness level, e.g. `Brightness::MAX` or `Brightness::from_percent(40)`,
+    ///   or a raw intensity step from `0` to `15` (`0x00` to `0x0F`)
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidIntensity` if a raw step is greater than 15.
+    pub fn set_intensity(
+        &mut self,
+        device_index: usize,
+        brightness: impl TryInto<Brightness, Error: Into<Error>>,
+    ) -> Result<()> {
+        let brightness = brightness.try_into().map_err(Into::into)?;
+        self.write_device_register(device_index, Register::Intensity, brightness.level())
+    }
+
+    /// Writes `data` to `register` on a contiguous range of devices in one
+    /// transaction; the other devices get the [`Filler`].
+    ///
+    /// Nothing is sent for an empty range.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if the range runs past the end of
+    /// the chain.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Dim the banner modules only
+    /// driver.write_register_range(0..4, Register::Intensity, 0x02)?;
+    /// ```
+    pub fn write_register_range(
+        &mut self,
+        devices: Range<usize>,
+        register: Register,
+        data: u8,
+    ) -> Result<()> {
+        if devices.end > self.device_count {
+            return Err(Error::InvalidDeviceIndex);
+        }
+        if devices.is_empty() {
+            return Ok(());
+        }
+        let mut ops = [NOOP_PACKET; MAX_DISPLAYS];
+        for (device_index, op) in ops.iter_mut().enumerate() {
+            if devices.contains(&device_index) {
+                *op = (register, data);
+            }
+        }
+        self.write_all_registers(&ops)
+    }
+
+    /// Sets the brightness of a contiguous range of devices in one
+    /// transaction, given as a [`Brightness`] or a raw intensity step from
+    /// `0` to `15`. See [`Self::write_register_range`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidIntensity` if a raw step is greater than 15,
+    /// or `Error::InvalidDeviceIndex` if the range runs past the end of the
+    /// chain.
+    pub fn set_intensity_range(
+        &mut self,
+        devices: Range<usize>,
+        brightness: impl TryInto<Brightness, Error: Into<Error>>,
+    ) -> Result<()> {
+        let brightness: Brightness = brightness.try_into().map_err(Into::into)?;
+        self.write_register_range(devices, Register::Intensity, brightness.level())
+    }
+
+    /// Powers on a contiguous range of devices in one transaction. See
+    /// [`Self::write_register_range`].
+    pub fn power_on_range(&mut self, devices: Range<usize>) -> Result<()> {
+        self.write_register_range(devices, Register::Shutdown, 0x01)
+    }
+
+    /// Powers off a contiguous range of devices in one transaction. See
+    /// [`Self::write_register_range`].
+    pub fn power_off_range(&mut self, devices: Range<usize>) -> Result<()> {
+        self.write_register_range(devices, Register::Shutdown, 0x00)
+    }
+
+    /// Enables or disables display test mode on a contiguous range of
+    /// devices in one transaction. See [`Self::write_register_range`].
+    pub fn test_range(&mut self, devices: Range<usize>, enable: bool) -> Result<()> {
+        self.write_register_range(devices, Register::DisplayTest, enable as u8)
+    }
+
+    /// Set brightness for all displays, given as a [`Brightness`] or a raw
+    /// intensity step from `0` to `15`
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidIntensity` if a raw step is greater than 15.
+    pub fn set_intensity_all(
+        &mut self,
+        brightness: impl TryInto<Brightness, Error: Into<Error>>,
+    ) -> Result<()> {
+        let brightness: Brightness = brightness.try_into().map_err(Into::into)?;
+        let ops = [(Register::Intensity, brightness.level()); MAX_DISPLAYS];
+        self.write_all_registers(&ops)
+    }
+
+    /// Sets the brightness of all displays unless every device is already
+    /// at that brightness.
+    ///
+    /// Returns `true` if the intensity was written.
+    pub fn update_intensity_all(&mut self, brightness: Brightness) -> Result<bool> {
+        if self
+            .states
+            .iter()
+            .take(self.device_count)
+            .all(|state| state.brightness() == brightness)
+        {
+            return Ok(false);
+        }
+        self.set_intensity_all(brightness)?;
+        Ok(true)
     }
 }
 
@@ -389,6 +819,41 @@ mod tests {
         spi.done();
     }
 
+    #[test]
+    fn test_last_frame_bytes_and_observer() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        static SEEN: AtomicUsize = AtomicUsize::new(0);
+
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![0x00, 0x00, Register::Intensity.addr(), 0x03]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit1.addr(), 0x42, 0x00, 0x00]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        assert_eq!(driver.last_frame_bytes(), [0; 4]);
+
+        driver.set_frame_observer(Some(|bytes| {
+            // Pack the four bytes of the chain into one value
+            let packed = bytes.iter().fold(0, |acc, &b| acc << 8 | b as usize);
+            SEEN.store(packed, Ordering::Relaxed);
+        }));
+        driver
+            .write_device_register(1, Register::Intensity, 0x03)
+            .unwrap();
+        assert_eq!(driver.last_frame_bytes(), [0x00, 0x00, 0x0A, 0x03]);
+        assert_eq!(SEEN.load(Ordering::Relaxed), 0x0A03);
+
+        driver.set_frame_observer(None);
+        driver.write_raw_digit(0, 1, 0x42).unwrap();
+        assert_eq!(driver.last_frame_bytes(), [0x02, 0x42, 0x00, 0x00]);
+        assert_eq!(SEEN.load(Ordering::Relaxed), 0x0A03);
+        spi.done();
+    }
+
     #[test]
     fn test_with_device_count_valid() {
         let mut spi = SpiMock::new(&[]);
@@ -647,6 +1112,45 @@ mod tests {
         spi.done();
     }
 
+    #[test]
+    fn test_clear_lit_digits() {
+        // Only the lit rows of device 1 are written
+        let mut expected_transactions = Vec::new();
+        for (register, data) in [(Register::Digit2, 0x18), (Register::Digit5, 0x81)] {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0x00,
+                register.addr(),
+                data,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+        for register in [Register::Digit2, Register::Digit5] {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0x00,
+                register.addr(),
+                0x00,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+
+        driver.write_raw_digit(1, 2, 0x18).unwrap();
+        driver.write_raw_digit(1, 5, 0x81).unwrap();
+        driver.clear_lit_digits(1).unwrap();
+        assert_eq!(driver.device_state(1).unwrap().digits(), &[0; 8]);
+        // Already blank, nothing is sent
+        driver.clear_lit_digits(1).unwrap();
+        driver.clear_lit_digits(0).unwrap();
+        assert_eq!(driver.clear_lit_digits(2), Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
+
     #[test]
     fn test_clear_display_invalid_index() {
         let mut spi = SpiMock::new(&[]); // No transactions expected for invalid index
@@ -659,6 +1163,98 @@ mod tests {
         spi.done();
     }
 
+    #[test]
+    fn test_filler() {
+        let expected_transactions = [
+            // Default: no-op with zero data
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                NOOP_PACKET.0.addr(),
+                NOOP_PACKET.1,
+                Register::Intensity.addr(),
+                0x03,
+            ]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0xA5,
+                Register::Intensity.addr(),
+                0x04,
+            ]),
+            Transaction::transaction_end(),
+            // Device 0 gets its own last intensity again
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Intensity.addr(),
+                0x00,
+                Register::Intensity.addr(),
+                0x05,
+            ]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        assert_eq!(driver.filler(), Filler::NoOp);
+
+        driver
+            .set_intensity(1, Brightness::saturating(0x03))
+            .unwrap();
+        let mut driver = driver.with_filler(Filler::NoOpWith(0xA5));
+        driver
+            .set_intensity(1, Brightness::saturating(0x04))
+            .unwrap();
+        let mut driver = driver.with_filler(Filler::Repeat);
+        driver
+            .write_all_registers(&[NOOP_PACKET, (Register::Intensity, 0x05)])
+            .unwrap();
+        assert_eq!(driver.device_state(0).unwrap().intensity(), 0x00);
+        spi.done();
+    }
+
+    #[test]
+    fn test_register_range() {
+        let noop = Register::NoOp.addr();
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                noop,
+                0x00,
+                Register::Intensity.addr(),
+                0x02,
+                Register::Intensity.addr(),
+                0x02,
+            ]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Shutdown.addr(),
+                0x00,
+                noop,
+                0x00,
+                noop,
+                0x00,
+            ]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi).with_device_count(3).unwrap();
+
+        driver
+            .set_intensity_range(1..3, Brightness::saturating(0x02))
+            .unwrap();
+        driver.power_off_range(0..1).unwrap();
+        driver.test_range(2..2, true).unwrap();
+        assert_eq!(driver.power_on_range(2..4), Err(Error::InvalidDeviceIndex));
+        assert_eq!(
+            driver.set_intensity_range(0..1, 0x10),
+            Err(Error::InvalidIntensity)
+        );
+        assert_eq!(driver.device_state(2).unwrap().intensity(), 0x02);
+        assert_eq!(driver.device_state(0).unwrap().intensity(), 0x00);
+        spi.done();
+    }
+
     #[test]
     fn test_write_raw_digit() {
         let device_index = 0;
@@ -690,6 +1286,36 @@ mod tests {
         spi.done();
     }
 
+    #[test]
+    fn test_set_led_keeps_other_leds() {
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit2.addr(), 0b0010_0000]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit2.addr(), 0b0010_0001]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit2.addr(), 0b0000_0001]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi);
+
+        driver.set_led(0, 2, 5, true).unwrap();
+        driver.set_led(0, 2, 0, true).unwrap();
+        // Already lit, nothing is sent
+        driver.set_led(0, 2, 0, true).unwrap();
+        driver.set_led(0, 2, 5, false).unwrap();
+
+        assert_eq!(driver.led(0, 2, 0), Ok(true));
+        assert_eq!(driver.led(0, 2, 5), Ok(false));
+        assert_eq!(driver.set_led(0, 8, 0, true), Err(Error::InvalidDigit));
+        assert_eq!(driver.set_led(0, 0, 8, true), Err(Error::InvalidDigit));
+        assert_eq!(driver.led(1, 0, 0), Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
+
     #[test]
     fn test_set_intensity_valid() {
         let device_index = 0;
@@ -947,4 +1573,94 @@ mod tests {
         assert_eq!(result, Err(Error::InvalidIntensity));
         spi.done();
     }
+
+    #[test]
+    fn test_device_state_tracks_writes() {
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Intensity.addr(),
+                0x03,
+                Register::Intensity.addr(),
+                0x09,
+            ]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0x00,
+                Register::Digit2.addr(),
+                0b1010_0101,
+            ]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Shutdown.addr(),
+                0x01,
+                Register::Shutdown.addr(),
+                0x01,
+            ]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+
+        driver
+            .write_all_registers(&[(Register::Intensity, 0x03), (Register::Intensity, 0x09)])
+            .unwrap();
+        driver.write_raw_digit(1, 2, 0b1010_0101).unwrap();
+        driver.power_on().unwrap();
+
+        let first = driver.device_state(0).unwrap();
+        assert_eq!(first.intensity(), 0x03);
+        assert_eq!(first.digit(2), Ok(0x00));
+        assert!(first.is_powered_on());
+
+        let second = driver.device_state(1).unwrap();
+        assert_eq!(second.intensity(), 0x09);
+        assert_eq!(second.digit(2), Ok(0b1010_0101));
+        assert!(second.is_powered_on());
+        spi.done();
+    }
+
+    #[test]
+    fn test_device_state_after_init() {
+        let mut expected_transactions = vec![];
+        for (reg, data) in [
+            (Register::Shutdown, 0x01),
+            (Register::DisplayTest, 0x00),
+            (Register::ScanLimit, NUM_DIGITS - 1),
+            (Register::DecodeMode, DecodeMode::NoDecode.value()),
+        ] {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![reg.addr(), data]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+        for digit_register in Register::digits() {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![digit_register.addr(), 0x00]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi);
+
+        driver.init().unwrap();
+
+        let state = driver.device_state(0).unwrap();
+        assert!(state.is_powered_on());
+        assert!(!state.is_test_mode());
+        assert_eq!(state.scan_limit(), NUM_DIGITS);
+        assert_eq!(state.decode_mode(), DecodeMode::NoDecode.value());
+        assert_eq!(state.digits(), &[0; 8]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_device_state_invalid_index() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+
+        assert_eq!(driver.device_state(1), Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
 }
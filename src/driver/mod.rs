@@ -1,5 +1,19 @@
 //! Core MAX7219 driver implementation
 
+mod batch;
+mod fade;
+mod flash;
+mod group;
 mod max7219;
+mod recovery;
+mod schedule;
+mod self_test;
+mod state;
 
+pub use batch::{BatchProgress, CommandBatch};
+pub use flash::FlashStyle;
+pub use group::DeviceGroup;
 pub use max7219::Max7219;
+pub use schedule::BrightnessSchedule;
+pub use self_test::{SelfTestConfig, SelfTestReport};
+pub use state::DeviceState;
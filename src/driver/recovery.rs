@@ -0,0 +1,224 @@
+//! Restoring devices that lost their registers, e.g. after being swapped
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{MAX_DISPLAYS, Max7219, Register, Result};
+
+/// Configuration registers restored before the digits, in the order they
+/// are sent
+const CONFIG_REGISTERS: [Register; 4] = [
+    Register::DisplayTest,
+    Register::ScanLimit,
+    Register::DecodeMode,
+    Register::Intensity,
+];
+
+impl<SPI> Max7219<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Re-sends every register of one device from the driver's shadow copy.
+    ///
+    /// A module plugged into a running chain starts in shutdown with random
+    /// digit data and knows nothing of the configuration sent at `init()`.
+    /// This restores its configuration, the frame last drawn on it and
+    /// finally its power state, without touching the other devices, so it
+    /// shows the same content as before it was swapped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if module_replaced_button.is_low()? {
+    ///     driver.reinitialize_device(2)?;
+    /// }
+    /// ```
+    pub fn reinitialize_device(&mut self, device_index: usize) -> Result<()> {
+        let state = *self.device_state(device_index)?;
+        for register in CONFIG_REGISTERS.into_iter().chain(Register::digits()) {
+            self.write_device_register(device_index, register, state.register_value(register))?;
+        }
+        self.write_device_register(
+            device_index,
+            Register::Shutdown,
+            state.register_value(Register::Shutdown),
+        )
+    }
+
+    /// Rewrites the configuration and power state of every device from the
+    /// shadow copy, one transaction per register.
+    ///
+    /// The MAX7219 is write-only, so a brown-out or an EMI burst that
+    /// corrupts its registers can't be detected directly; a device showing
+    /// garbage or sitting dark until the next full `init()` is the usual
+    /// symptom. Calling this periodically, or whenever the application
+    /// suspects a glitch (a supply dip, a reset-cause flag, a noisy
+    /// actuator switching), repairs such devices within one period.
+    /// Digits are not rewritten; use [`Self::reinitialize_device`] for that.
+    ///
+    /// The chain's shift registers still hold the packets of the previous
+    /// transaction, so with DOUT of the device furthest from the MCU wired
+    /// back to MISO every write shifts them out again. If the driver was
+    /// built [`with_readback`](Self::with_readback), packets that come back
+    /// different from the shadow mean the chain was disturbed, and only
+    /// then is [`Self::resync_count`] incremented. Without readback the
+    /// registers are rewritten blindly and nothing is counted.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if supply_monitor.dipped_since_last_check() {
+    ///     driver.resync_config()?;
+    ///     log::warn!("display resyncs: {}", driver.resync_count());
+    /// }
+    /// ```
+    pub fn resync_config(&mut self) -> Result<()> {
+        let device_count = self.device_count();
+        let mut intact = true;
+        for register in CONFIG_REGISTERS.into_iter().chain([Register::Shutdown]) {
+            let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+            for (device_index, op) in ops.iter_mut().take(device_count).enumerate() {
+                *op = (
+                    register,
+                    self.device_state(device_index)?.register_value(register),
+                );
+            }
+            if self.readback() {
+                intact &= self.write_all_registers_checked(&ops)?;
+            } else {
+                self.write_all_registers(&ops)?;
+            }
+        }
+        if !intact {
+            self.count_resync();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    #[test]
+    fn test_reinitialize_device() {
+        let noop = Register::NoOp.addr();
+        let mut expected = Vec::new();
+        let mut write = |register: Register, data: u8| {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![noop, 0, register.addr(), data]));
+            expected.push(Transaction::transaction_end());
+        };
+        write(Register::Intensity, 7);
+        write(Register::Digit3, 0x5A);
+        write(Register::Shutdown, 1);
+
+        write(Register::DisplayTest, 0);
+        write(Register::ScanLimit, 0);
+        write(Register::DecodeMode, 0);
+        write(Register::Intensity, 7);
+        for register in Register::digits() {
+            let data = if register == Register::Digit3 {
+                0x5A
+            } else {
+                0
+            };
+            write(register, data);
+        }
+        write(Register::Shutdown, 1);
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        driver
+            .write_device_register(1, Register::Intensity, 7)
+            .unwrap();
+        driver
+            .write_device_register(1, Register::Digit3, 0x5A)
+            .unwrap();
+        driver.power_on_device(1).unwrap();
+
+        driver.reinitialize_device(1).unwrap();
+        assert_eq!(
+            driver.reinitialize_device(2),
+            Err(Error::InvalidDeviceIndex)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_resync_config() {
+        let intensity = vec![Register::Intensity.addr(), 3, Register::Intensity.addr(), 9];
+        let resync = [
+            (Register::DisplayTest, [0, 0]),
+            (Register::ScanLimit, [0, 0]),
+            (Register::DecodeMode, [0, 0]),
+            (Register::Intensity, [3, 9]),
+            (Register::Shutdown, [0, 0]),
+        ]
+        .map(|(register, data)| vec![register.addr(), data[0], register.addr(), data[1]]);
+
+        let mut expected = vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(intensity.clone()),
+            Transaction::transaction_end(),
+        ];
+        // Each transfer shifts the previous packets back out of the chain
+        let mut previous = intensity;
+        for frame in &resync {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::transfer(frame.clone(), previous.clone()));
+            expected.push(Transaction::transaction_end());
+            previous = frame.clone();
+        }
+        // The second time a packet comes back corrupted
+        for (index, frame) in resync.iter().enumerate() {
+            let mut readback = previous.clone();
+            if index == 2 {
+                readback[1] ^= 0x04;
+            }
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::transfer(frame.clone(), readback));
+            expected.push(Transaction::transaction_end());
+            previous = frame.clone();
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi)
+            .with_device_count(2)
+            .unwrap()
+            .with_readback(true);
+        driver
+            .write_all_registers(&[(Register::Intensity, 3), (Register::Intensity, 9)])
+            .unwrap();
+
+        driver.resync_config().unwrap();
+        assert_eq!(driver.resync_count(), 0);
+        driver.resync_config().unwrap();
+        assert_eq!(driver.resync_count(), 1);
+        driver.reset_resync_count();
+        assert_eq!(driver.resync_count(), 0);
+        spi.done();
+    }
+
+    #[test]
+    fn test_resync_config_without_readback() {
+        let mut expected = Vec::new();
+        for register in CONFIG_REGISTERS.into_iter().chain([Register::Shutdown]) {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![register.addr(), 0]));
+            expected.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi);
+
+        driver.resync_config().unwrap();
+        assert_eq!(driver.resync_count(), 0);
+        spi.done();
+    }
+}
@@ -0,0 +1,240 @@
+//! Brightness by time of day
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{Brightness, Max7219, Result, easing::Easing, time::TimeOfDay};
+
+/// Day and night brightness, e.g. dimmed between 22:00 and 07:00 for a
+/// bedroom clock.
+///
+/// The brightness ramps over a few minutes at each boundary instead of
+/// jumping: from the day to the night level starting at the night's start,
+/// and back starting at its end. Each ramp has its own duration, and an
+/// [`Easing`] curve shapes both, so a long dusk can fade like the sky.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let schedule = BrightnessSchedule::new(
+///     Brightness::MAX,
+///     Brightness::MIN,
+///     TimeOfDay::from_hm(22, 0)?,
+///     TimeOfDay::from_hm(7, 0)?,
+/// );
+/// loop {
+///     driver.apply_brightness_schedule(&schedule, rtc.time_of_day())?;
+///     delay.delay_ms(1000);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrightnessSchedule {
+    day: Brightness,
+    night: Brightness,
+    night_start: TimeOfDay,
+    night_end: TimeOfDay,
+    dusk_seconds: u32,
+    dawn_seconds: u32,
+    easing: Easing,
+}
+
+impl BrightnessSchedule {
+    /// Create a schedule dimming to `night` from `night_start` until
+    /// `night_end`, with ten minute ramps
+    pub const fn new(
+        day: Brightness,
+        night: Brightness,
+        night_start: TimeOfDay,
+        night_end: TimeOfDay,
+    ) -> Self {
+        Self {
+            day,
+            night,
+            night_start,
+            night_end,
+            dusk_seconds: 10 * 60,
+            dawn_seconds: 10 * 60,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Sets how many minutes each ramp takes; 0 switches at once
+    pub const fn with_ramp_minutes(self, minutes: u16) -> Self {
+        self.with_ramps(minutes, minutes)
+    }
+
+    /// Sets how many minutes dimming at dusk and brightening at dawn take.
+    ///
+    /// A ramp longer than the night or the day is shortened to fit it.
+    ///
+    /// ```rust,ignore
+    /// // Fade out over 45 minutes, wake up over 20
+    /// let schedule = schedule.with_ramps(45, 20).with_easing(Easing::QuadInOut);
+    /// ```
+    pub const fn with_ramps(mut self, dusk_minutes: u16, dawn_minutes: u16) -> Self {
+        self.dusk_seconds = (dusk_minutes as u32).saturating_mul(60);
+        self.dawn_seconds = (dawn_minutes as u32).saturating_mul(60);
+        self
+    }
+
+    /// Sets the curve both ramps follow; the default is linear
+    pub const fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Returns the duration of the dusk and dawn ramps in seconds.
+    pub fn ramp_seconds(&self) -> (u32, u32) {
+        (self.dusk_seconds, self.dawn_seconds)
+    }
+
+    /// Returns the curve the ramps follow.
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+
+    /// Returns the day brightness.
+    pub fn day(&self) -> Brightness {
+        self.day
+    }
+
+    /// Returns the night brightness.
+    pub fn night(&self) -> Brightness {
+        self.night
+    }
+
+    /// Returns `true` if `time` lies between the start and end of the night.
+    pub fn is_night(&self, time: TimeOfDay) -> bool {
+        time.seconds_since(self.night_start) < self.night_end.seconds_since(self.night_start)
+    }
+
+    /// Returns the brightness at `time`.
+    pub fn brightness_at(&self, time: TimeOfDay) -> Brightness {
+        let night_length = self.night_end.seconds_since(self.night_start);
+        let (from, to, elapsed, duration) = if self.is_night(time) {
+            let duration = self.dusk_seconds.min(night_length);
+            let elapsed = time.seconds_since(self.night_start);
+            (self.day, self.night, elapsed, duration)
+        } else {
+            let day_length = self.night_start.seconds_since(self.night_end);
+            let duration = self.dawn_seconds.min(day_length);
+            let elapsed = time.seconds_since(self.night_end);
+            (self.night, self.day, elapsed, duration)
+        };
+        let level = self.easing.interpolate(
+            i32::from(from.level()),
+            i32::from(to.level()),
+            elapsed,
+            duration,
+        );
+        Brightness::saturating(level as u8)
+    }
+}
+
+impl<SPI> Max7219<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Sets every device to the brightness `schedule` gives for `time`.
+    ///
+    /// Call it regularly, e.g. once a second, so ramps progress smoothly.
+    /// Nothing is sent if every device is already at that brightness.
+    /// Returns `true` if the intensity was written.
+    pub fn apply_brightness_schedule(
+        &mut self,
+        schedule: &BrightnessSchedule,
+        time: TimeOfDay,
+    ) -> Result<bool> {
+        self.update_intensity_all(schedule.brightness_at(time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Register;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction};
+
+    fn at(hours: u8, minutes: u8) -> TimeOfDay {
+        TimeOfDay::from_hm(hours, minutes).unwrap()
+    }
+
+    fn schedule() -> BrightnessSchedule {
+        BrightnessSchedule::new(Brightness::MAX, Brightness::MIN, at(22, 0), at(7, 0))
+    }
+
+    #[test]
+    fn test_levels_and_ramps() {
+        let schedule = schedule();
+        assert_eq!(schedule.brightness_at(at(12, 0)), Brightness::MAX);
+        assert_eq!(schedule.brightness_at(at(3, 0)), Brightness::MIN);
+        assert!(schedule.is_night(at(23, 59)) && !schedule.is_night(at(7, 0)));
+
+        // Halfway through the ramps, 7.5 rounds towards the target
+        let dusk = schedule.brightness_at(at(22, 5)).level();
+        let dawn = schedule.brightness_at(at(7, 5)).level();
+        assert_eq!((dusk, dawn), (7, 8));
+        assert_eq!(schedule.brightness_at(at(22, 10)), Brightness::MIN);
+
+        let instant = schedule.with_ramp_minutes(0);
+        assert_eq!(instant.brightness_at(at(22, 0)), Brightness::MIN);
+        assert_eq!(instant.brightness_at(at(7, 0)), Brightness::MAX);
+    }
+
+    #[test]
+    fn test_separate_ramps_and_easing() {
+        let schedule = schedule().with_ramps(60, 20);
+        assert_eq!(schedule.ramp_seconds(), (3600, 1200));
+        assert_eq!(schedule.brightness_at(at(22, 30)).level(), 7);
+        assert_eq!(schedule.brightness_at(at(22, 59)).level(), 0);
+        assert_eq!(schedule.brightness_at(at(7, 20)), Brightness::MAX);
+
+        // Easing in starts slowly: after a quarter, a sixteenth of the way
+        let eased = schedule.with_easing(Easing::QuadIn);
+        assert_eq!(eased.brightness_at(at(22, 15)).level(), 14);
+        assert_eq!(eased.brightness_at(at(7, 5)).level(), 1);
+
+        // A ramp longer than the night is squeezed into it
+        let short_night =
+            BrightnessSchedule::new(Brightness::MAX, Brightness::MIN, at(1, 0), at(1, 30))
+                .with_ramps(60, 10);
+        assert_eq!(short_night.brightness_at(at(1, 15)).level(), 7);
+    }
+
+    #[test]
+    fn test_apply_writes_only_changes() {
+        let mut spi = SpiMock::new(&[]);
+        let mut driver = Max7219::new(&mut spi);
+        let schedule = schedule();
+
+        // The shadow state starts at the lowest intensity
+        assert!(
+            !driver
+                .apply_brightness_schedule(&schedule, at(2, 0))
+                .unwrap()
+        );
+        assert_eq!(
+            driver.device_state(0).unwrap().brightness(),
+            Brightness::MIN
+        );
+        spi.done();
+
+        let expected = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Intensity.addr(), 0x0F]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi);
+        assert!(
+            driver
+                .apply_brightness_schedule(&schedule, at(12, 0))
+                .unwrap()
+        );
+        assert!(
+            !driver
+                .apply_brightness_schedule(&schedule, at(13, 0))
+                .unwrap()
+        );
+        spi.done();
+    }
+}
@@ -0,0 +1,241 @@
+//! Guided self-test routine for installed panels
+
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+
+use crate::{
+    Brightness, DecodeMode, MAX_DISPLAYS, Max7219, NUM_DIGITS, Register, Result,
+    driver::DeviceState,
+};
+
+/// Registers restored after the digits, in the order they are sent; the
+/// power state goes last so nothing half-restored is shown
+const RESTORED_REGISTERS: [Register; 4] = [
+    Register::Intensity,
+    Register::DecodeMode,
+    Register::ScanLimit,
+    Register::Shutdown,
+];
+
+/// Timing of the individual self-test stages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfTestConfig {
+    /// How long display test mode (all LEDs on) is held, in milliseconds
+    pub display_test_ms: u32,
+    /// How long each LED stays lit while walking through the devices, in milliseconds
+    pub pixel_step_ms: u32,
+    /// How long each intensity level is held during the sweep, in milliseconds
+    pub intensity_step_ms: u32,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self {
+            display_test_ms: 1000,
+            pixel_step_ms: 30,
+            intensity_step_ms: 100,
+        }
+    }
+}
+
+/// Summary of what a self-test run exercised.
+///
+/// The MAX7219 cannot report faults, so the report only states what was
+/// shown; the technician watching the panel judges whether it looked right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SelfTestReport {
+    /// Number of devices that were tested
+    pub devices: usize,
+    /// Number of devices put through display test mode
+    pub display_test_devices: usize,
+    /// Number of individual LEDs (segments) lit one at a time
+    pub leds_walked: usize,
+    /// Number of intensity levels shown during the sweep
+    pub intensity_levels: u8,
+}
+
+impl<SPI> Max7219<SPI>
+where
+    SPI: SpiDevice,
+{
+    /// Runs a guided self-test using the default timing.
+    ///
+    /// See [`Self::self_test_with_config`] for the stages.
+    pub fn self_test<D: DelayNs>(&mut self, delay: &mut D) -> Result<SelfTestReport> {
+        self.self_test_with_config(delay, SelfTestConfig::default())
+    }
+
+    /// Runs a guided self-test to validate wiring and solder joints.
+    ///
+    /// The test runs through three stages on every device in the chain:
+    ///
+    /// 1. Display test mode lights every LED at full brightness.
+    /// 2. A single LED walks through each digit and bit, device by device,
+    ///    which reveals dead LEDs, shorted lines and swapped devices.
+    /// 3. All LEDs are lit while the intensity sweeps from 0 to 15.
+    ///
+    /// The devices are powered on, scan all eight digits and decode none of
+    /// them for the test, so every LED is driven whatever the chain was set
+    /// up for. Afterwards the digit data, intensity, decode mode, scan limit
+    /// and power state from before the test are restored from the driver's
+    /// shadow state.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let report = driver.self_test(&mut delay)?;
+    /// assert_eq!(report.leds_walked, driver.device_count() * 64);
+    /// ```
+    pub fn self_test_with_config<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        config: SelfTestConfig,
+    ) -> Result<SelfTestReport> {
+        let device_count = self.device_count();
+        let mut saved = [DeviceState::new(); MAX_DISPLAYS];
+        for (device_index, state) in saved.iter_mut().enumerate().take(device_count) {
+            *state = *self.device_state(device_index)?;
+        }
+
+        let mut report = SelfTestReport {
+            devices: device_count,
+            ..Default::default()
+        };
+
+        self.set_decode_mode_all(DecodeMode::NoDecode)?;
+        self.set_scan_limit_all(NUM_DIGITS)?;
+        self.power_on()?;
+
+        // Stage 1: every LED on
+        self.test_all(true)?;
+        delay.delay_ms(config.display_test_ms);
+        self.test_all(false)?;
+        report.display_test_devices = device_count;
+
+        // Stage 2: walk a single LED through every device
+        self.clear_all()?;
+        for device_index in 0..device_count {
+            for digit in 0..NUM_DIGITS {
+                for bit in (0..8).rev() {
+                    self.write_raw_digit(device_index, digit, 1 << bit)?;
+                    delay.delay_ms(config.pixel_step_ms);
+                    report.leds_walked = report.leds_walked.saturating_add(1);
+                }
+                self.write_raw_digit(device_index, digit, 0x00)?;
+            }
+        }
+
+        // Stage 3: intensity sweep with all LEDs lit
+        for digit_register in Register::digits() {
+            let ops = [(digit_register, 0xFF); MAX_DISPLAYS];
+            self.write_all_registers(&ops)?;
+        }
+        for level in Brightness::MIN.level()..=Brightness::MAX.level() {
+            self.set_intensity_all(Brightness::saturating(level))?;
+            delay.delay_ms(config.intensity_step_ms);
+            report.intensity_levels = report.intensity_levels.saturating_add(1);
+        }
+
+        // Restore what was shown before the test
+        for register in Register::digits().chain(RESTORED_REGISTERS) {
+            let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+            for (op, state) in ops.iter_mut().zip(saved.iter()).take(device_count) {
+                *op = (register, state.register_value(register));
+            }
+            self.write_all_registers(&ops)?;
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{delay::NoopDelay, spi::Mock as SpiMock, spi::Transaction};
+
+    fn write(bytes: Vec<u8>) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(bytes),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    #[test]
+    fn test_self_test_single_device() {
+        let mut expected = Vec::new();
+        // Content shown before the test
+        expected.extend(write(vec![Register::Digit3.addr(), 0x3C]));
+        expected.extend(write(vec![Register::Intensity.addr(), 0x04]));
+        expected.extend(write(vec![Register::ScanLimit.addr(), 0x03]));
+        expected.extend(write(vec![Register::DecodeMode.addr(), 0xFF]));
+        // Test setup
+        expected.extend(write(vec![Register::DecodeMode.addr(), 0x00]));
+        expected.extend(write(vec![Register::ScanLimit.addr(), 0x07]));
+        expected.extend(write(vec![Register::Shutdown.addr(), 0x01]));
+        // Stage 1
+        expected.extend(write(vec![Register::DisplayTest.addr(), 0x01]));
+        expected.extend(write(vec![Register::DisplayTest.addr(), 0x00]));
+        // Stage 2
+        for digit_register in Register::digits() {
+            expected.extend(write(vec![digit_register.addr(), 0x00]));
+        }
+        for digit_register in Register::digits() {
+            for bit in (0..8).rev() {
+                expected.extend(write(vec![digit_register.addr(), 1 << bit]));
+            }
+            expected.extend(write(vec![digit_register.addr(), 0x00]));
+        }
+        // Stage 3
+        for digit_register in Register::digits() {
+            expected.extend(write(vec![digit_register.addr(), 0xFF]));
+        }
+        for intensity in 0..=0x0F {
+            expected.extend(write(vec![Register::Intensity.addr(), intensity]));
+        }
+        // Restore
+        for digit_register in Register::digits() {
+            let data = if digit_register == Register::Digit3 {
+                0x3C
+            } else {
+                0x00
+            };
+            expected.extend(write(vec![digit_register.addr(), data]));
+        }
+        expected.extend(write(vec![Register::Intensity.addr(), 0x04]));
+        expected.extend(write(vec![Register::DecodeMode.addr(), 0xFF]));
+        expected.extend(write(vec![Register::ScanLimit.addr(), 0x03]));
+        expected.extend(write(vec![Register::Shutdown.addr(), 0x00]));
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi);
+        driver.write_raw_digit(0, 3, 0x3C).unwrap();
+        driver.set_intensity(0, 0x04).unwrap();
+        driver.set_scan_limit_all(4).unwrap();
+        driver.set_decode_mode_all(DecodeMode::AllDigits).unwrap();
+
+        let report = driver.self_test(&mut NoopDelay::new()).unwrap();
+        assert_eq!(
+            report,
+            SelfTestReport {
+                devices: 1,
+                display_test_devices: 1,
+                leds_walked: 64,
+                intensity_levels: 16,
+            }
+        );
+        let state = driver.device_state(0).unwrap();
+        assert_eq!(state.digit(3), Ok(0x3C));
+        assert_eq!(state.register_value(Register::ScanLimit), 0x03);
+        assert!(!state.is_powered_on());
+        spi.done();
+    }
+
+    #[test]
+    fn test_self_test_config_default() {
+        let config = SelfTestConfig::default();
+        assert_eq!(config.display_test_ms, 1000);
+        assert_eq!(config.pixel_step_ms, 30);
+        assert_eq!(config.intensity_step_ms, 100);
+    }
+}
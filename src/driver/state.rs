@@ -0,0 +1,182 @@
+//! Shadow copy of the MAX7219 register contents
+
+use crate::{Brightness, NUM_DIGITS, Register, Result, error::Error};
+
+/// Last-known register values of a single MAX7219 device.
+///
+/// The MAX7219 is write-only, so the driver keeps this shadow copy up to date
+/// every time it sends a register write. It reflects what the driver *sent*,
+/// not what the chip reports; before the first write the values are the
+/// power-on defaults (all zero, display in shutdown).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceState {
+    digits: [u8; NUM_DIGITS as usize],
+    intensity: u8,
+    decode_mode: u8,
+    scan_limit: u8,
+    powered_on: bool,
+    test_mode: bool,
+}
+
+impl DeviceState {
+    /// Create the state of a freshly powered device
+    pub const fn new() -> Self {
+        Self {
+            digits: [0; NUM_DIGITS as usize],
+            intensity: 0,
+            decode_mode: 0,
+            scan_limit: 0,
+            powered_on: false,
+            test_mode: false,
+        }
+    }
+
+    /// Returns the data last written to all digit registers (DIG0 to DIG7).
+    pub fn digits(&self) -> &[u8; NUM_DIGITS as usize] {
+        &self.digits
+    }
+
+    /// Returns the data last written to a single digit register (0 to 7).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `digit` is greater than 7.
+    pub fn digit(&self, digit: u8) -> Result<u8> {
+        self.digits
+            .get(digit as usize)
+            .copied()
+            .ok_or(Error::InvalidDigit)
+    }
+
+    /// Returns the last intensity value (0 to 15).
+    pub fn intensity(&self) -> u8 {
+        self.intensity
+    }
+
+    /// Returns the last intensity value as a [`Brightness`].
+    pub fn brightness(&self) -> Brightness {
+        Brightness::saturating(self.intensity)
+    }
+
+    /// Returns the raw value of the decode mode register.
+    ///
+    /// Each bit enables Code B decoding for the matching digit.
+    pub fn decode_mode(&self) -> u8 {
+        self.decode_mode
+    }
+
+    /// Returns the number of scanned digits (1 to 8).
+    ///
+    /// This is the value passed to `set_device_scan_limit`, not the raw
+    /// register value (which is one less).
+    pub fn scan_limit(&self) -> u8 {
+        self.scan_limit.saturating_add(1)
+    }
+
+    /// Returns `true` if the device was last taken out of shutdown mode.
+    pub fn is_powered_on(&self) -> bool {
+        self.powered_on
+    }
+
+    /// Returns `true` if display test mode is enabled.
+    pub fn is_test_mode(&self) -> bool {
+        self.test_mode
+    }
+
+    /// Returns the data last written to `register`, as it was sent.
+    pub(crate) fn register_value(&self, register: Register) -> u8 {
+        match register {
+            Register::NoOp => 0,
+            Register::DecodeMode => self.decode_mode,
+            Register::Intensity => self.intensity,
+            Register::ScanLimit => self.scan_limit,
+            Register::Shutdown => self.powered_on as u8,
+            Register::DisplayTest => self.test_mode as u8,
+            digit => digit
+                .digit_index()
+                .and_then(|index| self.digits.get(index as usize))
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Update the shadow copy after `data` was sent to `register`.
+    pub(crate) fn record(&mut self, register: Register, data: u8) {
+        match register {
+            Register::NoOp => {}
+            Register::Digit0
+            | Register::Digit1
+            | Register::Digit2
+            | Register::Digit3
+            | Register::Digit4
+            | Register::Digit5
+            | Register::Digit6
+            | Register::Digit7 => {
+                let index = register.digit_index();
+                if let Some(digit) = index.and_then(|index| self.digits.get_mut(index as usize)) {
+                    *digit = data;
+                }
+            }
+            Register::DecodeMode => self.decode_mode = data,
+            Register::Intensity => self.intensity = data & 0x0F,
+            Register::ScanLimit => self.scan_limit = data & 0x07,
+            Register::Shutdown => self.powered_on = data & 0x01 != 0,
+            Register::DisplayTest => self.test_mode = data & 0x01 != 0,
+        }
+    }
+}
+
+impl Default for DeviceState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_state() {
+        let state = DeviceState::new();
+        assert_eq!(state.digits(), &[0; 8]);
+        assert_eq!(state.intensity(), 0);
+        assert_eq!(state.decode_mode(), 0);
+        assert_eq!(state.scan_limit(), 1);
+        assert!(!state.is_powered_on());
+        assert!(!state.is_test_mode());
+    }
+
+    #[test]
+    fn test_record_registers() {
+        let mut state = DeviceState::new();
+        state.record(Register::Digit0, 0xAA);
+        state.record(Register::Digit7, 0x55);
+        state.record(Register::Intensity, 0x07);
+        state.record(Register::DecodeMode, 0x0F);
+        state.record(Register::ScanLimit, 0x03);
+        state.record(Register::Shutdown, 0x01);
+        state.record(Register::DisplayTest, 0x01);
+
+        assert_eq!(state.digit(0), Ok(0xAA));
+        assert_eq!(state.digit(7), Ok(0x55));
+        assert_eq!(state.intensity(), 0x07);
+        assert_eq!(state.decode_mode(), 0x0F);
+        assert_eq!(state.scan_limit(), 4);
+        assert!(state.is_powered_on());
+        assert!(state.is_test_mode());
+    }
+
+    #[test]
+    fn test_record_noop_is_ignored() {
+        let mut state = DeviceState::new();
+        state.record(Register::NoOp, 0xFF);
+        assert_eq!(state, DeviceState::new());
+    }
+
+    #[test]
+    fn test_digit_invalid() {
+        let state = DeviceState::new();
+        assert_eq!(state.digit(8), Err(Error::InvalidDigit));
+    }
+}
@@ -0,0 +1,307 @@
+//! Easing curves for animations
+//!
+//! The curves work in fixed point so they are cheap on MCUs without an FPU.
+//! Progress is given in permille: `0` is the start of an animation and
+//! [`PROGRESS_END`] (1000) is its end. The eased value uses the same scale.
+//!
+//! The same curves drive the crate's own transitions and faders, and are
+//! public so that application animations can match them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use max7219_display::easing::Easing;
+//!
+//! // Slide a sprite from column 0 to column 24 over 500 ms
+//! let x = Easing::CubicOut.interpolate(0, 24, 250, 500);
+//! assert_eq!(x, 21);
+//! ```
+
+use crate::time::Progress;
+
+/// Progress value marking the end of an animation
+pub const PROGRESS_END: u16 = 1000;
+
+/// Easing curve mapping linear progress onto eased progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Starts slowly, accelerates (quadratic)
+    QuadIn,
+    /// Starts quickly, decelerates (quadratic)
+    QuadOut,
+    /// Accelerates, then decelerates (quadratic)
+    QuadInOut,
+    /// Starts slowly, accelerates (cubic)
+    CubicIn,
+    /// Starts quickly, decelerates (cubic)
+    CubicOut,
+    /// Accelerates, then decelerates (cubic)
+    CubicInOut,
+    /// Bounces against the start before leaving it
+    BounceIn,
+    /// Bounces against the end before settling
+    BounceOut,
+}
+
+/// Scale used internally for better precision (1.0 = 1_000_000)
+const MICRO: i64 = 1_000_000;
+
+fn bounce_out(t: i64) -> i64 {
+    // Piecewise parabolas of the classic Penner bounce, in micro units
+    let (t, offset) = match t {
+        t if t < 363_636 => (t, 0),
+        t if t < 727_273 => (t.saturating_sub(545_455), 750_000),
+        t if t < 909_091 => (t.saturating_sub(818_182), 937_500),
+        t => (t.saturating_sub(954_545), 984_375),
+    };
+    // 7.5625 = 121 / 16
+    (t.saturating_mul(t).saturating_mul(121) / (16 * MICRO)).saturating_add(offset)
+}
+
+/// Product of two values in micro units
+fn scaled(a: i64, b: i64) -> i64 {
+    a.saturating_mul(b) / MICRO
+}
+
+impl Easing {
+    /// Returns the eased progress for a linear `progress` in permille.
+    ///
+    /// `progress` values above [`PROGRESS_END`] are clamped.
+    pub fn apply(&self, progress: u16) -> u16 {
+        let t = i64::from(progress.min(PROGRESS_END)).saturating_mul(1000);
+        let inv = MICRO.saturating_sub(t);
+        let eased = match self {
+            Easing::Linear => t,
+            Easing::QuadIn => scaled(t, t),
+            Easing::QuadOut => MICRO.saturating_sub(scaled(inv, inv)),
+            Easing::QuadInOut => {
+                if t < MICRO / 2 {
+                    scaled(t.saturating_mul(2), t)
+                } else {
+                    MICRO.saturating_sub(scaled(inv.saturating_mul(2), inv))
+                }
+            }
+            Easing::CubicIn => scaled(scaled(t, t), t),
+            Easing::CubicOut => MICRO.saturating_sub(scaled(scaled(inv, inv), inv)),
+            Easing::CubicInOut => {
+                if t < MICRO / 2 {
+                    scaled(scaled(t, t).saturating_mul(4), t)
+                } else {
+                    MICRO.saturating_sub(scaled(scaled(inv, inv).saturating_mul(4), inv))
+                }
+            }
+            Easing::BounceIn => MICRO.saturating_sub(bounce_out(inv)),
+            Easing::BounceOut => bounce_out(t),
+        };
+        // Round to the nearest permille
+        (eased.saturating_add(500) / 1000).clamp(0, i64::from(PROGRESS_END)) as u16
+    }
+
+    /// Returns the eased progress of an animation lasting `duration` time
+    /// units after `elapsed` units, in permille.
+    ///
+    /// A zero `duration` counts as finished.
+    pub fn progress(&self, elapsed: u32, duration: u32) -> u16 {
+        if duration == 0 || elapsed >= duration {
+            return self.apply(PROGRESS_END);
+        }
+        let linear = u64::from(elapsed)
+            .saturating_mul(u64::from(PROGRESS_END))
+            .checked_div(u64::from(duration))
+            .unwrap_or(0);
+        self.apply(linear as u16)
+    }
+
+    /// Returns the value between `from` and `to` after `elapsed` of
+    /// `duration` time units, following this curve.
+    pub fn interpolate(&self, from: i32, to: i32, elapsed: u32, duration: u32) -> i32 {
+        let value = self.interpolate_wide(from.into(), to.into(), elapsed, duration);
+        value as i32
+    }
+
+    /// [`Self::interpolate`] over a range wide enough for `u64` values.
+    fn interpolate_wide(&self, from: i128, to: i128, elapsed: u32, duration: u32) -> i128 {
+        const END: i128 = PROGRESS_END as i128;
+
+        let progress = i128::from(self.progress(elapsed, duration));
+        let delta = to.saturating_sub(from);
+        let offset = delta.saturating_mul(progress);
+        // Round half away from zero
+        let offset = offset.saturating_add(offset.signum().saturating_mul(END / 2)) / END;
+        from.saturating_add(offset)
+    }
+}
+
+/// Eased count from one value to another over a fixed time, e.g. a
+/// scoreboard counting up to a new score.
+///
+/// Numeric widgets use it for their `animate_to` methods; it can also drive
+/// application values directly.
+///
+/// # Example
+///
+/// ```rust
+/// use max7219_display::easing::{Easing, Tween};
+/// use max7219_display::time::Progress;
+///
+/// let mut tween = Tween::new(0, 100, 1000).with_easing(Easing::QuadOut);
+/// assert_eq!(tween.tick(500), Progress::Running);
+/// assert_eq!(tween.value(), 75);
+/// assert_eq!(tween.tick(500), Progress::Complete);
+/// assert_eq!(tween.value(), 100);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tween {
+    from: u64,
+    to: u64,
+    elapsed_ms: u32,
+    duration_ms: u32,
+    easing: Easing,
+}
+
+impl Tween {
+    /// Count from `from` to `to` in `duration_ms`, at constant speed
+    pub const fn new(from: u64, to: u64, duration_ms: u32) -> Self {
+        Self {
+            from,
+            to,
+            elapsed_ms: 0,
+            duration_ms,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Sets the easing curve of the count
+    pub const fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Returns the value the count ends at.
+    pub fn target(&self) -> u64 {
+        self.to
+    }
+
+    /// Returns `true` until the end value is reached.
+    pub fn is_running(&self) -> bool {
+        self.elapsed_ms < self.duration_ms
+    }
+
+    /// Returns the current value of the count, rounded like
+    /// [`Easing::interpolate`].
+    pub fn value(&self) -> u64 {
+        let value = self.easing.interpolate_wide(
+            self.from.into(),
+            self.to.into(),
+            self.elapsed_ms,
+            self.duration_ms,
+        );
+        value as u64
+    }
+
+    /// Advances the count by `dt_ms` milliseconds.
+    pub fn tick(&mut self, dt_ms: u32) -> Progress {
+        if !self.is_running() {
+            return Progress::Idle;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+        if self.is_running() {
+            Progress::Running
+        } else {
+            Progress::Complete
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 9] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+    ];
+
+    #[test]
+    fn test_endpoints() {
+        for easing in ALL {
+            assert_eq!(easing.apply(0), 0, "{easing:?}");
+            assert_eq!(easing.apply(PROGRESS_END), PROGRESS_END, "{easing:?}");
+            assert_eq!(easing.apply(u16::MAX), PROGRESS_END, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn test_midpoints() {
+        assert_eq!(Easing::Linear.apply(500), 500);
+        assert_eq!(Easing::QuadIn.apply(500), 250);
+        assert_eq!(Easing::QuadOut.apply(500), 750);
+        assert_eq!(Easing::QuadInOut.apply(500), 500);
+        assert_eq!(Easing::QuadInOut.apply(250), 125);
+        assert_eq!(Easing::CubicIn.apply(500), 125);
+        assert_eq!(Easing::CubicOut.apply(500), 875);
+        assert_eq!(Easing::CubicInOut.apply(250), 63);
+    }
+
+    #[test]
+    fn test_in_out_are_monotonic() {
+        for easing in [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+        ] {
+            let mut last = 0;
+            for progress in 0..=PROGRESS_END {
+                let value = easing.apply(progress);
+                assert!(value >= last, "{easing:?} at {progress}");
+                last = value;
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounce_touches_end() {
+        // The first bounce lands on the end at t = 1 / 2.75
+        assert!(Easing::BounceOut.apply(364) >= 999);
+        assert!(Easing::BounceOut.apply(545) < 800);
+        assert!(Easing::BounceIn.apply(636) <= 1);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(Easing::Linear.interpolate(0, 24, 250, 500), 12);
+        assert_eq!(Easing::CubicOut.interpolate(0, 24, 250, 500), 21);
+        assert_eq!(Easing::Linear.interpolate(15, 0, 1, 3), 10);
+        assert_eq!(Easing::QuadIn.interpolate(-8, 8, 600, 500), 8);
+        assert_eq!(Easing::Linear.interpolate(3, 9, 0, 0), 9);
+    }
+
+    #[test]
+    fn test_tween() {
+        let mut tween = Tween::new(100, 0, 400);
+        assert_eq!(tween.value(), 100);
+        assert_eq!(tween.tick(100), Progress::Running);
+        assert_eq!(tween.value(), 75);
+        assert_eq!(tween.tick(300), Progress::Complete);
+        assert_eq!(tween.value(), 0);
+        assert_eq!(tween.tick(100), Progress::Idle);
+
+        let instant = Tween::new(0, u64::MAX, 0);
+        assert!(!instant.is_running());
+        assert_eq!(instant.value(), u64::MAX);
+    }
+}
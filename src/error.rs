@@ -1,5 +1,7 @@
 //! Error types for MAX7219 driver
 
+use crate::chain::DeviceKind;
+
 /// Errors that can occur when using the MAX7219 driver
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
@@ -15,10 +17,27 @@ pub enum Error {
     InvalidDigit,
     /// Invalid intensity value (must be 0-15)
     InvalidIntensity,
-    /// Unsupported Character
-    UnsupportedChar,
+    /// The character has no glyph in the font or segment table
+    UnsupportedChar(char),
     /// Buffer Error
     BufferError,
+    /// Pixel mapping does not match the number of chained devices
+    InvalidMapping,
+    /// Animation data is malformed or a frame is out of range
+    InvalidAnimation,
+    /// A command payload is empty, unknown or has a malformed argument
+    InvalidCommand,
+    /// A template has more slots than it can hold, or no slot of a name
+    InvalidTemplate,
+    /// The operation does not apply to the kind of device it addresses
+    WrongDeviceKind {
+        /// Index of the addressed device
+        device_index: usize,
+        /// What the device actually is
+        kind: DeviceKind,
+    },
+    /// A shared display is already in use by an enclosing call
+    DisplayBusy,
     /// SPI communication error
     SpiError,
 }
@@ -33,8 +52,46 @@ impl core::fmt::Display for Error {
             Self::InvalidScanLimit => write!(f, "Invalid scan limit value"),
             Self::InvalidDeviceCount => write!(f, "Invalid device count"),
             Self::InvalidRegister => write!(f, "Invalid register address"),
-            Self::UnsupportedChar => write!(f, "Unsupported Character"),
+            Self::UnsupportedChar(ch) => write!(f, "Unsupported Character {ch:?}"),
             Self::BufferError => write!(f, "LED Matrix buffer error"),
+            Self::InvalidMapping => write!(f, "Invalid pixel mapping"),
+            Self::InvalidAnimation => write!(f, "Invalid animation data"),
+            Self::InvalidCommand => write!(f, "Invalid command"),
+            Self::InvalidTemplate => write!(f, "Invalid template slot"),
+            Self::DisplayBusy => write!(f, "Shared display is busy"),
+            Self::WrongDeviceKind { device_index, kind } => {
+                write!(f, "Device {device_index} is a {kind:?} device")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// What to do when a character has no glyph in a font.
+///
+/// `G` is the glyph type of the font: a segment pattern for seven-segment
+/// fonts or an 8x8 bitmap for LED matrix fonts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharPolicy<G> {
+    /// Leave the character out, as if it were not in the text
+    Skip,
+    /// Draw the given glyph instead
+    Replace(G),
+    /// Fail with `Error::UnsupportedChar`
+    Error,
+}
+
+impl<G: Copy> CharPolicy<G> {
+    /// Applies the policy to `ch`, which is missing from the font.
+    ///
+    /// Returns the glyph to draw, or `None` if the character is skipped.
+    pub const fn resolve(&self, ch: char) -> Result<Option<G>, Error> {
+        match *self {
+            Self::Skip => Ok(None),
+            Self::Replace(glyph) => Ok(Some(glyph)),
+            Self::Error => Err(Error::UnsupportedChar(ch)),
         }
     }
 }
@@ -72,6 +129,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_char_policy_resolve() {
+        assert_eq!(CharPolicy::<u8>::Skip.resolve('x'), Ok(None));
+        assert_eq!(CharPolicy::Replace(0x01u8).resolve('x'), Ok(Some(0x01)));
+        assert_eq!(
+            CharPolicy::<u8>::Error.resolve('x'),
+            Err(Error::UnsupportedChar('x'))
+        );
+    }
+
     #[test]
     fn test_error_device() {
         assert_eq!(
@@ -96,10 +163,24 @@ mod tests {
             "Invalid intensity value"
         );
         assert_eq!(
-            format!("{}", Error::UnsupportedChar),
-            "Unsupported Character"
+            format!("{}", Error::UnsupportedChar('€')),
+            "Unsupported Character '€'"
         );
         assert_eq!(format!("{}", Error::BufferError), "LED Matrix buffer error");
+        assert_eq!(
+            format!("{}", Error::InvalidMapping),
+            "Invalid pixel mapping"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                Error::WrongDeviceKind {
+                    device_index: 2,
+                    kind: DeviceKind::SevenSegment
+                }
+            ),
+            "Device 2 is a SevenSegment device"
+        );
         assert_eq!(format!("{}", Error::SpiError), "SPI communication error");
     }
 
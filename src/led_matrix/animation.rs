@@ -0,0 +1,453 @@
+//! Frame sequences stored in flash, optionally RLE-compressed
+//!
+//! A frame holds 8 rows (bit 7 = leftmost pixel) for each of one or more
+//! modules. Played with [`LedMatrix::play_animation`], the modules of a
+//! frame are repeated across the display, so an 8x8 icon animation shows on
+//! every module while a frame as wide as the display fills it once.
+//!
+//! # RLE format
+//!
+//! The first byte is the number of modules per frame (1 to
+//! [`MAX_DISPLAYS`]). Runs follow, each starting with a control byte whose
+//! low 7 bits plus one give the run length:
+//!
+//! | Control       | Run                                       |
+//! |---------------|-------------------------------------------|
+//! | `0x00..=0x7F` | the next 1 to 128 bytes, copied literally |
+//! | `0x80..=0xFF` | the next byte, repeated 1 to 128 times    |
+//!
+//! Runs never span two frames, so a frame ends exactly where its last run
+//! does. Blank rows and solid areas, which make up most of an animation,
+//! shrink to two bytes per run. [`encode_rle`] produces this format.
+//!
+//! [`LedMatrix::play_animation`]: crate::led_matrix::LedMatrix::play_animation
+
+use crate::{Error, MAX_DISPLAYS, Result};
+
+/// Longest run of the RLE format
+const MAX_RUN: usize = 128;
+
+/// Control bit marking a repeated run
+const REPEAT: u8 = 0x80;
+
+/// Where the frames of an animation are read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frames<'a> {
+    /// Uncompressed single-module frames
+    Raw(&'a [[u8; 8]]),
+    /// RLE runs following the header byte
+    Rle { modules: usize, runs: &'a [u8] },
+}
+
+/// A sequence of frames, uncompressed or RLE-compressed.
+///
+/// Both constructors are `const fn`, so an animation can be declared as a
+/// `static` next to its data.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// static BOOT: Animation = match Animation::from_rle(include_bytes!("boot.rle")) {
+///     Ok(animation) => animation,
+///     Err(_) => panic!("bad boot.rle header"),
+/// };
+///
+/// matrix.play_animation(&mut delay, &BOOT, 40)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Animation<'a> {
+    frames: Frames<'a>,
+}
+
+impl<'a> Animation<'a> {
+    /// Create an animation of uncompressed 8x8 frames
+    pub const fn from_frames(frames: &'a [[u8; 8]]) -> Self {
+        Self {
+            frames: Frames::Raw(frames),
+        }
+    }
+
+    /// Create an animation from RLE-compressed data, see the
+    /// [module documentation](self) for the format.
+    ///
+    /// Only the header is checked here, the runs when they are decoded; use
+    /// [`Self::frame_count`] to validate them up front.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidAnimation` if the header is missing or the
+    /// module count is not 1 to [`MAX_DISPLAYS`].
+    pub const fn from_rle(data: &'a [u8]) -> Result<Self> {
+        let [modules, runs @ ..] = data else {
+            return Err(Error::InvalidAnimation);
+        };
+        let modules = *modules as usize;
+        if modules == 0 || modules > MAX_DISPLAYS {
+            return Err(Error::InvalidAnimation);
+        }
+        Ok(Self {
+            frames: Frames::Rle { modules, runs },
+        })
+    }
+
+    /// Returns the number of modules in each frame.
+    pub const fn modules(&self) -> usize {
+        match self.frames {
+            Frames::Raw(_) => 1,
+            Frames::Rle { modules, .. } => modules,
+        }
+    }
+
+    /// Returns the number of bytes in each frame, 8 per module.
+    pub const fn frame_len(&self) -> usize {
+        self.modules().saturating_mul(8)
+    }
+
+    /// Returns the number of frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidAnimation` if RLE data is malformed.
+    pub const fn frame_count(&self) -> Result<usize> {
+        let (modules, mut runs) = match self.frames {
+            Frames::Raw(frames) => return Ok(frames.len()),
+            Frames::Rle { modules, runs } => (modules, runs),
+        };
+        if modules == 0 || modules > MAX_DISPLAYS {
+            return Err(Error::InvalidAnimation);
+        }
+        let mut scratch = [0; 8 * MAX_DISPLAYS];
+        let Some((frame, _)) = scratch.split_at_mut_checked(modules.saturating_mul(8)) else {
+            return Err(Error::InvalidAnimation);
+        };
+        let mut count: usize = 0;
+        while !runs.is_empty() {
+            runs = match decode_frame(runs, frame) {
+                Ok(rest) => rest,
+                Err(error) => return Err(error),
+            };
+            count = count.saturating_add(1);
+        }
+        Ok(count)
+    }
+
+    /// Decodes frame `index` into `out`, which must be
+    /// [`Self::frame_len`] bytes long.
+    ///
+    /// RLE frames are decoded from the start of the data, so play
+    /// animations in order with [`Self::decode_next`] instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidAnimation` if `index` is out of range, `out`
+    /// has the wrong length or the data is malformed.
+    pub const fn frame(&self, index: usize, out: &mut [u8]) -> Result<()> {
+        let mut cursor = AnimationCursor::start();
+        let mut skipped: usize = 0;
+        while skipped < index {
+            match self.decode_next(&mut cursor, out) {
+                Ok(true) => skipped = skipped.saturating_add(1),
+                Ok(false) => return Err(Error::InvalidAnimation),
+                Err(error) => return Err(error),
+            }
+        }
+        match self.decode_next(&mut cursor, out) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Error::InvalidAnimation),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Decodes the frame at `cursor` into `out` and moves the cursor past
+    /// it.
+    ///
+    /// Returns `false` once every frame was decoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidAnimation` if `out` is not [`Self::frame_len`]
+    /// bytes long or the data is malformed.
+    pub const fn decode_next(&self, cursor: &mut AnimationCursor, out: &mut [u8]) -> Result<bool> {
+        let modules = self.modules();
+        if modules == 0 || modules > MAX_DISPLAYS || out.len() != self.frame_len() {
+            return Err(Error::InvalidAnimation);
+        }
+        match self.frames {
+            Frames::Raw(frames) => {
+                let Some((_, [frame, ..])) = frames.split_at_checked(cursor.offset) else {
+                    return Ok(false);
+                };
+                out.copy_from_slice(frame);
+                cursor.offset = cursor.offset.saturating_add(1);
+                Ok(true)
+            }
+            Frames::Rle { runs, .. } => {
+                let Some((_, runs)) = runs.split_at_checked(cursor.offset) else {
+                    return Err(Error::InvalidAnimation);
+                };
+                if runs.is_empty() {
+                    return Ok(false);
+                }
+                match decode_frame(runs, out) {
+                    Ok(rest) => {
+                        let used = runs.len().saturating_sub(rest.len());
+                        cursor.offset = cursor.offset.saturating_add(used);
+                        Ok(true)
+                    }
+                    Err(error) => Err(error),
+                }
+            }
+        }
+    }
+}
+
+/// Position of the next frame of an [`Animation`], for decoding its frames
+/// in order without starting over for each one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnimationCursor {
+    /// Next frame (raw) or byte of the runs (RLE)
+    offset: usize,
+}
+
+impl AnimationCursor {
+    /// Returns a cursor at the first frame.
+    pub const fn start() -> Self {
+        Self { offset: 0 }
+    }
+}
+
+/// Decodes the runs of one frame from the front of `runs` into `out` and
+/// returns the runs that follow it.
+const fn decode_frame<'a>(mut runs: &'a [u8], out: &mut [u8]) -> Result<&'a [u8]> {
+    let mut rest: &mut [u8] = out;
+    while !rest.is_empty() {
+        let [control, data @ ..] = runs else {
+            return Err(Error::InvalidAnimation);
+        };
+        // At most 128, the run length of the control byte
+        let count = (*control & !REPEAT).wrapping_add(1) as usize;
+        let Some((run, tail)) = rest.split_at_mut_checked(count) else {
+            return Err(Error::InvalidAnimation);
+        };
+        if *control & REPEAT == 0 {
+            let Some((literal, data)) = data.split_at_checked(count) else {
+                return Err(Error::InvalidAnimation);
+            };
+            run.copy_from_slice(literal);
+            runs = data;
+        } else {
+            let [value, data @ ..] = data else {
+                return Err(Error::InvalidAnimation);
+            };
+            let mut run: &mut [u8] = run;
+            while let [byte, next @ ..] = run {
+                *byte = *value;
+                run = next;
+            }
+            runs = data;
+        }
+        rest = tail;
+    }
+    Ok(runs)
+}
+
+/// Compresses `frames`, each `modules * 8` bytes long, into `out` in the
+/// format read by [`Animation::from_rle`], and returns the encoded length.
+///
+/// Meant for tools and tests that prepare animation data; firmware only
+/// needs the decoder.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidAnimation` if `modules` is out of range or
+/// `frames` is not a whole number of frames, and `Error::BufferError` if
+/// `out` is too small.
+pub fn encode_rle(frames: &[u8], modules: usize, out: &mut [u8]) -> Result<usize> {
+    let frame_len = modules.saturating_mul(8);
+    if modules == 0 || modules > MAX_DISPLAYS || !frames.len().is_multiple_of(frame_len) {
+        return Err(Error::InvalidAnimation);
+    }
+    let mut len: usize = 0;
+    let mut push = |bytes: &[u8]| -> Result<()> {
+        let end = len.checked_add(bytes.len()).ok_or(Error::BufferError)?;
+        out.get_mut(len..end)
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(bytes);
+        len = end;
+        Ok(())
+    };
+    push(&[modules as u8])?;
+
+    for frame in frames.chunks_exact(frame_len) {
+        let mut rest = frame;
+        while let [first, ..] = rest {
+            let repeat = rest
+                .iter()
+                .take(MAX_RUN)
+                .take_while(|&byte| byte == first)
+                .count();
+            if repeat >= 3 {
+                push(&[REPEAT | repeat.saturating_sub(1) as u8, *first])?;
+                rest = rest.get(repeat..).unwrap_or_default();
+                continue;
+            }
+            // Copy bytes literally up to the next three equal bytes
+            let literal = rest
+                .windows(3)
+                .take(MAX_RUN)
+                .position(|bytes| bytes.iter().all(|byte| bytes.first() == Some(byte)))
+                .unwrap_or(rest.len().min(MAX_RUN));
+            let (literal, tail) = rest.split_at_checked(literal).unwrap_or((rest, &[]));
+            push(&[literal.len().saturating_sub(1) as u8])?;
+            push(literal)?;
+            rest = tail;
+        }
+    }
+    Ok(len)
+}
+
+/// Embeds an animation written by
+/// [`build::emit_frames`](crate::build::emit_frames) in a build script.
+///
+/// Expands to an [`Animation`] over the bytes of `$OUT_DIR/<name>.rle`, so
+/// it can initialize a `static`. A bad header fails the build:
+///
+/// ```rust,ignore
+/// static SPINNER: Animation = max7219_display::include_frames!("spinner");
+/// ```
+#[macro_export]
+macro_rules! include_frames {
+    ($name:literal) => {
+        match $crate::led_matrix::Animation::from_rle(include_bytes!(concat!(
+            env!("OUT_DIR"),
+            "/",
+            $name,
+            ".rle"
+        ))) {
+            Ok(animation) => animation,
+            Err(_) => panic!(concat!("invalid animation header in ", $name, ".rle")),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAMES: [[u8; 8]; 3] = [
+        [0, 0, 0, 0x18, 0x18, 0, 0, 0],
+        [0, 0, 0x3C, 0x24, 0x24, 0x3C, 0, 0],
+        [0xFF, 0x81, 0x42, 0x24, 0x18, 0x24, 0x42, 0x81],
+    ];
+
+    #[test]
+    fn test_rle_round_trip() {
+        let mut data = [0; 64];
+        let len = encode_rle(FRAMES.as_flattened(), 1, &mut data).unwrap();
+        assert_eq!(
+            &data[..8],
+            &[1, REPEAT | 2, 0, 1, 0x18, 0x18, REPEAT | 2, 0]
+        );
+
+        let animation = Animation::from_rle(&data[..len]).unwrap();
+        assert_eq!(animation.frame_count(), Ok(3));
+        let mut cursor = AnimationCursor::start();
+        let mut frame = [0; 8];
+        for expected in FRAMES {
+            assert_eq!(animation.decode_next(&mut cursor, &mut frame), Ok(true));
+            assert_eq!(frame, expected);
+        }
+        assert_eq!(animation.decode_next(&mut cursor, &mut frame), Ok(false));
+
+        animation.frame(1, &mut frame).unwrap();
+        assert_eq!(frame, FRAMES[1]);
+        assert_eq!(animation.frame(3, &mut frame), Err(Error::InvalidAnimation));
+    }
+
+    #[test]
+    fn test_rle_blank_frames() {
+        let mut data = [0; 32];
+        assert_eq!(encode_rle(&[0; 64], 1, &mut data), Ok(1 + 8 * 2));
+        assert_eq!(
+            encode_rle(&[0; 64], 1, &mut data[..8]),
+            Err(Error::BufferError)
+        );
+        assert_eq!(
+            encode_rle(&[0; 12], 1, &mut data),
+            Err(Error::InvalidAnimation)
+        );
+    }
+
+    #[test]
+    fn test_const_decode() {
+        // A blank frame and a full frame on two modules
+        static DATA: [u8; 5] = [2, REPEAT | 15, 0x00, REPEAT | 15, 0xFF];
+        static ANIMATION: Animation = match Animation::from_rle(&DATA) {
+            Ok(animation) => animation,
+            Err(_) => panic!(),
+        };
+        const SECOND: [u8; 16] = {
+            let mut frame = [0; 16];
+            assert!(ANIMATION.frame(1, &mut frame).is_ok());
+            frame
+        };
+        assert_eq!(SECOND, [0xFF; 16]);
+        assert_eq!(ANIMATION.frame_count(), Ok(2));
+    }
+
+    #[test]
+    fn test_raw_frames() {
+        let animation = Animation::from_frames(&FRAMES);
+        assert_eq!(animation.frame_count(), Ok(3));
+        let mut frame = [0; 8];
+        animation.frame(2, &mut frame).unwrap();
+        assert_eq!(frame, FRAMES[2]);
+    }
+
+    #[test]
+    fn test_malformed_data() {
+        // Run spanning two single-module frames
+        assert_eq!(
+            Animation::from_rle(&[1, REPEAT | 9, 0])
+                .unwrap()
+                .frame_count(),
+            Err(Error::InvalidAnimation)
+        );
+        // Literal run cut short
+        assert_eq!(
+            Animation::from_rle(&[1, 7, 1, 2, 3]).unwrap().frame_count(),
+            Err(Error::InvalidAnimation)
+        );
+        assert_eq!(Animation::from_rle(&[1]).unwrap().frame_count(), Ok(0));
+        let mut frame = [0; 16];
+        assert_eq!(
+            Animation::from_frames(&FRAMES).frame(0, &mut frame),
+            Err(Error::InvalidAnimation)
+        );
+    }
+
+    #[test]
+    fn test_invalid_module_count() {
+        assert_eq!(Animation::from_rle(&[]), Err(Error::InvalidAnimation));
+        // A zero-module frame would decode forever without consuming data
+        assert_eq!(
+            Animation::from_rle(&[0, REPEAT, 0xFF]),
+            Err(Error::InvalidAnimation)
+        );
+        assert_eq!(
+            Animation::from_rle(&[MAX_DISPLAYS as u8 + 1, REPEAT, 0]),
+            Err(Error::InvalidAnimation)
+        );
+        let mut cursor = AnimationCursor::start();
+        let zero = Animation {
+            frames: Frames::Rle {
+                modules: 0,
+                runs: &[REPEAT, 0xFF],
+            },
+        };
+        assert_eq!(
+            zero.decode_next(&mut cursor, &mut []),
+            Err(Error::InvalidAnimation)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Short animations confirming at startup that the display works
+//!
+//! Played once after `init()` with [`LedMatrix::boot_animation`], they show
+//! at a glance that every module is powered, chained and wired the right way
+//! round.
+//!
+//! [`LedMatrix::boot_animation`]: crate::led_matrix::LedMatrix::boot_animation
+
+/// Frames a logo stays fully visible after being revealed
+const LOGO_HOLD_FRAMES: usize = 8;
+
+/// Startup animation played by [`LedMatrix::boot_animation`].
+///
+/// Every animation ends with a blank display.
+///
+/// [`LedMatrix::boot_animation`]: crate::led_matrix::LedMatrix::boot_animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootAnimation {
+    /// A square grows from the centre of every module to its edges
+    ExpandingBox,
+    /// A lit column sweeps across the whole display from left to right
+    Sweep,
+    /// An 8x8 logo (bit 7 = leftmost pixel) is revealed row by row on every
+    /// module, then held for a moment
+    Logo(&'static [u8; 8]),
+}
+
+impl BootAnimation {
+    /// Time each frame is shown, in milliseconds
+    pub const STEP_MS: u32 = 60;
+
+    /// Returns the number of frames on a display `width` pixels wide.
+    pub fn frame_count(&self, width: usize) -> usize {
+        match self {
+            Self::ExpandingBox => 4,
+            Self::Sweep => width,
+            Self::Logo(_) => 8 + LOGO_HOLD_FRAMES,
+        }
+    }
+
+    /// Returns whether the logical pixel at (`x`, `y`) is lit in `frame`.
+    pub fn is_lit(&self, frame: usize, x: usize, y: usize) -> bool {
+        let (col, row) = (x % 8, y % 8);
+        match self {
+            Self::ExpandingBox => {
+                // Distance from the centre of the module, in half pixels
+                let distance =
+                    |v: usize| (v as isize).wrapping_mul(2).wrapping_sub(7).unsigned_abs();
+                frame.checked_mul(2).is_some_and(|double| {
+                    distance(col).max(distance(row)) == double.wrapping_add(1)
+                })
+            }
+            Self::Sweep => x == frame,
+            Self::Logo(rows) => {
+                row <= frame && rows.get(row).is_some_and(|data| data & (0x80 >> col) != 0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expanding_box() {
+        let animation = BootAnimation::ExpandingBox;
+        assert!(animation.is_lit(0, 3, 4) && animation.is_lit(0, 12, 11));
+        assert!(!animation.is_lit(0, 2, 4));
+        assert!(animation.is_lit(3, 0, 5) && animation.is_lit(3, 6, 7));
+        assert!(!animation.is_lit(3, 1, 6));
+    }
+
+    #[test]
+    fn test_sweep_and_logo() {
+        assert_eq!(BootAnimation::Sweep.frame_count(32), 32);
+        assert!(BootAnimation::Sweep.is_lit(9, 9, 0));
+        assert!(!BootAnimation::Sweep.is_lit(9, 8, 0));
+
+        static LOGO: [u8; 8] = [0x80, 0x40, 0, 0, 0, 0, 0, 0x01];
+        let logo = BootAnimation::Logo(&LOGO);
+        assert!(logo.is_lit(0, 0, 0) && logo.is_lit(0, 8, 0));
+        assert!(!logo.is_lit(0, 1, 1) && logo.is_lit(1, 1, 1));
+        assert!(logo.is_lit(15, 7, 7));
+    }
+}
@@ -1,4 +1,15 @@
 //! Matrix buffer for LED matrix operations
+//!
+//! Every constructor and accessor is a `const fn`, so icons and boot frames
+//! can be built at compile time and kept in flash:
+//!
+//! ```rust,ignore
+//! static BELL: MatrixBuffer = {
+//!     let mut bell = Symbol::Circle.to_buffer();
+//!     let _ = bell.set_row(7, 0b0001_1000);
+//!     bell
+//! };
+//! ```
 
 use crate::{Error, Result};
 
@@ -20,68 +31,88 @@ impl MatrixBuffer {
     }
 
     /// Get reference to buffer data
-    pub fn data(&self) -> &[u8; 8] {
+    pub const fn data(&self) -> &[u8; 8] {
         &self.data
     }
 
     /// Get mutable reference to buffer data
-    pub fn data_mut(&mut self) -> &mut [u8; 8] {
+    pub const fn data_mut(&mut self) -> &mut [u8; 8] {
         &mut self.data
     }
 
     /// Set a pixel in the buffer
-    pub fn set_pixel(&mut self, x: u8, y: u8, state: bool) -> Result<()> {
-        if x >= 8 || y >= 8 {
+    pub const fn set_pixel(&mut self, x: u8, y: u8, state: bool) -> Result<()> {
+        if x >= 8 {
             return Err(Error::BufferError);
         }
+        let Some(row) = self.row_mut(y) else {
+            return Err(Error::BufferError);
+        };
 
         let bit_mask = 1 << x;
         if state {
-            self.data[y as usize] |= bit_mask;
+            *row |= bit_mask;
         } else {
-            self.data[y as usize] &= !bit_mask;
+            *row &= !bit_mask;
         }
 
         Ok(())
     }
 
     /// Get pixel state from buffer
-    pub fn get_pixel(&self, x: u8, y: u8) -> Result<bool> {
-        if x >= 8 || y >= 8 {
+    pub const fn get_pixel(&self, x: u8, y: u8) -> Result<bool> {
+        if x >= 8 {
             return Err(Error::BufferError);
         }
+        let Some(row) = self.row(y) else {
+            return Err(Error::BufferError);
+        };
 
         let bit_mask = 1 << x;
-        Ok((self.data[y as usize] & bit_mask) != 0)
+        Ok((row & bit_mask) != 0)
     }
 
     /// Clear the entire buffer
-    pub fn clear(&mut self) {
+    pub const fn clear(&mut self) {
         self.data = [0; 8];
     }
 
     /// Fill the entire buffer
-    pub fn fill(&mut self) {
+    pub const fn fill(&mut self) {
         self.data = [0xFF; 8];
     }
 
     /// Set a row in the buffer
-    pub fn set_row(&mut self, row: u8, data: u8) -> Result<()> {
-        if row >= 8 {
+    pub const fn set_row(&mut self, row: u8, data: u8) -> Result<()> {
+        let Some(row) = self.row_mut(row) else {
             return Err(Error::BufferError);
-        }
-
-        self.data[row as usize] = data;
+        };
+        *row = data;
         Ok(())
     }
 
     /// Get a row from the buffer
-    pub fn get_row(&self, row: u8) -> Result<u8> {
-        if row >= 8 {
-            return Err(Error::BufferError);
+    pub const fn get_row(&self, row: u8) -> Result<u8> {
+        match self.row(row) {
+            Some(data) => Ok(data),
+            None => Err(Error::BufferError),
         }
+    }
 
-        Ok(self.data[row as usize])
+    /// Row `y`, or `None` past the bottom of the buffer
+    const fn row(&self, y: u8) -> Option<u8> {
+        match self.data.split_at_checked(y as usize) {
+            Some((_, [row, ..])) => Some(*row),
+            _ => None,
+        }
+    }
+
+    /// Mutable row `y`, or `None` past the bottom of the buffer
+    const fn row_mut(&mut self, y: u8) -> Option<&mut u8> {
+        match self.data.split_at_mut_checked(y as usize) {
+            Some((_, [row, ..])) => Some(row),
+            _ => None,
+        }
     }
 }
 
@@ -233,4 +264,18 @@ mod tests {
         assert_eq!(buffer.data()[0], 0b10101010);
         assert_eq!(buffer.data()[1], 0b01010101);
     }
+
+    #[test]
+    fn test_const_buffer() {
+        static FRAME: MatrixBuffer = {
+            let mut frame = MatrixBuffer::new();
+            let _ = frame.set_row(0, 0xF0);
+            let _ = frame.set_pixel(7, 7, true);
+            frame
+        };
+        const CORNER: Result<bool> = FRAME.get_pixel(7, 7);
+
+        assert_eq!(FRAME.data(), &[0xF0, 0, 0, 0, 0, 0, 0, 0x80]);
+        assert_eq!(CORNER, Ok(true));
+    }
 }
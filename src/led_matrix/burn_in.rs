@@ -0,0 +1,146 @@
+//! Burn-in mitigation by shifting the whole frame
+//!
+//! LEDs showing the same static content for months age unevenly. A
+//! [`PixelShift`] moves the displayed frame by a pixel or two every few
+//! minutes, so the lit pixels wander slightly. Like the heartbeat, the shift
+//! is applied when the matrix is flushed and never touches the framebuffer.
+
+/// Offsets visited in turn, scaled by the amplitude: the origin, then once
+/// around the surrounding square
+const ORBIT: [(i8, i8); 9] = [
+    (0, 0),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// Periodic offset of the whole frame, driven by [`PixelShift::tick`].
+///
+/// Content shifted past an edge is cut off, so keep a margin of
+/// `amplitude` pixels around static content.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // Move by one pixel every five minutes
+/// matrix.set_pixel_shift(Some(PixelShift::new(5 * 60 * 1000)));
+/// loop {
+///     matrix.tick_pixel_shift(100)?;
+///     delay.delay_ms(100);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelShift {
+    interval_ms: u32,
+    amplitude: u8,
+    elapsed_ms: u32,
+    step: usize,
+}
+
+impl PixelShift {
+    /// Shift by up to one pixel, moving on every `interval_ms`
+    pub const fn new(interval_ms: u32) -> Self {
+        Self {
+            interval_ms,
+            amplitude: 1,
+            elapsed_ms: 0,
+            step: 0,
+        }
+    }
+
+    /// Sets the largest shift in pixels along each axis
+    pub const fn with_amplitude(mut self, amplitude: u8) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Returns the time between moves in milliseconds.
+    pub fn interval_ms(&self) -> u32 {
+        self.interval_ms
+    }
+
+    /// Returns the largest shift in pixels along each axis.
+    pub fn amplitude(&self) -> u8 {
+        self.amplitude
+    }
+
+    /// Returns the current shift as `(dx, dy)` pixels.
+    pub fn offset(&self) -> (i32, i32) {
+        let (dx, dy) = ORBIT.get(self.step).copied().unwrap_or((0, 0));
+        let amplitude = i32::from(self.amplitude);
+        (
+            i32::from(dx).saturating_mul(amplitude),
+            i32::from(dy).saturating_mul(amplitude),
+        )
+    }
+
+    /// Advances the timer by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the offset changed and the display needs a flush.
+    /// An interval of 0 never moves.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        if self.interval_ms == 0 || self.amplitude == 0 {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+        if self.elapsed_ms < self.interval_ms {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.checked_rem(self.interval_ms).unwrap_or(0);
+        self.step = self
+            .step
+            .wrapping_add(1)
+            .checked_rem(ORBIT.len())
+            .unwrap_or(0);
+        true
+    }
+
+    /// Returns the content pixel shown at display pixel (`x`, `y`), or
+    /// `None` if the shift moved the display pixel past the content's edge.
+    pub fn source(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let (dx, dy) = self.offset();
+        let sx = (x as i32).saturating_sub(dx);
+        let sy = (y as i32).saturating_sub(dy);
+        (sx >= 0 && sy >= 0 && (sx as usize) < width && (sy as usize) < height)
+            .then_some((sx as usize, sy as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orbit() {
+        let mut shift = PixelShift::new(100).with_amplitude(2);
+        assert_eq!(shift.offset(), (0, 0));
+        assert!(!shift.tick(99));
+        assert!(shift.tick(1));
+        assert_eq!(shift.offset(), (2, 0));
+        for _ in 0..8 {
+            shift.tick(100);
+        }
+        assert_eq!(shift.offset(), (0, 0));
+        assert!(!PixelShift::new(0).tick(1000));
+    }
+
+    #[test]
+    fn test_source() {
+        let mut shift = PixelShift::new(1);
+        shift.tick(1);
+        assert_eq!(shift.source(0, 0, 8, 8), None);
+        assert_eq!(shift.source(1, 0, 8, 8), Some((0, 0)));
+        assert_eq!(shift.source(7, 7, 8, 8), Some((6, 7)));
+    }
+}
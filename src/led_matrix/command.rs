@@ -0,0 +1,222 @@
+//! Compact text commands, e.g. from MQTT payloads
+//!
+//! A gateway forwarding messages from a broker can hand each payload to
+//! [`LedMatrix::handle_command`] without a parser of its own. A payload is
+//! one command: an ASCII letter followed by its argument, so commands can be
+//! typed straight into `mosquitto_pub -m`.
+//!
+//! | Payload     | Effect                                              |
+//! |-------------|-----------------------------------------------------|
+//! | `T<text>`   | draws UTF-8 text, one character per module          |
+//! | `B<level>`  | sets the brightness of every device, `0` to `15`    |
+//! | `I<name>`   | clears the display and shows an icon top-left       |
+//! | `C`         | clears the display                                  |
+//!
+//! Icon names are `heart`, `smiley`, `sad`, `up`, `down`, `left`, `right`,
+//! `check`, `x`, `note` and `circle`, see [`Symbol`].
+//!
+//! ```rust,ignore
+//! mqtt.subscribe("panel/cmd")?;
+//! while let Some(message) = mqtt.next()? {
+//!     if let Err(error) = matrix.handle_command(message.payload()) {
+//!         log::warn!("bad command: {error}");
+//!     }
+//! }
+//! ```
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Brightness, Error, Result,
+    led_matrix::{LedMatrix, UpdateMode, symbols::Symbol},
+};
+
+/// Names of the icons of the `I` command
+const ICONS: [(&str, Symbol); 11] = [
+    ("heart", Symbol::Heart),
+    ("smiley", Symbol::Smiley),
+    ("sad", Symbol::SadFace),
+    ("up", Symbol::ArrowUp),
+    ("down", Symbol::ArrowDown),
+    ("left", Symbol::ArrowLeft),
+    ("right", Symbol::ArrowRight),
+    ("check", Symbol::Checkmark),
+    ("x", Symbol::XMark),
+    ("note", Symbol::MusicNote),
+    ("circle", Symbol::Circle),
+];
+
+/// A decoded command, see the [module documentation](self) for the encoding.
+#[derive(Debug, Clone, Copy)]
+pub enum Command<'a> {
+    /// Draw text, one character per module
+    Text(&'a str),
+    /// Set the brightness of every device
+    Brightness(Brightness),
+    /// Clear the display and show an icon in the top-left corner
+    Icon(Symbol),
+    /// Clear the display
+    Clear,
+}
+
+impl<'a> Command<'a> {
+    /// Decodes a payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidCommand` if the payload is empty, the command
+    /// letter is unknown or its argument is malformed, and
+    /// `Error::InvalidIntensity` for a brightness above 15.
+    pub fn parse(payload: &'a [u8]) -> Result<Self> {
+        let (&command, argument) = payload.split_first().ok_or(Error::InvalidCommand)?;
+        let argument = core::str::from_utf8(argument).map_err(|_| Error::InvalidCommand)?;
+        match command {
+            b'T' => Ok(Self::Text(argument)),
+            b'B' => {
+                let level = argument.parse().map_err(|_| Error::InvalidCommand)?;
+                Ok(Self::Brightness(Brightness::new(level)?))
+            }
+            b'I' => ICONS
+                .iter()
+                .find(|(name, _)| *name == argument)
+                .map(|&(_, symbol)| Self::Icon(symbol))
+                .ok_or(Error::InvalidCommand),
+            b'C' if argument.is_empty() => Ok(Self::Clear),
+            _ => Err(Error::InvalidCommand),
+        }
+    }
+}
+
+impl<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>
+    LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>
+where
+    SPI: SpiDevice,
+{
+    /// Decodes and runs one command, see the [`command`](self) module for
+    /// the encoding.
+    ///
+    /// Drawing commands follow the update mode like the methods they map
+    /// to, so call [`Self::flush`] afterwards in
+    /// [`UpdateMode::Buffered`](crate::led_matrix::UpdateMode::Buffered).
+    /// Brightness is always sent right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Command::parse`], or of the method the
+    /// command maps to.
+    pub fn handle_command(&mut self, payload: &[u8]) -> Result<()> {
+        match Command::parse(payload)? {
+            Command::Text(text) => self.draw_text(text),
+            Command::Brightness(brightness) => self.driver().set_intensity_all(brightness),
+            Command::Icon(symbol) => {
+                self.clear_buffer();
+                self.draw_glyph(0, 0, symbol.to_buffer().data())?;
+                if self.update_mode() == UpdateMode::WriteThrough {
+                    self.flush()?;
+                }
+                Ok(())
+            }
+            Command::Clear => self.clear_all(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, Register, led_matrix::display::SingleMatrix};
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    #[test]
+    fn test_parse() {
+        assert!(matches!(Command::parse(b"THi"), Ok(Command::Text("Hi"))));
+        assert!(matches!(
+            Command::parse(b"B7"),
+            Ok(Command::Brightness(brightness)) if brightness.level() == 7
+        ));
+        assert!(matches!(
+            Command::parse(b"Iheart"),
+            Ok(Command::Icon(Symbol::Heart))
+        ));
+        assert!(matches!(Command::parse(b"C"), Ok(Command::Clear)));
+
+        for payload in [&b""[..], b"B", b"Bx", b"Ihearts", b"Cx", b"Z", b"T\xFF"] {
+            assert!(matches!(
+                Command::parse(payload),
+                Err(Error::InvalidCommand)
+            ));
+        }
+        assert!(matches!(
+            Command::parse(b"B16"),
+            Err(Error::InvalidIntensity)
+        ));
+    }
+
+    #[test]
+    fn test_handle_command() {
+        let mut expected = Vec::new();
+        let mut write = |register: Register, data: u8| {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![register.addr(), data]));
+            expected.push(Transaction::transaction_end());
+        };
+        write(Register::Intensity, 3);
+        for (register, data) in Register::digits().zip(*Symbol::Heart.to_buffer().data()) {
+            write(register, data);
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut matrix = SingleMatrix::from_driver(Max7219::new(&mut spi)).unwrap();
+        matrix.handle_command(b"B3").unwrap();
+        matrix.handle_command(b"Iheart").unwrap();
+        assert_eq!(matrix.handle_command(b"?"), Err(Error::InvalidCommand));
+        spi.done();
+    }
+
+    #[test]
+    fn test_icon_on_leftmost_module() {
+        use crate::led_matrix::{UpdateMode, display::Matrix4};
+
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        matrix.handle_command(b"Iheart").unwrap();
+
+        let heart = Symbol::Heart.to_buffer();
+        for (y, row) in heart.data().iter().enumerate() {
+            for x in 0..32 {
+                let lit = x < 8 && row & (0x80 >> x) != 0;
+                assert_eq!(matrix.get_pixel(x, y).unwrap(), lit);
+            }
+        }
+        spi.done();
+    }
+
+    #[test]
+    fn test_icon_with_mapper() {
+        use crate::led_matrix::{TiledMapper, UpdateMode, display::Matrix4};
+
+        static GRID: TiledMapper = TiledMapper::new(2, 2).serpentine(true);
+
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_mapper(&GRID)
+            .unwrap();
+        matrix.fill_region(0, 0, 16, 16, true).unwrap();
+        matrix.handle_command(b"Iheart").unwrap();
+
+        let heart = Symbol::Heart.to_buffer();
+        for y in 0..16 {
+            for x in 0..16 {
+                let lit = x < 8 && y < 8 && heart.data()[y] & (0x80 >> x) != 0;
+                assert_eq!(matrix.get_pixel(x, y).unwrap(), lit);
+            }
+        }
+        spi.done();
+    }
+}
@@ -0,0 +1,358 @@
+//! 8x8xN LED cubes with one MAX7219 per layer
+//!
+//! Most hobby cubes stack 8x8 layers and drive each one with its own
+//! MAX7219, chained like the modules of a matrix. A [`CubeDriver`] keeps a
+//! 3D framebuffer and sends every layer to its device, so animations are
+//! drawn with voxel coordinates instead of per-device row bytes.
+//!
+//! `x` runs from left to right and `y` from back to front within a layer,
+//! matching the columns and rows of an 8x8 module; `z` runs from the bottom
+//! layer up.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{Error, MAX_DISPLAYS, Max7219, Register, Result, led_matrix::wiring::Wiring};
+
+/// Voxel position as (`x`, `y`, `z`)
+pub type Voxel = (usize, usize, usize);
+
+/// A cube of `LAYERS` stacked 8x8 layers, each driven by one device.
+///
+/// By default layer `z` is driven by device `z`, i.e. the bottom layer is
+/// furthest from the microcontroller, and every layer uses the FC-16
+/// wiring. Both can be changed per layer for cubes built the other way.
+///
+/// Drawing only changes the framebuffer; [`Self::flush`] sends it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut cube: CubeDriver<_> = CubeDriver::from_spi(spi)?;
+/// cube.draw_line((0, 0, 0), (7, 7, 7), true)?; // space diagonal
+/// cube.flush()?;
+/// ```
+pub struct CubeDriver<SPI, const LAYERS: usize = 8> {
+    driver: Max7219<SPI>,
+    /// Device driving each layer
+    devices: [usize; LAYERS],
+    /// How each layer is wired to its device
+    wiring: [Wiring; LAYERS],
+    /// Eight rows per layer, bit 7 = `x` 0
+    voxels: [[u8; 8]; LAYERS],
+}
+
+impl<SPI, const LAYERS: usize> CubeDriver<SPI, LAYERS>
+where
+    SPI: SpiDevice,
+{
+    /// Creates and initializes the driver for a chain of `LAYERS` devices.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `LAYERS` is not a valid device count or the
+    /// MAX7219 initialization fails.
+    pub fn from_spi(spi: SPI) -> Result<Self> {
+        let mut driver = Max7219::new(spi).with_device_count(LAYERS)?;
+        driver.init()?;
+        Self::from_driver(driver)
+    }
+
+    /// Creates a cube from an existing driver.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceCount` if the driver's device count is
+    /// not `LAYERS`.
+    pub fn from_driver(driver: Max7219<SPI>) -> Result<Self> {
+        if driver.device_count() != LAYERS {
+            return Err(Error::InvalidDeviceCount);
+        }
+        Ok(Self {
+            driver,
+            devices: core::array::from_fn(|z| z),
+            wiring: [Wiring::default(); LAYERS],
+            voxels: [[0; 8]; LAYERS],
+        })
+    }
+
+    /// Provides mutable access to the underlying MAX7219 driver.
+    pub fn driver(&mut self) -> &mut Max7219<SPI> {
+        &mut self.driver
+    }
+
+    /// Sets the device driving each layer, bottom layer first, consuming
+    /// and returning the cube.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidMapping` if a device is out of range or drives
+    /// more than one layer.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Chain wired from the top layer down
+    /// let cube = cube.with_layer_devices([7, 6, 5, 4, 3, 2, 1, 0])?;
+    /// ```
+    pub fn with_layer_devices(mut self, devices: [usize; LAYERS]) -> Result<Self> {
+        let mut used = [false; MAX_DISPLAYS];
+        for &device_index in devices.iter() {
+            let slot = used.get_mut(device_index).ok_or(Error::InvalidMapping)?;
+            if *slot || device_index >= LAYERS {
+                return Err(Error::InvalidMapping);
+            }
+            *slot = true;
+        }
+        self.devices = devices;
+        Ok(self)
+    }
+
+    /// Sets the same wiring for every layer, consuming and returning the cube.
+    pub fn with_wiring(mut self, wiring: Wiring) -> Self {
+        self.wiring = [wiring; LAYERS];
+        self
+    }
+
+    /// Sets the wiring of a single layer, e.g. one soldered rotated.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `z` is not a layer of the cube.
+    pub fn set_layer_wiring(&mut self, z: usize, wiring: Wiring) -> Result<()> {
+        let slot = self.wiring.get_mut(z).ok_or(Error::BufferError)?;
+        *slot = wiring;
+        Ok(())
+    }
+
+    /// Returns the size of the cube as (`x`, `y`, `z`).
+    pub fn size(&self) -> Voxel {
+        (8, 8, LAYERS)
+    }
+
+    /// Returns `true` if the voxel lies inside the cube.
+    fn contains(&self, (x, y, z): Voxel) -> bool {
+        x < 8 && y < 8 && z < LAYERS
+    }
+
+    /// Returns the row of the framebuffer holding a voxel.
+    fn row_mut(&mut self, (x, y, z): Voxel) -> Result<&mut u8> {
+        if x >= 8 {
+            return Err(Error::BufferError);
+        }
+        self.voxels
+            .get_mut(z)
+            .and_then(|layer| layer.get_mut(y))
+            .ok_or(Error::BufferError)
+    }
+
+    /// Sets a voxel in the framebuffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the voxel lies outside the cube.
+    pub fn set_voxel(&mut self, (x, y, z): Voxel, on: bool) -> Result<()> {
+        let row = self.row_mut((x, y, z))?;
+        let mask = 0x80 >> x;
+        if on {
+            *row |= mask;
+        } else {
+            *row &= !mask;
+        }
+        Ok(())
+    }
+
+    /// Returns the state of a voxel in the framebuffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the voxel lies outside the cube.
+    pub fn voxel(&self, (x, y, z): Voxel) -> Result<bool> {
+        if x >= 8 {
+            return Err(Error::BufferError);
+        }
+        let row = self
+            .voxels
+            .get(z)
+            .and_then(|layer| layer.get(y))
+            .ok_or(Error::BufferError)?;
+        Ok(row & (0x80 >> x) != 0)
+    }
+
+    /// Returns the eight rows of a layer (bit 7 = leftmost voxel).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `z` is not a layer of the cube.
+    pub fn layer(&self, z: usize) -> Result<&[u8; 8]> {
+        self.voxels.get(z).ok_or(Error::BufferError)
+    }
+
+    /// Replaces the eight rows of a layer (bit 7 = leftmost voxel), e.g.
+    /// with an 8x8 font glyph.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `z` is not a layer of the cube.
+    pub fn set_layer(&mut self, z: usize, rows: &[u8; 8]) -> Result<()> {
+        let layer = self.voxels.get_mut(z).ok_or(Error::BufferError)?;
+        *layer = *rows;
+        Ok(())
+    }
+
+    /// Switches every voxel of the framebuffer off.
+    pub fn clear(&mut self) {
+        self.voxels = [[0; 8]; LAYERS];
+    }
+
+    /// Draws a straight line between two voxels, both included.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if either end lies outside the cube.
+    pub fn draw_line(&mut self, from: Voxel, to: Voxel, on: bool) -> Result<()> {
+        if !self.contains(from) || !self.contains(to) {
+            return Err(Error::BufferError);
+        }
+
+        // Step along the longest axis, rounding the other two
+        let start = [from.0 as isize, from.1 as isize, from.2 as isize];
+        let delta = [
+            (to.0 as isize).saturating_sub(start[0]),
+            (to.1 as isize).saturating_sub(start[1]),
+            (to.2 as isize).saturating_sub(start[2]),
+        ];
+        let steps = delta.iter().map(|d| d.saturating_abs()).max().unwrap_or(0);
+        for step in 0..=steps {
+            let mut position = start;
+            for (coordinate, delta) in position.iter_mut().zip(delta) {
+                let rounded = delta
+                    .saturating_mul(step)
+                    .saturating_mul(2)
+                    .saturating_add(steps)
+                    .checked_div_euclid(steps.saturating_mul(2));
+                *coordinate = coordinate.saturating_add(rounded.unwrap_or(0));
+            }
+            let [x, y, z] = position;
+            self.set_voxel((x as usize, y as usize, z as usize), on)?;
+        }
+        Ok(())
+    }
+
+    /// Sends the framebuffer to the cube.
+    ///
+    /// Each digit register is written to every layer in one transaction, and
+    /// registers already showing the right data on every device are skipped.
+    pub fn flush(&mut self) -> Result<()> {
+        let mut images = [[0u8; 8]; LAYERS];
+        let layers = self.voxels.iter().zip(self.devices).zip(self.wiring);
+        for ((rows, device_index), wiring) in layers {
+            if let Some(image) = images.get_mut(device_index) {
+                *image = wiring.apply(rows);
+            }
+        }
+
+        for (digit, digit_register) in Register::digits().enumerate() {
+            let mut ops = [(Register::NoOp, 0); LAYERS];
+            let mut changed = false;
+            for (device_index, (op, image)) in ops.iter_mut().zip(&images).enumerate() {
+                let data = image.get(digit).copied().unwrap_or(0);
+                let state = self.driver.device_state(device_index)?;
+                changed |= state.register_value(digit_register) != data;
+                *op = (digit_register, data);
+            }
+            if changed {
+                self.driver.write_all_registers(&ops)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    #[test]
+    fn test_voxels() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut cube: CubeDriver<_, 2> = CubeDriver::from_driver(driver).unwrap();
+
+        assert_eq!(cube.size(), (8, 8, 2));
+        cube.set_voxel((1, 2, 1), true).unwrap();
+        assert_eq!(cube.voxel((1, 2, 1)), Ok(true));
+        assert_eq!(cube.layer(1).unwrap()[2], 0b0100_0000);
+        assert_eq!(cube.set_voxel((0, 0, 2), true), Err(Error::BufferError));
+        assert_eq!(
+            cube.draw_line((0, 0, 0), (8, 0, 0), true),
+            Err(Error::BufferError)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_from_driver_checks_layers() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        assert!(matches!(
+            CubeDriver::<_, 3>::from_driver(driver),
+            Err(Error::InvalidDeviceCount)
+        ));
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_line() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut cube: CubeDriver<_, 4> = CubeDriver::from_driver(driver).unwrap();
+
+        cube.draw_line((0, 7, 0), (6, 1, 3), true).unwrap();
+        for (x, y, z) in [(0, 7, 0), (1, 6, 1), (3, 4, 2), (6, 1, 3)] {
+            assert_eq!(cube.voxel((x, y, z)), Ok(true), "({x}, {y}, {z})");
+        }
+        let lit: u32 = (0..4)
+            .map(|z| {
+                cube.layer(z)
+                    .unwrap()
+                    .iter()
+                    .map(|row| row.count_ones())
+                    .sum::<u32>()
+            })
+            .sum();
+        assert_eq!(lit, 7);
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_remaps_layers() {
+        // Layer 0 is driven by device 1 and layer 1 by device 0
+        let mut expected = Vec::new();
+        for (digit, register) in Register::digits().enumerate() {
+            if digit == 0 || digit == 3 {
+                let (device0, device1) = if digit == 0 { (0, 0x80) } else { (0x01, 0) };
+                expected.push(Transaction::transaction_start());
+                expected.push(Transaction::write_vec(vec![
+                    register.addr(),
+                    device0,
+                    register.addr(),
+                    device1,
+                ]));
+                expected.push(Transaction::transaction_end());
+            }
+        }
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut cube: CubeDriver<_, 2> = CubeDriver::from_driver(driver)
+            .unwrap()
+            .with_layer_devices([1, 0])
+            .unwrap();
+        cube.set_voxel((0, 0, 0), true).unwrap();
+        cube.set_voxel((7, 3, 1), true).unwrap();
+        cube.flush().unwrap();
+        // Nothing changed
+        cube.flush().unwrap();
+        spi.done();
+    }
+}
@@ -0,0 +1,185 @@
+//! Per-region brightness emulation by temporal dithering
+//!
+//! The MAX7219 only has one intensity setting per chip, so a whole 8x8 module
+//! shares its brightness. A [`Dimmer`] makes parts of the display look dimmer
+//! by lighting their pixels on only some of the refreshes: a region with a
+//! duty of 2 out of [`DUTY_STEPS`] shows each lit pixel on a quarter of the
+//! frames. The pixels of a region take turns in a fixed pattern, so every
+//! frame shows some of them and the region does not visibly pulse.
+//!
+//! Like the heartbeat, dimming is applied when the matrix is flushed and
+//! never touches the framebuffer. The effect needs a steady refresh, see
+//! [`LedMatrix::tick_dimming`](crate::led_matrix::LedMatrix::tick_dimming).
+
+use crate::{Error, Result};
+
+/// Number of frames in a dithering cycle, and the duty of a fully lit region
+pub const DUTY_STEPS: u8 = 8;
+
+/// Maximum number of dimmed regions on a display
+pub const MAX_DIM_REGIONS: usize = 4;
+
+/// Frame offset of each pixel in a 4x2 tile, spreading the lit frames of
+/// neighbouring pixels as far apart as possible
+const ORDER: [[u8; 4]; 2] = [[0, 4, 2, 6], [5, 1, 7, 3]];
+
+/// Returns whether a pixel at (`x`, `y`) with a brightness of `duty` out of
+/// [`DUTY_STEPS`] is lit on dithering frame `frame`.
+///
+/// Over a cycle of [`DUTY_STEPS`] frames the pixel is lit `duty` times, and
+/// neighbouring pixels are lit on different frames.
+pub fn dither(x: usize, y: usize, frame: u8, duty: u8) -> bool {
+    let order = ORDER
+        .get(y % 2)
+        .and_then(|row| row.get(x % 4))
+        .copied()
+        .unwrap_or(0);
+    (frame % DUTY_STEPS).wrapping_add(order) % DUTY_STEPS < duty
+}
+
+/// A rectangle of the display shown at reduced brightness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimRegion {
+    /// Left edge in pixels
+    pub x: usize,
+    /// Top edge in pixels
+    pub y: usize,
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// Frames out of [`DUTY_STEPS`] on which lit pixels are shown
+    pub duty: u8,
+}
+
+impl DimRegion {
+    /// Create a region lit on `duty` out of [`DUTY_STEPS`] frames.
+    ///
+    /// Duties above [`DUTY_STEPS`] are treated as full brightness.
+    pub const fn new(x: usize, y: usize, width: usize, height: usize, duty: u8) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            duty,
+        }
+    }
+
+    /// Returns `true` if the region covers (`x`, `y`).
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x.saturating_add(self.width)).contains(&x)
+            && (self.y..self.y.saturating_add(self.height)).contains(&y)
+    }
+}
+
+/// Dimmed regions and the current dithering frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dimmer {
+    regions: [Option<DimRegion>; MAX_DIM_REGIONS],
+    frame: u8,
+}
+
+impl Dimmer {
+    /// Create a dimmer without regions
+    pub const fn new() -> Self {
+        Self {
+            regions: [None; MAX_DIM_REGIONS],
+            frame: 0,
+        }
+    }
+
+    /// Sets or removes the region in slot `index`.
+    ///
+    /// Where regions overlap, the one in the lowest slot wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `index` is not below [`MAX_DIM_REGIONS`].
+    pub fn set_region(&mut self, index: usize, region: Option<DimRegion>) -> Result<()> {
+        *self.regions.get_mut(index).ok_or(Error::BufferError)? = region;
+        Ok(())
+    }
+
+    /// Returns the region in slot `index`, if any.
+    pub fn region(&self, index: usize) -> Option<DimRegion> {
+        self.regions.get(index).copied().flatten()
+    }
+
+    /// Returns an iterator over the regions that are set.
+    pub fn regions(&self) -> impl Iterator<Item = DimRegion> + '_ {
+        self.regions.iter().flatten().copied()
+    }
+
+    /// Returns `true` if no region is set.
+    pub fn is_empty(&self) -> bool {
+        self.regions().next().is_none()
+    }
+
+    /// Removes all regions.
+    pub fn clear(&mut self) {
+        self.regions = [None; MAX_DIM_REGIONS];
+    }
+
+    /// Returns the current frame of the dithering cycle.
+    pub fn frame(&self) -> u8 {
+        self.frame
+    }
+
+    /// Moves on to the next frame of the dithering cycle.
+    pub fn advance(&mut self) {
+        self.frame = self.frame.wrapping_add(1) % DUTY_STEPS;
+    }
+
+    /// Applies dimming to a pixel: returns whether a pixel that is `lit`
+    /// in the content is shown at (`x`, `y`) in the current frame.
+    pub fn apply(&self, x: usize, y: usize, lit: bool) -> bool {
+        let Some(region) = self.regions().find(|region| region.contains(x, y)) else {
+            return lit;
+        };
+        lit && dither(x, y, self.frame, region.duty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duty_over_a_cycle() {
+        let mut dimmer = Dimmer::new();
+        dimmer
+            .set_region(0, Some(DimRegion::new(0, 0, 4, 2, 2)))
+            .unwrap();
+        assert!(dimmer.set_region(MAX_DIM_REGIONS, None).is_err());
+
+        let mut shown = [[0u8; 4]; 2];
+        for _ in 0..DUTY_STEPS {
+            for (y, row) in shown.iter_mut().enumerate() {
+                for (x, count) in row.iter_mut().enumerate() {
+                    *count += dimmer.apply(x, y, true) as u8;
+                }
+            }
+            // A quarter of the pixels is lit in every frame
+            let lit = (0..8).filter(|i| dimmer.apply(i % 4, i / 4, true)).count();
+            assert_eq!(lit, 2);
+            dimmer.advance();
+        }
+        assert_eq!(shown, [[2; 4]; 2]);
+    }
+
+    #[test]
+    fn test_outside_and_dark_pixels() {
+        let mut dimmer = Dimmer::new();
+        dimmer
+            .set_region(0, Some(DimRegion::new(0, 0, 1, 1, 0)))
+            .unwrap();
+        assert!(!dimmer.apply(0, 0, true));
+        assert!(dimmer.apply(1, 0, true));
+        assert!(!dimmer.apply(1, 0, false));
+
+        dimmer.clear();
+        assert!(dimmer.is_empty());
+        assert!(dimmer.apply(0, 0, true));
+    }
+}
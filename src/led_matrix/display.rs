@@ -1,13 +1,31 @@
 //! LED matrix display implementation
 
+use core::ops::Range;
+
 use embedded_hal::{delay::DelayNs, spi::SpiDevice};
 
 use crate::{
-    Error, MAX_DISPLAYS, Max7219, Register, Result,
+    Brightness, Error, FrameSink, MAX_DISPLAYS, Max7219, Register, Result, SinkSpi,
+    chain::{ChainLayout, DeviceKind, Mounting},
+    easing::PROGRESS_END,
     led_matrix::{
+        animation::{Animation, AnimationCursor},
+        boot::BootAnimation,
         buffer::MatrixBuffer,
+        burn_in::PixelShift,
+        dimming::{DUTY_STEPS, DimRegion, Dimmer},
+        flush::FlushStateMachine,
         fonts::{self, LedFont},
+        games::{self, CardRank},
+        heartbeat::Heartbeat,
+        layer::Layer,
+        mapping::{PixelAddress, PixelMapper},
+        patterns::TestPattern,
         scroll::{ScrollConfig, ScrollingText},
+        strip::{self, GlyphTransform},
+        text::{Align, TextFit, TextStyle},
+        transition::Wipe,
+        wiring::Wiring,
     },
 };
 
@@ -20,23 +38,144 @@ pub type Matrix4<SPI> = LedMatrix<SPI, 256, 4>;
 /// Represents an 8-in-1 LED matrix module (total 8x64 pixels) using eight chained MAX7219 devices.
 pub type Matrix8<SPI> = LedMatrix<SPI, 512, 8>;
 
+/// Calls a user-supplied pixel mapper or frame sink.
+///
+/// With the `panic-free` feature the call crosses a non-unwinding boundary,
+/// so a panicking hook aborts instead of unwinding through the matrix. Kept
+/// out of line so callers are still known not to unwind.
+#[cfg(feature = "panic-free")]
+#[allow(improper_ctypes_definitions)]
+#[inline(never)]
+extern "C" fn call_hook<R>(hook: impl FnOnce() -> R) -> R {
+    hook()
+}
+
+/// Calls a user-supplied pixel mapper or frame sink.
+#[cfg(not(feature = "panic-free"))]
+fn call_hook<R>(hook: impl FnOnce() -> R) -> R {
+    hook()
+}
+
+/// Moves the span of `len` pixels at `start` by `delta`, clipped to `0..limit`.
+fn shift_span(start: usize, len: usize, delta: i32, limit: usize) -> Range<usize> {
+    let clip = |position: i32| (position.max(0) as usize).min(limit);
+    let start = (start as i32).saturating_add(delta);
+    clip(start)..clip(start.saturating_add(len as i32))
+}
+
+/// Controls when drawing calls reach the hardware.
+///
+/// The mode can be changed at any time with [`LedMatrix::set_update_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateMode {
+    /// Drawing calls are written to the chip immediately.
+    ///
+    /// This is the simplest mode and works well for scripts that draw a
+    /// character or a line of text and move on.
+    #[default]
+    WriteThrough,
+    /// Drawing calls only update the framebuffer.
+    ///
+    /// Nothing is sent until [`LedMatrix::flush`] is called, so a whole
+    /// animation frame can be composed first and pushed out in one go.
+    Buffered,
+}
+
+/// Order in which a flush sends the digit registers of the chain.
+///
+/// Long chains take a moment to update, and the part already written shows
+/// the new frame while the rest still shows the old one. The order decides
+/// where that brief shearing appears.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushOrder {
+    /// One row of every device per SPI transaction, top to bottom.
+    ///
+    /// Needs the fewest transactions; all devices change together, one row
+    /// at a time, and a row never mixes old and new data across devices.
+    #[default]
+    RowMajor,
+    /// All rows of one device after another, furthest device first.
+    ///
+    /// Each device changes in one short burst, so content moving across
+    /// the chain does not tear inside a module.
+    DeviceMajor,
+}
+
+/// A rectangle of logical pixels that drawing is limited to.
+///
+/// See [`LedMatrix::set_clip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClipRect {
+    /// Left edge
+    pub x: usize,
+    /// Top edge
+    pub y: usize,
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+}
+
+impl ClipRect {
+    /// Returns `true` if the pixel at (`x`, `y`) lies inside the rectangle.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x.saturating_add(self.width)).contains(&x)
+            && (self.y..self.y.saturating_add(self.height)).contains(&y)
+    }
+}
+
 /// A high-level abstraction for controlling an LED matrix display using the MAX7219 driver.
-pub struct LedMatrix<SPI, const BUFFER_LENGTH: usize = 64, const DEVICE_COUNT: usize = 1> {
+///
+/// `LAYERS` overlay [`Layer`]s can be added on top of the framebuffer; they
+/// are composited at flush time and cost no memory when left at the default
+/// of zero.
+pub struct LedMatrix<
+    SPI,
+    const BUFFER_LENGTH: usize = 64,
+    const DEVICE_COUNT: usize = 1,
+    const LAYERS: usize = 0,
+> {
     driver: Max7219<SPI>,
+    /// Whether drawing calls write to the chip or only to the framebuffer
+    mode: UpdateMode,
+    /// How each device (by driver index) is wired to its 8x8 matrix
+    wiring: [Wiring; DEVICE_COUNT],
+    /// Kind of each device, if the chain is not made of matrices only
+    layout: Option<ChainLayout>,
+    /// Optional mapping of logical pixels onto the chain, replacing `wiring`
+    mapper: Option<&'static dyn PixelMapper>,
+    /// Optional blinking indicator drawn over the framebuffer at flush time
+    heartbeat: Option<Heartbeat>,
+    /// Regions dimmed by temporal dithering at flush time
+    dimmer: Dimmer,
+    /// Optional burn-in shift of the whole frame, applied at flush time
+    pixel_shift: Option<PixelShift>,
+    /// Rectangle that framebuffer drawing is limited to, if any
+    clip: Option<ClipRect>,
+    /// Order in which flushes send the digit registers
+    flush_order: FlushOrder,
+    /// Whether devices are shut down while a flush writes several rows
+    blank_during_flush: bool,
+    /// Overlay layers composited over the framebuffer, bottom to top
+    layers: [Layer<BUFFER_LENGTH>; LAYERS],
     /// The framebuffer with one `u8` per pixel (0 = off, non-zero = on).
     ///
     /// Each 8x8 display has 64 pixels. For `N` daisy-chained devices,
     /// the total framebuffer size is `N * 64` pixels.
     ///
+    /// Pixels are stored in logical 8x8 tiles, left to right and top to
+    /// bottom; inside a tile they are stored row by row.
+    ///
     /// For example, with 4 devices: `4 * 64 = 256` pixels.
     ///
     /// This buffer is modified by `embedded-graphics` through the
-    /// [`DrawTarget`](https://docs.rs/embedded-graphics-core/latest/embedded_graphics_core/draw_target/trait.DrawTarget.html) trait.
+    /// [`DrawTarget`](https://docs.rs/embedded-graphics-core/latest/embedded_graphics_core/draw_target/trait.DrawTarget.html) trait,
+    /// and by the drawing methods in both update modes.
     framebuffer: [u8; BUFFER_LENGTH],
 }
 
-impl<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize>
-    LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT>
+impl<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>
+    LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>
 where
     SPI: SpiDevice,
 {
@@ -65,6 +204,17 @@ where
         driver.init()?;
         Ok(Self {
             driver,
+            mode: UpdateMode::default(),
+            wiring: [Wiring::default(); DEVICE_COUNT],
+            layout: None,
+            mapper: None,
+            heartbeat: None,
+            dimmer: Dimmer::new(),
+            pixel_shift: None,
+            clip: None,
+            flush_order: FlushOrder::RowMajor,
+            blank_during_flush: false,
+            layers: [Layer::new(DEVICE_COUNT.saturating_mul(8), 8); LAYERS],
             framebuffer: [0; BUFFER_LENGTH],
         })
     }
@@ -105,6 +255,17 @@ where
         }
         Ok(Self {
             driver,
+            mode: UpdateMode::default(),
+            wiring: [Wiring::default(); DEVICE_COUNT],
+            layout: None,
+            mapper: None,
+            heartbeat: None,
+            dimmer: Dimmer::new(),
+            pixel_shift: None,
+            clip: None,
+            flush_order: FlushOrder::RowMajor,
+            blank_during_flush: false,
+            layers: [Layer::new(DEVICE_COUNT.saturating_mul(8), 8); LAYERS],
             framebuffer: [0; BUFFER_LENGTH],
         })
     }
@@ -116,729 +277,4084 @@ where
         &mut self.driver
     }
 
-    /// Clear a specific device
-    pub fn clear(&mut self, device_index: usize) -> Result<()> {
-        self.driver.clear_display(device_index)
+    /// Sets the update mode, consuming and returning the matrix.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut matrix = Matrix4::from_spi(spi)?.with_update_mode(UpdateMode::Buffered);
+    /// ```
+    pub fn with_update_mode(mut self, mode: UpdateMode) -> Self {
+        self.mode = mode;
+        self
     }
 
-    /// Clear all device
-    pub fn clear_all(&mut self) -> Result<()> {
-        self.driver.clear_all()
+    /// Switches between write-through and buffered drawing at runtime.
+    ///
+    /// The framebuffer is kept up to date in both modes, so switching from
+    /// [`UpdateMode::WriteThrough`] to [`UpdateMode::Buffered`] keeps what is
+    /// on screen, and switching back does not flush pending changes by itself.
+    pub fn set_update_mode(&mut self, mode: UpdateMode) {
+        self.mode = mode;
     }
 
-    /// Write a complete buffer to a specific display
-    pub fn write_buffer(&mut self, device_index: usize, buffer: &MatrixBuffer) -> Result<()> {
-        for (row, &data) in buffer.data().iter().enumerate() {
-            self.driver.write_raw_digit(device_index, row as u8, data)?;
-        }
-        Ok(())
+    /// Sets the order in which flushes send the digit registers.
+    pub fn with_flush_order(mut self, order: FlushOrder) -> Self {
+        self.flush_order = order;
+        self
     }
 
-    /// Draws a single 8x8 character on the specified display device.
+    /// Changes the order in which flushes send the digit registers.
+    pub fn set_flush_order(&mut self, order: FlushOrder) {
+        self.flush_order = order;
+    }
+
+    /// Returns the order in which flushes send the digit registers.
+    pub fn flush_order(&self) -> FlushOrder {
+        self.flush_order
+    }
+
+    /// Shuts the devices down while a flush writes more than one row, so a
+    /// large update appears at once instead of row by row.
     ///
-    /// The character is converted into an 8-byte bitmap using a predefined font.
-    /// If the character is unsupported, it will be replaced with "?" char.
+    /// Devices are only blanked if they are powered on, and are powered on
+    /// again when the flush is done, even if it failed. The display is dark for the duration
+    /// of the update, which is usually shorter than a refresh cycle but can
+    /// flicker on long chains with a slow SPI bus.
+    pub fn with_blank_during_flush(mut self, blank: bool) -> Self {
+        self.blank_during_flush = blank;
+        self
+    }
+
+    /// Changes whether devices are shut down during multi-row flushes.
+    pub fn set_blank_during_flush(&mut self, blank: bool) {
+        self.blank_during_flush = blank;
+    }
+
+    /// Returns whether devices are shut down during multi-row flushes.
+    pub fn blank_during_flush(&self) -> bool {
+        self.blank_during_flush
+    }
+
+    /// Returns the current update mode.
+    pub fn update_mode(&self) -> UpdateMode {
+        self.mode
+    }
+
+    /// Sets the same wiring for every device, consuming and returning the matrix.
     ///
-    /// Each byte in the bitmap corresponds to one row of the 8x8 LED matrix (from D0 to D7),
-    /// and is written to the digit registers of the specified `device_index`.
+    /// # Example
     ///
-    /// # Arguments
-    /// * `device_index` - Index of the target MAX7219 device in the daisy chain.
-    /// * `c` - The character to display.
+    /// ```rust,ignore
+    /// // Generic modules that drive one column per digit register
+    /// let mut matrix = SingleMatrix::from_spi(spi)?.with_wiring(Wiring::GENERIC);
+    /// ```
+    pub fn with_wiring(mut self, wiring: Wiring) -> Self {
+        self.wiring = [wiring; DEVICE_COUNT];
+        self
+    }
+
+    /// Sets the wiring of a single device in the chain.
+    ///
+    /// The wiring is applied whenever the device's pixels are sent to the chip,
+    /// so modules of different kinds can be mixed in one chain.
     ///
     /// # Errors
-    /// Returns an error if the digit conversion fails or if SPI communication fails.
-    pub fn draw_char(&mut self, device_index: usize, ch: char) -> Result<()> {
-        self.draw_char_with_font(device_index, ch, &fonts::STANDARD_LED_FONT)
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    pub fn set_device_wiring(&mut self, device_index: usize, wiring: Wiring) -> Result<()> {
+        let slot = self
+            .wiring
+            .get_mut(device_index)
+            .ok_or(Error::InvalidDeviceIndex)?;
+        *slot = wiring;
+        Ok(())
     }
 
-    /// Draws a single 8x8 character on the specified display device using a provided font.
+    /// Declares the kind and mounting of every device, consuming and
+    /// returning the matrix.
     ///
-    /// This function is similar to [`Self::draw_char`], but allows overriding the font used for rendering.
-    /// The character is mapped to an 8-byte bitmap. Each byte represents a row on the matrix, with
-    /// the most significant bit (bit 7) on the left and the least significant bit (bit 0) on the right.
+    /// Only the matrix devices of the layout are drawn: flushes send no-ops
+    /// to the others, and setting a pixel or addressing a device that is not
+    /// a matrix returns `Error::WrongDeviceKind`. Their pixels stay in the
+    /// framebuffer, so the logical size of the display doesn't change. A
+    /// matrix mounted upside down gets its wiring rotated by 180 degrees.
     ///
-    /// # Arguments
-    /// * `device_index` - Index of the MAX7219 device to write to.
-    /// * `ch` - The character to render on the display.
-    /// * `font` - The font to use for character lookup and rendering.
+    /// # Errors
     ///
-    pub fn draw_char_with_font(
-        &mut self,
-        device_index: usize,
-        ch: char,
-        font: &LedFont,
-    ) -> Result<()> {
-        let bitmap = font.get_char(ch);
-        // self.driver.draw_bitmap(bitmap, pos);
-        for (row, value) in bitmap.iter().enumerate() {
-            self.driver
-                .write_raw_digit(device_index, row as u8, *value)?;
+    /// Returns `Error::InvalidDeviceCount` if the layout doesn't describe
+    /// exactly `DEVICE_COUNT` devices.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // A 7-segment module at the far end of a chain of three matrices
+    /// let layout = ChainLayout::new(&[SevenSegment, Matrix, Matrix, Matrix])?;
+    /// let mut matrix = Matrix4::from_spi(spi)?.with_layout(layout)?;
+    /// ```
+    pub fn with_layout(mut self, layout: ChainLayout) -> Result<Self> {
+        if layout.len() != DEVICE_COUNT {
+            return Err(Error::InvalidDeviceCount);
         }
-        Ok(())
+        for (device_index, wiring) in self.wiring.iter_mut().enumerate() {
+            if layout.mounting(device_index)? == Mounting::UpsideDown {
+                *wiring = wiring.rotated();
+            }
+        }
+        self.layout = Some(layout);
+        Ok(self)
     }
 
-    /// Draw a string of text on the LED matrix using the default font.
-    /// Each character is displayed on one device in the daisy chain.
-    /// If the string is longer than the number of devices, the extra characters are ignored.
-    pub fn draw_text(&mut self, text: &str) -> Result<()> {
-        self.draw_text_with_font(text, &fonts::STANDARD_LED_FONT)
+    /// Returns the chain layout set with [`Self::with_layout`], if any.
+    pub fn layout(&self) -> Option<&ChainLayout> {
+        self.layout.as_ref()
     }
 
-    /// Draw a string of text on the LED matrix using a specified font.
-    /// Each character is displayed on one device in the daisy chain.
-    /// If the string is longer than the number of devices, the extra characters are ignored.
-    pub fn draw_text_with_font(&mut self, text: &str, font: &LedFont) -> Result<()> {
-        let device_count = self.driver.device_count();
+    /// Checks that a device drives a matrix.
+    fn require_matrix(&self, device_index: usize) -> Result<()> {
+        match self.layout {
+            Some(layout) => layout.require(device_index, DeviceKind::Matrix),
+            None if device_index < DEVICE_COUNT => Ok(()),
+            None => Err(Error::InvalidDeviceIndex),
+        }
+    }
 
-        let mut row_data = [[0u8; MAX_DISPLAYS]; 8];
+    /// Whether each device drives a matrix.
+    pub(crate) fn matrix_devices(&self) -> [bool; DEVICE_COUNT] {
+        core::array::from_fn(|device_index| self.require_matrix(device_index).is_ok())
+    }
 
-        for (device_index, ch) in text.chars().take(device_count).enumerate() {
-            let bitmap = font.get_char(ch);
-            for (row, &value) in bitmap.iter().enumerate() {
-                row_data[row][device_index] = value;
-            }
+    /// Replaces the default chain layout with a custom pixel mapper.
+    ///
+    /// Once a mapper is set it alone decides which LED shows each logical
+    /// pixel, and the per-device wiring is ignored. The logical size of the
+    /// display becomes the mapper's size, which allows grids of modules.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidMapping` if the mapper's size is not made of
+    /// whole 8x8 tiles or does not cover exactly `DEVICE_COUNT` modules.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// static GRID: TiledMapper = TiledMapper::new(2, 2).serpentine(true);
+    /// let mut matrix = Matrix4::from_spi(spi)?.with_mapper(&GRID)?; // 16x16 pixels
+    /// ```
+    pub fn with_mapper(mut self, mapper: &'static dyn PixelMapper) -> Result<Self> {
+        let (width, height) = call_hook(|| mapper.size(DEVICE_COUNT));
+        if width % 8 != 0
+            || height % 8 != 0
+            || width.checked_mul(height) != DEVICE_COUNT.checked_mul(64)
+        {
+            return Err(Error::InvalidMapping);
+        }
+        self.mapper = Some(mapper);
+        for layer in self.layers.iter_mut() {
+            layer.reset(width, height);
         }
+        Ok(self)
+    }
 
-        // Each digit_register targets the same row index (0 to 7) in every device.
-        // Example: if digit_register = Digit3 and device_count = 2,
-        // then ops will look like:
-        //     ops = [
-        //         (Digit3, row_data[3][0]), // device 1 (farthest), row 3
-        //         (Digit3, row_data[3][1]), // device 0 (nearest), row 3
-        //     ];
-        for (row_index, digit_register) in Register::digits().enumerate() {
-            let ops_row = row_data[row_index];
-            let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+    /// Returns the logical width of the display in pixels.
+    pub fn width(&self) -> usize {
+        self.size().0
+    }
 
-            for (device_index, op) in ops.iter_mut().take(device_count).enumerate() {
-                *op = (digit_register, ops_row[device_index]);
-            }
+    /// Returns the logical height of the display in pixels.
+    pub fn height(&self) -> usize {
+        self.size().1
+    }
 
-            self.driver.write_all_registers(&ops[..device_count])?;
+    fn size(&self) -> (usize, usize) {
+        match self.mapper {
+            Some(mapper) => call_hook(|| mapper.size(DEVICE_COUNT)),
+            None => (DEVICE_COUNT.saturating_mul(8), 8),
         }
+    }
 
-        Ok(())
+    /// Framebuffer index of the logical pixel at (`x`, `y`).
+    fn pixel_index(&self, x: usize, y: usize) -> Option<usize> {
+        let (width, height) = self.size();
+        if x >= width || y >= height {
+            return None;
+        }
+        // Both fit in the framebuffer, so the index cannot overflow
+        let tile = (y / 8).wrapping_mul(width / 8).wrapping_add(x / 8);
+        let index = tile
+            .wrapping_mul(64)
+            .wrapping_add((y % 8).wrapping_mul(8))
+            .wrapping_add(x % 8);
+        (index < self.framebuffer.len()).then_some(index)
     }
 
-    /// Scroll the given text across the LED matrix.
-    ///
-    /// This will render `text` using the current font and step through
-    /// each frame at the delay specified by `config.step_delay_ns`. If
-    /// `config.loop_text` is true, the text will repeat with
-    /// `config.loop_padding` pixels of blank space between repetitions.
+    /// Framebuffer index of the logical pixel at (`x`, `y`) if it may be
+    /// drawn, i.e. it lies inside the clip rectangle.
+    fn drawable_index(&self, x: usize, y: usize) -> Option<usize> {
+        if self.clip.is_some_and(|clip| !clip.contains(x, y)) {
+            return None;
+        }
+        self.pixel_index(x, y)
+    }
+
+    /// Framebuffer entry of the logical pixel at (`x`, `y`), or `None`
+    /// outside the display.
+    fn pixel(&self, x: usize, y: usize) -> Option<u8> {
+        self.framebuffer.get(self.pixel_index(x, y)?).copied()
+    }
+
+    /// Mutable framebuffer entry of the logical pixel at (`x`, `y`) if it
+    /// may be drawn.
+    fn drawable_pixel(&mut self, x: usize, y: usize) -> Option<&mut u8> {
+        let index = self.drawable_index(x, y)?;
+        self.framebuffer.get_mut(index)
+    }
+
+    /// Limits framebuffer drawing to the rectangle at (`x`, `y`) with the
+    /// given width and height.
     ///
+    /// Pixel, region, line, glyph, text layout and `embedded-graphics`
+    /// drawing outside the rectangle is silently dropped, and
+    /// [`Self::clear_buffer`] and [`Self::invert`] only touch the inside. This
+    /// keeps widgets sharing one framebuffer from drawing over each other.
+    /// Methods addressing whole devices, such as [`Self::draw_char`] and
+    /// [`Self::write_buffer`], and whole frames, such as
+    /// [`Self::set_framebuffer`], are not clipped.
     ///
-    /// # Parameters
+    /// # Errors
     ///
-    /// - `delay`: delay provider implementing `embedded_hal::delay::DelayNs`.
-    /// - `text`: the string slice to scroll.
-    /// - `config`: scrolling configuration (speed, step size, looping).
+    /// Returns `Error::BufferError` if the rectangle extends past the display.
     ///
-    /// # Errors
+    /// # Example
     ///
-    /// Returns a `MatrixError` if updating the display buffer fails.
-    pub fn scroll_text<D: DelayNs>(
-        &mut self,
-        delay: &mut D,
-        text: &str,
-        config: ScrollConfig,
-    ) -> Result<()> {
-        let mut scroller = ScrollingText::new(text, &fonts::STANDARD_LED_FONT, config);
-        scroller.reset();
+    /// ```rust,ignore
+    /// matrix.set_clip(0, 0, 16, 8)?;
+    /// clock.draw(&mut matrix)?; // cannot touch columns 16 and up
+    /// matrix.clear_clip();
+    /// ```
+    pub fn set_clip(&mut self, x: usize, y: usize, width: usize, height: usize) -> Result<()> {
+        let (display_width, display_height) = self.size();
+        if x.saturating_add(width) > display_width || y.saturating_add(height) > display_height {
+            return Err(Error::BufferError);
+        }
+        self.clip = Some(ClipRect {
+            x,
+            y,
+            width,
+            height,
+        });
+        Ok(())
+    }
 
-        let device_count = self.driver().device_count();
+    /// Removes the clip rectangle, so the whole display can be drawn on.
+    pub fn clear_clip(&mut self) {
+        self.clip = None;
+    }
 
-        loop {
-            // Store the original offset
-            let base_offset = scroller.current_offset;
+    /// Returns the clip rectangle, if any.
+    pub fn clip(&self) -> Option<ClipRect> {
+        self.clip
+    }
 
-            // Update each display device
-            for device_index in 0..device_count {
-                // Set offset for this specific device
-                // Each device shows 8 pixels, so device N shows pixels at offset + (N * 8)
-                scroller.current_offset = base_offset + (device_index as i32 * 8);
+    /// Runs `draw` with drawing limited to the given rectangle, then
+    /// restores the previous clip rectangle.
+    ///
+    /// The rectangle is intersected with the current clip, so nested calls
+    /// can only narrow the drawable area.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the rectangle extends past the display,
+    /// or the error returned by `draw`.
+    pub fn draw_clipped<R>(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        draw: impl FnOnce(&mut Self) -> Result<R>,
+    ) -> Result<R> {
+        let previous = self.clip;
+        self.set_clip(x, y, width, height)?;
+        if let (Some(outer), Some(inner)) = (previous, self.clip.as_mut()) {
+            let left = inner.x.max(outer.x);
+            let top = inner.y.max(outer.y);
+            let right =
+                (inner.x.saturating_add(inner.width)).min(outer.x.saturating_add(outer.width));
+            let bottom =
+                (inner.y.saturating_add(inner.height)).min(outer.y.saturating_add(outer.height));
+            *inner = ClipRect {
+                x: left,
+                y: top,
+                width: right.saturating_sub(left),
+                height: bottom.saturating_sub(top),
+            };
+        }
+        let result = draw(self);
+        self.clip = previous;
+        result
+    }
 
-                let frame = scroller.get_frame()?; // Each device shows 8 pixels width
-                self.write_buffer(device_index, &frame)?;
+    /// Hardware location of the logical pixel at (`x`, `y`).
+    fn locate(&self, x: usize, y: usize) -> Option<PixelAddress> {
+        match self.mapper {
+            Some(mapper) => call_hook(|| mapper.map(x, y, DEVICE_COUNT)),
+            None => {
+                let device = DEVICE_COUNT.checked_sub(1)?.checked_sub(x / 8)?;
+                if y >= 8 {
+                    return None;
+                }
+                let (digit, bit) = self
+                    .device_wiring(device)
+                    .unwrap_or_default()
+                    .locate((x % 8) as u8, y as u8);
+                Some(PixelAddress { device, digit, bit })
             }
+        }
+    }
 
-            // Restore the original offset and step to next position
-            scroller.current_offset = base_offset;
+    /// Returns the logical position of the pixel driven by a hardware LED,
+    /// or `None` if no logical pixel maps to it.
+    ///
+    /// This is the inverse of the current mapping and searches the whole
+    /// display, so it is meant for diagnostics rather than drawing.
+    pub fn logical_position(&self, address: PixelAddress) -> Option<(usize, usize)> {
+        let (width, height) = self.size();
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .find(|&(x, y)| self.locate(x, y) == Some(address))
+    }
 
-            if !scroller.step() {
-                break; // Stop if not looping and text has finished scrolling
+    /// Checks that the current mapping drives every LED of the chain from
+    /// exactly one logical pixel.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidMapping` if a logical pixel has no LED, two
+    /// pixels share an LED, or an LED is left without a pixel.
+    pub fn verify_mapping(&self) -> Result<()> {
+        let mut seen = [0u64; MAX_DISPLAYS];
+        let (width, height) = self.size();
+        for y in 0..height {
+            for x in 0..width {
+                let address = self.locate(x, y).ok_or(Error::InvalidMapping)?;
+                let mask = seen
+                    .get_mut(address.device)
+                    .filter(|_| {
+                        address.device < DEVICE_COUNT && address.digit < 8 && address.bit < 8
+                    })
+                    .ok_or(Error::InvalidMapping)?;
+                // Both are below 8, checked above
+                let bit = 1u64 << (address.digit.wrapping_mul(8).wrapping_add(address.bit));
+                if *mask & bit != 0 {
+                    return Err(Error::InvalidMapping);
+                }
+                *mask |= bit;
             }
-
-            delay.delay_ns(config.step_delay_ns);
         }
+        if seen.iter().take(DEVICE_COUNT).all(|&mask| mask == u64::MAX) {
+            Ok(())
+        } else {
+            Err(Error::InvalidMapping)
+        }
+    }
 
+    /// Sets a pixel in the framebuffer using logical coordinates.
+    ///
+    /// The change reaches the display on the next flush. A pixel outside the
+    /// clip rectangle is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the pixel lies outside the display and
+    /// `Error::WrongDeviceKind` if it is shown by a device the chain layout
+    /// declares as something other than a matrix.
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) -> Result<()> {
+        self.pixel_index(x, y).ok_or(Error::BufferError)?;
+        if self.layout.is_some()
+            && let Some(address) = self.locate(x, y)
+        {
+            self.require_matrix(address.device)?;
+        }
+        if let Some(pixel) = self.drawable_pixel(x, y) {
+            *pixel = on as u8;
+        }
         Ok(())
     }
 
-    /// Flush the internal display buffer to the actual LED matrix hardware.
+    /// Returns the state of a pixel in the framebuffer using logical coordinates.
     ///
-    /// This function goes row by row (0 to 7), and for each row, it builds an array of
-    /// SPI operations (`ops`) to send to all devices in the daisy-chained display.
-    /// It packs each row of pixels into a single byte for each device, then sends the data
-    /// using the driver's `write_all_registers` method.
+    /// # Errors
     ///
-    /// ### Example logic (DEVICE_COUNT = 2, row = 0):
-    /// Assume self.framebuffer contains pixel bits for 2 devices (128 total):
+    /// Returns `Error::BufferError` if the pixel lies outside the display.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Result<bool> {
+        let pixel = self.pixel(x, y).ok_or(Error::BufferError)?;
+        Ok(pixel != 0)
+    }
+
+    /// Inverts every pixel of the framebuffer inside the clip rectangle.
     ///
-    /// Device 0, row 0 pixels: [1, 0, 1, 0, 1, 0, 1, 0]  => 0b10101010 => 0xAA
-    /// Device 1, row 0 pixels: [1, 1, 1, 1, 0, 0, 0, 0]  => 0b11110000 => 0xF0
+    /// The change reaches the display on the next flush.
+    pub fn invert(&mut self) {
+        match self.clip {
+            Some(clip) => self.apply_clip(clip, |pixel| (pixel == 0) as u8),
+            None => {
+                for pixel in self.framebuffer.iter_mut() {
+                    *pixel = (*pixel == 0) as u8;
+                }
+            }
+        }
+    }
+
+    /// Inverts the rectangle at (`x`, `y`) with the given width and height,
+    /// e.g. to highlight the selected item of a menu.
     ///
-    /// Since SPI sends left to right, we must reverse the device order in the ops array:
-    ///     ops\[0\] = (Digit0, 0xF0)  // Device 1
-    ///     ops\[1\] = (Digit0, 0xAA)  // Device 0
+    /// The change reaches the display on the next flush.
     ///
-    /// These are sent out in one SPI write for Digit0, and similarly repeated for Digit1 through Digit7.
-    pub fn flush(&mut self) -> Result<()> {
-        for (row, digit_register) in Register::digits().enumerate() {
-            let mut ops = [(Register::NoOp, 0); DEVICE_COUNT];
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the region extends past the display.
+    pub fn invert_region(&mut self, x: usize, y: usize, width: usize, height: usize) -> Result<()> {
+        self.update_region(x, y, width, height, |pixel| (pixel == 0) as u8)
+    }
 
-            for device_index in 0..DEVICE_COUNT {
-                let buffer_start = device_index * 64 + row * 8;
-                let mut packed_byte = 0;
-                for col in 0..8 {
-                    let pixel_index = buffer_start + col;
-                    if pixel_index < self.framebuffer.len() && self.framebuffer[pixel_index] != 0 {
-                        // bit 7 is leftmost pixel (Col 0) on the display
-                        packed_byte |= 1 << (7 - col);
-                    }
-                }
+    /// Turns the rectangle at (`x`, `y`) with the given width and height
+    /// fully on or off.
+    ///
+    /// The change reaches the display on the next flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the region extends past the display.
+    pub fn fill_region(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        on: bool,
+    ) -> Result<()> {
+        self.update_region(x, y, width, height, |_| on as u8)
+    }
+
+    /// Draws a straight line from (`x0`, `y0`) to (`x1`, `y1`), both ends
+    /// included, into the framebuffer.
+    ///
+    /// The change reaches the display on the next flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if either end lies outside the display.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// matrix.draw_line(0, 7, 31, 0, true)?; // diagonal across a 4-module chain
+    /// matrix.flush()?;
+    /// ```
+    pub fn draw_line(
+        &mut self,
+        x0: usize,
+        y0: usize,
+        x1: usize,
+        y1: usize,
+        on: bool,
+    ) -> Result<()> {
+        if self.pixel_index(x0, y0).is_none() || self.pixel_index(x1, y1).is_none() {
+            return Err(Error::BufferError);
+        }
 
-                // Fill ops array in reverse order for SPI chain
-                let ops_index = DEVICE_COUNT - 1 - device_index;
-                ops[ops_index] = (digit_register, packed_byte);
+        // Bresenham's line algorithm
+        let (mut x, mut y) = (x0 as isize, y0 as isize);
+        let (x1, y1) = (x1 as isize, y1 as isize);
+        // The ends are on the display, so none of this can saturate
+        let dx = x1.saturating_sub(x).saturating_abs();
+        let dy = y1.saturating_sub(y).saturating_abs().saturating_neg();
+        let sx = if x < x1 { 1 } else { -1 };
+        let sy = if y < y1 { 1 } else { -1 };
+        let mut err = dx.saturating_add(dy);
+        loop {
+            self.set_pixel(x as usize, y as usize, on)?;
+            if x == x1 && y == y1 {
+                return Ok(());
+            }
+            let e2 = err.saturating_mul(2);
+            if e2 >= dy {
+                err = err.saturating_add(dy);
+                x = x.saturating_add(sx);
             }
+            if e2 <= dx {
+                err = err.saturating_add(dx);
+                y = y.saturating_add(sy);
+            }
+        }
+    }
 
-            self.driver.write_all_registers(&ops[..DEVICE_COUNT])?;
+    /// Applies `f` to every framebuffer pixel inside a region.
+    fn update_region(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        f: impl Fn(u8) -> u8,
+    ) -> Result<()> {
+        if x.saturating_add(width) > self.width() || y.saturating_add(height) > self.height() {
+            return Err(Error::BufferError);
+        }
+        for py in y..y.saturating_add(height) {
+            for px in x..x.saturating_add(width) {
+                if let Some(pixel) = self.drawable_pixel(px, py) {
+                    *pixel = f(*pixel);
+                }
+            }
         }
         Ok(())
     }
 
-    /// Clear the internal framebuffer (sets all pixels to 0).
-    pub fn clear_buffer(&mut self) {
-        self.framebuffer.fill(0);
+    /// Applies `f` to every framebuffer pixel inside the clip rectangle.
+    fn apply_clip(&mut self, clip: ClipRect, f: impl Fn(u8) -> u8) {
+        // The clip rectangle always lies inside the display
+        let _ = self.update_region(clip.x, clip.y, clip.width, clip.height, f);
     }
 
-    /// Clear screen by resetting buffer and flushing
-    pub fn clear_screen(&mut self) -> Result<()> {
+    /// Returns the wiring of a single device in the chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range.
+    pub fn device_wiring(&self, device_index: usize) -> Result<Wiring> {
+        self.wiring
+            .get(device_index)
+            .copied()
+            .ok_or(Error::InvalidDeviceIndex)
+    }
+
+    /// Clear a specific device
+    pub fn clear(&mut self, device_index: usize) -> Result<()> {
+        self.store_device_rows(device_index, &[0; 8])?;
+        if self.mode == UpdateMode::WriteThrough {
+            self.driver.clear_display(device_index)?;
+        }
+        Ok(())
+    }
+
+    /// Clear all device
+    pub fn clear_all(&mut self) -> Result<()> {
+        self.framebuffer.fill(0);
+        if self.mode == UpdateMode::WriteThrough {
+            self.driver.clear_all()?;
+        }
+        Ok(())
+    }
+
+    /// Write a complete buffer to a specific display
+    pub fn write_buffer(&mut self, device_index: usize, buffer: &MatrixBuffer) -> Result<()> {
+        self.write_device_rows(device_index, buffer.data())
+    }
+
+    /// Draws a die face (1 to 6) on the specified display device.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `face` is not in the range 1 to 6,
+    /// `Error::InvalidDeviceIndex` if `device_index` is out of range, or an
+    /// SPI error in write-through mode.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// matrix.draw_die(0, 5)?;
+    /// ```
+    pub fn draw_die(&mut self, device_index: usize, face: u8) -> Result<()> {
+        self.write_buffer(device_index, &games::die_face(face)?)
+    }
+
+    /// Draws a playing-card rank on the specified display device.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` for a numbered rank outside 2 to 10,
+    /// `Error::InvalidDeviceIndex` if `device_index` is out of range, or an
+    /// SPI error in write-through mode.
+    pub fn draw_card_rank(&mut self, device_index: usize, rank: CardRank) -> Result<()> {
+        self.write_buffer(device_index, &rank.to_buffer()?)
+    }
+
+    /// Stores eight logical rows of one device and, in write-through mode,
+    /// sends them to the chip using the device's wiring.
+    fn write_device_rows(&mut self, device_index: usize, rows: &[u8; 8]) -> Result<()> {
+        self.store_device_rows(device_index, rows)?;
+        if self.mode == UpdateMode::WriteThrough {
+            let digits = self.device_wiring(device_index)?.apply(rows);
+            for (digit, &data) in digits.iter().enumerate() {
+                self.driver
+                    .write_raw_digit(device_index, digit as u8, data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies eight row bytes (bit 7 = leftmost pixel) of one device into the framebuffer.
+    ///
+    /// The rows are stored in the logical tile shown by the device, which for
+    /// the default chain layout is tile `DEVICE_COUNT - 1 - i` for driver
+    /// device `i`, mirroring the reversed order used by [`Self::flush`].
+    fn store_device_rows(&mut self, device_index: usize, rows: &[u8; 8]) -> Result<()> {
+        self.require_matrix(device_index)?;
+
+        let block_start = self.device_tile(device_index).saturating_mul(64);
+        let block = self.framebuffer.iter_mut().skip(block_start).take(64);
+        for (index, pixel) in block.enumerate() {
+            let data = rows.get(index / 8).copied().unwrap_or(0);
+            *pixel = u8::from(data & (0x80 >> (index % 8)) != 0);
+        }
+        Ok(())
+    }
+
+    /// Returns the logical tile shown by a device.
+    fn device_tile(&self, device_index: usize) -> usize {
+        let tiles_x = self.width() / 8;
+        (0..DEVICE_COUNT)
+            .find(|&tile| {
+                let x = tile.checked_rem(tiles_x).unwrap_or(0).saturating_mul(8);
+                let y = tile.checked_div(tiles_x).unwrap_or(0).saturating_mul(8);
+                self.locate(x, y).map(|address| address.device) == Some(device_index)
+            })
+            .unwrap_or(DEVICE_COUNT.saturating_sub(1).saturating_sub(device_index))
+    }
+
+    /// Draws a single 8x8 character on the specified display device.
+    ///
+    /// The character is converted into an 8-byte bitmap using a predefined font.
+    /// If the character is unsupported, it will be replaced with "?" char.
+    ///
+    /// Each byte in the bitmap corresponds to one row of the 8x8 LED matrix (from D0 to D7),
+    /// and is written to the digit registers of the specified `device_index`.
+    ///
+    /// # Arguments
+    /// * `device_index` - Index of the target MAX7219 device in the daisy chain.
+    /// * `c` - The character to display.
+    ///
+    /// # Errors
+    /// Returns an error if the digit conversion fails or if SPI communication fails.
+    pub fn draw_char(&mut self, device_index: usize, ch: char) -> Result<()> {
+        self.draw_char_with_font(device_index, ch, &fonts::STANDARD_LED_FONT)
+    }
+
+    /// Draws a single 8x8 character on the specified display device using a provided font.
+    ///
+    /// This function is similar to [`Self::draw_char`], but allows overriding the font used for rendering.
+    /// The character is mapped to an 8-byte bitmap. Each byte represents a row on the matrix, with
+    /// the most significant bit (bit 7) on the left and the least significant bit (bit 0) on the right.
+    ///
+    /// # Arguments
+    /// * `device_index` - Index of the MAX7219 device to write to.
+    /// * `ch` - The character to render on the display.
+    /// * `font` - The font to use for character lookup and rendering.
+    ///
+    /// A character the font's [`CharPolicy`](crate::CharPolicy) skips leaves
+    /// the device unchanged; one it rejects returns `Error::UnsupportedChar`.
+    pub fn draw_char_with_font(
+        &mut self,
+        device_index: usize,
+        ch: char,
+        font: &LedFont,
+    ) -> Result<()> {
+        match font.lookup(ch)? {
+            Some(bitmap) => self.write_device_rows(device_index, &bitmap),
+            None => Ok(()),
+        }
+    }
+
+    /// Draw a string of text on the LED matrix using the default font.
+    /// Each character is displayed on one device in the daisy chain.
+    /// If the string is longer than the number of devices, the extra characters are ignored.
+    pub fn draw_text(&mut self, text: &str) -> Result<()> {
+        self.draw_text_with_font(text, &fonts::STANDARD_LED_FONT)
+    }
+
+    /// Draw a string of text on the LED matrix using a specified font.
+    /// Each character is displayed on one device in the daisy chain.
+    /// If the string is longer than the number of devices, the extra characters are ignored.
+    ///
+    /// Characters skipped by the font's [`CharPolicy`](crate::CharPolicy) take
+    /// no device. If the font rejects a character, `Error::UnsupportedChar` is
+    /// returned before anything is drawn.
+    pub fn draw_text_with_font(&mut self, text: &str, font: &LedFont) -> Result<()> {
+        let device_count = self.driver.device_count();
+        font.check(text.chars())?;
+
+        let mut images = [[0u8; 8]; MAX_DISPLAYS];
+
+        // Devices without a character are left blank
+        self.framebuffer.fill(0);
+
+        let glyphs = text.chars().filter_map(|ch| font.lookup(ch).ok().flatten());
+        let matrices = self.matrix_devices();
+        let devices =
+            (0..device_count).filter(|&device_index| matrices.get(device_index) == Some(&true));
+        for (device_index, bitmap) in devices.zip(glyphs) {
+            if let Some(image) = images.get_mut(device_index) {
+                *image = self.device_wiring(device_index)?.apply(&bitmap);
+            }
+            self.store_device_rows(device_index, &bitmap)?;
+        }
+
+        if self.mode == UpdateMode::Buffered {
+            return Ok(());
+        }
+
+        // Each digit_register targets the same row index (0 to 7) in every device.
+        // Example: if digit_register = Digit3 and device_count = 2,
+        // then ops will look like:
+        //     ops = [
+        //         (Digit3, images[0][3]), // device 1 (farthest), row 3
+        //         (Digit3, images[1][3]), // device 0 (nearest), row 3
+        //     ];
+        for (row_index, digit_register) in Register::digits().enumerate() {
+            let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+
+            let devices = ops
+                .iter_mut()
+                .zip(&images)
+                .zip(&matrices)
+                .take(device_count);
+            for ((op, image), &matrix) in devices {
+                if matrix {
+                    *op = (digit_register, image.get(row_index).copied().unwrap_or(0));
+                }
+            }
+
+            self.driver.write_all_registers(&ops)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws an 8x8 bitmap (bit 7 = leftmost pixel) into the framebuffer
+    /// with its top-left corner at (`x`, `y`).
+    ///
+    /// Lit and dark pixels are both drawn. Parts of the bitmap outside the
+    /// display are clipped, so glyphs can be partly off screen. The display
+    /// is not updated; call [`Self::flush`] afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::WrongDeviceKind` if a pixel lands on a device the
+    /// chain layout declares as something other than a matrix.
+    pub fn draw_glyph(&mut self, x: i32, y: i32, bitmap: &[u8; 8]) -> Result<()> {
+        let (width, height) = self.size();
+        for (dy, &row) in bitmap.iter().enumerate() {
+            let py = y.saturating_add(dy as i32);
+            if py < 0 || py as usize >= height {
+                continue;
+            }
+            for dx in 0..8 {
+                let px = x.saturating_add(dx);
+                if px < 0 || px as usize >= width {
+                    continue;
+                }
+                self.set_pixel(px as usize, py as usize, row & (0x80 >> dx) != 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Show a static label aligned within the display using the default font.
+    ///
+    /// See [`Self::show_text_fit_with_font`].
+    pub fn show_text_fit(&mut self, text: &str, align: Align) -> Result<()> {
+        self.show_text_fit_with_font(text, align, &fonts::STANDARD_LED_FONT)
+    }
+
+    /// Show a static label aligned within the display using a specified font.
+    ///
+    /// Unlike [`Self::draw_text`], glyphs are placed at pixel positions, so
+    /// the label can be centered or right-aligned across device boundaries.
+    /// Text that does not fit ends with an ellipsis glyph. The label is drawn
+    /// on the top 8 rows and the rest of the framebuffer is cleared.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the font rejects a character.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// matrix.show_text_fit("OK", Align::Center)?; // "  OK  "
+    /// matrix.show_text_fit("OVERHEAT", Align::Center)?; // "OVE…" on 4 modules
+    /// ```
+    pub fn show_text_fit_with_font(
+        &mut self,
+        text: &str,
+        align: Align,
+        font: &LedFont,
+    ) -> Result<()> {
+        self.show_text_styled_with_font(text, align, TextStyle::Normal, font)
+    }
+
+    /// Show a static label in the given style using the default font.
+    ///
+    /// See [`Self::show_text_styled_with_font`].
+    pub fn show_text_styled(&mut self, text: &str, align: Align, style: TextStyle) -> Result<()> {
+        self.show_text_styled_with_font(text, align, style, &fonts::STANDARD_LED_FONT)
+    }
+
+    /// Show a static label like [`Self::show_text_fit_with_font`], rendering
+    /// its cells in `style`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// matrix.show_text_styled("ALARM", Align::Center, TextStyle::Inverse)?;
+    /// ```
+    pub fn show_text_styled_with_font(
+        &mut self,
+        text: &str,
+        align: Align,
+        style: TextStyle,
+        font: &LedFont,
+    ) -> Result<()> {
+        font.check(text.chars())?;
         self.clear_buffer();
-        self.flush()
+
+        let (width, height) = self.size();
+        if height >= 8 {
+            let glyphs = text.chars().filter_map(|ch| font.lookup(ch).ok().flatten());
+            let fit = TextFit::new(glyphs.clone().count(), width, align);
+            let fit_x = fit.x as i32;
+            let cells = fit.chars.saturating_add(fit.ellipsis as usize);
+            let glyphs = glyphs.take(fit.chars);
+            let ellipsis = fit.ellipsis.then_some(fonts::ELLIPSIS);
+            for (cell, bitmap) in glyphs.chain(ellipsis).enumerate() {
+                let bitmap = style.apply(&bitmap, cell == 0, cell.saturating_add(1) == cells);
+                self.draw_glyph(
+                    fit_x.saturating_add((cell as i32).saturating_mul(8)),
+                    0,
+                    &bitmap,
+                )?;
+            }
+        }
+
+        if self.mode == UpdateMode::WriteThrough {
+            self.flush()?;
+        }
+        Ok(())
     }
-}
 
-#[cfg(feature = "graphics")]
-mod eg_imports {
-    pub use embedded_graphics_core::Pixel;
+    /// Scroll the given text across the LED matrix.
+    ///
+    /// This will render `text` using the current font and step through
+    /// each frame at the delay specified by `config.step_delay_ns`. If
+    /// `config.loop_text` is true, the text will repeat with
+    /// `config.loop_padding` pixels of blank space between repetitions.
+    ///
+    /// The text may contain `{pause=MS}` and `{speed=PERCENT}` markers, see
+    /// the [`scroll`](crate::led_matrix::scroll) module.
+    ///
+    /// # Parameters
+    ///
+    /// - `delay`: delay provider implementing `embedded_hal::delay::DelayNs`.
+    /// - `text`: the string slice to scroll.
+    /// - `config`: scrolling configuration (speed, step size, looping).
+    ///
+    /// # Errors
+    ///
+    /// Returns a `MatrixError` if updating the display buffer fails.
+    pub fn scroll_text<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        text: &str,
+        config: ScrollConfig,
+    ) -> Result<()> {
+        let mut scroller = ScrollingText::new(text, &fonts::STANDARD_LED_FONT, config);
+        scroller.check()?;
+        scroller.reset();
+
+        loop {
+            self.draw_scroller(&scroller, None)?;
+
+            if !scroller.step() {
+                break; // Stop if not looping and text has finished scrolling
+            }
+
+            delay.delay_ns(scroller.next_delay_ns());
+        }
+
+        Ok(())
+    }
+
+    /// Scroll the given text in sub-pixel steps using temporal dithering.
+    ///
+    /// Works like [`Self::scroll_text`], but the text moves an eighth of a
+    /// pixel at a time. Columns between two positions are shown at an
+    /// intermediate brightness by cycling through [`DUTY_STEPS`] dithering
+    /// frames while the text is between whole pixels (see
+    /// [`ScrollingText::get_dithered_frame`]), which looks much smoother at
+    /// low scroll speeds. The display is refreshed [`DUTY_STEPS`] times as
+    /// often as with whole-pixel steps, so the SPI bus must keep up with
+    /// `config.step_delay_ns / 64` per frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the font rejects a character, or
+    /// an error if updating the display fails.
+    pub fn scroll_text_smooth<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        text: &str,
+        config: ScrollConfig,
+    ) -> Result<()> {
+        let mut scroller = ScrollingText::new(text, &fonts::STANDARD_LED_FONT, config);
+        scroller.check()?;
+        scroller.reset();
+
+        // Each sub-pixel position is shown for one full dithering cycle
+        let frames = u32::from(DUTY_STEPS)
+            .saturating_mul(u32::from(config.pixels_per_step.max(1)))
+            .saturating_mul(u32::from(DUTY_STEPS));
+        loop {
+            let frame_ns = scroller.step_delay_ns().checked_div(frames).unwrap_or(0);
+            for frame in 0..DUTY_STEPS {
+                self.draw_scroller(&scroller, Some(frame))?;
+                delay.delay_ns(frame_ns);
+                if scroller.fraction() == 0 {
+                    // Whole pixel positions need no dithering
+                    delay.delay_ns(frame_ns.saturating_mul(u32::from(DUTY_STEPS - 1)));
+                    break;
+                }
+            }
+
+            if !scroller.step_fraction(1) {
+                break;
+            }
+            delay.delay_ns(scroller.take_pause_ns());
+        }
+
+        Ok(())
+    }
+
+    /// Draws the current window of `scroller` across the chain and shows it.
+    ///
+    /// For scrolling from your own loop instead of [`Self::scroll_text`],
+    /// e.g. to update the text while it runs with
+    /// [`ScrollingText::queue_text`] or [`ScrollingText::set_text`]. Step the
+    /// scroller between calls.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if updating the display fails.
+    pub fn show_scroller<const N: usize>(
+        &mut self,
+        scroller: &mut ScrollingText<'_, N>,
+    ) -> Result<()> {
+        self.draw_scroller(scroller, None)
+    }
+
+    /// Draws a strip of columns (bit 0 = top row) into the framebuffer with
+    /// its top-left corner at (`x`, `y`), e.g. one pre-rendered with
+    /// [`render_columns`](crate::led_matrix::strip::render_columns).
+    ///
+    /// Lit and dark pixels are both drawn, and parts outside the display are
+    /// clipped, as with [`Self::draw_glyph`]. The display is not updated;
+    /// call [`Self::flush`] afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::WrongDeviceKind` if a column lands on a device the
+    /// chain layout declares as something other than a matrix.
+    pub fn draw_columns(&mut self, x: i32, y: i32, columns: &[u8]) -> Result<()> {
+        let (width, height) = self.size();
+        for (dx, &column) in columns.iter().enumerate() {
+            let px = x.saturating_add(dx as i32);
+            if px < 0 || px as usize >= width {
+                continue;
+            }
+            for dy in 0..8 {
+                let py = y.saturating_add(dy);
+                if py < 0 || py as usize >= height {
+                    continue;
+                }
+                self.set_pixel(px as usize, py as usize, column & (1 << dy) != 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws `text` into the framebuffer with the top-left corner of its
+    /// first glyph at (`x`, `y`), bolder, wider or taller as set by
+    /// `transform`.
+    ///
+    /// Glyphs take [`GlyphTransform::width`] by [`GlyphTransform::height`]
+    /// pixels each, so tall text needs a display at least two modules high,
+    /// e.g. one set up with [`Self::with_mapper`]. Lit and dark pixels are
+    /// both drawn and parts outside the display are clipped, as with
+    /// [`Self::draw_glyph`]. The display is not updated; call
+    /// [`Self::flush`] afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` before anything is drawn if the font
+    /// rejects a character, and `Error::WrongDeviceKind` if the text lands
+    /// on a device the chain layout declares as something other than a
+    /// matrix.
+    pub fn draw_text_transformed(
+        &mut self,
+        x: i32,
+        y: i32,
+        text: &str,
+        font: &LedFont,
+        transform: GlyphTransform,
+    ) -> Result<()> {
+        self.draw_text_shifted(x, y, text, font, transform, |_| 0)
+    }
+
+    /// Draws text like [`Self::draw_text_transformed`], with each glyph
+    /// moved down by the number of pixels `shift` returns for its character
+    /// (up if negative).
+    ///
+    /// Shifting the baseline per glyph mixes content within a line, e.g. a
+    /// raised degree sign or unit, and a shift for the whole text places a
+    /// 5x7 font within an 8-pixel row or across the seam between two module
+    /// rows. Rows shifted past the display are clipped; to clip them to a
+    /// band of the display instead, e.g. the row of a multi-line layout,
+    /// draw inside [`Self::draw_clipped`]:
+    ///
+    /// ```rust,ignore
+    /// // Second line of a 16-pixel-high wall, with "C" raised by 2 pixels
+    /// matrix.draw_clipped(0, 8, 32, 8, |matrix| {
+    ///     let raised = |ch| if ch == 'C' { -2 } else { 0 };
+    ///     matrix.draw_text_shifted(0, 9, "21C", &FONT_5X7, GlyphTransform::default(), raised)
+    /// })?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` before anything is drawn if the font
+    /// rejects a character, and `Error::WrongDeviceKind` if a shifted glyph
+    /// lands on a device the chain layout declares as something other than
+    /// a matrix.
+    pub fn draw_text_shifted(
+        &mut self,
+        x: i32,
+        y: i32,
+        text: &str,
+        font: &LedFont,
+        transform: GlyphTransform,
+        shift: impl Fn(char) -> i32,
+    ) -> Result<()> {
+        font.check(text.chars())?;
+        let (width, height) = self.size();
+        let mut px = x;
+        for ch in text.chars() {
+            let Some(bitmap) = font.lookup(ch)? else {
+                continue;
+            };
+            let top = y.saturating_add(shift(ch));
+            let columns = transform.apply(&strip::glyph_columns(&bitmap));
+            for &column in columns.iter().take(transform.width()) {
+                for dy in 0..transform.height() {
+                    let py = top.saturating_add(dy as i32);
+                    if px < 0 || px as usize >= width || py < 0 || py as usize >= height {
+                        continue;
+                    }
+                    self.set_pixel(px as usize, py as usize, column & (1 << dy) != 0)?;
+                }
+                px = px.saturating_add(1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows a strip of columns (bit 0 = top row) across the chain, leftmost
+    /// first, e.g. the window of a
+    /// [`ColumnScroller`](crate::led_matrix::strip::ColumnScroller).
+    ///
+    /// The `i`th matrix device shows columns `8 * i` to `8 * i + 7`, as
+    /// with [`Self::scroll_text`]; devices past the end of `columns` are
+    /// blanked. Devices the chain layout declares as something other than
+    /// a matrix are skipped and keep what they show.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if updating the display fails.
+    pub fn show_columns(&mut self, columns: &[u8]) -> Result<()> {
+        let matrices = self.matrix_devices();
+        let devices = matrices
+            .iter()
+            .enumerate()
+            .filter_map(|(device_index, &matrix)| matrix.then_some(device_index));
+        for (window, device_index) in devices.enumerate() {
+            let mut rows = [0u8; 8];
+            let start = window.saturating_mul(8);
+            let device_columns = columns.get(start..).unwrap_or_default().iter();
+            for (col, &column) in device_columns.take(8).enumerate() {
+                for (row, data) in rows.iter_mut().enumerate() {
+                    if column & (1 << row) != 0 {
+                        *data |= 0x80 >> col;
+                    }
+                }
+            }
+            self.write_device_rows(device_index, &rows)?;
+        }
+
+        if self.mode == UpdateMode::Buffered {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Draws the current window of `scroller` across the chain, dithered on
+    /// `dither_frame` if given, and shows it.
+    fn draw_scroller<const N: usize>(
+        &mut self,
+        scroller: &ScrollingText<'_, N>,
+        dither_frame: Option<u8>,
+    ) -> Result<()> {
+        let mut windows = [[0u8; 8]; DEVICE_COUNT];
+        // Seven-segment devices in the chain are skipped
+        let matrices = self
+            .matrix_devices()
+            .iter()
+            .filter(|&&matrix| matrix)
+            .count();
+        scroller.window_columns(
+            dither_frame,
+            windows.get_mut(..matrices).unwrap_or_default(),
+        );
+        self.show_columns(windows.as_flattened())
+    }
+
+    /// Scroll every message in `queue` once, front to back.
+    ///
+    /// Messages are removed as they finish, unless the queue repeats, in
+    /// which case this never returns. `config.loop_text` is ignored.
+    ///
+    /// If the queue has a [separator](crate::MessageQueue::with_separator),
+    /// the messages scroll as one continuous
+    /// [ticker line](crate::MessageQueue::ticker_text) instead of the
+    /// display blanking between them, and `config.loop_padding` is ignored
+    /// as well. The queue is cleared once the line has scrolled past.
+    #[cfg(feature = "alloc")]
+    pub fn scroll_queue<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        queue: &mut crate::MessageQueue,
+        config: ScrollConfig,
+    ) -> Result<()> {
+        if queue.separator().is_some() {
+            let text = queue.ticker_text();
+            let config = ScrollConfig {
+                loop_text: queue.is_repeating(),
+                loop_padding: 0,
+                ..config
+            };
+            self.scroll_text(delay, &text, config)?;
+            queue.clear();
+            return Ok(());
+        }
+
+        let config = ScrollConfig {
+            loop_text: false,
+            ..config
+        };
+        while let Some(message) = queue.current() {
+            self.scroll_text(delay, message, config)?;
+            queue.advance();
+        }
+        Ok(())
+    }
+
+    /// Flush the internal display buffer to the actual LED matrix hardware.
+    ///
+    /// This function goes row by row (0 to 7), and for each row, it builds an array of
+    /// SPI operations (`ops`) to send to all devices in the daisy-chained display.
+    /// It packs each row of pixels into a single byte for each device, then sends the data
+    /// using the driver's `write_all_registers` method.
+    ///
+    /// ### Example logic (DEVICE_COUNT = 2, row = 0):
+    /// Assume self.framebuffer contains pixel bits for 2 devices (128 total):
+    ///
+    /// Device 0, row 0 pixels: [1, 0, 1, 0, 1, 0, 1, 0]  => 0b10101010 => 0xAA
+    /// Device 1, row 0 pixels: [1, 1, 1, 1, 0, 0, 0, 0]  => 0b11110000 => 0xF0
+    ///
+    /// Since SPI sends left to right, we must reverse the device order in the ops array:
+    ///     ops\[0\] = (Digit0, 0xF0)  // Device 1
+    ///     ops\[1\] = (Digit0, 0xAA)  // Device 0
+    ///
+    /// These are sent out in one SPI write for Digit0, and similarly repeated for Digit1 through Digit7.
+    ///
+    /// ### Atomic rows
+    ///
+    /// With the default [`FlushOrder::RowMajor`], a row is never left half
+    /// updated across the chain: its data for every device travels in one
+    /// SPI transaction, and all chips latch their packet on the same rising
+    /// edge of chip select. Synchronized animations spanning several modules
+    /// therefore change each row on all of them at once.
+    /// [`FlushOrder::DeviceMajor`] gives up this guarantee.
+    pub fn flush(&mut self) -> Result<()> {
+        self.flush_digits(0xFF, &[true; DEVICE_COUNT])
+    }
+
+    /// Flushes like [`Self::flush`] and delivers the frame to `sink` as
+    /// well, e.g. to forward it to a remote monitor or a log.
+    ///
+    /// The sink receives the rows of every device as shown, with layers,
+    /// dimming and heartbeat applied. If the sink fails, its error is
+    /// returned after the chain has been updated.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut forwarder = UartForwarder::new(uart);
+    /// matrix.draw_text("HI")?;
+    /// matrix.flush_with_tee(&mut forwarder)?;
+    /// ```
+    pub fn flush_with_tee(&mut self, sink: &mut dyn FrameSink) -> Result<()> {
+        self.flush()?;
+        let images = self.device_images();
+        call_hook(|| sink.write_rows(&images))
+    }
+
+    /// Flush only the given rows of the display to the hardware.
+    ///
+    /// Useful when the application knows exactly which rows changed, e.g.
+    /// a progress bar drawn on the bottom row, and wants to avoid resending
+    /// the whole frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the range extends past the last row.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// matrix.flush_rows(6..8)?; // push the two bottom rows only
+    /// ```
+    pub fn flush_rows(&mut self, rows: Range<usize>) -> Result<()> {
+        if rows.end > self.height() {
+            return Err(Error::BufferError);
+        }
+        let height = rows.end.saturating_sub(rows.start);
+        self.flush_region(0, rows.start, self.width(), height)
+    }
+
+    /// Flush the rectangular region starting at (`x`, `y`) with the given
+    /// width and height (in pixels) to the hardware.
+    ///
+    /// Only the digit registers driving the region are written, and devices
+    /// that the region does not touch receive no-ops, so their registers are
+    /// left untouched. Whole digit registers are resent for devices that are
+    /// partially covered, since a digit register cannot be updated bit by bit.
+    ///
+    /// The region is given in framebuffer coordinates; while a
+    /// [`PixelShift`] is set, the registers showing the shifted region are
+    /// written, as a full [`Self::flush`] would.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the region extends past the display.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Only the seconds digits on the rightmost module changed
+    /// matrix.flush_region(24, 0, 8, 8)?;
+    /// ```
+    pub fn flush_region(&mut self, x: usize, y: usize, width: usize, height: usize) -> Result<()> {
+        let (display_width, display_height) = self.size();
+        if x.saturating_add(width) > display_width || y.saturating_add(height) > display_height {
+            return Err(Error::BufferError);
+        }
+
+        let (dx, dy) = self.pixel_shift.map_or((0, 0), |shift| shift.offset());
+        let columns = shift_span(x, width, dx, display_width);
+        let rows = shift_span(y, height, dy, display_height);
+        self.flush_display_region(columns, rows)
+    }
+
+    /// Flushes the digit registers driving the display pixels in `columns`
+    /// and `rows`, after any pixel shift.
+    fn flush_display_region(&mut self, columns: Range<usize>, rows: Range<usize>) -> Result<()> {
+        // With column-major wiring a single logical row touches every digit
+        // register, so collect the digits and devices actually driving the region.
+        let mut digit_mask = 0u8;
+        let mut devices = [false; DEVICE_COUNT];
+        for py in rows {
+            for px in columns.clone() {
+                if let Some(address) = self.locate(px, py)
+                    && let Some(flag) = devices.get_mut(address.device)
+                {
+                    digit_mask |= 1 << (address.digit & 7);
+                    *flag = true;
+                }
+            }
+        }
+
+        self.flush_digits(digit_mask, &devices)
+    }
+
+    /// Sets or removes the heartbeat indicator.
+    ///
+    /// The heartbeat is drawn over the framebuffer whenever the display is
+    /// flushed and never modifies the framebuffer itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// matrix.set_heartbeat(Some(Heartbeat::corner(Corner::BottomRight, 1000)));
+    /// ```
+    pub fn set_heartbeat(&mut self, heartbeat: Option<Heartbeat>) {
+        self.heartbeat = heartbeat;
+    }
+
+    /// Returns the current heartbeat indicator, if any.
+    pub fn heartbeat(&self) -> Option<&Heartbeat> {
+        self.heartbeat.as_ref()
+    }
+
+    /// Advances the heartbeat by `dt_ms` milliseconds.
+    ///
+    /// When the indicator toggles, only the digit registers covering it are
+    /// resent, regardless of the update mode. Returns `true` if the display
+    /// was updated.
+    ///
+    /// Does nothing if no heartbeat is set.
+    pub fn tick_heartbeat(&mut self, dt_ms: u32) -> Result<bool> {
+        let (width, height) = self.size();
+        let Some(heartbeat) = self.heartbeat.as_mut() else {
+            return Ok(false);
+        };
+        if !heartbeat.tick(dt_ms) {
+            return Ok(false);
+        }
+        match heartbeat.area(width, height) {
+            // The heartbeat is not shifted with the frame
+            Some((x, y, w, h)) => {
+                self.flush_display_region(x..x.saturating_add(w), y..y.saturating_add(h))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Sets or removes the burn-in shift of the whole frame.
+    ///
+    /// The framebuffer and layers are shown offset by the shift; the
+    /// heartbeat and dimmed regions stay in place. Takes effect on the next
+    /// flush.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Move the clock face by a pixel every ten minutes
+    /// matrix.set_pixel_shift(Some(PixelShift::new(10 * 60 * 1000)));
+    /// ```
+    pub fn set_pixel_shift(&mut self, shift: Option<PixelShift>) {
+        self.pixel_shift = shift;
+    }
+
+    /// Returns the burn-in shift, if any.
+    pub fn pixel_shift(&self) -> Option<&PixelShift> {
+        self.pixel_shift.as_ref()
+    }
+
+    /// Advances the burn-in shift by `dt_ms` milliseconds and flushes the
+    /// whole display when the frame moves.
+    ///
+    /// Returns `true` if the display was updated.
+    pub fn tick_pixel_shift(&mut self, dt_ms: u32) -> Result<bool> {
+        let Some(shift) = self.pixel_shift.as_mut() else {
+            return Ok(false);
+        };
+        if !shift.tick(dt_ms) {
+            return Ok(false);
+        }
+        self.flush()?;
+        Ok(true)
+    }
+
+    /// Sets or removes a dimmed region in slot `index`.
+    ///
+    /// Lit pixels in the region are only shown on `duty` out of
+    /// [`DUTY_STEPS`](crate::led_matrix::dimming::DUTY_STEPS) frames, so it
+    /// looks dimmer than the rest of the display, even within a single chip.
+    /// Call [`Self::tick_dimming`] often enough that the frames blend, e.g.
+    /// every 2 ms.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `index` is not below
+    /// [`MAX_DIM_REGIONS`](crate::led_matrix::dimming::MAX_DIM_REGIONS).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Show the seconds of a clock at a quarter of the brightness
+    /// matrix.set_dim_region(0, Some(DimRegion::new(24, 0, 8, 8, 2)))?;
+    /// loop {
+    ///     matrix.tick_dimming()?;
+    ///     delay.delay_ms(2);
+    /// }
+    /// ```
+    pub fn set_dim_region(&mut self, index: usize, region: Option<DimRegion>) -> Result<()> {
+        self.dimmer.set_region(index, region)
+    }
+
+    /// Returns the dimmed regions.
+    pub fn dimmer(&self) -> &Dimmer {
+        &self.dimmer
+    }
+
+    /// Advances the dithering frame and resends the dimmed regions,
+    /// regardless of the update mode.
+    ///
+    /// Returns `true` if the display was updated. Does nothing if no region
+    /// is set.
+    pub fn tick_dimming(&mut self) -> Result<bool> {
+        if self.dimmer.is_empty() {
+            return Ok(false);
+        }
+        self.dimmer.advance();
+
+        let (width, height) = self.size();
+        let mut devices = [false; DEVICE_COUNT];
+        let mut digit_mask = 0u8;
+        for region in self.dimmer.regions() {
+            for y in region.y..region.y.saturating_add(region.height).min(height) {
+                for x in region.x..region.x.saturating_add(region.width).min(width) {
+                    if let Some(address) = self.locate(x, y)
+                        && let Some(flag) = devices.get_mut(address.device)
+                    {
+                        digit_mask |= 1 << (address.digit & 7);
+                        *flag = true;
+                    }
+                }
+            }
+        }
+        self.flush_digits(digit_mask, &devices)?;
+        Ok(digit_mask != 0)
+    }
+
+    /// Returns an overlay layer.
+    ///
+    /// Layer 0 is composited first, directly over the framebuffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `index` is not below `LAYERS`.
+    pub fn layer(&self, index: usize) -> Result<&Layer<BUFFER_LENGTH>> {
+        self.layers.get(index).ok_or(Error::BufferError)
+    }
+
+    /// Returns an overlay layer for drawing or showing and hiding it.
+    ///
+    /// Changes to the layer reach the display on the next flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `index` is not below `LAYERS`.
+    pub fn layer_mut(&mut self, index: usize) -> Result<&mut Layer<BUFFER_LENGTH>> {
+        self.layers.get_mut(index).ok_or(Error::BufferError)
+    }
+
+    /// Returns whether the pixel at (`x`, `y`) is lit on the hardware, i.e.
+    /// the framebuffer with all layers and overlays applied.
+    fn output_pixel(&self, x: usize, y: usize) -> bool {
+        let (width, height) = self.size();
+        let source = match self.pixel_shift {
+            Some(shift) => shift.source(x, y, width, height),
+            None => Some((x, y)),
+        };
+        let mut lit = false;
+        if let Some((sx, sy)) = source {
+            lit = self.pixel(sx, sy).is_some_and(|pixel| pixel != 0);
+            for layer in self.layers.iter() {
+                lit = layer.composite(sx, sy, lit);
+            }
+        }
+        lit = self.dimmer.apply(x, y, lit);
+        let heartbeat = self
+            .heartbeat
+            .is_some_and(|heartbeat| heartbeat.covers(x, y, width, height));
+        lit ^ heartbeat
+    }
+
+    /// Renders the current frame into `sink` instead of the chain.
+    ///
+    /// The sink receives the same device rows a [`Self::flush`] would send,
+    /// with layers, dimming and heartbeat applied. Nothing is written to
+    /// the chain.
+    pub fn render_to<S: FrameSink + ?Sized>(&self, sink: &mut S) -> Result<()> {
+        sink.write_rows(&self.device_images())
+    }
+
+    /// Starts a flush that is sent one SPI transaction at a time.
+    ///
+    /// The current frame, with layers, dimming and heartbeat applied, is
+    /// captured now; see [`FlushStateMachine`].
+    pub fn begin_flush(&self) -> FlushStateMachine<DEVICE_COUNT> {
+        FlushStateMachine::new(
+            self.device_images(),
+            self.matrix_devices(),
+            self.flush_order,
+        )
+    }
+
+    /// Converts the framebuffer into DIG0 to DIG7 data for every device (by driver index).
+    fn device_images(&self) -> [[u8; 8]; DEVICE_COUNT] {
+        let mut images = [[0u8; 8]; DEVICE_COUNT];
+        let (width, height) = self.size();
+        for y in 0..height {
+            for x in 0..width {
+                if !self.output_pixel(x, y) {
+                    continue;
+                }
+                if let Some(address) = self.locate(x, y)
+                    && let Some(digit) = images
+                        .get_mut(address.device)
+                        .and_then(|image| image.get_mut(address.digit as usize))
+                {
+                    *digit |= 1 << (address.bit & 7);
+                }
+            }
+        }
+        images
+    }
+
+    /// Sends the digit registers selected by `digit_mask`, one SPI transaction per digit.
+    ///
+    /// Matrix devices flagged in `devices` get their data, all other
+    /// devices receive no-ops.
+    fn flush_digits(&mut self, digit_mask: u8, devices: &[bool; DEVICE_COUNT]) -> Result<()> {
+        let images = self.device_images();
+        let matrices = self.matrix_devices();
+        let mut selected = [false; DEVICE_COUNT];
+        for ((selected, &device), &matrix) in selected.iter_mut().zip(devices).zip(&matrices) {
+            *selected = device && matrix;
+        }
+        let devices = selected;
+
+        // Devices to shut down while the rows are written
+        let mut blanked = [false; DEVICE_COUNT];
+        if self.blank_during_flush && digit_mask.count_ones() > 1 {
+            for (device_index, (blank, &selected)) in blanked.iter_mut().zip(&devices).enumerate() {
+                *blank = selected && self.driver.device_state(device_index)?.is_powered_on();
+            }
+        }
+        self.write_shutdown(&blanked, 0x00)?;
+        let written = self.write_digits(&images, digit_mask, &devices);
+        // Blanked devices are powered on again even if a write failed
+        let restored = self.write_shutdown(&blanked, 0x01);
+        written?;
+        restored
+    }
+
+    /// Writes the digit registers selected by `digit_mask` of the flagged
+    /// devices in the flush order.
+    fn write_digits(
+        &mut self,
+        images: &[[u8; 8]; DEVICE_COUNT],
+        digit_mask: u8,
+        devices: &[bool; DEVICE_COUNT],
+    ) -> Result<()> {
+        match self.flush_order {
+            FlushOrder::RowMajor => {
+                for (digit, digit_register) in Register::digits().enumerate() {
+                    if digit_mask & (1 << digit) == 0 {
+                        continue;
+                    }
+
+                    let mut ops = [(Register::NoOp, 0); DEVICE_COUNT];
+                    for ((op, &selected), image) in ops.iter_mut().zip(devices).zip(images) {
+                        if selected {
+                            *op = (digit_register, image.get(digit).copied().unwrap_or(0));
+                        }
+                    }
+
+                    self.driver.write_all_registers(&ops)?;
+                }
+            }
+            FlushOrder::DeviceMajor => {
+                for (device_index, (image, &selected)) in images.iter().zip(devices).enumerate() {
+                    if !selected {
+                        continue;
+                    }
+                    for (digit, (digit_register, &data)) in
+                        Register::digits().zip(image).enumerate()
+                    {
+                        if digit_mask & (1 << digit) != 0 {
+                            self.driver.write_device_register(
+                                device_index,
+                                digit_register,
+                                data,
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `value` to the Shutdown register of the selected devices in
+    /// one transaction, if any is selected.
+    fn write_shutdown(&mut self, devices: &[bool; DEVICE_COUNT], value: u8) -> Result<()> {
+        if !devices.contains(&true) {
+            return Ok(());
+        }
+        let mut ops = [(Register::NoOp, 0); DEVICE_COUNT];
+        for (op, &selected) in ops.iter_mut().zip(devices.iter()) {
+            if selected {
+                *op = (Register::Shutdown, value);
+            }
+        }
+        self.driver.write_all_registers(&ops)
+    }
+
+    /// Renders a diagnostic pattern on every device and flushes it.
+    ///
+    /// The pattern replaces the framebuffer contents and is sent regardless of
+    /// the update mode. See [`TestPattern`] for what each pattern reveals.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Each module shows its chain index: 0 should be the module furthest from the MCU
+    /// matrix.show_test_pattern(TestPattern::DeviceIndex)?;
+    /// ```
+    pub fn show_test_pattern(&mut self, pattern: TestPattern) -> Result<()> {
+        let matrices = self.matrix_devices();
+        for (device_index, &matrix) in matrices.iter().enumerate() {
+            if matrix {
+                self.store_device_rows(device_index, &pattern.rows(device_index))?;
+            }
+        }
+        self.flush()
+    }
+
+    /// Plays a short startup animation confirming the display works, then
+    /// leaves it blank.
+    ///
+    /// Meant to run once right after initialization: it blocks for the
+    /// whole animation, replaces the framebuffer and is sent regardless of
+    /// the update mode.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut matrix = Matrix4::from_spi(spi)?;
+    /// matrix.boot_animation(&mut delay, BootAnimation::ExpandingBox)?;
+    /// ```
+    pub fn boot_animation<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        animation: BootAnimation,
+    ) -> Result<()> {
+        let (width, height) = self.size();
+        for frame in 0..animation.frame_count(width) {
+            for y in 0..height {
+                for x in 0..width {
+                    if let Some(index) = self.pixel_index(x, y)
+                        && let Some(pixel) = self.framebuffer.get_mut(index)
+                    {
+                        *pixel = animation.is_lit(frame, x, y) as u8;
+                    }
+                }
+            }
+            self.flush()?;
+            delay.delay_ms(BootAnimation::STEP_MS);
+        }
+        self.framebuffer.fill(0);
+        self.flush()
+    }
+
+    /// Plays every frame of `animation` once, `frame_ms` milliseconds each,
+    /// and leaves the last frame on the display.
+    ///
+    /// The modules of a frame are repeated across the display, row by row
+    /// for tiled layouts. Like [`Self::boot_animation`] it blocks for the
+    /// whole animation, replaces the framebuffer and is sent regardless of
+    /// the update mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidAnimation` if the animation data is malformed;
+    /// the frames before the bad one have been shown by then.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// static SPINNER: Animation = max7219_display::include_frames!("spinner");
+    ///
+    /// matrix.play_animation(&mut delay, &SPINNER, 80)?;
+    /// ```
+    pub fn play_animation<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        animation: &Animation<'_>,
+        frame_ms: u32,
+    ) -> Result<()> {
+        let mut rows = [0; 8 * MAX_DISPLAYS];
+        let rows = rows
+            .get_mut(..animation.frame_len())
+            .ok_or(Error::InvalidAnimation)?;
+        let (width, height) = self.size();
+        let tiles_per_row = width / 8;
+        let mut cursor = AnimationCursor::start();
+        while animation.decode_next(&mut cursor, rows)? {
+            for y in 0..height {
+                for x in 0..width {
+                    let tile = (y / 8).saturating_mul(tiles_per_row).saturating_add(x / 8);
+                    let module = tile.checked_rem(animation.modules()).unwrap_or(0);
+                    let lit = rows
+                        .get(module.saturating_mul(8).saturating_add(y % 8))
+                        .is_some_and(|data| data & (0x80 >> (x % 8)) != 0);
+                    if let Some(index) = self.pixel_index(x, y)
+                        && let Some(pixel) = self.framebuffer.get_mut(index)
+                    {
+                        *pixel = lit as u8;
+                    }
+                }
+            }
+            self.flush()?;
+            delay.delay_ms(frame_ms);
+        }
+        Ok(())
+    }
+
+    /// Clear the internal framebuffer (sets all pixels to 0).
+    ///
+    /// Only the inside of the clip rectangle is cleared, if one is set.
+    pub fn clear_buffer(&mut self) {
+        match self.clip {
+            Some(clip) => self.apply_clip(clip, |_| 0),
+            None => self.framebuffer.fill(0),
+        }
+    }
+
+    /// Clear screen by resetting buffer and flushing
+    pub fn clear_screen(&mut self) -> Result<()> {
+        self.clear_buffer();
+        self.flush()
+    }
+
+    /// Returns the framebuffer, e.g. to keep a drawn screen for later.
+    ///
+    /// Pixels are stored in logical 8x8 tiles, one `u8` per pixel.
+    pub fn framebuffer(&self) -> &[u8; BUFFER_LENGTH] {
+        &self.framebuffer
+    }
+
+    /// Replaces the framebuffer with a frame taken from [`Self::framebuffer`].
+    ///
+    /// Like other drawing calls, the frame is sent right away in
+    /// [`UpdateMode::WriteThrough`].
+    pub fn set_framebuffer(&mut self, frame: &[u8; BUFFER_LENGTH]) -> Result<()> {
+        self.framebuffer = *frame;
+        if self.mode == UpdateMode::WriteThrough {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Fades the display out, swaps in `new_frame` and fades back in, using
+    /// the hardware intensity of each device.
+    ///
+    /// Each device ramps between its current intensity and the dimmest step
+    /// in up to 15 steps per half of `duration_ms`; unchanged steps are not
+    /// sent. The content is swapped while the devices are shut down, and
+    /// their intensity and shutdown state are restored afterwards. Devices
+    /// the chain layout declares as something other than a matrix are left
+    /// alone. Works in both update modes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let clock = *matrix.framebuffer();
+    /// matrix.crossfade(&mut delay, &clock, 600)?;
+    /// ```
+    pub fn crossfade<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        new_frame: &[u8; BUFFER_LENGTH],
+        duration_ms: u32,
+    ) -> Result<()> {
+        const STEPS: u32 = Brightness::MAX.level() as u32;
+
+        let mut intensities = [0u8; DEVICE_COUNT];
+        let mut powered = [false; DEVICE_COUNT];
+        let devices = intensities.iter_mut().zip(powered.iter_mut());
+        for (device_index, (intensity, powered)) in devices.enumerate() {
+            let state = self.driver.device_state(device_index)?;
+            *intensity = state.intensity();
+            *powered = state.is_powered_on();
+        }
+
+        let matrices = self.matrix_devices();
+        let step_ms = duration_ms / 2 / STEPS;
+        let fade_out = (0..STEPS).rev();
+        self.ramp_intensity(delay, &intensities, &matrices, fade_out, step_ms)?;
+
+        self.write_shutdown(&matrices, 0x00)?;
+        self.framebuffer = *new_frame;
+        self.flush()?;
+        let mut ops = [(Register::NoOp, 0); DEVICE_COUNT];
+        for ((op, &on), &matrix) in ops.iter_mut().zip(&powered).zip(&matrices) {
+            if matrix {
+                *op = (Register::Shutdown, on as u8);
+            }
+        }
+        self.driver.write_all_registers(&ops)?;
+
+        let fade_in = 1..=STEPS;
+        self.ramp_intensity(delay, &intensities, &matrices, fade_in, step_ms)
+    }
+
+    /// Reveals part of `new_frame` over the framebuffer: every pixel that
+    /// `wipe` reaches by `progress` (0 to [`PROGRESS_END`]) is copied over.
+    ///
+    /// The order is computed in logical coordinates, so tiled and serpentine
+    /// layouts are wiped across the whole surface. Call with increasing
+    /// progress from an animation loop; at [`PROGRESS_END`] the framebuffer
+    /// equals `new_frame`. The frame is sent in
+    /// [`UpdateMode::WriteThrough`].
+    pub fn wipe_step(
+        &mut self,
+        new_frame: &[u8; BUFFER_LENGTH],
+        wipe: Wipe,
+        progress: u16,
+    ) -> Result<()> {
+        let (width, height) = self.size();
+        let progress = usize::from(progress.min(PROGRESS_END));
+        let reached = wipe
+            .steps(width, height)
+            .saturating_mul(progress)
+            .checked_div(usize::from(PROGRESS_END))
+            .unwrap_or(0);
+        for y in 0..height {
+            for x in 0..width {
+                if wipe.rank(x, y, width, height) < reached
+                    && let Some(index) = self.pixel_index(x, y)
+                    && let (Some(pixel), Some(&new)) =
+                        (self.framebuffer.get_mut(index), new_frame.get(index))
+                {
+                    *pixel = new;
+                }
+            }
+        }
+        if self.mode == UpdateMode::WriteThrough {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Wipes from the current content to `new_frame` over `duration_ms`,
+    /// flushing after every step regardless of the update mode.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // 2x2 wall: the new screen grows from the middle of all four modules
+    /// matrix.wipe(&mut delay, &next_screen, Wipe::CenterOut, 400)?;
+    /// ```
+    pub fn wipe<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        new_frame: &[u8; BUFFER_LENGTH],
+        wipe: Wipe,
+        duration_ms: u32,
+    ) -> Result<()> {
+        let (width, height) = self.size();
+        let steps = wipe.steps(width, height).max(1);
+        let step_ms = duration_ms.checked_div(steps as u32).unwrap_or(0);
+        for step in 1..=steps {
+            let progress = step
+                .saturating_mul(usize::from(PROGRESS_END))
+                .checked_div(steps)
+                .unwrap_or(0) as u16;
+            self.wipe_step(new_frame, wipe, progress)?;
+            if self.mode == UpdateMode::Buffered {
+                self.flush()?;
+            }
+            delay.delay_ms(step_ms);
+        }
+        Ok(())
+    }
+
+    /// Scales the intensity of each device flagged in `devices` by
+    /// `step / 15` for every step, sending only steps that change a level.
+    fn ramp_intensity<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        intensities: &[u8; DEVICE_COUNT],
+        devices: &[bool; DEVICE_COUNT],
+        steps: impl Iterator<Item = u32>,
+        step_ms: u32,
+    ) -> Result<()> {
+        const STEPS: u32 = Brightness::MAX.level() as u32;
+
+        for step in steps {
+            let mut ops = [(Register::NoOp, 0); DEVICE_COUNT];
+            let mut changed = false;
+            let selected = ops.iter_mut().zip(intensities).zip(devices).enumerate();
+            for (device_index, ((op, &intensity), &selected)) in selected {
+                if !selected {
+                    continue;
+                }
+                let level = (u32::from(intensity).saturating_mul(step) / STEPS) as u8;
+                if self.driver.device_state(device_index)?.intensity() != level {
+                    *op = (Register::Intensity, level);
+                    changed = true;
+                }
+            }
+            if changed {
+                self.driver.write_all_registers(&ops)?;
+            }
+            delay.delay_ms(step_ms);
+        }
+        Ok(())
+    }
+}
+
+impl<S, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>
+    LedMatrix<SinkSpi<S, DEVICE_COUNT>, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>
+where
+    S: FrameSink,
+{
+    /// Creates a matrix that renders into `sink` instead of driving hardware.
+    ///
+    /// The chain is emulated by a [`SinkSpi`], so every drawing call,
+    /// effect and widget works unchanged and each flush reaches the sink as
+    /// one frame. Write-through drawing that only sends some rows is held
+    /// back until the next write starts a new frame, see [`SinkSpi`]; draw
+    /// in [`UpdateMode::Buffered`] and flush to pass on every frame.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use max7219_display::{
+    ///     FrameRecorder,
+    ///     led_matrix::{UpdateMode, display::Matrix4},
+    /// };
+    ///
+    /// let mut matrix =
+    ///     Matrix4::from_sink(FrameRecorder::<4>::new())?.with_update_mode(UpdateMode::Buffered);
+    /// matrix.set_pixel(0, 0, true)?;
+    /// matrix.flush()?;
+    /// assert!(matrix.sink().to_string().starts_with('#'));
+    /// # Ok::<(), max7219_display::Error>(())
+    /// ```
+    pub fn from_sink(sink: S) -> Result<Self> {
+        Self::from_spi(SinkSpi::new(sink))
+    }
+
+    /// Returns the sink receiving the frames.
+    pub fn sink(&self) -> &S {
+        self.driver.spi().sink()
+    }
+
+    /// Returns the sink receiving the frames mutably.
+    pub fn sink_mut(&mut self) -> &mut S {
+        self.driver.spi_mut().sink_mut()
+    }
+}
+
+#[cfg(feature = "graphics")]
+mod eg_imports {
+    pub use embedded_graphics_core::Pixel;
+
+    pub use embedded_graphics_core::pixelcolor::BinaryColor;
+    pub use embedded_graphics_core::prelude::{DrawTarget, OriginDimensions, Size};
+}
+
+#[cfg(feature = "graphics")]
+use eg_imports::*;
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::geometry::Dimensions;
+
+// Implementing embedded-graphics DrawTarget for LedMatrix
+#[cfg(feature = "graphics")]
+impl<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize> DrawTarget
+    for LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>
+where
+    SPI: SpiDevice,
+{
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+        for Pixel(pos, color) in pixels.into_iter() {
+            if bb.contains(pos)
+                && let Some(pixel) = self.drawable_pixel(pos.x as usize, pos.y as usize)
+            {
+                *pixel = color.is_on() as u8;
+            }
+        }
+        // Note: Does not call self.flush() automatically, regardless of the update mode.
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>
+    OriginDimensions for LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>
+{
+    fn size(&self) -> Size {
+        let (width, height) = match self.mapper {
+            Some(mapper) => call_hook(|| mapper.size(DEVICE_COUNT)),
+            None => (DEVICE_COUNT.saturating_mul(8), 8),
+        };
+        Size::new(width as u32, height as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FrameSink;
+    use crate::chain::{ChainLayout, DeviceKind, Mounting};
+    use crate::led_matrix::animation::{Animation, encode_rle};
+    use crate::led_matrix::boot::BootAnimation;
+    use crate::led_matrix::burn_in::PixelShift;
+    use crate::led_matrix::dimming::DimRegion;
+    use crate::led_matrix::display::{ClipRect, FlushOrder, Matrix4, SingleMatrix, UpdateMode};
+    use crate::led_matrix::fonts::{self, STANDARD_LED_FONT};
+    use crate::led_matrix::games::{self, CardRank};
+    use crate::led_matrix::heartbeat::{Corner, Heartbeat};
+    use crate::led_matrix::layer::BlendMode;
+    use crate::led_matrix::mapping::{PixelAddress, PixelMapper, TiledMapper};
+    use crate::led_matrix::patterns::TestPattern;
+    use crate::led_matrix::scroll::{ScrollConfig, ScrollingText};
+    use crate::led_matrix::strip::GlyphTransform;
+    use crate::led_matrix::text::{Align, TextStyle};
+    use crate::led_matrix::transition::Wipe;
+    use crate::led_matrix::wiring::{Orientation, Wiring};
+    use crate::led_matrix::{LedMatrix, buffer::MatrixBuffer, fonts::LedFont};
+    use crate::registers::Register;
+    use crate::{Brightness, CharPolicy, Error};
+    use crate::{Max7219, NUM_DIGITS};
+    use embedded_hal_mock::eh1::{delay::NoopDelay, spi::Mock as SpiMock, spi::Transaction};
+
+    fn write_reg(addr: u8, value: u8) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![addr, value]),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    #[test]
+    fn test_new() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let matrix: LedMatrix<_, 64, 1> = LedMatrix::from_driver(driver).unwrap();
+        assert_eq!(matrix.framebuffer, [0u8; 64]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_from_spi() {
+        let mut expected_transactions: Vec<Transaction<u8>> = vec![];
+
+        // power_on
+        expected_transactions.extend(write_reg(Register::Shutdown.addr(), 0x01));
+
+        // test_all(false)
+        expected_transactions.extend(write_reg(Register::DisplayTest.addr(), 0x00));
+
+        // set_scan_limit_all(NUM_DIGITS)
+        expected_transactions.extend(write_reg(Register::ScanLimit.addr(), NUM_DIGITS - 1));
+
+        // set_decode_mode_all(NoDecode)
+        expected_transactions.extend(write_reg(
+            Register::DecodeMode.addr(),
+            crate::registers::DecodeMode::NoDecode as u8,
+        ));
+
+        // clear_all() - 8 digits/rows
+        for digit in Register::digits() {
+            expected_transactions.extend(write_reg(digit.addr(), 0x00));
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let result: crate::Result<LedMatrix<_, 64, 1>> = LedMatrix::from_spi(&mut spi);
+
+        assert!(result.is_ok());
+        spi.done();
+    }
+
+    #[test]
+    fn test_from_spi_invalid_count() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        // not valid count
+        let result = LedMatrix::<_, 1024, 256>::from_driver(driver);
+        assert!(matches!(result, Err(Error::InvalidDeviceCount)));
+
+        // Mismatched device count
+        let driver = Max7219::new(&mut spi);
+        let result = Matrix4::from_driver(driver);
+        assert!(matches!(result, Err(Error::InvalidDeviceCount)));
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut expected_transactions = vec![];
+        for digit_register in Register::digits() {
+            expected_transactions.extend(write_reg(digit_register.addr(), 0x00));
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+
+        let result = matrix.clear(0);
+        assert!(result.is_ok());
+        spi.done();
+    }
+
+    #[test]
+    fn test_clear_all() {
+        // Test clearing all devices (but checking with 1 device for SingleMatrix)
+        let mut expected_transactions = vec![];
+        let device_count = 4;
+
+        for digit_register in Register::digits() {
+            expected_transactions.push(Transaction::transaction_start());
+            let batched_data = (0..device_count)
+                .flat_map(|_| vec![digit_register.addr(), 0x00])
+                .collect();
+            expected_transactions.push(Transaction::write_vec(batched_data));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver).expect("valid initialization");
+
+        let result = matrix.clear_all();
+        assert!(result.is_ok());
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_buffer() {
+        let device_index = 0;
+        let mut buffer = MatrixBuffer::new();
+        // Fill buffer with some test data
+        for i in 0..8 {
+            buffer.set_row(i, 0b10101010 << (i % 2)).unwrap();
+        }
+        let expected_data = buffer.data();
+
+        let mut expected_transactions = Vec::new();
+        for (row, &data) in expected_data.iter().enumerate() {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::try_digit(row as u8).unwrap().addr(),
+                data,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+
+        let result = matrix.write_buffer(device_index, &buffer);
+        assert!(result.is_ok());
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_buffer_invalid_index() {
+        let mut spi = SpiMock::new(&[]); // No SPI calls expected
+        let driver = Max7219::new(&mut spi).with_device_count(1).unwrap();
+        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+        let buffer = MatrixBuffer::new();
+
+        let result = matrix.write_buffer(1, &buffer); // Index 1 is invalid for device_count=1
+        // This error comes from Max7219::write_raw_digit via write_device_register
+        assert_eq!(result, Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_char() {
+        let device_index = 0;
+        let ch = 'A';
+        let expected_bitmap = STANDARD_LED_FONT.get_char(ch);
+
+        let mut expected_transactions = Vec::new();
+        for (row, &data) in expected_bitmap.iter().enumerate() {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::try_digit(row as u8).unwrap().addr(),
+                data,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+
+        let result = matrix.draw_char(device_index, ch);
+        assert!(result.is_ok());
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_char_with_font() {
+        let device_index = 0;
+        let ch = 'Z';
+        let test_font = STANDARD_LED_FONT;
+        let expected_bitmap = test_font.get_char(ch);
+
+        let mut expected_transactions = Vec::new();
+        for (row, &data) in expected_bitmap.iter().enumerate() {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::try_digit(row as u8).unwrap().addr(),
+                data,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+
+        matrix
+            .draw_char_with_font(device_index, ch, &test_font)
+            .expect("valid character");
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_char_invalid_index() {
+        let mut spi = SpiMock::new(&[]); // No SPI calls expected
+        let driver = Max7219::new(&mut spi).with_device_count(1).unwrap();
+        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+
+        let result = matrix.draw_char(1, 'A'); // Index 1 is invalid for device_count=1
+        // This error comes from Max7219::write_raw_digit via write_device_register
+        assert_eq!(result, Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_text_single_device() {
+        let text = "H";
+        let expected_ch = 'H';
+        let expected_bitmap = STANDARD_LED_FONT.get_char(expected_ch);
+        // For 1 device, text is written to device 0 (device_count - 1 - i = 0)
+        // Data is sent row by row using write_all_registers
+
+        let mut expected_transactions = Vec::new();
+        for (row_index, &data) in expected_bitmap.iter().enumerate() {
+            let digit_register = Register::try_digit(row_index as u8).unwrap();
+            expected_transactions.push(Transaction::transaction_start());
+            // For 1 device, ops[0] = (digit_register, data)
+            expected_transactions.push(Transaction::write_vec(vec![digit_register.addr(), data]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+
+        let result = matrix.draw_text(text);
+        assert!(result.is_ok());
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_text_multi_device() {
+        let device_count = 4;
+
+        let text = "Hi";
+        let expected_bitmap_h = STANDARD_LED_FONT.get_char('H');
+        let expected_bitmap_i = STANDARD_LED_FONT.get_char('i');
+        // Text chars are taken in order: 'H', 'i'
+        // They are written to devices in reverse order: 'H' -> device 1, 'i' -> device 0
+        // (because device_index = device_count - 1 - i)
+
+        let mut expected_transactions = Vec::new();
+        // Row by row transmission
+        for row_index in 0..8 {
+            let digit_register = Register::try_digit(row_index).unwrap();
+            expected_transactions.push(Transaction::transaction_start());
+            // ops[0] = (digit_register, data_for_device_1) // Sent first on SPI
+            // ops[1] = (digit_register, data_for_device_0) // Sent second, ends up in device 0
+            // Remaining devices gets filled with 0
+            let data_for_device_1 = expected_bitmap_h[row_index as usize];
+            let data_for_device_0 = expected_bitmap_i[row_index as usize];
+            expected_transactions.push(Transaction::write_vec(vec![
+                digit_register.addr(),
+                data_for_device_1, // Data for device 1
+                digit_register.addr(),
+                data_for_device_0, // Data for device 0
+                digit_register.addr(),
+                0x00,
+                digit_register.addr(),
+                0x00,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi)
+            .with_device_count(device_count)
+            .unwrap();
+        let mut matrix = Matrix4::from_driver(driver).unwrap();
+
+        let result = matrix.draw_text(text);
+        assert!(result.is_ok());
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_text_with_font() {
+        let text = "!";
+        // Create a simple test font
+        pub const TEST_FONT: &[([u8; 8], char)] = &[([0b10101010; 8], '!')];
+        let test_font = LedFont::new(TEST_FONT);
+        let expected_bitmap = [0b10101010u8; 8];
+        let device_count = 2;
+
+        let mut expected_transactions = Vec::new();
+
+        // For each row (Digit0 to Digit7)
+        for (row_index, &data) in expected_bitmap.iter().enumerate() {
+            let digit_register = Register::try_digit(row_index as u8).unwrap();
+            expected_transactions.push(Transaction::transaction_start());
+
+            // First device gets the font data, second device gets zero
+            expected_transactions.push(Transaction::write_vec(vec![
+                digit_register.addr(),
+                data, // Device 0
+                digit_register.addr(),
+                0x00, // Device 1 (no text)
+            ]));
+
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi)
+            .with_device_count(device_count)
+            .unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
+
+        let result = matrix.draw_text_with_font(text, &test_font);
+        assert!(result.is_ok());
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_clear_buffer() {
+        let mut spi = SpiMock::new(&[]); // No SPI interaction
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        // Modify the buffer
+        matrix.framebuffer[0] = 1;
+        matrix.framebuffer[10] = 1;
+        matrix.framebuffer[63] = 1;
+        assert_ne!(matrix.framebuffer, [0u8; 64]);
+
+        matrix.clear_buffer();
+
+        assert_eq!(matrix.framebuffer, [0u8; 64]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_clear_screen() {
+        // All digits 0..7 will be written with 0x00 for a single device
+        let mut expected_transactions = Vec::new();
+        for row in 0..8 {
+            let digit_register = Register::try_digit(row).unwrap();
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![digit_register.addr(), 0x00]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        // Modify the buffer
+        matrix.framebuffer[5] = 1;
+        matrix.framebuffer[15] = 1;
+
+        assert_ne!(matrix.framebuffer, [0u8; 64]);
+
+        let result = matrix.clear_screen();
+        assert!(result.is_ok());
+        assert_eq!(matrix.framebuffer, [0u8; 64]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_single_device() {
+        // We expect the flush to send 8 SPI transactions, one for each row (DIGIT0 to DIGIT7)
+        // Only rows 0 and 7 have pixel data: 0b10101010 (columns 0,2,4,6 lit)
+        // All other rows should be cleared (0b00000000)
+
+        let mut expected_transactions = Vec::new();
+        for (row, digit_register) in Register::digits().enumerate() {
+            // For rows 0 and 7, the framebuffer will result in this pattern:
+            // Columns 0, 2, 4, 6 are ON => bits 7, 5, 3, 1 set => 0b10101010
+            let expected_byte = if row == 0 || row == 7 {
+                0b10101010
+            } else {
+                0b00000000
+            };
+
+            // Each transaction sends [register, data] for that row
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                digit_register.addr(),
+                expected_byte,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        // Create the SPI mock with the expected sequence of writes
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        // Set framebuffer values to light up alternating columns in row 0 and row 7
+        // Row 0 corresponds to framebuffer indices 0 to 7
+        matrix.framebuffer[0] = 1; // Column 0
+        matrix.framebuffer[2] = 1; // Column 2
+        matrix.framebuffer[4] = 1; // Column 4
+        matrix.framebuffer[6] = 1; // Column 6
+
+        // Each device's framebuffer is a flat array of 64 bytes: 8 rows * 8 columns
+        // The layout is row-major: [row0[0..7], row1[0..7], ..., row7[0..7]]
+        //
+        // For a single device:
+        //   framebuffer[ 0.. 7] => row 0
+        //   framebuffer[ 8..15] => row 1
+        //   framebuffer[16..23] => row 2
+        //   framebuffer[24..31] => row 3
+        //   framebuffer[32..39] => row 4
+        //   framebuffer[40..47] => row 5
+        //   framebuffer[48..55] => row 6
+        //   framebuffer[56..63] => row 7 (last row)
+        //
+        // So to update row 7, we write to indices 56 to 63.
+        matrix.framebuffer[56] = 1; // Column 0
+        matrix.framebuffer[58] = 1; // Column 2
+        matrix.framebuffer[60] = 1; // Column 4
+        matrix.framebuffer[62] = 1; // Column 6
+
+        // Call flush, which will convert framebuffer rows into bytes and send via SPI
+        let result = matrix.flush();
+        assert!(result.is_ok());
+
+        spi.done();
+    }
+
+    #[test]
+    fn test_driver_mut_access() {
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Shutdown.addr(), 0x01]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let original_driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(original_driver).unwrap();
+
+        let driver = matrix.driver();
+
+        driver.power_on().expect("Power on should succeed");
+        spi.done();
+    }
+
+    #[test]
+    fn test_update_mode_default() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        assert_eq!(matrix.update_mode(), UpdateMode::WriteThrough);
+        spi.done();
+    }
+
+    #[test]
+    fn test_buffered_draw_char_defers_until_flush() {
+        let bitmap = STANDARD_LED_FONT.get_char('A');
+        let mut expected_transactions = Vec::new();
+        for (row, digit_register) in Register::digits().enumerate() {
+            expected_transactions.extend(write_reg(digit_register.addr(), bitmap[row]));
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        // No SPI traffic yet
+        matrix.draw_char(0, 'A').unwrap();
+        // Pushes the character out row by row
+        matrix.flush().unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_show_text_fit() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        let lit = |matrix: &LedMatrix<_, 128, 2>, bitmap: [u8; 8], x0: usize| {
+            (0..8).all(|y| {
+                (0..8).all(|bit| {
+                    matrix.get_pixel(x0 + bit, y).unwrap() == (bitmap[y] & (0x80 >> bit) != 0)
+                })
+            })
+        };
+
+        matrix.show_text_fit("1", Align::Center).unwrap();
+        assert!(lit(&matrix, STANDARD_LED_FONT.get_char('1'), 4));
+
+        matrix.show_text_fit("1", Align::Right).unwrap();
+        assert!(lit(&matrix, STANDARD_LED_FONT.get_char('1'), 8));
+        assert!(lit(&matrix, [0; 8], 0));
+
+        matrix.show_text_fit("123", Align::Center).unwrap();
+        assert!(lit(&matrix, STANDARD_LED_FONT.get_char('1'), 0));
+        assert!(lit(&matrix, fonts::ELLIPSIS, 8));
+
+        // Skipped characters take no space
+        let skipping = STANDARD_LED_FONT.with_unsupported(CharPolicy::Skip);
+        matrix
+            .show_text_fit_with_font("1€", Align::Right, &skipping)
+            .unwrap();
+        assert!(lit(&matrix, STANDARD_LED_FONT.get_char('1'), 8));
+
+        let strict = STANDARD_LED_FONT.with_unsupported(CharPolicy::Error);
+        assert_eq!(
+            matrix.show_text_fit_with_font("1€", Align::Right, &strict),
+            Err(Error::UnsupportedChar('€'))
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_clip_limits_drawing() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        matrix.set_pixel(12, 0, true).unwrap();
+
+        matrix.set_clip(0, 0, 8, 8).unwrap();
+        matrix.fill_region(0, 0, 16, 8, true).unwrap();
+        assert!(matrix.get_pixel(7, 7).unwrap());
+        assert!(!matrix.get_pixel(8, 0).unwrap());
+
+        // Clearing and inverting stay inside too
+        matrix.clear_buffer();
+        assert!(!matrix.get_pixel(0, 0).unwrap());
+        assert!(matrix.get_pixel(12, 0).unwrap());
+        matrix.invert();
+        assert!(matrix.get_pixel(0, 0).unwrap());
+        assert!(!matrix.get_pixel(13, 0).unwrap());
+
+        // Out of the display is still an error, out of the clip is not
+        assert_eq!(matrix.set_pixel(16, 0, true), Err(Error::BufferError));
+        assert_eq!(matrix.set_pixel(9, 0, true), Ok(()));
+        assert!(!matrix.get_pixel(9, 0).unwrap());
+        assert_eq!(matrix.set_clip(8, 0, 9, 8), Err(Error::BufferError));
+
+        matrix.clear_clip();
+        matrix.set_pixel(9, 0, true).unwrap();
+        assert!(matrix.get_pixel(9, 0).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_clipped_nests_and_restores() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        matrix
+            .draw_clipped(4, 0, 8, 8, |matrix| {
+                matrix.draw_clipped(0, 0, 6, 8, |matrix| {
+                    assert_eq!(
+                        matrix.clip(),
+                        Some(ClipRect {
+                            x: 4,
+                            y: 0,
+                            width: 2,
+                            height: 8
+                        })
+                    );
+                    matrix.fill_region(0, 0, 16, 1, true)
+                })
+            })
+            .unwrap();
+        assert_eq!(matrix.clip(), None);
+        let lit: Vec<usize> = (0..16)
+            .filter(|&x| matrix.get_pixel(x, 0).unwrap())
+            .collect();
+        assert_eq!(lit, [4, 5]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_show_text_styled() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        let one = STANDARD_LED_FONT.get_char('1');
+
+        matrix
+            .show_text_styled("1", Align::Left, TextStyle::Inverse)
+            .unwrap();
+        for (y, row) in one.iter().enumerate() {
+            for x in 0..8 {
+                let glyph = row & (0x80 >> x) != 0;
+                assert_eq!(matrix.get_pixel(x, y).unwrap(), !glyph);
+            }
+        }
+        // Only the label's cells are inverted
+        assert!(!matrix.get_pixel(8, 0).unwrap());
+
+        matrix
+            .show_text_styled("11", Align::Left, TextStyle::Boxed)
+            .unwrap();
+        assert!(
+            (0..16).all(|x| matrix.get_pixel(x, 0).unwrap() && matrix.get_pixel(x, 7).unwrap())
+        );
+        assert!(matrix.get_pixel(0, 3).unwrap() && matrix.get_pixel(15, 3).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn test_tick_dimming_resends_dimmed_rows() {
+        let expected = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit0.addr(), 0xAA]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        assert!(!matrix.tick_dimming().unwrap());
+
+        matrix.fill_region(0, 0, 8, 8, true).unwrap();
+        matrix
+            .set_dim_region(0, Some(DimRegion::new(0, 0, 8, 1, 4)))
+            .unwrap();
+        // Half of the top row is shown in each frame
+        assert!(matrix.tick_dimming().unwrap());
+        assert_eq!(matrix.dimmer().frame(), 1);
+        spi.done();
+    }
+
+    #[test]
+    fn test_crossfade_ramps_intensity_around_swap() {
+        let intensity = Register::Intensity.addr();
+        let mut expected = write_reg(intensity, 4);
+        for level in [3, 2, 1, 0] {
+            expected.extend(write_reg(intensity, level));
+        }
+        expected.extend(write_reg(Register::Shutdown.addr(), 0));
+        for digit in Register::digits() {
+            expected.extend(write_reg(digit.addr(), 0xFF));
+        }
+        // The device was never powered on, so it stays shut down
+        expected.extend(write_reg(Register::Shutdown.addr(), 0));
+        for level in [1, 2, 3, 4] {
+            expected.extend(write_reg(intensity, level));
+        }
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi);
+        driver.set_intensity(0, Brightness::saturating(4)).unwrap();
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        matrix.crossfade(&mut NoopDelay, &[1; 64], 300).unwrap();
+        assert_eq!(matrix.framebuffer(), &[1; 64]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_crossfade_leaves_other_devices_alone() {
+        let (intensity, noop) = (Register::Intensity.addr(), Register::NoOp.addr());
+        let write = |register: Register, data: u8| {
+            vec![
+                Transaction::transaction_start(),
+                Transaction::write_vec(vec![noop, 0, register.addr(), data]),
+                Transaction::transaction_end(),
+            ]
+        };
+        let mut expected = vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![intensity, 4, intensity, 4]),
+            Transaction::transaction_end(),
+        ];
+        for level in [3, 2, 1, 0] {
+            expected.extend(write(Register::Intensity, level));
+        }
+        expected.extend(write(Register::Shutdown, 0));
+        for digit in Register::digits() {
+            expected.extend(write(digit, 0xFF));
+        }
+        expected.extend(write(Register::Shutdown, 0));
+        for level in [1, 2, 3, 4] {
+            expected.extend(write(Register::Intensity, level));
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        driver
+            .write_all_registers(&[(Register::Intensity, 4), (Register::Intensity, 4)])
+            .unwrap();
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix]).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_layout(layout)
+            .unwrap();
+
+        matrix.crossfade(&mut NoopDelay, &[1; 128], 300).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_wipe_step_uses_logical_coordinates() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2).serpentine(true);
+
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_mapper(&GRID)
+            .unwrap();
+        let full = [1; 256];
+
+        // Half way through, the left 8 columns of the whole wall are revealed
+        matrix.wipe_step(&full, Wipe::LeftToRight, 500).unwrap();
+        for y in 0..16 {
+            assert!(matrix.get_pixel(7, y).unwrap());
+            assert!(!matrix.get_pixel(8, y).unwrap());
+        }
+
+        matrix.clear_buffer();
+        matrix.wipe_step(&full, Wipe::CenterOut, 250).unwrap();
+        assert!(matrix.get_pixel(7, 7).unwrap());
+        assert!(matrix.get_pixel(8, 9).unwrap());
+        assert!(!matrix.get_pixel(5, 8).unwrap());
+
+        matrix.wipe_step(&full, Wipe::Diagonal, 1000).unwrap();
+        assert_eq!(matrix.framebuffer(), &full);
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_glyph_clips() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        matrix.draw_glyph(-4, 6, &[0xFF; 8]).unwrap();
+        assert!(matrix.get_pixel(3, 6).unwrap());
+        assert!(matrix.get_pixel(3, 7).unwrap());
+        assert!(!matrix.get_pixel(4, 7).unwrap());
+        assert!(!matrix.get_pixel(0, 5).unwrap());
+
+        // Dark pixels are drawn too
+        matrix.draw_glyph(-4, 6, &[0xF0; 8]).unwrap();
+        assert!(!matrix.get_pixel(3, 6).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn test_buffered_draw_text_multi_device() {
+        let bitmap_h = STANDARD_LED_FONT.get_char('H');
+        let bitmap_i = STANDARD_LED_FONT.get_char('i');
+
+        // Flush reproduces the same layout draw_text would have sent directly
+        let mut expected_transactions = Vec::new();
+        for (row, digit_register) in Register::digits().enumerate() {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                digit_register.addr(),
+                bitmap_h[row],
+                digit_register.addr(),
+                bitmap_i[row],
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
+        matrix.set_update_mode(UpdateMode::Buffered);
+
+        matrix.draw_text("Hi").unwrap();
+        matrix.flush().unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_buffered_clear_does_not_write() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        matrix.framebuffer[3] = 1;
+        matrix.clear(0).unwrap();
+        assert_eq!(matrix.framebuffer, [0u8; 64]);
+
+        matrix.framebuffer[3] = 1;
+        matrix.clear_all().unwrap();
+        assert_eq!(matrix.framebuffer, [0u8; 64]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_buffered_invalid_index() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        assert_eq!(matrix.draw_char(1, 'A'), Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_through_keeps_framebuffer_in_sync() {
+        let mut expected_transactions = Vec::new();
+        for digit_register in Register::digits() {
+            expected_transactions.extend(write_reg(digit_register.addr(), 0b1000_0001));
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        matrix
+            .write_buffer(0, &MatrixBuffer::from_data([0b1000_0001; 8]))
+            .unwrap();
+
+        for row in 0..8 {
+            assert_eq!(matrix.framebuffer[row * 8], 1);
+            assert_eq!(matrix.framebuffer[row * 8 + 7], 1);
+            assert_eq!(matrix.framebuffer[row * 8 + 3], 0);
+        }
+        spi.done();
+    }
+
+    #[test]
+    fn test_layout_skips_other_devices() {
+        // Device 0 is a 7-segment module, device 1 an upside-down matrix
+        let mut expected_transactions = Vec::new();
+        for (digit, digit_register) in Register::digits().enumerate() {
+            let data = if digit == 7 { 0b0000_0001 } else { 0 };
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0,
+                digit_register.addr(),
+                data,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix])
+            .unwrap()
+            .with_mounting(1, Mounting::UpsideDown)
+            .unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_layout(layout)
+            .unwrap();
+
+        matrix.set_pixel(0, 0, true).unwrap();
+        assert_eq!(
+            matrix.set_pixel(8, 0, true),
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::SevenSegment
+            })
+        );
+        assert_eq!(
+            matrix.draw_char(0, 'A'),
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::SevenSegment
+            })
+        );
+        assert_eq!(
+            matrix.draw_glyph(4, 0, &[0; 8]),
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::SevenSegment
+            })
+        );
+        assert_eq!(
+            matrix.draw_columns(7, 0, &[0x00, 0x01]),
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::SevenSegment
+            })
+        );
+        let transform = GlyphTransform::default();
+        assert_eq!(
+            matrix.draw_text_transformed(4, 0, " ", &fonts::STANDARD_LED_FONT, transform),
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::SevenSegment
+            })
+        );
+        // Shifting doesn't move the glyph off the 7-segment device
+        assert_eq!(
+            matrix.draw_text_shifted(4, 0, " ", &fonts::STANDARD_LED_FONT, transform, |_| -4),
+            Err(Error::WrongDeviceKind {
+                device_index: 0,
+                kind: DeviceKind::SevenSegment
+            })
+        );
+        matrix.flush().unwrap();
+        spi.done();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_scroll_queue_puts_separator_between_messages() {
+        // One whole glyph per step: blank, "A", "-", "B", blank
+        let glyph = |ch| STANDARD_LED_FONT.lookup(ch).unwrap().unwrap();
+        let frames = [[0; 8], glyph('A'), glyph('-'), glyph('B'), [0; 8]];
+        let mut expected_transactions = Vec::new();
+        for rows in frames {
+            for (digit, data) in Register::digits().zip(rows) {
+                expected_transactions.extend(write_reg(digit.addr(), data));
+            }
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        let mut queue = crate::MessageQueue::new().with_separator("-");
+        queue.push("A");
+        queue.push("B");
+        let config = ScrollConfig {
+            pixels_per_step: 8,
+            ..ScrollConfig::default()
+        };
+        matrix
+            .scroll_queue(&mut NoopDelay::new(), &mut queue, config)
+            .unwrap();
+        assert!(queue.is_empty());
+        spi.done();
+    }
+
+    #[test]
+    fn test_scroller_skips_other_devices() {
+        // Device 0 is a 7-segment module and keeps its digits
+        let mut expected_transactions = Vec::new();
+        for digit_register in Register::digits() {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0,
+                digit_register.addr(),
+                0,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix]).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_layout(layout)
+            .unwrap();
+
+        let mut scroller =
+            ScrollingText::new(" ", &fonts::STANDARD_LED_FONT, ScrollConfig::default());
+        matrix.show_scroller(&mut scroller).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_writes_each_row_atomically() {
+        // One transaction per row carrying the packets of all four devices
+        let mut expected_transactions = Vec::new();
+        for (row, digit_register) in Register::digits().enumerate() {
+            let mut bytes = Vec::new();
+            for device_index in 0..4u8 {
+                bytes.extend([digit_register.addr(), (device_index << 4) | row as u8]);
+            }
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(bytes));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        for device_index in 0..4u8 {
+            let rows: [u8; 8] = core::array::from_fn(|row| (device_index << 4) | row as u8);
+            matrix
+                .write_buffer(device_index as usize, &MatrixBuffer::from_data(rows))
+                .unwrap();
+        }
+        matrix.flush().unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_boot_animation() {
+        let frames: [[u8; 8]; 5] = [
+            [0, 0, 0, 0x18, 0x18, 0, 0, 0],
+            [0, 0, 0x3C, 0x24, 0x24, 0x3C, 0, 0],
+            [0, 0x7E, 0x42, 0x42, 0x42, 0x42, 0x7E, 0],
+            [0xFF, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xFF],
+            [0; 8],
+        ];
+        let mut expected_transactions = Vec::new();
+        for rows in frames {
+            for (digit_register, data) in Register::digits().zip(rows) {
+                expected_transactions.extend(write_reg(digit_register.addr(), data));
+            }
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        matrix
+            .boot_animation(&mut NoopDelay::new(), BootAnimation::ExpandingBox)
+            .unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_columns_clips() {
+        let mut spi = SpiMock::new(&[]);
+        let mut matrix = SingleMatrix::from_driver(Max7219::new(&mut spi)).unwrap();
+        // Column 0 is left of the display and bit 7 of column 2 below it
+        matrix.draw_columns(-1, 2, &[0xFF, 0x01, 0x81]).unwrap();
+        assert!(matrix.get_pixel(0, 2).unwrap());
+        assert!(!matrix.get_pixel(0, 3).unwrap());
+        assert!(matrix.get_pixel(1, 2).unwrap());
+        let lit = matrix.framebuffer().iter().filter(|&&pixel| pixel != 0);
+        assert_eq!(lit.count(), 2);
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_text_transformed() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2);
+
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_mapper(&GRID)
+            .unwrap();
+        let font = LedFont::new(&[([0x80; 8], '|')]);
+        let transform = GlyphTransform {
+            bold: true,
+            wide: true,
+            tall: true,
+        };
+
+        matrix
+            .draw_text_transformed(1, 0, "|", &font, transform)
+            .unwrap();
+        // A bold bar is two columns wide, doubled to four, and 16 tall
+        for y in 0..16 {
+            let lit: Vec<_> = (0..16)
+                .filter(|&x| matrix.get_pixel(x, y).unwrap())
+                .collect();
+            assert_eq!(lit, [1, 2, 3, 4]);
+        }
+
+        let strict = font.with_unsupported(CharPolicy::Error);
+        assert_eq!(
+            matrix.draw_text_transformed(0, 0, "|x", &strict, transform),
+            Err(Error::UnsupportedChar('x'))
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_text_shifted() {
+        let mut spi = SpiMock::new(&[]);
+        let mut matrix = Matrix4::from_driver(Max7219::new(&mut spi).with_device_count(4).unwrap())
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        let font = LedFont::new(&[([0x80; 8], '1'), ([0x80; 8], '2'), ([0x80; 8], '3')]);
+        let lit_rows = |matrix: &Matrix4<_>, x| -> Vec<usize> {
+            (0..8)
+                .filter(|&y| matrix.get_pixel(x, y).unwrap())
+                .collect()
+        };
+
+        // "1" raised by 5 pixels and "2" lowered by 6, both clipped
+        let shift = |ch| if ch == '1' { -5 } else { 6 };
+        matrix
+            .draw_text_shifted(0, 0, "12", &font, GlyphTransform::default(), shift)
+            .unwrap();
+        assert_eq!(lit_rows(&matrix, 0), [0, 1, 2]);
+        assert_eq!(lit_rows(&matrix, 8), [6, 7]);
+
+        // A clip band cuts a shifted glyph at its edges
+        matrix.clear_buffer();
+        matrix
+            .draw_clipped(0, 2, 32, 3, |matrix| {
+                matrix.draw_text_shifted(16, 1, "3", &font, GlyphTransform::default(), |_| 0)
+            })
+            .unwrap();
+        assert_eq!(lit_rows(&matrix, 16), [2, 3, 4]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_show_columns() {
+        // A lit top row across the left half, and a full column at x = 7
+        let mut columns = [0x01u8; 8];
+        columns[3] = 0;
+        columns[7] = 0xFF;
+        let mut expected = Vec::new();
+        for (digit, data) in [0xEF, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01]
+            .into_iter()
+            .enumerate()
+        {
+            expected.extend(write_reg(digit as u8 + 1, data));
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let mut matrix = SingleMatrix::from_driver(Max7219::new(&mut spi)).unwrap();
+        matrix.show_columns(&columns).unwrap();
+        assert!(matrix.get_pixel(0, 0).unwrap());
+        assert!(!matrix.get_pixel(3, 0).unwrap());
+        spi.done();
+
+        // The 7-segment device 0 is skipped, so the strip starts on device 1
+        let mut expected_transactions = Vec::new();
+        for digit_register in Register::digits() {
+            expected_transactions.extend([
+                Transaction::transaction_start(),
+                Transaction::write_vec(vec![Register::NoOp.addr(), 0, digit_register.addr(), 0xFF]),
+                Transaction::transaction_end(),
+            ]);
+        }
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix]).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_layout(layout)
+            .unwrap();
+        matrix.show_columns(&[0xFF; 16]).unwrap();
+        assert!(matrix.get_pixel(0, 0).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn test_play_animation() {
+        let frames: [[u8; 8]; 2] = [[0x18; 8], [0x81, 0, 0, 0, 0, 0, 0, 0x81]];
+        let mut data = [0; 32];
+        let len = encode_rle(frames.as_flattened(), 1, &mut data).unwrap();
+
+        let mut expected_transactions = Vec::new();
+        for rows in frames {
+            for (digit_register, data) in Register::digits().zip(rows) {
+                let addr = digit_register.addr();
+                expected_transactions.extend([
+                    Transaction::transaction_start(),
+                    Transaction::write_vec(vec![addr, data, addr, data]),
+                    Transaction::transaction_end(),
+                ]);
+            }
+        }
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
+        matrix
+            .play_animation(
+                &mut NoopDelay::new(),
+                &Animation::from_rle(&data[..len]).unwrap(),
+                40,
+            )
+            .unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_rows() {
+        let mut expected_transactions = Vec::new();
+        expected_transactions.extend(write_reg(Register::Digit2.addr(), 0b1000_0000));
+        expected_transactions.extend(write_reg(Register::Digit3.addr(), 0b0000_0000));
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        matrix.framebuffer[2 * 8] = 1; // row 2, column 0
+        matrix.flush_rows(2..4).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_rows_invalid() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        assert_eq!(matrix.flush_rows(7..9), Err(Error::BufferError));
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_region_skips_untouched_devices() {
+        // Region covers x = 9..=10 (framebuffer block 1) on rows 0 and 1.
+        // Block 1 is the second device from the end of the SPI stream.
+        let mut expected_transactions = Vec::new();
+        for (digit_register, data) in [(Register::Digit0, 0b0110_0000), (Register::Digit1, 0)] {
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0x00,
+                Register::NoOp.addr(),
+                0x00,
+                digit_register.addr(),
+                data,
+                Register::NoOp.addr(),
+                0x00,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver).unwrap();
+
+        matrix.framebuffer[64 + 1] = 1; // block 1, row 0, column 1
+        matrix.framebuffer[64 + 2] = 1; // block 1, row 0, column 2
+        matrix.framebuffer[1] = 1; // block 0 is outside the region
+        matrix.flush_region(9, 0, 2, 2).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_device_major_order() {
+        let mut expected_transactions = Vec::new();
+        // Furthest device first, its rows with a no-op for the other device;
+        // the pixel at (0, 0) is on the nearest device
+        for (device, pixel) in [(0, 0), (1, 0b1000_0000)] {
+            for digit in Register::digits() {
+                let value = if digit == Register::Digit0 { pixel } else { 0 };
+                let mut packet = vec![0; 4];
+                packet[device * 2] = digit.addr();
+                packet[device * 2 + 1] = value;
+                expected_transactions.push(Transaction::transaction_start());
+                expected_transactions.push(Transaction::write_vec(packet));
+                expected_transactions.push(Transaction::transaction_end());
+            }
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_flush_order(FlushOrder::DeviceMajor);
+        assert_eq!(matrix.flush_order(), FlushOrder::DeviceMajor);
+
+        matrix.set_pixel(0, 0, true).unwrap();
+        matrix.flush().unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_with_tee() {
+        #[derive(Default)]
+        struct Recorder {
+            frames: usize,
+            nearest_row0: u8,
+        }
+        impl FrameSink for Recorder {
+            fn write_rows(&mut self, device_rows: &[[u8; 8]]) -> crate::Result<()> {
+                self.frames += 1;
+                self.nearest_row0 = device_rows[1][0];
+                Ok(())
+            }
+        }
+
+        let mut expected_transactions = Vec::new();
+        for digit in Register::digits() {
+            let value = if digit == Register::Digit0 {
+                0b1000_0000
+            } else {
+                0
+            };
+            expected_transactions.push(Transaction::transaction_start());
+            expected_transactions.push(Transaction::write_vec(vec![
+                digit.addr(),
+                0,
+                digit.addr(),
+                value,
+            ]));
+            expected_transactions.push(Transaction::transaction_end());
+        }
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut recorder = Recorder::default();
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        matrix.set_pixel(0, 0, true).unwrap();
+        matrix.flush_with_tee(&mut recorder).unwrap();
 
-    pub use embedded_graphics_core::pixelcolor::BinaryColor;
-    pub use embedded_graphics_core::prelude::{DrawTarget, OriginDimensions, Size};
-}
+        assert_eq!(recorder.frames, 1);
+        assert_eq!(recorder.nearest_row0, 0b1000_0000);
+        spi.done();
+    }
 
-#[cfg(feature = "graphics")]
-use eg_imports::*;
-#[cfg(feature = "graphics")]
-use embedded_graphics_core::geometry::Dimensions;
+    #[test]
+    fn test_blank_during_flush() {
+        let mut expected_transactions = write_reg(Register::Shutdown.addr(), 0x01);
+        // A single row is written without blanking
+        expected_transactions.extend(write_reg(Register::Digit0.addr(), 0b1000_0000));
+        expected_transactions.extend(write_reg(Register::Shutdown.addr(), 0x00));
+        for digit in Register::digits() {
+            let value = if digit == Register::Digit0 {
+                0b1000_0000
+            } else {
+                0
+            };
+            expected_transactions.extend(write_reg(digit.addr(), value));
+        }
+        expected_transactions.extend(write_reg(Register::Shutdown.addr(), 0x01));
 
-// Implementing embedded-graphics DrawTarget for LedMatrix
-#[cfg(feature = "graphics")]
-impl<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize> DrawTarget
-    for LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT>
-where
-    SPI: SpiDevice,
-{
-    type Color = BinaryColor;
-    type Error = core::convert::Infallible;
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(1).unwrap();
+        let mut matrix: SingleMatrix<_> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_blank_during_flush(true);
+        matrix.driver().power_on().unwrap();
+
+        matrix.set_pixel(0, 0, true).unwrap();
+        matrix.flush_rows(0..1).unwrap();
+        matrix.flush().unwrap();
+        spi.done();
+    }
 
-    fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
-    where
-        I: IntoIterator<Item = Pixel<Self::Color>>,
-    {
-        let bb = self.bounding_box();
-        for Pixel(pos, color) in pixels.into_iter() {
-            if bb.contains(pos) {
-                let device = (pos.x as usize) / 8;
-                let col = (pos.x as usize) % 8;
-                let row = pos.y as usize;
-
-                if device < DEVICE_COUNT && row < 8 && col < 8 {
-                    let index = device * 64 + row * 8 + col;
-                    if index < self.framebuffer.len() {
-                        self.framebuffer[index] = color.is_on() as u8;
+    #[test]
+    fn test_blank_during_flush_restores_power_on_error() {
+        use embedded_hal::spi::{ErrorKind, ErrorType, Operation, SpiDevice};
+
+        /// Records the written frames and fails the fourth transaction
+        #[derive(Default)]
+        struct FlakySpi {
+            writes: Vec<Vec<u8>>,
+            transactions: usize,
+        }
+        impl ErrorType for FlakySpi {
+            type Error = ErrorKind;
+        }
+        impl SpiDevice for FlakySpi {
+            fn transaction(
+                &mut self,
+                operations: &mut [Operation<'_, u8>],
+            ) -> Result<(), ErrorKind> {
+                self.transactions += 1;
+                if self.transactions == 4 {
+                    return Err(ErrorKind::Other);
+                }
+                for operation in operations {
+                    if let Operation::Write(bytes) = operation {
+                        self.writes.push(bytes.to_vec());
                     }
                 }
+                Ok(())
             }
         }
-        // Note: Does not call self.flush() automatically.
-        Ok(())
-    }
-}
 
-#[cfg(feature = "graphics")]
-impl<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize> OriginDimensions
-    for LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT>
-{
-    fn size(&self) -> Size {
-        Size::new(DEVICE_COUNT as u32 * 8, 8)
+        let mut matrix: SingleMatrix<_> = LedMatrix::from_driver(Max7219::new(FlakySpi::default()))
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered)
+            .with_blank_during_flush(true);
+        matrix.driver().power_on().unwrap();
+
+        // Power on, shutdown, Digit0, Digit1 fails, shutdown undone
+        assert_eq!(matrix.flush(), Err(Error::SpiError));
+        let shutdown = Register::Shutdown.addr();
+        let writes = &matrix.driver().spi().writes;
+        assert_eq!(writes.len(), 4);
+        assert_eq!(writes[1], [shutdown, 0x00]);
+        assert_eq!(writes[3], [shutdown, 0x01]);
+        assert!(matrix.driver().device_state(0).unwrap().is_powered_on());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::Error;
-    use crate::led_matrix::display::{Matrix4, SingleMatrix};
-    use crate::led_matrix::fonts::STANDARD_LED_FONT;
-    use crate::led_matrix::{LedMatrix, buffer::MatrixBuffer, fonts::LedFont};
-    use crate::registers::Register;
-    use crate::{Max7219, NUM_DIGITS};
-    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
 
-    fn write_reg(addr: u8, value: u8) -> Vec<Transaction<u8>> {
-        vec![
+    #[test]
+    fn test_flush_region_spanning_devices() {
+        let expected_transactions = [
             Transaction::transaction_start(),
-            Transaction::write_vec(vec![addr, value]),
+            Transaction::write_vec(vec![
+                Register::Digit7.addr(),
+                0b1000_0000,
+                Register::Digit7.addr(),
+                0b0000_0001,
+            ]),
             Transaction::transaction_end(),
-        ]
+        ];
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
+
+        matrix.framebuffer[7 * 8 + 7] = 1; // block 0, row 7, column 7
+        matrix.framebuffer[64 + 7 * 8] = 1; // block 1, row 7, column 0
+        matrix.flush_region(6, 7, 4, 1).unwrap();
+        spi.done();
     }
 
     #[test]
-    fn test_new() {
-        let mut spi = SpiMock::new(&[]);
+    fn test_flush_column_major_wiring() {
+        // Top row lit => every column register gets bit 7
+        let mut expected_transactions = Vec::new();
+        for digit_register in Register::digits() {
+            expected_transactions.extend(write_reg(digit_register.addr(), 0b1000_0000));
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
         let driver = Max7219::new(&mut spi);
-        let matrix: LedMatrix<_, 64, 1> = LedMatrix::from_driver(driver).unwrap();
-        assert_eq!(matrix.framebuffer, [0u8; 64]);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_wiring(Wiring::GENERIC);
+
+        matrix.framebuffer[..8].fill(1);
+        matrix.flush().unwrap();
         spi.done();
     }
 
     #[test]
-    fn test_from_spi() {
-        let mut expected_transactions: Vec<Transaction<u8>> = vec![];
-
-        // power_on
-        expected_transactions.extend(write_reg(Register::Shutdown.addr(), 0x01));
+    fn test_flush_region_column_major_touches_columns() {
+        // A single pixel at column 2 lives in digit register 2
+        let mut expected_transactions = Vec::new();
+        expected_transactions.extend(write_reg(Register::Digit2.addr(), 0b0000_0001));
 
-        // test_all(false)
-        expected_transactions.extend(write_reg(Register::DisplayTest.addr(), 0x00));
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_wiring(Wiring::GENERIC);
 
-        // set_scan_limit_all(NUM_DIGITS)
-        expected_transactions.extend(write_reg(Register::ScanLimit.addr(), NUM_DIGITS - 1));
+        matrix.framebuffer[7 * 8 + 2] = 1; // row 7, column 2
+        matrix.flush_region(2, 7, 1, 1).unwrap();
+        spi.done();
+    }
 
-        // set_decode_mode_all(NoDecode)
-        expected_transactions.extend(write_reg(
-            Register::DecodeMode.addr(),
-            crate::registers::DecodeMode::NoDecode as u8,
-        ));
+    #[test]
+    fn test_draw_char_applies_wiring() {
+        let wiring = Wiring::new(Orientation::RowMajor, true, false);
+        let bitmap = STANDARD_LED_FONT.get_char('T');
+        let digits = wiring.apply(&bitmap);
 
-        // clear_all() - 8 digits/rows
-        for digit in Register::digits() {
-            expected_transactions.extend(write_reg(digit.addr(), 0x00));
+        let mut expected_transactions = Vec::new();
+        for (digit, digit_register) in Register::digits().enumerate() {
+            expected_transactions.extend(write_reg(digit_register.addr(), digits[digit]));
         }
 
         let mut spi = SpiMock::new(&expected_transactions);
-        let result: crate::Result<LedMatrix<_, 64, 1>> = LedMatrix::from_spi(&mut spi);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        matrix.set_device_wiring(0, wiring).unwrap();
+        assert_eq!(matrix.device_wiring(0), Ok(wiring));
 
-        assert!(result.is_ok());
+        matrix.draw_char(0, 'T').unwrap();
         spi.done();
     }
 
     #[test]
-    fn test_from_spi_invalid_count() {
+    fn test_device_wiring_invalid_index() {
         let mut spi = SpiMock::new(&[]);
         let driver = Max7219::new(&mut spi);
-        // not valid count
-        let result = LedMatrix::<_, 1024, 256>::from_driver(driver);
-        assert!(matches!(result, Err(Error::InvalidDeviceCount)));
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
 
-        // Mismatched device count
-        let driver = Max7219::new(&mut spi);
-        let result = Matrix4::from_driver(driver);
-        assert!(matches!(result, Err(Error::InvalidDeviceCount)));
+        assert_eq!(
+            matrix.set_device_wiring(1, Wiring::GENERIC),
+            Err(Error::InvalidDeviceIndex)
+        );
+        assert_eq!(matrix.device_wiring(1), Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
+
+    #[test]
+    fn test_set_get_pixel() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
+
+        assert_eq!((matrix.width(), matrix.height()), (16, 8));
+        matrix.set_pixel(9, 1, true).unwrap();
+        assert!(matrix.get_pixel(9, 1).unwrap());
+        assert_eq!(matrix.framebuffer[64 + 8 + 1], 1);
+        matrix.set_pixel(9, 1, false).unwrap();
+        assert!(!matrix.get_pixel(9, 1).unwrap());
 
+        assert_eq!(matrix.set_pixel(16, 0, true), Err(Error::BufferError));
+        assert_eq!(matrix.get_pixel(0, 8), Err(Error::BufferError));
         spi.done();
     }
 
     #[test]
-    fn test_clear() {
-        let mut expected_transactions = vec![];
-        for digit_register in Register::digits() {
-            expected_transactions.extend(write_reg(digit_register.addr(), 0x00));
-        }
+    fn test_with_mapper_invalid_size() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2);
 
-        let mut spi = SpiMock::new(&expected_transactions);
-        let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
 
-        let result = matrix.clear(0);
-        assert!(result.is_ok());
+        assert!(matches!(
+            matrix.with_mapper(&GRID),
+            Err(Error::InvalidMapping)
+        ));
         spi.done();
     }
 
     #[test]
-    fn test_clear_all() {
-        // Test clearing all devices (but checking with 1 device for SingleMatrix)
-        let mut expected_transactions = vec![];
-        let device_count = 4;
+    fn test_flush_with_tiled_mapper() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2);
 
-        for digit_register in Register::digits() {
-            expected_transactions.push(Transaction::transaction_start());
-            let batched_data = (0..device_count)
-                .flat_map(|_| vec![digit_register.addr(), 0x00])
-                .collect();
-            expected_transactions.push(Transaction::write_vec(batched_data));
-            expected_transactions.push(Transaction::transaction_end());
-        }
+        // Pixel (0, 8) is the top-left LED of the bottom-left tile, which is
+        // chain position 2 (driver device 1).
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0x00,
+                Register::Digit0.addr(),
+                0b1000_0000,
+                Register::NoOp.addr(),
+                0x00,
+                Register::NoOp.addr(),
+                0x00,
+            ]),
+            Transaction::transaction_end(),
+        ];
 
         let mut spi = SpiMock::new(&expected_transactions);
         let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
-        let mut matrix = Matrix4::from_driver(driver).expect("valid initialization");
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_mapper(&GRID)
+            .unwrap();
 
-        let result = matrix.clear_all();
-        assert!(result.is_ok());
+        assert_eq!((matrix.width(), matrix.height()), (16, 16));
+        matrix.set_pixel(0, 8, true).unwrap();
+        matrix.flush_region(0, 8, 1, 1).unwrap();
         spi.done();
     }
 
     #[test]
-    fn test_write_buffer() {
-        let device_index = 0;
-        let mut buffer = MatrixBuffer::new();
-        // Fill buffer with some test data
-        for i in 0..8 {
-            buffer.set_row(i, 0b10101010 << (i % 2)).unwrap();
-        }
-        let expected_data = buffer.data();
+    fn test_draw_char_with_tiled_mapper() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2);
+
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_mapper(&GRID)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        // Driver device 0 is the last module in the chain: bottom-right tile
+        matrix
+            .write_buffer(0, &MatrixBuffer::from_data([0xFF; 8]))
+            .unwrap();
+        assert!(matrix.get_pixel(8, 8).unwrap());
+        assert!(matrix.get_pixel(15, 15).unwrap());
+        assert!(!matrix.get_pixel(7, 7).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn test_show_test_pattern_device_index() {
+        let zero = STANDARD_LED_FONT.get_char('0');
+        let one = STANDARD_LED_FONT.get_char('1');
 
         let mut expected_transactions = Vec::new();
-        for (row, &data) in expected_data.iter().enumerate() {
+        for (row, digit_register) in Register::digits().enumerate() {
             expected_transactions.push(Transaction::transaction_start());
             expected_transactions.push(Transaction::write_vec(vec![
-                Register::try_digit(row as u8).unwrap().addr(),
-                data,
+                digit_register.addr(),
+                zero[row],
+                digit_register.addr(),
+                one[row],
             ]));
             expected_transactions.push(Transaction::transaction_end());
         }
 
         let mut spi = SpiMock::new(&expected_transactions);
-        let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
 
-        let result = matrix.write_buffer(device_index, &buffer);
-        assert!(result.is_ok());
+        matrix.show_test_pattern(TestPattern::DeviceIndex).unwrap();
         spi.done();
     }
 
     #[test]
-    fn test_write_buffer_invalid_index() {
-        let mut spi = SpiMock::new(&[]); // No SPI calls expected
-        let driver = Max7219::new(&mut spi).with_device_count(1).unwrap();
-        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
-        let buffer = MatrixBuffer::new();
+    fn test_flush_region_invalid() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
 
-        let result = matrix.write_buffer(1, &buffer); // Index 1 is invalid for device_count=1
-        // This error comes from Max7219::write_raw_digit via write_device_register
-        assert_eq!(result, Err(Error::InvalidDeviceIndex));
+        assert_eq!(matrix.flush_region(4, 0, 5, 1), Err(Error::BufferError));
+        assert_eq!(matrix.flush_region(0, 7, 1, 2), Err(Error::BufferError));
+        assert_eq!(matrix.flush_region(0, 0, 0, 8), Ok(()));
         spi.done();
     }
 
     #[test]
-    fn test_draw_char() {
-        let device_index = 0;
-        let ch = 'A';
-        let expected_bitmap = STANDARD_LED_FONT.get_char(ch);
-
+    fn test_tick_heartbeat_flushes_mark_only() {
+        // Pixel (31, 7) lives on the device furthest from the MCU (driver index 0)
         let mut expected_transactions = Vec::new();
-        for (row, &data) in expected_bitmap.iter().enumerate() {
+        for data in [0b0000_0000, 0b0000_0001] {
             expected_transactions.push(Transaction::transaction_start());
             expected_transactions.push(Transaction::write_vec(vec![
-                Register::try_digit(row as u8).unwrap().addr(),
+                Register::Digit7.addr(),
                 data,
+                Register::NoOp.addr(),
+                0x00,
+                Register::NoOp.addr(),
+                0x00,
+                Register::NoOp.addr(),
+                0x00,
             ]));
             expected_transactions.push(Transaction::transaction_end());
         }
 
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver).unwrap();
+        matrix.set_heartbeat(Some(Heartbeat::pixel(31, 7, 1000)));
+
+        // Starts lit; goes dark after half a period, then lit again
+        assert_eq!(matrix.tick_heartbeat(400), Ok(false));
+        assert_eq!(matrix.tick_heartbeat(100), Ok(true));
+        assert_eq!(matrix.tick_heartbeat(500), Ok(true));
+        spi.done();
+    }
+
+    #[test]
+    fn test_heartbeat_overlay_leaves_framebuffer() {
+        let mut expected_transactions = Vec::new();
+        for digit_register in Register::digits() {
+            // Top-left 2x2 corner is inverted against the lit top row
+            let data = match digit_register {
+                Register::Digit0 => 0b0011_1111,
+                Register::Digit1 => 0b1100_0000,
+                _ => 0x00,
+            };
+            expected_transactions.extend(write_reg(digit_register.addr(), data));
+        }
+
         let mut spi = SpiMock::new(&expected_transactions);
         let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        matrix.set_heartbeat(Some(Heartbeat::corner(Corner::TopLeft, 1000)));
+        for x in 0..8 {
+            matrix.framebuffer[x] = 1;
+        }
 
-        let result = matrix.draw_char(device_index, ch);
-        assert!(result.is_ok());
+        matrix.flush().unwrap();
+        assert_eq!(matrix.get_pixel(0, 0), Ok(true));
+        assert_eq!(matrix.get_pixel(0, 1), Ok(false));
+        spi.done();
+    }
+
+    #[test]
+    fn test_tick_pixel_shift_moves_frame() {
+        let mut expected_transactions = Vec::new();
+        for digit_register in Register::digits() {
+            // Top-left pixel shown one column to the right
+            let data = match digit_register {
+                Register::Digit0 => 0b0100_0000,
+                _ => 0x00,
+            };
+            expected_transactions.extend(write_reg(digit_register.addr(), data));
+        }
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        assert_eq!(matrix.tick_pixel_shift(1000), Ok(false));
+        matrix.set_pixel_shift(Some(PixelShift::new(1000)));
+        matrix.framebuffer[0] = 1;
+
+        assert_eq!(matrix.tick_pixel_shift(999), Ok(false));
+        assert_eq!(matrix.tick_pixel_shift(1), Ok(true));
+        assert_eq!(matrix.pixel_shift().map(PixelShift::offset), Some((1, 0)));
+        assert_eq!(matrix.get_pixel(0, 0), Ok(true));
+        spi.done();
+    }
+
+    #[test]
+    fn test_flush_rows_follows_pixel_shift() {
+        // Row 0 is shown on row 1, one column to the right
+        let expected_transactions = write_reg(Register::Digit1.addr(), 0b0100_0000);
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        let mut shift = PixelShift::new(1);
+        shift.tick(1);
+        shift.tick(1);
+        assert_eq!(shift.offset(), (1, 1));
+        matrix.set_pixel_shift(Some(shift));
+        matrix.framebuffer[0] = 1;
+
+        matrix.flush_rows(0..1).unwrap();
+        // Shifted past the bottom edge, nothing to send
+        matrix.flush_rows(7..8).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_tick_heartbeat_without_heartbeat() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+
+        assert!(matrix.heartbeat().is_none());
+        assert_eq!(matrix.tick_heartbeat(10_000), Ok(false));
         spi.done();
     }
 
     #[test]
-    fn test_draw_char_with_font() {
-        let device_index = 0;
-        let ch = 'Z';
-        let test_font = STANDARD_LED_FONT;
-        let expected_bitmap = test_font.get_char(ch);
-
-        let mut expected_transactions = Vec::new();
-        for (row, &data) in expected_bitmap.iter().enumerate() {
-            expected_transactions.push(Transaction::transaction_start());
-            expected_transactions.push(Transaction::write_vec(vec![
-                Register::try_digit(row as u8).unwrap().addr(),
-                data,
-            ]));
-            expected_transactions.push(Transaction::transaction_end());
-        }
-
-        let mut spi = SpiMock::new(&expected_transactions);
+    fn test_invert() {
+        let mut spi = SpiMock::new(&[]);
         let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        matrix.set_pixel(2, 3, true).unwrap();
 
-        matrix
-            .draw_char_with_font(device_index, ch, &test_font)
-            .expect("valid character");
+        matrix.invert();
+        assert_eq!(matrix.get_pixel(2, 3), Ok(false));
+        assert_eq!(matrix.get_pixel(0, 0), Ok(true));
+        assert_eq!(matrix.framebuffer.iter().filter(|&&p| p != 0).count(), 63);
         spi.done();
     }
 
     #[test]
-    fn test_draw_char_invalid_index() {
-        let mut spi = SpiMock::new(&[]); // No SPI calls expected
-        let driver = Max7219::new(&mut spi).with_device_count(1).unwrap();
-        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+    fn test_invert_region() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver).unwrap();
+        matrix.set_pixel(7, 0, true).unwrap();
 
-        let result = matrix.draw_char(1, 'A'); // Index 1 is invalid for device_count=1
-        // This error comes from Max7219::write_raw_digit via write_device_register
-        assert_eq!(result, Err(Error::InvalidDeviceIndex));
+        // Spans the first two modules
+        matrix.invert_region(6, 0, 4, 2).unwrap();
+        assert_eq!(matrix.get_pixel(6, 0), Ok(true));
+        assert_eq!(matrix.get_pixel(7, 0), Ok(false));
+        assert_eq!(matrix.get_pixel(9, 1), Ok(true));
+        assert_eq!(matrix.get_pixel(10, 1), Ok(false));
+        assert_eq!(matrix.get_pixel(6, 2), Ok(false));
+
+        assert_eq!(matrix.invert_region(30, 0, 3, 1), Err(Error::BufferError));
         spi.done();
     }
 
     #[test]
-    fn test_draw_text_single_device() {
-        let text = "H";
-        let expected_ch = 'H';
-        let expected_bitmap = STANDARD_LED_FONT.get_char(expected_ch);
-        // For 1 device, text is written to device 0 (device_count - 1 - i = 0)
-        // Data is sent row by row using write_all_registers
-
+    fn test_fill_region_then_flush() {
         let mut expected_transactions = Vec::new();
-        for (row_index, &data) in expected_bitmap.iter().enumerate() {
-            let digit_register = Register::try_digit(row_index as u8).unwrap();
-            expected_transactions.push(Transaction::transaction_start());
-            // For 1 device, ops[0] = (digit_register, data)
-            expected_transactions.push(Transaction::write_vec(vec![digit_register.addr(), data]));
-            expected_transactions.push(Transaction::transaction_end());
+        for digit_register in Register::digits() {
+            let data = match digit_register {
+                Register::Digit2 | Register::Digit3 => 0b0011_1100,
+                _ => 0x00,
+            };
+            expected_transactions.extend(write_reg(digit_register.addr(), data));
         }
 
         let mut spi = SpiMock::new(&expected_transactions);
         let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).expect("valid initialization");
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
 
-        let result = matrix.draw_text(text);
-        assert!(result.is_ok());
+        matrix.fill_region(0, 0, 8, 8, true).unwrap();
+        matrix.fill_region(0, 0, 8, 2, false).unwrap();
+        matrix.fill_region(0, 4, 8, 4, false).unwrap();
+        matrix.fill_region(0, 2, 2, 2, false).unwrap();
+        matrix.fill_region(6, 2, 2, 2, false).unwrap();
+        matrix.flush().unwrap();
+
+        assert_eq!(
+            matrix.fill_region(0, 7, 1, 2, true),
+            Err(Error::BufferError)
+        );
         spi.done();
     }
 
     #[test]
-    fn test_draw_text_multi_device() {
-        let device_count = 4;
+    fn test_draw_line() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver).unwrap();
 
-        let text = "Hi";
-        let expected_bitmap_h = STANDARD_LED_FONT.get_char('H');
-        let expected_bitmap_i = STANDARD_LED_FONT.get_char('i');
-        // Text chars are taken in order: 'H', 'i'
-        // They are written to devices in reverse order: 'H' -> device 1, 'i' -> device 0
-        // (because device_index = device_count - 1 - i)
+        matrix.draw_line(0, 0, 7, 7, true).unwrap();
+        for i in 0..8 {
+            assert_eq!(matrix.get_pixel(i, i), Ok(true));
+        }
 
-        let mut expected_transactions = Vec::new();
-        // Row by row transmission
-        for row_index in 0..8 {
-            let digit_register = Register::try_digit(row_index).unwrap();
-            expected_transactions.push(Transaction::transaction_start());
-            // ops[0] = (digit_register, data_for_device_1) // Sent first on SPI
-            // ops[1] = (digit_register, data_for_device_0) // Sent second, ends up in device 0
-            // Remaining devices gets filled with 0
-            let data_for_device_1 = expected_bitmap_h[row_index as usize];
-            let data_for_device_0 = expected_bitmap_i[row_index as usize];
-            expected_transactions.push(Transaction::write_vec(vec![
-                digit_register.addr(),
-                data_for_device_1, // Data for device 1
-                digit_register.addr(),
-                data_for_device_0, // Data for device 0
-                digit_register.addr(),
-                0x00,
-                digit_register.addr(),
-                0x00,
-            ]));
-            expected_transactions.push(Transaction::transaction_end());
+        // Horizontal line across module boundaries, drawn right to left
+        matrix.draw_line(20, 3, 5, 3, true).unwrap();
+        for x in 5..=20 {
+            assert_eq!(matrix.get_pixel(x, 3), Ok(true));
         }
+        assert_eq!(matrix.get_pixel(21, 3), Ok(false));
 
-        let mut spi = SpiMock::new(&expected_transactions);
-        let driver = Max7219::new(&mut spi)
-            .with_device_count(device_count)
-            .unwrap();
-        let mut matrix = Matrix4::from_driver(driver).unwrap();
+        // Shallow line lights one pixel per column
+        matrix.clear_buffer();
+        matrix.draw_line(0, 0, 31, 7, true).unwrap();
+        for x in 0..32 {
+            let lit = (0..8)
+                .filter(|&y| matrix.get_pixel(x, y) == Ok(true))
+                .count();
+            assert_eq!(lit, 1);
+        }
 
-        let result = matrix.draw_text(text);
-        assert!(result.is_ok());
+        assert_eq!(matrix.draw_line(0, 0, 32, 0, true), Err(Error::BufferError));
         spi.done();
     }
 
     #[test]
-    fn test_draw_text_with_font() {
-        let text = "!";
-        // Create a simple test font
-        pub const TEST_FONT: &[([u8; 8], char)] = &[([0b10101010; 8], '!')];
-        let test_font = LedFont::new(TEST_FONT);
-        let expected_bitmap = [0b10101010u8; 8];
-        let device_count = 2;
-
+    fn test_draw_die() {
         let mut expected_transactions = Vec::new();
+        for (digit_register, data) in Register::digits().zip(games::die_face(2).unwrap().data()) {
+            expected_transactions.extend(write_reg(digit_register.addr(), *data));
+        }
 
-        // For each row (Digit0 to Digit7)
-        for (row_index, &data) in expected_bitmap.iter().enumerate() {
-            let digit_register = Register::try_digit(row_index as u8).unwrap();
-            expected_transactions.push(Transaction::transaction_start());
+        let mut spi = SpiMock::new(&expected_transactions);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
 
-            // First device gets the font data, second device gets zero
-            expected_transactions.push(Transaction::write_vec(vec![
-                digit_register.addr(),
-                data, // Device 0
-                digit_register.addr(),
-                0x00, // Device 1 (no text)
-            ]));
+        matrix.draw_die(0, 2).unwrap();
+        assert_eq!(matrix.draw_die(0, 7), Err(Error::InvalidDigit));
+        assert_eq!(matrix.draw_die(1, 1), Err(Error::InvalidDeviceIndex));
+        spi.done();
+    }
 
-            expected_transactions.push(Transaction::transaction_end());
+    #[test]
+    fn test_draw_card_rank_buffered() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        matrix.draw_card_rank(0, CardRank::King).unwrap();
+        let king = STANDARD_LED_FONT.get_char('K');
+        for (y, row) in king.iter().enumerate() {
+            for x in 0..8 {
+                assert_eq!(matrix.get_pixel(x, y), Ok(row & (0x80 >> x) != 0));
+            }
         }
+        spi.done();
+    }
 
-        let mut spi = SpiMock::new(&expected_transactions);
-        let driver = Max7219::new(&mut spi)
-            .with_device_count(device_count)
-            .unwrap();
-        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
-
-        let result = matrix.draw_text_with_font(text, &test_font);
-        assert!(result.is_ok());
+    #[test]
+    fn test_verify_mapping() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2).serpentine(true);
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let matrix = Matrix4::from_driver(driver).unwrap();
+        assert_eq!(matrix.verify_mapping(), Ok(()));
 
+        let matrix = matrix.with_mapper(&GRID).unwrap();
+        assert_eq!(matrix.verify_mapping(), Ok(()));
         spi.done();
     }
 
     #[test]
-    fn test_clear_buffer() {
-        let mut spi = SpiMock::new(&[]); // No SPI interaction
-        let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+    fn test_verify_mapping_detects_overlap() {
+        /// Maps every module onto the same device
+        struct Overlapping;
 
-        // Modify the buffer
-        matrix.framebuffer[0] = 1;
-        matrix.framebuffer[10] = 1;
-        matrix.framebuffer[63] = 1;
-        assert_ne!(matrix.framebuffer, [0u8; 64]);
+        impl PixelMapper for Overlapping {
+            fn size(&self, device_count: usize) -> (usize, usize) {
+                (device_count * 8, 8)
+            }
 
-        matrix.clear_buffer();
+            fn map(&self, x: usize, y: usize, _device_count: usize) -> Option<PixelAddress> {
+                Some(PixelAddress {
+                    device: 0,
+                    digit: y as u8,
+                    bit: (x % 8) as u8,
+                })
+            }
+        }
 
-        assert_eq!(matrix.framebuffer, [0u8; 64]);
+        static OVERLAPPING: Overlapping = Overlapping;
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_mapper(&OVERLAPPING)
+            .unwrap();
+        assert_eq!(matrix.verify_mapping(), Err(Error::InvalidMapping));
         spi.done();
     }
 
     #[test]
-    fn test_clear_screen() {
-        // All digits 0..7 will be written with 0x00 for a single device
+    fn test_logical_position() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let matrix = Matrix4::from_driver(driver).unwrap();
+
+        let address = PixelAddress {
+            device: 3,
+            digit: 2,
+            bit: 7,
+        };
+        assert_eq!(matrix.logical_position(address), Some((0, 2)));
+        let address = PixelAddress {
+            device: 4,
+            digit: 0,
+            bit: 0,
+        };
+        assert_eq!(matrix.logical_position(address), None);
+        spi.done();
+    }
+
+    #[test]
+    fn test_layers_composite_at_flush() {
         let mut expected_transactions = Vec::new();
-        for row in 0..8 {
-            let digit_register = Register::try_digit(row).unwrap();
-            expected_transactions.push(Transaction::transaction_start());
-            expected_transactions.push(Transaction::write_vec(vec![digit_register.addr(), 0x00]));
-            expected_transactions.push(Transaction::transaction_end());
+        // Alert layer replaces the top-left 4x2 block, clearing the lit top row there
+        for digit_register in Register::digits() {
+            let data = match digit_register {
+                Register::Digit0 => 0b0000_1111,
+                _ => 0x00,
+            };
+            expected_transactions.extend(write_reg(digit_register.addr(), data));
+        }
+        // Hiding the alert layer brings back the framebuffer underneath
+        for digit_register in Register::digits() {
+            let data = match digit_register {
+                Register::Digit0 => 0xFF,
+                _ => 0x00,
+            };
+            expected_transactions.extend(write_reg(digit_register.addr(), data));
         }
 
         let mut spi = SpiMock::new(&expected_transactions);
         let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
+        let mut matrix: LedMatrix<_, 64, 1, 1> = LedMatrix::from_driver(driver).unwrap();
+        for x in 0..8 {
+            matrix.set_pixel(x, 0, true).unwrap();
+        }
 
-        // Modify the buffer
-        matrix.framebuffer[5] = 1;
-        matrix.framebuffer[15] = 1;
+        let alert = matrix.layer_mut(0).unwrap();
+        alert.set_blend(BlendMode::Replace);
+        alert.fill_region(0, 0, 4, 2, false).unwrap();
+        matrix.flush().unwrap();
 
-        assert_ne!(matrix.framebuffer, [0u8; 64]);
+        matrix.layer_mut(0).unwrap().hide();
+        matrix.flush().unwrap();
 
-        let result = matrix.clear_screen();
-        assert!(result.is_ok());
-        assert_eq!(matrix.framebuffer, [0u8; 64]);
+        assert_eq!(matrix.get_pixel(0, 0), Ok(true));
         spi.done();
     }
 
     #[test]
-    fn test_flush_single_device() {
-        // We expect the flush to send 8 SPI transactions, one for each row (DIGIT0 to DIGIT7)
-        // Only rows 0 and 7 have pixel data: 0b10101010 (columns 0,2,4,6 lit)
-        // All other rows should be cleared (0b00000000)
-
+    fn test_layers_stack_in_order() {
         let mut expected_transactions = Vec::new();
-        for (row, digit_register) in Register::digits().enumerate() {
-            // For rows 0 and 7, the framebuffer will result in this pattern:
-            // Columns 0, 2, 4, 6 are ON => bits 7, 5, 3, 1 set => 0b10101010
-            let expected_byte = if row == 0 || row == 7 {
-                0b10101010
-            } else {
-                0b00000000
+        for digit_register in Register::digits() {
+            let data = match digit_register {
+                // Layer 0 lights (0, 0) and (1, 0), layer 1 inverts (1, 0)
+                Register::Digit0 => 0b1000_0000,
+                _ => 0x00,
             };
-
-            // Each transaction sends [register, data] for that row
-            expected_transactions.push(Transaction::transaction_start());
-            expected_transactions.push(Transaction::write_vec(vec![
-                digit_register.addr(),
-                expected_byte,
-            ]));
-            expected_transactions.push(Transaction::transaction_end());
+            expected_transactions.extend(write_reg(digit_register.addr(), data));
         }
 
-        // Create the SPI mock with the expected sequence of writes
         let mut spi = SpiMock::new(&expected_transactions);
         let driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
-
-        // Set framebuffer values to light up alternating columns in row 0 and row 7
-        // Row 0 corresponds to framebuffer indices 0 to 7
-        matrix.framebuffer[0] = 1; // Column 0
-        matrix.framebuffer[2] = 1; // Column 2
-        matrix.framebuffer[4] = 1; // Column 4
-        matrix.framebuffer[6] = 1; // Column 6
+        let mut matrix: LedMatrix<_, 64, 1, 2> = LedMatrix::from_driver(driver).unwrap();
+        matrix
+            .layer_mut(0)
+            .unwrap()
+            .fill_region(0, 0, 2, 1, true)
+            .unwrap();
+        let top = matrix.layer_mut(1).unwrap();
+        top.set_blend(BlendMode::Xor);
+        top.set_pixel(1, 0, true).unwrap();
 
-        // Each device's framebuffer is a flat array of 64 bytes: 8 rows * 8 columns
-        // The layout is row-major: [row0[0..7], row1[0..7], ..., row7[0..7]]
-        //
-        // For a single device:
-        //   framebuffer[ 0.. 7] => row 0
-        //   framebuffer[ 8..15] => row 1
-        //   framebuffer[16..23] => row 2
-        //   framebuffer[24..31] => row 3
-        //   framebuffer[32..39] => row 4
-        //   framebuffer[40..47] => row 5
-        //   framebuffer[48..55] => row 6
-        //   framebuffer[56..63] => row 7 (last row)
-        //
-        // So to update row 7, we write to indices 56 to 63.
-        matrix.framebuffer[56] = 1; // Column 0
-        matrix.framebuffer[58] = 1; // Column 2
-        matrix.framebuffer[60] = 1; // Column 4
-        matrix.framebuffer[62] = 1; // Column 6
+        matrix.flush().unwrap();
+        spi.done();
+    }
 
-        // Call flush, which will convert framebuffer rows into bytes and send via SPI
-        let result = matrix.flush();
-        assert!(result.is_ok());
+    #[test]
+    fn test_layer_invalid_index() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver).unwrap();
 
+        assert!(matrix.layer(0).is_err());
+        assert!(matrix.layer_mut(0).is_err());
         spi.done();
     }
 
     #[test]
-    fn test_driver_mut_access() {
-        let expected_transactions = [
-            Transaction::transaction_start(),
-            Transaction::write_vec(vec![Register::Shutdown.addr(), 0x01]),
-            Transaction::transaction_end(),
-        ];
-        let mut spi = SpiMock::new(&expected_transactions);
-        let original_driver = Max7219::new(&mut spi);
-        let mut matrix = SingleMatrix::from_driver(original_driver).unwrap();
-
-        let driver = matrix.driver();
+    fn test_layers_follow_mapper_size() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2);
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let matrix: LedMatrix<_, 256, 4, 1> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_mapper(&GRID)
+            .unwrap();
 
-        driver.power_on().expect("Power on should succeed");
+        let layer = matrix.layer(0).unwrap();
+        assert_eq!((layer.width(), layer.height()), (16, 16));
         spi.done();
     }
 }
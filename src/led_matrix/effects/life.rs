@@ -0,0 +1,340 @@
+//! Conway's Game of Life
+
+use embedded_hal::spi::SpiDevice;
+use rand_core::RngCore;
+
+use crate::{
+    Error, Result,
+    led_matrix::{LedMatrix, UpdateMode},
+};
+
+/// Cell is alive in the current generation
+const ALIVE: u8 = 0b01;
+/// Cell is alive in the generation being computed
+const NEXT_ALIVE: u8 = 0b10;
+
+/// Conway's Game of Life played on the whole display.
+///
+/// Each call to [`GameOfLife::tick`] computes one generation. By default the
+/// edges wrap around, so gliders leaving one side come back on the other.
+/// Redrawing every generation also makes a handy stress test for flush
+/// performance.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut life: GameOfLife<256> = GameOfLife::new(matrix.width(), matrix.height());
+/// life.seed_random(&mut rng, 35);
+/// loop {
+///     life.draw(&mut matrix)?;
+///     if !life.tick() {
+///         life.seed_random(&mut rng, 35); // stuck in a still life
+///     }
+///     delay.delay_ms(150);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameOfLife<const BUFFER_LENGTH: usize> {
+    width: usize,
+    height: usize,
+    wrap: bool,
+    generation: u32,
+    cells: [u8; BUFFER_LENGTH],
+}
+
+impl<const BUFFER_LENGTH: usize> GameOfLife<BUFFER_LENGTH> {
+    /// Create an empty board of the given size with wrapping edges.
+    ///
+    /// Cells beyond `BUFFER_LENGTH` are treated as outside the board.
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            wrap: true,
+            generation: 0,
+            cells: [0; BUFFER_LENGTH],
+        }
+    }
+
+    /// Create a board seeded from the current framebuffer of the matrix.
+    pub fn from_matrix<SPI, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        matrix: &LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Self
+    where
+        SPI: SpiDevice,
+    {
+        let mut life = Self::new(matrix.width(), matrix.height());
+        for y in 0..life.height {
+            for x in 0..life.width {
+                if matrix.get_pixel(x, y) == Ok(true) {
+                    // Cannot fail, the board has the size of the matrix
+                    let _ = life.set_cell(x, y, true);
+                }
+            }
+        }
+        life
+    }
+
+    /// Enable or disable wrapping at the edges
+    pub const fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Returns the width of the board in cells.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the board in cells.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of generations computed since the board was seeded.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Returns the number of living cells.
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell & ALIVE != 0).count()
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = y.checked_mul(self.width)?.checked_add(x)?;
+        (index < BUFFER_LENGTH).then_some(index)
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Option<&u8> {
+        self.cells.get(self.index(x, y)?)
+    }
+
+    fn cell_mut(&mut self, x: usize, y: usize) -> Option<&mut u8> {
+        let index = self.index(x, y)?;
+        self.cells.get_mut(index)
+    }
+
+    /// Brings a cell to life or kills it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the cell lies outside the board.
+    pub fn set_cell(&mut self, x: usize, y: usize, alive: bool) -> Result<()> {
+        let cell = self.cell_mut(x, y).ok_or(Error::BufferError)?;
+        *cell = alive as u8;
+        Ok(())
+    }
+
+    /// Returns `true` if the cell is alive; cells outside the board are dead.
+    pub fn is_alive(&self, x: usize, y: usize) -> bool {
+        self.cell(x, y).is_some_and(|cell| cell & ALIVE != 0)
+    }
+
+    /// Kills every cell and resets the generation counter.
+    pub fn clear(&mut self) {
+        self.cells.fill(0);
+        self.generation = 0;
+    }
+
+    /// Reseeds the board, bringing each cell to life with a probability of
+    /// `density_percent` (0 to 100), and resets the generation counter.
+    pub fn seed_random<R: RngCore>(&mut self, rng: &mut R, density_percent: u8) {
+        self.clear();
+        let density = u32::from(density_percent.min(100));
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if rng.next_u32() % 100 < density
+                    && let Some(cell) = self.cell_mut(x, y)
+                {
+                    *cell = ALIVE;
+                }
+            }
+        }
+    }
+
+    fn live_neighbours(&self, x: usize, y: usize) -> u8 {
+        let mut count: u8 = 0;
+        for dy in [-1isize, 0, 1] {
+            for dx in [-1isize, 0, 1] {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let (mut nx, mut ny) = (
+                    (x as isize).saturating_add(dx),
+                    (y as isize).saturating_add(dy),
+                );
+                if self.wrap {
+                    nx = nx.checked_rem_euclid(self.width as isize).unwrap_or(nx);
+                    ny = ny.checked_rem_euclid(self.height as isize).unwrap_or(ny);
+                }
+                if nx >= 0 && ny >= 0 && self.is_alive(nx as usize, ny as usize) {
+                    count = count.saturating_add(1);
+                }
+            }
+        }
+        count
+    }
+
+    /// Computes the next generation.
+    ///
+    /// Returns `false` if the board did not change, i.e. it died out or
+    /// settled into a still life.
+    pub fn tick(&mut self) -> bool {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let alive = self.is_alive(x, y);
+                let next = matches!((alive, self.live_neighbours(x, y)), (true, 2) | (_, 3));
+                if next && let Some(cell) = self.cell_mut(x, y) {
+                    *cell |= NEXT_ALIVE;
+                }
+            }
+        }
+
+        let mut changed = false;
+        for cell in self.cells.iter_mut() {
+            let next = *cell >> 1;
+            changed |= next != *cell & ALIVE;
+            *cell = next;
+        }
+        self.generation = self.generation.wrapping_add(1);
+        changed
+    }
+
+    /// Replaces the framebuffer with the board.
+    ///
+    /// In write-through mode the frame is flushed right away; in buffered
+    /// mode it is sent on the next flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns an SPI error if the flush fails.
+    pub fn draw<SPI, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        matrix.clear_buffer();
+        for y in 0..self.height.min(matrix.height()) {
+            for x in 0..self.width.min(matrix.width()) {
+                if self.is_alive(x, y) {
+                    matrix.set_pixel(x, y, true)?;
+                }
+            }
+        }
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, led_matrix::display::SingleMatrix};
+    use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+    /// Deterministic generator returning 0, 1, 2, ...
+    struct Counter(u32);
+
+    impl RngCore for Counter {
+        fn next_u32(&mut self) -> u32 {
+            self.0 += 1;
+            self.0 - 1
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::from(self.next_u32())
+        }
+
+        fn fill_bytes(&mut self, dst: &mut [u8]) {
+            dst.fill(0);
+        }
+    }
+
+    #[test]
+    fn test_blinker_oscillates() {
+        let mut life: GameOfLife<64> = GameOfLife::new(8, 8);
+        for x in 2..=4 {
+            life.set_cell(x, 3, true).unwrap();
+        }
+
+        assert!(life.tick());
+        assert!(life.is_alive(3, 2) && life.is_alive(3, 3) && life.is_alive(3, 4));
+        assert!(!life.is_alive(2, 3));
+
+        assert!(life.tick());
+        assert!(life.is_alive(2, 3) && life.is_alive(4, 3));
+        assert_eq!(life.generation(), 2);
+        assert_eq!(life.population(), 3);
+    }
+
+    #[test]
+    fn test_block_is_still() {
+        let mut life: GameOfLife<64> = GameOfLife::new(8, 8);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            life.set_cell(x, y, true).unwrap();
+        }
+        assert!(!life.tick());
+        assert_eq!(life.population(), 4);
+    }
+
+    #[test]
+    fn test_wrapping_edges() {
+        // A vertical blinker on the left edge grows across the wrapped edge
+        let mut wrapped: GameOfLife<64> = GameOfLife::new(8, 8);
+        let mut bounded = wrapped.with_wrap(false);
+        for y in 2..=4 {
+            wrapped.set_cell(0, y, true).unwrap();
+            bounded.set_cell(0, y, true).unwrap();
+        }
+        wrapped.tick();
+        bounded.tick();
+        assert!(wrapped.is_alive(7, 3));
+        assert!(!bounded.is_alive(7, 3));
+        assert!(bounded.is_alive(1, 3));
+    }
+
+    #[test]
+    fn test_seed_random_density() {
+        let mut life: GameOfLife<64> = GameOfLife::new(8, 8);
+        life.seed_random(&mut Counter(0), 50);
+        // Values 0 to 63: those with `value % 100 < 50` are 0..50
+        assert_eq!(life.population(), 50);
+        life.seed_random(&mut Counter(0), 0);
+        assert_eq!(life.population(), 0);
+    }
+
+    #[test]
+    fn test_from_matrix_and_draw() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        for x in 2..=4 {
+            matrix.set_pixel(x, 3, true).unwrap();
+        }
+
+        let mut life = GameOfLife::from_matrix(&matrix);
+        assert_eq!(life.population(), 3);
+        life.tick();
+        life.draw(&mut matrix).unwrap();
+        assert_eq!(matrix.get_pixel(3, 2), Ok(true));
+        assert_eq!(matrix.get_pixel(2, 3), Ok(false));
+        spi.done();
+    }
+
+    #[test]
+    fn test_set_cell_invalid() {
+        let mut life: GameOfLife<64> = GameOfLife::new(8, 8);
+        assert_eq!(life.set_cell(8, 0, true), Err(Error::BufferError));
+        assert!(!life.is_alive(8, 0));
+    }
+}
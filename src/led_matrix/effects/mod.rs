@@ -0,0 +1,15 @@
+//! Idle animations and effects for LED matrix displays
+//!
+//! Available with the `effects` feature. Effects keep their own state,
+//! advance on `tick()` and render onto an
+//! [`LedMatrix`](crate::led_matrix::LedMatrix) when asked to draw. Effects
+//! that need randomness take any [`rand_core::RngCore`] supplied by the
+//! application, so they stay `no_std` friendly.
+
+pub mod life;
+pub mod rain;
+pub mod sparkle;
+
+pub use life::GameOfLife;
+pub use rain::MatrixRain;
+pub use sparkle::Sparkle;
@@ -0,0 +1,224 @@
+//! Falling "digital rain" columns
+
+use embedded_hal::spi::SpiDevice;
+use rand_core::RngCore;
+
+use crate::{
+    Result,
+    led_matrix::{LedMatrix, UpdateMode},
+};
+
+/// State of the drop falling in one column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Drop {
+    /// Row of the head; negative while waiting above the display
+    head: i16,
+    /// Number of lit pixels including the head
+    length: u8,
+    /// Ticks between two steps, 1 = fastest
+    period: u8,
+    /// Ticks since the last step
+    counter: u8,
+}
+
+/// Falling columns with fading trails, one drop per display column.
+///
+/// The MAX7219 has no per-pixel brightness, so the trail fades by blinking
+/// its last pixel on every other frame.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut rain: MatrixRain<32> = MatrixRain::new(8);
+/// loop {
+///     rain.tick(&mut rng);
+///     rain.draw(&mut matrix)?;
+///     delay.delay_ms(60);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixRain<const WIDTH: usize> {
+    height: usize,
+    max_length: u8,
+    frame: u32,
+    drops: [Drop; WIDTH],
+}
+
+impl<const WIDTH: usize> MatrixRain<WIDTH> {
+    /// Create rain for a display `WIDTH` columns wide and `height` rows tall.
+    ///
+    /// All drops start above the display and enter it on later ticks.
+    pub const fn new(height: usize) -> Self {
+        Self {
+            height,
+            max_length: 4,
+            frame: 0,
+            drops: [Drop {
+                head: -1,
+                length: 0,
+                period: 1,
+                counter: 0,
+            }; WIDTH],
+        }
+    }
+
+    /// Sets the longest trail, including the head (at least 1)
+    pub const fn with_max_length(mut self, max_length: u8) -> Self {
+        self.max_length = if max_length == 0 { 1 } else { max_length };
+        self
+    }
+
+    /// Starts a new drop above the display with random length, speed and delay.
+    fn respawn<R: RngCore>(&mut self, column: usize, rng: &mut R) {
+        let random = rng.next_u32();
+        let max_length = u32::from(self.max_length);
+        if let Some(drop) = self.drops.get_mut(column) {
+            *drop = Drop {
+                head: (-1i16).saturating_sub((random % 8) as i16),
+                length: ((random >> 8).checked_rem(max_length).unwrap_or(0) as u8)
+                    .saturating_add(1),
+                period: (((random >> 16) % 3) as u8).saturating_add(1),
+                counter: 0,
+            };
+        }
+    }
+
+    /// Advances every drop; drops whose trail has left the display are
+    /// respawned using `rng`.
+    pub fn tick<R: RngCore>(&mut self, rng: &mut R) {
+        self.frame = self.frame.wrapping_add(1);
+        for column in 0..WIDTH {
+            let Some(drop) = self.drops.get_mut(column) else {
+                continue;
+            };
+            if drop.length == 0 {
+                self.respawn(column, rng);
+                continue;
+            }
+            drop.counter = drop.counter.saturating_add(1);
+            if drop.counter < drop.period {
+                continue;
+            }
+            drop.counter = 0;
+            drop.head = drop.head.saturating_add(1);
+            // Respawn once the last trail pixel has left the display
+            let tail = i16::from(drop.length).saturating_sub(1);
+            if drop.head.saturating_sub(tail) >= self.height as i16 {
+                self.respawn(column, rng);
+            }
+        }
+    }
+
+    /// Returns `true` if the pixel at (`x`, `y`) is lit in the current frame.
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        let Some(drop) = self.drops.get(x) else {
+            return false;
+        };
+        if drop.length == 0 || y >= self.height {
+            return false;
+        }
+        let distance = drop.head.saturating_sub(y as i16);
+        let tail = i16::from(drop.length).saturating_sub(1);
+        match distance {
+            d if d < 0 || d > tail => false,
+            // The last trail pixel blinks to fake a fade
+            d if d == tail && tail > 0 => self.frame.is_multiple_of(2),
+            _ => true,
+        }
+    }
+
+    /// Replaces the framebuffer with the current frame.
+    ///
+    /// In write-through mode the frame is flushed right away; in buffered
+    /// mode it is sent on the next flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns an SPI error if the flush fails.
+    pub fn draw<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        matrix.clear_buffer();
+        for y in 0..self.height.min(matrix.height()) {
+            for x in 0..WIDTH.min(matrix.width()) {
+                if self.is_lit(x, y) {
+                    matrix.set_pixel(x, y, true)?;
+                }
+            }
+        }
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generator always returning the same value
+    struct Fixed(u32);
+
+    impl RngCore for Fixed {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::from(self.0)
+        }
+
+        fn fill_bytes(&mut self, dst: &mut [u8]) {
+            dst.fill(0);
+        }
+    }
+
+    #[test]
+    fn test_drops_fall_and_respawn() {
+        // Head starts at row -1, trail of 3, one step per tick
+        let mut rng = Fixed(2 << 8);
+        let mut rain: MatrixRain<1> = MatrixRain::new(8);
+        rain.tick(&mut rng);
+        assert!(!(0..8).any(|y| rain.is_lit(0, y)));
+
+        rain.tick(&mut rng);
+        assert!(rain.is_lit(0, 0));
+
+        for _ in 0..3 {
+            rain.tick(&mut rng);
+        }
+        // Head on row 3: rows 2 and 3 always lit, row 1 blinks
+        assert!(rain.is_lit(0, 3) && rain.is_lit(0, 2));
+        assert!(!rain.is_lit(0, 4) && !rain.is_lit(0, 0));
+        let blink = rain.is_lit(0, 1);
+        rain.frame += 1;
+        assert_ne!(rain.is_lit(0, 1), blink);
+
+        // Once the trail has left the display the drop starts over
+        for _ in 0..7 {
+            rain.tick(&mut rng);
+        }
+        assert!(!(0..8).any(|y| rain.is_lit(0, y)));
+        assert_eq!(rain.drops[0].head, -1);
+    }
+
+    #[test]
+    fn test_is_lit_out_of_range() {
+        let rain: MatrixRain<4> = MatrixRain::new(8);
+        assert!(!rain.is_lit(4, 0));
+        assert!(!rain.is_lit(0, 8));
+    }
+
+    #[test]
+    fn test_max_length() {
+        let mut rng = Fixed(u32::MAX);
+        let mut rain: MatrixRain<2> = MatrixRain::new(8).with_max_length(2);
+        rain.tick(&mut rng);
+        assert!(rain.drops.iter().all(|drop| (1..=2).contains(&drop.length)));
+    }
+}
@@ -0,0 +1,126 @@
+//! Randomly toggling pixels
+
+use embedded_hal::spi::SpiDevice;
+use rand_core::RngCore;
+
+use crate::{
+    Result,
+    led_matrix::{LedMatrix, UpdateMode},
+};
+
+/// Toggles a few random pixels of the framebuffer on every tick.
+///
+/// Works on top of whatever is shown, so it can add a twinkle to a static
+/// image or, starting from a blank display, act as an idle screen.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let sparkle = Sparkle::new(3);
+/// loop {
+///     sparkle.tick(&mut matrix, &mut rng)?;
+///     delay.delay_ms(50);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sparkle {
+    per_tick: usize,
+}
+
+impl Sparkle {
+    /// Create a sparkle effect toggling `per_tick` pixels on every tick
+    pub const fn new(per_tick: usize) -> Self {
+        Self { per_tick }
+    }
+
+    /// Returns the number of pixels toggled on every tick.
+    pub fn per_tick(&self) -> usize {
+        self.per_tick
+    }
+
+    /// Toggles `per_tick` random pixels in the framebuffer.
+    ///
+    /// In write-through mode the frame is flushed right away; in buffered
+    /// mode it is sent on the next flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns an SPI error if the flush fails.
+    pub fn tick<
+        SPI,
+        R,
+        const BUFFER_LENGTH: usize,
+        const DEVICE_COUNT: usize,
+        const LAYERS: usize,
+    >(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+        rng: &mut R,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+        R: RngCore,
+    {
+        let (width, height) = (matrix.width(), matrix.height());
+        for _ in 0..self.per_tick {
+            let position = (rng.next_u32() as usize)
+                .checked_rem(width.saturating_mul(height))
+                .unwrap_or(0);
+            let x = position.checked_rem(width).unwrap_or(0);
+            let y = position.checked_div(width).unwrap_or(0);
+            matrix.invert_region(x, y, 1, 1)?;
+        }
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, led_matrix::display::Matrix4};
+    use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+    /// Generator returning the given values in a loop
+    struct Sequence<'a>(&'a [u32], usize);
+
+    impl RngCore for Sequence<'_> {
+        fn next_u32(&mut self) -> u32 {
+            let value = self.0[self.1 % self.0.len()];
+            self.1 += 1;
+            value
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::from(self.next_u32())
+        }
+
+        fn fill_bytes(&mut self, dst: &mut [u8]) {
+            dst.fill(0);
+        }
+    }
+
+    #[test]
+    fn test_sparkle_toggles_pixels() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        matrix.set_pixel(31, 7, true).unwrap();
+
+        // 33 = (1, 1), 255 = (31, 7)
+        let mut rng = Sequence(&[33, 255], 0);
+        Sparkle::new(2).tick(&mut matrix, &mut rng).unwrap();
+        assert_eq!(matrix.get_pixel(1, 1), Ok(true));
+        assert_eq!(matrix.get_pixel(31, 7), Ok(false));
+
+        // Values wrap around the display size
+        let mut rng = Sequence(&[256 + 33], 0);
+        Sparkle::new(1).tick(&mut matrix, &mut rng).unwrap();
+        assert_eq!(matrix.get_pixel(1, 1), Ok(false));
+        spi.done();
+    }
+}
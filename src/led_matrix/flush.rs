@@ -0,0 +1,296 @@
+//! Resumable flushes for tasks with tight deadlines
+//!
+//! [`LedMatrix::flush`](crate::led_matrix::LedMatrix::flush) sends the whole
+//! chain in one blocking call. On long chains with a slow SPI bus that can
+//! take longer than an RTIC task or interrupt handler may run. A
+//! [`FlushStateMachine`] splits the same update into single SPI
+//! transactions, so it can be spread over idle time.
+//!
+//! With the `nb` feature, [`FlushStateMachine::poll`] offers the same steps
+//! as an [`nb::Result`], for frameworks that poll until an operation stops
+//! returning `WouldBlock`.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Register, Result,
+    led_matrix::{LedMatrix, display::FlushOrder},
+    time::Progress,
+};
+
+/// A flush in progress, sent one SPI transaction per [`Self::step`].
+///
+/// The frame is captured when the flush starts with
+/// [`LedMatrix::begin_flush`], so drawing the next frame while this one is
+/// still being sent does not mix the two. The matrix's
+/// [`FlushOrder`] is respected; blanking during the flush is not applied.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // In an RTIC task with the matrix as a shared resource
+/// let mut flush = matrix.begin_flush();
+/// while flush.step(&mut matrix)? == Progress::Running {
+///     // yield to higher priority work between transactions
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlushStateMachine<const DEVICE_COUNT: usize> {
+    /// DIG0 to DIG7 data of every device (by driver index)
+    images: [[u8; 8]; DEVICE_COUNT],
+    /// Devices that receive their image, the others get no-ops
+    devices: [bool; DEVICE_COUNT],
+    order: FlushOrder,
+    /// Transactions sent so far
+    sent: usize,
+}
+
+impl<const DEVICE_COUNT: usize> FlushStateMachine<DEVICE_COUNT> {
+    /// Create a flush of the given device images
+    pub(crate) fn new(
+        images: [[u8; 8]; DEVICE_COUNT],
+        devices: [bool; DEVICE_COUNT],
+        order: FlushOrder,
+    ) -> Self {
+        Self {
+            images,
+            devices,
+            order,
+            sent: 0,
+        }
+    }
+
+    /// Returns the number of SPI transactions the whole flush takes.
+    ///
+    /// Device by device, only the flushed devices take transactions.
+    pub fn len(&self) -> usize {
+        match self.order {
+            FlushOrder::RowMajor => 8,
+            FlushOrder::DeviceMajor => {
+                let selected = self.devices.iter().filter(|&&device| device).count();
+                selected.saturating_mul(8)
+            }
+        }
+    }
+
+    /// Returns `true` if the flush has nothing to send.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of SPI transactions still to send.
+    pub fn remaining(&self) -> usize {
+        self.len().saturating_sub(self.sent)
+    }
+
+    /// Returns `true` once every transaction has been sent.
+    pub fn is_done(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Returns the packet of one digit of a device, or `None` if the device
+    /// is not flushed.
+    fn packet(&self, device_index: usize, digit: usize) -> Option<(Register, u8)> {
+        if !self.devices.get(device_index).copied()? {
+            return None;
+        }
+        let data = *self.images.get(device_index)?.get(digit)?;
+        Some((Register::digit(digit as u8)?, data))
+    }
+
+    /// Sends the next SPI transaction of the flush.
+    ///
+    /// Returns [`Progress::Running`] while transactions remain,
+    /// [`Progress::Complete`] when the last one was sent by this call, and
+    /// [`Progress::Idle`] afterwards. A failed transaction is retried by the
+    /// next call.
+    pub fn step<SPI, const BUFFER_LENGTH: usize, const LAYERS: usize>(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<Progress>
+    where
+        SPI: SpiDevice,
+    {
+        if self.is_done() {
+            return Ok(Progress::Idle);
+        }
+
+        let mut ops = [(Register::NoOp, 0); DEVICE_COUNT];
+        match self.order {
+            FlushOrder::RowMajor => {
+                let digit = self.sent;
+                for (device_index, op) in ops.iter_mut().enumerate() {
+                    if let Some(packet) = self.packet(device_index, digit) {
+                        *op = packet;
+                    }
+                }
+            }
+            FlushOrder::DeviceMajor => {
+                // Devices that are not flushed are skipped
+                let (selected, digit) = (self.sent / 8, self.sent % 8);
+                let device_index = (0..DEVICE_COUNT)
+                    .filter(|&device_index| self.devices.get(device_index) == Some(&true))
+                    .nth(selected)
+                    .unwrap_or(DEVICE_COUNT);
+                if let (Some(packet), Some(op)) =
+                    (self.packet(device_index, digit), ops.get_mut(device_index))
+                {
+                    *op = packet;
+                }
+            }
+        }
+        matrix.driver().write_all_registers(&ops)?;
+        self.sent = self.sent.saturating_add(1);
+
+        if !self.is_done() {
+            return Ok(Progress::Running);
+        }
+        Ok(Progress::Complete)
+    }
+
+    /// Sends the next SPI transaction, reporting `WouldBlock` until the
+    /// flush is done.
+    ///
+    /// Each call sends at most one transaction, so polling loops never block
+    /// for longer than that.
+    ///
+    /// ```rust,ignore
+    /// let mut flush = matrix.begin_flush();
+    /// nb::block!(flush.poll(&mut matrix))?;
+    /// ```
+    #[cfg(feature = "nb")]
+    pub fn poll<SPI, const BUFFER_LENGTH: usize, const LAYERS: usize>(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> nb::Result<(), crate::Error>
+    where
+        SPI: SpiDevice,
+    {
+        match self.step(matrix)? {
+            Progress::Running => Err(nb::Error::WouldBlock),
+            Progress::Complete | Progress::Idle => Ok(()),
+        }
+    }
+
+    /// Sends all remaining transactions.
+    pub fn finish<SPI, const BUFFER_LENGTH: usize, const LAYERS: usize>(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        while self.step(matrix)? == Progress::Running {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Max7219;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction};
+
+    fn write(data: Vec<u8>) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(data),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    #[test]
+    fn test_row_major_steps_one_row_per_call() {
+        let mut expected = Vec::new();
+        for digit in Register::DIGITS {
+            let nearest = if digit == Register::Digit0 { 0x80 } else { 0 };
+            expected.extend(write(vec![digit.addr(), 0, digit.addr(), nearest]));
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver).unwrap();
+        matrix.set_pixel(0, 0, true).unwrap();
+
+        let mut flush = matrix.begin_flush();
+        assert_eq!(flush.len(), 8);
+        // Later drawing does not change the flush in progress
+        matrix.set_pixel(1, 0, true).unwrap();
+
+        for _ in 0..7 {
+            assert_eq!(flush.step(&mut matrix), Ok(Progress::Running));
+        }
+        assert_eq!(flush.step(&mut matrix), Ok(Progress::Complete));
+        assert!(flush.is_done());
+        assert_eq!(flush.step(&mut matrix), Ok(Progress::Idle));
+        spi.done();
+    }
+
+    #[test]
+    fn test_device_major_finish() {
+        let mut expected = Vec::new();
+        for device in 0..2 {
+            for digit in Register::DIGITS {
+                let mut data = vec![0; 4];
+                data[device * 2] = digit.addr();
+                expected.extend(write(data));
+            }
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_flush_order(FlushOrder::DeviceMajor);
+
+        let mut flush = matrix.begin_flush();
+        assert_eq!(flush.remaining(), 16);
+        flush.finish(&mut matrix).unwrap();
+        assert_eq!(flush.remaining(), 0);
+        spi.done();
+    }
+
+    #[test]
+    fn test_device_major_skips_other_devices() {
+        use crate::chain::{ChainLayout, DeviceKind};
+
+        let mut expected = Vec::new();
+        for digit in Register::DIGITS {
+            expected.extend(write(vec![Register::NoOp.addr(), 0, digit.addr(), 0]));
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix]).unwrap();
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_driver(driver)
+            .unwrap()
+            .with_flush_order(FlushOrder::DeviceMajor)
+            .with_layout(layout)
+            .unwrap();
+
+        let mut flush = matrix.begin_flush();
+        assert_eq!(flush.len(), 8);
+        flush.finish(&mut matrix).unwrap();
+        spi.done();
+    }
+
+    #[cfg(feature = "nb")]
+    #[test]
+    fn test_poll_would_block_until_done() {
+        let mut expected = Vec::new();
+        for digit in Register::DIGITS {
+            expected.extend(write(vec![digit.addr(), 0]));
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi).with_device_count(1).unwrap();
+        let mut matrix: LedMatrix<_, 64, 1> = LedMatrix::from_driver(driver).unwrap();
+
+        let mut flush = matrix.begin_flush();
+        assert_eq!(flush.poll(&mut matrix), Err(nb::Error::WouldBlock));
+        assert_eq!(flush.remaining(), 7);
+        nb::block!(flush.poll(&mut matrix)).unwrap();
+        assert_eq!(flush.poll(&mut matrix), Ok(()));
+        spi.done();
+    }
+}
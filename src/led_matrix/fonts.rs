@@ -1,25 +1,275 @@
 //! Font definitions for use with 8x8 LED matrix displays.
 
+use core::ops::Range;
+
+use crate::{Result, error::CharPolicy};
+
+/// Width of a glyph cell in pixels; every glyph advances the text by this
+pub const GLYPH_WIDTH: usize = 8;
+
+/// Height of a line of text in pixels
+pub const LINE_HEIGHT: usize = 8;
+
+/// Horizontal metrics of one glyph, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphMetrics {
+    /// Distance from the start of this glyph to the start of the next
+    pub advance: usize,
+    /// Blank columns before the first lit column
+    pub left_bearing: usize,
+    /// Columns from the first to the last lit column, 0 for a blank glyph
+    pub ink_width: usize,
+}
+
+impl GlyphMetrics {
+    /// Measures the lit columns of a bitmap.
+    pub const fn of_bitmap(bitmap: &[u8; 8]) -> Self {
+        let mut columns = 0u8;
+        let mut rows = bitmap.as_slice();
+        while let [row, rest @ ..] = rows {
+            columns |= *row;
+            rows = rest;
+        }
+        // Bit 7 is the leftmost column
+        let (left_bearing, ink_width) = match columns {
+            0 => (0, 0),
+            _ => (
+                columns.leading_zeros() as usize,
+                // The lit columns lie between the blank ones, so this is at most 8
+                8u32.wrapping_sub(columns.leading_zeros())
+                    .wrapping_sub(columns.trailing_zeros()) as usize,
+            ),
+        };
+        Self {
+            advance: GLYPH_WIDTH,
+            left_bearing,
+            ink_width,
+        }
+    }
+
+    /// Blank columns after the last lit column, up to the next glyph
+    pub const fn right_bearing(&self) -> usize {
+        self.advance
+            .saturating_sub(self.left_bearing)
+            .saturating_sub(self.ink_width)
+    }
+}
+
 /// 8x8 font mapping for LED matrix displays
 #[derive(Copy, Clone)]
 pub struct LedFont {
     char_map: &'static [([u8; 8], char)],
+    unsupported: CharPolicy<[u8; 8]>,
 }
 
 impl LedFont {
     /// Create a new font
+    ///
+    /// Characters missing from the map are drawn as [`FONT8X8_UNKNOWN`].
     pub const fn new(char_map: &'static [([u8; 8], char)]) -> Self {
-        Self { char_map }
+        Self {
+            char_map,
+            unsupported: CharPolicy::Replace(FONT8X8_UNKNOWN),
+        }
+    }
+
+    /// Returns the character map of the font.
+    pub const fn char_map(&self) -> &'static [([u8; 8], char)] {
+        self.char_map
+    }
+
+    /// Sets what text rendering does with characters missing from the map.
+    pub const fn with_unsupported(mut self, policy: CharPolicy<[u8; 8]>) -> Self {
+        self.unsupported = policy;
+        self
+    }
+
+    /// Returns the policy for characters missing from the map.
+    pub const fn unsupported(&self) -> CharPolicy<[u8; 8]> {
+        self.unsupported
     }
 
     /// Get bitmap pattern for a character
-    pub fn get_char(&self, ch: char) -> [u8; 8] {
-        for &(bitmap, c) in self.char_map {
-            if c == ch {
-                return bitmap;
+    ///
+    /// Missing characters get the replacement glyph of the font's policy, or
+    /// a blank bitmap if they would be skipped or rejected.
+    pub const fn get_char(&self, ch: char) -> [u8; 8] {
+        match self.lookup(ch) {
+            Ok(Some(bitmap)) => bitmap,
+            _ => [0; 8],
+        }
+    }
+
+    /// Looks up the bitmap for a character, applying the font's
+    /// [`CharPolicy`] if it is missing.
+    ///
+    /// Returns `None` if the character should be skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the character is missing and the
+    /// policy is [`CharPolicy::Error`].
+    pub const fn lookup(&self, ch: char) -> Result<Option<[u8; 8]>> {
+        let mut entries = self.char_map;
+        while let [(bitmap, c), rest @ ..] = entries {
+            if *c == ch {
+                return Ok(Some(*bitmap));
             }
+            entries = rest;
+        }
+        self.unsupported.resolve(ch)
+    }
+
+    /// Returns the metrics of the glyph drawn for `ch`, or `None` if the
+    /// font's policy skips or rejects it.
+    pub fn glyph_metrics(&self, ch: char) -> Option<GlyphMetrics> {
+        match self.lookup(ch) {
+            Ok(Some(bitmap)) => Some(GlyphMetrics::of_bitmap(&bitmap)),
+            _ => None,
+        }
+    }
+
+    /// Returns how far `ch` advances the text: [`GLYPH_WIDTH`], or 0 if
+    /// the font's policy skips or rejects it.
+    pub fn glyph_width(&self, ch: char) -> usize {
+        self.glyph_metrics(ch).map_or(0, |metrics| metrics.advance)
+    }
+
+    /// Returns the width of `text` in pixels as drawn with this font,
+    /// glyph cells included. Scroll markers are not interpreted.
+    pub fn text_width(&self, text: &str) -> usize {
+        text.chars().map(|ch| self.glyph_width(ch)).sum()
+    }
+
+    /// Returns the columns of `text` that hold lit pixels, counted from the
+    /// start of the text, or `None` if nothing is lit.
+    ///
+    /// Unlike [`Self::text_width`] this leaves out the blank columns at
+    /// either end, e.g. to right-align a value pixel-exactly:
+    ///
+    /// ```rust
+    /// use max7219_display::led_matrix::fonts::STANDARD_LED_FONT;
+    ///
+    /// let ink = STANDARD_LED_FONT.ink_bounds("42").unwrap();
+    /// let x = 32 - ink.end; // last lit column on the right edge
+    /// assert!(x > 16);
+    /// ```
+    pub fn ink_bounds(&self, text: &str) -> Option<Range<usize>> {
+        let mut x: usize = 0;
+        let mut bounds: Option<Range<usize>> = None;
+        for metrics in text.chars().filter_map(|ch| self.glyph_metrics(ch)) {
+            if metrics.ink_width > 0 {
+                let start = x.saturating_add(metrics.left_bearing);
+                let end = start.saturating_add(metrics.ink_width);
+                bounds = Some(bounds.map_or(start, |bounds| bounds.start)..end);
+            }
+            x = x.saturating_add(metrics.advance);
+        }
+        bounds
+    }
+
+    /// Returns the height of a line of text in pixels.
+    pub const fn line_height(&self) -> usize {
+        LINE_HEIGHT
+    }
+
+    /// Returns `false` if the font's policy leaves `ch` out of the text.
+    pub(crate) const fn is_drawn(&self, ch: char) -> bool {
+        !matches!(self.lookup(ch), Ok(None))
+    }
+
+    /// Checks that every character of `chars` can be rendered.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` for the first character rejected by
+    /// the font's policy.
+    pub fn check(&self, chars: impl IntoIterator<Item = char>) -> Result<()> {
+        chars
+            .into_iter()
+            .try_for_each(|ch| self.lookup(ch).map(|_| ()))
+    }
+}
+
+/// 8x8 font whose glyphs are loaded one at a time by a function.
+///
+/// On Harvard-architecture targets such as AVR, a `static` table is copied
+/// into SRAM at startup unless it is placed in program memory, which
+/// ordinary loads can't read. A 256-glyph CP437 font takes 2 KB, all the
+/// SRAM of an ATmega328P, so keep it in flash and let the loader copy out
+/// the one glyph being drawn. The loader returns `None` for characters the
+/// font lacks, which are then handled by the font's [`CharPolicy`] (drawn
+/// as [`FONT8X8_UNKNOWN`] by default).
+///
+/// Unlike [`LedFont`], glyphs can only be looked up at run time, so draw
+/// them with [`LedMatrix::draw_glyph`](crate::led_matrix::LedMatrix::draw_glyph).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// avr_progmem::progmem! {
+///     static progmem CP437: [[u8; 8]; 256] = include!("cp437.in");
+/// }
+///
+/// fn load_cp437(ch: char) -> Option<[u8; 8]> {
+///     let code = u8::try_from(ch).ok()?;
+///     Some(CP437.load_at(usize::from(code)))
+/// }
+///
+/// const FONT: LoaderFont = LoaderFont::new(load_cp437);
+/// for (index, ch) in "21°C".chars().enumerate() {
+///     matrix.draw_glyph(index as i32 * 8, 0, &FONT.get_char(ch))?;
+/// }
+/// matrix.flush()?;
+/// ```
+#[derive(Copy, Clone)]
+pub struct LoaderFont {
+    load: fn(char) -> Option<[u8; 8]>,
+    unsupported: CharPolicy<[u8; 8]>,
+}
+
+impl LoaderFont {
+    /// Create a font whose glyphs are loaded by `load`
+    ///
+    /// Characters `load` returns `None` for are drawn as [`FONT8X8_UNKNOWN`].
+    pub const fn new(load: fn(char) -> Option<[u8; 8]>) -> Self {
+        Self {
+            load,
+            unsupported: CharPolicy::Replace(FONT8X8_UNKNOWN),
+        }
+    }
+
+    /// Sets what text rendering does with characters the loader lacks.
+    pub const fn with_unsupported(mut self, policy: CharPolicy<[u8; 8]>) -> Self {
+        self.unsupported = policy;
+        self
+    }
+
+    /// Get bitmap pattern for a character
+    ///
+    /// Missing characters get the replacement glyph of the font's policy, or
+    /// a blank bitmap if they would be skipped or rejected.
+    pub fn get_char(&self, ch: char) -> [u8; 8] {
+        match self.lookup(ch) {
+            Ok(Some(bitmap)) => bitmap,
+            _ => [0; 8],
+        }
+    }
+
+    /// Loads the bitmap for a character, applying the font's
+    /// [`CharPolicy`] if it is missing.
+    ///
+    /// Returns `None` if the character should be skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the character is missing and the
+    /// policy is [`CharPolicy::Error`].
+    pub fn lookup(&self, ch: char) -> Result<Option<[u8; 8]>> {
+        match (self.load)(ch) {
+            Some(bitmap) => Ok(Some(bitmap)),
+            None => self.unsupported.resolve(ch),
         }
-        FONT8X8_UNKNOWN
     }
 }
 
@@ -36,6 +286,11 @@ pub const FONT8X8_UNKNOWN: [u8; 8] = [
     0b00000000, 0b00111100, 0b01000010, 0b00000010, 0b00001100, 0b00000000, 0b00001100, 0b00000000,
 ];
 
+/// Ellipsis glyph shown where text was cut off to fit the display.
+pub const ELLIPSIS: [u8; 8] = [
+    0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b10010010, 0b00000000,
+];
+
 /// A simple 8x8 font map, with bit-reversed (corrected) glyphs to fix mirroring
 #[rustfmt::skip]
 pub const FONT8X8: &[([u8; 8], char)] = &[
@@ -1274,6 +1529,19 @@ pub const FONT8X8: &[([u8; 8], char)] = &[
         ],
         '~',
     ),
+    (
+        [
+            0b00000000,
+            0b00000000,
+            0b00011000,
+            0b00111100,
+            0b00111100,
+            0b00011000,
+            0b00000000,
+            0b00000000,
+        ],
+        '•',
+    ),
 ];
 
 #[cfg(test)]
@@ -1301,7 +1569,7 @@ mod tests {
         ];
 
         let font = LedFont::new(TEST_FONT_DATA);
-        assert_eq!(font.char_map, TEST_FONT_DATA);
+        assert_eq!(font.char_map(), TEST_FONT_DATA);
     }
 
     #[test]
@@ -1322,6 +1590,68 @@ mod tests {
         assert_eq!(bitmap_a, expected_a);
     }
 
+    #[test]
+    fn test_const_lookup() {
+        // Glyphs resolved at compile time end up in flash as plain data
+        static ICONS: [[u8; 8]; 2] = [
+            STANDARD_LED_FONT.get_char('A'),
+            STANDARD_LED_FONT.get_char('€'),
+        ];
+        assert_eq!(ICONS, [STANDARD_LED_FONT.get_char('A'), FONT8X8_UNKNOWN]);
+    }
+
+    #[test]
+    fn test_loader_font() {
+        fn load(ch: char) -> Option<[u8; 8]> {
+            ch.is_ascii_digit().then_some([ch as u8; 8])
+        }
+        let font = LoaderFont::new(load);
+
+        assert_eq!(font.get_char('7'), [b'7'; 8]);
+        assert_eq!(font.get_char('x'), FONT8X8_UNKNOWN);
+        let strict = font.with_unsupported(CharPolicy::Error);
+        assert_eq!(strict.lookup('x'), Err(crate::Error::UnsupportedChar('x')));
+    }
+
+    #[test]
+    fn test_glyph_metrics() {
+        let metrics = GlyphMetrics::of_bitmap(&[0, 0x18, 0x38, 0x18, 0x18, 0x7E, 0, 0]);
+        assert_eq!(
+            metrics,
+            GlyphMetrics {
+                advance: 8,
+                left_bearing: 1,
+                ink_width: 6
+            }
+        );
+        assert_eq!(metrics.right_bearing(), 1);
+        assert_eq!(GlyphMetrics::of_bitmap(&[0; 8]).ink_width, 0);
+
+        assert_eq!(STANDARD_LED_FONT.glyph_width('A'), GLYPH_WIDTH);
+        assert_eq!(STANDARD_LED_FONT.text_width("Hi!"), 24);
+        assert_eq!(STANDARD_LED_FONT.line_height(), LINE_HEIGHT);
+
+        let skipping = STANDARD_LED_FONT.with_unsupported(CharPolicy::Skip);
+        assert_eq!(skipping.glyph_width('€'), 0);
+        assert_eq!(skipping.text_width("1€2"), 16);
+    }
+
+    #[test]
+    fn test_ink_bounds() {
+        let font = STANDARD_LED_FONT;
+        assert_eq!(font.ink_bounds(""), None);
+        assert_eq!(font.ink_bounds("  "), None);
+
+        let one = GlyphMetrics::of_bitmap(&font.get_char('1'));
+        assert_eq!(
+            font.ink_bounds(" 1 "),
+            Some(8 + one.left_bearing..16 - one.right_bearing())
+        );
+        let bounds = font.ink_bounds("11").unwrap();
+        assert_eq!(bounds.start, one.left_bearing);
+        assert_eq!(bounds.end, 16 - one.right_bearing());
+    }
+
     #[test]
     fn test_get_char_missing() {
         // Test getting bitmap for non-existing character
@@ -0,0 +1,131 @@
+//! Dice faces and playing-card rank glyphs sized for one 8x8 module
+
+use crate::{
+    Error, Result,
+    led_matrix::{buffer::MatrixBuffer, fonts::STANDARD_LED_FONT},
+};
+
+/// Pips of the six die faces, with 2x2 pixel pips
+#[rustfmt::skip]
+const DIE_FACES: [[u8; 8]; 6] = [
+    [0b00000000, 0b00000000, 0b00000000, 0b00011000, 0b00011000, 0b00000000, 0b00000000, 0b00000000],
+    [0b00000000, 0b01100000, 0b01100000, 0b00000000, 0b00000000, 0b00000110, 0b00000110, 0b00000000],
+    [0b00000000, 0b01100000, 0b01100000, 0b00011000, 0b00011000, 0b00000110, 0b00000110, 0b00000000],
+    [0b00000000, 0b01100110, 0b01100110, 0b00000000, 0b00000000, 0b01100110, 0b01100110, 0b00000000],
+    [0b00000000, 0b01100110, 0b01100110, 0b00011000, 0b00011000, 0b01100110, 0b01100110, 0b00000000],
+    [0b01100110, 0b01100110, 0b00000000, 0b01100110, 0b01100110, 0b00000000, 0b01100110, 0b01100110],
+];
+
+/// Condensed "10" that fits the width of one module
+#[rustfmt::skip]
+const RANK_TEN: [u8; 8] = [
+    0b00000000,
+    0b01001100,
+    0b11010010,
+    0b01010010,
+    0b01010010,
+    0b01010010,
+    0b11101100,
+    0b00000000,
+];
+
+/// Returns the glyph of a die face.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidDigit` if `face` is not in the range 1 to 6.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// matrix.write_buffer(0, &die_face(roll)?)?;
+/// ```
+pub fn die_face(face: u8) -> Result<MatrixBuffer> {
+    match face {
+        1..=6 => DIE_FACES
+            .get(usize::from(face).saturating_sub(1))
+            .copied()
+            .map(MatrixBuffer::from_data)
+            .ok_or(Error::InvalidDigit),
+        _ => Err(Error::InvalidDigit),
+    }
+}
+
+/// Rank of a playing card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardRank {
+    /// Ace
+    Ace,
+    /// Numbered card, 2 to 10
+    Number(u8),
+    /// Jack
+    Jack,
+    /// Queen
+    Queen,
+    /// King
+    King,
+}
+
+impl CardRank {
+    /// Convert the rank into a `MatrixBuffer` pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` for a `Number` outside 2 to 10.
+    pub fn to_buffer(&self) -> Result<MatrixBuffer> {
+        let ch = match self {
+            CardRank::Ace => 'A',
+            CardRank::Jack => 'J',
+            CardRank::Queen => 'Q',
+            CardRank::King => 'K',
+            CardRank::Number(10) => return Ok(MatrixBuffer::from_data(RANK_TEN)),
+            CardRank::Number(n @ 2..=9) => b'0'.saturating_add(*n) as char,
+            CardRank::Number(_) => return Err(Error::InvalidDigit),
+        };
+        Ok(MatrixBuffer::from_data(STANDARD_LED_FONT.get_char(ch)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(buffer: &MatrixBuffer) -> u32 {
+        buffer.data().iter().map(|row| row.count_ones()).sum()
+    }
+
+    #[test]
+    fn test_die_faces_have_matching_pips() {
+        for face in 1..=6 {
+            // Each pip is 2x2 pixels
+            assert_eq!(lit(&die_face(face).unwrap()), face as u32 * 4);
+        }
+    }
+
+    #[test]
+    fn test_die_face_invalid() {
+        assert_eq!(die_face(0).err(), Some(Error::InvalidDigit));
+        assert_eq!(die_face(7).err(), Some(Error::InvalidDigit));
+    }
+
+    #[test]
+    fn test_card_ranks() {
+        assert_eq!(
+            CardRank::Ace.to_buffer().unwrap().data(),
+            &STANDARD_LED_FONT.get_char('A')
+        );
+        assert_eq!(
+            CardRank::Number(7).to_buffer().unwrap().data(),
+            &STANDARD_LED_FONT.get_char('7')
+        );
+        assert_eq!(CardRank::Number(10).to_buffer().unwrap().data(), &RANK_TEN);
+        assert_eq!(
+            CardRank::Number(1).to_buffer().err(),
+            Some(Error::InvalidDigit)
+        );
+        assert_eq!(
+            CardRank::Number(11).to_buffer().err(),
+            Some(Error::InvalidDigit)
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Heartbeat indicator for long-running installations
+//!
+//! A [`Heartbeat`] blinks a single pixel or a small corner mark so it is easy
+//! to see that the MCU is still alive. It is drawn as an overlay when the
+//! matrix is flushed and never touches the framebuffer, so the main content
+//! is left as it is.
+
+/// Corner of the display used by a corner heartbeat mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    /// Top-left corner
+    TopLeft,
+    /// Top-right corner
+    TopRight,
+    /// Bottom-left corner
+    BottomLeft,
+    /// Bottom-right corner
+    BottomRight,
+}
+
+/// What the heartbeat blinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartbeatMark {
+    /// A single pixel at logical coordinates (x, y)
+    Pixel(usize, usize),
+    /// A 2x2 block in a corner of the display
+    Corner(Corner),
+}
+
+/// Blinking "still alive" indicator driven by [`Heartbeat::tick`].
+///
+/// The mark is inverted against the content underneath while it is lit, so it
+/// stays visible on both dark and lit areas.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// matrix.set_heartbeat(Some(Heartbeat::corner(Corner::TopRight, 1000)));
+/// loop {
+///     matrix.tick_heartbeat(10)?; // called every 10 ms
+///     delay.delay_ms(10);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+    mark: HeartbeatMark,
+    period_ms: u32,
+    elapsed_ms: u32,
+}
+
+impl Heartbeat {
+    /// Blink a single pixel, completing one on/off cycle every `period_ms`.
+    pub const fn pixel(x: usize, y: usize, period_ms: u32) -> Self {
+        Self::new(HeartbeatMark::Pixel(x, y), period_ms)
+    }
+
+    /// Blink a 2x2 block in a corner, completing one on/off cycle every `period_ms`.
+    pub const fn corner(corner: Corner, period_ms: u32) -> Self {
+        Self::new(HeartbeatMark::Corner(corner), period_ms)
+    }
+
+    /// Create a heartbeat for the given mark.
+    ///
+    /// A `period_ms` of 0 keeps the mark permanently lit.
+    pub const fn new(mark: HeartbeatMark, period_ms: u32) -> Self {
+        Self {
+            mark,
+            period_ms,
+            elapsed_ms: 0,
+        }
+    }
+
+    /// Returns the blinking mark.
+    pub fn mark(&self) -> HeartbeatMark {
+        self.mark
+    }
+
+    /// Returns the blink period in milliseconds.
+    pub fn period_ms(&self) -> u32 {
+        self.period_ms
+    }
+
+    /// Changes the blink period without restarting the current cycle.
+    pub fn set_period_ms(&mut self, period_ms: u32) {
+        self.period_ms = period_ms;
+    }
+
+    /// Returns `true` while the mark is in the lit half of its cycle.
+    pub fn is_lit(&self) -> bool {
+        self.period_ms == 0 || self.elapsed_ms < self.period_ms / 2
+    }
+
+    /// Advances the heartbeat by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the mark switched between lit and dark, meaning the
+    /// display needs to be refreshed.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        if self.period_ms == 0 {
+            return false;
+        }
+        let was_lit = self.is_lit();
+        // Wrap around the period without overflowing
+        let step = dt_ms.checked_rem(self.period_ms).unwrap_or(0);
+        let left = self.period_ms.saturating_sub(self.elapsed_ms);
+        self.elapsed_ms = if step >= left {
+            step.saturating_sub(left)
+        } else {
+            self.elapsed_ms.saturating_add(step)
+        };
+        was_lit != self.is_lit()
+    }
+
+    /// Returns the area covered by the mark as `(x, y, width, height)` on a
+    /// display of the given size, or `None` if it lies outside.
+    pub fn area(&self, width: usize, height: usize) -> Option<(usize, usize, usize, usize)> {
+        let area = match self.mark {
+            HeartbeatMark::Pixel(x, y) => (x, y, 1, 1),
+            HeartbeatMark::Corner(corner) => {
+                if width < 2 || height < 2 {
+                    return None;
+                }
+                let (right, bottom) = (width.saturating_sub(2), height.saturating_sub(2));
+                match corner {
+                    Corner::TopLeft => (0, 0, 2, 2),
+                    Corner::TopRight => (right, 0, 2, 2),
+                    Corner::BottomLeft => (0, bottom, 2, 2),
+                    Corner::BottomRight => (right, bottom, 2, 2),
+                }
+            }
+        };
+        (area.0.saturating_add(area.2) <= width && area.1.saturating_add(area.3) <= height)
+            .then_some(area)
+    }
+
+    /// Returns `true` if the mark is lit at (`x`, `y`) on a display of the given size.
+    pub fn covers(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        if !self.is_lit() {
+            return false;
+        }
+        self.area(width, height).is_some_and(|(ax, ay, aw, ah)| {
+            (ax..ax.saturating_add(aw)).contains(&x) && (ay..ay.saturating_add(ah)).contains(&y)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_toggles_every_half_period() {
+        let mut heartbeat = Heartbeat::pixel(0, 0, 1000);
+        assert!(heartbeat.is_lit());
+
+        assert!(!heartbeat.tick(400));
+        assert!(heartbeat.is_lit());
+
+        assert!(heartbeat.tick(100));
+        assert!(!heartbeat.is_lit());
+
+        assert!(heartbeat.tick(500));
+        assert!(heartbeat.is_lit());
+    }
+
+    #[test]
+    fn test_tick_large_step_wraps() {
+        let mut heartbeat = Heartbeat::pixel(0, 0, 1000);
+        heartbeat.tick(2700);
+        assert!(!heartbeat.is_lit());
+    }
+
+    #[test]
+    fn test_zero_period_always_lit() {
+        let mut heartbeat = Heartbeat::pixel(0, 0, 0);
+        assert!(!heartbeat.tick(1234));
+        assert!(heartbeat.is_lit());
+    }
+
+    #[test]
+    fn test_corner_area() {
+        let heartbeat = Heartbeat::corner(Corner::BottomRight, 1000);
+        assert_eq!(heartbeat.area(32, 8), Some((30, 6, 2, 2)));
+        assert!(heartbeat.covers(31, 7, 32, 8));
+        assert!(!heartbeat.covers(29, 7, 32, 8));
+
+        let heartbeat = Heartbeat::corner(Corner::TopLeft, 1000);
+        assert_eq!(heartbeat.area(8, 8), Some((0, 0, 2, 2)));
+    }
+
+    #[test]
+    fn test_pixel_outside_display() {
+        let heartbeat = Heartbeat::pixel(8, 0, 1000);
+        assert_eq!(heartbeat.area(8, 8), None);
+        assert!(!heartbeat.covers(8, 0, 8, 8));
+    }
+
+    #[test]
+    fn test_covers_only_when_lit() {
+        let mut heartbeat = Heartbeat::pixel(3, 4, 100);
+        assert!(heartbeat.covers(3, 4, 8, 8));
+        heartbeat.tick(50);
+        assert!(!heartbeat.covers(3, 4, 8, 8));
+    }
+}
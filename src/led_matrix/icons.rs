@@ -0,0 +1,219 @@
+//! 8x8 weather and moon-phase icons for clock and weather displays
+
+use crate::led_matrix::buffer::MatrixBuffer;
+
+/// Weather condition icons.
+///
+/// Like [`Symbol`](crate::led_matrix::symbols::Symbol), an icon becomes a
+/// displayable buffer with `.to_buffer()`:
+///
+/// ```rust,ignore
+/// let icon = match forecast.code {
+///     0..=1 => WeatherIcon::Sun,
+///     2..=3 => WeatherIcon::Cloud,
+///     51..=67 => WeatherIcon::Rain,
+///     71..=77 => WeatherIcon::Snow,
+///     _ => WeatherIcon::Storm,
+/// };
+/// matrix.write_buffer(0, &icon.to_buffer())?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherIcon {
+    /// Sun with rays
+    Sun,
+    /// Cloud
+    Cloud,
+    /// Cloud with rain streaks
+    Rain,
+    /// Cloud with snowflakes
+    Snow,
+    /// Cloud with a lightning bolt
+    Storm,
+}
+
+impl WeatherIcon {
+    /// Convert the icon into a `MatrixBuffer` pattern.
+    #[rustfmt::skip]
+    pub const fn to_buffer(&self) -> MatrixBuffer {
+        MatrixBuffer::from_data(match self {
+            WeatherIcon::Sun => [
+                0b00011000,
+                0b01000010,
+                0b00111100,
+                0b10111101,
+                0b10111101,
+                0b00111100,
+                0b01000010,
+                0b00011000,
+            ],
+            WeatherIcon::Cloud => [
+                0b00000000,
+                0b00000000,
+                0b00011000,
+                0b01100110,
+                0b10000001,
+                0b10000001,
+                0b01111110,
+                0b00000000,
+            ],
+            WeatherIcon::Rain => [
+                0b00011000,
+                0b01100110,
+                0b10000001,
+                0b01111110,
+                0b00000000,
+                0b01001001,
+                0b10010010,
+                0b00000000,
+            ],
+            WeatherIcon::Snow => [
+                0b00011000,
+                0b01100110,
+                0b10000001,
+                0b01111110,
+                0b00000000,
+                0b01000100,
+                0b00010001,
+                0b01000100,
+            ],
+            WeatherIcon::Storm => [
+                0b00011000,
+                0b01100110,
+                0b10000001,
+                0b01111110,
+                0b00010000,
+                0b00111000,
+                0b00001000,
+                0b00010000,
+            ],
+        })
+    }
+}
+
+/// The eight principal phases of the moon, as seen from the northern
+/// hemisphere (the lit side grows from the right).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoonPhase {
+    /// Dark disc, drawn as an outline
+    New,
+    /// Thin sliver lit on the right
+    WaxingCrescent,
+    /// Right half lit
+    FirstQuarter,
+    /// Lit but for a sliver on the left
+    WaxingGibbous,
+    /// Fully lit disc
+    Full,
+    /// Lit but for a sliver on the right
+    WaningGibbous,
+    /// Left half lit
+    LastQuarter,
+    /// Thin sliver lit on the left
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    /// All phases in order, starting at the new moon
+    pub const ALL: [MoonPhase; 8] = [
+        MoonPhase::New,
+        MoonPhase::WaxingCrescent,
+        MoonPhase::FirstQuarter,
+        MoonPhase::WaxingGibbous,
+        MoonPhase::Full,
+        MoonPhase::WaningGibbous,
+        MoonPhase::LastQuarter,
+        MoonPhase::WaningCrescent,
+    ];
+
+    /// Returns the phase nearest to a moon age in days since the last new
+    /// moon; ages past the 29.5-day cycle wrap around.
+    pub const fn from_age_days(age: u8) -> Self {
+        // Each phase spans about 3.7 days, centred on its exact age
+        // At most 29 * 8 + 15, far from overflowing
+        let index = (age as usize % 30).wrapping_mul(8).wrapping_add(15) / 30 % 8;
+        match Self::ALL.split_at_checked(index) {
+            Some((_, [phase, ..])) => *phase,
+            _ => MoonPhase::New,
+        }
+    }
+
+    /// Convert the phase into a `MatrixBuffer` pattern.
+    #[rustfmt::skip]
+    pub const fn to_buffer(&self) -> MatrixBuffer {
+        let (upper, middle) = match self {
+            MoonPhase::New => (0b01000010, 0b10000001),
+            MoonPhase::WaxingCrescent => (0b01000110, 0b10000011),
+            MoonPhase::FirstQuarter => (0b01001110, 0b10001111),
+            MoonPhase::WaxingGibbous => (0b01011110, 0b10111111),
+            MoonPhase::Full => (0b01111110, 0b11111111),
+            MoonPhase::WaningGibbous => (0b01111010, 0b11111101),
+            MoonPhase::LastQuarter => (0b01110010, 0b11110001),
+            MoonPhase::WaningCrescent => (0b01100010, 0b11000001),
+        };
+        MatrixBuffer::from_data([
+            0b00111100,
+            upper,
+            middle,
+            middle,
+            middle,
+            middle,
+            upper,
+            0b00111100,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moon_phase_from_age() {
+        assert_eq!(MoonPhase::from_age_days(0), MoonPhase::New);
+        assert_eq!(MoonPhase::from_age_days(7), MoonPhase::FirstQuarter);
+        assert_eq!(MoonPhase::from_age_days(15), MoonPhase::Full);
+        assert_eq!(MoonPhase::from_age_days(22), MoonPhase::LastQuarter);
+        assert_eq!(MoonPhase::from_age_days(29), MoonPhase::New);
+        assert_eq!(MoonPhase::from_age_days(37), MoonPhase::FirstQuarter);
+    }
+
+    #[test]
+    fn test_moon_phases_mirror() {
+        let mirrored = |phase: MoonPhase| phase.to_buffer().data().map(u8::reverse_bits);
+        for (waxing, waning) in [
+            (MoonPhase::WaxingCrescent, MoonPhase::WaningCrescent),
+            (MoonPhase::FirstQuarter, MoonPhase::LastQuarter),
+            (MoonPhase::WaxingGibbous, MoonPhase::WaningGibbous),
+        ] {
+            assert_eq!(mirrored(waxing), *waning.to_buffer().data());
+        }
+        // Lit area grows towards the full moon
+        let lit = |phase: MoonPhase| -> u32 {
+            phase
+                .to_buffer()
+                .data()
+                .iter()
+                .map(|row| row.count_ones())
+                .sum()
+        };
+        for pair in MoonPhase::ALL[..5].windows(2) {
+            assert!(lit(pair[0]) < lit(pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_weather_icons_differ() {
+        let icons = [
+            WeatherIcon::Sun,
+            WeatherIcon::Cloud,
+            WeatherIcon::Rain,
+            WeatherIcon::Snow,
+            WeatherIcon::Storm,
+        ];
+        for (index, icon) in icons.iter().enumerate() {
+            for other in &icons[index + 1..] {
+                assert_ne!(icon.to_buffer().data(), other.to_buffer().data());
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Overlay layers composited over the framebuffer
+//!
+//! A [`Layer`] is an extra drawing surface of the same size as the display.
+//! Layers owned by an [`LedMatrix`](crate::led_matrix::LedMatrix) are combined
+//! with the framebuffer at flush time, bottom to top, using each layer's
+//! [`BlendMode`]. Hiding a layer restores whatever is underneath without the
+//! application having to save and redraw those pixels.
+//!
+//! Layers only reach the hardware through a flush. Drawing calls in
+//! [`UpdateMode::WriteThrough`](crate::led_matrix::UpdateMode::WriteThrough)
+//! send the framebuffer rows of the touched device as they are.
+
+use crate::{Error, Result};
+
+/// How a layer is combined with the content below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Lit layer pixels are added to the content below.
+    #[default]
+    Or,
+    /// Lit layer pixels invert the content below.
+    Xor,
+    /// Every pixel drawn on the layer, lit or dark, replaces the content
+    /// below. Pixels never drawn (or erased) stay transparent.
+    Replace,
+}
+
+/// Pixel never drawn, or erased
+const TRANSPARENT: u8 = 0;
+/// Pixel drawn dark
+const OFF: u8 = 1;
+/// Pixel drawn lit
+const ON: u8 = 2;
+
+/// Drawing surface composited over the framebuffer at flush time.
+///
+/// Pixels use the same logical coordinates as the display. Every pixel starts
+/// out transparent; drawing it makes it part of the layer until it is erased.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // A 4-module display with one alert layer
+/// let mut matrix: LedMatrix<_, 256, 4, 1> = LedMatrix::from_spi(spi)?;
+/// matrix.draw_text("12:30")?;
+///
+/// let alert = matrix.layer_mut(0)?;
+/// alert.set_blend(BlendMode::Replace);
+/// alert.fill_region(0, 0, 8, 8, true)?;
+/// matrix.flush()?;
+///
+/// matrix.layer_mut(0)?.hide(); // the clock is back
+/// matrix.flush()?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layer<const BUFFER_LENGTH: usize> {
+    width: usize,
+    height: usize,
+    visible: bool,
+    blend: BlendMode,
+    pixels: [u8; BUFFER_LENGTH],
+}
+
+impl<const BUFFER_LENGTH: usize> Layer<BUFFER_LENGTH> {
+    /// Create a visible, fully transparent layer of the given size.
+    ///
+    /// Pixels beyond `BUFFER_LENGTH` are treated as outside the layer.
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            visible: true,
+            blend: BlendMode::Or,
+            pixels: [TRANSPARENT; BUFFER_LENGTH],
+        }
+    }
+
+    /// Returns the width of the layer in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the layer in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Resizes the layer and erases its contents.
+    pub(crate) fn reset(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.clear();
+    }
+
+    /// Makes the layer part of the composited output.
+    pub fn show(&mut self) {
+        self.visible = true;
+    }
+
+    /// Removes the layer from the composited output, keeping its contents.
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Shows or hides the layer.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Returns `true` if the layer is shown.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Sets how the layer is combined with the content below it.
+    pub fn set_blend(&mut self, blend: BlendMode) {
+        self.blend = blend;
+    }
+
+    /// Returns how the layer is combined with the content below it.
+    pub fn blend(&self) -> BlendMode {
+        self.blend
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = y.checked_mul(self.width)?.checked_add(x)?;
+        (index < BUFFER_LENGTH).then_some(index)
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Option<u8> {
+        self.pixels.get(self.index(x, y)?).copied()
+    }
+
+    fn pixel_mut(&mut self, x: usize, y: usize) -> Result<&mut u8> {
+        let index = self.index(x, y).ok_or(Error::BufferError)?;
+        self.pixels.get_mut(index).ok_or(Error::BufferError)
+    }
+
+    /// Draws a lit or dark pixel on the layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the pixel lies outside the layer.
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) -> Result<()> {
+        *self.pixel_mut(x, y)? = if on { ON } else { OFF };
+        Ok(())
+    }
+
+    /// Makes a pixel transparent again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the pixel lies outside the layer.
+    pub fn erase_pixel(&mut self, x: usize, y: usize) -> Result<()> {
+        *self.pixel_mut(x, y)? = TRANSPARENT;
+        Ok(())
+    }
+
+    /// Returns the drawn state of a pixel, or `None` if it is transparent.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the pixel lies outside the layer.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Result<Option<bool>> {
+        Ok(match self.pixel(x, y).ok_or(Error::BufferError)? {
+            TRANSPARENT => None,
+            value => Some(value == ON),
+        })
+    }
+
+    /// Draws the rectangle at (`x`, `y`) with the given size lit or dark.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the region extends past the layer.
+    pub fn fill_region(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        on: bool,
+    ) -> Result<()> {
+        if x.saturating_add(width) > self.width || y.saturating_add(height) > self.height {
+            return Err(Error::BufferError);
+        }
+        for py in y..y.saturating_add(height) {
+            for px in x..x.saturating_add(width) {
+                self.set_pixel(px, py, on)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes every pixel transparent.
+    pub fn clear(&mut self) {
+        self.pixels.fill(TRANSPARENT);
+    }
+
+    /// Combines the layer with the pixel `below` it at (`x`, `y`).
+    pub fn composite(&self, x: usize, y: usize, below: bool) -> bool {
+        if !self.visible {
+            return below;
+        }
+        let Some(pixel) = self.pixel(x, y) else {
+            return below;
+        };
+        match (self.blend, pixel) {
+            (_, TRANSPARENT) => below,
+            (BlendMode::Or, value) => below || value == ON,
+            (BlendMode::Xor, value) => below ^ (value == ON),
+            (BlendMode::Replace, value) => value == ON,
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+mod eg_impl {
+    use super::Layer;
+    use embedded_graphics_core::{
+        Pixel,
+        pixelcolor::BinaryColor,
+        prelude::{DrawTarget, OriginDimensions, Size},
+    };
+
+    impl<const BUFFER_LENGTH: usize> DrawTarget for Layer<BUFFER_LENGTH> {
+        type Color = BinaryColor;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(pos, color) in pixels.into_iter() {
+                if pos.x >= 0 && pos.y >= 0 {
+                    // Pixels outside the layer are ignored
+                    let _ = self.set_pixel(pos.x as usize, pos.y as usize, color.is_on());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<const BUFFER_LENGTH: usize> OriginDimensions for Layer<BUFFER_LENGTH> {
+        fn size(&self) -> Size {
+            Size::new(self.width as u32, self.height as u32)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_layer_is_transparent() {
+        let layer: Layer<64> = Layer::new(8, 8);
+        assert!(layer.is_visible());
+        assert_eq!(layer.get_pixel(3, 3), Ok(None));
+        assert!(layer.composite(3, 3, true));
+        assert!(!layer.composite(3, 3, false));
+    }
+
+    #[test]
+    fn test_blend_modes() {
+        let mut layer: Layer<64> = Layer::new(8, 8);
+        layer.set_pixel(0, 0, true).unwrap();
+        layer.set_pixel(1, 0, false).unwrap();
+
+        assert!(layer.composite(0, 0, false));
+        assert!(layer.composite(1, 0, true));
+
+        layer.set_blend(BlendMode::Xor);
+        assert!(!layer.composite(0, 0, true));
+        assert!(layer.composite(1, 0, true));
+
+        layer.set_blend(BlendMode::Replace);
+        assert!(layer.composite(0, 0, false));
+        assert!(!layer.composite(1, 0, true));
+        assert!(layer.composite(2, 0, true));
+    }
+
+    #[test]
+    fn test_hidden_layer_is_ignored() {
+        let mut layer: Layer<64> = Layer::new(8, 8);
+        layer.set_blend(BlendMode::Replace);
+        layer.fill_region(0, 0, 8, 8, false).unwrap();
+        layer.hide();
+        assert!(layer.composite(4, 4, true));
+        layer.show();
+        assert!(!layer.composite(4, 4, true));
+    }
+
+    #[test]
+    fn test_erase_and_bounds() {
+        let mut layer: Layer<128> = Layer::new(16, 8);
+        layer.set_pixel(15, 7, true).unwrap();
+        assert_eq!(layer.get_pixel(15, 7), Ok(Some(true)));
+        layer.erase_pixel(15, 7).unwrap();
+        assert_eq!(layer.get_pixel(15, 7), Ok(None));
+
+        assert_eq!(layer.set_pixel(16, 0, true), Err(Error::BufferError));
+        assert_eq!(
+            layer.fill_region(10, 0, 8, 1, true),
+            Err(Error::BufferError)
+        );
+    }
+}
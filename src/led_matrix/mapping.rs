@@ -0,0 +1,253 @@
+//! Logical pixel to hardware mapping
+//!
+//! A [`PixelMapper`] translates a logical pixel position on the whole display
+//! into the device, digit register and bit that drive the matching LED. All
+//! orientation and wiring quirks of a build live in one mapper, and the rest
+//! of the crate only deals with logical coordinates.
+//!
+//! Built-in mappers cover the common cases:
+//!
+//! - [`ChainMapper::FC16`] - a horizontal chain of FC-16 modules
+//! - [`ChainMapper::GENERIC`] - a horizontal chain of column-major generic modules
+//! - [`TiledMapper`] - modules arranged in a grid, optionally serpentine
+//!
+//! Hand-wired or exotic layouts can implement the trait themselves.
+
+use crate::led_matrix::wiring::Wiring;
+
+/// Hardware location of a single LED.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelAddress {
+    /// Index of the device in the daisy chain (0 = furthest from the MCU)
+    pub device: usize,
+    /// Digit register (0 to 7) driving the LED
+    pub digit: u8,
+    /// Bit (0 to 7) within the digit register
+    pub bit: u8,
+}
+
+/// Maps logical pixel coordinates onto the MAX7219 chain.
+///
+/// Logical coordinates start at (0, 0) in the top-left corner of the whole
+/// display. Implementations must map every logical pixel inside
+/// [`PixelMapper::size`] to a distinct LED.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// /// A single module mounted upside down
+/// struct UpsideDown;
+///
+/// impl PixelMapper for UpsideDown {
+///     fn size(&self, device_count: usize) -> (usize, usize) {
+///         (device_count * 8, 8)
+///     }
+///
+///     fn map(&self, x: usize, y: usize, device_count: usize) -> Option<PixelAddress> {
+///         let (width, height) = self.size(device_count);
+///         if x >= width || y >= height {
+///             return None;
+///         }
+///         Some(PixelAddress { device: x / 8, digit: 7 - y as u8, bit: (x % 8) as u8 })
+///     }
+/// }
+///
+/// static MAPPER: UpsideDown = UpsideDown;
+/// let matrix = Matrix4::from_spi(spi)?.with_mapper(&MAPPER)?;
+/// ```
+pub trait PixelMapper {
+    /// Returns the logical `(width, height)` in pixels for a chain of
+    /// `device_count` devices.
+    ///
+    /// Both values must be multiples of 8.
+    fn size(&self, device_count: usize) -> (usize, usize);
+
+    /// Returns the hardware location of the logical pixel at (`x`, `y`),
+    /// or `None` if the pixel lies outside the display.
+    fn map(&self, x: usize, y: usize, device_count: usize) -> Option<PixelAddress>;
+}
+
+/// Modules chained in a single horizontal row.
+///
+/// The module nearest to the MCU shows the leftmost 8 columns, matching the
+/// default layout of [`LedMatrix`](crate::led_matrix::LedMatrix).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChainMapper {
+    /// Wiring shared by every module in the chain
+    pub wiring: Wiring,
+}
+
+impl ChainMapper {
+    /// A chain of FC-16 modules
+    pub const FC16: Self = Self::new(Wiring::FC16);
+
+    /// A chain of generic column-major modules
+    pub const GENERIC: Self = Self::new(Wiring::GENERIC);
+
+    /// Create a chain mapper with the given module wiring
+    pub const fn new(wiring: Wiring) -> Self {
+        Self { wiring }
+    }
+}
+
+impl PixelMapper for ChainMapper {
+    fn size(&self, device_count: usize) -> (usize, usize) {
+        (device_count.saturating_mul(8), 8)
+    }
+
+    fn map(&self, x: usize, y: usize, device_count: usize) -> Option<PixelAddress> {
+        let device = device_count.checked_sub(1)?.checked_sub(x / 8)?;
+        if y >= 8 {
+            return None;
+        }
+
+        let (digit, bit) = self.wiring.locate((x % 8) as u8, y as u8);
+        Some(PixelAddress { device, digit, bit })
+    }
+}
+
+/// Modules arranged in a grid of `columns` by `rows` tiles.
+///
+/// Tiles are chained row by row starting at the top-left tile, which is the
+/// module nearest to the MCU. With `serpentine` enabled, every odd row is
+/// chained right to left, as is common for zig-zag wired walls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TiledMapper {
+    /// Number of modules per row
+    pub columns: usize,
+    /// Number of module rows
+    pub rows: usize,
+    /// Whether odd rows are chained right to left
+    pub serpentine: bool,
+    /// Wiring shared by every module in the grid
+    pub wiring: Wiring,
+}
+
+impl TiledMapper {
+    /// Create a grid of FC-16 modules chained row by row
+    pub const fn new(columns: usize, rows: usize) -> Self {
+        Self {
+            columns,
+            rows,
+            serpentine: false,
+            wiring: Wiring::FC16,
+        }
+    }
+
+    /// Chain odd rows right to left
+    pub const fn serpentine(mut self, serpentine: bool) -> Self {
+        self.serpentine = serpentine;
+        self
+    }
+
+    /// Use the given module wiring
+    pub const fn wiring(mut self, wiring: Wiring) -> Self {
+        self.wiring = wiring;
+        self
+    }
+}
+
+impl PixelMapper for TiledMapper {
+    fn size(&self, _device_count: usize) -> (usize, usize) {
+        (self.columns.saturating_mul(8), self.rows.saturating_mul(8))
+    }
+
+    fn map(&self, x: usize, y: usize, device_count: usize) -> Option<PixelAddress> {
+        let (width, height) = self.size(device_count);
+        if x >= width || y >= height {
+            return None;
+        }
+
+        let (tile_x, tile_y) = (x / 8, y / 8);
+        let tile_x = if self.serpentine && tile_y % 2 == 1 {
+            self.columns.saturating_sub(1).saturating_sub(tile_x)
+        } else {
+            tile_x
+        };
+        // Position counted from the MCU
+        let position = tile_y.checked_mul(self.columns)?.checked_add(tile_x)?;
+        let device = device_count.checked_sub(1)?.checked_sub(position)?;
+
+        let (digit, bit) = self.wiring.locate((x % 8) as u8, (y % 8) as u8);
+        Some(PixelAddress { device, digit, bit })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_mapper_fc16() {
+        let mapper = ChainMapper::FC16;
+        assert_eq!(mapper.size(4), (32, 8));
+        assert_eq!(
+            mapper.map(0, 0, 4),
+            Some(PixelAddress {
+                device: 3,
+                digit: 0,
+                bit: 7
+            })
+        );
+        assert_eq!(
+            mapper.map(31, 7, 4),
+            Some(PixelAddress {
+                device: 0,
+                digit: 7,
+                bit: 0
+            })
+        );
+        assert_eq!(mapper.map(32, 0, 4), None);
+        assert_eq!(mapper.map(0, 8, 4), None);
+    }
+
+    #[test]
+    fn test_chain_mapper_generic() {
+        let mapper = ChainMapper::GENERIC;
+        assert_eq!(
+            mapper.map(2, 5, 1),
+            Some(PixelAddress {
+                device: 0,
+                digit: 2,
+                bit: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_tiled_mapper() {
+        let mapper = TiledMapper::new(2, 2);
+        assert_eq!(mapper.size(4), (16, 16));
+        // Top-left tile is nearest to the MCU
+        assert_eq!(mapper.map(0, 0, 4).map(|a| a.device), Some(3));
+        assert_eq!(mapper.map(8, 0, 4).map(|a| a.device), Some(2));
+        assert_eq!(mapper.map(0, 8, 4).map(|a| a.device), Some(1));
+        assert_eq!(mapper.map(15, 15, 4).map(|a| a.device), Some(0));
+        assert_eq!(
+            mapper.map(9, 10, 4),
+            Some(PixelAddress {
+                device: 0,
+                digit: 2,
+                bit: 6
+            })
+        );
+        assert_eq!(mapper.map(16, 0, 4), None);
+    }
+
+    #[test]
+    fn test_tiled_mapper_serpentine() {
+        let mapper = TiledMapper::new(2, 2).serpentine(true);
+        assert_eq!(mapper.map(0, 0, 4).map(|a| a.device), Some(3));
+        assert_eq!(mapper.map(8, 0, 4).map(|a| a.device), Some(2));
+        // Second row runs right to left
+        assert_eq!(mapper.map(8, 8, 4).map(|a| a.device), Some(1));
+        assert_eq!(mapper.map(0, 8, 4).map(|a| a.device), Some(0));
+    }
+
+    #[test]
+    fn test_tiled_mapper_short_chain() {
+        // Grid needs 4 devices but only 3 are chained
+        let mapper = TiledMapper::new(2, 2);
+        assert_eq!(mapper.map(15, 15, 3), None);
+    }
+}
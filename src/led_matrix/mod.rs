@@ -1,9 +1,50 @@
 //! LED matrix display implementation
 
+pub mod animation;
+pub mod boot;
 pub mod buffer;
+pub mod burn_in;
+pub mod command;
+pub mod cube;
+pub mod dimming;
 pub mod display;
+#[cfg(feature = "effects")]
+pub mod effects;
+pub mod flush;
 pub mod fonts;
+pub mod games;
+pub mod heartbeat;
+pub mod icons;
+pub mod layer;
+pub mod mapping;
+pub mod patterns;
+pub mod scheduler;
 pub mod scroll;
+pub mod snake;
+pub mod strip;
 pub mod symbols;
+pub mod text;
+pub mod transition;
+pub mod widgets;
+pub mod wiring;
 
-pub use display::LedMatrix;
+pub use animation::{Animation, AnimationCursor};
+pub use boot::BootAnimation;
+pub use burn_in::PixelShift;
+pub use command::Command;
+pub use cube::{CubeDriver, Voxel};
+pub use dimming::{DimRegion, Dimmer};
+pub use display::{ClipRect, FlushOrder, LedMatrix, UpdateMode};
+pub use flush::FlushStateMachine;
+pub use games::{CardRank, die_face};
+pub use heartbeat::{Corner, Heartbeat, HeartbeatMark};
+pub use icons::{MoonPhase, WeatherIcon};
+pub use layer::{BlendMode, Layer};
+pub use mapping::{ChainMapper, PixelAddress, PixelMapper, TiledMapper};
+pub use patterns::TestPattern;
+pub use scheduler::{DueTasks, Scheduler, TaskId};
+pub use snake::Snake;
+pub use strip::{ColumnScroller, GlyphTransform};
+pub use text::{Align, TextStyle};
+pub use transition::Wipe;
+pub use wiring::{Orientation, Wiring};
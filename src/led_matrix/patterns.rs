@@ -0,0 +1,113 @@
+//! Diagnostic test patterns for LED matrix modules
+//!
+//! Each pattern makes one property of the wiring obvious at a glance, which
+//! shortens the "why is my display mirrored" debugging loop:
+//!
+//! - [`TestPattern::Checkerboard`] - every LED can be toggled independently
+//! - [`TestPattern::RowIndex`] - row order and column direction
+//! - [`TestPattern::ColumnSweep`] - column order and row direction
+//! - [`TestPattern::DeviceIndex`] - position of each device in the chain
+
+use crate::led_matrix::fonts::STANDARD_LED_FONT;
+
+/// Diagnostic pattern rendered on every device of a matrix chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestPattern {
+    /// Alternating lit and dark pixels.
+    ///
+    /// Pass `true` to swap lit and dark pixels, showing every LED once across
+    /// both variants.
+    Checkerboard(bool),
+    /// Row `n` lights its leftmost `n + 1` pixels.
+    ///
+    /// The shortest bar should be at the top and all bars should start at the
+    /// left edge.
+    RowIndex,
+    /// Column `n` lights its topmost `n + 1` pixels.
+    ///
+    /// The shortest bar should be on the left and all bars should hang from
+    /// the top edge.
+    ColumnSweep,
+    /// Each device shows its index in the chain (0 = furthest from the MCU).
+    DeviceIndex,
+}
+
+impl TestPattern {
+    /// Returns the eight logical rows (bit 7 = leftmost pixel) for a device.
+    pub fn rows(&self, device_index: usize) -> [u8; 8] {
+        let mut rows = [0u8; 8];
+        match self {
+            TestPattern::Checkerboard(inverted) => {
+                for (row, data) in rows.iter_mut().enumerate() {
+                    let even = if *inverted { 0x55 } else { 0xAA };
+                    *data = if row % 2 == 0 { even } else { !even };
+                }
+            }
+            TestPattern::RowIndex => {
+                for (row, data) in rows.iter_mut().enumerate() {
+                    // Keep the leftmost `row + 1` bits
+                    *data = !0xFFu8
+                        .checked_shr((row as u32).saturating_add(1))
+                        .unwrap_or(0);
+                }
+            }
+            TestPattern::ColumnSweep => {
+                for (row, data) in rows.iter_mut().enumerate() {
+                    // Column `col` is lit on rows 0..=col
+                    for col in row..8 {
+                        *data |= 0x80 >> col;
+                    }
+                }
+            }
+            TestPattern::DeviceIndex => {
+                let digit = char::from_digit((device_index % 10) as u32, 10).unwrap_or('?');
+                rows = STANDARD_LED_FONT.get_char(digit);
+            }
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkerboard() {
+        let rows = TestPattern::Checkerboard(false).rows(0);
+        assert_eq!(rows, [0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55]);
+
+        let inverted = TestPattern::Checkerboard(true).rows(0);
+        for (a, b) in rows.iter().zip(inverted.iter()) {
+            assert_eq!(a ^ b, 0xFF);
+        }
+    }
+
+    #[test]
+    fn test_row_index() {
+        let rows = TestPattern::RowIndex.rows(3);
+        assert_eq!(rows[0], 0b1000_0000);
+        assert_eq!(rows[1], 0b1100_0000);
+        assert_eq!(rows[7], 0b1111_1111);
+    }
+
+    #[test]
+    fn test_column_sweep() {
+        let rows = TestPattern::ColumnSweep.rows(0);
+        assert_eq!(rows[0], 0b1111_1111);
+        assert_eq!(rows[1], 0b0111_1111);
+        assert_eq!(rows[7], 0b0000_0001);
+    }
+
+    #[test]
+    fn test_device_index() {
+        assert_eq!(
+            TestPattern::DeviceIndex.rows(2),
+            STANDARD_LED_FONT.get_char('2')
+        );
+        assert_eq!(
+            TestPattern::DeviceIndex.rows(7),
+            STANDARD_LED_FONT.get_char('7')
+        );
+    }
+}
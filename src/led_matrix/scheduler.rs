@@ -0,0 +1,322 @@
+//! Scheduling several animations onto one display
+//!
+//! When a clock widget, a heartbeat and a marquee all update on their own,
+//! each of them flushing the display wastes SPI bandwidth and may exceed
+//! the frame rate the application wants. A [`Scheduler`] tells each
+//! registered animation when to step, collects the regions they changed and
+//! sends them in a single flush per frame.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, Result,
+    led_matrix::LedMatrix,
+    time::{Clock, Ticker, is_due},
+};
+
+/// Handle of an animation registered with a [`Scheduler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(usize);
+
+/// Set of animations due to step, returned by [`Scheduler::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DueTasks(u32);
+
+impl DueTasks {
+    /// Returns `true` if the animation should step now.
+    pub fn contains(&self, task: TaskId) -> bool {
+        self.0 & (1 << task.0) != 0
+    }
+
+    /// Returns `true` if no animation is due.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Task {
+    interval_ms: u32,
+    next_due: Option<u32>,
+}
+
+/// Steps up to `TASKS` animations (at most 32) and coalesces their updates
+/// into one flush per frame.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut scheduler: Scheduler<2> = Scheduler::new(30);
+/// let clock_task = scheduler.register(1000)?;
+/// let scroll_task = scheduler.register(50)?;
+///
+/// loop {
+///     let due = scheduler.poll(&clock);
+///     if due.contains(clock_task) {
+///         draw_clock(&mut matrix)?;
+///         scheduler.mark_dirty(0, 0, 16, 8);
+///     }
+///     if due.contains(scroll_task) {
+///         step_marquee(&mut matrix)?;
+///         scheduler.mark_dirty(16, 0, 16, 8);
+///     }
+///     scheduler.flush(&mut matrix, &clock)?;
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scheduler<const TASKS: usize> {
+    tasks: [Option<Task>; TASKS],
+    frame: Ticker,
+    /// Bounding box of all changes since the last flush: (x0, y0, x1, y1), exclusive ends
+    dirty: Option<(usize, usize, usize, usize)>,
+}
+
+impl<const TASKS: usize> Scheduler<TASKS> {
+    /// Create a scheduler flushing at most `max_fps` times per second.
+    ///
+    /// A `max_fps` of 0 flushes whenever something changed.
+    pub const fn new(max_fps: u32) -> Self {
+        Self {
+            tasks: [None; TASKS],
+            frame: Ticker::from_fps(max_fps),
+            dirty: None,
+        }
+    }
+
+    /// Registers an animation stepping every `interval_ms` milliseconds.
+    ///
+    /// The animation is due on the first poll.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if all `TASKS` slots (or 32) are taken.
+    pub fn register(&mut self, interval_ms: u32) -> Result<TaskId> {
+        let (index, slot) = self
+            .tasks
+            .iter_mut()
+            .enumerate()
+            .take(32)
+            .find(|(_, slot)| slot.is_none())
+            .ok_or(Error::BufferError)?;
+        *slot = Some(Task {
+            interval_ms,
+            next_due: None,
+        });
+        Ok(TaskId(index))
+    }
+
+    /// Removes an animation, freeing its slot.
+    pub fn unregister(&mut self, task: TaskId) {
+        if let Some(slot) = self.tasks.get_mut(task.0) {
+            *slot = None;
+        }
+    }
+
+    /// Changes how often an animation steps.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the task is not registered.
+    pub fn set_interval(&mut self, task: TaskId, interval_ms: u32) -> Result<()> {
+        let task = self
+            .tasks
+            .get_mut(task.0)
+            .and_then(Option::as_mut)
+            .ok_or(Error::BufferError)?;
+        task.interval_ms = interval_ms;
+        Ok(())
+    }
+
+    /// Returns the animations due at `now_ms` and schedules their next step.
+    pub fn poll_at(&mut self, now_ms: u32) -> DueTasks {
+        let mut due = 0;
+        for (index, task) in self.tasks.iter_mut().enumerate().take(32) {
+            let Some(task) = task else {
+                continue;
+            };
+            if task
+                .next_due
+                .is_none_or(|deadline| is_due(now_ms, deadline))
+            {
+                due |= 1 << index;
+                // Keep a steady cadence unless the animation fell behind
+                let base = match task.next_due {
+                    Some(deadline) if !is_due(now_ms, deadline.wrapping_add(task.interval_ms)) => {
+                        deadline
+                    }
+                    _ => now_ms,
+                };
+                task.next_due = Some(base.wrapping_add(task.interval_ms));
+            }
+        }
+        DueTasks(due)
+    }
+
+    /// Same as [`Self::poll_at`] using the current time of `clock`.
+    pub fn poll<C: Clock>(&mut self, clock: &C) -> DueTasks {
+        self.poll_at(clock.now_ms())
+    }
+
+    /// Records that the rectangle at (`x`, `y`) with the given size changed.
+    ///
+    /// All rectangles marked before the next flush are merged into their
+    /// bounding box.
+    pub fn mark_dirty(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (x1, y1) = (x.saturating_add(width), y.saturating_add(height));
+        self.dirty = Some(match self.dirty {
+            Some((dx0, dy0, dx1, dy1)) => (dx0.min(x), dy0.min(y), dx1.max(x1), dy1.max(y1)),
+            None => (x, y, x1, y1),
+        });
+    }
+
+    /// Returns the pending region as `(x, y, width, height)`, if any.
+    pub fn dirty_region(&self) -> Option<(usize, usize, usize, usize)> {
+        self.dirty
+            .map(|(x0, y0, x1, y1)| (x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0)))
+    }
+
+    /// Flushes the pending region if anything changed and the frame-rate
+    /// limit allows it at `now_ms`.
+    ///
+    /// Returns `true` if a flush was sent. The region is clipped to the
+    /// display.
+    ///
+    /// # Errors
+    ///
+    /// Returns an SPI error if the flush fails; the region stays pending.
+    pub fn flush_at<
+        SPI,
+        const BUFFER_LENGTH: usize,
+        const DEVICE_COUNT: usize,
+        const LAYERS: usize,
+    >(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+        now_ms: u32,
+    ) -> Result<bool>
+    where
+        SPI: SpiDevice,
+    {
+        let Some((x0, y0, x1, y1)) = self.dirty else {
+            return Ok(false);
+        };
+        if !self.frame.ready_at(now_ms) {
+            return Ok(false);
+        }
+        let (x1, y1) = (x1.min(matrix.width()), y1.min(matrix.height()));
+        if x0 < x1 && y0 < y1 {
+            matrix.flush_region(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0))?;
+        }
+        self.dirty = None;
+        Ok(true)
+    }
+
+    /// Same as [`Self::flush_at`] using the current time of `clock`.
+    pub fn flush<
+        SPI,
+        C,
+        const BUFFER_LENGTH: usize,
+        const DEVICE_COUNT: usize,
+        const LAYERS: usize,
+    >(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+        clock: &C,
+    ) -> Result<bool>
+    where
+        SPI: SpiDevice,
+        C: Clock,
+    {
+        self.flush_at(matrix, clock.now_ms())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, Register, led_matrix::display::Matrix4};
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    #[test]
+    fn test_poll_steps_tasks_on_their_interval() {
+        let mut scheduler: Scheduler<2> = Scheduler::new(0);
+        let fast = scheduler.register(10).unwrap();
+        let slow = scheduler.register(25).unwrap();
+
+        let due = scheduler.poll_at(0);
+        assert!(due.contains(fast) && due.contains(slow));
+        assert!(scheduler.poll_at(5).is_empty());
+
+        let due = scheduler.poll_at(12);
+        assert!(due.contains(fast) && !due.contains(slow));
+        // Cadence is kept: next step at 20, not 22
+        assert!(scheduler.poll_at(20).contains(fast));
+
+        let due = scheduler.poll_at(25);
+        assert!(due.contains(slow) && !due.contains(fast));
+    }
+
+    #[test]
+    fn test_register_full() {
+        let mut scheduler: Scheduler<1> = Scheduler::new(0);
+        let task = scheduler.register(10).unwrap();
+        assert_eq!(scheduler.register(10), Err(Error::BufferError));
+
+        scheduler.unregister(task);
+        assert!(scheduler.register(10).is_ok());
+        assert_eq!(
+            scheduler.set_interval(TaskId(3), 5),
+            Err(Error::BufferError)
+        );
+    }
+
+    #[test]
+    fn test_dirty_regions_are_merged() {
+        let mut scheduler: Scheduler<0> = Scheduler::new(0);
+        assert_eq!(scheduler.dirty_region(), None);
+        scheduler.mark_dirty(2, 1, 2, 2);
+        scheduler.mark_dirty(10, 0, 1, 1);
+        scheduler.mark_dirty(5, 5, 0, 3);
+        assert_eq!(scheduler.dirty_region(), Some((2, 0, 9, 3)));
+    }
+
+    #[test]
+    fn test_flush_once_per_frame() {
+        // One region flush covering x = 8..16 on rows 0 and 1 of a 4-module chain
+        let mut expected = Vec::new();
+        for digit_register in [Register::Digit0, Register::Digit1] {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![
+                Register::NoOp.addr(),
+                0x00,
+                Register::NoOp.addr(),
+                0x00,
+                digit_register.addr(),
+                0x00,
+                Register::NoOp.addr(),
+                0x00,
+            ]));
+            expected.push(Transaction::transaction_end());
+        }
+
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver).unwrap();
+        let mut scheduler: Scheduler<0> = Scheduler::new(50);
+
+        assert_eq!(scheduler.flush_at(&mut matrix, 0), Ok(false));
+
+        scheduler.mark_dirty(8, 0, 4, 1);
+        scheduler.mark_dirty(12, 1, 4, 1);
+        assert_eq!(scheduler.flush_at(&mut matrix, 0), Ok(true));
+
+        // Within the same 20 ms frame nothing is sent
+        scheduler.mark_dirty(0, 0, 1, 1);
+        assert_eq!(scheduler.flush_at(&mut matrix, 10), Ok(false));
+        assert_eq!(scheduler.dirty_region(), Some((0, 0, 1, 1)));
+        spi.done();
+    }
+}
@@ -1,12 +1,82 @@
 //! Scroll Text Renderer Module
 //!
 //! This module provides a configurable text scroller for 8x8 LED matrix
+//!
+//! # Inline markers
+//!
+//! The text may contain markers that control the scroll while it runs. A
+//! marker takes no space on the display and fires when its position in the
+//! text reaches the scroll offset:
+//!
+//! - `{pause=MS}` holds the text still for `MS` milliseconds
+//! - `{speed=PERCENT}` changes the speed, relative to the configured step delay
+//!   (`{speed=50}` is half speed, `{speed=100}` restores it)
+//! - `{{` shows a literal `{`
+//!
+//! ```rust,ignore
+//! matrix.scroll_text(&mut delay, "PRICE {pause=1500}$4.99 {speed=50}SALE", config)?;
+//! ```
+//!
+//! # Live text
+//!
+//! A [`ScrollingText`] driven from your own loop can take new text while it
+//! runs, so a ticker of prices or sensor readings never restarts:
+//! [`ScrollingText::queue_text`] swaps it in at the next wrap point and
+//! [`ScrollingText::set_text`] right away. Both copy the text, so the buffer
+//! it was formatted in can be reused for the next reading. Show each step
+//! with [`LedMatrix::show_scroller`](crate::led_matrix::LedMatrix::show_scroller).
+//!
+//! ```rust
+//! use core::fmt::Write;
+//! use max7219_display::led_matrix::fonts::STANDARD_LED_FONT;
+//! use max7219_display::led_matrix::scroll::{ScrollConfig, ScrollingText};
+//!
+//! let mut scroller = ScrollingText::new("T=20C", &STANDARD_LED_FONT, ScrollConfig::default());
+//! let mut text = String::new();
+//! for reading in [21, 22, 23] {
+//!     text.clear();
+//!     write!(text, "T={reading}C").unwrap();
+//!     scroller.queue_text(&text)?;
+//!     scroller.step();
+//! }
+//! assert_eq!(scroller.queued_text(), Some("T=23C"));
+//! # Ok::<(), max7219_display::Error>(())
+//! ```
 
 use crate::{
-    Result,
-    led_matrix::{buffer::MatrixBuffer, fonts::LedFont},
+    Error, Result,
+    led_matrix::{
+        buffer::MatrixBuffer,
+        dimming::{DUTY_STEPS, dither},
+        fonts::LedFont,
+    },
+    time::Progress,
 };
 
+/// Direction the text moves across the display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScrollDirection {
+    /// Text enters on the right and moves left
+    #[default]
+    Left,
+    /// Text enters on the left and moves right
+    Right,
+}
+
+/// Order in which the characters of the text are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    /// First character on the left, e.g. Latin script
+    #[default]
+    LeftToRight,
+    /// First character on the right, e.g. Hebrew or Arabic script.
+    ///
+    /// Glyphs are drawn as the font defines them; only their order is
+    /// reversed. Combine with [`ScrollDirection::Right`] so the message
+    /// starts with its first character.
+    RightToLeft,
+}
+
 /// Configuration for scrolling text behavior
 #[derive(Clone, Copy)]
 pub struct ScrollConfig {
@@ -18,6 +88,10 @@ pub struct ScrollConfig {
     pub loop_text: bool,
     /// Padding between text repetitions when looping (in pixels)
     pub loop_padding: u8,
+    /// Direction the text moves in
+    pub direction: ScrollDirection,
+    /// Layout order of the characters
+    pub text_direction: TextDirection,
 }
 
 impl Default for ScrollConfig {
@@ -27,45 +101,252 @@ impl Default for ScrollConfig {
             pixels_per_step: 1,
             loop_text: true,
             loop_padding: 16, // 2 character widths
+            direction: ScrollDirection::Left,
+            text_direction: TextDirection::LeftToRight,
+        }
+    }
+}
+
+/// A piece of marquee text: a glyph or an inline marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// Character drawn on the display
+    Glyph(char),
+    /// Hold the scroll for the given milliseconds
+    Pause(u32),
+    /// Scroll at the given percentage of the configured speed
+    Speed(u16),
+}
+
+/// Splits the next token off the front of `text`.
+fn next_token(text: &str) -> Option<(Token, &str)> {
+    let mut chars = text.chars();
+    let first = chars.next()?;
+    let rest = chars.as_str();
+    if first != '{' {
+        return Some((Token::Glyph(first), rest));
+    }
+    if let Some(rest) = rest.strip_prefix('{') {
+        return Some((Token::Glyph('{'), rest));
+    }
+
+    let marker = rest.split_once('}').and_then(|(body, after)| {
+        let (name, value) = body.split_once('=')?;
+        let token = match name {
+            "pause" => Token::Pause(value.parse().ok()?),
+            "speed" => Token::Speed(value.parse().ok().filter(|&speed| speed > 0)?),
+            _ => return None,
+        };
+        Some((token, after))
+    });
+    // Anything that is not a valid marker is shown as written
+    Some(marker.unwrap_or((Token::Glyph('{'), rest)))
+}
+
+/// Iterator over the tokens of a marquee text
+struct Tokens<'a>(&'a str);
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let (token, rest) = next_token(self.0)?;
+        self.0 = rest;
+        Some(token)
+    }
+}
+
+/// Number of bytes of text a [`ScrollingText`] holds by default
+pub const TEXT_CAPACITY: usize = 64;
+
+/// Text copied into a fixed buffer
+#[derive(Clone, Copy)]
+struct TextBuf<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> TextBuf<N> {
+    /// Copies `text`, or returns `Error::BufferError` if it is longer than `N` bytes.
+    fn new(text: &str) -> Result<Self> {
+        let mut bytes = [0; N];
+        bytes
+            .get_mut(..text.len())
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(text.as_bytes());
+        Ok(Self {
+            bytes,
+            len: text.len(),
+        })
+    }
+
+    fn as_str(&self) -> &str {
+        // Only whole string slices are ever copied in
+        core::str::from_utf8(self.bytes.get(..self.len).unwrap_or_default()).unwrap_or("")
+    }
+}
+
+/// The text being scrolled
+#[derive(Clone, Copy)]
+enum Text<'a, const N: usize> {
+    /// Text given to the constructor, borrowed for any length
+    Borrowed(&'a str),
+    /// Text copied in by `set_text` or the queue
+    Owned(TextBuf<N>),
+}
+
+impl<const N: usize> Text<'_, N> {
+    fn as_str(&self) -> &str {
+        match self {
+            Text::Borrowed(text) => text,
+            Text::Owned(buf) => buf.as_str(),
         }
     }
 }
 
 /// Scrolling text renderer for LED matrix displays
-pub struct ScrollingText<'a> {
-    text: &'a str,
+///
+/// The text given to [`Self::new`] is borrowed; text passed to
+/// [`Self::set_text`] or [`Self::queue_text`] while the scroll runs is
+/// copied into a buffer of `N` bytes.
+pub struct ScrollingText<'a, const N: usize = TEXT_CAPACITY> {
+    text: Text<'a, N>,
+    /// Text waiting to replace `text` at the next wrap point
+    queued: Option<TextBuf<N>>,
     font: &'a LedFont,
     config: ScrollConfig,
     text_width: usize,
     pub(crate) current_offset: i32,
+    /// Position between `current_offset` and the next column, in
+    /// [`DUTY_STEPS`]ths of a pixel
+    fraction: u8,
+    /// Current speed in percent of the configured speed, set by `{speed=..}`
+    speed_percent: u16,
+    /// Pause requested by `{pause=..}` markers, not yet taken
+    pending_pause_ms: u32,
 }
 
 impl<'a> ScrollingText<'a> {
-    /// Create a new scrolling text instance
+    /// Create a new scrolling text instance that takes up to
+    /// [`TEXT_CAPACITY`] bytes of new text
     pub fn new(text: &'a str, font: &'a LedFont, config: ScrollConfig) -> Self {
+        Self::new_sized(text, font, config)
+    }
+
+    /// Create with default configuration
+    pub fn new_default(text: &'a str, font: &'a LedFont) -> Self {
+        Self::new(text, font, ScrollConfig::default())
+    }
+}
+
+impl<'a, const N: usize> ScrollingText<'a, N> {
+    /// Create a new scrolling text instance that takes up to `N` bytes of
+    /// new text, e.g. `ScrollingText::<'_, 256>::new_sized(..)`
+    pub fn new_sized(text: &'a str, font: &'a LedFont, config: ScrollConfig) -> Self {
         let mut scroller = Self {
-            text,
+            text: Text::Borrowed(text),
+            queued: None,
             font,
             config,
             text_width: 0,
             current_offset: 0,
+            fraction: 0,
+            speed_percent: 100,
+            pending_pause_ms: 0,
         };
         scroller.calculate_text_width();
         scroller
     }
 
-    /// Create with default configuration
-    pub fn new_default(text: &'a str, font: &'a LedFont) -> Self {
-        Self::new(text, font, ScrollConfig::default())
+    /// Returns the text being scrolled.
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// Returns the scroll configuration.
+    pub fn config(&self) -> &ScrollConfig {
+        &self.config
+    }
+
+    /// Replaces the text right away, keeping the scroll position.
+    ///
+    /// The width is measured again, so when `text` starts with the current
+    /// text, e.g. a reading appended to a live ticker, the columns on the
+    /// display stay where they are. Looping text that no longer reaches the
+    /// current offset starts over. Any text waiting in
+    /// [`Self::queue_text`] is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `text` is longer than `N` bytes. The
+    /// scroller is left unchanged in that case.
+    pub fn set_text(&mut self, text: &str) -> Result<()> {
+        self.text = Text::Owned(TextBuf::new(text)?);
+        self.queued = None;
+        self.calculate_text_width();
+        if self.config.loop_text && self.current_offset >= self.text_width as i32 {
+            self.current_offset = 0;
+        }
+        Ok(())
+    }
+
+    /// Replaces the text at the next wrap point, so the current pass
+    /// finishes undisturbed.
+    ///
+    /// Looping text wraps after its padding has scrolled by; text that
+    /// doesn't loop is replaced by the next [`Self::reset`]. Queuing again
+    /// before then replaces the waiting text.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `text` is longer than `N` bytes. Any
+    /// text already waiting stays queued in that case.
+    pub fn queue_text(&mut self, text: &str) -> Result<()> {
+        self.queued = Some(TextBuf::new(text)?);
+        Ok(())
+    }
+
+    /// Returns the text waiting for the next wrap point, if any.
+    pub fn queued_text(&self) -> Option<&str> {
+        self.queued.as_ref().map(TextBuf::as_str)
+    }
+
+    /// Swaps in the queued text, if any.
+    fn swap_queued(&mut self) {
+        if let Some(text) = self.queued.take() {
+            self.text = Text::Owned(text);
+            self.calculate_text_width();
+        }
+    }
+
+    /// Characters drawn on the display, without markers and characters the
+    /// font skips
+    fn glyphs(&self) -> impl Iterator<Item = char> + '_ {
+        Tokens(self.text.as_str()).filter_map(|token| match token {
+            Token::Glyph(ch) if self.font.is_drawn(ch) => Some(ch),
+            _ => None,
+        })
+    }
+
+    /// Checks that the font can draw every character of the text.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the font's
+    /// [`CharPolicy`](crate::CharPolicy) rejects a character.
+    pub fn check(&self) -> Result<()> {
+        self.font.check(self.glyphs())
     }
 
     /// Calculate the width
     fn calculate_text_width(&mut self) {
-        self.text_width = self.text.chars().count() * 8;
+        self.text_width = self.glyphs().count().saturating_mul(8);
 
         // Add loop padding if configured
         if self.config.loop_text {
-            self.text_width += self.config.loop_padding as usize;
+            self.text_width = self
+                .text_width
+                .saturating_add(self.config.loop_padding as usize);
         }
     }
 
@@ -78,7 +359,7 @@ impl<'a> ScrollingText<'a> {
             let mut row_data = 0u8;
             for col in 0..8 {
                 if self.pixel_on(col, row) {
-                    row_data |= 1 << (7 - col);
+                    row_data |= 0x80 >> col;
                 }
             }
             buffer.set_row(row as u8, row_data)?;
@@ -86,67 +367,340 @@ impl<'a> ScrollingText<'a> {
 
         Ok(buffer)
     }
+
+    /// Get the 8x8 frame at the current sub-pixel position for one frame of
+    /// a temporal dithering cycle.
+    ///
+    /// Pixels that change between this column and the next are shown at an
+    /// intermediate brightness: lit on a share of the [`DUTY_STEPS`] frames
+    /// matching the [`Self::fraction`]. Showing every frame of the cycle in
+    /// turn makes slow scrolling look much smoother than whole-pixel steps.
+    /// Without a fraction this is the same as [`Self::get_frame`].
+    ///
+    /// `x_origin` is the display column of the frame's left edge, so the
+    /// dithering pattern lines up across devices.
+    pub fn get_dithered_frame(&self, frame: u8, x_origin: usize) -> Result<MatrixBuffer> {
+        let mut buffer = MatrixBuffer::new();
+        let fraction = self.fraction;
+        let next_offset = self.current_offset.saturating_add(1);
+
+        for row in 0..8 {
+            let mut row_data = 0u8;
+            for col in 0..8 {
+                let current = self.pixel_on_at(self.current_offset, col, row);
+                let next = fraction > 0 && self.pixel_on_at(next_offset, col, row);
+                let duty = match (current, next) {
+                    (true, true) => DUTY_STEPS,
+                    (true, false) => DUTY_STEPS.saturating_sub(fraction),
+                    (false, true) => fraction,
+                    (false, false) => 0,
+                };
+                if dither(x_origin.saturating_add(col), row, frame, duty) {
+                    row_data |= 0x80 >> col;
+                }
+            }
+            buffer.set_row(row as u8, row_data)?;
+        }
+
+        Ok(buffer)
+    }
+
     /// Return true if the pixel at (source_col, row) should be on
     fn pixel_on(&self, source_col: usize, row: usize) -> bool {
+        self.pixel_on_at(self.current_offset, source_col, row)
+    }
+
+    /// Return true if the pixel at (source_col, row) is on at scroll `offset`
+    fn pixel_on_at(&self, offset: i32, source_col: usize, row: usize) -> bool {
+        let Some((char_index, bit_index)) = self.glyph_position(offset, source_col) else {
+            return false;
+        };
+        let bitmap = self.glyph_bitmap(char_index);
+        let row_data = bitmap.get(row).copied().unwrap_or(0);
+
+        // Check bit (left to right)
+        row_data & (0x80 >> bit_index) != 0
+    }
+
+    /// Returns the index of the glyph and the column within it shown at
+    /// `source_col` of a frame at scroll `offset`, or `None` if that column
+    /// is blank.
+    fn glyph_position(&self, offset: i32, source_col: usize) -> Option<(usize, usize)> {
+        // Scrolling right is scrolling left seen in a mirror
+        let scroll_right = self.config.direction == ScrollDirection::Right;
+        let source_col = if scroll_right {
+            7usize.saturating_sub(source_col)
+        } else {
+            source_col
+        };
+
         // Calculate the actual column position considering the offset
-        let actual_col = self.current_offset as isize + source_col as isize;
+        let actual_col = (offset as isize).saturating_add(source_col as isize);
 
         // If the actual column is negative, no pixel should be on
         if actual_col < 0 {
-            return false;
+            return None;
         }
 
         let col = actual_col as usize;
 
         // If outside text width and not looping, no pixel
         if col >= self.text_width && !self.config.loop_text {
-            return false;
+            return None;
         }
 
         // Wrap around if looping
         let final_col = if self.config.loop_text && col >= self.text_width {
-            col % self.text_width
+            col.checked_rem(self.text_width).unwrap_or(col)
         } else {
             col
         };
 
         // Only actual text columns (exclude padding)
-        let text_pixels = self.text.chars().count() * 8;
+        let text_pixels = self.glyphs().count().saturating_mul(8);
         if final_col >= text_pixels {
-            return false;
+            return None;
         }
 
-        let char_index = final_col / 8;
+        // Undo the mirror so glyphs are not drawn backwards
+        let final_col = if scroll_right {
+            text_pixels.saturating_sub(1).saturating_sub(final_col)
+        } else {
+            final_col
+        };
+
+        let mut char_index = final_col / 8;
         let bit_index = final_col % 8;
+        if self.config.text_direction == TextDirection::RightToLeft {
+            char_index = (text_pixels / 8)
+                .saturating_sub(1)
+                .saturating_sub(char_index);
+        }
+
+        Some((char_index, bit_index))
+    }
 
+    /// Returns the bitmap of the glyph at `char_index`.
+    fn glyph_bitmap(&self, char_index: usize) -> [u8; 8] {
         // Safe since char_index < char count
-        let ch = self.text.chars().nth(char_index).unwrap_or('?');
-        let bitmap = self.font.get_char(ch);
-        let row_data = bitmap[row];
+        let ch = self.glyphs().nth(char_index).unwrap_or('?');
+        self.font.get_char(ch)
+    }
 
-        // Check bit (left to right)
-        (row_data >> (7 - bit_index)) & 1 != 0
+    /// Fills `windows` with the columns (bit 0 = top row) shown across that
+    /// many 8-column windows, leftmost first, dithered on `dither_frame` if
+    /// given.
+    ///
+    /// Window `i` shows what [`Self::get_frame`] returns at an offset of
+    /// `8 * i` further, mirrored in order when scrolling right. Each glyph
+    /// is looked up once per run of columns instead of once per pixel.
+    pub(crate) fn window_columns(&self, dither_frame: Option<u8>, windows: &mut [[u8; 8]]) {
+        let count = windows.len();
+        let scroll_right = self.config.direction == ScrollDirection::Right;
+        let mut cached: Option<(usize, [u8; 8])> = None;
+        let mut column_at = |offset: i32, source_col: usize| -> u8 {
+            let Some((char_index, bit_index)) = self.glyph_position(offset, source_col) else {
+                return 0;
+            };
+            let bitmap = match cached {
+                Some((index, bitmap)) if index == char_index => bitmap,
+                _ => {
+                    let bitmap = self.glyph_bitmap(char_index);
+                    cached = Some((char_index, bitmap));
+                    bitmap
+                }
+            };
+            bitmap
+                .iter()
+                .enumerate()
+                .filter(|&(_, &bits)| bits & (0x80 >> bit_index) != 0)
+                .fold(0, |column, (row, _)| column | 1 << row)
+        };
+
+        for (position, window) in windows.iter_mut().enumerate() {
+            // Scrolling right mirrors the order of the windows as well
+            let source = if scroll_right {
+                count.saturating_sub(1).saturating_sub(position)
+            } else {
+                position
+            };
+            let offset = self
+                .current_offset
+                .saturating_add((source as i32).saturating_mul(8));
+            for (col, column) in window.iter_mut().enumerate() {
+                let current = column_at(offset, col);
+                *column = match dither_frame {
+                    None => current,
+                    Some(frame) => {
+                        let next = match self.fraction {
+                            0 => 0,
+                            _ => column_at(offset.saturating_add(1), col),
+                        };
+                        let x = source.saturating_mul(8).saturating_add(col);
+                        (0..8).fold(0, |column, row| {
+                            let duty = match (current & (1 << row) != 0, next & (1 << row) != 0) {
+                                (true, true) => DUTY_STEPS,
+                                (true, false) => DUTY_STEPS.saturating_sub(self.fraction),
+                                (false, true) => self.fraction,
+                                (false, false) => 0,
+                            };
+                            if dither(x, row, frame, duty) {
+                                column | 1 << row
+                            } else {
+                                column
+                            }
+                        })
+                    }
+                };
+            }
+        }
     }
 
     /// Advance the scroll position by the configured step size
     pub fn step(&mut self) -> bool {
-        self.current_offset += self.config.pixels_per_step as i32;
+        self.step_by(self.config.pixels_per_step)
+    }
+
+    /// Advance the scroll position by `pixels`
+    fn step_by(&mut self, pixels: u8) -> bool {
+        let previous = self.current_offset;
+        self.current_offset = self.current_offset.saturating_add(pixels as i32);
 
         if self.config.loop_text {
             // Reset when we've scrolled past the text width
             if self.current_offset >= self.text_width as i32 {
+                self.fire_markers(previous, (self.text_width as i32).saturating_sub(1));
+                self.swap_queued();
                 self.current_offset = 0;
+                self.fire_markers(-1, 0);
+            } else {
+                self.fire_markers(previous, self.current_offset);
             }
             true // Always continue when looping
         } else {
+            self.fire_markers(previous, self.current_offset);
             // Stop when text has completely scrolled off screen
-            self.current_offset < (self.text_width as i32 + 8)
+            self.current_offset < (self.text_width as i32).saturating_add(8)
+        }
+    }
+
+    /// Advances the scroll position by `eighths` [`DUTY_STEPS`]ths of a
+    /// pixel, for sub-pixel scrolling with [`Self::get_dithered_frame`].
+    ///
+    /// Each whole pixel moves the text like a [`Self::step`] of one pixel,
+    /// firing markers on the way. Returns `false` once non-looping text has
+    /// scrolled off the display.
+    pub fn step_fraction(&mut self, eighths: u8) -> bool {
+        const STEPS: u16 = DUTY_STEPS as u16;
+
+        let mut running = !self.is_finished();
+        let total = u16::from(self.fraction).saturating_add(u16::from(eighths));
+        self.fraction = (total % STEPS) as u8;
+        for _ in 0..total / STEPS {
+            running = self.step_by(1);
+        }
+        running
+    }
+
+    /// Returns the position between the current and the next column, in
+    /// [`DUTY_STEPS`]ths of a pixel.
+    pub fn fraction(&self) -> u8 {
+        self.fraction
+    }
+
+    /// Advances like [`Self::step`] and reports when a pass is complete.
+    ///
+    /// Returns [`Progress::Complete`] when the text has scrolled off the
+    /// display, or, when looping, each time it starts over. Once a
+    /// non-looping scroll is complete, further calls return
+    /// [`Progress::Idle`] until [`Self::reset`].
+    pub fn advance(&mut self) -> Progress {
+        if self.is_finished() {
+            return Progress::Idle;
+        }
+        let previous = self.current_offset;
+        let running = self.step();
+        let wrapped = self.config.pixels_per_step > 0 && self.current_offset <= previous;
+        if !running || (self.config.loop_text && wrapped) {
+            Progress::Complete
+        } else {
+            Progress::Running
         }
     }
 
-    /// Reset scroll position to the beginning
+    /// Returns `true` once non-looping text has scrolled off the display.
+    pub fn is_finished(&self) -> bool {
+        !self.config.loop_text && self.current_offset >= (self.text_width as i32).saturating_add(8)
+    }
+
+    /// Returns `true` if the last character of the text enters the display first.
+    fn enters_backwards(&self) -> bool {
+        (self.config.direction == ScrollDirection::Right)
+            != (self.config.text_direction == TextDirection::RightToLeft)
+    }
+
+    /// Applies the markers whose column lies in `(from, to]`.
+    ///
+    /// Columns count from the edge where the text enters the display.
+    fn fire_markers(&mut self, from: i32, to: i32) {
+        let text_pixels = (self.glyphs().count() as i32).saturating_mul(8);
+        let backwards = self.enters_backwards();
+        let mut column = 0i32;
+        for token in Tokens(self.text.as_str()) {
+            let entry_column = if backwards {
+                text_pixels.saturating_sub(column)
+            } else {
+                column
+            };
+            match token {
+                Token::Glyph(ch) if self.font.is_drawn(ch) => column = column.saturating_add(8),
+                Token::Glyph(_) => {}
+                _ if entry_column <= from || entry_column > to => {}
+                Token::Pause(ms) => {
+                    self.pending_pause_ms = self.pending_pause_ms.saturating_add(ms)
+                }
+                Token::Speed(percent) => self.speed_percent = percent,
+            }
+        }
+    }
+
+    /// Returns how long to wait before the next step, in nanoseconds.
+    ///
+    /// This is the configured step delay adjusted by the current
+    /// `{speed=..}` marker, plus any pause requested by a `{pause=..}`
+    /// marker since the last call.
+    pub fn next_delay_ns(&mut self) -> u32 {
+        let step = u64::from(self.step_delay_ns());
+        let pause = u64::from(self.take_pause_ns());
+        step.saturating_add(pause).min(u64::from(u32::MAX)) as u32
+    }
+
+    /// The configured step delay adjusted by the current speed
+    pub(crate) fn step_delay_ns(&self) -> u32 {
+        let step = (u64::from(self.config.step_delay_ns) * 100)
+            .checked_div(u64::from(self.speed_percent))
+            .unwrap_or(u64::MAX);
+        step.min(u64::from(u32::MAX)) as u32
+    }
+
+    /// Pause requested by markers since the last call
+    pub(crate) fn take_pause_ns(&mut self) -> u32 {
+        let pause = u64::from(core::mem::take(&mut self.pending_pause_ms)) * 1_000_000;
+        pause.min(u64::from(u32::MAX)) as u32
+    }
+
+    /// Returns the current speed in percent of the configured speed.
+    pub fn speed_percent(&self) -> u16 {
+        self.speed_percent
+    }
+
+    /// Reset scroll position to the beginning, swapping in any queued text
     pub fn reset(&mut self) {
+        self.swap_queued();
         self.current_offset = -(8i32); // Start with text off-screen to the right
+        self.fraction = 0;
+        self.speed_percent = 100;
+        self.pending_pause_ms = 0;
     }
 
     /// Get current scroll offset
@@ -158,6 +712,7 @@ impl<'a> ScrollingText<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::CharPolicy;
 
     // Create a minimal font for testing
     #[rustfmt::skip]
@@ -219,7 +774,7 @@ mod tests {
         let config = ScrollConfig::default();
         let scroller = ScrollingText::new(text, &TEST_FONT, config);
 
-        assert_eq!(scroller.text, text);
+        assert_eq!(scroller.text(), text);
         assert_eq!(scroller.text_width, 32);
         assert_eq!(scroller.current_offset, 0);
     }
@@ -244,6 +799,78 @@ mod tests {
         assert_eq!(scroller.current_offset, -8);
     }
 
+    #[test]
+    fn test_set_text_keeps_position() {
+        let config = ScrollConfig {
+            loop_text: false,
+            ..Default::default()
+        };
+        let mut scroller = ScrollingText::new("0", &TEST_FONT, config);
+        scroller.current_offset = 4;
+        let before = *scroller.get_frame().unwrap().data();
+
+        scroller.set_text("01").unwrap();
+        assert_eq!(scroller.text(), "01");
+        assert_eq!(scroller.text_width, 16);
+        assert_eq!(scroller.offset(), 4);
+        // The left half still shows the end of "0", the appended "1" enters
+        let after = scroller.get_frame().unwrap();
+        for (after, before) in after.data().iter().zip(before) {
+            assert_eq!(after & 0xF0, before & 0xF0);
+        }
+        assert_ne!(*after.data(), before);
+
+        // Looping text shorter than the offset starts over
+        let mut scroller = ScrollingText::new("0101", &TEST_FONT, ScrollConfig::default());
+        scroller.current_offset = 40;
+        scroller.set_text("1").unwrap();
+        assert_eq!(scroller.offset(), 0);
+
+        // Text that doesn't fit leaves the scroller as it was
+        let mut small: ScrollingText<'_, 2> =
+            ScrollingText::new_sized("0", &TEST_FONT, ScrollConfig::default());
+        assert_eq!(small.set_text("011"), Err(Error::BufferError));
+        assert_eq!(small.text(), "0");
+    }
+
+    #[test]
+    fn test_queue_text_swaps_at_wrap() {
+        let config = ScrollConfig {
+            loop_text: true,
+            loop_padding: 0,
+            ..Default::default()
+        };
+        let mut scroller = ScrollingText::new("0", &TEST_FONT, config);
+        scroller.reset();
+        scroller.queue_text("01").unwrap();
+        while scroller.offset() < 7 {
+            scroller.step();
+            assert_eq!(scroller.text(), "0");
+        }
+        assert_eq!(scroller.queued_text(), Some("01"));
+
+        scroller.step();
+        assert_eq!(scroller.offset(), 0);
+        assert_eq!(scroller.text(), "01");
+        assert_eq!(scroller.text_width, 16);
+        assert_eq!(scroller.queued_text(), None);
+
+        // Without looping the next reset swaps the text
+        let mut scroller = ScrollingText::new(
+            "0",
+            &TEST_FONT,
+            ScrollConfig {
+                loop_text: false,
+                ..Default::default()
+            },
+        );
+        scroller.queue_text("1").unwrap();
+        while scroller.step() {}
+        assert_eq!(scroller.text(), "0");
+        scroller.reset();
+        assert_eq!(scroller.text(), "1");
+    }
+
     #[test]
     fn test_offset() {
         let mut scroller = ScrollingText::new_default("01", &TEST_FONT);
@@ -370,6 +997,296 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_markers_take_no_space() {
+        let scroller = ScrollingText::new_default("0{pause=500}1{speed=50}", &TEST_FONT);
+        assert_eq!(scroller.text_width, 32);
+        assert!(scroller.pixel_on(11, 0)); // second glyph is '1'
+    }
+
+    #[test]
+    fn test_skipped_chars_take_no_space() {
+        let font = TEST_FONT.with_unsupported(CharPolicy::Skip);
+        let scroller = ScrollingText::new_default("0x{pause=5}1", &font);
+        let plain = ScrollingText::new_default("01", &font);
+        assert_eq!(scroller.text_width, plain.text_width);
+        assert!(scroller.check().is_ok());
+
+        let strict = TEST_FONT.with_unsupported(CharPolicy::Error);
+        let scroller = ScrollingText::new_default("0x1", &strict);
+        assert_eq!(scroller.check(), Err(crate::Error::UnsupportedChar('x')));
+    }
+
+    #[test]
+    fn test_literal_and_invalid_markers() {
+        assert_eq!(next_token("{{x"), Some((Token::Glyph('{'), "x")));
+        assert_eq!(next_token("{pause=12}x"), Some((Token::Pause(12), "x")));
+        assert_eq!(
+            next_token("{speed=0}"),
+            Some((Token::Glyph('{'), "speed=0}"))
+        );
+        assert_eq!(next_token("{blink}"), Some((Token::Glyph('{'), "blink}")));
+        assert_eq!(
+            next_token("{pause=12"),
+            Some((Token::Glyph('{'), "pause=12"))
+        );
+        assert_eq!(next_token(""), None);
+    }
+
+    #[test]
+    fn test_pause_marker_fires_once_at_its_column() {
+        let config = ScrollConfig {
+            step_delay_ns: 1_000,
+            loop_text: false,
+            ..Default::default()
+        };
+        let mut scroller = ScrollingText::new("0{pause=2}1", &TEST_FONT, config);
+        scroller.current_offset = 6;
+
+        scroller.step(); // offset 7
+        assert_eq!(scroller.next_delay_ns(), 1_000);
+
+        scroller.step(); // offset 8 reaches the marker
+        assert_eq!(scroller.next_delay_ns(), 2_001_000);
+        assert_eq!(scroller.next_delay_ns(), 1_000);
+    }
+
+    #[test]
+    fn test_step_fraction_carries_whole_pixels() {
+        let mut scroller = ScrollingText::new_default("01", &TEST_FONT);
+        scroller.current_offset = 30;
+        assert!(scroller.step_fraction(5));
+        assert_eq!((scroller.offset(), scroller.fraction()), (30, 5));
+        assert!(scroller.step_fraction(4));
+        assert_eq!((scroller.offset(), scroller.fraction()), (31, 1));
+        // Wraps like a whole step
+        assert!(scroller.step_fraction(7));
+        assert_eq!((scroller.offset(), scroller.fraction()), (0, 0));
+
+        scroller.reset();
+        assert_eq!(scroller.fraction(), 0);
+    }
+
+    #[test]
+    fn test_dithered_frame_blends_edge_columns() {
+        let mut scroller = ScrollingText::new_default("1", &TEST_FONT);
+        scroller.current_offset = 0;
+        let whole = scroller.get_frame().unwrap();
+        for frame in 0..DUTY_STEPS {
+            assert_eq!(
+                scroller.get_dithered_frame(frame, 0).unwrap().data(),
+                whole.data()
+            );
+        }
+
+        // Halfway: pixels lit in both positions stay lit, edge pixels are
+        // lit on half of the frames
+        scroller.step_fraction(DUTY_STEPS / 2);
+        let mut shown = [[0u8; 8]; 8];
+        for frame in 0..DUTY_STEPS {
+            let buffer = scroller.get_dithered_frame(frame, 0).unwrap();
+            for (row, counts) in shown.iter_mut().enumerate() {
+                let bits = buffer.data()[row];
+                for (col, count) in counts.iter_mut().enumerate() {
+                    *count += (bits >> (7 - col)) & 1;
+                }
+            }
+        }
+        // Row 6 of '1' is 0b01111110: columns 1..=6 at offset 0, 0..=5 at 1
+        assert_eq!(shown[6], [4, 8, 8, 8, 8, 8, 4, 0]);
+    }
+
+    #[test]
+    fn test_advance_reports_completion() {
+        let config = ScrollConfig {
+            loop_text: false,
+            pixels_per_step: 8,
+            ..Default::default()
+        };
+        let mut scroller = ScrollingText::new("01", &TEST_FONT, config);
+        scroller.reset();
+        assert_eq!(scroller.advance(), Progress::Running); // 0
+        assert_eq!(scroller.advance(), Progress::Running); // 8
+        assert_eq!(scroller.advance(), Progress::Running); // 16
+        assert_eq!(scroller.advance(), Progress::Complete); // 24, off screen
+        assert!(scroller.is_finished());
+        assert_eq!(scroller.advance(), Progress::Idle);
+
+        scroller.reset();
+        assert_eq!(scroller.advance(), Progress::Running);
+    }
+
+    #[test]
+    fn test_advance_looping_completes_each_pass() {
+        let config = ScrollConfig {
+            loop_text: true,
+            loop_padding: 0,
+            pixels_per_step: 8,
+            ..Default::default()
+        };
+        let mut scroller = ScrollingText::new("0", &TEST_FONT, config);
+        scroller.reset();
+        assert_eq!(scroller.advance(), Progress::Running); // 0
+        assert_eq!(scroller.advance(), Progress::Complete); // wraps to 0
+        assert_eq!(scroller.advance(), Progress::Complete);
+        assert!(!scroller.is_finished());
+    }
+
+    /// Renders the scroller across `windows` 8-pixel windows the way
+    /// `LedMatrix::scroll_text` does, as one row of `'#'` and `'.'`.
+    fn render_row(scroller: &mut ScrollingText, windows: usize, row: usize) -> String {
+        let base = scroller.current_offset;
+        let mut frames = Vec::new();
+        for window in 0..windows {
+            scroller.current_offset = base + window as i32 * 8;
+            frames.push(scroller.get_frame().unwrap());
+        }
+        scroller.current_offset = base;
+        if scroller.config.direction == ScrollDirection::Right {
+            frames.reverse();
+        }
+        frames
+            .iter()
+            .flat_map(|frame| {
+                let data = frame.get_row(row as u8).unwrap();
+                (0..8).map(move |bit| if data & (0x80 >> bit) != 0 { '#' } else { '.' })
+            })
+            .collect()
+    }
+
+    fn render_text(text: &str, config: ScrollConfig, offset: i32) -> String {
+        let mut scroller = ScrollingText::new(text, &TEST_FONT, config);
+        scroller.current_offset = offset;
+        render_row(&mut scroller, 2, 0)
+    }
+
+    #[test]
+    fn test_scroll_right_mirrors_position_not_glyphs() {
+        let left = ScrollConfig {
+            loop_text: false,
+            ..Default::default()
+        };
+        let right = ScrollConfig {
+            direction: ScrollDirection::Right,
+            ..left
+        };
+        // Fully visible, the text reads the same in both directions
+        assert_eq!(render_text("01", left, 0), render_text("01", right, 0));
+
+        // Half way in: the start of the text on the right when scrolling
+        // left, the end of the text on the left when scrolling right
+        let entering_left = render_text("01", left, -8);
+        let entering_right = render_text("01", right, -8);
+        assert_eq!(&entering_left[8..], &render_text("0", left, 0)[..8]);
+        assert_eq!(&entering_right[..8], &render_text("1", left, 0)[..8]);
+    }
+
+    #[test]
+    fn test_window_columns_match_frames() {
+        use crate::led_matrix::strip::glyph_columns;
+
+        for direction in [ScrollDirection::Left, ScrollDirection::Right] {
+            let config = ScrollConfig {
+                direction,
+                ..Default::default()
+            };
+            let mut scroller = ScrollingText::new("01", &TEST_FONT, config);
+            scroller.current_offset = -3;
+            scroller.step_fraction(3);
+            let base = scroller.current_offset;
+
+            let mut windows = [[0u8; 8]; 3];
+            for dither_frame in [None, Some(0), Some(5)] {
+                scroller.window_columns(dither_frame, &mut windows);
+                for (position, window) in windows.iter().enumerate() {
+                    let source = match direction {
+                        ScrollDirection::Left => position,
+                        ScrollDirection::Right => 2 - position,
+                    };
+                    scroller.current_offset = base + 8 * source as i32;
+                    let frame = match dither_frame {
+                        Some(frame) => scroller.get_dithered_frame(frame, 8 * source),
+                        None => scroller.get_frame(),
+                    };
+                    scroller.current_offset = base;
+                    assert_eq!(*window, glyph_columns(frame.unwrap().data()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_right_to_left_layout() {
+        let rtl = ScrollConfig {
+            loop_text: false,
+            text_direction: TextDirection::RightToLeft,
+            ..Default::default()
+        };
+        let ltr = ScrollConfig {
+            loop_text: false,
+            ..Default::default()
+        };
+        assert_eq!(render_text("01", rtl, 0), render_text("10", ltr, 0));
+
+        // Scrolling right, the first character enters first
+        let natural = ScrollConfig {
+            direction: ScrollDirection::Right,
+            ..rtl
+        };
+        assert_eq!(
+            &render_text("01", natural, -8)[..8],
+            &render_text("0", ltr, 0)[..8]
+        );
+    }
+
+    #[test]
+    fn test_markers_follow_entry_order() {
+        let config = ScrollConfig {
+            step_delay_ns: 0,
+            loop_text: false,
+            direction: ScrollDirection::Right,
+            ..Default::default()
+        };
+        // The '1' enters first, so the marker after it fires at column 8
+        let mut scroller = ScrollingText::new("0{pause=5}1", &TEST_FONT, config);
+        scroller.current_offset = 7;
+        scroller.step();
+        assert_eq!(scroller.next_delay_ns(), 5_000_000);
+    }
+
+    #[test]
+    fn test_speed_marker() {
+        let config = ScrollConfig {
+            step_delay_ns: 1_000,
+            loop_text: true,
+            loop_padding: 0,
+            ..Default::default()
+        };
+        let mut scroller = ScrollingText::new("{speed=50}0{speed=200}1", &TEST_FONT, config);
+        scroller.reset();
+        for _ in 0..8 {
+            scroller.step();
+        }
+        // Offset 0: the first marker has fired
+        assert_eq!(scroller.speed_percent(), 50);
+        assert_eq!(scroller.next_delay_ns(), 2_000);
+
+        for _ in 0..8 {
+            scroller.step();
+        }
+        assert_eq!(scroller.next_delay_ns(), 500);
+
+        // Looping back to the start fires the first marker again
+        for _ in 0..8 {
+            scroller.step();
+        }
+        assert_eq!(scroller.offset(), 0);
+        assert_eq!(scroller.speed_percent(), 50);
+
+        scroller.reset();
+        assert_eq!(scroller.speed_percent(), 100);
+    }
+
     #[test]
     fn test_get_frame() {
         let scroller = ScrollingText::new_default("0", &TEST_FONT);
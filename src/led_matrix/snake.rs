@@ -0,0 +1,174 @@
+//! Snake walk through every LED of the chain
+//!
+//! The [`Snake`] walks the logical display row by row, left to right, and
+//! leaves it to the current mapping to place each pixel on an LED. A correct
+//! mapping shows a snake crawling smoothly along the rows of the whole
+//! display, while a broken one makes it jump between modules, run the wrong
+//! way or miss parts of the display.
+//! [`LedMatrix::verify_mapping`] checks that every LED is covered without
+//! looking.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Result,
+    led_matrix::{LedMatrix, UpdateMode},
+};
+
+/// Lit "snake" walking through every pixel of the display in reading order.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut snake = Snake::new(4);
+/// while snake.tick(&mut matrix)? {
+///     delay.delay_ms(20);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snake {
+    length: usize,
+    step: usize,
+}
+
+impl Snake {
+    /// Create a snake of `length` LEDs (at least 1)
+    pub const fn new(length: usize) -> Self {
+        Self {
+            length: if length == 0 { 1 } else { length },
+            step: 0,
+        }
+    }
+
+    /// Returns the number of steps taken in the current walk.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Starts the walk over from the top-left pixel.
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Advances the snake by one pixel and draws it into the framebuffer,
+    /// replacing its contents.
+    ///
+    /// In write-through mode the frame is flushed right away; in buffered
+    /// mode it is sent on the next flush.
+    ///
+    /// Returns `false` once the tail has left the last pixel; the next call
+    /// starts a new walk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an SPI error if the flush fails.
+    pub fn tick<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<bool>
+    where
+        SPI: SpiDevice,
+    {
+        let width = matrix.width();
+        let total = width.saturating_mul(matrix.height());
+        let end = total.saturating_add(self.length);
+        if self.step >= end {
+            self.step = 0;
+        }
+
+        matrix.clear_buffer();
+        let first = self.step.saturating_sub(self.length.saturating_sub(1));
+        for index in first..self.step.saturating_add(1).min(total) {
+            let (Some(x), Some(y)) = (index.checked_rem(width), index.checked_div(width)) else {
+                break;
+            };
+            matrix.set_pixel(x, y, true)?;
+        }
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+
+        self.step = self.step.saturating_add(1);
+        Ok(self.step < end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Max7219,
+        led_matrix::{TiledMapper, display::Matrix4},
+    };
+    use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+    fn lit_pixels(matrix: &Matrix4<&mut SpiMock<u8>>) -> Vec<(usize, usize)> {
+        let mut lit = Vec::new();
+        for y in 0..matrix.height() {
+            for x in 0..matrix.width() {
+                if matrix.get_pixel(x, y) == Ok(true) {
+                    lit.push((x, y));
+                }
+            }
+        }
+        lit
+    }
+
+    #[test]
+    fn test_snake_walks_rows_in_order() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        let mut snake = Snake::new(3);
+        assert!(snake.tick(&mut matrix).unwrap());
+        assert_eq!(lit_pixels(&matrix), vec![(0, 0)]);
+
+        for _ in 0..9 {
+            snake.tick(&mut matrix).unwrap();
+        }
+        // Head on the 10th pixel, crossing into the second module
+        assert_eq!(lit_pixels(&matrix), vec![(7, 0), (8, 0), (9, 0)]);
+
+        for _ in 0..23 {
+            snake.tick(&mut matrix).unwrap();
+        }
+        // The head wraps to the next row of the display
+        assert_eq!(lit_pixels(&matrix), vec![(30, 0), (31, 0), (0, 1)]);
+        spi.done();
+    }
+
+    #[test]
+    fn test_snake_visits_every_led_once() {
+        static GRID: TiledMapper = TiledMapper::new(2, 2).serpentine(true);
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_mapper(&GRID)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        let mut visited = [false; 256];
+        let mut snake = Snake::new(1);
+        while snake.tick(&mut matrix).unwrap() {
+            let lit = lit_pixels(&matrix);
+            if snake.step() <= 256 {
+                assert_eq!(lit.len(), 1);
+                let (x, y) = lit[0];
+                assert!(!visited[y * 16 + x]);
+                visited[y * 16 + x] = true;
+            } else {
+                assert!(lit.is_empty());
+            }
+        }
+        assert!(visited.iter().all(|&v| v));
+
+        // The walk starts over
+        assert!(snake.tick(&mut matrix).unwrap());
+        assert_eq!(snake.step(), 1);
+        spi.done();
+    }
+}
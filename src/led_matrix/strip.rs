@@ -0,0 +1,417 @@
+//! Cached column strips for fast marquees
+//!
+//! [`ScrollingText`](crate::led_matrix::scroll::ScrollingText) rasterizes
+//! the whole visible window from the text on every frame, looking up each
+//! glyph it shows. [`ColumnScroller`] instead keeps the visible columns of the message
+//! rasterized in a small window: each step moves the window by one column
+//! and rasterizes only the column that enters, looking a glyph up once per
+//! 8 steps. On a 64-column chain scrolling at 60 Hz this frees most of the
+//! CPU time of a Cortex-M0.
+//!
+//! A column is one byte with bit 0 as the top row.
+//!
+//! ```rust,ignore
+//! let mut marquee: ColumnScroller<'_, 32> =
+//!     ColumnScroller::new("NEXT TRAIN 12:05", &STANDARD_LED_FONT)?.with_looping(16);
+//! loop {
+//!     marquee.step();
+//!     matrix.show_columns(marquee.columns())?;
+//!     delay.delay_ms(16);
+//! }
+//! ```
+//!
+//! Scroll markers are not interpreted and the text always moves left; use
+//! `ScrollingText` for those.
+//!
+//! # Pre-rendered strips
+//!
+//! [`render_columns`] rasterizes a whole message into a column buffer
+//! without scrolling it, to keep it in RAM, post-process it, e.g. stretch
+//! or embolden it, and blit it with
+//! [`LedMatrix::draw_columns`](crate::led_matrix::LedMatrix::draw_columns):
+//!
+//! ```rust,ignore
+//! let mut strip = [0u8; 64];
+//! let len = render_columns("SALE", &STANDARD_LED_FONT, &mut strip)?;
+//! for column in &mut strip[..len] {
+//!     *column |= *column << 1; // bolder vertical strokes
+//! }
+//! matrix.draw_columns(x, 0, &strip[..len])?;
+//! matrix.flush()?;
+//! ```
+
+use crate::{
+    Error, Result,
+    led_matrix::fonts::{GLYPH_WIDTH, LedFont},
+    time::Progress,
+};
+
+/// Rasterizes `text` into `out`, one byte per column (bit 0 = top row),
+/// and returns the number of columns written.
+///
+/// Every glyph takes [`GLYPH_WIDTH`] columns; characters the font's
+/// [`CharPolicy`](crate::CharPolicy) skips take none. The length can be
+/// worked out beforehand with [`LedFont::text_width`]. Scroll markers are
+/// not interpreted.
+///
+/// # Errors
+///
+/// Returns `Error::UnsupportedChar` if the font rejects a character, and
+/// `Error::BufferError` if `out` is too short for the text. `out` may be
+/// partly written in either case.
+///
+/// # Example
+///
+/// ```rust
+/// use max7219_display::led_matrix::fonts::STANDARD_LED_FONT;
+/// use max7219_display::led_matrix::strip::{glyph_columns, render_columns};
+///
+/// let mut strip = [0u8; 16];
+/// assert_eq!(render_columns("Hi", &STANDARD_LED_FONT, &mut strip), Ok(16));
+/// let h = glyph_columns(&STANDARD_LED_FONT.get_char('H'));
+/// assert_eq!(strip[..8], h);
+/// ```
+pub fn render_columns(text: &str, font: &LedFont, out: &mut [u8]) -> Result<usize> {
+    let mut len: usize = 0;
+    for ch in text.chars() {
+        let Some(bitmap) = font.lookup(ch)? else {
+            continue;
+        };
+        let end = len.checked_add(GLYPH_WIDTH).ok_or(Error::BufferError)?;
+        out.get_mut(len..end)
+            .ok_or(Error::BufferError)?
+            .copy_from_slice(&glyph_columns(&bitmap));
+        len = end;
+    }
+    Ok(len)
+}
+
+/// Emphasis applied to glyphs while they are rasterized, so important
+/// messages stand out without a second font.
+///
+/// A transformed glyph is described by up to 16 columns of up to 16 pixels
+/// (bit 0 = top row); tall text spans two module rows.
+///
+/// ```rust,ignore
+/// let headline = GlyphTransform { bold: true, tall: true, ..Default::default() };
+/// matrix.draw_text_transformed(0, 0, "ALARM", &STANDARD_LED_FONT, headline)?;
+/// matrix.flush()?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GlyphTransform {
+    /// Thicken vertical strokes by lighting the pixel right of each lit one
+    pub bold: bool,
+    /// Double the width of every column
+    pub wide: bool,
+    /// Double the height of every row
+    pub tall: bool,
+}
+
+impl GlyphTransform {
+    /// Returns the width of a transformed glyph cell in pixels.
+    pub const fn width(&self) -> usize {
+        if self.wide {
+            2 * GLYPH_WIDTH
+        } else {
+            GLYPH_WIDTH
+        }
+    }
+
+    /// Returns the height of a transformed glyph cell in pixels.
+    pub const fn height(&self) -> usize {
+        if self.tall { 16 } else { 8 }
+    }
+
+    /// Transforms the columns of a glyph, see [`glyph_columns`].
+    ///
+    /// The first [`Self::width`] columns of the result are used.
+    pub fn apply(&self, columns: &[u8; 8]) -> [u16; 16] {
+        let mut out = [0; 16];
+        let previous = core::iter::once(0).chain(columns.iter().copied());
+        let cells = out.chunks_mut(if self.wide { 2 } else { 1 });
+        for ((cell, &column), previous) in cells.zip(columns).zip(previous) {
+            let column = if self.bold { column | previous } else { column };
+            let column = if self.tall {
+                (0..8u32)
+                    .filter(|row| column & (1 << row) != 0)
+                    .fold(0, |tall, row| tall | 0b11 << row.wrapping_mul(2))
+            } else {
+                u16::from(column)
+            };
+            cell.fill(column);
+        }
+        out
+    }
+}
+
+/// Converts the rows of a glyph (bit 7 = leftmost pixel) into its columns
+/// (bit 0 = top row).
+pub fn glyph_columns(bitmap: &[u8; 8]) -> [u8; 8] {
+    core::array::from_fn(|col| {
+        bitmap
+            .iter()
+            .enumerate()
+            .filter(|&(_, &bits)| bits & (0x80 >> col) != 0)
+            .fold(0, |column, (row, _)| column | 1 << row)
+    })
+}
+
+/// Marquee keeping the `COLUMNS` visible columns of its text rasterized,
+/// see the [module documentation](self).
+#[derive(Clone)]
+pub struct ColumnScroller<'a, const COLUMNS: usize> {
+    text: &'a str,
+    font: &'a LedFont,
+    /// Visible columns, leftmost first
+    window: [u8; COLUMNS],
+    /// Text not yet rasterized
+    rest: &'a str,
+    /// Columns of the glyph entering the window
+    glyph: [u8; GLYPH_WIDTH],
+    /// Next column of `glyph` to enter
+    glyph_column: usize,
+    /// Blank columns still to enter before the pass ends
+    blank: usize,
+    /// Blank columns between repetitions, if looping
+    loop_padding: Option<usize>,
+    finished: bool,
+}
+
+impl<'a, const COLUMNS: usize> ColumnScroller<'a, COLUMNS> {
+    /// Create a marquee scrolling `text` once across a blank window
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the font rejects a character.
+    pub fn new(text: &'a str, font: &'a LedFont) -> Result<Self> {
+        font.check(text.chars())?;
+        Ok(Self {
+            text,
+            font,
+            window: [0; COLUMNS],
+            rest: text,
+            glyph: [0; GLYPH_WIDTH],
+            glyph_column: GLYPH_WIDTH,
+            blank: COLUMNS,
+            loop_padding: None,
+            finished: false,
+        })
+    }
+
+    /// Repeat the text forever with `padding` blank columns between the end
+    /// of the text and its next start.
+    pub fn with_looping(mut self, padding: usize) -> Self {
+        self.loop_padding = Some(padding);
+        self.blank = padding;
+        self
+    }
+
+    /// Returns the text being scrolled.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the visible columns, leftmost first.
+    pub fn columns(&self) -> &[u8; COLUMNS] {
+        &self.window
+    }
+
+    /// Blanks the window and starts the text over.
+    pub fn reset(&mut self) {
+        self.window = [0; COLUMNS];
+        self.rest = self.text;
+        self.glyph_column = GLYPH_WIDTH;
+        self.blank = self.loop_padding.unwrap_or(COLUMNS);
+        self.finished = false;
+    }
+
+    /// Returns `true` once text that doesn't loop has scrolled off.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Moves the text one column to the left.
+    ///
+    /// Returns [`Progress::Complete`] when the text has scrolled off, or,
+    /// when looping, each time it starts over. Once a pass that doesn't
+    /// loop is complete, further calls return [`Progress::Idle`] until
+    /// [`Self::reset`].
+    pub fn step(&mut self) -> Progress {
+        if self.finished {
+            return Progress::Idle;
+        }
+        let (column, progress) = match (self.next_column(), self.loop_padding) {
+            (Some(column), _) => (column, Progress::Running),
+            (None, Some(padding)) => {
+                self.rest = self.text;
+                self.blank = padding;
+                (self.next_column().unwrap_or(0), Progress::Complete)
+            }
+            (None, None) => {
+                // The trailing blank columns have cleared the window
+                self.finished = true;
+                return Progress::Complete;
+            }
+        };
+        self.window.copy_within(1.., 0);
+        if let Some(last) = self.window.last_mut() {
+            *last = column;
+        }
+        progress
+    }
+
+    /// Rasterizes the next column of the pass, or `None` at its end.
+    fn next_column(&mut self) -> Option<u8> {
+        if let Some(&column) = self.glyph.get(self.glyph_column) {
+            self.glyph_column = self.glyph_column.saturating_add(1);
+            return Some(column);
+        }
+        let mut chars = self.rest.chars();
+        while let Some(ch) = chars.next() {
+            // Checked in `new`, so only skipped characters have no glyph
+            if let Ok(Some(bitmap)) = self.font.lookup(ch) {
+                self.rest = chars.as_str();
+                self.glyph = glyph_columns(&bitmap);
+                self.glyph_column = 1;
+                return self.glyph.first().copied();
+            }
+        }
+        self.rest = "";
+        if self.blank > 0 {
+            self.blank = self.blank.saturating_sub(1);
+            return Some(0);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led_matrix::fonts::STANDARD_LED_FONT;
+    use crate::{CharPolicy, Error};
+
+    const BAR: [u8; 8] = [0x80; 8];
+    const FONT: LedFont = LedFont::new(&[(BAR, '|'), ([0; 8], ' ')]);
+
+    #[test]
+    fn test_glyph_columns() {
+        assert_eq!(glyph_columns(&BAR), [0xFF, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            glyph_columns(&[0x01, 0, 0, 0, 0, 0, 0, 0x80]),
+            [0x80, 0, 0, 0, 0, 0, 0, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_glyph_transform() {
+        let columns = [0x01, 0x03, 0, 0, 0, 0, 0, 0x80];
+        let plain = GlyphTransform::default();
+        assert_eq!(plain.apply(&columns)[..8], [1, 3, 0, 0, 0, 0, 0, 0x80]);
+        assert_eq!((plain.width(), plain.height()), (8, 8));
+
+        let bold = GlyphTransform {
+            bold: true,
+            ..plain
+        };
+        assert_eq!(bold.apply(&columns)[..8], [1, 3, 3, 0, 0, 0, 0, 0x80]);
+
+        let wide = GlyphTransform {
+            wide: true,
+            ..plain
+        };
+        assert_eq!(wide.width(), 16);
+        assert_eq!(wide.apply(&columns)[..4], [1, 1, 3, 3]);
+        assert_eq!(wide.apply(&columns)[14..], [0x80, 0x80]);
+
+        let tall = GlyphTransform {
+            tall: true,
+            ..plain
+        };
+        assert_eq!(tall.height(), 16);
+        assert_eq!(tall.apply(&columns)[..2], [0b11, 0b1111]);
+        assert_eq!(tall.apply(&columns)[7], 0xC000);
+        assert_eq!(tall.apply(&columns)[8..], [0; 8]);
+    }
+
+    #[test]
+    fn test_render_columns() {
+        let mut strip = [0xAA; 24];
+        assert_eq!(render_columns("| |", &FONT, &mut strip), Ok(24));
+        assert_eq!(strip[0], 0xFF);
+        assert_eq!(strip[1..16], [0; 15]);
+        assert_eq!(strip[16], 0xFF);
+
+        let skipping = FONT.with_unsupported(CharPolicy::Skip);
+        assert_eq!(render_columns("x|x", &skipping, &mut strip), Ok(8));
+        assert_eq!(
+            render_columns("|||", &FONT, &mut strip[..20]),
+            Err(Error::BufferError)
+        );
+        let strict = FONT.with_unsupported(CharPolicy::Error);
+        assert_eq!(
+            render_columns("x", &strict, &mut strip),
+            Err(Error::UnsupportedChar('x'))
+        );
+    }
+
+    #[test]
+    fn test_text_enters_and_leaves() {
+        let mut marquee: ColumnScroller<'_, 4> = ColumnScroller::new("|", &FONT).unwrap();
+        assert_eq!(marquee.columns(), &[0; 4]);
+        assert_eq!(marquee.step(), Progress::Running);
+        assert_eq!(marquee.columns(), &[0, 0, 0, 0xFF]);
+        for _ in 0..3 {
+            marquee.step();
+        }
+        assert_eq!(marquee.columns(), &[0xFF, 0, 0, 0]);
+
+        // The other 4 glyph columns, then 4 blank ones
+        for _ in 0..8 {
+            assert_eq!(marquee.step(), Progress::Running);
+        }
+        assert_eq!(marquee.columns(), &[0; 4]);
+        assert_eq!(marquee.step(), Progress::Complete);
+        assert!(marquee.is_finished());
+        assert_eq!(marquee.step(), Progress::Idle);
+
+        marquee.reset();
+        marquee.step();
+        assert_eq!(marquee.columns(), &[0, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn test_looping() {
+        let mut marquee: ColumnScroller<'_, 2> =
+            ColumnScroller::new("|", &FONT).unwrap().with_looping(2);
+        let mut lit = Vec::new();
+        let mut completions = Vec::new();
+        for step in 0..20 {
+            if marquee.step() == Progress::Complete {
+                completions.push(step);
+            }
+            lit.push(marquee.columns()[1] != 0);
+        }
+        // One pass is 8 glyph columns plus 2 blank ones
+        assert_eq!(completions, [10]);
+        assert!(lit[0] && lit[10]);
+        assert_eq!(lit.iter().filter(|&&on| on).count(), 2);
+    }
+
+    #[test]
+    fn test_matches_font() {
+        let mut marquee: ColumnScroller<'_, 8> =
+            ColumnScroller::new("A", &STANDARD_LED_FONT).unwrap();
+        for _ in 0..8 {
+            marquee.step();
+        }
+        let columns = glyph_columns(&STANDARD_LED_FONT.get_char('A'));
+        assert_eq!(marquee.columns(), &columns);
+
+        let strict = FONT.with_unsupported(CharPolicy::Error);
+        assert!(matches!(
+            ColumnScroller::<'_, 8>::new("|x", &strict),
+            Err(Error::UnsupportedChar('x'))
+        ));
+    }
+}
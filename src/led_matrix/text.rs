@@ -0,0 +1,146 @@
+//! Static text layout helpers
+//!
+//! Glyphs are 8 pixels wide, so the width of a string is `8 * chars`.
+
+/// Horizontal alignment of text within the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    /// Text starts at the left edge
+    #[default]
+    Left,
+    /// Text is centered, rounding towards the left
+    Center,
+    /// Text ends at the right edge
+    Right,
+}
+
+/// How the glyphs of a label are rendered.
+///
+/// Styles apply to the cells of the label only; the rest of the display is
+/// left dark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextStyle {
+    /// Lit glyphs on a dark background
+    #[default]
+    Normal,
+    /// Dark glyphs on a lit background, e.g. for the active menu item
+    Inverse,
+    /// Glyphs surrounded by a one pixel frame, e.g. for alarm text
+    Boxed,
+    /// Glyphs with the bottom row lit
+    Underlined,
+}
+
+impl TextStyle {
+    /// Returns the bitmap of one glyph cell in this style.
+    ///
+    /// `first` and `last` tell whether the cell starts or ends the label, so
+    /// a box is only closed at the ends.
+    pub fn apply(self, bitmap: &[u8; 8], first: bool, last: bool) -> [u8; 8] {
+        match self {
+            TextStyle::Normal => *bitmap,
+            TextStyle::Inverse => bitmap.map(|row| !row),
+            TextStyle::Underlined => {
+                let mut cell = *bitmap;
+                cell[7] = 0xFF;
+                cell
+            }
+            TextStyle::Boxed => {
+                let sides = if first { 0x80 } else { 0 } | if last { 0x01 } else { 0 };
+                let mut cell = bitmap.map(|row| row | sides);
+                cell[0] = 0xFF;
+                cell[7] = 0xFF;
+                cell
+            }
+        }
+    }
+}
+
+/// Where a string goes on a display of a given width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFit {
+    /// X coordinate of the first glyph
+    pub x: usize,
+    /// Number of characters from the start of the string that are drawn
+    pub chars: usize,
+    /// Whether an ellipsis glyph follows the drawn characters
+    pub ellipsis: bool,
+}
+
+impl TextFit {
+    /// Lays out `char_count` 8-pixel glyphs on a display `width` pixels wide.
+    ///
+    /// If the text does not fit, as many characters as possible are kept and
+    /// the last cell shows an ellipsis instead.
+    pub fn new(char_count: usize, width: usize, align: Align) -> Self {
+        let cells = width / 8;
+        let (chars, ellipsis) = if char_count <= cells {
+            (char_count, false)
+        } else {
+            (cells.saturating_sub(1), cells > 0)
+        };
+
+        let used = chars.saturating_add(ellipsis as usize).saturating_mul(8);
+        let free = width.saturating_sub(used);
+        let x = match align {
+            Align::Left => 0,
+            Align::Center => free / 2,
+            Align::Right => free,
+        };
+        Self { x, chars, ellipsis }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_styles() {
+        let glyph = [0, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0];
+        assert_eq!(TextStyle::Normal.apply(&glyph, true, true), glyph);
+        assert_eq!(
+            TextStyle::Inverse.apply(&glyph, true, true),
+            [0xFF, 0xE7, 0xE7, 0xE7, 0xE7, 0xE7, 0xE7, 0xFF]
+        );
+        assert_eq!(TextStyle::Underlined.apply(&glyph, false, false)[7], 0xFF);
+        assert_eq!(
+            TextStyle::Boxed.apply(&glyph, true, false),
+            [0xFF, 0x98, 0x98, 0x98, 0x98, 0x98, 0x98, 0xFF]
+        );
+        assert_eq!(TextStyle::Boxed.apply(&glyph, false, true)[3], 0x19);
+    }
+
+    #[test]
+    fn test_fit_aligns_short_text() {
+        let fit = TextFit::new(2, 32, Align::Center);
+        assert_eq!(
+            fit,
+            TextFit {
+                x: 8,
+                chars: 2,
+                ellipsis: false
+            }
+        );
+        assert_eq!(TextFit::new(1, 20, Align::Center).x, 6);
+        assert_eq!(TextFit::new(1, 32, Align::Right).x, 24);
+        assert_eq!(TextFit::new(1, 32, Align::Left).x, 0);
+    }
+
+    #[test]
+    fn test_fit_truncates_with_ellipsis() {
+        let fit = TextFit::new(6, 32, Align::Right);
+        assert_eq!(
+            fit,
+            TextFit {
+                x: 0,
+                chars: 3,
+                ellipsis: true
+            }
+        );
+
+        let fit = TextFit::new(2, 8, Align::Left);
+        assert_eq!((fit.chars, fit.ellipsis), (0, true));
+        assert!(!TextFit::new(2, 4, Align::Left).ellipsis);
+    }
+}
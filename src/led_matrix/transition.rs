@@ -0,0 +1,92 @@
+//! Wipe transitions between two frames
+//!
+//! A [`Wipe`] reveals a new frame pixel by pixel in a fixed order. The order
+//! is computed in logical coordinates, so on tiled or serpentine walls the
+//! wipe runs across the whole surface rather than inside each module.
+//!
+//! See [`LedMatrix::wipe`](crate::led_matrix::LedMatrix::wipe) and
+//! [`LedMatrix::wipe_step`](crate::led_matrix::LedMatrix::wipe_step).
+
+/// Order in which a wipe reveals the new frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Wipe {
+    /// Column by column, starting on the left
+    #[default]
+    LeftToRight,
+    /// Column by column, starting on the right
+    RightToLeft,
+    /// Row by row, starting at the top
+    TopToBottom,
+    /// Row by row, starting at the bottom
+    BottomToTop,
+    /// Diagonal lines, starting in the top-left corner
+    Diagonal,
+    /// Growing rectangles, starting in the center
+    CenterOut,
+}
+
+impl Wipe {
+    /// Returns the number of steps the wipe takes on a display of the given
+    /// size, i.e. the number of distinct [`Self::rank`] values.
+    pub fn steps(self, width: usize, height: usize) -> usize {
+        match self {
+            Wipe::LeftToRight | Wipe::RightToLeft => width,
+            Wipe::TopToBottom | Wipe::BottomToTop => height,
+            Wipe::Diagonal => width.saturating_add(height).saturating_sub(1),
+            Wipe::CenterOut => width.max(height).div_ceil(2),
+        }
+    }
+
+    /// Returns the step at which the pixel at (`x`, `y`) is revealed on a
+    /// display of the given size, starting at 0.
+    pub fn rank(self, x: usize, y: usize, width: usize, height: usize) -> usize {
+        match self {
+            Wipe::LeftToRight => x,
+            Wipe::RightToLeft => width.saturating_sub(x.saturating_add(1)),
+            Wipe::TopToBottom => y,
+            Wipe::BottomToTop => height.saturating_sub(y.saturating_add(1)),
+            Wipe::Diagonal => x.saturating_add(y),
+            Wipe::CenterOut => {
+                let dx = x.saturating_mul(2).saturating_add(1).abs_diff(width);
+                let dy = y.saturating_mul(2).saturating_add(1).abs_diff(height);
+                dx.max(dy) / 2
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranks_cover_every_step() {
+        let (width, height) = (16, 16);
+        for wipe in [
+            Wipe::LeftToRight,
+            Wipe::RightToLeft,
+            Wipe::TopToBottom,
+            Wipe::BottomToTop,
+            Wipe::Diagonal,
+            Wipe::CenterOut,
+        ] {
+            let steps = wipe.steps(width, height);
+            let mut seen = [false; 31];
+            for y in 0..height {
+                for x in 0..width {
+                    seen[wipe.rank(x, y, width, height)] = true;
+                }
+            }
+            assert!(seen[..steps].iter().all(|&s| s), "{wipe:?}");
+            assert!(!seen[steps..].iter().any(|&s| s), "{wipe:?}");
+        }
+    }
+
+    #[test]
+    fn test_center_out_starts_in_the_middle() {
+        assert_eq!(Wipe::CenterOut.rank(7, 7, 16, 16), 0);
+        assert_eq!(Wipe::CenterOut.rank(8, 8, 16, 16), 0);
+        assert_eq!(Wipe::CenterOut.rank(0, 8, 16, 16), 7);
+        assert_eq!(Wipe::CenterOut.rank(16, 4, 32, 8), 0);
+    }
+}
@@ -0,0 +1,239 @@
+//! Minimal analog clock face for tiled matrix grids
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, Result,
+    led_matrix::{LedMatrix, UpdateMode},
+};
+
+/// `sin(6° * i) * 1024` for `i` in 0 to 15, i.e. the first quarter of a
+/// clock face in minute steps.
+const SIN_TABLE: [i32; 16] = [
+    0, 107, 213, 316, 416, 512, 602, 685, 761, 828, 887, 935, 974, 1002, 1018, 1024,
+];
+
+/// Sine of the angle of a minute position (0 to 59, clockwise from 12), scaled by 1024.
+fn sin_position(position: usize) -> i32 {
+    let position = position % 60;
+    let (quarter, step) = (position / 15, position % 15);
+    let sin = |step: usize| SIN_TABLE.get(step).copied().unwrap_or(1024);
+    let mirrored = 15usize.saturating_sub(step);
+    match quarter {
+        0 => sin(step),
+        1 => sin(mirrored),
+        2 => sin(step).saturating_neg(),
+        _ => sin(mirrored).saturating_neg(),
+    }
+}
+
+/// Analog clock with hour and minute hands, drawn across the whole display.
+///
+/// Meant for square grids of 16x16 pixels or more (see
+/// [`TiledMapper`](crate::led_matrix::TiledMapper)); on smaller displays the
+/// hands become too short to read.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// static GRID: TiledMapper = TiledMapper::new(2, 2);
+/// let mut matrix = Matrix4::from_spi(spi)?.with_mapper(&GRID)?;
+///
+/// let mut clock = AnalogClock::new().with_hour_marks(true);
+/// clock.set_time(10, 10)?;
+/// clock.draw(&mut matrix)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnalogClock {
+    hours: u8,
+    minutes: u8,
+    hour_marks: bool,
+}
+
+impl AnalogClock {
+    /// Create a clock showing 12:00
+    pub const fn new() -> Self {
+        Self {
+            hours: 0,
+            minutes: 0,
+            hour_marks: false,
+        }
+    }
+
+    /// Enable or disable marks at 12, 3, 6 and 9 o'clock
+    pub const fn with_hour_marks(mut self, enabled: bool) -> Self {
+        self.hour_marks = enabled;
+        self
+    }
+
+    /// Sets the time to show.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `hours` is above 23 or `minutes` is above 59.
+    pub fn set_time(&mut self, hours: u8, minutes: u8) -> Result<()> {
+        if hours > 23 || minutes > 59 {
+            return Err(Error::InvalidDigit);
+        }
+        self.hours = hours;
+        self.minutes = minutes;
+        Ok(())
+    }
+
+    /// Returns the time shown as `(hours, minutes)`.
+    pub fn time(&self) -> (u8, u8) {
+        (self.hours, self.minutes)
+    }
+
+    /// Returns the end point of a hand pointing at `position` (0 to 59) with
+    /// the given length, starting at `center`.
+    fn hand_end(center: (usize, usize), position: usize, length: usize) -> (usize, usize) {
+        let length = length as i32;
+        let dx = sin_position(position).saturating_mul(length);
+        let dy = sin_position(position.saturating_add(15))
+            .saturating_neg()
+            .saturating_mul(length);
+        // Round to the nearest pixel
+        let round = |d: i32| d.saturating_add(d.signum().saturating_mul(512)) / 1024;
+        let x = (center.0 as i32).saturating_add(round(dx));
+        let y = (center.1 as i32).saturating_add(round(dy));
+        (x.max(0) as usize, y.max(0) as usize)
+    }
+
+    /// Replaces the framebuffer with the clock face.
+    ///
+    /// In write-through mode the face is flushed right away; in buffered
+    /// mode it is sent on the next flush.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the display is smaller than 4x4 pixels,
+    /// or an SPI error if the flush fails.
+    pub fn draw<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        let (width, height) = (matrix.width(), matrix.height());
+        let radius = width.min(height) / 2;
+        if radius < 2 {
+            return Err(Error::BufferError);
+        }
+        let radius = radius.saturating_sub(1);
+        let center = (
+            (width / 2).saturating_sub(1),
+            (height / 2).saturating_sub(1),
+        );
+
+        matrix.clear_buffer();
+        if self.hour_marks {
+            for position in [0, 15, 30, 45] {
+                let (x, y) = Self::hand_end(center, position, radius);
+                matrix.set_pixel(x, y, true)?;
+            }
+        }
+
+        let minute_position = self.minutes as usize;
+        let hour_position = (self.hours as usize % 12)
+            .saturating_mul(5)
+            .saturating_add(self.minutes as usize / 12);
+        for (position, length) in [
+            (minute_position, radius),
+            (hour_position, (radius.saturating_mul(3) / 5).max(1)),
+        ] {
+            let (x, y) = Self::hand_end(center, position, length);
+            matrix.draw_line(center.0, center.1, x, y, true)?;
+        }
+
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Max7219,
+        led_matrix::{TiledMapper, display::Matrix4},
+    };
+    use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+    static GRID: TiledMapper = TiledMapper::new(2, 2);
+
+    #[test]
+    fn test_sin_position() {
+        assert_eq!(sin_position(0), 0);
+        assert_eq!(sin_position(15), 1024);
+        assert_eq!(sin_position(30), 0);
+        assert_eq!(sin_position(45), -1024);
+        assert_eq!(sin_position(5), 512);
+        assert_eq!(sin_position(35), -512);
+    }
+
+    #[test]
+    fn test_hand_end() {
+        let center = (7, 7);
+        assert_eq!(AnalogClock::hand_end(center, 0, 7), (7, 0));
+        assert_eq!(AnalogClock::hand_end(center, 15, 7), (14, 7));
+        assert_eq!(AnalogClock::hand_end(center, 30, 7), (7, 14));
+        assert_eq!(AnalogClock::hand_end(center, 45, 7), (0, 7));
+    }
+
+    #[test]
+    fn test_draw_three_oclock() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_mapper(&GRID)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        let mut clock = AnalogClock::new();
+        clock.set_time(15, 0).unwrap();
+        clock.draw(&mut matrix).unwrap();
+
+        // Minute hand points straight up, hour hand straight right
+        for y in 0..=7 {
+            assert_eq!(matrix.get_pixel(7, y), Ok(true));
+        }
+        for x in 7..=11 {
+            assert_eq!(matrix.get_pixel(x, 7), Ok(true));
+        }
+        assert_eq!(matrix.get_pixel(12, 7), Ok(false));
+        assert_eq!(matrix.get_pixel(7, 8), Ok(false));
+        spi.done();
+    }
+
+    #[test]
+    fn test_hour_marks() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_mapper(&GRID)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        AnalogClock::new()
+            .with_hour_marks(true)
+            .draw(&mut matrix)
+            .unwrap();
+        assert_eq!(matrix.get_pixel(7, 14), Ok(true));
+        assert_eq!(matrix.get_pixel(0, 7), Ok(true));
+        assert_eq!(matrix.get_pixel(14, 7), Ok(true));
+        spi.done();
+    }
+
+    #[test]
+    fn test_set_time_invalid() {
+        let mut clock = AnalogClock::new();
+        assert_eq!(clock.set_time(24, 0), Err(Error::InvalidDigit));
+        assert_eq!(clock.set_time(0, 60), Err(Error::InvalidDigit));
+    }
+}
@@ -0,0 +1,441 @@
+//! Large numerals spanning whole modules, rolling on change
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Result,
+    easing::{Easing, Tween},
+    led_matrix::{LedMatrix, UpdateMode, fonts::STANDARD_LED_FONT},
+    threshold::Thresholds,
+};
+
+/// Size of each numeral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigitSize {
+    /// 8x8 pixels, one module per digit
+    #[default]
+    Single,
+    /// 16x8 pixels, the font stretched over two modules per digit
+    Double,
+}
+
+impl DigitSize {
+    /// Returns the width of a digit in pixels.
+    pub const fn width(self) -> usize {
+        match self {
+            DigitSize::Single => 8,
+            DigitSize::Double => 16,
+        }
+    }
+}
+
+/// Doubles every bit of `bits`, so `0b10` becomes `0b1100`.
+fn stretch(bits: u8) -> u16 {
+    (0..8u32).fold(0, |wide, bit| {
+        if bits & (1 << bit) != 0 {
+            wide | (0b11 << bit.wrapping_mul(2))
+        } else {
+            wide
+        }
+    })
+}
+
+/// One digit position with its roll animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Cell {
+    /// Digit shown, `None` for a blank leading position
+    digit: Option<u8>,
+    /// Digit rolling out, if the animation is running
+    previous: Option<Option<u8>>,
+}
+
+/// A row of `N` large numerals for wall clocks and counters built from
+/// matrix modules.
+///
+/// When a digit changes, the old numeral rolls up and out while the new one
+/// rolls in from below. The animation is driven by [`BigDigits::tick`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // "12:34" without the colon on a chain of four modules
+/// let mut clock: BigDigits<4> = BigDigits::new().with_leading_zeros(true);
+/// clock.set_value(1234);
+/// loop {
+///     if clock.tick(10) {
+///         clock.draw(&mut matrix)?;
+///     }
+///     delay.delay_ms(10);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BigDigits<const N: usize> {
+    cells: [Cell; N],
+    size: DigitSize,
+    leading_zeros: bool,
+    roll_ms: u32,
+    elapsed_ms: u32,
+    value: u32,
+    easing: Easing,
+    count: Option<Tween>,
+    thresholds: Option<Thresholds>,
+}
+
+impl<const N: usize> Default for BigDigits<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> BigDigits<N> {
+    /// Create a row of 8x8 digits showing 0, rolling in 300 ms
+    pub const fn new() -> Self {
+        let mut cells = [Cell {
+            digit: None,
+            previous: None,
+        }; N];
+        if let Some(last) = cells.last_mut() {
+            last.digit = Some(0);
+        }
+        Self {
+            cells,
+            size: DigitSize::Single,
+            leading_zeros: false,
+            roll_ms: 300,
+            elapsed_ms: 0,
+            value: 0,
+            easing: Easing::Linear,
+            count: None,
+            thresholds: None,
+        }
+    }
+
+    /// Sets the size of the digits
+    pub const fn with_size(mut self, size: DigitSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Pads the value with zeros instead of blanks, e.g. for clocks
+    pub const fn with_leading_zeros(mut self, leading_zeros: bool) -> Self {
+        self.leading_zeros = leading_zeros;
+        self
+    }
+
+    /// Sets how long a digit takes to roll to its new value; 0 switches
+    /// instantly
+    pub const fn with_roll_ms(mut self, roll_ms: u32) -> Self {
+        self.roll_ms = roll_ms;
+        self
+    }
+
+    /// Sets the easing curve of counts started with [`Self::animate_to`]
+    pub const fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Blink or change the brightness according to alarm rules, evaluated
+    /// whenever the value changes
+    pub fn with_thresholds(mut self, thresholds: Thresholds) -> Self {
+        self.thresholds = Some(thresholds);
+        self.show(self.value, false);
+        self
+    }
+
+    /// Returns the alarm rules and their current state, if any.
+    pub fn thresholds(&self) -> Option<&Thresholds> {
+        self.thresholds.as_ref()
+    }
+
+    /// Returns the value shown, which moves towards the target while a
+    /// count runs.
+    pub const fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns the width of the whole row in pixels.
+    pub const fn width(&self) -> usize {
+        N.saturating_mul(self.size.width())
+    }
+
+    /// Shows `value`, rolling the digits that change, and stops any count.
+    ///
+    /// Only the lowest `N` digits are shown. A roll that is still running
+    /// restarts from the digit currently on its way in.
+    pub fn set_value(&mut self, value: u32) {
+        self.count = None;
+        self.show(value, self.roll_ms > 0);
+    }
+
+    /// Counts visibly from the current value to `value` within
+    /// `duration_ms`, following the easing curve set with
+    /// [`Self::with_easing`].
+    ///
+    /// The digits switch instantly while counting instead of rolling.
+    /// [`Self::tick`] advances the count.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut score: BigDigits<4> = BigDigits::new().with_easing(Easing::QuadOut);
+    /// score.animate_to(250, 1500);
+    /// loop {
+    ///     if score.tick(20) {
+    ///         score.draw(&mut matrix)?;
+    ///     }
+    ///     delay.delay_ms(20);
+    /// }
+    /// ```
+    pub fn animate_to(&mut self, value: u32, duration_ms: u32) {
+        let count = Tween::new(u64::from(self.value), u64::from(value), duration_ms)
+            .with_easing(self.easing);
+        self.count = count.is_running().then_some(count);
+        for cell in self.cells.iter_mut() {
+            cell.previous = None;
+        }
+        self.show(count.value() as u32, false);
+    }
+
+    /// Returns `true` while a count started with [`Self::animate_to`] runs.
+    pub fn is_counting(&self) -> bool {
+        self.count.is_some()
+    }
+
+    /// Updates the cells for `value`, rolling changed digits if `roll`, and
+    /// evaluates the alarm rules. Returns `true` if the alert changed.
+    fn show(&mut self, mut value: u32, roll: bool) -> bool {
+        self.value = value;
+        let alerted = self
+            .thresholds
+            .as_mut()
+            .is_some_and(|thresholds| thresholds.update(i64::from(value)));
+        let mut changed = false;
+        for (index, cell) in self.cells.iter_mut().enumerate().rev() {
+            let leading = value == 0 && index.saturating_add(1) < N;
+            let digit = if leading && !self.leading_zeros {
+                None
+            } else {
+                Some((value % 10) as u8)
+            };
+            value /= 10;
+
+            if cell.digit != digit {
+                cell.previous = roll.then_some(cell.digit);
+                cell.digit = digit;
+                changed = true;
+            }
+        }
+        if changed {
+            self.elapsed_ms = 0;
+        }
+        alerted
+    }
+
+    /// Returns `true` while digits are rolling.
+    pub fn is_rolling(&self) -> bool {
+        self.cells.iter().any(|cell| cell.previous.is_some())
+    }
+
+    /// Advances the count, the roll animation and the alarm blink by
+    /// `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the digits moved and need to be drawn again.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        let blinked = self
+            .thresholds
+            .as_mut()
+            .is_some_and(|thresholds| thresholds.tick(dt_ms));
+        if let Some(count) = self.count.as_mut() {
+            let done = count.tick(dt_ms).is_complete();
+            let value = count.value() as u32;
+            if done {
+                self.count = None;
+            }
+            let previous = self.value;
+            let alerted = self.show(value, false);
+            return self.value != previous || alerted || blinked;
+        }
+        if !self.is_rolling() {
+            return blinked;
+        }
+        let before = self.offset();
+        self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+        if self.elapsed_ms >= self.roll_ms {
+            for cell in self.cells.iter_mut() {
+                cell.previous = None;
+            }
+            return true;
+        }
+        self.offset() != before
+    }
+
+    /// Rows the rolling digits have moved up, 0 to 8
+    fn offset(&self) -> usize {
+        match self
+            .elapsed_ms
+            .checked_mul(8)
+            .and_then(|e| e.checked_div(self.roll_ms))
+        {
+            Some(offset) => (offset as usize).min(8),
+            None => 8,
+        }
+    }
+
+    /// Returns the rows of a digit position in the current animation frame.
+    fn rows(&self, cell: &Cell) -> [u8; 8] {
+        let glyph = |digit: Option<u8>| match digit {
+            Some(digit) => STANDARD_LED_FONT.get_char(char::from(b'0'.saturating_add(digit))),
+            None => [0; 8],
+        };
+        let next = glyph(cell.digit);
+        let Some(previous) = cell.previous else {
+            return next;
+        };
+        let previous = glyph(previous);
+        let offset = self.offset();
+        core::array::from_fn(|row| {
+            let source = row.saturating_add(offset);
+            previous
+                .get(source)
+                .or_else(|| source.checked_sub(8).and_then(|source| next.get(source)))
+                .copied()
+                .unwrap_or(0)
+        })
+    }
+
+    /// Draws the digits from the left edge of the top 8 rows, clearing the
+    /// framebuffer first.
+    ///
+    /// Alarm rules apply to every matrix device, see [`Thresholds`].
+    pub fn draw<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        matrix.clear_buffer();
+        if let Some(thresholds) = &self.thresholds {
+            let devices = matrix.matrix_devices();
+            if !thresholds.apply(matrix.driver(), &devices)? {
+                if matrix.update_mode() == UpdateMode::WriteThrough {
+                    matrix.flush()?;
+                }
+                return Ok(());
+            }
+        }
+
+        for (index, cell) in self.cells.iter().enumerate() {
+            let rows = self.rows(cell);
+            let x = index.saturating_mul(self.size.width()) as i32;
+            match self.size {
+                DigitSize::Single => matrix.draw_glyph(x, 0, &rows)?,
+                DigitSize::Double => {
+                    let wide = rows.map(stretch);
+                    matrix.draw_glyph(x, 0, &wide.map(|row| (row >> 8) as u8))?;
+                    matrix.draw_glyph(x.saturating_add(8), 0, &wide.map(|row| row as u8))?;
+                }
+            }
+        }
+
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stretch() {
+        assert_eq!(stretch(0b1000_0001), 0b1100_0000_0000_0011);
+        assert_eq!(stretch(0xFF), 0xFFFF);
+    }
+
+    #[test]
+    fn test_leading_blanks_and_zeros() {
+        let mut digits: BigDigits<3> = BigDigits::new();
+        digits.set_value(7);
+        assert_eq!(digits.cells.map(|cell| cell.digit), [None, None, Some(7)]);
+
+        let mut digits: BigDigits<3> = BigDigits::new().with_leading_zeros(true);
+        digits.set_value(1042);
+        assert_eq!(
+            digits.cells.map(|cell| cell.digit),
+            [Some(0), Some(4), Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_roll_animation() {
+        let zero = STANDARD_LED_FONT.get_char('0');
+        let one = STANDARD_LED_FONT.get_char('1');
+        let mut digits: BigDigits<1> = BigDigits::new().with_roll_ms(80);
+        digits.set_value(1);
+        assert!(digits.is_rolling());
+        assert_eq!(digits.rows(&digits.cells[0]), zero);
+
+        // 3 of 8 rows after 30 ms
+        assert!(digits.tick(30));
+        let rows = digits.rows(&digits.cells[0]);
+        assert_eq!(rows[..5], zero[3..]);
+        assert_eq!(rows[5..], one[..3]);
+        assert!(!digits.tick(1));
+
+        assert!(digits.tick(50));
+        assert!(!digits.is_rolling());
+        assert_eq!(digits.rows(&digits.cells[0]), one);
+        assert!(!digits.tick(10));
+    }
+
+    #[test]
+    fn test_animate_to_counts_without_rolling() {
+        let mut digits: BigDigits<2> = BigDigits::new();
+        digits.animate_to(40, 400);
+        assert!(digits.is_counting());
+        assert!(!digits.tick(5));
+        assert!(digits.tick(95));
+        assert_eq!(digits.value(), 10);
+        assert!(!digits.is_rolling());
+        assert_eq!(digits.cells.map(|cell| cell.digit), [Some(1), Some(0)]);
+
+        assert!(digits.tick(300));
+        assert_eq!(digits.value(), 40);
+        assert!(!digits.is_counting());
+        assert!(!digits.tick(100));
+    }
+
+    #[test]
+    fn test_thresholds_brightness_and_blink() {
+        use crate::{
+            Brightness,
+            threshold::{AlertAction, Threshold},
+        };
+
+        let thresholds = Thresholds::new(Brightness::from_percent(50))
+            .with_rule(Threshold::above(99, AlertAction::Blink { period_ms: 200 }))
+            .unwrap()
+            .with_rule(Threshold::below(
+                10,
+                AlertAction::Brightness(Brightness::MIN),
+            ))
+            .unwrap();
+        let mut digits: BigDigits<3> = BigDigits::new().with_thresholds(thresholds);
+        assert_eq!(
+            digits.thresholds().map(Thresholds::brightness),
+            Some(Brightness::MIN)
+        );
+
+        digits.animate_to(100, 100);
+        assert!(digits.tick(100));
+        let thresholds = digits.thresholds().unwrap();
+        assert!(thresholds.is_blinking());
+        assert_eq!(thresholds.brightness(), Brightness::from_percent(50));
+        assert!(digits.tick(100));
+        assert!(!digits.thresholds().unwrap().is_visible());
+    }
+}
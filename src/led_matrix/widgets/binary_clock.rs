@@ -0,0 +1,234 @@
+//! Binary clock for a single 8x8 module
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, Result,
+    led_matrix::{LedMatrix, buffer::MatrixBuffer},
+};
+
+/// How the hours, minutes and seconds are split into columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BinaryClockLayout {
+    /// One column per decimal digit: `H H . M M . S S`.
+    ///
+    /// Column 2 and 5 are left empty as separators.
+    #[default]
+    Bcd,
+    /// Each value in plain binary, drawn two columns wide: `H H . M M . S S`.
+    Binary,
+}
+
+/// Where the least significant bit of each column is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// Least significant bit on the bottom row
+    #[default]
+    LsbBottom,
+    /// Least significant bit on the top row
+    LsbTop,
+}
+
+/// Binary clock rendered on one 8x8 module.
+///
+/// With the seconds pulse enabled, the pixel at the far end of the first
+/// separator column (column 2) is lit on even seconds.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut clock = BinaryClock::new().with_seconds_pulse(true);
+/// clock.set_time(12, 34, 56)?;
+/// clock.draw(&mut matrix, 0)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BinaryClock {
+    layout: BinaryClockLayout,
+    bit_order: BitOrder,
+    seconds_pulse: bool,
+    hours: u8,
+    minutes: u8,
+    seconds: u8,
+}
+
+impl BinaryClock {
+    /// Create a BCD clock showing 00:00:00
+    pub const fn new() -> Self {
+        Self {
+            layout: BinaryClockLayout::Bcd,
+            bit_order: BitOrder::LsbBottom,
+            seconds_pulse: false,
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+        }
+    }
+
+    /// Use the given column layout
+    pub const fn with_layout(mut self, layout: BinaryClockLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Use the given bit order
+    pub const fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Enable or disable the seconds pulse pixel
+    pub const fn with_seconds_pulse(mut self, enabled: bool) -> Self {
+        self.seconds_pulse = enabled;
+        self
+    }
+
+    /// Sets the time to show.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `hours` is above 23 or `minutes` or
+    /// `seconds` is above 59.
+    pub fn set_time(&mut self, hours: u8, minutes: u8, seconds: u8) -> Result<()> {
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return Err(Error::InvalidDigit);
+        }
+        self.hours = hours;
+        self.minutes = minutes;
+        self.seconds = seconds;
+        Ok(())
+    }
+
+    /// Returns the time shown as `(hours, minutes, seconds)`.
+    pub fn time(&self) -> (u8, u8, u8) {
+        (self.hours, self.minutes, self.seconds)
+    }
+
+    /// Returns the eight rows (bit 7 = leftmost pixel) of the clock face.
+    pub fn rows(&self) -> [u8; 8] {
+        let mut rows = [0u8; 8];
+        let columns: [(usize, u8); 6] = match self.layout {
+            BinaryClockLayout::Bcd => [
+                (0, self.hours / 10),
+                (1, self.hours % 10),
+                (3, self.minutes / 10),
+                (4, self.minutes % 10),
+                (6, self.seconds / 10),
+                (7, self.seconds % 10),
+            ],
+            BinaryClockLayout::Binary => [
+                (0, self.hours),
+                (1, self.hours),
+                (3, self.minutes),
+                (4, self.minutes),
+                (6, self.seconds),
+                (7, self.seconds),
+            ],
+        };
+
+        for (col, value) in columns {
+            for bit in 0..6 {
+                if value & (1 << bit) != 0
+                    && let Some(row) = rows.get_mut(self.row_of_bit(bit))
+                {
+                    *row |= 0x80 >> col;
+                }
+            }
+        }
+
+        if self.seconds_pulse
+            && self.seconds.is_multiple_of(2)
+            && let Some(row) = rows.get_mut(self.row_of_bit(7))
+        {
+            *row |= 0x80 >> 2;
+        }
+        rows
+    }
+
+    fn row_of_bit(&self, bit: usize) -> usize {
+        match self.bit_order {
+            BitOrder::LsbBottom => 7usize.saturating_sub(bit),
+            BitOrder::LsbTop => bit,
+        }
+    }
+
+    /// Draws the clock face on one device of the matrix.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if `device_index` is out of range,
+    /// or an SPI error in write-through mode.
+    pub fn draw<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+        device_index: usize,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        matrix.write_buffer(device_index, &MatrixBuffer::from_data(self.rows()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bcd_rows() {
+        let mut clock = BinaryClock::new();
+        clock.set_time(12, 34, 56).unwrap();
+        let rows = clock.rows();
+        // Bottom row holds bit 0 of every digit: 1, 3 and 5 are odd
+        assert_eq!(rows[7], 0b1001_0010);
+        // Bit 1: 2, 3, 6
+        assert_eq!(rows[6], 0b0101_0001);
+        // Bit 2: 4, 5, 6
+        assert_eq!(rows[5], 0b0000_1011);
+        assert_eq!(rows[4], 0);
+    }
+
+    #[test]
+    fn test_lsb_top_mirrors_rows() {
+        let mut bottom = BinaryClock::new();
+        bottom.set_time(7, 8, 9).unwrap();
+        let mut top = bottom.with_bit_order(BitOrder::LsbTop);
+        top.set_time(7, 8, 9).unwrap();
+
+        let (bottom, top) = (bottom.rows(), top.rows());
+        for row in 0..8 {
+            assert_eq!(bottom[row], top[7 - row]);
+        }
+    }
+
+    #[test]
+    fn test_binary_layout() {
+        let mut clock = BinaryClock::new().with_layout(BinaryClockLayout::Binary);
+        clock.set_time(23, 59, 1).unwrap();
+        let rows = clock.rows();
+        // 23 = 0b10111, 59 = 0b111011, 1 = 0b1
+        assert_eq!(rows[7], 0b1101_1011);
+        assert_eq!(rows[6], 0b1101_1000);
+        assert_eq!(rows[5], 0b1100_0000);
+        assert_eq!(rows[3], 0b1101_1000);
+        assert_eq!(rows[2], 0b0001_1000);
+    }
+
+    #[test]
+    fn test_seconds_pulse() {
+        let mut clock = BinaryClock::new().with_seconds_pulse(true);
+        clock.set_time(0, 0, 0).unwrap();
+        assert_eq!(clock.rows()[0], 0b0010_0000);
+
+        clock.set_time(0, 0, 1).unwrap();
+        assert_eq!(clock.rows()[0], 0);
+    }
+
+    #[test]
+    fn test_set_time_invalid() {
+        let mut clock = BinaryClock::new();
+        assert_eq!(clock.set_time(24, 0, 0), Err(Error::InvalidDigit));
+        assert_eq!(clock.set_time(0, 60, 0), Err(Error::InvalidDigit));
+        assert_eq!(clock.set_time(0, 0, 60), Err(Error::InvalidDigit));
+        assert_eq!(clock.time(), (0, 0, 0));
+    }
+}
@@ -0,0 +1,25 @@
+//! Ready-made widgets for LED matrix displays
+//!
+//! Widgets keep their own state and render it onto an
+//! [`LedMatrix`](crate::led_matrix::LedMatrix) when asked to draw. They
+//! work with any transport, so a matrix made with
+//! [`LedMatrix::from_sink`](crate::led_matrix::LedMatrix::from_sink) renders
+//! them into a [`FrameSink`](crate::FrameSink) without hardware.
+
+pub mod analog_clock;
+pub mod big_digits;
+pub mod binary_clock;
+pub mod percent;
+pub mod rolling_text;
+pub mod rotation;
+#[cfg(feature = "seven-segment")]
+pub mod segment_digits;
+
+pub use analog_clock::AnalogClock;
+pub use big_digits::{BigDigits, DigitSize};
+pub use binary_clock::{BinaryClock, BinaryClockLayout, BitOrder};
+pub use percent::{Percent, Trend};
+pub use rolling_text::{RollDirection, RollingText};
+pub use rotation::{Alert, Screen, ScreenRotation, Transition};
+#[cfg(feature = "seven-segment")]
+pub use segment_digits::{SegmentDigits, segment_glyph};
@@ -0,0 +1,331 @@
+//! Percentage readout with a trend arrow
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Result,
+    easing::{Easing, Tween},
+    led_matrix::{
+        LedMatrix, UpdateMode,
+        fonts::STANDARD_LED_FONT,
+        text::{Align, TextFit},
+    },
+    threshold::Thresholds,
+};
+
+/// Arrow pointing up, shown while the value rises
+pub const ARROW_UP: [u8; 8] = [
+    0b00011000, 0b00111100, 0b01111110, 0b11011011, 0b00011000, 0b00011000, 0b00011000, 0b00000000,
+];
+
+/// Arrow pointing down, shown while the value falls
+pub const ARROW_DOWN: [u8; 8] = [
+    0b00000000, 0b00011000, 0b00011000, 0b00011000, 0b11011011, 0b01111110, 0b00111100, 0b00011000,
+];
+
+/// Arrow pointing right, shown while the value is unchanged
+pub const ARROW_STEADY: [u8; 8] = [
+    0b00000000, 0b00001000, 0b00001100, 0b11111110, 0b11111110, 0b00001100, 0b00001000, 0b00000000,
+];
+
+/// Direction of the last change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Trend {
+    /// The value went up
+    Up,
+    /// The value went down
+    Down,
+    /// The value did not change, or there is no earlier value
+    #[default]
+    Steady,
+}
+
+/// Percentage such as humidity or battery charge, followed by an arrow
+/// showing whether it went up or down since the previous value.
+///
+/// The arrow takes the rightmost 8 columns and the number, with a `%` sign,
+/// is right-aligned before it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut battery = Percent::new();
+/// battery.set(87);
+/// battery.set(86);
+/// battery.draw(&mut matrix)?; // "86%" and a down arrow
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Percent {
+    value: u8,
+    trend: Trend,
+    easing: Easing,
+    count: Option<Tween>,
+    thresholds: Option<Thresholds>,
+}
+
+impl Percent {
+    /// Create a readout showing 0% with a steady trend
+    pub const fn new() -> Self {
+        Self {
+            value: 0,
+            trend: Trend::Steady,
+            easing: Easing::Linear,
+            count: None,
+            thresholds: None,
+        }
+    }
+
+    /// Blink or change the brightness according to alarm rules, evaluated
+    /// whenever the value changes
+    pub fn with_thresholds(mut self, thresholds: Thresholds) -> Self {
+        self.thresholds = Some(thresholds);
+        self.show(self.value);
+        self
+    }
+
+    /// Returns the alarm rules and their current state, if any.
+    pub fn thresholds(&self) -> Option<&Thresholds> {
+        self.thresholds.as_ref()
+    }
+
+    /// Sets the easing curve of counts started with [`Self::animate_to`]
+    pub const fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets a new value and updates the trend by comparing it with the
+    /// previous one. Stops any count.
+    pub fn set(&mut self, value: u8) {
+        self.count = None;
+        self.trend = self.trend_to(value);
+        self.show(value);
+    }
+
+    /// Counts visibly from the current value to `value` within
+    /// `duration_ms`, following the easing curve set with
+    /// [`Self::with_easing`].
+    ///
+    /// The trend arrow points towards the target right away.
+    /// [`Self::tick`] advances the count.
+    pub fn animate_to(&mut self, value: u8, duration_ms: u32) {
+        self.trend = self.trend_to(value);
+        let count = Tween::new(u64::from(self.value), u64::from(value), duration_ms)
+            .with_easing(self.easing);
+        self.show(count.value() as u8);
+        self.count = count.is_running().then_some(count);
+    }
+
+    /// Returns `true` while a count started with [`Self::animate_to`] runs.
+    pub fn is_counting(&self) -> bool {
+        self.count.is_some()
+    }
+
+    /// Advances a running count and the alarm blink by `dt_ms`
+    /// milliseconds.
+    ///
+    /// Returns `true` if the readout needs to be drawn again.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        let blinked = self
+            .thresholds
+            .as_mut()
+            .is_some_and(|thresholds| thresholds.tick(dt_ms));
+        let Some(count) = self.count.as_mut() else {
+            return blinked;
+        };
+        let done = count.tick(dt_ms).is_complete();
+        let value = count.value() as u8;
+        if done {
+            self.count = None;
+        }
+        self.show(value) || blinked
+    }
+
+    /// Sets the value and evaluates the alarm rules. Returns `true` if the
+    /// readout needs to be drawn again.
+    fn show(&mut self, value: u8) -> bool {
+        let changed = value != self.value;
+        self.value = value;
+        let alerted = self
+            .thresholds
+            .as_mut()
+            .is_some_and(|thresholds| thresholds.update(i64::from(value)));
+        changed || alerted
+    }
+
+    /// Returns the trend of a change from the current value to `value`.
+    fn trend_to(&self, value: u8) -> Trend {
+        match value.cmp(&self.value) {
+            core::cmp::Ordering::Greater => Trend::Up,
+            core::cmp::Ordering::Less => Trend::Down,
+            core::cmp::Ordering::Equal => Trend::Steady,
+        }
+    }
+
+    /// Returns the value shown.
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// Returns the direction of the last change.
+    pub fn trend(&self) -> Trend {
+        self.trend
+    }
+
+    /// Draws the readout on the top 8 rows, clearing the framebuffer first.
+    ///
+    /// Alarm rules apply to every matrix device, see [`Thresholds`].
+    pub fn draw<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        matrix.clear_buffer();
+        if let Some(thresholds) = &self.thresholds {
+            let devices = matrix.matrix_devices();
+            if !thresholds.apply(matrix.driver(), &devices)? {
+                if matrix.update_mode() == UpdateMode::WriteThrough {
+                    matrix.flush()?;
+                }
+                return Ok(());
+            }
+        }
+
+        let arrow = match self.trend {
+            Trend::Up => ARROW_UP,
+            Trend::Down => ARROW_DOWN,
+            Trend::Steady => ARROW_STEADY,
+        };
+        let width = matrix.width();
+        let arrow_x = width.saturating_sub(8);
+        matrix.draw_glyph(arrow_x as i32, 0, &arrow)?;
+
+        let mut text = [0u8; 4];
+        let len = percent_text(self.value, &mut text);
+        let fit = TextFit::new(len, arrow_x, Align::Right);
+        for (cell, &ch) in text.iter().take(fit.chars).enumerate() {
+            let bitmap = STANDARD_LED_FONT.get_char(char::from(ch));
+            let x = fit.x.saturating_add(cell.saturating_mul(8));
+            matrix.draw_glyph(x as i32, 0, &bitmap)?;
+        }
+
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `value` followed by `%` as ASCII and returns the length.
+fn percent_text(value: u8, out: &mut [u8; 4]) -> usize {
+    let digits = [100, 10, 1]
+        .into_iter()
+        .filter(|&divisor| value >= divisor || divisor == 1)
+        .map(|divisor| b'0'.saturating_add(value.checked_div(divisor).unwrap_or(0) % 10));
+    let mut len: usize = 0;
+    for (slot, ch) in out.iter_mut().zip(digits.chain([b'%'])) {
+        *slot = ch;
+        len = len.saturating_add(1);
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, led_matrix::display::Matrix4};
+    use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+    #[test]
+    fn test_trend() {
+        let mut percent = Percent::new();
+        percent.set(50);
+        assert_eq!(percent.trend(), Trend::Up);
+        percent.set(50);
+        assert_eq!(percent.trend(), Trend::Steady);
+        percent.set(49);
+        assert_eq!(percent.trend(), Trend::Down);
+        assert_eq!(percent.value(), 49);
+    }
+
+    #[test]
+    fn test_animate_to() {
+        let mut battery = Percent::new();
+        battery.set(80);
+        battery.animate_to(40, 1000);
+        assert_eq!(battery.trend(), Trend::Down);
+        assert!(battery.tick(500));
+        assert_eq!(battery.value(), 60);
+        assert!(battery.tick(500));
+        assert_eq!(battery.value(), 40);
+        assert!(!battery.is_counting());
+        assert!(!battery.tick(10));
+    }
+
+    #[test]
+    fn test_thresholds_blink_below_limit() {
+        use crate::{
+            Brightness,
+            threshold::{AlertAction, Threshold},
+        };
+
+        let thresholds = Thresholds::new(Brightness::MAX)
+            .with_rule(Threshold::below(15, AlertAction::Blink { period_ms: 1000 }))
+            .unwrap();
+        let mut battery = Percent::new().with_thresholds(thresholds);
+        assert_eq!(
+            battery.thresholds().map(Thresholds::is_blinking),
+            Some(true)
+        );
+        battery.set(50);
+        assert!(!battery.tick(600));
+        battery.set(10);
+        assert!(!battery.tick(499));
+        assert!(battery.tick(1));
+        assert_eq!(
+            battery.thresholds().map(Thresholds::is_visible),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_percent_text() {
+        let mut out = [0u8; 4];
+        let len = percent_text(7, &mut out);
+        assert_eq!(&out[..len], b"7%");
+        let len = percent_text(100, &mut out);
+        assert_eq!(&out[..len], b"100%");
+    }
+
+    #[test]
+    fn test_draw_layout() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        let mut percent = Percent::new();
+        percent.set(42);
+        percent.draw(&mut matrix).unwrap();
+
+        let glyph_at = |matrix: &Matrix4<_>, x0: usize| {
+            let mut rows = [0u8; 8];
+            for (y, row) in rows.iter_mut().enumerate() {
+                for bit in 0..8 {
+                    if matrix.get_pixel(x0 + bit, y).unwrap() {
+                        *row |= 0x80 >> bit;
+                    }
+                }
+            }
+            rows
+        };
+        assert_eq!(glyph_at(&matrix, 0), STANDARD_LED_FONT.get_char('4'));
+        assert_eq!(glyph_at(&matrix, 8), STANDARD_LED_FONT.get_char('2'));
+        assert_eq!(glyph_at(&matrix, 16), STANDARD_LED_FONT.get_char('%'));
+        assert_eq!(glyph_at(&matrix, 24), ARROW_UP);
+        spi.done();
+    }
+}
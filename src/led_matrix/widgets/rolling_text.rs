@@ -0,0 +1,314 @@
+//! Text fields whose changed characters roll vertically, split-flap style
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Result,
+    led_matrix::{LedMatrix, UpdateMode, fonts::LedFont},
+};
+
+/// Direction a changed character rolls in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RollDirection {
+    /// The old character leaves at the top, the new one enters from below
+    #[default]
+    Up,
+    /// The old character leaves at the bottom, the new one enters from above
+    Down,
+}
+
+/// One character position with its roll animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    ch: char,
+    /// Character rolling out, if the animation is running
+    previous: Option<char>,
+    /// Time since the roll started
+    elapsed_ms: u32,
+}
+
+impl Cell {
+    /// Rows the character has moved in a roll of `roll_ms`, 0 to 8
+    fn offset(&self, roll_ms: u32) -> usize {
+        match self
+            .elapsed_ms
+            .checked_mul(8)
+            .and_then(|e| e.checked_div(roll_ms))
+        {
+            Some(offset) => (offset as usize).min(8),
+            None => 8,
+        }
+    }
+}
+
+/// A field of `N` 8x8 characters at a fixed position, where each character
+/// that changes rolls out while its replacement rolls in.
+///
+/// A clock built from one field per unit can give each its own look, e.g.
+/// only the seconds rolling. The animation is driven by
+/// [`RollingText::tick`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut hours: RollingText<'_, 2> = RollingText::new(&STANDARD_LED_FONT).with_roll_ms(0);
+/// let mut seconds: RollingText<'_, 2> = RollingText::new(&STANDARD_LED_FONT)
+///     .with_position(16, 0)
+///     .with_direction(RollDirection::Down);
+/// loop {
+///     let now = rtc.now();
+///     hours.set_text(&now.hours);
+///     seconds.set_text(&now.seconds);
+///     if hours.tick(10) | seconds.tick(10) {
+///         hours.draw(&mut matrix)?;
+///         seconds.draw(&mut matrix)?;
+///     }
+///     delay.delay_ms(10);
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct RollingText<'a, const N: usize> {
+    cells: [Cell; N],
+    font: &'a LedFont,
+    x: i32,
+    y: i32,
+    roll_ms: u32,
+    direction: RollDirection,
+}
+
+impl<'a, const N: usize> RollingText<'a, N> {
+    /// Create a blank field at the top-left corner, rolling up in 250 ms
+    pub const fn new(font: &'a LedFont) -> Self {
+        Self {
+            cells: [Cell {
+                ch: ' ',
+                previous: None,
+                elapsed_ms: 0,
+            }; N],
+            font,
+            x: 0,
+            y: 0,
+            roll_ms: 250,
+            direction: RollDirection::Up,
+        }
+    }
+
+    /// Places the top-left corner of the field at (`x`, `y`)
+    pub const fn with_position(mut self, x: i32, y: i32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Sets how long a character takes to roll to its new value; 0
+    /// switches instantly
+    pub const fn with_roll_ms(mut self, roll_ms: u32) -> Self {
+        self.roll_ms = roll_ms;
+        self
+    }
+
+    /// Sets the direction characters roll in
+    pub const fn with_direction(mut self, direction: RollDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns the width of the field in pixels.
+    pub const fn width(&self) -> usize {
+        N.saturating_mul(8)
+    }
+
+    /// Returns the characters shown once any roll has finished.
+    pub fn chars(&self) -> [char; N] {
+        self.cells.map(|cell| cell.ch)
+    }
+
+    /// Shows the first `N` characters of `text`, padded with spaces, and
+    /// rolls the ones that change.
+    ///
+    /// A roll that is still running restarts from the character currently
+    /// on its way in. Rolls of characters that did not change carry on.
+    pub fn set_text(&mut self, text: &str) {
+        let mut chars = text.chars();
+        for cell in self.cells.iter_mut() {
+            let ch = chars.next().unwrap_or(' ');
+            if cell.ch != ch {
+                cell.previous = (self.roll_ms > 0).then_some(cell.ch);
+                cell.ch = ch;
+                cell.elapsed_ms = 0;
+            }
+        }
+    }
+
+    /// Returns `true` while characters are rolling.
+    pub fn is_rolling(&self) -> bool {
+        self.cells.iter().any(|cell| cell.previous.is_some())
+    }
+
+    /// Advances the roll animation by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the characters moved and need to be drawn again.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        let mut moved = false;
+        for cell in self.cells.iter_mut().filter(|cell| cell.previous.is_some()) {
+            let before = cell.offset(self.roll_ms);
+            cell.elapsed_ms = cell.elapsed_ms.saturating_add(dt_ms);
+            if cell.elapsed_ms >= self.roll_ms {
+                cell.previous = None;
+                moved = true;
+            } else if cell.offset(self.roll_ms) != before {
+                moved = true;
+            }
+        }
+        moved
+    }
+
+    /// Returns the rows of a character position in the current frame.
+    fn rows(&self, cell: &Cell) -> [u8; 8] {
+        let next = self.font.get_char(cell.ch);
+        let Some(previous) = cell.previous else {
+            return next;
+        };
+        let previous = self.font.get_char(previous);
+        let offset = cell.offset(self.roll_ms);
+        core::array::from_fn(|row| {
+            let (leaving, entering) = match self.direction {
+                RollDirection::Up => {
+                    let source = row.saturating_add(offset);
+                    (Some(source), source.checked_sub(8))
+                }
+                RollDirection::Down => (
+                    row.checked_sub(offset),
+                    row.saturating_add(8).checked_sub(offset),
+                ),
+            };
+            leaving
+                .and_then(|source| previous.get(source))
+                .or_else(|| entering.and_then(|source| next.get(source)))
+                .copied()
+                .unwrap_or(0)
+        })
+    }
+
+    /// Draws the field into the framebuffer, leaving the rest of the
+    /// display as it is so several fields can share it.
+    ///
+    /// Parts outside the display are clipped.
+    pub fn draw<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        for (index, cell) in self.cells.iter().enumerate() {
+            let x = self.x.saturating_add(index.saturating_mul(8) as i32);
+            matrix.draw_glyph(x, self.y, &self.rows(cell))?;
+        }
+
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led_matrix::fonts::STANDARD_LED_FONT;
+
+    #[test]
+    fn test_set_text_pads_and_rolls_changes() {
+        let mut field: RollingText<'_, 3> = RollingText::new(&STANDARD_LED_FONT);
+        field.set_text("12");
+        assert_eq!(field.chars(), ['1', '2', ' ']);
+        assert!(field.is_rolling());
+        assert!(field.tick(250));
+        assert!(!field.is_rolling());
+
+        field.set_text("13");
+        assert_eq!(
+            field.cells.map(|cell| cell.previous),
+            [None, Some('2'), None]
+        );
+
+        let mut instant: RollingText<'_, 1> = RollingText::new(&STANDARD_LED_FONT).with_roll_ms(0);
+        instant.set_text("7");
+        assert!(!instant.is_rolling());
+        assert!(!instant.tick(10));
+    }
+
+    #[test]
+    fn test_roll_directions() {
+        let zero = STANDARD_LED_FONT.get_char('0');
+        let one = STANDARD_LED_FONT.get_char('1');
+        let mut up: RollingText<'_, 1> = RollingText::new(&STANDARD_LED_FONT).with_roll_ms(80);
+        up.set_text("0");
+        up.tick(80);
+        let mut down = up.with_direction(RollDirection::Down);
+
+        // 3 of 8 rows after 30 ms
+        up.set_text("1");
+        assert!(up.tick(30));
+        let rows = up.rows(&up.cells[0]);
+        assert_eq!(rows[..5], zero[3..]);
+        assert_eq!(rows[5..], one[..3]);
+        assert!(!up.tick(1));
+
+        down.set_text("1");
+        down.tick(30);
+        let rows = down.rows(&down.cells[0]);
+        assert_eq!(rows[..3], one[5..]);
+        assert_eq!(rows[3..], zero[..5]);
+
+        assert!(down.tick(50));
+        assert_eq!(down.rows(&down.cells[0]), one);
+    }
+
+    #[test]
+    fn test_cells_roll_independently() {
+        let mut field: RollingText<'_, 2> = RollingText::new(&STANDARD_LED_FONT).with_roll_ms(80);
+        field.set_text("00");
+        field.tick(80);
+
+        field.set_text("10");
+        assert!(field.tick(40));
+        // The second cell starting its roll leaves the first one half way
+        field.set_text("11");
+        assert_eq!(field.cells.map(|cell| cell.elapsed_ms), [40, 0]);
+        assert!(field.tick(40));
+        assert_eq!(field.cells.map(|cell| cell.previous), [None, Some('0')]);
+        assert!(field.tick(40));
+        assert!(!field.is_rolling());
+    }
+
+    #[test]
+    fn test_draw_keeps_rest_of_display() {
+        use crate::led_matrix::display::Matrix4;
+        use crate::{Max7219, led_matrix::UpdateMode};
+        use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(4).unwrap();
+        let mut matrix = Matrix4::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        matrix.set_pixel(0, 0, true).unwrap();
+
+        let mut field: RollingText<'_, 1> = RollingText::new(&STANDARD_LED_FONT)
+            .with_position(24, 0)
+            .with_roll_ms(0);
+        field.set_text("8");
+        field.draw(&mut matrix).unwrap();
+
+        assert!(matrix.get_pixel(0, 0).unwrap());
+        let eight = STANDARD_LED_FONT.get_char('8');
+        for (y, row) in eight.iter().enumerate() {
+            for x in 0..8 {
+                assert_eq!(matrix.get_pixel(24 + x, y).unwrap(), row & (0x80 >> x) != 0);
+            }
+        }
+        spi.done();
+    }
+}
@@ -0,0 +1,596 @@
+//! Rotating playlist of screens
+//!
+//! A [`ScreenRotation`] shows each registered [`Screen`] for its dwell time,
+//! then moves on to the next, e.g. clock → date → temperature → message.
+//! Everything is driven by [`ScreenRotation::tick`], so the main loop only
+//! has to report the elapsed time.
+//!
+//! An alert screen raised with [`ScreenRotation::raise_alert`] preempts the
+//! rotation, optionally flashing, and the rotation resumes once it expires or
+//! is cleared.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, Result,
+    led_matrix::{LedMatrix, UpdateMode},
+};
+
+use super::{AnalogClock, BigDigits, Percent};
+
+/// Something that can fill the display, such as a widget.
+///
+/// Closures taking the display are screens too, which is the easiest way to
+/// show text or combine widgets.
+pub trait Screen<M: ?Sized> {
+    /// Draws the screen. The display is not cleared beforehand.
+    fn draw(&mut self, target: &mut M) -> Result<()>;
+
+    /// Advances the screen's own animation by `dt_ms` milliseconds while it
+    /// is shown. Returns `true` if it needs to be drawn again.
+    fn tick(&mut self, _dt_ms: u32) -> bool {
+        false
+    }
+}
+
+impl<M: ?Sized, F> Screen<M> for F
+where
+    F: FnMut(&mut M) -> Result<()>,
+{
+    fn draw(&mut self, target: &mut M) -> Result<()> {
+        self(target)
+    }
+}
+
+/// Makes a widget with a matrix `draw` method usable as a [`Screen`],
+/// forwarding its `tick` method too for widgets listed `with tick`
+macro_rules! impl_widget_screen {
+    ($($widget:ident $(<const $n:ident: usize>)? $(with $tick:ident)?),* $(,)?) => {
+        $(
+            impl<
+                SPI,
+                const BUFFER_LENGTH: usize,
+                const DEVICE_COUNT: usize,
+                const LAYERS: usize,
+                $(const $n: usize,)?
+            > Screen<LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>> for $widget$(<$n>)?
+            where
+                SPI: SpiDevice,
+            {
+                fn draw(
+                    &mut self,
+                    target: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+                ) -> Result<()> {
+                    Self::draw(self, target)
+                }
+
+                $(
+                    fn $tick(&mut self, dt_ms: u32) -> bool {
+                        Self::$tick(self, dt_ms)
+                    }
+                )?
+            }
+        )*
+    };
+}
+
+impl_widget_screen!(
+    AnalogClock,
+    Percent with tick,
+    BigDigits<const N: usize> with tick,
+);
+
+/// How the rotation moves from one screen to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transition {
+    /// Draw the next screen right away
+    #[default]
+    Cut,
+    /// Blank the display for `gap_ms` milliseconds first
+    Blank {
+        /// Length of the blank gap in milliseconds
+        gap_ms: u32,
+    },
+}
+
+/// Settings of an alert that preempts the rotation.
+///
+/// Higher priorities win: an alert only replaces an active alert of the
+/// same or lower priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Alert {
+    priority: u8,
+    duration_ms: u32,
+    flash_ms: u32,
+}
+
+impl Alert {
+    /// Create a steady alert with the given priority that stays until it is
+    /// cleared
+    pub const fn new(priority: u8) -> Self {
+        Self {
+            priority,
+            duration_ms: 0,
+            flash_ms: 0,
+        }
+    }
+
+    /// Ends the alert after `duration_ms` milliseconds; 0 keeps it until
+    /// [`ScreenRotation::clear_alert`] is called.
+    pub const fn with_duration(mut self, duration_ms: u32) -> Self {
+        self.duration_ms = duration_ms;
+        self
+    }
+
+    /// Flashes the alert, toggling it on and off every `flash_ms`
+    /// milliseconds; 0 shows it steadily.
+    pub const fn with_flash(mut self, flash_ms: u32) -> Self {
+        self.flash_ms = flash_ms;
+        self
+    }
+
+    /// Returns the priority.
+    pub const fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Returns the duration in milliseconds, 0 if unlimited.
+    pub const fn duration_ms(&self) -> u32 {
+        self.duration_ms
+    }
+
+    /// Returns the flash half-period in milliseconds, 0 if steady.
+    pub const fn flash_ms(&self) -> u32 {
+        self.flash_ms
+    }
+
+    /// Whether the alert is lit `elapsed_ms` after it was raised
+    fn visible_at(&self, elapsed_ms: u32) -> bool {
+        elapsed_ms
+            .checked_div(self.flash_ms)
+            .is_none_or(|half_periods| half_periods.is_multiple_of(2))
+    }
+}
+
+/// An alert on the display
+struct ActiveAlert<'a, M: ?Sized> {
+    screen: &'a mut dyn Screen<M>,
+    alert: Alert,
+    elapsed_ms: u32,
+    /// Whether the alert is lit, `None` before it was first drawn
+    visible: Option<bool>,
+}
+
+/// A screen with its dwell time and the transition that leads away from it
+struct Entry<'a, M: ?Sized> {
+    screen: &'a mut dyn Screen<M>,
+    dwell_ms: u32,
+    transition: Transition,
+}
+
+/// What the rotation is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// The current screen has not been drawn yet
+    Pending,
+    /// The current screen is shown
+    Showing,
+    /// Blank gap before the next screen
+    Gap,
+}
+
+/// Cycles through up to `N` screens, each shown for its own dwell time.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut clock = AnalogClock::new();
+/// let mut humidity = Percent::new();
+/// let mut hello = |m: &mut Matrix4<_>| m.show_text_fit("HI", Align::Center);
+///
+/// let mut rotation: ScreenRotation<_, 3> = ScreenRotation::new();
+/// rotation.add(&mut clock, 10_000)?;
+/// rotation.add_with_transition(&mut humidity, 5_000, Transition::Blank { gap_ms: 200 })?;
+/// rotation.add(&mut hello, 3_000)?;
+///
+/// loop {
+///     rotation.tick(&mut matrix, 10)?;
+///     delay.delay_ms(10);
+/// }
+/// ```
+pub struct ScreenRotation<'a, M: ?Sized, const N: usize> {
+    entries: [Option<Entry<'a, M>>; N],
+    current: usize,
+    elapsed_ms: u32,
+    phase: Phase,
+    alert: Option<ActiveAlert<'a, M>>,
+}
+
+impl<'a, M: ?Sized, const N: usize> Default for ScreenRotation<'a, M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, M: ?Sized, const N: usize> ScreenRotation<'a, M, N> {
+    /// Create an empty rotation
+    pub const fn new() -> Self {
+        Self {
+            entries: [const { None }; N],
+            current: 0,
+            elapsed_ms: 0,
+            phase: Phase::Pending,
+            alert: None,
+        }
+    }
+
+    /// Adds a screen shown for `dwell_ms` milliseconds, followed by a cut.
+    ///
+    /// A dwell time of 0 keeps the screen until [`Self::next`] is called.
+    /// Returns the index of the screen.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if all `N` slots are taken.
+    pub fn add(&mut self, screen: &'a mut dyn Screen<M>, dwell_ms: u32) -> Result<usize> {
+        self.add_with_transition(screen, dwell_ms, Transition::Cut)
+    }
+
+    /// Adds a screen shown for `dwell_ms` milliseconds, followed by the
+    /// given transition. Returns the index of the screen.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if all `N` slots are taken.
+    pub fn add_with_transition(
+        &mut self,
+        screen: &'a mut dyn Screen<M>,
+        dwell_ms: u32,
+        transition: Transition,
+    ) -> Result<usize> {
+        let (index, slot) = self
+            .entries
+            .iter_mut()
+            .enumerate()
+            .find(|(_, entry)| entry.is_none())
+            .ok_or(Error::BufferError)?;
+        *slot = Some(Entry {
+            screen,
+            dwell_ms,
+            transition,
+        });
+        Ok(index)
+    }
+
+    /// Returns the number of screens.
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.is_some()).count()
+    }
+
+    /// Returns `true` if no screens were added.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the index of the screen being shown, or about to be shown.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Preempts the rotation with an alert screen, replacing any active
+    /// alert of the same or lower priority.
+    ///
+    /// The alert is drawn on the next tick. When it ends, the interrupted
+    /// screen is drawn again with a fresh dwell time. Returns `false`, leaving
+    /// everything untouched, if a higher-priority alert is active.
+    pub fn raise_alert(&mut self, screen: &'a mut dyn Screen<M>, alert: Alert) -> bool {
+        if self
+            .alert
+            .as_ref()
+            .is_some_and(|active| active.alert.priority > alert.priority)
+        {
+            return false;
+        }
+        self.alert = Some(ActiveAlert {
+            screen,
+            alert,
+            elapsed_ms: 0,
+            visible: None,
+        });
+        true
+    }
+
+    /// Ends the active alert; the rotation is drawn again on the next tick.
+    pub fn clear_alert(&mut self) {
+        if self.alert.take().is_some() {
+            self.phase = Phase::Pending;
+        }
+    }
+
+    /// Returns the active alert, if any.
+    pub fn alert(&self) -> Option<Alert> {
+        self.alert.as_ref().map(|active| active.alert)
+    }
+
+    /// Index of the screen after `index`, wrapping around
+    fn following(&self, index: usize) -> usize {
+        let count = self.len().max(1);
+        index.saturating_add(1).checked_rem(count).unwrap_or(0)
+    }
+}
+
+impl<
+    'a,
+    SPI,
+    const BUFFER_LENGTH: usize,
+    const DEVICE_COUNT: usize,
+    const LAYERS: usize,
+    const N: usize,
+> ScreenRotation<'a, LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>, N>
+where
+    SPI: SpiDevice,
+{
+    /// Advances the rotation by `dt_ms` milliseconds, drawing whenever the
+    /// display has to change.
+    ///
+    /// While an alert is active the rotation is paused and only the alert is
+    /// drawn. In [`UpdateMode::Buffered`] the matrix is flushed after drawing.
+    /// Returns the index of the screen if a new one was put on the display.
+    pub fn tick(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+        dt_ms: u32,
+    ) -> Result<Option<usize>> {
+        if self.alert.is_some() {
+            self.tick_alert(matrix, dt_ms)?;
+            if self.alert.is_some() {
+                return Ok(None);
+            }
+        }
+
+        let Some(entry) = self.entries.get_mut(self.current).and_then(Option::as_mut) else {
+            if self.phase == Phase::Pending {
+                // Nothing to show, don't leave an ended alert behind
+                self.phase = Phase::Showing;
+                matrix.clear_screen()?;
+            }
+            return Ok(None);
+        };
+
+        match self.phase {
+            Phase::Pending => {
+                self.show_current(matrix)?;
+                return Ok(Some(self.current));
+            }
+            Phase::Showing => {
+                self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+                let redraw = entry.screen.tick(dt_ms);
+                if entry.dwell_ms == 0 || self.elapsed_ms < entry.dwell_ms {
+                    if redraw {
+                        self.draw_current(matrix)?;
+                    }
+                    return Ok(None);
+                }
+                if let Transition::Blank { gap_ms } = entry.transition
+                    && gap_ms > 0
+                {
+                    self.phase = Phase::Gap;
+                    self.elapsed_ms = 0;
+                    matrix.clear_screen()?;
+                    return Ok(None);
+                }
+            }
+            Phase::Gap => {
+                self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+                let gap_ms = match entry.transition {
+                    Transition::Blank { gap_ms } => gap_ms,
+                    Transition::Cut => 0,
+                };
+                if self.elapsed_ms < gap_ms {
+                    return Ok(None);
+                }
+            }
+        }
+
+        self.next(matrix).map(Some)
+    }
+
+    /// Draws, flashes and expires the active alert
+    fn tick_alert(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+        dt_ms: u32,
+    ) -> Result<()> {
+        let Some(active) = self.alert.as_mut() else {
+            return Ok(());
+        };
+
+        let redraw = match active.visible {
+            None => true,
+            Some(visible) => {
+                active.elapsed_ms = active.elapsed_ms.saturating_add(dt_ms);
+                let duration_ms = active.alert.duration_ms;
+                if duration_ms > 0 && active.elapsed_ms >= duration_ms {
+                    self.clear_alert();
+                    return Ok(());
+                }
+                let animated = active.screen.tick(dt_ms);
+                visible != active.alert.visible_at(active.elapsed_ms) || (visible && animated)
+            }
+        };
+        if !redraw {
+            return Ok(());
+        }
+
+        let visible = active.alert.visible_at(active.elapsed_ms);
+        active.visible = Some(visible);
+        if !visible {
+            return matrix.clear_screen();
+        }
+        active.screen.draw(matrix)?;
+        if matrix.update_mode() == UpdateMode::Buffered {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Switches to the next screen right away and draws it.
+    ///
+    /// Returns the index of the new screen.
+    pub fn next(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<usize> {
+        self.current = self.following(self.current);
+        self.show_current(matrix)?;
+        Ok(self.current)
+    }
+
+    /// Restarts the dwell time of the current screen and draws it.
+    fn show_current(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()> {
+        self.phase = Phase::Showing;
+        self.elapsed_ms = 0;
+        self.draw_current(matrix)
+    }
+
+    fn draw_current(
+        &mut self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()> {
+        if let Some(entry) = self.entries.get_mut(self.current).and_then(Option::as_mut) {
+            entry.screen.draw(matrix)?;
+            if matrix.update_mode() == UpdateMode::Buffered {
+                matrix.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, led_matrix::display::SingleMatrix};
+    use core::cell::Cell;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction};
+
+    type Matrix<'s> = SingleMatrix<&'s mut SpiMock<u8>>;
+
+    /// Expected traffic of one flush of a single device
+    fn flush_rows(rows: [u8; 8]) -> Vec<Transaction<u8>> {
+        let mut expected = Vec::new();
+        for (digit, row) in rows.iter().enumerate() {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![digit as u8 + 1, *row]));
+            expected.push(Transaction::transaction_end());
+        }
+        expected
+    }
+
+    #[test]
+    fn test_rotation_cycles_with_dwell_times() {
+        let mut expected = flush_rows([0xFF; 8]); // A
+        expected.extend(flush_rows([0; 8])); // B
+        expected.extend(flush_rows([0xFF; 8])); // A again
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        let mut a = |m: &mut Matrix| m.fill_region(0, 0, 8, 8, true);
+        let mut b = |m: &mut Matrix| {
+            m.clear_buffer();
+            Ok(())
+        };
+        let mut extra = |_: &mut Matrix| Ok(());
+        let mut rotation: ScreenRotation<Matrix, 2> = ScreenRotation::new();
+        rotation.add(&mut a, 100).unwrap();
+        rotation.add(&mut b, 50).unwrap();
+        assert!(rotation.add(&mut extra, 1).is_err());
+
+        assert_eq!(rotation.tick(&mut matrix, 0).unwrap(), Some(0));
+        assert_eq!(rotation.tick(&mut matrix, 99).unwrap(), None);
+        assert_eq!(rotation.tick(&mut matrix, 1).unwrap(), Some(1));
+        assert_eq!(rotation.tick(&mut matrix, 50).unwrap(), Some(0));
+        spi.done();
+    }
+
+    #[test]
+    fn test_blank_transition_and_redraw() {
+        struct Blinker<'c>(&'c Cell<u32>);
+        impl Screen<Matrix<'_>> for Blinker<'_> {
+            fn draw(&mut self, _: &mut Matrix<'_>) -> Result<()> {
+                self.0.set(self.0.get() + 1);
+                Ok(())
+            }
+            fn tick(&mut self, _dt_ms: u32) -> bool {
+                true
+            }
+        }
+
+        let mut expected = Vec::new();
+        for _ in 0..3 {
+            expected.extend(flush_rows([0; 8]));
+        }
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        let draws = Cell::new(0);
+        let mut blinker = Blinker(&draws);
+        let mut rotation: ScreenRotation<Matrix, 1> = ScreenRotation::new();
+        rotation
+            .add_with_transition(&mut blinker, 20, Transition::Blank { gap_ms: 30 })
+            .unwrap();
+
+        rotation.tick(&mut matrix, 0).unwrap(); // draw + flush
+        rotation.tick(&mut matrix, 10).unwrap(); // redraw + flush
+        assert_eq!(draws.get(), 2);
+        rotation.tick(&mut matrix, 10).unwrap(); // dwell over: blank
+        assert_eq!(rotation.tick(&mut matrix, 29).unwrap(), None);
+        assert_eq!(draws.get(), 2);
+        spi.done();
+    }
+
+    #[test]
+    fn test_alert_flashes_and_resumes_rotation() {
+        let mut expected = flush_rows([0; 8]); // clock
+        expected.extend(flush_rows([0xFF; 8])); // alert on
+        expected.extend(flush_rows([0; 8])); // alert off
+        expected.extend(flush_rows([0xFF; 8])); // alert on
+        expected.extend(flush_rows([0; 8])); // clock again
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut matrix = SingleMatrix::from_driver(driver)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+
+        let mut clock = |m: &mut Matrix| {
+            m.clear_buffer();
+            Ok(())
+        };
+        let mut alarm = |m: &mut Matrix| m.fill_region(0, 0, 8, 8, true);
+        let mut minor = |_: &mut Matrix| Ok(());
+        let mut rotation: ScreenRotation<Matrix, 1> = ScreenRotation::new();
+        rotation.add(&mut clock, 1_000).unwrap();
+
+        assert_eq!(rotation.tick(&mut matrix, 0).unwrap(), Some(0));
+        assert!(rotation.raise_alert(&mut alarm, Alert::new(5).with_duration(300).with_flash(100)));
+        assert!(!rotation.raise_alert(&mut minor, Alert::new(1)));
+        assert_eq!(rotation.alert().map(|alert| alert.priority()), Some(5));
+
+        assert_eq!(rotation.tick(&mut matrix, 0).unwrap(), None); // on
+        assert_eq!(rotation.tick(&mut matrix, 50).unwrap(), None);
+        assert_eq!(rotation.tick(&mut matrix, 50).unwrap(), None); // off
+        assert_eq!(rotation.tick(&mut matrix, 100).unwrap(), None); // on
+        assert_eq!(rotation.tick(&mut matrix, 100).unwrap(), Some(0)); // expired
+        assert!(rotation.alert().is_none());
+        spi.done();
+    }
+}
@@ -0,0 +1,203 @@
+//! 7-segment style digits drawn on matrix modules
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, Result,
+    led_matrix::{LedMatrix, UpdateMode},
+    seven_segment::{Font, NumberFormat, STANDARD_FONT, Segment, display::render_text},
+};
+
+/// Pixels of each segment in an 8x8 cell as `(segment, row, mask)`.
+///
+/// Vertical segments are two pixels tall and horizontal ones three wide,
+/// leaving the rightmost column for the decimal point and the bottom row as
+/// spacing between stacked rows of modules.
+const SEGMENT_PIXELS: [(Segment, usize, u8); 12] = [
+    (Segment::A, 0, 0b0011_1000),
+    (Segment::F, 1, 0b0100_0000),
+    (Segment::B, 1, 0b0000_0100),
+    (Segment::F, 2, 0b0100_0000),
+    (Segment::B, 2, 0b0000_0100),
+    (Segment::G, 3, 0b0011_1000),
+    (Segment::E, 4, 0b0100_0000),
+    (Segment::C, 4, 0b0000_0100),
+    (Segment::E, 5, 0b0100_0000),
+    (Segment::C, 5, 0b0000_0100),
+    (Segment::D, 6, 0b0011_1000),
+    (Segment::DP, 6, 0b0000_0001),
+];
+
+/// Converts a segment pattern (DP A B C D E F G) into an 8x8 bitmap.
+pub fn segment_glyph(pattern: u8) -> [u8; 8] {
+    let mut glyph = [0u8; 8];
+    for (segment, row, mask) in SEGMENT_PIXELS {
+        if segment.is_lit(pattern)
+            && let Some(row) = glyph.get_mut(row)
+        {
+            *row |= mask;
+        }
+    }
+    glyph
+}
+
+/// `N` 7-segment style digits, one per 8x8 module.
+///
+/// Lets matrix hardware double as a numeric display with the familiar look,
+/// using the same [`NumberFormat`] and [`Font`] as
+/// [`SevenSegment`](crate::seven_segment::SevenSegment).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut digits: SegmentDigits<4> = SegmentDigits::new();
+/// digits.set_f32(21.5, NumberFormat::new().with_decimals(1))?; // "21.5"
+/// digits.draw(&mut matrix)?;
+/// ```
+#[derive(Clone, Copy)]
+pub struct SegmentDigits<const N: usize> {
+    segments: [u8; N],
+    font: Font,
+}
+
+impl<const N: usize> Default for SegmentDigits<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> SegmentDigits<N> {
+    /// Create blank digits using the standard 7-segment font
+    pub const fn new() -> Self {
+        Self {
+            segments: [0; N],
+            font: STANDARD_FONT,
+        }
+    }
+
+    /// Sets the font used to turn characters into segments
+    pub const fn with_font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Returns the segment patterns, leftmost digit first.
+    pub fn segments(&self) -> &[u8; N] {
+        &self.segments
+    }
+
+    /// Sets the segment pattern of one digit directly.
+    ///
+    /// Returns `Error::InvalidDigit` if `index` is out of range.
+    pub fn set_segments(&mut self, index: usize, pattern: u8) -> Result<()> {
+        *self.segments.get_mut(index).ok_or(Error::InvalidDigit)? = pattern;
+        Ok(())
+    }
+
+    /// Clears all digits.
+    pub fn clear(&mut self) {
+        self.segments = [0; N];
+    }
+
+    /// Shows `value` right-aligned, blanking digits left of the field.
+    ///
+    /// Returns `Error::InvalidDigit` if the number does not fit; the digits
+    /// are left unchanged.
+    pub fn set_u64(&mut self, value: u64, format: NumberFormat) -> Result<()> {
+        let width = format.width().unwrap_or(N);
+        let start = N.checked_sub(width).ok_or(Error::InvalidDigit)?;
+        let mut segments = [0u8; N];
+        format.render(value, &self.font, &mut segments[start..])?;
+        self.segments = segments;
+        Ok(())
+    }
+
+    /// Shows a float right-aligned, blanking digits left of the field.
+    ///
+    /// Returns `Error::InvalidDigit` if the value is not finite or does not
+    /// fit; the digits are left unchanged.
+    pub fn set_f32(&mut self, value: f32, format: NumberFormat) -> Result<()> {
+        let width = format.width().unwrap_or(N);
+        let start = N.checked_sub(width).ok_or(Error::InvalidDigit)?;
+        let mut segments = [0u8; N];
+        format.render_f32(value, &self.font, &mut segments[start..])?;
+        self.segments = segments;
+        Ok(())
+    }
+
+    /// Shows `text` left-aligned; a `'.'` lights the decimal point of the
+    /// digit before it.
+    ///
+    /// Returns `Error::InvalidDigit` if the text is too long; the digits are
+    /// left unchanged.
+    pub fn set_text(&mut self, text: &str) -> Result<()> {
+        let mut segments = [0u8; N];
+        render_text(text, &self.font, &mut segments)?;
+        self.segments = segments;
+        Ok(())
+    }
+
+    /// Draws the digits from the left edge of the top 8 rows, clearing the
+    /// framebuffer first.
+    pub fn draw<SPI, const BUFFER_LENGTH: usize, const DEVICE_COUNT: usize, const LAYERS: usize>(
+        &self,
+        matrix: &mut LedMatrix<SPI, BUFFER_LENGTH, DEVICE_COUNT, LAYERS>,
+    ) -> Result<()>
+    where
+        SPI: SpiDevice,
+    {
+        matrix.clear_buffer();
+
+        for (index, &pattern) in self.segments.iter().enumerate() {
+            matrix.draw_glyph(index.saturating_mul(8) as i32, 0, &segment_glyph(pattern))?;
+        }
+
+        if matrix.update_mode() == UpdateMode::WriteThrough {
+            matrix.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segments;
+
+    #[test]
+    fn test_segment_glyph() {
+        assert_eq!(segment_glyph(0), [0; 8]);
+        assert_eq!(
+            segment_glyph(segments!(A | G | D)),
+            [0x38, 0, 0, 0x38, 0, 0, 0x38, 0]
+        );
+        assert_eq!(
+            segment_glyph(segments!(B | C | DP)),
+            [0, 0x04, 0x04, 0, 0x04, 0x04, 0x01, 0]
+        );
+    }
+
+    #[test]
+    fn test_set_values() {
+        let one = STANDARD_FONT.get_char('1');
+        let five = STANDARD_FONT.get_char('5');
+        let mut digits: SegmentDigits<3> = SegmentDigits::new();
+
+        digits.set_u64(15, NumberFormat::new()).unwrap();
+        assert_eq!(digits.segments(), &[0, one, five]);
+
+        digits
+            .set_f32(1.5, NumberFormat::new().with_decimals(1))
+            .unwrap();
+        assert_eq!(digits.segments(), &[0, one | 0x80, five]);
+
+        assert_eq!(
+            digits.set_u64(1234, NumberFormat::new()),
+            Err(Error::InvalidDigit)
+        );
+        assert_eq!(digits.segments(), &[0, one | 0x80, five]);
+
+        digits.set_text("51").unwrap();
+        assert_eq!(digits.segments(), &[five, one, 0]);
+    }
+}
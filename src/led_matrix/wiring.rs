@@ -0,0 +1,187 @@
+//! Wiring description for 8x8 LED matrix modules
+//!
+//! Not every module connects the MAX7219 the same way. FC-16 style modules
+//! drive one row per digit register, while many "generic" modules drive one
+//! column per digit register, sometimes with the bit order reversed. A
+//! [`Wiring`] describes that mapping so the framebuffer can be converted into
+//! register data without any transposition code on the user side.
+
+/// Which matrix line each digit register (DIG0 to DIG7) drives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    /// Each digit register drives one row, each bit one column (FC-16 modules).
+    #[default]
+    RowMajor,
+    /// Each digit register drives one column, each bit one row.
+    ColumnMajor,
+}
+
+/// Describes how one MAX7219 device is wired to its 8x8 LED matrix.
+///
+/// The default wiring matches FC-16 modules: DIG0 is the top row and bit 7
+/// is the leftmost column.
+///
+/// | Orientation   | DIG0        | Bit 7          |
+/// |---------------|-------------|----------------|
+/// | `RowMajor`    | top row     | leftmost column|
+/// | `ColumnMajor` | left column | top row        |
+///
+/// `reverse_digits` and `reverse_bits` flip the respective order, which covers
+/// modules that are mounted upside down or mirrored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Wiring {
+    /// Whether digit registers drive rows or columns
+    pub orientation: Orientation,
+    /// DIG0 drives the bottom row (row-major) or rightmost column (column-major)
+    pub reverse_digits: bool,
+    /// Bit 0 drives the leftmost column (row-major) or top row (column-major)
+    pub reverse_bits: bool,
+}
+
+impl Wiring {
+    /// Wiring used by FC-16 modules (the default)
+    pub const FC16: Self = Self::new(Orientation::RowMajor, false, false);
+
+    /// Wiring used by common generic modules that drive one column per digit
+    pub const GENERIC: Self = Self::new(Orientation::ColumnMajor, false, false);
+
+    /// Create a new wiring description
+    pub const fn new(orientation: Orientation, reverse_digits: bool, reverse_bits: bool) -> Self {
+        Self {
+            orientation,
+            reverse_digits,
+            reverse_bits,
+        }
+    }
+
+    /// Returns the wiring of the same module rotated by 180 degrees.
+    pub const fn rotated(self) -> Self {
+        Self::new(self.orientation, !self.reverse_digits, !self.reverse_bits)
+    }
+
+    /// Returns the `(digit, bit)` pair that drives the LED at (`col`, `row`).
+    ///
+    /// Both coordinates must be in the range 0 to 7, with (0, 0) the top-left
+    /// LED of the module.
+    pub const fn locate(&self, col: u8, row: u8) -> (u8, u8) {
+        let (digit, bit) = match self.orientation {
+            Orientation::RowMajor => (row, 7u8.saturating_sub(col)),
+            Orientation::ColumnMajor => (col, 7u8.saturating_sub(row)),
+        };
+        let digit = if self.reverse_digits {
+            7u8.saturating_sub(digit)
+        } else {
+            digit
+        };
+        let bit = if self.reverse_bits {
+            7u8.saturating_sub(bit)
+        } else {
+            bit
+        };
+        (digit, bit)
+    }
+
+    /// Converts eight logical rows (bit 7 = leftmost pixel) into the data
+    /// bytes for DIG0 to DIG7.
+    ///
+    /// Being a `const fn`, it can convert frames stored in flash at compile
+    /// time for a chain with a fixed wiring.
+    pub const fn apply(&self, rows: &[u8; 8]) -> [u8; 8] {
+        if matches!(self.orientation, Orientation::RowMajor)
+            && !self.reverse_digits
+            && !self.reverse_bits
+        {
+            return *rows;
+        }
+
+        let mut digits = [0; 8];
+        let mut rest: &[u8] = rows;
+        let mut row: u8 = 0;
+        while let [data, tail @ ..] = rest {
+            let mut col: u8 = 0;
+            while col < 8 {
+                if *data & (0x80 >> col) != 0 {
+                    let (digit, bit) = self.locate(col, row);
+                    if let Some((_, [digit, ..])) = digits.split_at_mut_checked(digit as usize) {
+                        *digit |= 1 << bit;
+                    }
+                }
+                col = col.saturating_add(1);
+            }
+            rest = tail;
+            row = row.saturating_add(1);
+        }
+        digits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_fc16() {
+        assert_eq!(Wiring::default(), Wiring::FC16);
+    }
+
+    #[test]
+    fn test_locate_row_major() {
+        let wiring = Wiring::FC16;
+        assert_eq!(wiring.locate(0, 0), (0, 7));
+        assert_eq!(wiring.locate(7, 0), (0, 0));
+        assert_eq!(wiring.locate(2, 5), (5, 5));
+    }
+
+    #[test]
+    fn test_rotated() {
+        let wiring = Wiring::FC16.rotated();
+        assert_eq!(wiring.locate(0, 0), (7, 0));
+        assert_eq!(wiring.locate(7, 7), (0, 7));
+        assert_eq!(wiring.rotated(), Wiring::FC16);
+    }
+
+    #[test]
+    fn test_locate_column_major() {
+        let wiring = Wiring::GENERIC;
+        assert_eq!(wiring.locate(0, 0), (0, 7));
+        assert_eq!(wiring.locate(7, 0), (7, 7));
+        assert_eq!(wiring.locate(2, 5), (2, 2));
+    }
+
+    #[test]
+    fn test_locate_reversed() {
+        let wiring = Wiring::new(Orientation::RowMajor, true, true);
+        assert_eq!(wiring.locate(0, 0), (7, 0));
+        assert_eq!(wiring.locate(7, 7), (0, 7));
+    }
+
+    #[test]
+    fn test_apply_fc16_is_identity() {
+        let rows = [0x81, 0x42, 0x24, 0x18, 0x18, 0x24, 0x42, 0x81];
+        assert_eq!(Wiring::FC16.apply(&rows), rows);
+    }
+
+    #[test]
+    fn test_apply_column_major_transposes() {
+        // Only the top row is lit
+        let rows = [0xFF, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(Wiring::GENERIC.apply(&rows), [0b1000_0000; 8]);
+
+        // Only the leftmost column is lit
+        let rows = [0b1000_0000; 8];
+        assert_eq!(Wiring::GENERIC.apply(&rows), [0xFF, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_apply_const() {
+        const WIRED: [u8; 8] = Wiring::GENERIC.apply(&[0xFF, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(WIRED, [0b1000_0000; 8]);
+    }
+
+    #[test]
+    fn test_apply_reverse_bits_mirrors_rows() {
+        let wiring = Wiring::new(Orientation::RowMajor, false, true);
+        let rows = [0b1100_0000, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(wiring.apply(&rows), [0b0000_0011, 0, 0, 0, 0, 0, 0, 0]);
+    }
+}
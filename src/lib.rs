@@ -2,16 +2,45 @@
 #![cfg_attr(not(test), no_std)]
 #![deny(unsafe_code)]
 #![warn(missing_docs)]
+#![cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable,
+        clippy::todo,
+        clippy::unimplemented,
+        clippy::arithmetic_side_effects
+    )
+)]
 #![cfg_attr(not(doctest), doc = include_str!("../README.md"))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
+
+pub mod bar;
+pub mod chain;
+pub mod colon;
 pub mod driver;
+pub mod easing;
 pub mod error;
 pub mod registers;
+pub mod sink;
+pub mod threshold;
+pub mod time;
 
 // Re-exports
+pub use bar::BarDevice;
+pub use colon::Colon;
 pub use driver::Max7219;
-pub use error::Error;
-pub use registers::{DecodeMode, Register};
+pub use error::{CharPolicy, Error};
+pub use registers::{Brightness, DecodeMode, Filler, Register};
+pub use sink::{FrameRecorder, FrameSink, SinkError, SinkSpi};
 
 // Additional Feature specific modules and re-exports
 #[cfg(feature = "led-matrix")]
@@ -26,6 +55,45 @@ pub use seven_segment::SevenSegment;
 #[cfg(feature = "led-matrix")]
 pub use led_matrix::LedMatrix;
 
+#[cfg(all(feature = "led-matrix", feature = "seven-segment"))]
+pub mod marquee;
+
+#[cfg(all(feature = "led-matrix", feature = "seven-segment"))]
+pub use marquee::MixedMarquee;
+
+#[cfg(feature = "heapless")]
+pub mod message;
+
+#[cfg(feature = "heapless")]
+pub use message::Message;
+
+#[cfg(feature = "heapless")]
+pub mod template;
+
+#[cfg(feature = "heapless")]
+pub use template::Template;
+
+#[cfg(feature = "alloc")]
+pub mod queue;
+
+#[cfg(feature = "alloc")]
+pub use queue::MessageQueue;
+
+#[cfg(feature = "critical-section")]
+pub mod shared;
+
+#[cfg(feature = "critical-section")]
+pub use shared::StaticDisplay;
+
+#[cfg(feature = "std")]
+pub mod server;
+
+#[cfg(feature = "std")]
+pub use server::FrameServer;
+
+#[cfg(feature = "build")]
+pub mod build;
+
 /// Maximum number of daisy-chained displays supported
 pub const MAX_DISPLAYS: usize = 8;
 
@@ -37,3 +105,101 @@ pub const NUM_DIGITS: u8 = 8;
 /// This alias simplifies function signatures by defaulting the error type
 /// to the crate's custom [`Error`] enum.
 pub(crate) type Result<T> = core::result::Result<T, crate::error::Error>;
+
+/// Link-time check that the driver can't panic, in the spirit of the
+/// `no_panic` crate: a call that can unwind keeps a drop guard referencing
+/// an undefined symbol, so the test binary fails to link. Only meaningful
+/// with optimizations and a single codegen unit, so run it with
+/// `CARGO_PROFILE_RELEASE_CODEGEN_UNITS=1 cargo test --release --features panic-free,led-matrix,seven-segment`.
+///
+/// Inputs go through [`core::hint::black_box`] so the optimizer can't prove
+/// the calls succeed and drop the error paths the check is about.
+#[cfg(all(test, feature = "panic-free", not(debug_assertions)))]
+mod panic_free {
+    use core::{convert::Infallible, hint::black_box};
+
+    use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+
+    use crate::{Brightness, Max7219, Register};
+
+    struct PanicGuard;
+
+    impl Drop for PanicGuard {
+        #[allow(unsafe_code)]
+        fn drop(&mut self) {
+            unsafe extern "C" {
+                /// Never defined: referencing it fails the link
+                fn max7219_display_code_path_may_panic() -> !;
+            }
+            unsafe { max7219_display_code_path_may_panic() }
+        }
+    }
+
+    /// Runs `f`, failing the link if it can panic.
+    #[inline(always)]
+    fn no_panic<R>(f: impl FnOnce() -> R) -> R {
+        let guard = PanicGuard;
+        let result = f();
+        core::mem::forget(guard);
+        result
+    }
+
+    /// SPI device that accepts every transaction
+    struct NullSpi;
+
+    impl ErrorType for NullSpi {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for NullSpi {
+        fn transaction(&mut self, _operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_driver_cannot_panic() {
+        let mut driver = Max7219::new(NullSpi);
+        let result = no_panic(|| {
+            driver.init()?;
+            driver.set_intensity_all(black_box(Brightness::MAX))?;
+            driver.write_raw_digit(black_box(0), black_box(3), black_box(0x5A))?;
+            driver.set_led(black_box(0), black_box(0), black_box(0), true)?;
+            driver.write_register_range(black_box(0..1), Register::DisplayTest, 0)?;
+            driver.clear_all()
+        });
+        assert!(result.is_ok());
+
+        // Out of range input is an error, not a panic
+        let result = no_panic(|| driver.set_led(black_box(0), black_box(8), black_box(0), true));
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "led-matrix")]
+    #[test]
+    fn test_led_matrix_cannot_panic() {
+        use crate::led_matrix::display::Matrix4;
+
+        let result = no_panic(|| {
+            let driver = Max7219::new(NullSpi).with_device_count(4)?;
+            let mut matrix = Matrix4::from_driver(driver)?;
+            matrix.set_pixel(black_box(31), black_box(7), true)?;
+            matrix.flush()?;
+            matrix.set_pixel(black_box(32), black_box(0), true)
+        });
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "seven-segment")]
+    #[test]
+    fn test_seven_segment_cannot_panic() {
+        use crate::seven_segment::{display::SevenSegment, fonts::STANDARD_FONT};
+
+        let mut display = SevenSegment::new(Max7219::new(NullSpi));
+        let result = no_panic(|| {
+            display.write_str(black_box("12.34"), &STANDARD_FONT)?;
+            display.write_str(black_box("too long text"), &STANDARD_FONT)
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! Scrolling text across chains mixing matrices and 7-segment modules
+//!
+//! A [`MixedMarquee`] treats a hybrid front panel as one long display. The
+//! text moves a pixel column at a time; on matrix modules it is drawn with
+//! an 8x8 font, and on 7-segment modules every digit shows the character
+//! passing over it. A character is as wide as a matrix module, so each
+//! 7-segment digit spans eight columns and the text keeps one speed along
+//! the whole chain.
+//!
+//! As with [`LedMatrix`](crate::LedMatrix) and
+//! [`SevenSegment`](crate::SevenSegment), the device nearest the
+//! microcontroller is on the left. Bar graph devices are left dark and take
+//! no columns, and modules mounted upside down are drawn rotated.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Max7219, NUM_DIGITS, Register, Result,
+    chain::{ChainLayout, DeviceKind, Mounting},
+    led_matrix::{fonts::LedFont, wiring::Wiring},
+    seven_segment::{
+        Font,
+        fonts::{DP, rotate_segments},
+    },
+    time::Progress,
+};
+
+/// Columns taken by one character, and by each 7-segment digit
+const CELL: usize = 8;
+
+/// Text scrolling from right to left over a chain described by a
+/// [`ChainLayout`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // 7-segment module furthest from the MCU, then two matrices
+/// let layout = ChainLayout::new(&[
+///     DeviceKind::SevenSegment,
+///     DeviceKind::Matrix,
+///     DeviceKind::Matrix,
+/// ])?;
+/// let mut marquee = MixedMarquee::new("HELLO 42", layout, &STANDARD_LED_FONT, &STANDARD_FONT)
+///     .with_step_ms(40);
+/// loop {
+///     if marquee.tick(10) {
+///         marquee.draw(&mut driver)?;
+///     }
+///     delay.delay_ms(10);
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct MixedMarquee<'a> {
+    text: &'a str,
+    layout: ChainLayout,
+    matrix_font: &'a LedFont,
+    segment_font: &'a Font,
+    wiring: Wiring,
+    looping: bool,
+    gap: usize,
+    step_ms: u32,
+    elapsed_ms: u32,
+    offset: usize,
+}
+
+impl<'a> MixedMarquee<'a> {
+    /// Create a looping marquee with a gap of two characters, moving one
+    /// column every 50 ms
+    pub fn new(
+        text: &'a str,
+        layout: ChainLayout,
+        matrix_font: &'a LedFont,
+        segment_font: &'a Font,
+    ) -> Self {
+        Self {
+            text,
+            layout,
+            matrix_font,
+            segment_font,
+            wiring: Wiring::FC16,
+            looping: true,
+            gap: 2,
+            step_ms: 50,
+            elapsed_ms: 0,
+            offset: 0,
+        }
+    }
+
+    /// Sets the wiring of the matrix modules
+    pub const fn with_wiring(mut self, wiring: Wiring) -> Self {
+        self.wiring = wiring;
+        self
+    }
+
+    /// Whether the text repeats once it has passed
+    pub const fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Sets the number of blank characters between repetitions
+    pub const fn with_gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Sets the time between one-column steps in milliseconds
+    pub const fn with_step_ms(mut self, step_ms: u32) -> Self {
+        self.step_ms = step_ms;
+        self
+    }
+
+    /// Returns the layout of the chain.
+    pub fn layout(&self) -> &ChainLayout {
+        &self.layout
+    }
+
+    /// Returns the width of the chain in columns.
+    pub fn width(&self) -> usize {
+        self.layout
+            .kinds()
+            .iter()
+            .map(|kind| match kind {
+                DeviceKind::Matrix => CELL,
+                DeviceKind::SevenSegment => CELL * usize::from(NUM_DIGITS),
+                DeviceKind::BarGraph => 0,
+            })
+            .sum()
+    }
+
+    /// Width of the text in columns
+    fn text_width(&self) -> usize {
+        self.text.chars().count().saturating_mul(CELL)
+    }
+
+    /// Columns after which looping text starts over
+    fn period(&self) -> usize {
+        self.text_width()
+            .saturating_add(self.gap.saturating_mul(CELL))
+    }
+
+    /// Returns the number of steps taken since the text started entering.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Moves the text back outside the right edge.
+    pub fn reset(&mut self) {
+        self.offset = 0;
+        self.elapsed_ms = 0;
+    }
+
+    /// Returns `true` once non-looping text has left the chain.
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.offset >= self.text_width().saturating_add(self.width())
+    }
+
+    /// Moves the text one column to the left and reports when a pass is
+    /// complete.
+    ///
+    /// Returns [`Progress::Complete`] when non-looping text has left the
+    /// chain, or each time looping text starts over, and
+    /// [`Progress::Idle`] after a non-looping pass until [`Self::reset`].
+    pub fn advance(&mut self) -> Progress {
+        if self.is_finished() {
+            return Progress::Idle;
+        }
+        self.offset = self.offset.saturating_add(1);
+        let period = self.period();
+        if self.looping && period > 0 && self.offset >= period.saturating_add(self.width()) {
+            self.offset = self.offset.saturating_sub(period);
+            return Progress::Complete;
+        }
+        if self.is_finished() {
+            Progress::Complete
+        } else {
+            Progress::Running
+        }
+    }
+
+    /// Advances the scroll by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the text moved and needs to be drawn again.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        if self.step_ms == 0 || self.is_finished() {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+        let mut moved = false;
+        while self.elapsed_ms >= self.step_ms && !self.is_finished() {
+            self.elapsed_ms = self.elapsed_ms.saturating_sub(self.step_ms);
+            self.advance();
+            moved = true;
+        }
+        moved
+    }
+
+    /// Returns the character and the column within it shown at chain
+    /// column `x`, if any.
+    fn text_at(&self, x: usize) -> Option<(char, usize)> {
+        let column = x.checked_add(self.offset)?.checked_sub(self.width())?;
+        let period = self.period();
+        let column = if self.looping {
+            column.checked_rem(period).unwrap_or(column)
+        } else {
+            column
+        };
+        let ch = self.text.chars().nth(column / CELL)?;
+        Some((ch, column % CELL))
+    }
+
+    /// Returns the register data of every device for the current position,
+    /// indexed like the driver.
+    pub fn frame(&self) -> [[u8; NUM_DIGITS as usize]; crate::MAX_DISPLAYS] {
+        let mut images = [[0u8; NUM_DIGITS as usize]; crate::MAX_DISPLAYS];
+        let mut x: usize = 0;
+        let devices = images.iter_mut().zip(self.layout.kinds());
+        for (device_index, (image, kind)) in devices.enumerate().rev() {
+            let upside_down = self.layout.mounting(device_index) == Ok(Mounting::UpsideDown);
+            match kind {
+                DeviceKind::Matrix => {
+                    let mut rows = [0u8; 8];
+                    for col in 0..CELL {
+                        let Some((ch, bit)) = self.text_at(x.saturating_add(col)) else {
+                            continue;
+                        };
+                        let glyph = self.matrix_font.get_char(ch);
+                        for (data, line) in rows.iter_mut().zip(glyph) {
+                            if line & (0x80 >> bit) != 0 {
+                                *data |= 0x80 >> col;
+                            }
+                        }
+                    }
+                    let wiring = if upside_down {
+                        self.wiring.rotated()
+                    } else {
+                        self.wiring
+                    };
+                    *image = wiring.apply(&rows);
+                    x = x.saturating_add(CELL);
+                }
+                DeviceKind::SevenSegment => {
+                    for digit in image.iter_mut() {
+                        // The character covering the middle of the digit
+                        if let Some((ch, _)) = self.text_at(x.saturating_add(CELL / 2)) {
+                            *digit = if ch == '.' {
+                                DP
+                            } else {
+                                self.segment_font.get_char(ch)
+                            };
+                        }
+                        x = x.saturating_add(CELL);
+                    }
+                    if upside_down {
+                        image.reverse();
+                        for digit in image.iter_mut() {
+                            *digit = rotate_segments(*digit);
+                        }
+                    }
+                }
+                DeviceKind::BarGraph => {}
+            }
+        }
+        images
+    }
+
+    /// Draws the current position, sending only the digit registers that
+    /// changed.
+    ///
+    /// 7-segment devices are switched to raw segment mode if needed, and bar
+    /// graph devices are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceCount` if the driver's chain length
+    /// differs from the layout.
+    pub fn draw<SPI: SpiDevice>(&self, driver: &mut Max7219<SPI>) -> Result<()> {
+        let count = self.layout.len();
+        if driver.device_count() != count {
+            return Err(crate::Error::InvalidDeviceCount);
+        }
+        for (device_index, kind) in self.layout.kinds().iter().enumerate() {
+            if *kind == DeviceKind::SevenSegment
+                && driver.device_state(device_index)?.decode_mode() != 0
+            {
+                driver.set_device_decode_mask(device_index, 0)?;
+            }
+        }
+
+        let images = self.frame();
+        for (digit, register) in Register::digits().enumerate() {
+            let mut ops = [(Register::NoOp, 0); crate::MAX_DISPLAYS];
+            let mut changed = false;
+            let devices = ops.iter_mut().zip(self.layout.kinds()).zip(&images);
+            for (device_index, ((op, kind), image)) in devices.enumerate() {
+                if *kind == DeviceKind::BarGraph {
+                    continue;
+                }
+                let data = image.get(digit).copied().unwrap_or(0);
+                changed |= driver.device_state(device_index)?.register_value(register) != data;
+                *op = (register, data);
+            }
+            if changed {
+                driver.write_all_registers(&ops)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{led_matrix::fonts::STANDARD_LED_FONT, seven_segment::STANDARD_FONT};
+
+    fn marquee(text: &str) -> MixedMarquee<'_> {
+        // Matrix nearest the MCU on the left, 7-segment module on the right
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix]).unwrap();
+        MixedMarquee::new(text, layout, &STANDARD_LED_FONT, &STANDARD_FONT).with_looping(false)
+    }
+
+    #[test]
+    fn test_enters_on_seven_segment_side() {
+        let mut marquee = marquee("1");
+        assert_eq!(marquee.width(), 72);
+        for _ in 0..3 {
+            marquee.advance();
+        }
+        assert_eq!(marquee.frame()[0], [0; 8]);
+        marquee.advance();
+        // Half of the character covers the last digit
+        assert_eq!(marquee.frame()[0][7], STANDARD_FONT.get_char('1'));
+        assert_eq!(marquee.frame()[1], [0; 8]);
+    }
+
+    #[test]
+    fn test_moves_onto_matrix() {
+        let mut marquee = marquee("1");
+        // The character's first column reaches the matrix's right edge
+        for _ in 0..65 {
+            marquee.advance();
+        }
+        let glyph = STANDARD_LED_FONT.get_char('1');
+        let frame = marquee.frame();
+        for row in 0..8 {
+            assert_eq!(frame[1][row], (glyph[row] & 0x80) >> 7);
+        }
+        assert_eq!(frame[0][0], STANDARD_FONT.get_char('1'));
+
+        for _ in 0..14 {
+            marquee.advance();
+        }
+        assert_eq!(marquee.advance(), Progress::Complete);
+        assert!(marquee.is_finished());
+        assert_eq!(marquee.frame()[1], [0; 8]);
+    }
+
+    #[test]
+    fn test_draw_sends_changed_rows() {
+        use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+        let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Digit7.addr(),
+                STANDARD_FONT.get_char('1'),
+                Register::Digit7.addr(),
+                0x00,
+            ]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut marquee = marquee("1");
+        for _ in 0..4 {
+            marquee.advance();
+        }
+        marquee.draw(&mut driver).unwrap();
+        marquee.draw(&mut driver).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_draw_rejects_other_chain() {
+        use embedded_hal_mock::eh1::spi::Mock as SpiMock;
+
+        let mut spi = SpiMock::new(&[]);
+        let mut driver = Max7219::new(&mut spi);
+        assert_eq!(
+            marquee("1").draw(&mut driver),
+            Err(crate::Error::InvalidDeviceCount)
+        );
+        spi.done();
+    }
+}
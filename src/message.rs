@@ -0,0 +1,139 @@
+//! Fixed-capacity message buffers
+//!
+//! Every text API in this crate takes a `&str`, so static messages need no
+//! buffer at all. For text built at runtime, such as sensor readings or
+//! prices, [`Message`] formats into a [`heapless::String`] whose capacity
+//! `N` (in bytes) is fixed at compile time. It lives on the stack or in a
+//! `static`, so no allocator is needed and the RAM cost is known up front.
+
+use core::fmt::{self, Write};
+use core::ops::Deref;
+
+use crate::{Error, Result};
+
+/// Text of at most `N` bytes, built without allocating.
+///
+/// # Example
+///
+/// ```rust
+/// use core::fmt::Write;
+/// use max7219_display::Message;
+///
+/// let mut message: Message<16> = Message::new();
+/// write!(message, "T={}C", 21).unwrap();
+/// assert_eq!(message.as_str(), "T=21C");
+///
+/// let price = Message::<16>::from_fmt(format_args!("${}.{:02}", 4, 99)).unwrap();
+/// assert_eq!(&*price, "$4.99");
+/// // matrix.scroll_text(&mut delay, &price, config)?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Message<const N: usize> {
+    text: heapless::String<N>,
+}
+
+impl<const N: usize> Message<N> {
+    /// Create an empty message
+    pub const fn new() -> Self {
+        Self {
+            text: heapless::String::new(),
+        }
+    }
+
+    /// Create a message from formatting arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the text is longer than `N` bytes.
+    pub fn from_fmt(args: fmt::Arguments<'_>) -> Result<Self> {
+        let mut message = Self::new();
+        message.write_fmt(args).map_err(|_| Error::BufferError)?;
+        Ok(message)
+    }
+
+    /// Appends a string slice.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the message would exceed `N` bytes.
+    /// The message is left unchanged in that case.
+    pub fn push_str(&mut self, text: &str) -> Result<()> {
+        self.text.push_str(text).map_err(|_| Error::BufferError)
+    }
+
+    /// Appends a single character.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the message would exceed `N` bytes.
+    pub fn push(&mut self, ch: char) -> Result<()> {
+        self.text.push(ch).map_err(|_| Error::BufferError)
+    }
+
+    /// Removes all text, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.text.clear();
+    }
+
+    /// Returns the text as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// Returns the maximum length of the message in bytes.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize> Deref for Message<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> Write for Message<N> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.push_str(text).map_err(|_| fmt::Error)
+    }
+}
+
+impl<const N: usize> fmt::Display for Message<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_message() {
+        let mut message: Message<8> = Message::new();
+        message.push_str("AB").unwrap();
+        message.push('C').unwrap();
+        write!(message, "{}", 12).unwrap();
+        assert_eq!(message.as_str(), "ABC12");
+        assert_eq!(message.len(), 5);
+        assert_eq!(message.capacity(), 8);
+
+        message.clear();
+        assert!(message.is_empty());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut message: Message<4> = Message::new();
+        message.push_str("ABC").unwrap();
+        assert_eq!(message.push_str("DE"), Err(Error::BufferError));
+        assert_eq!(message.as_str(), "ABC");
+
+        assert_eq!(
+            Message::<4>::from_fmt(format_args!("{}", 123_456)),
+            Err(Error::BufferError)
+        );
+    }
+}
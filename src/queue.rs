@@ -0,0 +1,173 @@
+//! Heap-backed message queue
+//!
+//! On targets with an allocator, [`MessageQueue`] holds any number of
+//! messages of any length, e.g. news headlines or notifications waiting to
+//! be scrolled across the display.
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// First-in, first-out queue of owned messages.
+///
+/// # Example
+///
+/// ```rust
+/// use max7219_display::MessageQueue;
+///
+/// let mut queue = MessageQueue::new();
+/// queue.push("Next train 12:05");
+/// queue.push_urgent("Platform change!");
+/// assert_eq!(queue.current(), Some("Platform change!"));
+///
+/// queue.advance();
+/// assert_eq!(queue.current(), Some("Next train 12:05"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MessageQueue {
+    messages: VecDeque<String>,
+    repeat: bool,
+    separator: Option<String>,
+}
+
+impl MessageQueue {
+    /// Create an empty queue that drops messages once shown
+    pub const fn new() -> Self {
+        Self {
+            messages: VecDeque::new(),
+            repeat: false,
+            separator: None,
+        }
+    }
+
+    /// Keep shown messages by moving them to the back of the queue, so the
+    /// queue cycles through its messages forever.
+    pub fn with_repeat(mut self, repeat: bool) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Scroll the messages as one continuous ticker line with `separator`
+    /// between them, e.g. `" • "` or three spaces, instead of blanking the
+    /// display after each message.
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = Some(separator.into());
+        self
+    }
+
+    /// Returns `true` if shown messages are kept.
+    pub fn is_repeating(&self) -> bool {
+        self.repeat
+    }
+
+    /// Returns the ticker separator, if set.
+    pub fn separator(&self) -> Option<&str> {
+        self.separator.as_deref()
+    }
+
+    /// Joins the messages into one ticker line, with the separator between
+    /// them.
+    ///
+    /// A repeating queue also ends with the separator, so it sits between
+    /// the last and the first message when the line wraps around.
+    pub fn ticker_text(&self) -> String {
+        let separator = self.separator().unwrap_or_default();
+        let mut text = self.iter().collect::<Vec<_>>().join(separator);
+        if self.repeat && !self.is_empty() {
+            text.push_str(separator);
+        }
+        text
+    }
+
+    /// Adds a message to the back of the queue.
+    pub fn push(&mut self, message: impl Into<String>) {
+        self.messages.push_back(message.into());
+    }
+
+    /// Adds a message to the front of the queue so it is shown next.
+    pub fn push_urgent(&mut self, message: impl Into<String>) {
+        self.messages.push_front(message.into());
+    }
+
+    /// Returns the message at the front of the queue.
+    pub fn current(&self) -> Option<&str> {
+        self.messages.front().map(String::as_str)
+    }
+
+    /// Moves on to the next message.
+    ///
+    /// Returns the message that was at the front. With repeat enabled it
+    /// is moved to the back of the queue and `None` is returned.
+    pub fn advance(&mut self) -> Option<String> {
+        let message = self.messages.pop_front()?;
+        if self.repeat {
+            self.messages.push_back(message);
+            None
+        } else {
+            Some(message)
+        }
+    }
+
+    /// Returns the number of queued messages.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Returns `true` if there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Removes all messages.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    /// Iterates over the messages from front to back.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.messages.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fifo_order() {
+        let mut queue = MessageQueue::new();
+        queue.push("one");
+        queue.push(String::from("two"));
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(queue.advance().as_deref(), Some("one"));
+        assert_eq!(queue.current(), Some("two"));
+        assert_eq!(queue.advance().as_deref(), Some("two"));
+        assert!(queue.is_empty());
+        assert_eq!(queue.advance(), None);
+    }
+
+    #[test]
+    fn test_repeat_cycles() {
+        let mut queue = MessageQueue::new().with_repeat(true);
+        queue.push("one");
+        queue.push("two");
+        queue.advance();
+        queue.advance();
+        assert_eq!(queue.iter().collect::<Vec<_>>(), ["one", "two"]);
+    }
+
+    #[test]
+    fn test_ticker_text() {
+        let mut queue = MessageQueue::new().with_separator(" • ");
+        assert_eq!(queue.ticker_text(), "");
+        queue.push("one");
+        queue.push("two");
+        assert_eq!(queue.ticker_text(), "one • two");
+
+        let mut queue = queue.with_repeat(true);
+        assert_eq!(queue.ticker_text(), "one • two • ");
+        queue.clear();
+        assert_eq!(queue.ticker_text(), "");
+    }
+}
@@ -44,24 +44,73 @@ pub enum Register {
 }
 
 impl Register {
+    /// Every register, in address order
+    pub const ALL: [Register; 14] = [
+        Register::NoOp,
+        Register::Digit0,
+        Register::Digit1,
+        Register::Digit2,
+        Register::Digit3,
+        Register::Digit4,
+        Register::Digit5,
+        Register::Digit6,
+        Register::Digit7,
+        Register::DecodeMode,
+        Register::Intensity,
+        Register::ScanLimit,
+        Register::Shutdown,
+        Register::DisplayTest,
+    ];
+
+    /// The digit registers, Digit0 to Digit7
+    pub const DIGITS: [Register; 8] = [
+        Register::Digit0,
+        Register::Digit1,
+        Register::Digit2,
+        Register::Digit3,
+        Register::Digit4,
+        Register::Digit5,
+        Register::Digit6,
+        Register::Digit7,
+    ];
+
     /// Convert register to u8 value
     pub const fn addr(self) -> u8 {
         self as u8
     }
 
+    /// Returns the register of digit `n` (0-7), or `None` if out of range.
+    pub const fn digit(n: u8) -> Option<Self> {
+        match Self::DIGITS.as_slice().split_at_checked(n as usize) {
+            Some((_, [register, ..])) => Some(*register),
+            _ => None,
+        }
+    }
+
+    /// Returns the digit index (0-7) of a digit register, or `None` for
+    /// control registers.
+    pub const fn digit_index(self) -> Option<u8> {
+        match self {
+            Register::Digit0
+            | Register::Digit1
+            | Register::Digit2
+            | Register::Digit3
+            | Register::Digit4
+            | Register::Digit5
+            | Register::Digit6
+            | Register::Digit7 => Some(self.addr().saturating_sub(Register::Digit0.addr())),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for the digit registers Digit0 to Digit7.
+    pub const fn is_digit(self) -> bool {
+        self.digit_index().is_some()
+    }
+
     /// Try to convert a digit index (0-7) into a corresponding `Register::DigitN`.
     pub(crate) fn try_digit(digit: u8) -> Result<Self> {
-        match digit {
-            0 => Ok(Register::Digit0),
-            1 => Ok(Register::Digit1),
-            2 => Ok(Register::Digit2),
-            3 => Ok(Register::Digit3),
-            4 => Ok(Register::Digit4),
-            5 => Ok(Register::Digit5),
-            6 => Ok(Register::Digit6),
-            7 => Ok(Register::Digit7),
-            _ => Err(Error::InvalidDigit),
-        }
+        Self::digit(digit).ok_or(Error::InvalidDigit)
     }
 
     /// Returns an iterator over all digit registers (Digit0 to Digit7).
@@ -69,17 +118,54 @@ impl Register {
     /// Useful for iterating through display rows or columns when writing
     /// to all digits of a MAX7219 device in order.
     pub fn digits() -> impl Iterator<Item = Register> {
-        [
-            Register::Digit0,
-            Register::Digit1,
-            Register::Digit2,
-            Register::Digit3,
-            Register::Digit4,
-            Register::Digit5,
-            Register::Digit6,
-            Register::Digit7,
-        ]
-        .into_iter()
+        Self::DIGITS.into_iter()
+    }
+}
+
+/// Packet sent to the devices of the chain a transaction doesn't address.
+///
+/// Every transaction carries one packet per device, so writing to one device
+/// means sending something harmless to all the others. That is normally a
+/// no-op with a zero data byte, [`NOOP_PACKET`]. On long or noisy chains
+/// where a chip misreads such packets, another filler may be more robust.
+///
+/// See [`Max7219::with_filler`](crate::Max7219::with_filler).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filler {
+    /// [`NOOP_PACKET`]: the no-op register with a zero data byte
+    #[default]
+    NoOp,
+    /// The no-op register with the given data byte
+    NoOpWith(u8),
+    /// The same register as the addressed device, with the value the
+    /// driver last wrote to it on the unaddressed device.
+    ///
+    /// A corrupted packet then rewrites a register with data it already
+    /// holds instead of hitting an arbitrary one. It relies on the shadow
+    /// state matching the chips, so only switch to it after `init()`.
+    Repeat,
+}
+
+/// The packet sent to unaddressed devices by default
+pub const NOOP_PACKET: (Register, u8) = (Register::NoOp, 0x00);
+
+/// Looks up a register by its address, e.g. the upper byte of a packet.
+impl TryFrom<u8> for Register {
+    type Error = Error;
+
+    /// Returns `Error::InvalidRegister` for the unused addresses 0x0D, 0x0E
+    /// and anything above 0x0F.
+    fn try_from(addr: u8) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|register| register.addr() == addr)
+            .ok_or(Error::InvalidRegister)
+    }
+}
+
+impl From<Register> for u8 {
+    fn from(register: Register) -> u8 {
+        register.addr()
     }
 }
 
@@ -123,6 +209,115 @@ impl DecodeMode {
     }
 }
 
+/// Brightness of a device, the value of its intensity register.
+///
+/// The MAX7219 dims its LEDs with pulse-width modulation in 16 steps, from
+/// [`Brightness::MIN`] (1/32 duty cycle, dim but not off) to
+/// [`Brightness::MAX`] (31/32). Use [`Brightness::from_percent`] to work in
+/// perceived brightness instead of raw steps.
+///
+/// The intensity APIs also take a raw step, which is converted with
+/// [`TryFrom<u8>`](Brightness::try_from) and rejected past 15:
+///
+/// ```rust,ignore
+/// driver.set_intensity(0, Brightness::from_percent(40))?;
+/// driver.set_intensity(1, 8)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Brightness(u8);
+
+/// Perceived brightness in percent of each intensity step.
+///
+/// Step `n` drives the LEDs for (2n + 1)/32 of the time. The eye responds
+/// roughly to the square root of the duty cycle, so each entry is
+/// `sqrt((2n + 1) / 31)` as a percentage and the low steps look much
+/// further apart than the high ones. Even the lowest step is visibly lit.
+const PERCENT_OF_LEVEL: [u8; 16] = [
+    18, 31, 40, 48, 54, 60, 65, 70, 74, 78, 82, 86, 90, 93, 97, 100,
+];
+
+impl Brightness {
+    /// Dimmest setting
+    pub const MIN: Self = Self(0x00);
+    /// Brightest setting
+    pub const MAX: Self = Self(0x0F);
+
+    /// Creates a brightness from a raw intensity step (0-15).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidIntensity` if `level` is greater than 15.
+    pub const fn new(level: u8) -> Result<Self> {
+        if level > Self::MAX.0 {
+            return Err(Error::InvalidIntensity);
+        }
+        Ok(Self(level))
+    }
+
+    /// Creates a brightness from a raw intensity step, clamped to 15.
+    pub const fn saturating(level: u8) -> Self {
+        if level > Self::MAX.0 {
+            Self::MAX
+        } else {
+            Self(level)
+        }
+    }
+
+    /// Returns the intensity step closest to `percent` perceived brightness.
+    ///
+    /// Percentages above 100 are treated as 100.
+    pub const fn from_percent(percent: u8) -> Self {
+        let percent = if percent > 100 { 100 } else { percent };
+        let mut level = 0;
+        while level < Self::MAX.0 {
+            let current = percent_of_level(level);
+            let next = percent_of_level(level.saturating_add(1));
+            if next <= percent {
+                level = level.saturating_add(1);
+            } else {
+                if next.saturating_sub(percent) < percent.saturating_sub(current) {
+                    level = level.saturating_add(1);
+                }
+                break;
+            }
+        }
+        Self(level)
+    }
+
+    /// Returns the raw intensity step (0-15).
+    pub const fn level(self) -> u8 {
+        self.0
+    }
+
+    /// Returns the perceived brightness in percent.
+    pub const fn percent(self) -> u8 {
+        percent_of_level(self.0)
+    }
+}
+
+/// Returns the perceived brightness of an intensity step, 100 past the last
+/// step.
+const fn percent_of_level(level: u8) -> u8 {
+    match PERCENT_OF_LEVEL.as_slice().split_at_checked(level as usize) {
+        Some((_, [percent, ..])) => *percent,
+        _ => 100,
+    }
+}
+
+impl TryFrom<u8> for Brightness {
+    type Error = Error;
+
+    fn try_from(level: u8) -> Result<Self> {
+        Self::new(level)
+    }
+}
+
+impl From<Brightness> for u8 {
+    fn from(brightness: Brightness) -> u8 {
+        brightness.level()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,6 +368,49 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_try_from_u8_round_trips() {
+        for register in Register::ALL {
+            assert_eq!(Register::try_from(u8::from(register)), Ok(register));
+        }
+        assert_eq!(Register::try_from(0x0D), Err(Error::InvalidRegister));
+        assert_eq!(Register::try_from(0x0E), Err(Error::InvalidRegister));
+        assert_eq!(Register::try_from(0x10), Err(Error::InvalidRegister));
+    }
+
+    #[test]
+    fn test_digit_helpers() {
+        assert_eq!(Register::digit(3), Some(Register::Digit3));
+        assert_eq!(Register::digit(8), None);
+        assert_eq!(Register::Digit7.digit_index(), Some(7));
+        assert_eq!(Register::Intensity.digit_index(), None);
+        assert!(Register::Digit0.is_digit());
+        assert!(!Register::NoOp.is_digit());
+    }
+
+    #[test]
+    fn test_brightness_bounds() {
+        assert_eq!(Brightness::new(15), Ok(Brightness::MAX));
+        assert_eq!(Brightness::new(16), Err(Error::InvalidIntensity));
+        assert_eq!(Brightness::try_from(0), Ok(Brightness::MIN));
+        assert_eq!(Brightness::saturating(200), Brightness::MAX);
+        assert_eq!(u8::from(Brightness::saturating(7)), 7);
+    }
+
+    #[test]
+    fn test_brightness_percent_round_trips() {
+        assert_eq!(Brightness::from_percent(0), Brightness::MIN);
+        assert_eq!(Brightness::from_percent(100), Brightness::MAX);
+        assert_eq!(Brightness::from_percent(250), Brightness::MAX);
+        // Half the perceived brightness is about a quarter of the duty cycle
+        assert_eq!(Brightness::from_percent(50).level(), 3);
+        assert_eq!(Brightness::MIN.percent(), 18);
+        for level in 0..=15 {
+            let brightness = Brightness::new(level).unwrap();
+            assert_eq!(Brightness::from_percent(brightness.percent()), brightness);
+        }
+    }
+
     #[test]
     fn test_decode_mode_value() {
         assert_eq!(DecodeMode::NoDecode.value(), 0x00);
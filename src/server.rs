@@ -0,0 +1,375 @@
+//! Network display server for Linux hosts
+//!
+//! [`FrameServer`] turns a chain attached to a Raspberry Pi or another Linux
+//! board into a network display: it reads frames from TCP connections, UDP
+//! datagrams or any byte stream such as stdin and passes them to a
+//! [`FrameSink`], usually the [`Max7219`](crate::Max7219) driver itself.
+//!
+//! # Frame format
+//!
+//! A frame is the DIG0 to DIG7 data of every device, furthest device first,
+//! as received by [`FrameSink::write_rows`]: 8 bytes per device and nothing
+//! else. For a chain of four devices every frame is 32 bytes.
+//!
+//! - Streams (TCP, stdin, pipes) carry frames back to back.
+//! - Each UDP datagram carries one frame; datagrams of another length are
+//!   dropped.
+//!
+//! [`StreamSink`] writes this format, so a renderer running elsewhere can
+//! drive the display through the usual high-level API.
+//!
+//! ```rust,ignore
+//! let spi = SpidevDevice::open("/dev/spidev0.0")?;
+//! let mut driver = Max7219::new(spi).with_device_count(4)?;
+//! driver.init()?;
+//!
+//! let mut server = FrameServer::new(driver, 4)?;
+//! server.serve_tcp("0.0.0.0:7219")?;
+//! ```
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, ToSocketAddrs, UdpSocket},
+    thread,
+    time::Duration,
+};
+
+use crate::{Error, MAX_DISPLAYS, Result, sink::FrameSink};
+
+/// Failed accepts in a row after which [`FrameServer::serve_listener`]
+/// gives up
+const ACCEPT_ATTEMPTS: u32 = 8;
+
+/// Pause after the first failed accept, growing by as much after each
+/// further one
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Receives frames over the network or a byte stream and passes them to a
+/// [`FrameSink`].
+#[derive(Debug)]
+pub struct FrameServer<S> {
+    sink: S,
+    device_count: usize,
+    frames: u64,
+    /// How long a TCP connection may stall before it is dropped
+    read_timeout: Option<Duration>,
+}
+
+impl<S: FrameSink> FrameServer<S> {
+    /// Create a server for frames of `device_count` devices
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceCount` if `device_count` is 0 or more
+    /// than [`MAX_DISPLAYS`].
+    pub fn new(sink: S, device_count: usize) -> Result<Self> {
+        if device_count == 0 || device_count > MAX_DISPLAYS {
+            return Err(Error::InvalidDeviceCount);
+        }
+        Ok(Self {
+            sink,
+            device_count,
+            frames: 0,
+            read_timeout: Some(Duration::from_secs(10)),
+        })
+    }
+
+    /// Sets how long a TCP connection may go without sending data before
+    /// it is closed, 10 seconds by default; `None` waits forever.
+    ///
+    /// Connections are served one at a time, so a client that stalls in
+    /// the middle of a frame would otherwise block all others.
+    pub fn with_read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Returns the length of a frame in bytes.
+    pub fn frame_len(&self) -> usize {
+        self.device_count.saturating_mul(8)
+    }
+
+    /// Returns the number of frames passed to the sink.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns a reference to the sink.
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Returns a mutable reference to the sink.
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Consumes the server and returns the sink.
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    /// Passes one frame to the sink.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if `frame` is not [`Self::frame_len`]
+    /// bytes long, or the error of the sink.
+    pub fn push(&mut self, frame: &[u8]) -> Result<()> {
+        if frame.len() != self.frame_len() {
+            return Err(Error::BufferError);
+        }
+        let mut rows = [[0; 8]; MAX_DISPLAYS];
+        for (rows, data) in rows.iter_mut().zip(frame.chunks_exact(8)) {
+            rows.copy_from_slice(data);
+        }
+        let rows = rows.get(..self.device_count).ok_or(Error::BufferError)?;
+        self.sink.write_rows(rows)?;
+        self.frames = self.frames.saturating_add(1);
+        Ok(())
+    }
+
+    /// Reads the next frame of `reader` into `frame`.
+    ///
+    /// Returns `false` at the end of the stream.
+    fn read_frame<R: Read>(reader: &mut R, frame: &mut [u8]) -> io::Result<bool> {
+        let mut len: usize = 0;
+        while let Some(rest) = frame.get_mut(len..).filter(|rest| !rest.is_empty()) {
+            match reader.read(rest) {
+                Ok(0) if len == 0 => return Ok(false),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => len = len.saturating_add(read),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Passes the frames of a byte stream, such as stdin or a pipe, to the
+    /// sink until the stream ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns read errors, an `UnexpectedEof` error if the stream ends in
+    /// the middle of a frame, and errors of the sink.
+    pub fn serve_stream<R: Read>(&mut self, mut reader: R) -> io::Result<()> {
+        let mut frame = [0; 8 * MAX_DISPLAYS];
+        let frame = frame
+            .get_mut(..self.frame_len())
+            .ok_or(io::ErrorKind::InvalidInput)?;
+        while Self::read_frame(&mut reader, frame)? {
+            self.push(frame).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    /// Listens on `address` and passes the frames of every TCP connection
+    /// to the sink, one connection at a time. Runs until the sink or the
+    /// listener fails.
+    ///
+    /// See [`Self::serve_listener`].
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of binding the socket, of accepting connections and
+    /// of the sink.
+    pub fn serve_tcp(&mut self, address: impl ToSocketAddrs) -> io::Result<()> {
+        self.serve_listener(&TcpListener::bind(address)?)
+    }
+
+    /// Passes the frames of every connection accepted by `listener` to the
+    /// sink, one connection at a time. Runs until the sink or the listener
+    /// fails.
+    ///
+    /// A connection that breaks, ends in the middle of a frame or stalls
+    /// for longer than the [read timeout](Self::with_read_timeout) is
+    /// closed without affecting the server. A failed accept, e.g. a client
+    /// resetting before it was accepted or the process running out of file
+    /// descriptors for a moment, is retried after a pause that grows with
+    /// every failure in a row.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last accept once 8 accepts in a row have
+    /// failed, and errors of the sink.
+    pub fn serve_listener(&mut self, listener: &TcpListener) -> io::Result<()> {
+        let mut frame = [0; 8 * MAX_DISPLAYS];
+        let frame = frame
+            .get_mut(..self.frame_len())
+            .ok_or(io::ErrorKind::InvalidInput)?;
+        let mut failures: u32 = 0;
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    failures = failures.saturating_add(1);
+                    if failures >= ACCEPT_ATTEMPTS {
+                        return Err(error);
+                    }
+                    thread::sleep(ACCEPT_BACKOFF.saturating_mul(failures));
+                    continue;
+                }
+            };
+            failures = 0;
+            // Without the timeout a stalled client would block the others
+            if stream.set_read_timeout(self.read_timeout).is_err() {
+                continue;
+            }
+            while let Ok(true) = Self::read_frame(&mut stream, frame) {
+                self.push(frame).map_err(io::Error::other)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Binds a UDP socket to `address` and passes every datagram holding a
+    /// frame to the sink. Runs until the sink fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of the socket and of the sink.
+    pub fn serve_udp(&mut self, address: impl ToSocketAddrs) -> io::Result<()> {
+        let socket = UdpSocket::bind(address)?;
+        loop {
+            self.receive(&socket)?;
+        }
+    }
+
+    /// Waits for one datagram on `socket` and passes it to the sink if it
+    /// holds a frame.
+    ///
+    /// Returns `false` if the datagram was dropped for having the wrong
+    /// length.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of the socket and of the sink.
+    pub fn receive(&mut self, socket: &UdpSocket) -> io::Result<bool> {
+        // One spare byte tells oversized datagrams apart
+        let mut datagram = [0; 8 * MAX_DISPLAYS + 1];
+        let len = socket.recv(&mut datagram)?;
+        let frame = datagram.get(..len).unwrap_or_default();
+        if frame.len() != self.frame_len() {
+            return Ok(false);
+        }
+        self.push(frame).map_err(io::Error::other)?;
+        Ok(true)
+    }
+}
+
+/// A [`FrameSink`] writing frames in the format read by [`FrameServer`],
+/// e.g. to a `TcpStream` connected to a remote display.
+///
+/// ```rust,ignore
+/// let stream = TcpStream::connect("wall.local:7219")?;
+/// let spi = SinkSpi::<_, 4>::new(StreamSink::new(stream));
+/// let mut matrix = Matrix4::from_spi(spi)?;
+/// matrix.draw_text("Hi!")?;
+/// ```
+#[derive(Debug)]
+pub struct StreamSink<W> {
+    writer: W,
+}
+
+impl<W: Write> StreamSink<W> {
+    /// Create a sink writing to `writer`
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Consumes the sink and returns the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Returns `Error::SpiError` if the frame can't be written, as the stream
+/// stands in for the SPI bus.
+impl<W: Write> FrameSink for StreamSink<W> {
+    fn write_rows(&mut self, device_rows: &[[u8; 8]]) -> Result<()> {
+        self.writer
+            .write_all(device_rows.as_flattened())
+            .and_then(|()| self.writer.flush())
+            .map_err(|_| Error::SpiError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::FrameRecorder;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stream_round_trip() {
+        let mut sink = StreamSink::new(Vec::new());
+        sink.write_rows(&[[1; 8], [2; 8]]).unwrap();
+        sink.write_rows(&[[3; 8], [4; 8]]).unwrap();
+        let bytes = sink.into_inner();
+        assert_eq!(bytes.len(), 32);
+
+        let mut server = FrameServer::new(FrameRecorder::<2>::new(), 2).unwrap();
+        server.serve_stream(Cursor::new(&bytes)).unwrap();
+        assert_eq!(server.frames(), 2);
+        assert_eq!(server.sink().rows(), &[[3; 8], [4; 8]]);
+
+        let error = server.serve_stream(Cursor::new(&bytes[..20])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(server.frames(), 3);
+    }
+
+    #[test]
+    fn test_push() {
+        let mut server = FrameServer::new(FrameRecorder::<1>::new(), 1).unwrap();
+        assert_eq!(server.push(&[0xAA; 8]), Ok(()));
+        assert_eq!(server.push(&[0xAA; 9]), Err(Error::BufferError));
+        assert_eq!(server.into_sink().rows(), &[[0xAA; 8]]);
+        assert!(matches!(
+            FrameServer::new(FrameRecorder::<1>::new(), 9),
+            Err(Error::InvalidDeviceCount)
+        ));
+    }
+
+    #[test]
+    fn test_stalled_connection_times_out() {
+        use std::{net::TcpStream, thread};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stalled = TcpStream::connect(address).unwrap();
+            stalled.write_all(&[0xFF; 3]).unwrap();
+            let mut next = TcpStream::connect(address).unwrap();
+            next.write_all(&[0xAA; 8]).unwrap();
+            // Keep the stalled connection open until the server moved on
+            let mut closed = [0; 1];
+            let _ = stalled.read(&mut closed);
+        });
+
+        // The sink fails on the first frame, ending the server
+        let mut server = FrameServer::new(FrameRecorder::<2>::new(), 1)
+            .unwrap()
+            .with_read_timeout(Some(Duration::from_millis(100)));
+        let error = server.serve_listener(&listener).unwrap_err();
+        assert_eq!(
+            error.into_inner().unwrap().to_string(),
+            Error::InvalidDeviceCount.to_string()
+        );
+        client.join().unwrap();
+    }
+
+    #[test]
+    fn test_receive_udp() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(socket.local_addr().unwrap()).unwrap();
+        client.send(&[0x0F; 9]).unwrap();
+        client.send(&[0xF0; 8]).unwrap();
+
+        let mut server = FrameServer::new(FrameRecorder::<1>::new(), 1).unwrap();
+        assert!(!server.receive(&socket).unwrap());
+        assert!(server.receive(&socket).unwrap());
+        assert_eq!(server.sink().rows(), &[[0xF0; 8]]);
+    }
+}
@@ -0,0 +1,251 @@
+//! Best-effort rendering of words for 7-segment labels
+//!
+//! Menu labels and status words often contain letters a 7-segment digit
+//! cannot draw, and are longer than the display. An [`Abbreviator`] renders
+//! them consistently:
+//!
+//! - letters missing from the font are tried in the other case, then
+//!   replaced by an approximation from [`SUBSTITUTIONS`], e.g. `M` as an `n`
+//!   with a bar on top;
+//! - letters without any approximation are skipped;
+//! - words listed in a dictionary use the patterns given there;
+//! - when the text is too long, vowels inside words are dropped from the
+//!   end until it fits, so `"SENSOR"` becomes `"SNSR"`.
+
+use crate::{
+    Error, Result,
+    error::CharPolicy,
+    seven_segment::{Font, display::MAX_DIGITS, fonts::DP},
+};
+
+/// Approximations of letters the standard font lacks
+///
+/// Bit mapping: DP A B C D E F G
+pub const SUBSTITUTIONS: &[(char, u8)] = &[
+    ('G', 0b0101_1110), // G without the middle bar
+    ('I', 0b0000_0110), // left bar
+    ('J', 0b0011_1100), // J
+    ('K', 0b0101_0111), // h with a top bar
+    ('M', 0b0101_0101), // n with a top bar
+    ('N', 0b0001_0101), // n
+    ('O', 0b0001_1101), // o, telling it apart from zero
+    ('Q', 0b0111_0011), // q
+    ('R', 0b0000_0101), // r
+    ('S', 0b0101_1011), // same as 5
+    ('T', 0b0000_1111), // t
+    ('V', 0b0001_1100), // u
+    ('X', 0b0011_0111), // same as H
+    ('Y', 0b0011_1011), // y
+    ('Z', 0b0110_1101), // same as 2
+];
+
+/// Converts words to segment patterns, see the [module documentation](self).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// const LABELS: Abbreviator = Abbreviator::new(STANDARD_FONT)
+///     .with_dictionary(&[("WIFI", &[0b0011_1110, 0b0000_0110, 0b0100_0111, 0b0000_0110])]);
+///
+/// display.write_abbreviated("Sensor 3", &LABELS)?; // "SnSr 3" on 6 digits
+/// ```
+#[derive(Clone, Copy)]
+pub struct Abbreviator {
+    font: Font,
+    dictionary: &'static [(&'static str, &'static [u8])],
+    substitutions: bool,
+    unsupported: CharPolicy<u8>,
+}
+
+impl Abbreviator {
+    /// Render with `font`, substituting and skipping missing letters
+    pub const fn new(font: Font) -> Self {
+        Self {
+            font,
+            dictionary: &[],
+            substitutions: true,
+            unsupported: CharPolicy::Skip,
+        }
+    }
+
+    /// Render the listed words with fixed patterns instead.
+    ///
+    /// Words are matched ignoring ASCII case, and are never shortened.
+    pub const fn with_dictionary(
+        mut self,
+        dictionary: &'static [(&'static str, &'static [u8])],
+    ) -> Self {
+        self.dictionary = dictionary;
+        self
+    }
+
+    /// Whether to approximate missing letters with [`SUBSTITUTIONS`]
+    /// (enabled by default)
+    pub const fn with_substitutions(mut self, enabled: bool) -> Self {
+        self.substitutions = enabled;
+        self
+    }
+
+    /// Sets what happens to characters that can't be drawn at all. The
+    /// default is [`CharPolicy::Skip`].
+    pub const fn with_unsupported(mut self, policy: CharPolicy<u8>) -> Self {
+        self.unsupported = policy;
+        self
+    }
+
+    /// Returns the segment pattern for `ch`, or `None` if it is skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the character can't be drawn and
+    /// the policy is [`CharPolicy::Error`].
+    pub fn glyph(&self, ch: char) -> Result<Option<u8>> {
+        let upper = ch.to_ascii_uppercase();
+        let found = self
+            .font
+            .find(ch)
+            .or_else(|| self.font.find(upper))
+            .or_else(|| self.font.find(ch.to_ascii_lowercase()))
+            .or_else(|| {
+                SUBSTITUTIONS
+                    .iter()
+                    .filter(|_| self.substitutions)
+                    .find(|&&(letter, _)| letter == upper)
+                    .map(|&(_, segments)| segments)
+            });
+        match found {
+            Some(segments) => Ok(Some(segments)),
+            None => self.unsupported.resolve(ch),
+        }
+    }
+
+    /// Renders `text` into `out`, dropping vowels inside words if it would
+    /// not fit otherwise.
+    ///
+    /// A `'.'` lights the decimal point of the preceding digit. Returns the
+    /// number of digits written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the text doesn't fit even without
+    /// those vowels, or `Error::UnsupportedChar` as for [`Self::glyph`].
+    pub fn render(&self, text: &str, out: &mut [u8]) -> Result<usize> {
+        // Patterns with a flag telling whether they may be dropped
+        let mut glyphs = [(0u8, false); MAX_DIGITS];
+        let mut len: usize = 0;
+        for (index, word) in text.split(' ').enumerate() {
+            if index > 0 {
+                *glyphs.get_mut(len).ok_or(Error::InvalidDigit)? = (0, false);
+                len = len.saturating_add(1);
+            }
+            if let Some(&(_, patterns)) = self
+                .dictionary
+                .iter()
+                .find(|(entry, _)| entry.eq_ignore_ascii_case(word))
+            {
+                for &pattern in patterns {
+                    *glyphs.get_mut(len).ok_or(Error::InvalidDigit)? = (pattern, false);
+                    len = len.saturating_add(1);
+                }
+                continue;
+            }
+            let mut first = true;
+            for ch in word.chars() {
+                if ch == '.'
+                    && let Some(previous) = len.checked_sub(1).and_then(|last| glyphs.get_mut(last))
+                    && previous.0 & DP == 0
+                {
+                    *previous = (previous.0 | DP, false);
+                    continue;
+                }
+                let pattern = if ch == '.' {
+                    DP
+                } else {
+                    match self.glyph(ch)? {
+                        Some(pattern) => pattern,
+                        None => continue,
+                    }
+                };
+                let vowel = matches!(ch.to_ascii_uppercase(), 'A' | 'E' | 'I' | 'O' | 'U');
+                *glyphs.get_mut(len).ok_or(Error::InvalidDigit)? = (pattern, vowel && !first);
+                len = len.saturating_add(1);
+                first = false;
+            }
+        }
+
+        // Drop vowels from the end until the text fits
+        let mut index = len;
+        while len > out.len() && index > 0 {
+            index = index.saturating_sub(1);
+            if glyphs.get(index).is_some_and(|&(_, vowel)| vowel)
+                && let Some(rest) = glyphs.get_mut(index..len)
+            {
+                rest.rotate_left(1);
+                len = len.saturating_sub(1);
+            }
+        }
+        let out = out.get_mut(..len).ok_or(Error::InvalidDigit)?;
+        for (segments, &(pattern, _)) in out.iter_mut().zip(glyphs.iter()) {
+            *segments = pattern;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seven_segment::STANDARD_FONT;
+
+    fn render(abbreviator: &Abbreviator, text: &str, len: usize) -> Result<Vec<u8>> {
+        let mut out = vec![0xFF; len];
+        let used = abbreviator.render(text, &mut out)?;
+        out.truncate(used);
+        Ok(out)
+    }
+
+    #[test]
+    fn test_glyph() {
+        let abbreviator = Abbreviator::new(STANDARD_FONT);
+        assert_eq!(
+            abbreviator.glyph('b'),
+            Ok(Some(STANDARD_FONT.get_char('B')))
+        );
+        assert_eq!(abbreviator.glyph('m'), Ok(Some(0b0101_0101)));
+        assert_eq!(abbreviator.glyph('W'), Ok(None));
+        assert_eq!(abbreviator.with_substitutions(false).glyph('m'), Ok(None));
+        assert_eq!(
+            abbreviator.with_unsupported(CharPolicy::Error).glyph('W'),
+            Err(Error::UnsupportedChar('W'))
+        );
+    }
+
+    #[test]
+    fn test_drops_vowels_to_fit() {
+        let abbreviator = Abbreviator::new(STANDARD_FONT);
+        let s = 0b0101_1011;
+        let n = 0b0001_0101;
+        let r = 0b0000_0101;
+        let e = STANDARD_FONT.get_char('E');
+        let o = 0b0001_1101;
+        assert_eq!(
+            render(&abbreviator, "Sensor", 8),
+            Ok(vec![s, e, n, s, o, r])
+        );
+        assert_eq!(render(&abbreviator, "Sensor", 5), Ok(vec![s, e, n, s, r]));
+        assert_eq!(render(&abbreviator, "Sensor", 4), Ok(vec![s, n, s, r]));
+        assert_eq!(render(&abbreviator, "Sensor", 3), Err(Error::InvalidDigit));
+        // A leading vowel stays
+        assert_eq!(render(&abbreviator, "one", 2), Ok(vec![o, n]));
+    }
+
+    #[test]
+    fn test_dictionary_and_decimal_point() {
+        const WIFI: &[u8] = &[0b0011_1110, 0b0000_0110];
+        let abbreviator = Abbreviator::new(STANDARD_FONT).with_dictionary(&[("wifi", WIFI)]);
+        assert_eq!(
+            render(&abbreviator, "WiFi 1.", 8),
+            Ok(vec![WIFI[0], WIFI[1], 0, STANDARD_FONT.get_char('1') | DP])
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Burn-in mitigation for 7-segment displays
+//!
+//! A 7-segment display can't shift its content the way a matrix can, but
+//! some glyphs have equivalent forms that light different segments, e.g. a
+//! `1` on the right or on the left side of the digit. A [`GlyphAlternator`]
+//! periodically swaps the shown glyphs for their variants, and can move a
+//! decimal point colon between two digits, so no segment stays lit all the
+//! time.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Result,
+    seven_segment::{
+        SevenSegment,
+        fonts::{DP, NINE_NO_TAIL, SEVEN_WITH_TAIL},
+    },
+};
+
+/// Glyphs with an equivalent variant: (standard form, variant)
+const VARIANTS: [(u8, u8); 3] = [
+    (crate::segments!(B | C), crate::segments!(E | F)),
+    (crate::segments!(A | B | C), SEVEN_WITH_TAIL),
+    (crate::segments!(A | B | C | D | F | G), NINE_NO_TAIL),
+];
+
+/// Periodic alternation between equivalent glyph forms, driven by
+/// [`GlyphAlternator::tick`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // Clock "12.34" with the colon made from the decimal points of
+/// // digits 1 and 2: alternate every minute
+/// let mut alternator = GlyphAlternator::new(60_000).with_colon(1, 2);
+/// loop {
+///     if alternator.tick(100) {
+///         alternator.apply(&mut display)?;
+///     }
+///     delay.delay_ms(100);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphAlternator {
+    interval_ms: u32,
+    elapsed_ms: u32,
+    alternate: bool,
+    swap_glyphs: bool,
+    colon: Option<(usize, usize)>,
+}
+
+impl GlyphAlternator {
+    /// Swap glyphs for their variants every `interval_ms`
+    pub const fn new(interval_ms: u32) -> Self {
+        Self {
+            interval_ms,
+            elapsed_ms: 0,
+            alternate: false,
+            swap_glyphs: true,
+            colon: None,
+        }
+    }
+
+    /// Whether to swap glyphs for their variants (enabled by default)
+    pub const fn with_glyph_swap(mut self, enabled: bool) -> Self {
+        self.swap_glyphs = enabled;
+        self
+    }
+
+    /// Moves a decimal point colon between the digits at positions `first`
+    /// and `second` (0 = leftmost), whichever of the two has it lit
+    pub const fn with_colon(mut self, first: usize, second: usize) -> Self {
+        self.colon = Some((first, second));
+        self
+    }
+
+    /// Returns the time between swaps in milliseconds.
+    pub fn interval_ms(&self) -> u32 {
+        self.interval_ms
+    }
+
+    /// Returns whether the variants are currently shown.
+    pub fn is_alternate(&self) -> bool {
+        self.alternate
+    }
+
+    /// Advances the timer by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the phase changed and [`Self::apply`] should be
+    /// called. An interval of 0 never swaps.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        if self.interval_ms == 0 {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+        if self.elapsed_ms < self.interval_ms {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.checked_rem(self.interval_ms).unwrap_or(0);
+        self.alternate = !self.alternate;
+        true
+    }
+
+    /// Returns the form of `pattern` for the current phase.
+    ///
+    /// Either form of a glyph with a variant is accepted, and the decimal
+    /// point is kept. Other patterns are returned unchanged.
+    pub fn glyph(&self, pattern: u8) -> u8 {
+        if !self.swap_glyphs {
+            return pattern;
+        }
+        let glyph = pattern & !DP;
+        for &(standard, variant) in VARIANTS.iter() {
+            if glyph == standard || glyph == variant {
+                let target = if self.alternate { variant } else { standard };
+                return target | (pattern & DP);
+            }
+        }
+        pattern
+    }
+
+    /// Rewrites the digits shown on `display` in the current phase.
+    ///
+    /// Reads the patterns back from the driver's shadow registers, so call
+    /// it again after writing new content. Digits in Code B decode mode are
+    /// left alone.
+    ///
+    /// Returns the number of digits that were rewritten.
+    pub fn apply<SPI: SpiDevice>(&self, display: &mut SevenSegment<SPI>) -> Result<usize> {
+        let colon_lit = match self.colon {
+            Some((first, second)) => {
+                Self::shown(display, first)?.is_some_and(|pattern| pattern & DP != 0)
+                    || Self::shown(display, second)?.is_some_and(|pattern| pattern & DP != 0)
+            }
+            None => false,
+        };
+        let mut changed: usize = 0;
+        for position in 0..display.digit_count() {
+            let Some(pattern) = Self::shown(display, position)? else {
+                continue;
+            };
+            let mut target = self.glyph(pattern);
+            if let Some((first, second)) = self.colon
+                && colon_lit
+                && (position == first || position == second)
+            {
+                let lit = (position == second) == self.alternate;
+                target = if lit { target | DP } else { target & !DP };
+            }
+            if target != pattern {
+                display.update_segments_at(position, &[target])?;
+                changed = changed.saturating_add(1);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Returns the raw pattern shown at `position`, or `None` for digits
+    /// that are missing or in decode mode.
+    fn shown<SPI: SpiDevice>(
+        display: &mut SevenSegment<SPI>,
+        position: usize,
+    ) -> Result<Option<u8>> {
+        let Some((device_index, digit)) = display.locate(position) else {
+            return Ok(None);
+        };
+        let (register, _) = display.physical(device_index, digit, 0);
+        let state = display.driver().device_state(device_index)?;
+        if state.decode_mode() & (1 << register) != 0 {
+            return Ok(None);
+        }
+        let raw = state.digit(register)?;
+        Ok(Some(display.physical(device_index, digit, raw).1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, Register, segments, seven_segment::STANDARD_FONT};
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    #[test]
+    fn test_glyph() {
+        let mut alternator = GlyphAlternator::new(100);
+        let one = STANDARD_FONT.get_char('1');
+        assert_eq!(alternator.glyph(one | DP), one | DP);
+        assert!(!alternator.tick(50));
+        assert!(alternator.tick(50));
+        assert!(alternator.is_alternate());
+        assert_eq!(alternator.glyph(one | DP), segments!(E | F | DP));
+        assert_eq!(alternator.glyph(STANDARD_FONT.get_char('9')), NINE_NO_TAIL);
+        assert_eq!(alternator.glyph(0x7E), 0x7E);
+        assert_eq!(alternator.with_glyph_swap(false).glyph(one), one);
+    }
+
+    #[test]
+    fn test_apply() {
+        let one = STANDARD_FONT.get_char('1');
+        let zero = STANDARD_FONT.get_char('0');
+        let expected_transactions = [
+            // Content: "1.0" on digits 0 and 1
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit0.addr(), one | DP]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit1.addr(), zero]),
+            Transaction::transaction_end(),
+            // Alternate phase: mirrored one, colon moved to digit 1
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit0.addr(), segments!(E | F)]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit1.addr(), zero | DP]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut display = SevenSegment::new(Max7219::new(&mut spi));
+        display.update_segments_at(0, &[one | DP, zero]).unwrap();
+
+        let mut alternator = GlyphAlternator::new(10).with_colon(0, 1);
+        assert_eq!(alternator.apply(&mut display).unwrap(), 0);
+        alternator.tick(10);
+        assert_eq!(alternator.apply(&mut display).unwrap(), 2);
+        assert_eq!(alternator.apply(&mut display).unwrap(), 0);
+        spi.done();
+    }
+
+    #[test]
+    fn test_apply_upside_down() {
+        use crate::{
+            chain::{ChainLayout, DeviceKind, Mounting},
+            seven_segment::fonts::rotate_segments,
+        };
+
+        let one = STANDARD_FONT.get_char('1');
+        let expected_transactions = [
+            // Digit 0 of an upside-down device is register 7
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit7.addr(), rotate_segments(one)]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Digit7.addr(),
+                rotate_segments(segments!(E | F)),
+            ]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected_transactions);
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment])
+            .unwrap()
+            .with_mounting(0, Mounting::UpsideDown)
+            .unwrap();
+        let mut display = SevenSegment::new(Max7219::new(&mut spi))
+            .with_layout(layout)
+            .unwrap();
+        display.update_segments_at(0, &[one]).unwrap();
+
+        let mut alternator = GlyphAlternator::new(10);
+        alternator.tick(10);
+        assert_eq!(alternator.apply(&mut display).unwrap(), 1);
+        assert_eq!(alternator.apply(&mut display).unwrap(), 0);
+        spi.done();
+    }
+}
@@ -0,0 +1,147 @@
+//! Characters of the MAX7219 Code B font
+//!
+//! With Code B decoding enabled for a digit (see
+//! [`DecodeMode`](crate::DecodeMode)), the chip draws the glyph itself from
+//! a 4-bit code, with bit 7 lighting the decimal point.
+
+use crate::{Error, Result};
+
+/// Decimal point bit of a Code B digit register
+const DP_BIT: u8 = 0b1000_0000;
+
+/// A glyph of the built-in Code B font
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum CodeBChar {
+    /// 0
+    Zero = 0x00,
+    /// 1
+    One = 0x01,
+    /// 2
+    Two = 0x02,
+    /// 3
+    Three = 0x03,
+    /// 4
+    Four = 0x04,
+    /// 5
+    Five = 0x05,
+    /// 6
+    Six = 0x06,
+    /// 7
+    Seven = 0x07,
+    /// 8
+    Eight = 0x08,
+    /// 9
+    Nine = 0x09,
+    /// -
+    Dash = 0x0A,
+    /// E
+    E = 0x0B,
+    /// H
+    H = 0x0C,
+    /// L
+    L = 0x0D,
+    /// P
+    P = 0x0E,
+    /// Blank
+    #[default]
+    Blank = 0x0F,
+}
+
+impl CodeBChar {
+    /// Returns the 4-bit code the chip decodes.
+    pub const fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the glyph of a decimal digit (0-9), or `None` if out of range.
+    pub const fn from_digit(digit: u8) -> Option<Self> {
+        Some(match digit {
+            0 => Self::Zero,
+            1 => Self::One,
+            2 => Self::Two,
+            3 => Self::Three,
+            4 => Self::Four,
+            5 => Self::Five,
+            6 => Self::Six,
+            7 => Self::Seven,
+            8 => Self::Eight,
+            9 => Self::Nine,
+            _ => return None,
+        })
+    }
+
+    /// Returns the glyph with its decimal point lit.
+    pub const fn with_dp(self) -> CodeB {
+        CodeB { ch: self, dp: true }
+    }
+}
+
+/// Accepts `'0'..='9'`, `'-'`, `'E'`, `'H'`, `'L'`, `'P'` and `' '`.
+impl TryFrom<char> for CodeBChar {
+    type Error = Error;
+
+    /// Returns `Error::UnsupportedChar` for any other character.
+    fn try_from(ch: char) -> Result<Self> {
+        match ch {
+            '0'..='9' => Ok(Self::from_digit((ch as u8).wrapping_sub(b'0')).unwrap_or_default()),
+            '-' => Ok(Self::Dash),
+            'E' => Ok(Self::E),
+            'H' => Ok(Self::H),
+            'L' => Ok(Self::L),
+            'P' => Ok(Self::P),
+            ' ' => Ok(Self::Blank),
+            _ => Err(Error::UnsupportedChar(ch)),
+        }
+    }
+}
+
+/// A Code B glyph and its decimal point, as written to a digit register
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CodeB {
+    /// The glyph
+    pub ch: CodeBChar,
+    /// Whether the decimal point is lit
+    pub dp: bool,
+}
+
+impl CodeB {
+    /// Returns the value of the digit register.
+    pub const fn value(self) -> u8 {
+        if self.dp {
+            self.ch.code() | DP_BIT
+        } else {
+            self.ch.code()
+        }
+    }
+}
+
+impl From<CodeBChar> for CodeB {
+    fn from(ch: CodeBChar) -> Self {
+        Self { ch, dp: false }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_match_datasheet() {
+        assert_eq!(CodeBChar::Zero.code(), 0x00);
+        assert_eq!(CodeBChar::Nine.code(), 0x09);
+        assert_eq!(CodeBChar::Dash.code(), 0x0A);
+        assert_eq!(CodeBChar::Blank.code(), 0x0F);
+        assert_eq!(CodeB::from(CodeBChar::P).value(), 0x0E);
+        assert_eq!(CodeBChar::Seven.with_dp().value(), 0x87);
+    }
+
+    #[test]
+    fn test_try_from_char() {
+        assert_eq!(CodeBChar::try_from('4'), Ok(CodeBChar::Four));
+        assert_eq!(CodeBChar::try_from('H'), Ok(CodeBChar::H));
+        assert_eq!(CodeBChar::try_from(' '), Ok(CodeBChar::Blank));
+        assert_eq!(CodeBChar::try_from('h'), Err(Error::UnsupportedChar('h')));
+        assert_eq!(CodeBChar::from_digit(10), None);
+    }
+}
@@ -0,0 +1,453 @@
+//! Tally counter widget for 7-segment displays
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, MAX_DISPLAYS, Result,
+    easing::{Easing, Tween},
+    seven_segment::{Font, NumberFormat, SevenSegment, display::MAX_DIGITS},
+    threshold::Thresholds,
+};
+
+/// What happens when the counter is moved past its limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CounterPolicy {
+    /// Stop at the minimum or maximum
+    #[default]
+    Saturate,
+    /// Continue from the other end, e.g. 9999 + 1 = 0
+    Wrap,
+}
+
+/// Counter with increment/decrement and configurable limits.
+///
+/// Drawing only sends the digits that differ from what the display shows,
+/// so a counter incremented many times per second keeps the SPI traffic low.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut counter = Counter::new(0, 9999)
+///     .with_policy(CounterPolicy::Wrap)
+///     .with_format(NumberFormat::blank_padded(4));
+/// loop {
+///     if button_pressed() {
+///         counter.inc();
+///         counter.draw(&mut display, &STANDARD_FONT)?; // usually one digit
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counter {
+    value: u64,
+    min: u64,
+    max: u64,
+    policy: CounterPolicy,
+    format: NumberFormat,
+    position: usize,
+    easing: Easing,
+    animation: Option<Tween>,
+    thresholds: Option<Thresholds>,
+}
+
+impl Counter {
+    /// Create a saturating counter between `min` and `max` (inclusive),
+    /// starting at `min`
+    pub const fn new(min: u64, max: u64) -> Self {
+        Self {
+            value: min,
+            min,
+            max,
+            policy: CounterPolicy::Saturate,
+            format: NumberFormat::new(),
+            position: 0,
+            easing: Easing::Linear,
+            animation: None,
+            thresholds: None,
+        }
+    }
+
+    /// Use the given behaviour at the limits
+    pub const fn with_policy(mut self, policy: CounterPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Use the given number format
+    pub const fn with_format(mut self, format: NumberFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Draw the counter starting at the given digit position (0 = leftmost)
+    pub const fn with_position(mut self, position: usize) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Use the given easing curve for [`Self::animate_to`]
+    pub const fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Blink or change the brightness according to alarm rules, evaluated
+    /// whenever the value changes
+    pub fn with_thresholds(mut self, thresholds: Thresholds) -> Self {
+        self.thresholds = Some(thresholds);
+        self.show(self.value);
+        self
+    }
+
+    /// Returns the alarm rules and their current state, if any.
+    pub fn thresholds(&self) -> Option<&Thresholds> {
+        self.thresholds.as_ref()
+    }
+
+    /// Returns the current value, which moves towards the target while an
+    /// animation runs.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Returns the `(min, max)` limits.
+    pub fn limits(&self) -> (u64, u64) {
+        (self.min, self.max)
+    }
+
+    /// Returns the value an animation is heading for, or the current value
+    /// if none is running.
+    pub fn target(&self) -> u64 {
+        self.animation
+            .map_or(self.value, |animation| animation.target())
+    }
+
+    /// Returns `true` while an animation started with [`Self::animate_to`]
+    /// runs.
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    /// Sets the value, stopping any animation.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the value is outside the limits.
+    pub fn set(&mut self, value: u64) -> Result<()> {
+        if !(self.min..=self.max).contains(&value) {
+            return Err(Error::InvalidDigit);
+        }
+        self.animation = None;
+        self.show(value);
+        Ok(())
+    }
+
+    /// Counts visibly from the current value to `value` within
+    /// `duration_ms`, following the counter's easing curve.
+    ///
+    /// Call [`Self::tick`] to advance the count and [`Self::draw`] when it
+    /// reports a change. [`Self::set`], [`Self::inc`] and friends stop the
+    /// animation at the value reached so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the value is outside the limits.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut donations = Counter::new(0, 99_999).with_easing(Easing::CubicOut);
+    /// donations.animate_to(1250, 2000)?;
+    /// while donations.is_animating() {
+    ///     if donations.tick(20) {
+    ///         donations.draw(&mut display, &STANDARD_FONT)?;
+    ///     }
+    ///     delay.delay_ms(20);
+    /// }
+    /// ```
+    pub fn animate_to(&mut self, value: u64, duration_ms: u32) -> Result<()> {
+        if !(self.min..=self.max).contains(&value) {
+            return Err(Error::InvalidDigit);
+        }
+        let animation = Tween::new(self.value, value, duration_ms).with_easing(self.easing);
+        self.show(animation.value());
+        self.animation = animation.is_running().then_some(animation);
+        Ok(())
+    }
+
+    /// Advances a running animation and the alarm blink by `dt_ms`
+    /// milliseconds.
+    ///
+    /// Returns `true` if the counter needs to be drawn again.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        let blinked = self
+            .thresholds
+            .as_mut()
+            .is_some_and(|thresholds| thresholds.tick(dt_ms));
+        let Some(animation) = self.animation.as_mut() else {
+            return blinked;
+        };
+        let done = animation.tick(dt_ms).is_complete();
+        let value = animation.value();
+        if done {
+            self.animation = None;
+        }
+        self.show(value) || blinked
+    }
+
+    /// Resets the counter to its minimum, stopping any animation.
+    pub fn reset(&mut self) {
+        self.animation = None;
+        self.show(self.min);
+    }
+
+    /// Adds one. Returns `true` if the value changed.
+    pub fn inc(&mut self) -> bool {
+        self.animation = None;
+        let next = match self.value.checked_add(1) {
+            Some(next) if next <= self.max => next,
+            _ => match self.policy {
+                CounterPolicy::Saturate => self.max,
+                CounterPolicy::Wrap => self.min,
+            },
+        };
+        self.show(next)
+    }
+
+    /// Subtracts one. Returns `true` if the value changed.
+    pub fn dec(&mut self) -> bool {
+        self.animation = None;
+        let next = match self.value.checked_sub(1) {
+            Some(next) if next >= self.min => next,
+            _ => match self.policy {
+                CounterPolicy::Saturate => self.min,
+                CounterPolicy::Wrap => self.max,
+            },
+        };
+        self.show(next)
+    }
+
+    /// Draws the value, sending only the digits that changed.
+    ///
+    /// Without a fixed width in the format, the counter runs to the last
+    /// digit of the chain. Alarm rules apply to the devices holding the
+    /// counter's digits, see [`Thresholds`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the value does not fit.
+    pub fn draw<SPI: SpiDevice>(&self, display: &mut SevenSegment<SPI>, font: &Font) -> Result<()> {
+        let available = display
+            .digit_count()
+            .checked_sub(self.position)
+            .ok_or(Error::InvalidDigit)?;
+
+        let mut segments = [0u8; MAX_DIGITS];
+        let field = segments.get_mut(..available).ok_or(Error::InvalidDigit)?;
+        let len = self.format.render(self.value, font, field)?;
+        let segments = field.get_mut(..len).ok_or(Error::InvalidDigit)?;
+        if let Some(thresholds) = &self.thresholds {
+            let mut devices = [false; MAX_DISPLAYS];
+            for position in self.position..self.position.saturating_add(segments.len()) {
+                if let Some(selected) = display
+                    .locate(position)
+                    .and_then(|(device_index, _)| devices.get_mut(device_index))
+                {
+                    *selected = true;
+                }
+            }
+            if !thresholds.apply(display.driver(), &devices)? {
+                segments.fill(0);
+            }
+        }
+        display.update_segments_at(self.position, segments)
+    }
+
+    /// Sets the value and evaluates the alarm rules. Returns `true` if the
+    /// counter needs to be drawn again.
+    fn show(&mut self, value: u64) -> bool {
+        let changed = value != self.value;
+        self.value = value;
+        let alerted = self
+            .thresholds
+            .as_mut()
+            .is_some_and(|thresholds| thresholds.update(i64::try_from(value).unwrap_or(i64::MAX)));
+        changed || alerted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Max7219, Register, seven_segment::STANDARD_FONT};
+    use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
+
+    #[test]
+    fn test_saturate() {
+        let mut counter = Counter::new(1, 3);
+        assert!(counter.inc());
+        assert!(counter.inc());
+        assert!(!counter.inc());
+        assert_eq!(counter.value(), 3);
+
+        counter.reset();
+        assert!(!counter.dec());
+        assert_eq!(counter.value(), 1);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let mut counter = Counter::new(0, 9).with_policy(CounterPolicy::Wrap);
+        counter.set(9).unwrap();
+        assert!(counter.inc());
+        assert_eq!(counter.value(), 0);
+        assert!(counter.dec());
+        assert_eq!(counter.value(), 9);
+
+        let mut full = Counter::new(0, u64::MAX).with_policy(CounterPolicy::Wrap);
+        full.set(u64::MAX).unwrap();
+        full.inc();
+        assert_eq!(full.value(), 0);
+
+        assert_eq!(counter.set(10), Err(Error::InvalidDigit));
+    }
+
+    #[test]
+    fn test_animate_to() {
+        let mut counter = Counter::new(0, 1000);
+        counter.animate_to(100, 1000).unwrap();
+        assert!(counter.is_animating());
+        assert_eq!(counter.target(), 100);
+        assert_eq!(counter.value(), 0);
+        assert!(!counter.tick(4));
+        assert!(counter.tick(246));
+        assert_eq!(counter.value(), 25);
+        assert!(counter.tick(750));
+        assert_eq!(counter.value(), 100);
+        assert!(!counter.is_animating());
+        assert!(!counter.tick(100));
+
+        counter.animate_to(0, 1000).unwrap();
+        counter.tick(500);
+        assert!(counter.inc());
+        assert_eq!(counter.value(), 51);
+        assert!(!counter.is_animating());
+
+        counter.animate_to(7, 0).unwrap();
+        assert_eq!(counter.value(), 7);
+        assert!(!counter.is_animating());
+        assert_eq!(counter.animate_to(1001, 10), Err(Error::InvalidDigit));
+    }
+
+    #[test]
+    fn test_thresholds() {
+        use crate::{
+            Brightness,
+            threshold::{AlertAction, Threshold},
+        };
+
+        let blank = |register: Register| {
+            [
+                Transaction::transaction_start(),
+                Transaction::write_vec(vec![register.addr(), 0x00]),
+                Transaction::transaction_end(),
+            ]
+        };
+        let mut expected_transactions = vec![
+            // Above 8: maximum brightness
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Intensity.addr(), 0x0F]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit0.addr(), STANDARD_FONT.get_char('9')]),
+            Transaction::transaction_end(),
+        ];
+        // Dark half of the blink
+        expected_transactions.extend(blank(Register::Digit0));
+
+        let thresholds = Thresholds::new(Brightness::MIN)
+            .with_rule(Threshold::above(
+                8,
+                AlertAction::Brightness(Brightness::MAX),
+            ))
+            .unwrap()
+            .with_rule(Threshold::above(8, AlertAction::Blink { period_ms: 100 }))
+            .unwrap();
+        let mut counter = Counter::new(0, 9)
+            .with_format(NumberFormat::blank_padded(1))
+            .with_thresholds(thresholds);
+        assert_eq!(
+            counter.thresholds().map(Thresholds::brightness),
+            Some(Brightness::MIN)
+        );
+        counter.set(9).unwrap();
+        assert!(!counter.tick(10));
+
+        let mut spi = SpiMock::new(&expected_transactions);
+        let mut display = SevenSegment::new(Max7219::new(&mut spi));
+        counter.draw(&mut display, &STANDARD_FONT).unwrap();
+        assert!(counter.tick(40));
+        counter.draw(&mut display, &STANDARD_FONT).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_thresholds_brighten_own_devices_only() {
+        use crate::{Brightness, FrameRecorder, SinkSpi};
+
+        let spi = SinkSpi::<_, 2>::new(FrameRecorder::<2>::new());
+        let mut display = SevenSegment::new(Max7219::new(spi).with_device_count(2).unwrap());
+        // Digit 8 is on the second device from the left, the furthest one
+        let counter = Counter::new(0, 9)
+            .with_format(NumberFormat::blank_padded(1))
+            .with_position(8)
+            .with_thresholds(Thresholds::new(Brightness::MAX));
+        counter.draw(&mut display, &STANDARD_FONT).unwrap();
+
+        let brightness = |display: &mut SevenSegment<_>, device_index| {
+            display
+                .driver()
+                .device_state(device_index)
+                .unwrap()
+                .brightness()
+        };
+        assert_eq!(brightness(&mut display, 0), Brightness::MAX);
+        assert_eq!(brightness(&mut display, 1), Brightness::MIN);
+    }
+
+    #[test]
+    fn test_draw_sends_changed_digits_only() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let write = |register: Register, data| {
+            [
+                Transaction::transaction_start(),
+                Transaction::write_vec(vec![register.addr(), data]),
+                Transaction::transaction_end(),
+            ]
+        };
+        let mut expected = Vec::new();
+        // First draw of " 9" (blank digit already matches)
+        expected.extend(write(Register::Digit7, f('9')));
+        // 10: both digits change
+        expected.extend(write(Register::Digit6, f('1')));
+        expected.extend(write(Register::Digit7, f('0')));
+        // 11: only the last digit
+        expected.extend(write(Register::Digit7, f('1')));
+
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+        let mut counter = Counter::new(0, 99)
+            .with_format(NumberFormat::blank_padded(2))
+            .with_position(6);
+
+        counter.set(9).unwrap();
+        counter.draw(&mut display, &STANDARD_FONT).unwrap();
+        counter.inc();
+        counter.draw(&mut display, &STANDARD_FONT).unwrap();
+        counter.inc();
+        counter.draw(&mut display, &STANDARD_FONT).unwrap();
+        counter.draw(&mut display, &STANDARD_FONT).unwrap();
+        spi.done();
+    }
+}
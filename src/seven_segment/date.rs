@@ -0,0 +1,302 @@
+//! Date layouts for 7-segment displays
+
+use crate::{Error, Result};
+
+/// Order of the date fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateFormat {
+    /// `DD.MM.YY`, six digits
+    #[default]
+    DayMonthYear,
+    /// `MM.DD.YY`, six digits
+    MonthDayYear,
+    /// `YYYY-MM-DD` on ten or more digits, `YYYY.MM.DD` on eight
+    YearMonthDay,
+}
+
+impl DateFormat {
+    /// Returns the number of digits the layout needs with decimal point
+    /// separators.
+    pub fn digits(&self) -> usize {
+        match self {
+            DateFormat::DayMonthYear | DateFormat::MonthDayYear => 6,
+            DateFormat::YearMonthDay => 8,
+        }
+    }
+}
+
+/// Three-digit day and month names for desk clocks.
+///
+/// Seven segments can't draw every letter, so the names are best-effort
+/// mixes of upper and lower case, e.g. `Mon`, `tuE` and `noU`; M and W are
+/// drawn like an upside-down U and a U. A zero pattern leaves a digit blank,
+/// which suits two-letter abbreviations. Replace the tables for other
+/// languages or other spellings:
+///
+/// ```rust,ignore
+/// const GERMAN: DateNames = DateNames::new(&GERMAN_DAYS, DateNames::ENGLISH.months());
+/// display.write_day_name(0, 1, &GERMAN)?; // "Mo"
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateNames {
+    days: &'static [[u8; 3]; 7],
+    months: &'static [[u8; 3]; 12],
+}
+
+/// Letters of the built-in names, taken from
+/// [`STANDARD_FONT`](crate::seven_segment::STANDARD_FONT) where it has them
+mod letters {
+    use crate::segments;
+    use crate::seven_segment::STANDARD_FONT;
+
+    pub const A: u8 = STANDARD_FONT.get_char('A');
+    pub const B: u8 = STANDARD_FONT.get_char('B');
+    pub const C: u8 = STANDARD_FONT.get_char('C');
+    pub const D: u8 = STANDARD_FONT.get_char('D');
+    pub const E: u8 = STANDARD_FONT.get_char('E');
+    pub const F: u8 = STANDARD_FONT.get_char('F');
+    pub const G: u8 = segments!(A | C | D | E | F);
+    pub const LOWER_H: u8 = segments!(C | E | F | G);
+    pub const I: u8 = segments!(E | F);
+    pub const J: u8 = segments!(B | C | D | E);
+    pub const L: u8 = STANDARD_FONT.get_char('L');
+    pub const M: u8 = segments!(A | B | C | E | F);
+    pub const N: u8 = segments!(C | E | G);
+    pub const O: u8 = STANDARD_FONT.get_char('0');
+    pub const LOWER_O: u8 = segments!(C | D | E | G);
+    pub const P: u8 = STANDARD_FONT.get_char('P');
+    pub const R: u8 = segments!(E | G);
+    pub const S: u8 = STANDARD_FONT.get_char('5');
+    pub const T: u8 = segments!(D | E | F | G);
+    pub const U: u8 = STANDARD_FONT.get_char('U');
+    pub const LOWER_U: u8 = segments!(C | D | E);
+    pub const Y: u8 = segments!(B | C | D | F | G);
+}
+
+use letters::*;
+
+/// English day names, Monday first
+const ENGLISH_DAYS: [[u8; 3]; 7] = [
+    [M, LOWER_O, N],
+    [T, LOWER_U, E],
+    [U, E, D],
+    [T, LOWER_H, LOWER_U],
+    [F, R, I],
+    [S, A, T],
+    [S, LOWER_U, N],
+];
+
+/// English month names, January first
+const ENGLISH_MONTHS: [[u8; 3]; 12] = [
+    [J, A, N],
+    [F, E, B],
+    [M, A, R],
+    [A, P, R],
+    [M, A, Y],
+    [J, LOWER_U, N],
+    [J, LOWER_U, L],
+    [A, U, G],
+    [S, E, P],
+    [O, C, T],
+    [N, LOWER_O, U],
+    [D, E, C],
+];
+
+impl DateNames {
+    /// English names: `Mon` to `Sun` and `JAn` to `dEC`
+    pub const ENGLISH: Self = Self::new(&ENGLISH_DAYS, &ENGLISH_MONTHS);
+
+    /// Create name tables from segment patterns, Monday and January first
+    pub const fn new(days: &'static [[u8; 3]; 7], months: &'static [[u8; 3]; 12]) -> Self {
+        Self { days, months }
+    }
+
+    /// Returns the day names, Monday first.
+    pub const fn days(&self) -> &'static [[u8; 3]; 7] {
+        self.days
+    }
+
+    /// Returns the month names, January first.
+    pub const fn months(&self) -> &'static [[u8; 3]; 12] {
+        self.months
+    }
+
+    /// Returns the name of an ISO weekday, 1 (Monday) to 7 (Sunday).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `weekday` is out of range.
+    pub fn day(&self, weekday: u8) -> Result<[u8; 3]> {
+        let index = usize::from(weekday).checked_sub(1);
+        index
+            .and_then(|index| self.days.get(index))
+            .copied()
+            .ok_or(Error::InvalidDigit)
+    }
+
+    /// Returns the name of a month, 1 (January) to 12 (December).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `month` is out of range.
+    pub fn month(&self, month: u8) -> Result<[u8; 3]> {
+        let index = usize::from(month).checked_sub(1);
+        index
+            .and_then(|index| self.months.get(index))
+            .copied()
+            .ok_or(Error::InvalidDigit)
+    }
+}
+
+impl Default for DateNames {
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
+
+/// Returns the number of days in `month` (1 to 12) of `year`.
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Writes the date as text into `out` and returns its length.
+///
+/// Fields are separated by `'.'`, which lights the decimal point of the
+/// digit before it, or by `'-'` for [`DateFormat::YearMonthDay`] when
+/// `dashes` is set.
+pub(crate) fn date_text(
+    year: u16,
+    month: u8,
+    day: u8,
+    format: DateFormat,
+    dashes: bool,
+    out: &mut [u8; 10],
+) -> Result<usize> {
+    if year > 9999 || !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(Error::InvalidDigit);
+    }
+
+    // Every value is checked to be below 100 above
+    let two = |value: u8| [b'0'.wrapping_add(value / 10), b'0'.wrapping_add(value % 10)];
+    let short_year = two((year % 100) as u8);
+    let mut len: usize = 0;
+    let mut push = |bytes: &[u8]| {
+        let end = len.saturating_add(bytes.len());
+        if let Some(slot) = out.get_mut(len..end) {
+            slot.copy_from_slice(bytes);
+            len = end;
+        }
+    };
+    match format {
+        DateFormat::DayMonthYear => {
+            push(&two(day));
+            push(b".");
+            push(&two(month));
+            push(b".");
+            push(&short_year);
+        }
+        DateFormat::MonthDayYear => {
+            push(&two(month));
+            push(b".");
+            push(&two(day));
+            push(b".");
+            push(&short_year);
+        }
+        DateFormat::YearMonthDay => {
+            let separator: &[u8] = if dashes { b"-" } else { b"." };
+            push(&two((year / 100) as u8));
+            push(&short_year);
+            push(separator);
+            push(&two(month));
+            push(separator);
+            push(&two(day));
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(year: u16, month: u8, day: u8, format: DateFormat, dashes: bool) -> Result<String> {
+        let mut out = [0u8; 10];
+        let len = date_text(year, month, day, format, dashes, &mut out)?;
+        Ok(String::from_utf8(out[..len].to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_layouts() {
+        assert_eq!(
+            text(2024, 3, 7, DateFormat::DayMonthYear, false).unwrap(),
+            "07.03.24"
+        );
+        assert_eq!(
+            text(2024, 3, 7, DateFormat::MonthDayYear, false).unwrap(),
+            "03.07.24"
+        );
+        assert_eq!(
+            text(2024, 3, 7, DateFormat::YearMonthDay, true).unwrap(),
+            "2024-03-07"
+        );
+        assert_eq!(
+            text(2024, 3, 7, DateFormat::YearMonthDay, false).unwrap(),
+            "2024.03.07"
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        let names = DateNames::ENGLISH;
+        assert_eq!(names.day(1), Ok([M, LOWER_O, N]));
+        assert_eq!(names.day(7), Ok([S, LOWER_U, N]));
+        assert_eq!(names.month(12), Ok([D, E, C]));
+        assert_eq!(names.day(0), Err(Error::InvalidDigit));
+        assert_eq!(names.day(8), Err(Error::InvalidDigit));
+        assert_eq!(names.month(13), Err(Error::InvalidDigit));
+
+        // Letters shared with the standard font look the same
+        let font = crate::seven_segment::STANDARD_FONT;
+        for (letter, ch) in [
+            (A, 'A'),
+            (B, 'B'),
+            (C, 'C'),
+            (D, 'D'),
+            (E, 'E'),
+            (F, 'F'),
+            (L, 'L'),
+            (P, 'P'),
+            (U, 'U'),
+        ] {
+            assert_eq!(letter, font.get_char(ch));
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(text(2024, 2, 29, DateFormat::DayMonthYear, false).is_ok());
+        assert_eq!(
+            text(2023, 2, 29, DateFormat::DayMonthYear, false),
+            Err(Error::InvalidDigit)
+        );
+        assert_eq!(
+            text(1900, 2, 29, DateFormat::DayMonthYear, false),
+            Err(Error::InvalidDigit)
+        );
+        assert_eq!(
+            text(2024, 13, 1, DateFormat::DayMonthYear, false),
+            Err(Error::InvalidDigit)
+        );
+        assert_eq!(
+            text(2024, 4, 31, DateFormat::DayMonthYear, false),
+            Err(Error::InvalidDigit)
+        );
+    }
+}
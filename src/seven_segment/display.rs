@@ -1,12 +1,81 @@
 //! 7-segment display implementation
 
-use embedded_hal::spi::SpiDevice;
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
 
-use crate::{Error, Max7219, Result, seven_segment::Font};
+use crate::{
+    Error, MAX_DISPLAYS, Max7219, NUM_DIGITS, Register, Result,
+    chain::{ChainLayout, DeviceKind, Mounting},
+    seven_segment::{
+        Abbreviator, CodeB, CodeBChar, DateFormat, DateNames, Font, NumberFormat, SegmentScroller,
+        Unit,
+        date::date_text,
+        fonts::{DP, PERCENT, rotate_segments},
+        measurement::render_measurement,
+    },
+};
+
+/// Largest number of digits a chain of MAX7219 devices can drive
+pub const MAX_DIGITS: usize = MAX_DISPLAYS * NUM_DIGITS as usize;
+
+/// Renders `text` into segment patterns, one per digit.
+///
+/// A `'.'` lights the decimal point of the digit before it instead of
+/// taking a digit of its own, so `"12.5"` needs three digits.
+///
+/// Characters missing from the font are handled by its
+/// [`CharPolicy`](crate::CharPolicy).
+///
+/// Returns the number of digits used, `Error::InvalidDigit` if `out` is too
+/// short, or `Error::UnsupportedChar` if the font rejects a character.
+pub(crate) fn render_text(text: &str, font: &Font, out: &mut [u8]) -> Result<usize> {
+    let mut len = 0usize;
+    for ch in text.chars() {
+        if ch == '.'
+            && let Some(previous) = len.checked_sub(1).and_then(|last| out.get_mut(last))
+            && *previous & DP == 0
+        {
+            *previous |= DP;
+            continue;
+        }
+        let segments = if ch == '.' {
+            DP
+        } else {
+            match font.lookup(ch)? {
+                Some(segments) => segments,
+                None => continue,
+            }
+        };
+        *out.get_mut(len).ok_or(Error::InvalidDigit)? = segments;
+        len = len.saturating_add(1);
+    }
+    Ok(len)
+}
+
+/// Writes the decimal digits of `value` to the end of `buf` and returns them.
+pub(crate) fn u64_digits(mut value: u64, buf: &mut [u8; 20]) -> &str {
+    let mut start = buf.len();
+    for slot in buf.iter_mut().rev() {
+        *slot = b'0'.wrapping_add((value % 10) as u8);
+        start = start.saturating_sub(1);
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    // Only ASCII digits were written
+    core::str::from_utf8(buf.get(start..).unwrap_or_default()).unwrap_or("")
+}
 
 /// A high-level abstraction for controlling a 7-segment display using the MAX7219 driver.
+///
+/// The display manages the decode mode register itself: digits written with
+/// [`Self::write_code_b`] get Code B decoding, digits written with segment
+/// patterns get raw mode, and the register is only written when a digit has
+/// to switch. Numbers and custom glyphs can therefore be mixed freely.
 pub struct SevenSegment<SPI> {
     driver: Max7219<SPI>,
+    /// Kind of each device, if the chain is not made of 7-segment modules only
+    layout: Option<ChainLayout>,
 }
 
 impl<SPI> SevenSegment<SPI>
@@ -28,7 +97,10 @@ where
     /// let mut display = SevenSegment::new(driver);
     /// ```
     pub fn new(driver: Max7219<SPI>) -> Self {
-        Self { driver }
+        Self {
+            driver,
+            layout: None,
+        }
     }
 
     /// Simplifies initialization by creating a new `SevenSegment` instance
@@ -56,7 +128,74 @@ where
     pub fn from_spi(spi: SPI, device_count: usize) -> Result<Self> {
         let mut driver = Max7219::new(spi).with_device_count(device_count)?;
         driver.init()?;
-        Ok(Self { driver })
+        Ok(Self::new(driver))
+    }
+
+    /// Declares the kind and mounting of every device, consuming and
+    /// returning the display.
+    ///
+    /// Only the 7-segment devices of the layout show digits: positions skip
+    /// the other devices, whole-chain writes send them no-ops, and writing to
+    /// a digit of one of them returns `Error::WrongDeviceKind`. On a module
+    /// mounted upside down, digit 0 is the rightmost digit register and raw
+    /// segment patterns are rotated; Code B glyphs can't be rotated.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceCount` if the layout doesn't describe
+    /// every device of the chain.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // An 8x8 matrix nearest the MCU, followed by an 8-digit module
+    /// let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix])?;
+    /// let mut display = SevenSegment::from_spi(spi, 2)?.with_layout(layout)?;
+    /// display.write_str("12345678", &STANDARD_FONT)?; // shown by device 0
+    /// ```
+    pub fn with_layout(mut self, layout: ChainLayout) -> Result<Self> {
+        if layout.len() != self.driver.device_count() {
+            return Err(Error::InvalidDeviceCount);
+        }
+        self.layout = Some(layout);
+        Ok(self)
+    }
+
+    /// Returns the chain layout set with [`Self::with_layout`], if any.
+    pub fn layout(&self) -> Option<&ChainLayout> {
+        self.layout.as_ref()
+    }
+
+    /// Checks that a device drives 7-segment digits.
+    fn require_digits(&self, device_index: usize) -> Result<()> {
+        match self.layout {
+            Some(layout) => layout.require(device_index, DeviceKind::SevenSegment),
+            None if device_index < self.driver.device_count() => Ok(()),
+            None => Err(Error::InvalidDeviceIndex),
+        }
+    }
+
+    /// Returns the 7-segment devices, nearest the MCU first.
+    fn digit_devices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.driver.device_count())
+            .rev()
+            .filter(|&device_index| self.require_digits(device_index).is_ok())
+    }
+
+    /// Returns the digit register and segment pattern that show `pattern`
+    /// at a digit of a device, taking its mounting into account.
+    ///
+    /// The mapping is its own inverse, so it also turns a pattern read back
+    /// from a register into the one shown.
+    pub(crate) fn physical(&self, device_index: usize, digit: u8, pattern: u8) -> (u8, u8) {
+        let mounting = self
+            .layout
+            .and_then(|layout| layout.mounting(device_index).ok())
+            .unwrap_or_default();
+        match mounting {
+            Mounting::Normal => (digit, pattern),
+            Mounting::UpsideDown => (7u8.saturating_sub(digit), rotate_segments(pattern)),
+        }
     }
 
     /// Provides mutable access to the underlying MAX7219 driver.
@@ -127,6 +266,9 @@ where
     /// * `digit` - The digit position (0 to 7) on the device.
     /// * `ch` - The character to write.
     /// * `font` - The font used to convert the character into a segment pattern.
+    ///
+    /// A character the font's [`CharPolicy`](crate::CharPolicy) skips leaves
+    /// the digit unchanged; one it rejects returns `Error::UnsupportedChar`.
     pub fn write_char_to_device(
         &mut self,
         device_index: usize,
@@ -134,45 +276,489 @@ where
         ch: char,
         font: &Font,
     ) -> Result<()> {
-        let data = font.get_char(ch);
+        if let Some(data) = font.lookup(ch)? {
+            self.write_raw(device_index, digit, data)?;
+        }
 
-        self.driver.write_raw_digit(device_index, digit, data)?;
+        Ok(())
+    }
+
+    /// Returns the number of digits across all chained devices.
+    pub fn digit_count(&self) -> usize {
+        self.digit_devices()
+            .count()
+            .saturating_mul(NUM_DIGITS as usize)
+    }
+
+    /// Returns the device and digit showing the given position of the
+    /// logical display, where position 0 is the leftmost digit.
+    ///
+    /// The leftmost eight digits belong to the device nearest the MCU (the
+    /// last one in the chain), so text flows across devices the way the
+    /// modules are usually mounted. Devices the chain layout declares as
+    /// something other than 7-segment digits are skipped.
+    pub fn locate(&self, position: usize) -> Option<(usize, u8)> {
+        const DIGITS: usize = NUM_DIGITS as usize;
+        let device_index = self.digit_devices().nth(position / DIGITS)?;
+        Some((device_index, (position % DIGITS) as u8))
+    }
+
+    /// Shows segment patterns across the whole chain, starting at the
+    /// leftmost digit. Digits past the end of `segments` are blanked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if there are more patterns than digits.
+    pub fn write_segments(&mut self, segments: &[u8]) -> Result<()> {
+        if segments.len() > self.digit_count() {
+            return Err(Error::InvalidDigit);
+        }
 
+        let device_count = self.driver.device_count();
+        let mut data = [[0u8; NUM_DIGITS as usize]; MAX_DISPLAYS];
+        for (position, &pattern) in segments.iter().enumerate() {
+            if let Some((device_index, digit)) = self.locate(position) {
+                let (digit, pattern) = self.physical(device_index, digit, pattern);
+                let slot = data
+                    .get_mut(device_index)
+                    .and_then(|digits| digits.get_mut(digit as usize));
+                if let Some(slot) = slot {
+                    *slot = pattern;
+                }
+            }
+        }
+
+        let mut digit_devices = [false; MAX_DISPLAYS];
+        for (device_index, selected) in digit_devices.iter_mut().take(device_count).enumerate() {
+            if self.require_digits(device_index).is_ok() {
+                *selected = true;
+                self.set_decoded(device_index, 0xFF, false)?;
+            }
+        }
+        for (digit, digit_register) in Register::digits().enumerate() {
+            let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+            for ((op, &selected), digits) in ops.iter_mut().zip(&digit_devices).zip(&data) {
+                if selected {
+                    *op = (digit_register, digits.get(digit).copied().unwrap_or(0));
+                }
+            }
+            self.driver.write_all_registers(&ops)?;
+        }
         Ok(())
     }
 
-    /// Writes a BCD-compatible character to a digit on the first MAX7219 device.
+    /// Shows segment patterns starting at `position`, sending only the
+    /// digits whose pattern differs from what the driver last wrote.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the patterns run past the last digit.
+    pub fn update_segments_at(&mut self, position: usize, segments: &[u8]) -> Result<()> {
+        if position.saturating_add(segments.len()) > self.digit_count() {
+            return Err(Error::InvalidDigit);
+        }
+        for (offset, &pattern) in segments.iter().enumerate() {
+            if let Some((device_index, digit)) = self.locate(position.saturating_add(offset)) {
+                let state = self.driver.device_state(device_index)?;
+                let (register, shown) = self.physical(device_index, digit, pattern);
+                let decoded = state.decode_mode() & (1 << register) != 0;
+                if decoded || state.digit(register)? != shown {
+                    self.write_raw(device_index, digit, pattern)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes text across all chained devices, starting at the leftmost
+    /// digit, and blanks the remaining digits.
+    ///
+    /// A `'.'` is shown as the decimal point of the preceding character.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the text needs more digits than the
+    /// chain has.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Two chained 8-digit modules act as one 16-digit display
+    /// display.write_str("12345678.9ABCDEF", &STANDARD_FONT)?;
+    /// ```
+    pub fn write_str(&mut self, text: &str, font: &Font) -> Result<()> {
+        let mut segments = [0u8; MAX_DIGITS];
+        let field = segments
+            .get_mut(..self.digit_count())
+            .ok_or(Error::InvalidDigit)?;
+        let len = render_text(text, font, field)?;
+        self.write_segments(field.get(..len).ok_or(Error::InvalidDigit)?)
+    }
+
+    /// Writes a label starting at the leftmost digit and blanks the
+    /// remaining digits, approximating letters the font lacks and dropping
+    /// vowels if the label is too long.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the label doesn't fit even when
+    /// abbreviated.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let labels = Abbreviator::new(STANDARD_FONT);
+    /// display.write_abbreviated("Sensor", &labels)?; // "SnSr" on 4 digits
+    /// ```
+    pub fn write_abbreviated(&mut self, text: &str, abbreviator: &Abbreviator) -> Result<()> {
+        let mut segments = [0u8; MAX_DIGITS];
+        let field = segments
+            .get_mut(..self.digit_count())
+            .ok_or(Error::InvalidDigit)?;
+        let len = abbreviator.render(text, field)?;
+        self.write_segments(field.get(..len).ok_or(Error::InvalidDigit)?)
+    }
+
+    /// Scrolls `text` once from right to left through all digits, one digit
+    /// every `step_ms`, then leaves the display blank.
+    ///
+    /// For looping text or scrolling without blocking, drive a
+    /// [`SegmentScroller`] instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the font's policy rejects a
+    /// character of the text.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// display.scroll_str(&mut delay, "ERR SENSOR 3", &STANDARD_FONT, 250)?;
+    /// ```
+    pub fn scroll_str<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        text: &str,
+        font: &Font,
+        step_ms: u32,
+    ) -> Result<()> {
+        let mut scroller = SegmentScroller::new(text, font)?.with_looping(false);
+        while scroller.step() {
+            scroller.draw(self)?;
+            delay.delay_ms(step_ms);
+        }
+        scroller.draw(self)
+    }
+
+    /// Writes an unsigned number right-aligned across all chained devices,
+    /// blanking the digits to its left.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the number has more digits than the
+    /// chain.
+    pub fn write_u64(&mut self, value: u64, font: &Font) -> Result<()> {
+        self.write_u64_formatted(value, NumberFormat::new(), font)
+    }
+
+    /// Writes an unsigned number right-aligned across all chained devices
+    /// using the given padding and field width. Digits left of the field
+    /// are blanked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the number does not fit the field or
+    /// the field does not fit the chain.
+    pub fn write_u64_formatted(
+        &mut self,
+        value: u64,
+        format: NumberFormat,
+        font: &Font,
+    ) -> Result<()> {
+        let digit_count = self.digit_count();
+        let width = format.width().unwrap_or(digit_count);
+        let start = digit_count.checked_sub(width).ok_or(Error::InvalidDigit)?;
+
+        let mut segments = [0u8; MAX_DIGITS];
+        let segments = segments.get_mut(..digit_count).ok_or(Error::InvalidDigit)?;
+        format.render(
+            value,
+            font,
+            segments.get_mut(start..).ok_or(Error::InvalidDigit)?,
+        )?;
+        self.write_segments(segments)
+    }
+
+    /// Writes a float right-aligned across all chained devices, blanking the
+    /// digits left of the field.
+    ///
+    /// Up to `format`'s decimals are shown; decimals are dropped if the
+    /// number would not fit otherwise. If it does not fit at all, the
+    /// format's [`Overflow`](crate::seven_segment::Overflow) setting decides
+    /// between an error and exponent notation.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the number is not finite or does not
+    /// fit.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let format = NumberFormat::new().with_overflow(Overflow::Scientific);
+    /// display.write_f32(1_234_567.0, format.with_width(4), &STANDARD_FONT)?; // "1.2E6"
+    /// ```
+    pub fn write_f32(&mut self, value: f32, format: NumberFormat, font: &Font) -> Result<()> {
+        let digit_count = self.digit_count();
+        let width = format.width().unwrap_or(digit_count);
+        let start = digit_count.checked_sub(width).ok_or(Error::InvalidDigit)?;
+
+        let mut segments = [0u8; MAX_DIGITS];
+        let segments = segments.get_mut(..digit_count).ok_or(Error::InvalidDigit)?;
+        format.render_f32(
+            value,
+            font,
+            segments.get_mut(start..).ok_or(Error::InvalidDigit)?,
+        )?;
+        self.write_segments(segments)
+    }
+
+    /// Writes a measurement with an automatically chosen SI prefix across
+    /// all chained devices.
+    ///
+    /// Values below 1 are shown in milli units and values of 1000 or more
+    /// in kilo units, using as many decimals as the digits allow. The last
+    /// digit shows the unit and the one before it the prefix, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the value is not finite or does not fit.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// display.write_measurement(0.125, Unit::Ampere, &STANDARD_FONT)?; // "125.000nA" on 8 digits
+    /// ```
+    pub fn write_measurement(&mut self, value: f32, unit: Unit, font: &Font) -> Result<()> {
+        let digit_count = self.digit_count();
+        let mut segments = [0u8; MAX_DIGITS];
+        let segments = segments.get_mut(..digit_count).ok_or(Error::InvalidDigit)?;
+        render_measurement(value, unit, font, segments)?;
+        self.write_segments(segments)
+    }
+
+    /// Writes a percentage right-aligned across all chained devices, with
+    /// the last two digits forming a `%` sign.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the chain has fewer than five digits.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// display.write_percent(64, &STANDARD_FONT)?; // "   64°o"
+    /// ```
+    pub fn write_percent(&mut self, value: u8, font: &Font) -> Result<()> {
+        let digit_count = self.digit_count();
+        let number_end = digit_count
+            .checked_sub(PERCENT.len())
+            .ok_or(Error::InvalidDigit)?;
+
+        let mut segments = [0u8; MAX_DIGITS];
+        let segments = segments.get_mut(..digit_count).ok_or(Error::InvalidDigit)?;
+        let (number, percent) = segments
+            .split_at_mut_checked(number_end)
+            .ok_or(Error::InvalidDigit)?;
+        NumberFormat::new().render(u64::from(value), font, number)?;
+        percent.copy_from_slice(&PERCENT);
+        self.write_segments(segments)
+    }
+
+    /// Writes a date starting at the leftmost digit and blanks the rest.
+    ///
+    /// Day and month fields are separated by decimal points. The ISO layout
+    /// uses dashes when the chain has at least ten digits.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the date does not exist, the year is
+    /// above 9999, or the chain has too few digits.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// display.write_date(2024, 3, 7, DateFormat::DayMonthYear, &STANDARD_FONT)?; // "07.03.24"
+    /// ```
+    pub fn write_date(
+        &mut self,
+        year: u16,
+        month: u8,
+        day: u8,
+        format: DateFormat,
+        font: &Font,
+    ) -> Result<()> {
+        let dashes = self.digit_count() >= 10;
+        let mut text = [0u8; 10];
+        let len = date_text(year, month, day, format, dashes, &mut text)?;
+        // Only ASCII digits and separators were written
+        let text = text.get(..len).ok_or(Error::InvalidDigit)?;
+        let text = core::str::from_utf8(text).map_err(|_| Error::InvalidDigit)?;
+        self.write_str(text, font)
+    }
+
+    /// Writes an unsigned number into a field starting at `position`,
+    /// leaving the other digits as they are.
+    ///
+    /// Without a fixed width the field runs to the last digit of the chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if the number does not fit the field or
+    /// the field does not fit the chain.
+    pub fn write_u64_at(
+        &mut self,
+        position: usize,
+        value: u64,
+        format: NumberFormat,
+        font: &Font,
+    ) -> Result<()> {
+        let available = self
+            .digit_count()
+            .checked_sub(position)
+            .ok_or(Error::InvalidDigit)?;
+
+        let mut segments = [0u8; MAX_DIGITS];
+        let field = segments.get_mut(..available).ok_or(Error::InvalidDigit)?;
+        let len = format.render(value, font, field)?;
+        self.write_segments_at(position, field.get(..len).unwrap_or_default())
+    }
+
+    /// Writes the three-digit name of an ISO weekday, 1 (Monday) to 7
+    /// (Sunday), starting at `position` and leaving the other digits as they
+    /// are.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `weekday` is out of range or the name
+    /// does not fit the chain.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// display.write_day_name(0, 3, &DateNames::ENGLISH)?; // "UEd"
+    /// display.write_u64_at(4, 7, NumberFormat::zero_padded(2), &STANDARD_FONT)?;
+    /// ```
+    pub fn write_day_name(
+        &mut self,
+        position: usize,
+        weekday: u8,
+        names: &DateNames,
+    ) -> Result<()> {
+        self.write_segments_at(position, &names.day(weekday)?)
+    }
+
+    /// Writes the three-digit name of a month, 1 (January) to 12
+    /// (December), starting at `position` and leaving the other digits as
+    /// they are.
     ///
-    /// This method assumes the MAX7219 is configured in BCD decode mode
-    /// (`DecodeMode::CodeB`). Only a limited set of characters are supported
-    /// in this mode: digits '0'..='9', 'E', 'H', 'L', 'P', '-' and blank (space).
+    /// # Errors
     ///
-    /// The BCD decode mode must be enabled beforehand using `set_decode_mode()`.
+    /// Returns `Error::InvalidDigit` if `month` is out of range or the name
+    /// does not fit the chain.
+    pub fn write_month_name(
+        &mut self,
+        position: usize,
+        month: u8,
+        names: &DateNames,
+    ) -> Result<()> {
+        self.write_segments_at(position, &names.month(month)?)
+    }
+
+    /// Writes segment patterns starting at `position`, leaving the other
+    /// digits as they are.
+    fn write_segments_at(&mut self, position: usize, segments: &[u8]) -> Result<()> {
+        if position.saturating_add(segments.len()) > self.digit_count() {
+            return Err(Error::InvalidDigit);
+        }
+        for (offset, &pattern) in segments.iter().enumerate() {
+            if let Some((device_index, digit)) = self.locate(position.saturating_add(offset)) {
+                self.write_raw(device_index, digit, pattern)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a BCD-compatible character to a digit on the first MAX7219 device.
+    ///
+    /// Only a limited set of characters are supported in BCD (Code B) mode:
+    /// digits '0'..='9', 'E', 'H', 'L', 'P', '-' and blank (space). Code B
+    /// decoding is enabled for the digit first if needed.
     ///
     /// Returns an error if the character is not supported in BCD mode.
     pub fn write_bcd_char(&mut self, digit: u8, ch: char) -> Result<()> {
-        let data = match ch {
-            '0'..='9' => ch as u8 - b'0',
-            '-' => 0x0A,
-            'E' => 0x0B,
-            'H' => 0x0C,
-            'L' => 0x0D,
-            'P' => 0x0E,
-            ' ' => 0x0F,
-            _ => return Err(Error::UnsupportedChar),
-        };
+        self.write_code_b(0, digit, CodeBChar::try_from(ch)?)
+    }
+
+    /// Writes a Code B glyph to a digit of a device.
+    ///
+    /// Code B decoding is enabled for the digit first if needed.
+    ///
+    /// ```rust,ignore
+    /// display.write_code_b(0, 0, CodeBChar::Five)?;
+    /// display.write_code_b(0, 1, CodeBChar::Two.with_dp())?; // "2."
+    /// ```
+    pub fn write_code_b(
+        &mut self,
+        device_index: usize,
+        digit: u8,
+        ch: impl Into<CodeB>,
+    ) -> Result<()> {
+        let value = ch.into().value();
+        Register::try_digit(digit)?;
+        self.require_digits(device_index)?;
+        let (digit, _) = self.physical(device_index, digit, 0);
+        self.set_decoded(device_index, 1 << digit, true)?;
+        self.driver.write_raw_digit(device_index, digit, value)
+    }
 
-        self.driver.write_raw_digit(0, digit, data)?;
+    /// Writes a segment pattern to a digit, switching it to raw mode first
+    /// if needed.
+    fn write_raw(&mut self, device_index: usize, digit: u8, pattern: u8) -> Result<()> {
+        Register::try_digit(digit)?;
+        self.require_digits(device_index)?;
+        let (digit, pattern) = self.physical(device_index, digit, pattern);
+        self.set_decoded(device_index, 1 << digit, false)?;
+        self.driver.write_raw_digit(device_index, digit, pattern)
+    }
 
+    /// Enables or disables Code B decoding for the digits in `mask`, writing
+    /// the decode mode register only if it changes.
+    fn set_decoded(&mut self, device_index: usize, mask: u8, decoded: bool) -> Result<()> {
+        let current = self.driver.device_state(device_index)?.decode_mode();
+        let wanted = if decoded {
+            current | mask
+        } else {
+            current & !mask
+        };
+        if wanted != current {
+            self.driver.set_device_decode_mask(device_index, wanted)?;
+        }
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::u64_digits;
     use crate::{
-        Error, Max7219, Register,
-        seven_segment::{STANDARD_FONT, SevenSegment, fonts},
+        CharPolicy, Error, Max7219, Register,
+        chain::{ChainLayout, DeviceKind, Mounting},
+        seven_segment::{
+            CodeBChar, DateFormat, DateNames, Font, NumberFormat, STANDARD_FONT, SevenSegment,
+            fonts,
+            fonts::{DP, PERCENT},
+        },
     };
     use embedded_hal_mock::eh1::{spi::Mock as SpiMock, spi::Transaction};
 
@@ -391,6 +977,10 @@ mod tests {
         let expected_data = 0x05; // BCD for '5'
 
         let expected_transactions = [
+            // Code B is enabled for digit 3 first
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::DecodeMode.addr(), 0x08]),
+            Transaction::transaction_end(),
             Transaction::transaction_start(),
             Transaction::write_vec(vec![Register::Digit3.addr(), expected_data]),
             Transaction::transaction_end(),
@@ -412,6 +1002,9 @@ mod tests {
         let expected_data = 0x0A;
 
         let expected_transactions = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::DecodeMode.addr(), 0x02]),
+            Transaction::transaction_end(),
             Transaction::transaction_start(),
             Transaction::write_vec(vec![Register::Digit1.addr(), expected_data]),
             Transaction::transaction_end(),
@@ -426,6 +1019,68 @@ mod tests {
         spi.done();
     }
 
+    #[test]
+    fn test_write_code_b() {
+        let write = |register: Register, data: u8| {
+            [
+                Transaction::transaction_start(),
+                Transaction::write_vec(vec![register.addr(), data]),
+                Transaction::transaction_end(),
+            ]
+        };
+        let expected = [
+            write(Register::DecodeMode, 0x04),
+            write(Register::Digit2, 0x85),
+            write(Register::DecodeMode, 0x0C),
+            write(Register::Digit3, 0x01),
+        ]
+        .concat();
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display
+            .write_code_b(0, 2, CodeBChar::Five.with_dp())
+            .unwrap();
+        display.write_code_b(0, 3, CodeBChar::One).unwrap();
+        assert_eq!(
+            display.write_code_b(0, 8, CodeBChar::One),
+            Err(Error::InvalidDigit)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_decode_mode_switches_only_when_needed() {
+        let write = |register: Register, data: u8| {
+            [
+                Transaction::transaction_start(),
+                Transaction::write_vec(vec![register.addr(), data]),
+                Transaction::transaction_end(),
+            ]
+        };
+        let expected = [
+            write(Register::DecodeMode, 0x01),
+            write(Register::Digit0, 0x07),
+            write(Register::Digit0, 0x08),
+            // Digit 1 is already raw
+            write(Register::Digit1, STANDARD_FONT.get_char('A')),
+            write(Register::DecodeMode, 0x00),
+            write(Register::Digit0, STANDARD_FONT.get_char('P')),
+        ]
+        .concat();
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display.write_code_b(0, 0, CodeBChar::Seven).unwrap();
+        display.write_code_b(0, 0, CodeBChar::Eight).unwrap();
+        display.write_char(1, 'A', &STANDARD_FONT).unwrap();
+        // Digit 0 switches to raw mode
+        display.write_char(0, 'P', &STANDARD_FONT).unwrap();
+        spi.done();
+    }
+
     #[test]
     fn test_write_bcd_char_unsupported() {
         let mut spi = SpiMock::new(&[]); // No SPI calls expected
@@ -433,7 +1088,7 @@ mod tests {
         let mut display = SevenSegment::new(driver);
 
         let result = display.write_bcd_char(0, 'X'); // 'X' is not supported in BCD mode
-        assert_eq!(result, Err(Error::UnsupportedChar));
+        assert_eq!(result, Err(Error::UnsupportedChar('X')));
         spi.done();
     }
 
@@ -449,6 +1104,307 @@ mod tests {
         spi.done();
     }
 
+    fn digit_writes(data: &[[u8; 8]]) -> Vec<Transaction<u8>> {
+        let mut expected = Vec::new();
+        for (digit, register) in Register::digits().enumerate() {
+            let mut bytes = Vec::new();
+            for device in data {
+                bytes.extend([register.addr(), device[digit]]);
+            }
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(bytes));
+            expected.push(Transaction::transaction_end());
+        }
+        expected
+    }
+
+    #[test]
+    fn test_locate_reverses_device_order() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let display = SevenSegment::new(driver);
+        assert_eq!(display.digit_count(), 16);
+        assert_eq!(display.locate(0), Some((1, 0)));
+        assert_eq!(display.locate(7), Some((1, 7)));
+        assert_eq!(display.locate(8), Some((0, 0)));
+        assert_eq!(display.locate(16), None);
+        spi.done();
+    }
+
+    #[test]
+    fn test_layout_skips_other_devices() {
+        // Device 0 is an upside-down 7-segment module, device 1 a matrix:
+        // position 0 is digit register 7 and A becomes D
+        let mut spi = SpiMock::new(&[
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![
+                Register::Digit7.addr(),
+                crate::segments!(D),
+                0x00,
+                0x00,
+            ]),
+            Transaction::transaction_end(),
+        ]);
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let layout = ChainLayout::new(&[DeviceKind::SevenSegment, DeviceKind::Matrix])
+            .unwrap()
+            .with_mounting(0, Mounting::UpsideDown)
+            .unwrap();
+        let mut display = SevenSegment::new(driver).with_layout(layout).unwrap();
+
+        assert_eq!(display.digit_count(), 8);
+        assert_eq!(display.locate(0), Some((0, 0)));
+        assert_eq!(display.locate(8), None);
+        display
+            .update_segments_at(0, &[crate::segments!(A)])
+            .unwrap();
+        // Already shown, nothing is sent
+        display
+            .update_segments_at(0, &[crate::segments!(A)])
+            .unwrap();
+        assert_eq!(
+            display.write_char_to_device(1, 0, '1', &STANDARD_FONT),
+            Err(Error::WrongDeviceKind {
+                device_index: 1,
+                kind: DeviceKind::Matrix
+            })
+        );
+        spi.done();
+
+        let mut spi = SpiMock::new(&[]);
+        let single = SevenSegment::new(Max7219::new(&mut spi));
+        assert!(single.with_layout(layout).is_err());
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_str_spans_devices() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let near = [
+            f('1'),
+            f('2'),
+            f('3'),
+            f('4'),
+            f('5'),
+            f('6'),
+            f('7'),
+            f('8') | DP,
+        ];
+        let far = [f('9'), f('A'), 0, 0, 0, 0, 0, 0];
+        let mut spi = SpiMock::new(&digit_writes(&[far, near]));
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut display = SevenSegment::new(driver);
+
+        display.write_str("12345678.9A", &STANDARD_FONT).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_str_too_long() {
+        let mut spi = SpiMock::new(&[]);
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+        assert_eq!(
+            display.write_str("123456789", &STANDARD_FONT),
+            Err(Error::InvalidDigit)
+        );
+        // Decimal points do not take a digit
+        assert_eq!(
+            display.write_str("1.2.3.4.5.6.7.8..", &STANDARD_FONT),
+            Err(Error::InvalidDigit)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_str_unsupported_policy() {
+        const DIGITS: Font = Font::new(&[('1', 0b0011_0000), ('2', 0b0110_1101)]);
+        let skipping = DIGITS.with_unsupported(CharPolicy::Skip);
+        let mut spi = SpiMock::new(&digit_writes(&[[
+            0b0011_0000,
+            0b0110_1101,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ]]));
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display.write_str("1x2", &skipping).unwrap();
+        assert_eq!(
+            display.write_str("1x2", &DIGITS.with_unsupported(CharPolicy::Error)),
+            Err(Error::UnsupportedChar('x'))
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_u64_right_aligned() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let near = [0, 0, 0, 0, 0, 0, 0, f('1')];
+        let far = [
+            f('2'),
+            f('3'),
+            f('4'),
+            f('5'),
+            f('6'),
+            f('7'),
+            f('8'),
+            f('9'),
+        ];
+        let mut spi = SpiMock::new(&digit_writes(&[far, near]));
+        let driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+        let mut display = SevenSegment::new(driver);
+
+        display.write_u64(123_456_789, &STANDARD_FONT).unwrap();
+        assert_eq!(
+            display.write_u64(u64::MAX, &STANDARD_FONT),
+            Err(Error::InvalidDigit)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_u64_zero_padded_field() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let mut data = [0u8; 8];
+        data[5..].copy_from_slice(&[f('0'), f('0'), f('7')]);
+        let mut spi = SpiMock::new(&digit_writes(&[data]));
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display
+            .write_u64_formatted(7, NumberFormat::zero_padded(3), &STANDARD_FONT)
+            .unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_u64_at_only_touches_field() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let expected = [
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit2.addr(), f('0')]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![Register::Digit3.addr(), f('5')]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display
+            .write_u64_at(2, 5, NumberFormat::zero_padded(2), &STANDARD_FONT)
+            .unwrap();
+        assert_eq!(
+            display.write_u64_at(7, 5, NumberFormat::zero_padded(2), &STANDARD_FONT),
+            Err(Error::InvalidDigit)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_day_and_month_names() {
+        let names = DateNames::ENGLISH;
+        let mut expected = Vec::new();
+        for (register, pattern) in [Register::Digit0, Register::Digit1, Register::Digit2]
+            .into_iter()
+            .zip(names.day(5).unwrap())
+            .chain(
+                [Register::Digit5, Register::Digit6, Register::Digit7]
+                    .into_iter()
+                    .zip(names.month(2).unwrap()),
+            )
+        {
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![register.addr(), pattern]));
+            expected.push(Transaction::transaction_end());
+        }
+        let mut spi = SpiMock::new(&expected);
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display.write_day_name(0, 5, &names).unwrap();
+        display.write_month_name(5, 2, &names).unwrap();
+        assert_eq!(
+            display.write_month_name(6, 2, &names),
+            Err(Error::InvalidDigit)
+        );
+        assert_eq!(
+            display.write_day_name(0, 0, &names),
+            Err(Error::InvalidDigit)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_f32() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let data = [0, 0, 0, 0, f('1'), f('2') | DP, f('5'), f('0')];
+        let mut spi = SpiMock::new(&digit_writes(&[data]));
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display
+            .write_f32(12.5, NumberFormat::new(), &STANDARD_FONT)
+            .unwrap();
+        assert_eq!(
+            display.write_f32(1e9, NumberFormat::new(), &STANDARD_FONT),
+            Err(Error::InvalidDigit)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_percent() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let data = [0, 0, 0, 0, f('6'), f('4'), PERCENT[0], PERCENT[1]];
+        let mut spi = SpiMock::new(&digit_writes(&[data]));
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display.write_percent(64, &STANDARD_FONT).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn test_write_date() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let data = [
+            f('0'),
+            f('7') | DP,
+            f('0'),
+            f('3') | DP,
+            f('2'),
+            f('4'),
+            0,
+            0,
+        ];
+        let mut spi = SpiMock::new(&digit_writes(&[data]));
+        let driver = Max7219::new(&mut spi);
+        let mut display = SevenSegment::new(driver);
+
+        display
+            .write_date(2024, 3, 7, DateFormat::DayMonthYear, &STANDARD_FONT)
+            .unwrap();
+        assert_eq!(
+            display.write_date(2024, 2, 30, DateFormat::DayMonthYear, &STANDARD_FONT),
+            Err(Error::InvalidDigit)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_u64_digits() {
+        let mut buf = [0u8; 20];
+        assert_eq!(u64_digits(0, &mut buf), "0");
+        assert_eq!(u64_digits(u64::MAX, &mut buf), "18446744073709551615");
+    }
+
     // Test driver() method indirectly by using it to call a Max7219 function
     #[test]
     fn test_driver_mut_access() {
@@ -1,29 +1,144 @@
 //! Font definitions for 7-segment displays
 
+use crate::{Result, error::CharPolicy};
+
 /// 7-segment font mapping
+#[derive(Clone, Copy)]
 pub struct Font {
     char_map: &'static [(char, u8)],
+    overrides: &'static [(char, u8)],
+    unsupported: CharPolicy<u8>,
 }
 
 impl Font {
     /// Create a new font from character mappings
+    ///
+    /// Characters missing from the map are drawn blank.
     pub const fn new(char_map: &'static [(char, u8)]) -> Self {
-        Self { char_map }
+        Self {
+            char_map,
+            overrides: &[],
+            unsupported: CharPolicy::Replace(0x00),
+        }
+    }
+
+    /// Replaces or adds glyphs without copying the whole table.
+    ///
+    /// Entries in `overrides` win over the character map, so a display can
+    /// use its own conventions, e.g. a '6' without its top tail or a custom
+    /// 'X', while other displays keep the standard font.
+    ///
+    /// ```rust,ignore
+    /// const PANEL_FONT: Font = STANDARD_FONT.with_overrides(&[
+    ///     ('6', fonts::SIX_NO_TAIL),
+    ///     ('X', 0b0011_0111),
+    /// ]);
+    /// ```
+    pub const fn with_overrides(mut self, overrides: &'static [(char, u8)]) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Returns the character map the font was created from.
+    pub const fn char_map(&self) -> &'static [(char, u8)] {
+        self.char_map
+    }
+
+    /// Returns the glyphs overriding the character map.
+    pub const fn overrides(&self) -> &'static [(char, u8)] {
+        self.overrides
+    }
+
+    /// Sets what text rendering does with characters missing from the map.
+    ///
+    /// ```rust,ignore
+    /// const STRICT_FONT: Font = STANDARD_FONT.with_unsupported(CharPolicy::Error);
+    /// ```
+    pub const fn with_unsupported(mut self, policy: CharPolicy<u8>) -> Self {
+        self.unsupported = policy;
+        self
+    }
+
+    /// Returns the policy for characters missing from the map.
+    pub const fn unsupported(&self) -> CharPolicy<u8> {
+        self.unsupported
     }
 
     /// Get segment pattern for a character
-    pub fn get_char(&self, ch: char) -> u8 {
-        for &(font_char, segments) in self.char_map {
-            if font_char == ch {
-                return segments;
-            }
+    ///
+    /// Missing characters get the replacement glyph of the font's policy, or
+    /// blank if they would be skipped or rejected.
+    pub const fn get_char(&self, ch: char) -> u8 {
+        match self.lookup(ch) {
+            Ok(Some(segments)) => segments,
+            _ => 0x00,
         }
-        0x00 // Return blank for unknown characters
     }
+
+    /// Looks up the segment pattern for a character, applying the font's
+    /// [`CharPolicy`] if it is missing.
+    ///
+    /// Returns `None` if the character should be skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the character is missing and the
+    /// policy is [`CharPolicy::Error`].
+    pub const fn lookup(&self, ch: char) -> Result<Option<u8>> {
+        match self.find(ch) {
+            Some(segments) => Ok(Some(segments)),
+            None => self.unsupported.resolve(ch),
+        }
+    }
+
+    /// Returns the segment pattern for a character if the font has one,
+    /// without applying the font's [`CharPolicy`].
+    pub const fn find(&self, ch: char) -> Option<u8> {
+        match find_in(self.overrides, ch) {
+            Some(segments) => Some(segments),
+            None => find_in(self.char_map, ch),
+        }
+    }
+}
+
+/// Returns the segment pattern of the first entry of `entries` for `ch`.
+const fn find_in(mut entries: &[(char, u8)], ch: char) -> Option<u8> {
+    while let [(font_char, segments), rest @ ..] = entries {
+        if *font_char == ch {
+            return Some(*segments);
+        }
+        entries = rest;
+    }
+    None
+}
+
+/// Decimal point segment, combined with a character pattern using `|`
+pub const DP: u8 = 0b1000_0000;
+
+/// Two digits forming a percent sign: a small circle at the top followed by
+/// one at the bottom
+pub const PERCENT: [u8; 2] = [0b0110_0011, 0b0001_1101];
+
+/// '6' without the top segment, as used by many calculators
+pub const SIX_NO_TAIL: u8 = crate::segments!(C | D | E | F | G);
+
+/// '9' without the bottom segment
+pub const NINE_NO_TAIL: u8 = crate::segments!(A | B | C | F | G);
+
+/// '7' with the upper left segment lit, common in Asian and older displays
+pub const SEVEN_WITH_TAIL: u8 = crate::segments!(A | B | C | F);
+
+/// Rotates a segment pattern by 180 degrees, for digits mounted upside down
+///
+/// A swaps with D, B with E and C with F; G and the decimal point stay
+/// where they are.
+pub const fn rotate_segments(pattern: u8) -> u8 {
+    (pattern & 0b1000_0001) | ((pattern & 0b0111_0000) >> 3) | ((pattern & 0b0000_1110) << 3)
 }
 
-/// Standard 7-segment font
-/// Bit mapping: DP G F E D C B A
+/// Character to segment table of [`STANDARD_FONT`]
+///
+/// Bit mapping: DP A B C D E F G
 ///
 /// ```text
 ///   A
@@ -32,7 +147,10 @@ impl Font {
 /// E   C
 ///   D    DP
 /// ```
-pub const STANDARD_FONT: Font = Font::new(&[
+///
+/// Use it as the base of a custom font, or override single glyphs with
+/// [`Font::with_overrides`].
+pub const STANDARD_FONT_MAP: &[(char, u8)] = &[
     ('0', 0b01111110), // 0
     ('1', 0b00110000), // 1
     ('2', 0b01101101), // 2
@@ -55,12 +173,28 @@ pub const STANDARD_FONT: Font = Font::new(&[
     ('U', 0b00111110), // U
     ('-', 0b00000001), // -
     (' ', 0b00000000), // Space
-]);
+];
+
+/// Standard 7-segment font, see [`STANDARD_FONT_MAP`]
+pub const STANDARD_FONT: Font = Font::new(STANDARD_FONT_MAP);
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_rotate_segments() {
+        assert_eq!(
+            rotate_segments(STANDARD_FONT.get_char('7')),
+            crate::segments!(D | E | F)
+        );
+        assert_eq!(
+            rotate_segments(crate::segments!(G) | DP),
+            crate::segments!(G) | DP
+        );
+        assert_eq!(rotate_segments(rotate_segments(0b0101_1010)), 0b0101_1010);
+    }
+
     #[test]
     fn test_standard_font_known_chars() {
         // Test a selection of characters from the standard font
@@ -107,4 +241,33 @@ mod tests {
         assert_eq!(font.get_char('B'), 0x00);
         assert_eq!(font.get_char('C'), 0x00); // Unknown char
     }
+
+    #[test]
+    fn test_overrides_win_over_char_map() {
+        const PANEL_FONT: Font =
+            STANDARD_FONT.with_overrides(&[('6', SIX_NO_TAIL), ('X', 0b0011_0111)]);
+        assert_eq!(PANEL_FONT.get_char('6'), SIX_NO_TAIL);
+        assert_eq!(PANEL_FONT.get_char('X'), 0b0011_0111);
+        assert_eq!(PANEL_FONT.get_char('9'), STANDARD_FONT.get_char('9'));
+        assert_eq!(STANDARD_FONT.get_char('6'), 0b01011111);
+        assert_eq!(PANEL_FONT.char_map().len(), STANDARD_FONT_MAP.len());
+    }
+
+    #[test]
+    fn test_unsupported_policy() {
+        const TEST_DATA: &[(char, u8)] = &[('A', 0xFF)];
+        let font = Font::new(TEST_DATA);
+        assert_eq!(font.lookup('A'), Ok(Some(0xFF)));
+        assert_eq!(font.lookup('Z'), Ok(Some(0x00)));
+
+        let font = Font::new(TEST_DATA).with_unsupported(CharPolicy::Replace(0x01));
+        assert_eq!(font.get_char('Z'), 0x01);
+
+        let font = Font::new(TEST_DATA).with_unsupported(CharPolicy::Skip);
+        assert_eq!(font.lookup('Z'), Ok(None));
+        assert_eq!(font.get_char('Z'), 0x00);
+
+        let font = Font::new(TEST_DATA).with_unsupported(CharPolicy::Error);
+        assert_eq!(font.lookup('Z'), Err(crate::Error::UnsupportedChar('Z')));
+    }
 }
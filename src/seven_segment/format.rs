@@ -0,0 +1,560 @@
+//! Number formatting options for 7-segment displays
+
+use core::fmt::{self, Write};
+
+use crate::{
+    Error, Result,
+    seven_segment::{
+        Font,
+        display::{MAX_DIGITS, render_text, u64_digits},
+        fonts::DP,
+        measurement::UnitSuffix,
+    },
+};
+
+/// What fills the unused digits to the left of a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Padding {
+    /// Leading zeros are suppressed and the digits stay blank, e.g. `"  42"`
+    #[default]
+    Blank,
+    /// Leading zeros are shown, e.g. `"0042"`
+    Zero,
+}
+
+/// What to do when a float does not fit the field even without decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Return `Error::InvalidDigit`
+    #[default]
+    Error,
+    /// Switch to exponent notation, e.g. `1.2E6`
+    Scientific,
+    /// Switch to exponent notation with the exponent a multiple of three,
+    /// e.g. `12.5E3`
+    Engineering,
+}
+
+/// How a number is laid out on the display.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // Clock: "09" and "05"
+/// let two_digits = NumberFormat::zero_padded(2);
+/// display.write_u64_at(0, 9, two_digits, &STANDARD_FONT)?;
+/// display.write_u64_at(2, 5, two_digits, &STANDARD_FONT)?;
+///
+/// // Counter: "  42" on a 4-digit field
+/// display.write_u64_formatted(42, NumberFormat::blank_padded(4), &STANDARD_FONT)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    width: Option<usize>,
+    padding: Padding,
+    thousands_separator: bool,
+    decimals: u8,
+    overflow: Overflow,
+    suffix: Option<UnitSuffix>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumberFormat {
+    /// Right-aligned in the available digits, leading zeros suppressed
+    pub const fn new() -> Self {
+        Self {
+            width: None,
+            padding: Padding::Blank,
+            thousands_separator: false,
+            decimals: 2,
+            overflow: Overflow::Error,
+            suffix: None,
+        }
+    }
+
+    /// A field of exactly `width` digits padded with zeros
+    pub const fn zero_padded(width: usize) -> Self {
+        Self::new().with_width(width).with_padding(Padding::Zero)
+    }
+
+    /// A field of exactly `width` digits padded with blanks
+    pub const fn blank_padded(width: usize) -> Self {
+        Self::new().with_width(width)
+    }
+
+    /// Use a field of exactly `width` digits
+    pub const fn with_width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Fill unused digits with `padding`
+    pub const fn with_padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Light the decimal point after every third digit from the right, as in
+    /// `1.234.567`.
+    ///
+    /// Separators do not take up digits; padding is never grouped.
+    pub const fn with_thousands_separator(mut self, enabled: bool) -> Self {
+        self.thousands_separator = enabled;
+        self
+    }
+
+    /// Returns `true` if thousands are separated by decimal points.
+    pub fn thousands_separator(&self) -> bool {
+        self.thousands_separator
+    }
+
+    /// Show floats with up to `decimals` digits after the decimal point.
+    ///
+    /// Decimals are dropped from the right when the number would not fit
+    /// otherwise. The default is 2.
+    pub const fn with_decimals(mut self, decimals: u8) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Choose what happens when a float does not fit even without decimals
+    pub const fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Reserve the last digits of the field for a unit suffix, e.g. `°C`.
+    ///
+    /// The suffix is part of the field: a fixed width of 4 with a two-digit
+    /// suffix leaves two digits for the number.
+    pub const fn with_suffix(mut self, suffix: UnitSuffix) -> Self {
+        self.suffix = Some(suffix);
+        self
+    }
+
+    /// Returns the unit suffix, if any.
+    pub fn suffix(&self) -> Option<UnitSuffix> {
+        self.suffix
+    }
+
+    /// Returns the maximum number of decimals shown for floats.
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// Returns the overflow behaviour for floats.
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Returns the field width, if fixed.
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+
+    /// Returns the padding.
+    pub fn padding(&self) -> Padding {
+        self.padding
+    }
+
+    /// Renders `value` into `out`, using the fixed width or else all of `out`.
+    ///
+    /// Returns the number of digits written, or `Error::InvalidDigit` if the
+    /// value does not fit.
+    pub(crate) fn render(&self, value: u64, font: &Font, out: &mut [u8]) -> Result<usize> {
+        self.render_suffixed(out, |format, number| {
+            format.render_number(value, font, number)
+        })
+    }
+
+    /// Renders `value` into `out`, using the fixed width or else all of `out`.
+    ///
+    /// Returns the number of digits written, or `Error::InvalidDigit` if the
+    /// value is not finite or does not fit.
+    pub(crate) fn render_f32(&self, value: f32, font: &Font, out: &mut [u8]) -> Result<usize> {
+        self.render_suffixed(out, |format, number| {
+            format.render_f32_number(value, font, number)
+        })
+    }
+
+    /// Splits the unit suffix off the end of the field and lets `render`
+    /// fill the rest with the number.
+    fn render_suffixed<F>(&self, out: &mut [u8], render: F) -> Result<usize>
+    where
+        F: FnOnce(&Self, &mut [u8]) -> Result<usize>,
+    {
+        let Some(suffix) = self.suffix else {
+            return render(self, out);
+        };
+        let width = self.width.unwrap_or(out.len());
+        let field = out.get_mut(..width).ok_or(Error::InvalidDigit)?;
+        let number_width = width.checked_sub(suffix.len()).ok_or(Error::InvalidDigit)?;
+        let (number, tail) = field
+            .split_at_mut_checked(number_width)
+            .ok_or(Error::InvalidDigit)?;
+        let format = Self {
+            width: Some(number_width),
+            suffix: None,
+            ..*self
+        };
+        render(&format, number)?;
+        tail.copy_from_slice(suffix.segments());
+        Ok(width)
+    }
+
+    /// Renders the number alone into the field.
+    fn render_number(&self, value: u64, font: &Font, out: &mut [u8]) -> Result<usize> {
+        let width = self.width.unwrap_or(out.len());
+        let field = out.get_mut(..width).ok_or(Error::InvalidDigit)?;
+
+        let mut buf = [0u8; 20];
+        let digits = u64_digits(value, &mut buf);
+        let start = width.checked_sub(digits.len()).ok_or(Error::InvalidDigit)?;
+
+        let pad = match self.padding {
+            Padding::Blank => font.get_char(' '),
+            Padding::Zero => font.get_char('0'),
+        };
+        let (padding, number) = field
+            .split_at_mut_checked(start)
+            .ok_or(Error::InvalidDigit)?;
+        padding.fill(pad);
+        let count = digits.len();
+        for (index, (segments, ch)) in number.iter_mut().zip(digits.chars()).enumerate() {
+            *segments = font.get_char(ch);
+            let remaining = count.saturating_sub(1).saturating_sub(index);
+            if self.thousands_separator && remaining > 0 && remaining.is_multiple_of(3) {
+                *segments |= DP;
+            }
+        }
+        Ok(width)
+    }
+
+    /// Renders the float alone into the field.
+    fn render_f32_number(&self, value: f32, font: &Font, out: &mut [u8]) -> Result<usize> {
+        if !value.is_finite() {
+            return Err(Error::InvalidDigit);
+        }
+        let width = self.width.unwrap_or(out.len());
+        let field = out.get_mut(..width).ok_or(Error::InvalidDigit)?;
+
+        let negative = value < 0.0;
+        let magnitude = f64::from(value).abs();
+        let text = (0..=self.decimals)
+            .rev()
+            .filter_map(|decimals| fixed(magnitude, negative, decimals))
+            .find(|text| text.digits() <= width)
+            .or_else(|| match self.overflow {
+                Overflow::Error => None,
+                Overflow::Scientific => exponent(magnitude, negative, 1, width),
+                Overflow::Engineering => exponent(magnitude, negative, 3, width),
+            })
+            .ok_or(Error::InvalidDigit)?;
+
+        let mut segments = [0u8; MAX_DIGITS];
+        let len = render_text(text.as_str(), font, &mut segments)?;
+        let start = width.checked_sub(len).ok_or(Error::InvalidDigit)?;
+        let pad = match self.padding {
+            Padding::Blank => font.get_char(' '),
+            Padding::Zero => font.get_char('0'),
+        };
+        let (padding, number) = field
+            .split_at_mut_checked(start)
+            .ok_or(Error::InvalidDigit)?;
+        padding.fill(pad);
+        for (digit, &pattern) in number.iter_mut().zip(&segments) {
+            *digit = pattern;
+        }
+        // Keep the sign in front of zero padding
+        if self.padding == Padding::Zero
+            && text.as_str().starts_with('-')
+            && let (Some(first), Some(sign)) = (padding.first_mut(), number.first_mut())
+        {
+            *sign = pad;
+            *first = font.get_char('-');
+        }
+        Ok(width)
+    }
+}
+
+/// Small stack buffer holding a formatted number
+struct NumberText {
+    buf: [u8; 32],
+    len: usize,
+}
+
+impl NumberText {
+    fn new() -> Self {
+        Self {
+            buf: [0; 32],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // Only ASCII is ever written
+        core::str::from_utf8(self.buf.get(..self.len).unwrap_or_default()).unwrap_or("")
+    }
+
+    /// Number of digits needed, with decimal points sharing a digit
+    fn digits(&self) -> usize {
+        self.as_str().chars().filter(|&ch| ch != '.').count()
+    }
+}
+
+impl Write for NumberText {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let end = self.len.saturating_add(text.len());
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(text.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Rounds `magnitude` to `decimals` places and formats it, e.g. `-12.50`.
+fn fixed(magnitude: f64, negative: bool, decimals: u8) -> Option<NumberText> {
+    let scale = 10u64.checked_pow(u32::from(decimals))?;
+    let scaled = magnitude * scale as f64 + 0.5;
+    if scaled >= u64::MAX as f64 {
+        return None;
+    }
+    let rounded = scaled as u64;
+
+    let mut text = NumberText::new();
+    // No "-0.00" for tiny negative values
+    if negative && rounded != 0 {
+        text.write_char('-').ok()?;
+    }
+    write!(text, "{}", rounded.checked_div(scale)?).ok()?;
+    if decimals > 0 {
+        let fraction = rounded.checked_rem(scale)?;
+        write!(text, ".{:01$}", fraction, usize::from(decimals)).ok()?;
+    }
+    Some(text)
+}
+
+/// Formats `magnitude` as mantissa and exponent with the exponent a multiple
+/// of `step`, using as many mantissa decimals as fit in `width` digits.
+fn exponent(magnitude: f64, negative: bool, step: i32, width: usize) -> Option<NumberText> {
+    if magnitude == 0.0 {
+        return fixed(0.0, negative, 0).filter(|text| text.digits() <= width);
+    }
+
+    let mut mantissa = magnitude;
+    let mut exp = 0i32;
+    while mantissa >= 10.0 {
+        mantissa /= 10.0;
+        exp = exp.saturating_add(1);
+    }
+    while mantissa < 1.0 {
+        mantissa *= 10.0;
+        exp = exp.saturating_sub(1);
+    }
+    while exp.checked_rem_euclid(step).is_some_and(|rem| rem != 0) {
+        mantissa *= 10.0;
+        exp = exp.saturating_sub(1);
+    }
+    let limit = if step == 3 { 1000.0 } else { 10.0 };
+
+    // Rounding can carry into a new digit, e.g. 9.99 -> 10.0
+    for _ in 0..2 {
+        let mut suffix = NumberText::new();
+        write!(suffix, "E{exp}").ok()?;
+        let fixed_digits = usize::from(negative).saturating_add(suffix.digits());
+        let int_digits = if mantissa >= 100.0 {
+            3
+        } else if mantissa >= 10.0 {
+            2
+        } else {
+            1
+        };
+        let budget = width.checked_sub(fixed_digits.saturating_add(int_digits))?;
+        let decimals = u8::try_from(budget.min(9)).ok()?;
+
+        let mut text = fixed(mantissa, negative, decimals)?;
+        let rounded: f64 = text.as_str().trim_start_matches('-').parse().ok()?;
+        if rounded >= limit {
+            mantissa /= limit;
+            exp = exp.saturating_add(step);
+            continue;
+        }
+        text.write_str(suffix.as_str()).ok()?;
+        return Some(text);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seven_segment::STANDARD_FONT;
+
+    fn render(format: NumberFormat, value: u64, len: usize) -> Result<Vec<u8>> {
+        let mut out = vec![0xFF; len];
+        let used = format.render(value, &STANDARD_FONT, &mut out)?;
+        out.truncate(used);
+        Ok(out)
+    }
+
+    fn segments(text: &str) -> Vec<u8> {
+        text.chars().map(|ch| STANDARD_FONT.get_char(ch)).collect()
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(render(NumberFormat::new(), 42, 4), Ok(segments("  42")));
+        assert_eq!(
+            render(NumberFormat::zero_padded(2), 9, 8),
+            Ok(segments("09"))
+        );
+        assert_eq!(
+            render(NumberFormat::blank_padded(3), 0, 8),
+            Ok(segments("  0"))
+        );
+        assert_eq!(
+            render(NumberFormat::new().with_padding(Padding::Zero), 5, 3),
+            Ok(segments("005"))
+        );
+    }
+
+    #[test]
+    fn test_suffix() {
+        let mut with_celsius = segments("21.5");
+        with_celsius.remove(2);
+        with_celsius[1] |= DP;
+        with_celsius.extend_from_slice(UnitSuffix::CELSIUS.segments());
+
+        let format = NumberFormat::new()
+            .with_decimals(1)
+            .with_suffix(UnitSuffix::CELSIUS);
+        let mut out = vec![0xFF; 5];
+        assert_eq!(format.render_f32(21.5, &STANDARD_FONT, &mut out), Ok(5));
+        assert_eq!(out, with_celsius);
+
+        let mut expected = segments(" 7");
+        expected.extend_from_slice(UnitSuffix::HERTZ.segments());
+        let hertz = NumberFormat::blank_padded(4).with_suffix(UnitSuffix::HERTZ);
+        assert_eq!(render(hertz, 7, 8), Ok(expected));
+        assert_eq!(render(hertz, 700, 8), Err(Error::InvalidDigit));
+        assert_eq!(
+            render(NumberFormat::new().with_suffix(UnitSuffix::PERCENT), 1, 1),
+            Err(Error::InvalidDigit)
+        );
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let format = NumberFormat::new().with_thousands_separator(true);
+        let mut expected = segments(" 1234567");
+        expected[1] |= DP;
+        expected[4] |= DP;
+        assert_eq!(render(format, 1_234_567, 8), Ok(expected));
+
+        let format = NumberFormat::zero_padded(5).with_thousands_separator(true);
+        assert_eq!(render(format, 999, 8), Ok(segments("00999")));
+    }
+
+    fn render_float(format: NumberFormat, value: f32, len: usize) -> Result<Vec<u8>> {
+        let mut out = vec![0xFF; len];
+        let used = format.render_f32(value, &STANDARD_FONT, &mut out)?;
+        out.truncate(used);
+        Ok(out)
+    }
+
+    fn float_segments(text: &str, len: usize) -> Vec<u8> {
+        let mut out = vec![0; MAX_DIGITS];
+        let used = render_text(text, &STANDARD_FONT, &mut out).unwrap();
+        let mut field = vec![STANDARD_FONT.get_char(' '); len - used];
+        field.extend_from_slice(&out[..used]);
+        field
+    }
+
+    #[test]
+    fn test_float_fixed() {
+        let format = NumberFormat::new();
+        assert_eq!(
+            render_float(format, 1.23456, 6),
+            Ok(float_segments("1.23", 6))
+        );
+        assert_eq!(
+            render_float(format, -0.5, 4),
+            Ok(float_segments("-0.50", 4))
+        );
+        assert_eq!(
+            render_float(format, -0.001, 4),
+            Ok(float_segments("0.00", 4))
+        );
+        // Decimals are dropped to make the number fit
+        assert_eq!(
+            render_float(format, 1234.56, 5),
+            Ok(float_segments("1234.6", 5))
+        );
+
+        let zero = NumberFormat::zero_padded(6).with_decimals(1);
+        assert_eq!(
+            render_float(zero, -2.5, 8),
+            Ok(float_segments("-0002.5", 6))
+        );
+    }
+
+    #[test]
+    fn test_float_overflow() {
+        let format = NumberFormat::new();
+        assert_eq!(render_float(format, 1.2e6, 4), Err(Error::InvalidDigit));
+        assert_eq!(render_float(format, f32::NAN, 4), Err(Error::InvalidDigit));
+
+        let scientific = format.with_overflow(Overflow::Scientific);
+        assert_eq!(
+            render_float(scientific, 1.2e6, 4),
+            Ok(float_segments("1.2E6", 4))
+        );
+        assert_eq!(
+            render_float(scientific, -9.99e9, 5),
+            Ok(float_segments("-1E10", 5))
+        );
+        // Small values still fit, so they are not switched to exponents
+        assert_eq!(
+            render_float(scientific, 1.5e-7, 4),
+            Ok(float_segments("0.00", 4))
+        );
+
+        let engineering = format.with_overflow(Overflow::Engineering);
+        assert_eq!(
+            render_float(engineering, 12_500_000.0, 5),
+            Ok(float_segments("12.5E6", 5))
+        );
+        // "123E3" would need five digits
+        assert_eq!(
+            render_float(engineering, 123_456.0, 4),
+            Err(Error::InvalidDigit)
+        );
+    }
+
+    #[test]
+    fn test_negative_exponent() {
+        let text = exponent(1.5e-7, false, 1, 5).unwrap();
+        assert_eq!(text.as_str(), "1.5E-7");
+        let text = exponent(0.0042, true, 3, 6).unwrap();
+        assert_eq!(text.as_str(), "-4.2E-3");
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            render(NumberFormat::zero_padded(2), 100, 8),
+            Err(Error::InvalidDigit)
+        );
+        assert_eq!(
+            render(NumberFormat::blank_padded(9), 1, 8),
+            Err(Error::InvalidDigit)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Auto-ranging display of electrical measurements
+//!
+//! Segment patterns for the unit and SI prefix glyphs are approximations,
+//! since 7 segments cannot draw every letter:
+//!
+//! ```text
+//!  milli "n"    kilo "k"    volt "U"    amp "A"    ohm "∩"
+//!                 ---                     ---        ---
+//!                |           |   |       |   |      |   |
+//!   ---           ---                     ---
+//!  |   |         |   |       |   |       |   |      |   |
+//!                             ---
+//! ```
+
+use crate::{
+    Error, Result,
+    seven_segment::{Font, NumberFormat, fonts::PERCENT},
+};
+
+/// Segment pattern shown for the milli (10⁻³) prefix
+pub const MILLI: u8 = crate::segments!(C | E | G);
+
+/// Segment pattern shown for the kilo (10³) prefix
+pub const KILO: u8 = crate::segments!(A | C | E | F | G);
+
+/// Volt sign, a `U`
+const VOLT: u8 = crate::segments!(B | C | D | E | F);
+
+/// Ampere sign, an `A`
+const AMPERE: u8 = crate::segments!(A | B | C | E | F | G);
+
+/// Ohm sign, an upside-down `U`
+const OHM: u8 = crate::segments!(A | B | C | E | F);
+
+/// Unit of a measurement, shown as the last digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Volts, shown as `U`
+    Volt,
+    /// Amperes, shown as `A`
+    Ampere,
+    /// Ohms, shown as `∩`
+    Ohm,
+}
+
+impl Unit {
+    /// Returns the segment pattern of the unit glyph.
+    pub fn segments(&self) -> u8 {
+        match self {
+            Unit::Volt => VOLT,
+            Unit::Ampere => AMPERE,
+            Unit::Ohm => OHM,
+        }
+    }
+}
+
+impl From<Unit> for UnitSuffix {
+    fn from(unit: Unit) -> Self {
+        UnitSuffix::new(unit.segments())
+    }
+}
+
+/// Degree sign, a small circle at the top
+const DEGREE: u8 = crate::segments!(A | B | F | G);
+
+/// Unit glyphs shown after a number, one or two digits wide.
+///
+/// Reserve the digits with [`NumberFormat::with_suffix`], so every
+/// measurement display shows its unit the same way. Ready-made suffixes
+/// cover temperatures (`°C`, `°F`), percentages, frequencies (`Hz`),
+/// voltages and currents; [`UnitSuffix::new`] and [`UnitSuffix::pair`] take
+/// any other patterns.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // "21.5°C" on a 6-digit display
+/// let format = NumberFormat::new().with_decimals(1).with_suffix(UnitSuffix::CELSIUS);
+/// display.write_f32(21.5, format, &STANDARD_FONT)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitSuffix {
+    glyphs: [u8; 2],
+    len: u8,
+}
+
+impl UnitSuffix {
+    /// Degrees Celsius, `°C`
+    pub const CELSIUS: Self = Self::pair(DEGREE, crate::segments!(A | D | E | F));
+    /// Degrees Fahrenheit, `°F`
+    pub const FAHRENHEIT: Self = Self::pair(DEGREE, crate::segments!(A | E | F | G));
+    /// Degrees without a scale, `°`
+    pub const DEGREE: Self = Self::new(DEGREE);
+    /// Percent, drawn as two small circles over two digits
+    pub const PERCENT: Self = Self::pair(PERCENT[0], PERCENT[1]);
+    /// Hertz, `Hz` with the `z` drawn like a `2`
+    pub const HERTZ: Self = Self::pair(
+        crate::segments!(B | C | E | F | G),
+        crate::segments!(A | B | D | E | G),
+    );
+    /// Volts, shown as `U`
+    pub const VOLT: Self = Self::new(VOLT);
+    /// Amperes, `A`
+    pub const AMPERE: Self = Self::new(AMPERE);
+
+    /// Create a one-digit suffix
+    pub const fn new(glyph: u8) -> Self {
+        Self {
+            glyphs: [glyph, 0],
+            len: 1,
+        }
+    }
+
+    /// Create a two-digit suffix
+    pub const fn pair(first: u8, second: u8) -> Self {
+        Self {
+            glyphs: [first, second],
+            len: 2,
+        }
+    }
+
+    /// Returns the segment patterns of the suffix, one per digit.
+    pub fn segments(&self) -> &[u8] {
+        self.glyphs.get(..usize::from(self.len)).unwrap_or_default()
+    }
+
+    /// Returns the number of digits the suffix takes.
+    pub fn len(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    /// Always `false`: a suffix takes at least one digit.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Renders `value` followed by an SI prefix and the unit glyph, filling all
+/// of `out`.
+///
+/// Values below 1 are shown in milli units and values of 1000 or more in
+/// kilo units. The number gets as many decimals as the remaining digits
+/// allow.
+pub(crate) fn render_measurement(
+    value: f32,
+    unit: Unit,
+    font: &Font,
+    out: &mut [u8],
+) -> Result<()> {
+    let magnitude = value.abs();
+    let (scaled, prefix) = if magnitude != 0.0 && magnitude < 1.0 {
+        (value * 1000.0, Some(MILLI))
+    } else if magnitude >= 1000.0 {
+        (value / 1000.0, Some(KILO))
+    } else {
+        (value, None)
+    };
+
+    let width = out
+        .len()
+        .checked_sub(usize::from(prefix.is_some()).saturating_add(1))
+        .ok_or(Error::InvalidDigit)?;
+    let decimals = width.saturating_sub(1).min(9) as u8;
+    let format = NumberFormat::new().with_decimals(decimals);
+    let (number, suffix) = out.split_at_mut_checked(width).ok_or(Error::InvalidDigit)?;
+    format.render_f32(scaled, font, number)?;
+
+    if let (Some(prefix), Some(first)) = (prefix, suffix.first_mut()) {
+        *first = prefix;
+    }
+    if let Some(last) = suffix.last_mut() {
+        *last = unit.segments();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seven_segment::{STANDARD_FONT, display::render_text};
+
+    fn measure(value: f32, unit: Unit, len: usize) -> Result<Vec<u8>> {
+        let mut out = vec![0xFF; len];
+        render_measurement(value, unit, &STANDARD_FONT, &mut out)?;
+        Ok(out)
+    }
+
+    fn expected(number: &str, suffix: &[u8]) -> Vec<u8> {
+        let mut out = vec![0; 8];
+        let len = render_text(number, &STANDARD_FONT, &mut out).unwrap();
+        out.truncate(len);
+        out.extend_from_slice(suffix);
+        out
+    }
+
+    #[test]
+    fn test_auto_range() {
+        let volt = Unit::Volt.segments();
+        assert_eq!(measure(4.95, Unit::Volt, 4), Ok(expected("4.95", &[volt])));
+        assert_eq!(
+            measure(0.125, Unit::Ampere, 5),
+            Ok(expected("125", &[MILLI, Unit::Ampere.segments()]))
+        );
+        assert_eq!(
+            measure(4700.0, Unit::Ohm, 5),
+            Ok(expected("4.70", &[KILO, Unit::Ohm.segments()]))
+        );
+        assert_eq!(measure(-12.0, Unit::Volt, 4), Ok(expected("-12", &[volt])));
+    }
+
+    #[test]
+    fn test_suffixes() {
+        assert_eq!(UnitSuffix::VOLT, UnitSuffix::from(Unit::Volt));
+        assert_eq!(UnitSuffix::AMPERE, UnitSuffix::from(Unit::Ampere));
+        assert_eq!(UnitSuffix::PERCENT.segments(), &PERCENT);
+        assert_eq!(
+            UnitSuffix::CELSIUS.segments()[1],
+            STANDARD_FONT.get_char('C')
+        );
+        assert_eq!(UnitSuffix::HERTZ.segments()[1], STANDARD_FONT.get_char('2'));
+        assert_eq!(UnitSuffix::DEGREE.len(), 1);
+    }
+
+    #[test]
+    fn test_too_few_digits() {
+        assert_eq!(measure(1.0, Unit::Volt, 1), Err(Error::InvalidDigit));
+        assert_eq!(
+            measure(12_000_000.0, Unit::Volt, 4),
+            Err(Error::InvalidDigit)
+        );
+    }
+}
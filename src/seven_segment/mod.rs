@@ -1,7 +1,25 @@
 //! 7-segment display implementation
 
+pub mod abbrev;
+pub mod burn_in;
+pub mod code_b;
+pub mod counter;
+pub mod date;
 pub mod display;
 pub mod fonts;
+pub mod format;
+pub mod measurement;
+pub mod scroll;
+pub mod segments;
 
+pub use abbrev::Abbreviator;
+pub use burn_in::GlyphAlternator;
+pub use code_b::{CodeB, CodeBChar};
+pub use counter::{Counter, CounterPolicy};
+pub use date::{DateFormat, DateNames};
 pub use display::SevenSegment;
-pub use fonts::{Font, STANDARD_FONT};
+pub use fonts::{Font, STANDARD_FONT, STANDARD_FONT_MAP};
+pub use format::{NumberFormat, Overflow, Padding};
+pub use measurement::{Unit, UnitSuffix};
+pub use scroll::SegmentScroller;
+pub use segments::Segment;
@@ -0,0 +1,305 @@
+//! Scrolling text across 7-segment digits
+//!
+//! Messages longer than the display, like `"ERR SENSOR 3"` on a 4-digit
+//! module, move through it one digit per step. A `'.'` lights the decimal
+//! point of the character before it, as with
+//! [`SevenSegment::write_str`], so it doesn't take a step of its own.
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    Error, Result,
+    seven_segment::{Font, SevenSegment, display::MAX_DIGITS, fonts::DP},
+    time::Progress,
+};
+
+/// Text moving from right to left through the digits of a display.
+///
+/// The text enters at the right edge. Looping text repeats after a gap of
+/// blank digits; otherwise it scrolls until it has left the display.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut scroller = SegmentScroller::new("ERR SENSOR 3", &STANDARD_FONT)?
+///     .with_step_ms(250);
+/// loop {
+///     if scroller.tick(10) {
+///         scroller.draw(&mut display)?;
+///     }
+///     delay.delay_ms(10);
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct SegmentScroller<'a> {
+    text: &'a str,
+    font: &'a Font,
+    len: usize,
+    looping: bool,
+    gap: usize,
+    step_ms: u32,
+    elapsed_ms: u32,
+    offset: usize,
+    width: usize,
+}
+
+impl<'a> SegmentScroller<'a> {
+    /// Create a looping scroller with a gap of two digits, moving one digit
+    /// every 300 ms.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnsupportedChar` if the font's policy rejects a
+    /// character of the text.
+    pub fn new(text: &'a str, font: &'a Font) -> Result<Self> {
+        let mut len: usize = 0;
+        let mut dp_free = false;
+        for ch in text.chars() {
+            if ch == '.' && dp_free {
+                dp_free = false;
+                continue;
+            }
+            if ch == '.' || font.lookup(ch)?.is_some() {
+                len = len.saturating_add(1);
+                dp_free = ch != '.';
+            }
+        }
+        Ok(Self {
+            text,
+            font,
+            len,
+            looping: true,
+            gap: 2,
+            step_ms: 300,
+            elapsed_ms: 0,
+            offset: 0,
+            width: 8,
+        })
+    }
+
+    /// Whether the text repeats once it has passed
+    pub const fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Sets the number of blank digits between repetitions
+    pub const fn with_gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Sets the time between steps in milliseconds
+    pub const fn with_step_ms(mut self, step_ms: u32) -> Self {
+        self.step_ms = step_ms;
+        self
+    }
+
+    /// Sets the number of digits the text scrolls through, 8 by default.
+    ///
+    /// [`Self::draw`] sets it to the digit count of the display.
+    pub const fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Returns the number of digits the text takes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the text has nothing to show.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of steps taken since the text started entering.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Moves the text back outside the right edge.
+    pub fn reset(&mut self) {
+        self.offset = 0;
+        self.elapsed_ms = 0;
+    }
+
+    /// Returns `true` once non-looping text has left the display.
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.offset >= self.len.saturating_add(self.width)
+    }
+
+    /// Moves the text one digit to the left.
+    ///
+    /// Returns `false` once non-looping text has left the display.
+    pub fn step(&mut self) -> bool {
+        self.advance();
+        !self.is_finished()
+    }
+
+    /// Moves the text one digit to the left and reports when a pass is
+    /// complete.
+    ///
+    /// Returns [`Progress::Complete`] when non-looping text has left the
+    /// display, or each time looping text starts over. Once a non-looping
+    /// scroll is complete, further calls return [`Progress::Idle`] until
+    /// [`Self::reset`].
+    pub fn advance(&mut self) -> Progress {
+        if self.is_finished() {
+            return Progress::Idle;
+        }
+        self.offset = self.offset.saturating_add(1);
+        let period = self.len.saturating_add(self.gap);
+        if self.looping && period > 0 && self.offset >= period.saturating_add(self.width) {
+            self.offset = self.offset.saturating_sub(period);
+            return Progress::Complete;
+        }
+        if self.is_finished() {
+            Progress::Complete
+        } else {
+            Progress::Running
+        }
+    }
+
+    /// Advances the scroll by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the text moved and needs to be drawn again.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        if self.step_ms == 0 || self.is_finished() {
+            return false;
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(dt_ms);
+        let mut moved = false;
+        while self.elapsed_ms >= self.step_ms && !self.is_finished() {
+            self.elapsed_ms = self.elapsed_ms.saturating_sub(self.step_ms);
+            self.advance();
+            moved = true;
+        }
+        moved
+    }
+
+    /// Fills `out` with the digits currently visible, `out.len()` being the
+    /// width of the display.
+    pub fn frame(&self, out: &mut [u8]) {
+        let width = out.len();
+        out.fill(0);
+        let period = self.len.saturating_add(self.gap);
+        for (index, segments) in out.iter_mut().enumerate() {
+            let Some(position) = self.offset.saturating_add(index).checked_sub(width) else {
+                continue;
+            };
+            let position = if self.looping {
+                position.checked_rem(period).unwrap_or(position)
+            } else {
+                position
+            };
+            if let Some(pattern) = self.pattern(position) {
+                *segments = pattern;
+            }
+        }
+    }
+
+    /// Draws the visible digits across the whole display, sending only the
+    /// digits that changed.
+    pub fn draw<SPI: SpiDevice>(&mut self, display: &mut SevenSegment<SPI>) -> Result<()> {
+        let digit_count = display.digit_count();
+        self.width = digit_count;
+        let mut segments = [0u8; MAX_DIGITS];
+        let segments = segments.get_mut(..digit_count).ok_or(Error::InvalidDigit)?;
+        self.frame(segments);
+        display.update_segments_at(0, segments)
+    }
+
+    /// Returns the pattern of the digit at `position` in the text.
+    fn pattern(&self, position: usize) -> Option<u8> {
+        let mut index: usize = 0;
+        let mut current: Option<u8> = None;
+        for ch in self.text.chars() {
+            if ch == '.'
+                && let Some(pattern) = current
+                && pattern & DP == 0
+            {
+                current = Some(pattern | DP);
+                continue;
+            }
+            let next = if ch == '.' {
+                Some(DP)
+            } else {
+                self.font.lookup(ch).ok().flatten()
+            };
+            let Some(next) = next else {
+                continue;
+            };
+            if current.is_some() {
+                if index == position {
+                    return current;
+                }
+                index = index.saturating_add(1);
+            }
+            current = Some(next);
+        }
+        current.filter(|_| index == position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seven_segment::STANDARD_FONT;
+
+    fn frame(scroller: &SegmentScroller, width: usize) -> Vec<u8> {
+        let mut out = vec![0xFF; width];
+        scroller.frame(&mut out);
+        out
+    }
+
+    #[test]
+    fn test_decimal_points_merge() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let scroller = SegmentScroller::new("1.2..3", &STANDARD_FONT).unwrap();
+        assert_eq!(scroller.len(), 4);
+        assert_eq!(scroller.pattern(0), Some(f('1') | DP));
+        assert_eq!(scroller.pattern(1), Some(f('2') | DP));
+        assert_eq!(scroller.pattern(2), Some(DP));
+        assert_eq!(scroller.pattern(3), Some(f('3')));
+        assert_eq!(scroller.pattern(4), None);
+    }
+
+    #[test]
+    fn test_single_pass() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let mut scroller = SegmentScroller::new("AB", &STANDARD_FONT)
+            .unwrap()
+            .with_looping(false)
+            .with_width(3);
+        assert_eq!(frame(&scroller, 3), [0, 0, 0]);
+        assert_eq!(scroller.advance(), Progress::Running);
+        assert_eq!(frame(&scroller, 3), [0, 0, f('A')]);
+        scroller.advance();
+        scroller.advance();
+        assert_eq!(frame(&scroller, 3), [f('A'), f('B'), 0]);
+        scroller.advance();
+        assert_eq!(scroller.advance(), Progress::Complete);
+        assert_eq!(frame(&scroller, 3), [0, 0, 0]);
+        assert_eq!(scroller.advance(), Progress::Idle);
+        assert!(!scroller.tick(1000));
+    }
+
+    #[test]
+    fn test_loop_with_gap() {
+        let f = |ch| STANDARD_FONT.get_char(ch);
+        let mut scroller = SegmentScroller::new("12", &STANDARD_FONT)
+            .unwrap()
+            .with_gap(1)
+            .with_width(2)
+            .with_step_ms(100);
+        assert!(scroller.tick(200));
+        assert_eq!(frame(&scroller, 2), [f('1'), f('2')]);
+        assert!(scroller.tick(100));
+        assert_eq!(frame(&scroller, 2), [f('2'), 0]);
+        assert_eq!(scroller.advance(), Progress::Running);
+        assert_eq!(frame(&scroller, 2), [0, f('1')]);
+        assert_eq!(scroller.advance(), Progress::Complete);
+        assert_eq!(frame(&scroller, 2), [f('1'), f('2')]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Building segment patterns by name
+//!
+//! Digit registers take one bit per segment (DP A B C D E F G, most
+//! significant bit first). Instead of writing binary literals, name the
+//! segments and let the compiler combine and check them:
+//!
+//! ```rust
+//! use max7219_display::segments;
+//! use max7219_display::seven_segment::segments::{Segment, pattern};
+//!
+//! const LOWER_O: u8 = segments!(C | D | E | G);
+//! const UPPER_C: u8 = pattern(&[Segment::A, Segment::D, Segment::E, Segment::F]);
+//!
+//! assert_eq!(LOWER_O, 0b0001_1101);
+//! assert_eq!(UPPER_C, 0b0100_1110);
+//! ```
+//!
+//! ```text
+//!   A
+//! F   B
+//!   G
+//! E   C
+//!   D    DP
+//! ```
+
+/// One segment of a digit, with its bit in the digit register as value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Segment {
+    /// Top
+    A = 0b0100_0000,
+    /// Upper right
+    B = 0b0010_0000,
+    /// Lower right
+    C = 0b0001_0000,
+    /// Bottom
+    D = 0b0000_1000,
+    /// Lower left
+    E = 0b0000_0100,
+    /// Upper left
+    F = 0b0000_0010,
+    /// Middle
+    G = 0b0000_0001,
+    /// Decimal point
+    DP = 0b1000_0000,
+}
+
+impl Segment {
+    /// All segments, in bit order from the most significant bit
+    pub const ALL: [Segment; 8] = [
+        Segment::DP,
+        Segment::A,
+        Segment::B,
+        Segment::C,
+        Segment::D,
+        Segment::E,
+        Segment::F,
+        Segment::G,
+    ];
+
+    /// Returns the bit of the segment in the digit register.
+    pub const fn bit(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns `true` if the segment is lit in `pattern`.
+    pub const fn is_lit(self, pattern: u8) -> bool {
+        pattern & self.bit() != 0
+    }
+}
+
+/// Combines segments into the pattern for a digit register.
+///
+/// Listing a segment twice has no further effect.
+pub const fn pattern(segments: &[Segment]) -> u8 {
+    let mut bits = 0;
+    let mut rest = segments;
+    while let [segment, tail @ ..] = rest {
+        bits |= segment.bit();
+        rest = tail;
+    }
+    bits
+}
+
+/// Builds a segment pattern from segment names at compile time.
+///
+/// Segments are named `A` to `G` and `DP`, see
+/// [`Segment`](crate::seven_segment::segments::Segment). Unknown names fail
+/// to compile. `segments!()` is the blank pattern.
+///
+/// ```rust
+/// use max7219_display::segments;
+///
+/// const SEVEN: u8 = segments!(A | B | C);
+/// const SEVEN_DOT: u8 = segments!(A | B | C | DP);
+///
+/// assert_eq!(SEVEN, 0b0111_0000);
+/// assert_eq!(SEVEN_DOT, 0b1111_0000);
+/// ```
+#[macro_export]
+macro_rules! segments {
+    ($($segment:ident)|*) => {
+        $crate::seven_segment::segments::pattern(&[
+            $($crate::seven_segment::segments::Segment::$segment),*
+        ])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seven_segment::STANDARD_FONT;
+
+    #[test]
+    fn test_macro_matches_font() {
+        assert_eq!(
+            segments!(A | B | C | D | E | F),
+            STANDARD_FONT.get_char('0')
+        );
+        assert_eq!(segments!(B | C), STANDARD_FONT.get_char('1'));
+        assert_eq!(segments!(G), STANDARD_FONT.get_char('-'));
+        assert_eq!(segments!(), 0);
+        assert_eq!(segments!(A | A), segments!(A));
+    }
+
+    #[test]
+    fn test_all_segments() {
+        assert_eq!(pattern(&Segment::ALL), 0xFF);
+        assert!(Segment::DP.is_lit(0b1000_0000));
+        assert!(!Segment::G.is_lit(0b1111_1110));
+    }
+}
@@ -0,0 +1,156 @@
+//! Sharing a display between `main` and interrupt handlers
+//!
+//! Bare-metal firmware often draws from `main` while a timer interrupt
+//! scrolls text or blinks a colon. [`StaticDisplay`] holds the driver in a
+//! `static` and hands out access inside a critical section, so neither side
+//! needs `unsafe` or a hand-rolled `static mut`.
+//!
+//! The target must provide a
+//! [`critical-section`](https://docs.rs/critical-section) implementation,
+//! usually enabled through a feature of the HAL or the `cortex-m` crate.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+use crate::{Error, Result};
+
+/// A display that lives in a `static` and can be used from `main` and from
+/// interrupt handlers.
+///
+/// `T` can be any display type, such as [`Max7219`](crate::Max7219),
+/// `LedMatrix` or `SevenSegment`. The static starts empty and is filled
+/// once the SPI peripheral has been set up.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// static DISPLAY: StaticDisplay<SingleMatrix<MySpi>> = StaticDisplay::new();
+///
+/// fn main() -> ! {
+///     let matrix = SingleMatrix::from_spi(spi).unwrap();
+///     DISPLAY.init(matrix).ok();
+///     loop {
+///         DISPLAY.with(|matrix| matrix.draw_text("HI"));
+///     }
+/// }
+///
+/// #[interrupt]
+/// fn TIM2() {
+///     DISPLAY.with(|matrix| matrix.tick_heartbeat(10));
+/// }
+/// ```
+pub struct StaticDisplay<T> {
+    inner: Mutex<RefCell<Option<T>>>,
+}
+
+impl<T> Default for StaticDisplay<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> StaticDisplay<T> {
+    /// Create an empty slot, usable in a `static` initializer
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Puts `display` into the slot.
+    ///
+    /// Returns the display back as `Err` if the slot is already filled,
+    /// including when it is in use by an enclosing call to [`Self::with`].
+    pub fn init(&self, display: T) -> core::result::Result<(), T> {
+        critical_section::with(|cs| {
+            let Ok(mut slot) = self.inner.borrow(cs).try_borrow_mut() else {
+                return Err(display);
+            };
+            if slot.is_some() {
+                return Err(display);
+            }
+            *slot = Some(display);
+            Ok(())
+        })
+    }
+
+    /// Returns `true` once a display has been put into the slot.
+    pub fn is_initialized(&self) -> bool {
+        critical_section::with(|cs| {
+            // The slot is only borrowed while `with` runs on a display
+            self.inner
+                .borrow(cs)
+                .try_borrow()
+                .map_or(true, |slot| slot.is_some())
+        })
+    }
+
+    /// Runs `f` on the display inside a critical section.
+    ///
+    /// Interrupts are masked while `f` runs, so keep it short: drawing into
+    /// the framebuffer or a single flush, not a blocking animation.
+    ///
+    /// Returns `None` if the slot is empty, or if it is already in use by
+    /// an enclosing call to `with`.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        critical_section::with(|cs| {
+            let mut slot = self.inner.borrow(cs).try_borrow_mut().ok()?;
+            slot.as_mut().map(f)
+        })
+    }
+
+    /// Takes the display out of the slot, leaving it empty.
+    ///
+    /// Returns `Ok(None)` if the slot is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DisplayBusy`] if the slot is in use by an enclosing
+    /// call to [`Self::with`].
+    pub fn take(&self) -> Result<Option<T>> {
+        critical_section::with(|cs| {
+            let mut slot = self
+                .inner
+                .borrow(cs)
+                .try_borrow_mut()
+                .map_err(|_| Error::DisplayBusy)?;
+            Ok(slot.take())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_once() {
+        static SLOT: StaticDisplay<u8> = StaticDisplay::new();
+        assert!(!SLOT.is_initialized());
+        assert_eq!(SLOT.with(|value| *value), None);
+
+        assert_eq!(SLOT.init(1), Ok(()));
+        assert_eq!(SLOT.init(2), Err(2));
+        assert_eq!(SLOT.with(|value| *value), Some(1));
+        assert_eq!(SLOT.take(), Ok(Some(1)));
+        assert_eq!(SLOT.take(), Ok(None));
+        assert!(!SLOT.is_initialized());
+    }
+
+    #[test]
+    fn test_nested_access_is_refused() {
+        let slot = StaticDisplay::new();
+        slot.init(5u8).ok();
+        let nested = slot.with(|value| {
+            *value += 1;
+            slot.with(|value| *value)
+        });
+        assert_eq!(nested, Some(None));
+        assert_eq!(slot.with(|value| *value), Some(6));
+
+        let inside = slot.with(|_| (slot.is_initialized(), slot.init(7), slot.take()));
+        assert_eq!(inside, Some((true, Err(7), Err(Error::DisplayBusy))));
+        assert_eq!(slot.take(), Ok(Some(6)));
+    }
+}
@@ -0,0 +1,488 @@
+//! Destinations for rendered frames
+//!
+//! A frame is the DIG0 to DIG7 data of every device in a chain, indexed like
+//! the driver: index 0 is the device furthest from the MCU. A [`FrameSink`]
+//! receives such frames, which decouples rendering from the transport:
+//!
+//! - [`Max7219`] sends them over SPI
+//! - [`FrameRecorder`] keeps the last frame, e.g. to check it in tests or to
+//!   print it as a simulator
+//! - application sinks forward them to a remote monitor or a log
+//!
+//! [`SinkSpi`] goes the other way: it stands in for the SPI device, decodes
+//! the MAX7219 protocol and passes the resulting frames to a sink, so the
+//! whole high-level stack runs without hardware.
+//!
+//! [`LedMatrix::from_sink`](crate::led_matrix::LedMatrix::from_sink) wraps
+//! a sink this way, and the widgets draw into such a matrix like into one
+//! driving hardware:
+//!
+//! ```rust,ignore
+//! let mut matrix = Matrix4::from_sink(FrameRecorder::<4>::new())?;
+//! clock.draw(&mut matrix)?;
+//! println!("{}", matrix.sink());
+//! ```
+
+use core::fmt;
+
+use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+
+use crate::{Error, Max7219, Register, Result};
+
+/// Receives complete frames of device rows.
+///
+/// # Example
+///
+/// ```rust
+/// use max7219_display::{Error, sink::FrameSink};
+///
+/// /// Counts the lit pixels of the last frame
+/// struct LitCounter(u32);
+///
+/// impl FrameSink for LitCounter {
+///     fn write_rows(&mut self, device_rows: &[[u8; 8]]) -> Result<(), Error> {
+///         self.0 = device_rows.iter().flatten().map(|row| row.count_ones()).sum();
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait FrameSink {
+    /// Receives the DIG0 to DIG7 data of every device, furthest device first.
+    fn write_rows(&mut self, device_rows: &[[u8; 8]]) -> Result<()>;
+}
+
+impl<S: FrameSink + ?Sized> FrameSink for &mut S {
+    fn write_rows(&mut self, device_rows: &[[u8; 8]]) -> Result<()> {
+        (**self).write_rows(device_rows)
+    }
+}
+
+/// Sends the frame to the chain, one digit register of every device per
+/// transaction.
+///
+/// Returns `Error::InvalidDeviceCount` if the frame does not have one entry
+/// per device.
+impl<SPI: SpiDevice> FrameSink for Max7219<SPI> {
+    fn write_rows(&mut self, device_rows: &[[u8; 8]]) -> Result<()> {
+        let device_count = self.device_count();
+        if device_rows.len() != device_count {
+            return Err(Error::InvalidDeviceCount);
+        }
+        let mut ops = [(Register::NoOp, 0); crate::MAX_DISPLAYS];
+        for (digit, register) in Register::digits().enumerate() {
+            for (op, rows) in ops.iter_mut().zip(device_rows) {
+                *op = (register, rows.get(digit).copied().unwrap_or(0));
+            }
+            self.write_all_registers(&ops)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sink that keeps the last frame of a chain of `DEVICE_COUNT` devices.
+///
+/// Its [`Display`](fmt::Display) output draws the frame with `#` for lit
+/// and `.` for dark pixels in the default layout of a single row of
+/// modules, the device nearest to the MCU on the left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameRecorder<const DEVICE_COUNT: usize> {
+    rows: [[u8; 8]; DEVICE_COUNT],
+    frames: usize,
+}
+
+impl<const DEVICE_COUNT: usize> Default for FrameRecorder<DEVICE_COUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DEVICE_COUNT: usize> FrameRecorder<DEVICE_COUNT> {
+    /// Create a recorder with a blank frame
+    pub const fn new() -> Self {
+        Self {
+            rows: [[0; 8]; DEVICE_COUNT],
+            frames: 0,
+        }
+    }
+
+    /// Returns the last frame, furthest device first.
+    pub fn rows(&self) -> &[[u8; 8]; DEVICE_COUNT] {
+        &self.rows
+    }
+
+    /// Returns the number of frames received.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns whether bit `bit` of digit `digit` of a device is lit.
+    pub fn is_lit(&self, device_index: usize, digit: u8, bit: u8) -> bool {
+        self.rows
+            .get(device_index)
+            .and_then(|rows| rows.get(digit as usize))
+            .is_some_and(|row| row & (1 << (bit & 7)) != 0)
+    }
+}
+
+impl<const DEVICE_COUNT: usize> FrameSink for FrameRecorder<DEVICE_COUNT> {
+    fn write_rows(&mut self, device_rows: &[[u8; 8]]) -> Result<()> {
+        if device_rows.len() != DEVICE_COUNT {
+            return Err(Error::InvalidDeviceCount);
+        }
+        self.rows.copy_from_slice(device_rows);
+        self.frames = self.frames.saturating_add(1);
+        Ok(())
+    }
+}
+
+impl<const DEVICE_COUNT: usize> fmt::Display for FrameRecorder<DEVICE_COUNT> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for digit in 0..8 {
+            for rows in self.rows.iter().rev() {
+                let row = rows.get(digit).copied().unwrap_or(0);
+                for bit in (0..8).rev() {
+                    let lit = row & (1 << bit) != 0;
+                    f.write_str(if lit { "#" } else { "." })?;
+                }
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// An SPI device that decodes MAX7219 packets into frames for a
+/// [`FrameSink`] instead of driving hardware.
+///
+/// Digit register writes update a shadow of the chain. The frame is passed
+/// to the sink once every digit of every device has been written, or when
+/// a transaction rewrites a digit of the pending frame, so a flush sent
+/// row by row reaches the sink as one frame. Frames that did not change
+/// are not passed on. Other registers are accepted and ignored, and reads
+/// return zeros.
+///
+/// A partial update, e.g. from
+/// [`LedMatrix::flush_region`](crate::led_matrix::LedMatrix::flush_region),
+/// is held back until the next write; [`SinkSpi::flush_frame`] passes it
+/// on right away.
+///
+/// If the sink fails, the transaction that completed the frame fails with
+/// a [`SinkError`], which the driver reports as `Error::SpiError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkSpi<S, const DEVICE_COUNT: usize> {
+    sink: S,
+    rows: [[u8; 8]; DEVICE_COUNT],
+    /// Digits of each device written since the last frame, one bit per digit
+    written: [u8; DEVICE_COUNT],
+    /// Whether the pending frame differs from the last one passed on
+    changed: bool,
+}
+
+impl<S: FrameSink, const DEVICE_COUNT: usize> SinkSpi<S, DEVICE_COUNT> {
+    /// Create a virtual chain of `DEVICE_COUNT` blank devices feeding `sink`
+    pub const fn new(sink: S) -> Self {
+        Self {
+            sink,
+            rows: [[0; 8]; DEVICE_COUNT],
+            written: [0; DEVICE_COUNT],
+            changed: false,
+        }
+    }
+
+    /// Returns the sink.
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Returns the sink mutably.
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Consumes the device, returning the sink.
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    /// Passes the pending frame to the sink, if it changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the sink's error; the frame is dropped either way.
+    pub fn flush_frame(&mut self) -> Result<()> {
+        self.written = [0; DEVICE_COUNT];
+        if !core::mem::take(&mut self.changed) {
+            return Ok(());
+        }
+        self.sink.write_rows(&self.rows)
+    }
+
+    /// Calls `f` with the device index, digit and data of every digit
+    /// register packet in `bytes`.
+    fn for_each_digit(bytes: &[u8], mut f: impl FnMut(usize, u8, u8)) {
+        for (device_index, packet) in bytes.chunks_exact(2).enumerate() {
+            if let &[address, data] = packet
+                && let Some(digit) = Register::try_from(address)
+                    .ok()
+                    .and_then(Register::digit_index)
+            {
+                f(device_index, digit, data);
+            }
+        }
+    }
+
+    /// Returns the digits of each device that `bytes` writes, one bit per digit.
+    fn digits_written(bytes: &[u8], digits: &mut [u8; DEVICE_COUNT]) {
+        Self::for_each_digit(bytes, |device_index, digit, _| {
+            if let Some(mask) = digits.get_mut(device_index) {
+                *mask |= 1 << (digit & 7);
+            }
+        });
+    }
+
+    /// Applies the packets of one write to the pending frame.
+    fn decode(&mut self, bytes: &[u8]) {
+        let (rows, written, changed) = (&mut self.rows, &mut self.written, &mut self.changed);
+        Self::for_each_digit(bytes, |device_index, digit, data| {
+            if let Some(row) = rows
+                .get_mut(device_index)
+                .and_then(|rows| rows.get_mut(digit as usize))
+            {
+                *changed |= *row != data;
+                *row = data;
+            }
+            if let Some(mask) = written.get_mut(device_index) {
+                *mask |= 1 << (digit & 7);
+            }
+        });
+    }
+}
+
+/// Error of a [`SinkSpi`] transaction: the sink failed to take a frame.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SinkError(pub Error);
+
+impl embedded_hal::spi::Error for SinkError {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+
+impl<S, const DEVICE_COUNT: usize> ErrorType for SinkSpi<S, DEVICE_COUNT> {
+    type Error = SinkError;
+}
+
+impl<S: FrameSink, const DEVICE_COUNT: usize> SpiDevice for SinkSpi<S, DEVICE_COUNT> {
+    fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> core::result::Result<(), SinkError> {
+        // A transaction rewriting a digit starts the next frame
+        let mut digits = [0u8; DEVICE_COUNT];
+        for operation in operations.iter() {
+            match operation {
+                Operation::Write(bytes) => Self::digits_written(bytes, &mut digits),
+                Operation::Transfer(_, write) => Self::digits_written(write, &mut digits),
+                Operation::TransferInPlace(bytes) => Self::digits_written(bytes, &mut digits),
+                Operation::Read(_) | Operation::DelayNs(_) => {}
+            }
+        }
+        if digits
+            .iter()
+            .zip(&self.written)
+            .any(|(digits, written)| digits & written != 0)
+        {
+            self.flush_frame().map_err(SinkError)?;
+        }
+
+        for operation in operations.iter_mut() {
+            match operation {
+                Operation::Write(bytes) => self.decode(bytes),
+                Operation::Transfer(read, write) => {
+                    self.decode(write);
+                    read.fill(0);
+                }
+                Operation::TransferInPlace(bytes) => {
+                    self.decode(bytes);
+                    bytes.fill(0);
+                }
+                Operation::Read(bytes) => bytes.fill(0),
+                Operation::DelayNs(_) => {}
+            }
+        }
+        if self.written.iter().all(|&written| written == 0xFF) {
+            self.flush_frame().map_err(SinkError)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction};
+
+    #[test]
+    fn test_driver_sends_frame_row_by_row() {
+        let mut expected = Vec::new();
+        for digit in Register::digits() {
+            let far = if digit == Register::Digit2 { 0x0F } else { 0 };
+            expected.push(Transaction::transaction_start());
+            expected.push(Transaction::write_vec(vec![
+                digit.addr(),
+                far,
+                digit.addr(),
+                0,
+            ]));
+            expected.push(Transaction::transaction_end());
+        }
+        let mut spi = SpiMock::new(&expected);
+        let mut driver = Max7219::new(&mut spi).with_device_count(2).unwrap();
+
+        let mut frame = [[0u8; 8]; 2];
+        frame[0][2] = 0x0F;
+        driver.write_rows(&frame).unwrap();
+        assert_eq!(
+            driver.write_rows(&frame[..1]),
+            Err(Error::InvalidDeviceCount)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn test_sink_spi_decodes_packets() {
+        let mut spi: SinkSpi<FrameRecorder<2>, 2> = SinkSpi::new(FrameRecorder::new());
+        // Digit1 of the furthest device, no-op for the nearest
+        spi.write(&[Register::Digit1.addr(), 0x81, 0x00, 0x00])
+            .unwrap();
+        // Other registers do not produce frames
+        spi.write(&[Register::Intensity.addr(), 3, Register::Intensity.addr(), 3])
+            .unwrap();
+        spi.write(&[Register::Digit1.addr(), 0x81, 0x00, 0x00])
+            .unwrap();
+
+        let recorder = spi.into_sink();
+        assert_eq!(recorder.frames(), 1);
+        assert!(recorder.is_lit(0, 1, 7) && recorder.is_lit(0, 1, 0));
+        assert!(!recorder.is_lit(1, 1, 7));
+    }
+
+    #[test]
+    fn test_sink_spi_buffers_whole_frames() {
+        let mut driver = Max7219::new(SinkSpi::<_, 2>::new(FrameRecorder::<2>::new()))
+            .with_device_count(2)
+            .unwrap();
+        let mut frame = [[0u8; 8]; 2];
+        frame[1][7] = 0x01;
+        driver.write_rows(&frame).unwrap();
+        // Sent row by row, received as one frame
+        assert_eq!(driver.spi().sink().frames(), 1);
+        assert_eq!(driver.spi().sink().rows(), &frame);
+
+        // A partial update waits for the next write or an explicit flush
+        driver
+            .spi_mut()
+            .write(&[0x00, 0x00, Register::Digit3.addr(), 0xF0])
+            .unwrap();
+        assert_eq!(driver.spi().sink().frames(), 1);
+        driver.spi_mut().flush_frame().unwrap();
+        assert_eq!(driver.spi().sink().frames(), 2);
+        assert!(driver.spi().sink().is_lit(1, 3, 7));
+    }
+
+    #[test]
+    fn test_recorder_display() {
+        let mut recorder = FrameRecorder::<2>::new();
+        let mut frame = [[0u8; 8]; 2];
+        frame[1][0] = 0x80; // nearest device, leftmost column
+        frame[0][7] = 0x01; // furthest device, rightmost column
+        recorder.write_rows(&frame).unwrap();
+
+        let text = recorder.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "#...............");
+        assert_eq!(lines[7], "...............#");
+    }
+
+    #[cfg(feature = "led-matrix")]
+    #[test]
+    fn test_matrix_without_hardware() {
+        use crate::led_matrix::{LedMatrix, UpdateMode};
+
+        let mut matrix: LedMatrix<_, 128, 2> = LedMatrix::from_sink(FrameRecorder::<2>::new())
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        matrix.set_pixel(9, 3, true).unwrap();
+        matrix.flush().unwrap();
+        assert_eq!(matrix.sink().frames(), 1);
+        assert_eq!(
+            matrix.sink().to_string().lines().nth(3),
+            Some(".........#......")
+        );
+
+        let mut recorder = FrameRecorder::<2>::new();
+        matrix.render_to(&mut recorder).unwrap();
+        assert_eq!(&recorder.rows()[..], &matrix.sink().rows()[..]);
+    }
+
+    #[cfg(feature = "led-matrix")]
+    #[test]
+    fn test_sink_error_fails_flush() {
+        use crate::led_matrix::{LedMatrix, UpdateMode};
+
+        struct FailingSink;
+        impl FrameSink for FailingSink {
+            fn write_rows(&mut self, _device_rows: &[[u8; 8]]) -> Result<()> {
+                Err(Error::BufferError)
+            }
+        }
+
+        // The transaction completing the frame fails with the sink's error
+        let mut spi = SinkSpi::<_, 1>::new(FailingSink);
+        let packets: Vec<_> = Register::digits()
+            .map(|digit| [digit.addr(), 0xFF])
+            .collect();
+        let mut operations: Vec<_> = packets
+            .iter()
+            .map(|packet| Operation::Write(&packet[..]))
+            .collect();
+        assert_eq!(
+            spi.transaction(&mut operations),
+            Err(SinkError(Error::BufferError))
+        );
+
+        let mut matrix: LedMatrix<_, 64, 1> = LedMatrix::from_sink(FailingSink)
+            .unwrap()
+            .with_update_mode(UpdateMode::Buffered);
+        matrix.set_pixel(0, 0, true).unwrap();
+        assert_eq!(matrix.flush(), Err(Error::SpiError));
+    }
+
+    #[cfg(feature = "led-matrix")]
+    #[test]
+    fn test_widget_draws_into_sink() {
+        use crate::led_matrix::{
+            display::Matrix4, fonts::STANDARD_LED_FONT, widgets::rolling_text::RollingText,
+        };
+
+        let mut matrix = Matrix4::from_sink(FrameRecorder::<4>::new()).unwrap();
+        let mut field: RollingText<'_, 1> = RollingText::new(&STANDARD_LED_FONT)
+            .with_position(8, 0)
+            .with_roll_ms(0);
+        field.set_text("8");
+        field.draw(&mut matrix).unwrap();
+
+        let eight = STANDARD_LED_FONT.get_char('8');
+        assert_eq!(matrix.sink().frames(), 1);
+        // Second module from the left is the third device from the MCU end
+        for (digit, row) in eight.iter().enumerate() {
+            for bit in 0..8u8 {
+                assert_eq!(
+                    matrix.sink().is_lit(2, digit as u8, bit),
+                    row & (1 << bit) != 0
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Ticker templates with live value slots
+//!
+//! A [`Template`] such as `"CO2 {ppm}ppm  T {temp}C"` keeps the value of
+//! each `{slot}` on its own. Values can be updated whenever a reading comes
+//! in, which only copies a few bytes; the full text is rebuilt by
+//! [`Template::render_into`], and only if a value changed. Rendering at the
+//! wrap point of a looping scroll keeps the hot path free of formatting:
+//!
+//! ```rust,ignore
+//! let mut template: Template<2> = Template::new("CO2 {ppm}ppm  T {temp}C")?;
+//! let mut text: Message<48> = Message::new();
+//! loop {
+//!     template.render_into(&mut text)?;
+//!     // Starts at offset 0, right where the previous pass wrapped
+//!     let mut scroller = ScrollingText::new(&text, &STANDARD_LED_FONT, config);
+//!     loop {
+//!         template.set_fmt("ppm", format_args!("{}", co2.read()))?;
+//!         matrix.show_scroller(&mut scroller)?;
+//!         if scroller.advance() == Progress::Complete {
+//!             break;
+//!         }
+//!         delay.delay_ns(scroller.next_delay_ns());
+//!     }
+//! }
+//! ```
+//!
+//! Scroll markers such as `{pause=500}` and the `{{` escape pass through
+//! unchanged, and a `{` in a value is escaped so it is shown as written.
+
+use core::fmt::{self, Write};
+
+use crate::{Error, Message, Result};
+
+/// Maximum length of a slot value in bytes
+pub const SLOT_LEN: usize = 16;
+
+/// A named slot and its current value
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Slot<'a> {
+    name: &'a str,
+    value: Message<SLOT_LEN>,
+}
+
+/// Returns `true` if `{name}` is a slot rather than a scroll marker.
+fn is_slot_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '{'])
+}
+
+/// Splits `text` at its first slot into the text before it, the slot name
+/// and the text after it.
+fn next_slot(text: &str) -> Option<(&str, &str, &str)> {
+    let mut start = 0;
+    while let Some(open) = text.get(start..)?.find('{') {
+        let open = start.saturating_add(open);
+        let rest = text.get(open.saturating_add(1)..)?;
+        if rest.starts_with('{') {
+            start = open.saturating_add(2);
+            continue;
+        }
+        if let Some((name, after)) = rest.split_once('}')
+            && is_slot_name(name)
+        {
+            return Some((text.get(..open)?, name, after));
+        }
+        start = open.saturating_add(1);
+    }
+    None
+}
+
+/// Text with up to `SLOTS` named value slots, see the
+/// [module documentation](self).
+///
+/// # Example
+///
+/// ```rust
+/// use max7219_display::{Message, template::Template};
+///
+/// let mut template: Template<2> = Template::new("T {temp}C  {hum}%").unwrap();
+/// template.set("temp", "21").unwrap();
+/// template.set_fmt("hum", format_args!("{}", 48)).unwrap();
+///
+/// let mut text: Message<32> = Message::new();
+/// assert!(template.render_into(&mut text).unwrap());
+/// assert_eq!(text.as_str(), "T 21C  48%");
+/// // Nothing changed since, so the text is left alone
+/// assert!(!template.render_into(&mut text).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template<'a, const SLOTS: usize> {
+    pattern: &'a str,
+    slots: heapless::Vec<Slot<'a>, SLOTS>,
+    dirty: bool,
+}
+
+impl<'a, const SLOTS: usize> Template<'a, SLOTS> {
+    /// Create a template with every slot empty
+    ///
+    /// A slot used more than once in `pattern` shares one value.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidTemplate` if `pattern` names more than
+    /// `SLOTS` slots.
+    pub fn new(pattern: &'a str) -> Result<Self> {
+        let mut slots = heapless::Vec::new();
+        let mut rest = pattern;
+        while let Some((_, name, after)) = next_slot(rest) {
+            if !slots.iter().any(|slot: &Slot| slot.name == name) {
+                let slot = Slot {
+                    name,
+                    value: Message::new(),
+                };
+                slots.push(slot).map_err(|_| Error::InvalidTemplate)?;
+            }
+            rest = after;
+        }
+        Ok(Self {
+            pattern,
+            slots,
+            dirty: true,
+        })
+    }
+
+    /// Returns the pattern the template was created from.
+    pub fn pattern(&self) -> &'a str {
+        self.pattern
+    }
+
+    /// Iterates over the slot names in order of first appearance.
+    pub fn slot_names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.slots.iter().map(|slot| slot.name)
+    }
+
+    /// Returns the current value of slot `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.slot(name).map(|slot| slot.value.as_str())
+    }
+
+    fn slot(&self, name: &str) -> Option<&Slot<'a>> {
+        self.slots.iter().find(|slot| slot.name == name)
+    }
+
+    /// Sets the value of slot `name`.
+    ///
+    /// The text is only marked for rendering if the value changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidTemplate` if there is no slot `name`, and
+    /// `Error::BufferError` if `value` is longer than [`SLOT_LEN`] bytes.
+    /// The old value is kept in both cases.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let slot = self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.name == name)
+            .ok_or(Error::InvalidTemplate)?;
+        if slot.value.as_str() != value {
+            let mut new = Message::new();
+            new.push_str(value)?;
+            slot.value = new;
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Sets the value of slot `name` from formatting arguments, e.g.
+    /// `format_args!("{:.1}", celsius)`.
+    ///
+    /// # Errors
+    ///
+    /// As for [`Self::set`].
+    pub fn set_fmt(&mut self, name: &str, args: fmt::Arguments<'_>) -> Result<()> {
+        let value = Message::<SLOT_LEN>::from_fmt(args)?;
+        self.set(name, &value)
+    }
+
+    /// Returns `true` if a value changed since the last render.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Renders the text into `out` if a value changed since the last
+    /// render, and returns whether it did.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if the text is longer than `N` bytes.
+    /// `out` keeps the previous text and the template stays dirty, so the
+    /// next call tries again.
+    pub fn render_into<const N: usize>(&mut self, out: &mut Message<N>) -> Result<bool> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        let mut text = Message::new();
+        self.write_text(&mut text).map_err(|_| Error::BufferError)?;
+        *out = text;
+        self.dirty = false;
+        Ok(true)
+    }
+
+    /// Writes the text with every slot filled in.
+    fn write_text<W: Write>(&self, out: &mut W) -> fmt::Result {
+        let mut rest = self.pattern;
+        while let Some((before, name, after)) = next_slot(rest) {
+            out.write_str(before)?;
+            let value = self.get(name).unwrap_or_default();
+            for (index, part) in value.split('{').enumerate() {
+                if index > 0 {
+                    out.write_str("{{")?;
+                }
+                out.write_str(part)?;
+            }
+            rest = after;
+        }
+        out.write_str(rest)
+    }
+}
+
+/// Writes the text with every slot filled in, whether or not it changed.
+impl<const SLOTS: usize> fmt::Display for Template<'_, SLOTS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_text(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots() {
+        let template: Template<2> = Template::new("{a} {pause=5}{{x} {b}{a}").unwrap();
+        assert_eq!(template.slot_names().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(template.get("a"), Some(""));
+        assert_eq!(template.get("x"), None);
+
+        assert_eq!(Template::<1>::new("{a}{b}"), Err(Error::InvalidTemplate));
+        assert!(Template::<0>::new("{=} {} {{a}").is_ok());
+    }
+
+    #[test]
+    fn test_render_when_dirty() {
+        let mut template: Template<2> = Template::new("{pause=5}{a}/{b}/{a}").unwrap();
+        let mut text: Message<32> = Message::new();
+        assert!(template.render_into(&mut text).unwrap());
+        assert_eq!(text.as_str(), "{pause=5}//");
+
+        template.set("a", "1").unwrap();
+        template.set_fmt("b", format_args!("{}", 22)).unwrap();
+        assert!(template.is_dirty());
+        assert!(template.render_into(&mut text).unwrap());
+        assert_eq!(text.as_str(), "{pause=5}1/22/1");
+
+        // Setting the same value leaves the text alone
+        template.set("a", "1").unwrap();
+        assert!(!template.is_dirty());
+        assert!(!template.render_into(&mut text).unwrap());
+    }
+
+    #[test]
+    fn test_value_errors() {
+        let mut template: Template<1> = Template::new("{a}").unwrap();
+        template.set("a", "ok").unwrap();
+        assert_eq!(template.set("b", "1"), Err(Error::InvalidTemplate));
+        assert_eq!(
+            template.set("a", "0123456789abcdefg"),
+            Err(Error::BufferError)
+        );
+        assert_eq!(template.get("a"), Some("ok"));
+
+        let mut text: Message<1> = Message::new();
+        text.push('x').unwrap();
+        assert_eq!(template.render_into(&mut text), Err(Error::BufferError));
+        assert_eq!(text.as_str(), "x");
+        assert!(template.is_dirty());
+    }
+
+    #[test]
+    fn test_values_are_escaped() {
+        let mut template: Template<1> = Template::new("<{a}>").unwrap();
+        template.set("a", "{pause=9}").unwrap();
+        assert_eq!(format!("{template}"), "<{{pause=9}>");
+    }
+}
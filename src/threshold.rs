@@ -0,0 +1,304 @@
+//! Alarm rules for numeric widgets
+//!
+//! Without colors, a display draws attention through blinking and
+//! brightness. [`Thresholds`] holds a few rules such as "blink above 90" or
+//! "dim below 10", which numeric widgets evaluate every time their value
+//! changes, so the alarm behavior lives next to the display logic instead of
+//! in the application loop.
+//!
+//! ```rust
+//! use max7219_display::Brightness;
+//! use max7219_display::threshold::{AlertAction, Threshold, Thresholds};
+//!
+//! let mut thresholds = Thresholds::new(Brightness::from_percent(50))
+//!     .with_rule(Threshold::above(90, AlertAction::Blink { period_ms: 500 }))?
+//!     .with_rule(Threshold::above(90, AlertAction::Brightness(Brightness::MAX)))?
+//!     .with_rule(Threshold::below(10, AlertAction::Brightness(Brightness::MIN)))?;
+//!
+//! thresholds.update(95);
+//! assert!(thresholds.is_blinking());
+//! assert_eq!(thresholds.brightness(), Brightness::MAX);
+//!
+//! thresholds.update(5);
+//! assert!(!thresholds.is_blinking());
+//! assert_eq!(thresholds.brightness(), Brightness::MIN);
+//! # Ok::<(), max7219_display::Error>(())
+//! ```
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{Brightness, Error, MAX_DISPLAYS, Max7219, Register, Result};
+
+/// Maximum number of rules in one [`Thresholds`]
+pub const MAX_THRESHOLDS: usize = 4;
+
+/// When a rule applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    /// The value is greater than the limit
+    Above(i64),
+    /// The value is less than the limit
+    Below(i64),
+}
+
+impl Condition {
+    /// Returns `true` if `value` meets the condition.
+    pub const fn matches(self, value: i64) -> bool {
+        match self {
+            Condition::Above(limit) => value > limit,
+            Condition::Below(limit) => value < limit,
+        }
+    }
+}
+
+/// What a rule does while its condition holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertAction {
+    /// Blink the value, visible for the first half of each period
+    Blink {
+        /// Length of one on/off cycle in milliseconds
+        period_ms: u32,
+    },
+    /// Show the value at the given brightness
+    Brightness(Brightness),
+}
+
+/// One alarm rule: an action taken while the value meets a condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    condition: Condition,
+    action: AlertAction,
+}
+
+impl Threshold {
+    /// Create a rule taking `action` while the value meets `condition`
+    pub const fn new(condition: Condition, action: AlertAction) -> Self {
+        Self { condition, action }
+    }
+
+    /// Create a rule taking `action` while the value is above `limit`
+    pub const fn above(limit: i64, action: AlertAction) -> Self {
+        Self::new(Condition::Above(limit), action)
+    }
+
+    /// Create a rule taking `action` while the value is below `limit`
+    pub const fn below(limit: i64, action: AlertAction) -> Self {
+        Self::new(Condition::Below(limit), action)
+    }
+
+    /// Returns the condition of the rule.
+    pub fn condition(&self) -> Condition {
+        self.condition
+    }
+
+    /// Returns the action of the rule.
+    pub fn action(&self) -> AlertAction {
+        self.action
+    }
+}
+
+/// Set of alarm rules with the alert state they produce for the current
+/// value.
+///
+/// If several matching rules set the brightness or blink, the first one
+/// added wins. Without a matching brightness rule the value is shown at the
+/// normal brightness.
+///
+/// A widget drawing with alarm rules sets the devices it is shown on to
+/// their brightness, leaving the rest of the chain as it is, and leaves its
+/// value blank in the dark half of a blink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    rules: [Option<Threshold>; MAX_THRESHOLDS],
+    normal: Brightness,
+    brightness: Brightness,
+    blink_ms: Option<u32>,
+    elapsed_ms: u32,
+}
+
+impl Thresholds {
+    /// Create an empty rule set, showing values at `normal` brightness
+    pub const fn new(normal: Brightness) -> Self {
+        Self {
+            rules: [None; MAX_THRESHOLDS],
+            normal,
+            brightness: normal,
+            blink_ms: None,
+            elapsed_ms: 0,
+        }
+    }
+
+    /// Adds a rule after the existing ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::BufferError` if there are already
+    /// [`MAX_THRESHOLDS`] rules.
+    pub fn with_rule(mut self, rule: Threshold) -> Result<Self> {
+        let slot = self
+            .rules
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Error::BufferError)?;
+        *slot = Some(rule);
+        Ok(self)
+    }
+
+    /// Returns the rules in the order they were added.
+    pub fn rules(&self) -> impl Iterator<Item = &Threshold> {
+        self.rules.iter().flatten()
+    }
+
+    /// Evaluates the rules for a new value.
+    ///
+    /// Returns `true` if the brightness or blinking changed. A blink that
+    /// starts begins with the value visible.
+    pub fn update(&mut self, value: i64) -> bool {
+        let matching = || {
+            self.rules
+                .iter()
+                .flatten()
+                .filter(|rule| rule.condition.matches(value))
+        };
+        let brightness = matching()
+            .find_map(|rule| match rule.action {
+                AlertAction::Brightness(brightness) => Some(brightness),
+                AlertAction::Blink { .. } => None,
+            })
+            .unwrap_or(self.normal);
+        let blink_ms = matching().find_map(|rule| match rule.action {
+            AlertAction::Blink { period_ms } => Some(period_ms),
+            AlertAction::Brightness(_) => None,
+        });
+
+        let changed = brightness != self.brightness || blink_ms != self.blink_ms;
+        if blink_ms != self.blink_ms {
+            self.elapsed_ms = 0;
+        }
+        self.brightness = brightness;
+        self.blink_ms = blink_ms;
+        changed
+    }
+
+    /// Returns the brightness for the current value.
+    pub fn brightness(&self) -> Brightness {
+        self.brightness
+    }
+
+    /// Returns `true` while a blink rule applies.
+    pub fn is_blinking(&self) -> bool {
+        self.blink_ms.is_some()
+    }
+
+    /// Returns `true` if the value should be shown right now, i.e. it isn't
+    /// blinking or is in the visible half of the blink.
+    pub fn is_visible(&self) -> bool {
+        match self.blink_ms {
+            Some(period_ms) if period_ms > 0 => self.elapsed_ms < period_ms / 2,
+            _ => true,
+        }
+    }
+
+    /// Sets the devices flagged in `devices` (by driver index) to the
+    /// brightness for the current value, and returns whether the value
+    /// should be shown right now.
+    ///
+    /// Devices already at that brightness are left alone, and the others
+    /// are written in one transaction. The built-in widgets call this when
+    /// drawn; custom widgets can do the same.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDeviceIndex` if a device past the end of the
+    /// chain is flagged, or the error of the write.
+    pub fn apply<SPI: SpiDevice>(
+        &self,
+        driver: &mut Max7219<SPI>,
+        devices: &[bool],
+    ) -> Result<bool> {
+        let mut ops = [(Register::NoOp, 0); MAX_DISPLAYS];
+        let mut stale = false;
+        for (device_index, (op, _)) in ops
+            .iter_mut()
+            .zip(devices)
+            .enumerate()
+            .filter(|(_, (_, selected))| **selected)
+        {
+            if driver.device_state(device_index)?.brightness() != self.brightness {
+                *op = (Register::Intensity, self.brightness.level());
+                stale = true;
+            }
+        }
+        if stale {
+            driver.write_all_registers(&ops)?;
+        }
+        Ok(self.is_visible())
+    }
+
+    /// Advances the blink by `dt_ms` milliseconds.
+    ///
+    /// Returns `true` if the value appeared or disappeared and needs to be
+    /// drawn again.
+    pub fn tick(&mut self, dt_ms: u32) -> bool {
+        let Some(period_ms) = self.blink_ms.filter(|&period_ms| period_ms > 0) else {
+            return false;
+        };
+        let was_visible = self.is_visible();
+        self.elapsed_ms = u64::from(self.elapsed_ms)
+            .saturating_add(u64::from(dt_ms))
+            .checked_rem(u64::from(period_ms))
+            .unwrap_or(0) as u32;
+        self.is_visible() != was_visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let dim = Brightness::from_percent(20);
+        let mut thresholds = Thresholds::new(Brightness::from_percent(50))
+            .with_rule(Threshold::above(
+                100,
+                AlertAction::Brightness(Brightness::MAX),
+            ))
+            .unwrap()
+            .with_rule(Threshold::above(50, AlertAction::Brightness(dim)))
+            .unwrap();
+        assert!(thresholds.update(120));
+        assert_eq!(thresholds.brightness(), Brightness::MAX);
+        assert!(thresholds.update(60));
+        assert_eq!(thresholds.brightness(), dim);
+        assert!(!thresholds.update(70));
+        assert!(thresholds.update(50));
+        assert_eq!(thresholds.brightness(), Brightness::from_percent(50));
+        assert_eq!(thresholds.rules().count(), 2);
+    }
+
+    #[test]
+    fn test_blink() {
+        let mut thresholds = Thresholds::new(Brightness::MAX)
+            .with_rule(Threshold::below(0, AlertAction::Blink { period_ms: 400 }))
+            .unwrap();
+        assert!(!thresholds.tick(300));
+        assert!(thresholds.update(-1));
+        assert!(thresholds.is_visible());
+        assert!(!thresholds.tick(199));
+        assert!(thresholds.tick(1));
+        assert!(!thresholds.is_visible());
+        assert!(thresholds.update(0));
+        assert!(thresholds.is_visible());
+    }
+
+    #[test]
+    fn test_full() {
+        let rule = Threshold::above(0, AlertAction::Brightness(Brightness::MAX));
+        let mut thresholds = Thresholds::new(Brightness::MIN);
+        for _ in 0..MAX_THRESHOLDS {
+            thresholds = thresholds.with_rule(rule).unwrap();
+        }
+        assert_eq!(thresholds.with_rule(rule), Err(Error::BufferError));
+    }
+}
@@ -0,0 +1,522 @@
+//! Time source abstraction and frame-rate limiting
+//!
+//! Tick-driven components take the current time from a [`Clock`], a
+//! monotonic millisecond counter supplied by the application. The counter may
+//! wrap around; all comparisons use wrapping arithmetic.
+//!
+//! Ready-made time sources:
+//!
+//! - any closure returning an [`Instant`], e.g. `|| embassy_time::Instant::now()`
+//! - an `AtomicU32` millisecond counter incremented from a timer interrupt
+//! - [`EmbassyClock`] with the `embassy-time` feature
+//!
+//! [`Instant`] is implemented for raw `u32`/`u64` millisecond counts,
+//! `embassy_time::Instant` (feature `embassy-time`) and `fugit::Instant`
+//! (feature `fugit`). [`DeltaTimer`] turns any clock into the `dt_ms` values
+//! expected by `tick(dt_ms)` methods.
+//!
+//! Animations and timers that run to an end report it with [`Progress`], so
+//! an application state machine can move on when it sees
+//! [`Progress::Complete`].
+
+/// Monotonic millisecond time source.
+///
+/// # Example
+///
+/// ```rust
+/// use max7219_display::time::Clock;
+///
+/// struct SysTick {
+///     millis: u32,
+/// }
+///
+/// impl Clock for SysTick {
+///     fn now_ms(&self) -> u32 {
+///         self.millis
+///     }
+/// }
+/// ```
+pub trait Clock {
+    /// Returns the current time in milliseconds, wrapping at `u32::MAX`.
+    fn now_ms(&self) -> u32;
+}
+
+/// A point in time that can be expressed in milliseconds.
+pub trait Instant: Copy {
+    /// Returns the time since the clock's epoch in milliseconds, wrapping at
+    /// `u32::MAX`.
+    fn as_millis_u32(&self) -> u32;
+}
+
+impl Instant for u32 {
+    fn as_millis_u32(&self) -> u32 {
+        *self
+    }
+}
+
+impl Instant for u64 {
+    fn as_millis_u32(&self) -> u32 {
+        *self as u32
+    }
+}
+
+#[cfg(feature = "embassy-time")]
+impl Instant for embassy_time::Instant {
+    fn as_millis_u32(&self) -> u32 {
+        self.as_millis() as u32
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> Instant for fugit::Instant<u32, NOM, DENOM> {
+    fn as_millis_u32(&self) -> u32 {
+        let ticks = u64::from(self.ticks());
+        ticks
+            .saturating_mul(u64::from(NOM))
+            .saturating_mul(1000)
+            .checked_div(u64::from(DENOM))
+            .unwrap_or(0) as u32
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> Instant for fugit::Instant<u64, NOM, DENOM> {
+    fn as_millis_u32(&self) -> u32 {
+        let ticks = u128::from(self.ticks());
+        ticks
+            .saturating_mul(u128::from(NOM))
+            .saturating_mul(1000)
+            .checked_div(u128::from(DENOM))
+            .unwrap_or(0) as u32
+    }
+}
+
+/// Any closure returning an [`Instant`] is a clock.
+impl<F, I> Clock for F
+where
+    F: Fn() -> I,
+    I: Instant,
+{
+    fn now_ms(&self) -> u32 {
+        self().as_millis_u32()
+    }
+}
+
+/// A millisecond counter incremented by the application, e.g. from SysTick.
+#[cfg(target_has_atomic = "32")]
+impl Clock for core::sync::atomic::AtomicU32 {
+    fn now_ms(&self) -> u32 {
+        self.load(core::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// Clock backed by the `embassy-time` driver of the application.
+#[cfg(feature = "embassy-time")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EmbassyClock;
+
+#[cfg(feature = "embassy-time")]
+impl Clock for EmbassyClock {
+    #[inline]
+    fn now_ms(&self) -> u32 {
+        embassy_time::Instant::now().as_millis_u32()
+    }
+}
+
+/// Measures the time between successive calls, for driving `tick(dt_ms)`
+/// style components from a [`Clock`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut timer = DeltaTimer::new();
+/// loop {
+///     let dt = timer.dt_ms(&clock);
+///     matrix.tick_heartbeat(dt)?;
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DeltaTimer {
+    last: Option<u32>,
+}
+
+impl DeltaTimer {
+    /// Create a timer; the first measurement returns 0
+    pub const fn new() -> Self {
+        Self { last: None }
+    }
+
+    /// Returns the milliseconds elapsed since the previous call at `now_ms`.
+    pub fn dt_ms_at(&mut self, now_ms: u32) -> u32 {
+        let dt = self.last.map_or(0, |last| now_ms.wrapping_sub(last));
+        self.last = Some(now_ms);
+        dt
+    }
+
+    /// Same as [`Self::dt_ms_at`] using the current time of `clock`.
+    pub fn dt_ms<C: Clock>(&mut self, clock: &C) -> u32 {
+        self.dt_ms_at(clock.now_ms())
+    }
+}
+
+/// Returns `true` if `now` is at or after `deadline`, taking wrap-around of
+/// the millisecond counter into account.
+pub(crate) fn is_due(now: u32, deadline: u32) -> bool {
+    (now.wrapping_sub(deadline) as i32) >= 0
+}
+
+/// Limits how often something happens, e.g. frames per second.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut ticker = Ticker::from_fps(30);
+/// loop {
+///     if ticker.ready(&clock) {
+///         matrix.flush()?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ticker {
+    interval_ms: u32,
+    next_due: Option<u32>,
+}
+
+impl Ticker {
+    /// Create a ticker firing at most every `interval_ms` milliseconds
+    pub const fn new(interval_ms: u32) -> Self {
+        Self {
+            interval_ms,
+            next_due: None,
+        }
+    }
+
+    /// Create a ticker firing at most `fps` times per second.
+    ///
+    /// An `fps` of 0 means no limit.
+    pub const fn from_fps(fps: u32) -> Self {
+        match 1000u32.checked_div(fps) {
+            Some(interval_ms) => Self::new(interval_ms),
+            None => Self::new(0),
+        }
+    }
+
+    /// Returns the minimum time between two firings, in milliseconds.
+    pub fn interval_ms(&self) -> u32 {
+        self.interval_ms
+    }
+
+    /// Returns `true` if the ticker may fire at `now_ms`, and if so starts
+    /// the next interval.
+    ///
+    /// The first call always fires.
+    pub fn ready_at(&mut self, now_ms: u32) -> bool {
+        match self.next_due {
+            Some(deadline) if !is_due(now_ms, deadline) => false,
+            _ => {
+                self.next_due = Some(now_ms.wrapping_add(self.interval_ms));
+                true
+            }
+        }
+    }
+
+    /// Same as [`Self::ready_at`] using the current time of `clock`.
+    pub fn ready<C: Clock>(&mut self, clock: &C) -> bool {
+        self.ready_at(clock.now_ms())
+    }
+
+    /// Makes the next call fire regardless of the time.
+    pub fn reset(&mut self) {
+        self.next_due = None;
+    }
+}
+
+/// State reported each time an animation or timer is advanced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// Not running: never started, or already complete
+    Idle,
+    /// Still running
+    Running,
+    /// Finished with this step; reported exactly once per run
+    Complete,
+}
+
+impl Progress {
+    /// Returns `true` for [`Progress::Complete`].
+    pub fn is_complete(self) -> bool {
+        self == Progress::Complete
+    }
+
+    /// Calls `f` if this is [`Progress::Complete`], then returns `self`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// scroller.advance().on_complete(|| screen = Screen::Clock);
+    /// ```
+    pub fn on_complete<F: FnOnce()>(self, f: F) -> Self {
+        if self.is_complete() {
+            f();
+        }
+        self
+    }
+}
+
+/// One-shot timer, e.g. how long a message stays on screen.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut timeout = Timeout::new(5000);
+/// timeout.start(&clock);
+/// loop {
+///     if timeout.poll(&clock).is_complete() {
+///         show_next_screen()?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    duration_ms: u32,
+    deadline: Option<u32>,
+}
+
+impl Timeout {
+    /// Create a stopped timer of `duration_ms` milliseconds
+    pub const fn new(duration_ms: u32) -> Self {
+        Self {
+            duration_ms,
+            deadline: None,
+        }
+    }
+
+    /// Returns the duration in milliseconds.
+    pub fn duration_ms(&self) -> u32 {
+        self.duration_ms
+    }
+
+    /// Starts, or restarts, the timer at `now_ms`.
+    pub fn start_at(&mut self, now_ms: u32) {
+        self.deadline = Some(now_ms.wrapping_add(self.duration_ms));
+    }
+
+    /// Same as [`Self::start_at`] using the current time of `clock`.
+    pub fn start<C: Clock>(&mut self, clock: &C) {
+        self.start_at(clock.now_ms());
+    }
+
+    /// Stops the timer without completing it.
+    pub fn cancel(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Returns `true` between starting and completing.
+    pub fn is_running(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// Checks the timer at `now_ms`.
+    ///
+    /// Returns [`Progress::Complete`] the first time the duration has
+    /// elapsed, and [`Progress::Idle`] afterwards until it is started again.
+    pub fn poll_at(&mut self, now_ms: u32) -> Progress {
+        match self.deadline {
+            None => Progress::Idle,
+            Some(deadline) if is_due(now_ms, deadline) => {
+                self.deadline = None;
+                Progress::Complete
+            }
+            Some(_) => Progress::Running,
+        }
+    }
+
+    /// Same as [`Self::poll_at`] using the current time of `clock`.
+    pub fn poll<C: Clock>(&mut self, clock: &C) -> Progress {
+        self.poll_at(clock.now_ms())
+    }
+}
+
+/// Seconds in a day
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// A wall-clock time within a day, with one second resolution.
+///
+/// Used by schedules that depend on the time of day, such as
+/// [`BrightnessSchedule`](crate::driver::BrightnessSchedule). The time comes
+/// from the application, e.g. an RTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TimeOfDay(u32);
+
+impl TimeOfDay {
+    /// Midnight, 00:00:00
+    pub const MIDNIGHT: Self = Self(0);
+
+    /// Create a time from hours, minutes and seconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDigit` if `hours` is above 23 or `minutes` or
+    /// `seconds` is above 59.
+    pub const fn new(hours: u8, minutes: u8, seconds: u8) -> crate::Result<Self> {
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return Err(crate::Error::InvalidDigit);
+        }
+        Ok(Self(
+            // At most 86399, checked above
+            (hours as u32)
+                .wrapping_mul(3600)
+                .wrapping_add((minutes as u32).wrapping_mul(60))
+                .wrapping_add(seconds as u32),
+        ))
+    }
+
+    /// Create a time from hours and minutes, see [`Self::new`].
+    pub const fn from_hm(hours: u8, minutes: u8) -> crate::Result<Self> {
+        Self::new(hours, minutes, 0)
+    }
+
+    /// Create a time from seconds since midnight, wrapping at a full day.
+    pub const fn from_seconds(seconds: u32) -> Self {
+        Self(seconds % SECONDS_PER_DAY)
+    }
+
+    /// Returns the seconds since midnight.
+    pub const fn seconds(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the time as `(hours, minutes, seconds)`.
+    pub const fn hms(self) -> (u8, u8, u8) {
+        (
+            (self.0 / 3600) as u8,
+            (self.0 / 60 % 60) as u8,
+            (self.0 % 60) as u8,
+        )
+    }
+
+    /// Returns the seconds from `earlier` to this time, going forward and
+    /// wrapping past midnight.
+    pub const fn seconds_since(self, earlier: Self) -> u32 {
+        // Both times are below a day, so this stays far from overflowing
+        self.0.wrapping_add(SECONDS_PER_DAY).wrapping_sub(earlier.0) % SECONDS_PER_DAY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due_wraps() {
+        assert!(is_due(10, 10));
+        assert!(!is_due(9, 10));
+        assert!(is_due(5, u32::MAX - 5));
+        assert!(!is_due(u32::MAX - 5, 5));
+    }
+
+    #[test]
+    fn test_ticker_limits_rate() {
+        let mut ticker = Ticker::from_fps(25);
+        assert_eq!(ticker.interval_ms(), 40);
+        assert!(ticker.ready_at(100));
+        assert!(!ticker.ready_at(120));
+        assert!(!ticker.ready_at(139));
+        assert!(ticker.ready_at(140));
+        assert!(!ticker.ready_at(150));
+
+        ticker.reset();
+        assert!(ticker.ready_at(150));
+    }
+
+    #[test]
+    fn test_closure_clock() {
+        let clock = || 1234u64;
+        assert_eq!(clock.now_ms(), 1234);
+
+        let counter = core::sync::atomic::AtomicU32::new(42);
+        assert_eq!(counter.now_ms(), 42);
+    }
+
+    #[test]
+    fn test_delta_timer() {
+        let mut timer = DeltaTimer::new();
+        assert_eq!(timer.dt_ms_at(100), 0);
+        assert_eq!(timer.dt_ms_at(130), 30);
+        assert_eq!(timer.dt_ms_at(2), u32::MAX - 127);
+        assert_eq!(timer.dt_ms(&|| 10u32), 8);
+    }
+
+    #[cfg(feature = "fugit")]
+    #[test]
+    fn test_fugit_instant() {
+        let instant = fugit::Instant::<u32, 1, 32_768>::from_ticks(65_536);
+        assert_eq!(instant.as_millis_u32(), 2000);
+        let instant = fugit::Instant::<u64, 1, 1_000_000>::from_ticks(1_500_000);
+        assert_eq!(instant.as_millis_u32(), 1500);
+    }
+
+    #[cfg(feature = "embassy-time")]
+    #[test]
+    fn test_embassy_instant() {
+        let instant = embassy_time::Instant::from_millis(4321);
+        assert_eq!(instant.as_millis_u32(), 4321);
+    }
+
+    #[test]
+    fn test_ticker_with_clock() {
+        struct Fixed(u32);
+        impl Clock for Fixed {
+            fn now_ms(&self) -> u32 {
+                self.0
+            }
+        }
+
+        let mut ticker = Ticker::new(10);
+        assert!(ticker.ready(&Fixed(u32::MAX - 2)));
+        assert!(!ticker.ready(&Fixed(3)));
+        assert!(ticker.ready(&Fixed(7)));
+    }
+
+    #[test]
+    fn test_timeout_completes_once() {
+        let mut timeout = Timeout::new(100);
+        assert_eq!(timeout.poll_at(0), Progress::Idle);
+
+        timeout.start_at(u32::MAX - 10);
+        assert!(timeout.is_running());
+        assert_eq!(timeout.poll_at(50), Progress::Running);
+        assert_eq!(timeout.poll_at(89), Progress::Complete);
+        assert_eq!(timeout.poll_at(90), Progress::Idle);
+        assert!(!timeout.is_running());
+
+        timeout.start_at(0);
+        timeout.cancel();
+        assert_eq!(timeout.poll_at(200), Progress::Idle);
+    }
+
+    #[test]
+    fn test_progress_on_complete() {
+        let mut calls = 0;
+        Progress::Running.on_complete(|| calls += 1);
+        assert!(Progress::Complete.on_complete(|| calls += 1).is_complete());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        let time = TimeOfDay::new(22, 30, 15).unwrap();
+        assert_eq!(time.hms(), (22, 30, 15));
+        assert_eq!(TimeOfDay::from_hm(24, 0), Err(crate::Error::InvalidDigit));
+        assert_eq!(
+            TimeOfDay::from_seconds(SECONDS_PER_DAY + 61).hms(),
+            (0, 1, 1)
+        );
+
+        let morning = TimeOfDay::from_hm(7, 0).unwrap();
+        let evening = TimeOfDay::from_hm(22, 0).unwrap();
+        assert_eq!(morning.seconds_since(evening), 9 * 3600);
+        assert_eq!(evening.seconds_since(morning), 15 * 3600);
+    }
+}